@@ -0,0 +1,137 @@
+// Baselines for the work that doesn't wait on MySQL: rendering the task list, reading and
+// writing todo.txt lines and building the multi-row INSERTs a bulk import is sent as. Compare
+// a change against them with `cargo bench -- --save-baseline before` and `--baseline before`.
+
+use std::hint::black_box;
+
+use chrono::{Days, NaiveDate, NaiveDateTime};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use futures::executor::block_on;
+use taskcore::export::todotxt::format_line;
+use taskcore::import::todotxt::parse_line;
+use taskcore::memory::MemoryStore;
+use taskcore::priority::Priority;
+use taskcore::repository::{self, Access, NewTask, TaskBundle};
+use taskcore::{format_task, views, Task};
+
+const TASKS: usize = 10_000;
+
+fn start() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2026, 1, 1).unwrap().and_hms_opt(9, 0, 0).unwrap()
+}
+
+// Tasks with a bit of everything, the way a long-used list looks
+fn new_tasks(count: usize) -> Vec<NewTask> {
+    (0..count)
+        .map(|n| {
+            let created_at = start() + Days::new((n / 20) as u64);
+            NewTask {
+                id: None,
+                description: format!("Task number {} with a description of a usual length, café ☕", n),
+                completed: n % 3 == 0,
+                created_at,
+                created_by: Some("bench".to_string()),
+                updated_by: Some("bench".to_string()),
+                updated_at: created_at,
+                completed_at: None,
+                due_at: (n % 4 == 0).then(|| created_at + Days::new(7)),
+                priority: [None, Some(Priority::Low), Some(Priority::Medium), Some(Priority::High)][n % 4],
+                project_id: None,
+                owner_id: None,
+                assignee_id: None,
+                workspace_id: None,
+            }
+        })
+        .collect()
+}
+
+fn tasks(count: usize) -> Vec<Task> {
+    new_tasks(count)
+        .into_iter()
+        .enumerate()
+        .map(|(n, task)| Task {
+            id: n as i32 + 1,
+            description: task.description,
+            completed: task.completed,
+            created_at: task.created_at,
+            created_by: task.created_by,
+            updated_by: task.updated_by,
+            updated_at: task.updated_at,
+            due_at: task.due_at,
+            priority: task.priority,
+            project_id: None,
+            owner_id: None,
+            assignee_id: None,
+            workspace_id: None,
+        })
+        .collect()
+}
+
+fn rendering(c: &mut Criterion) {
+    let tasks = tasks(TASKS);
+    let store = MemoryStore::new("bench");
+    let bundles: Vec<TaskBundle> = new_tasks(TASKS)
+        .into_iter()
+        .map(|task| TaskBundle {
+            task,
+            tags: Vec::new(),
+            project: None,
+            checklist: Vec::new(),
+            notes: Vec::new(),
+            external: None,
+        })
+        .collect();
+    store.import(&bundles);
+
+    let mut group = c.benchmark_group("render");
+    group.throughput(Throughput::Elements(TASKS as u64));
+    group.bench_function("format_task", |b| {
+        b.iter(|| tasks.iter().map(|task| format_task(black_box(task)).len()).sum::<usize>())
+    });
+    // `task list`, less the database: streamed, newest first, into a buffer
+    group.bench_function("list", |b| {
+        b.iter(|| {
+            let mut out = Vec::with_capacity(TASKS * 120);
+            block_on(views::all(&store, &mut out)).unwrap();
+            out.len()
+        })
+    });
+    group.finish();
+}
+
+fn todotxt(c: &mut Criterion) {
+    let tasks = tasks(TASKS);
+    let tags = vec!["home".to_string(), "errand".to_string()];
+    let lines: Vec<String> = tasks.iter().map(|task| format_line(task, Some("Chores"), &tags)).collect();
+
+    let mut group = c.benchmark_group("todotxt");
+    group.throughput(Throughput::Elements(TASKS as u64));
+    group.bench_function("format", |b| {
+        b.iter(|| tasks.iter().map(|task| format_line(black_box(task), Some("Chores"), &tags).len()).sum::<usize>())
+    });
+    // What `task import todotxt` does before it talks to the database
+    group.bench_function("parse", |b| {
+        b.iter(|| lines.iter().filter(|line| parse_line(black_box(line)).is_ok()).count())
+    });
+    group.finish();
+}
+
+fn bulk_insert(c: &mut Criterion) {
+    let tasks = new_tasks(TASKS);
+
+    let mut group = c.benchmark_group("bulk_insert");
+    group.throughput(Throughput::Elements(TASKS as u64));
+    // Every chunk of one import, bound and ready to send
+    group.bench_function("chunks", |b| {
+        b.iter(|| {
+            for chunk in tasks.chunks(repository::BATCH_SIZE) {
+                let mut query = repository::insert_query(chunk, Some("bench"), Access::default());
+                let _bound = black_box(query.build());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, rendering, todotxt, bulk_insert);
+criterion_main!(benches);
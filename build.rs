@@ -0,0 +1,12 @@
+// Generates the gRPC service from proto/task.proto. The file is parsed with protox, so no
+// protoc needs to be installed. Only `task serve` has the service, so builds without the
+// `server` feature skip it.
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    println!("cargo:rerun-if-changed=proto");
+    #[cfg(feature = "server")]
+    {
+        let descriptors = protox::compile(["proto/task.proto"], ["proto"])?;
+        tonic_build::configure().build_client(false).compile_fds(descriptors)?;
+    }
+    Ok(())
+}
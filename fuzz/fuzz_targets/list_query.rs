@@ -0,0 +1,10 @@
+// Query strings of GET /tasks: the filters and the pagination cursor, straight from clients
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|query: &str| {
+    if let Ok((_, _, limit)) = taskcore::server::parse_list_query(query) {
+        assert!((1..=500).contains(&limit));
+    }
+});
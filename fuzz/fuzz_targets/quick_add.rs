@@ -0,0 +1,29 @@
+// The todo.txt line syntax, read by `task import todotxt` and by the Telegram bot for whatever
+// anyone sends it. Lines that parse are written back out and must parse again.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use taskcore::export::todotxt::format_line;
+use taskcore::import::todotxt::parse_line;
+use taskcore::Task;
+
+fuzz_target!(|line: &str| {
+    let Ok(bundle) = parse_line(line) else { return };
+    let new = bundle.task;
+    let task = Task {
+        id: 1,
+        description: new.description,
+        completed: new.completed,
+        created_at: new.created_at,
+        created_by: new.created_by,
+        updated_by: new.updated_by,
+        updated_at: new.updated_at,
+        due_at: new.due_at,
+        priority: new.priority,
+        project_id: None,
+        owner_id: None,
+        assignee_id: None,
+        workspace_id: None,
+    };
+    let _ = parse_line(&format_line(&task, bundle.project.as_deref(), &bundle.tags));
+});
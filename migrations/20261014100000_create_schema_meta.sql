@@ -0,0 +1,7 @@
+-- Single-row table recording the schema version the database was last migrated to.
+-- `task migrate` writes the version of the newest migration it applied; the binary
+-- compares it against the migrations it was built with before running any command.
+CREATE TABLE schema_meta (
+    id TINYINT PRIMARY KEY,
+    version BIGINT NOT NULL
+);
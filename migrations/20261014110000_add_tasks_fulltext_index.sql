@@ -0,0 +1,2 @@
+-- Relevance-ranked search over task descriptions
+ALTER TABLE tasks ADD FULLTEXT INDEX tasks_description_fulltext (description);
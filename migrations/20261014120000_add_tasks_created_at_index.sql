@@ -0,0 +1,2 @@
+-- Supports keyset pagination over (created_at, id), the default "newest first" ordering
+CREATE INDEX tasks_created_at_id ON tasks (created_at, id);
@@ -0,0 +1,8 @@
+-- Who created/last changed each task, and when it last changed.
+-- Existing rows have no known author; their last change is taken to be their creation.
+ALTER TABLE tasks
+    ADD COLUMN created_by VARCHAR(64) NULL,
+    ADD COLUMN updated_by VARCHAR(64) NULL,
+    ADD COLUMN updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP;
+
+UPDATE tasks SET updated_at = created_at;
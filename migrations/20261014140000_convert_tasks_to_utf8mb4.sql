@@ -0,0 +1,5 @@
+-- Store text as full 4-byte UTF-8 so emoji and other non-BMP characters survive the round trip.
+-- The database default only affects tables created later; CONVERT rewrites the existing data.
+ALTER DATABASE CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+ALTER TABLE tasks CONVERT TO CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
@@ -0,0 +1,23 @@
+-- Optional due date and priority per task, plus free-form tags shared between tasks.
+-- Tag names are unique (case-insensitively, per the table collation); a tag disappears
+-- from a task when either side of the link is deleted.
+ALTER TABLE tasks
+    ADD COLUMN due_at DATETIME NULL,
+    ADD COLUMN priority ENUM('low', 'medium', 'high') NULL;
+
+CREATE INDEX tasks_due_at ON tasks (due_at);
+
+CREATE TABLE tags (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    name VARCHAR(64) NOT NULL,
+    UNIQUE KEY tags_name (name)
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+CREATE TABLE task_tags (
+    task_id INT NOT NULL,
+    tag_id INT NOT NULL,
+    PRIMARY KEY (task_id, tag_id),
+    KEY task_tags_tag_id (tag_id),
+    CONSTRAINT task_tags_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE,
+    CONSTRAINT task_tags_tag FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE
+);
@@ -0,0 +1,21 @@
+-- Projects group tasks; deleting a project keeps its tasks, just without a project.
+-- Checklist items are ordered sub-steps of a single task.
+CREATE TABLE projects (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    name VARCHAR(64) NOT NULL,
+    UNIQUE KEY projects_name (name)
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+ALTER TABLE tasks
+    ADD COLUMN project_id INT NULL,
+    ADD CONSTRAINT tasks_project FOREIGN KEY (project_id) REFERENCES projects (id) ON DELETE SET NULL;
+
+CREATE TABLE checklist_items (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    task_id INT NOT NULL,
+    position INT NOT NULL,
+    text VARCHAR(255) NOT NULL,
+    done BOOLEAN NOT NULL DEFAULT FALSE,
+    KEY checklist_items_task (task_id, position),
+    CONSTRAINT checklist_items_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
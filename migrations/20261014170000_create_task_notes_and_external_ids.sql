@@ -0,0 +1,20 @@
+-- Timestamped free-text notes on a task (e.g. Taskwarrior annotations).
+CREATE TABLE task_notes (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    task_id INT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    body TEXT NOT NULL,
+    KEY task_notes_task (task_id, created_at),
+    CONSTRAINT task_notes_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+-- Identity of a task in another system, e.g. a Taskwarrior UUID or a remote issue number.
+-- Importers use it to recognize tasks they brought in before; syncs to find the remote side.
+CREATE TABLE external_ids (
+    task_id INT NOT NULL,
+    source VARCHAR(32) NOT NULL,
+    external_id VARCHAR(255) NOT NULL,
+    PRIMARY KEY (source, external_id),
+    KEY external_ids_task (task_id),
+    CONSTRAINT external_ids_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
@@ -0,0 +1,3 @@
+-- When a linked task was last brought in line with the other system. A task whose
+-- updated_at is later than this has local changes that still need to be pushed.
+ALTER TABLE external_ids ADD COLUMN synced_at DATETIME NULL;
@@ -0,0 +1,20 @@
+-- CalDAV sync state. Every VTODO resource on the server is tracked with the entity tag seen
+-- at the last sync, which tells changes on the server apart from changes here. A row outlives
+-- its task (task_id becomes NULL), so a task deleted here is deleted on the server as well
+-- instead of coming back with the next sync. Hrefs are case-sensitive, hence the binary collation.
+CREATE TABLE caldav_resources (
+    href VARCHAR(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_bin NOT NULL PRIMARY KEY,
+    uid VARCHAR(255) NOT NULL,
+    task_id INT NULL,
+    etag VARCHAR(255) NULL,
+    synced_at DATETIME NULL,
+    KEY caldav_resources_task (task_id),
+    CONSTRAINT caldav_resources_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE SET NULL
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+-- Collection tag (ctag) of each synced collection as of the last sync. While it is unchanged
+-- nothing on the server changed and listing the resources can be skipped.
+CREATE TABLE caldav_collections (
+    url VARCHAR(255) CHARACTER SET utf8mb4 COLLATE utf8mb4_bin NOT NULL PRIMARY KEY,
+    ctag VARCHAR(255) NULL
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
@@ -0,0 +1,17 @@
+-- Every webhook request, one row per event and URL, kept as a delivery log. The payload is
+-- stored so a failed delivery can be sent again unchanged. There is no foreign key on task_id:
+-- the log of a deleted task (including its "deleted" event) stays.
+CREATE TABLE webhook_deliveries (
+    id BIGINT UNSIGNED NOT NULL AUTO_INCREMENT PRIMARY KEY,
+    url VARCHAR(2048) NOT NULL,
+    event VARCHAR(32) NOT NULL,
+    task_id INT NULL,
+    payload TEXT NOT NULL,
+    status ENUM('pending', 'delivered', 'failed') NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    response_code SMALLINT UNSIGNED NULL,
+    error VARCHAR(1024) NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_attempt_at DATETIME NULL,
+    KEY webhook_deliveries_status (status, id)
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
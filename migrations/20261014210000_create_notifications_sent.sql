@@ -0,0 +1,10 @@
+-- Notifications already sent, so running `task notify` again (e.g. from cron) doesn't repeat
+-- them. `subject` identifies what was notified about: "<task id>@<due time>" for reminders,
+-- so a task whose due date moves gets reminded again, and the date for daily digests.
+CREATE TABLE notifications_sent (
+    channel VARCHAR(32) NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    subject VARCHAR(64) NOT NULL,
+    sent_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (channel, kind, subject)
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
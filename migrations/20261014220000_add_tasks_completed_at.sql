@@ -0,0 +1,5 @@
+-- When each task was completed, for reports over a time range; NULL while it is pending.
+-- For tasks completed before this column existed, their last change is the best guess.
+ALTER TABLE tasks ADD COLUMN completed_at DATETIME NULL;
+
+UPDATE tasks SET completed_at = updated_at WHERE completed = TRUE;
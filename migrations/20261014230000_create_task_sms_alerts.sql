@@ -0,0 +1,7 @@
+-- Tasks whose reminders are also sent by SMS. Texts cost money, so this is opt-in per task
+-- (`task sms enable <id>`) and `task notify sms` ignores every other task.
+CREATE TABLE task_sms_alerts (
+    task_id INT NOT NULL PRIMARY KEY,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT task_sms_alerts_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
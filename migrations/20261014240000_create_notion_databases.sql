@@ -0,0 +1,6 @@
+-- Notion sync state: the newest last_edited_time seen in each synced database, as Notion
+-- wrote it (RFC 3339, to the minute). The next sync only asks for pages edited since then.
+CREATE TABLE notion_databases (
+    database_id VARCHAR(64) NOT NULL PRIMARY KEY,
+    last_edited_time VARCHAR(64) NULL
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
@@ -0,0 +1,26 @@
+-- Accounts for `task login`. Passwords are stored as Argon2id hashes in the PHC string format.
+-- Once the first account exists every task belongs to one (owner_id) and commands only see
+-- the tasks of the logged-in user; before that, owner_id stays NULL and nothing is scoped.
+CREATE TABLE users (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    username VARCHAR(64) NOT NULL,
+    password_hash VARCHAR(255) NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY users_username (username)
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+-- Logins. Only a SHA-256 hash of the token is kept; the token itself is in the keyring of the
+-- machine that logged in.
+CREATE TABLE user_sessions (
+    token_hash CHAR(64) NOT NULL PRIMARY KEY,
+    user_id INT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL,
+    KEY user_sessions_user (user_id),
+    CONSTRAINT user_sessions_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+-- A user who still owns tasks can't be deleted
+ALTER TABLE tasks
+    ADD COLUMN owner_id INT NULL,
+    ADD CONSTRAINT tasks_owner FOREIGN KEY (owner_id) REFERENCES users (id);
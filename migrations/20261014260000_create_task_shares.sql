@@ -0,0 +1,26 @@
+-- Tasks a user shared with others, one at a time or (project_shares) all of the owner's tasks
+-- in a project, now and later. can_write lets the other user change, complete and delete them
+-- as well. Projects aren't owned by anyone, so a project share is always of the sharer's tasks.
+CREATE TABLE task_shares (
+    task_id INT NOT NULL,
+    user_id INT NOT NULL,
+    can_write BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (task_id, user_id),
+    KEY task_shares_user (user_id),
+    CONSTRAINT task_shares_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE,
+    CONSTRAINT task_shares_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+CREATE TABLE project_shares (
+    owner_id INT NOT NULL,
+    project_id INT NOT NULL,
+    user_id INT NOT NULL,
+    can_write BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (owner_id, project_id, user_id),
+    KEY project_shares_user (user_id),
+    CONSTRAINT project_shares_owner FOREIGN KEY (owner_id) REFERENCES users (id) ON DELETE CASCADE,
+    CONSTRAINT project_shares_project FOREIGN KEY (project_id) REFERENCES projects (id) ON DELETE CASCADE,
+    CONSTRAINT project_shares_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
@@ -0,0 +1,6 @@
+-- The user a task is assigned to, who may be someone other than its owner. The assignee can
+-- see and change the task like its owner; deleting the account unassigns it.
+ALTER TABLE tasks
+    ADD COLUMN assignee_id INT NULL,
+    ADD KEY tasks_assignee (assignee_id),
+    ADD CONSTRAINT tasks_assignee FOREIGN KEY (assignee_id) REFERENCES users (id) ON DELETE SET NULL;
@@ -0,0 +1,40 @@
+-- Workspaces let one database hold several independent groups. Users join workspaces, and each
+-- project and task is in at most one; commands only see the projects and tasks of the
+-- workspace picked with `task workspace switch`. Those outside every workspace (workspace_id
+-- NULL) are each user's personal space, where everything was before there were workspaces.
+CREATE TABLE workspaces (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    name VARCHAR(64) NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY workspaces_name (name)
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+CREATE TABLE workspace_members (
+    workspace_id INT NOT NULL,
+    user_id INT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (workspace_id, user_id),
+    KEY workspace_members_user (user_id),
+    CONSTRAINT workspace_members_workspace FOREIGN KEY (workspace_id) REFERENCES workspaces (id) ON DELETE CASCADE,
+    CONSTRAINT workspace_members_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+-- Project names are unique within a workspace. NULLs never collide in a unique key, so the
+-- personal space counts as workspace 0 there. (MySQL doesn't allow ON DELETE CASCADE on the
+-- base column of a stored generated column, so a workspace has to be emptied to delete it.)
+ALTER TABLE projects
+    ADD COLUMN workspace_id INT NULL,
+    ADD COLUMN workspace_key INT AS (COALESCE(workspace_id, 0)) STORED,
+    DROP KEY projects_name,
+    ADD UNIQUE KEY projects_name (workspace_key, name),
+    ADD CONSTRAINT projects_workspace FOREIGN KEY (workspace_id) REFERENCES workspaces (id);
+
+ALTER TABLE tasks
+    ADD COLUMN workspace_id INT NULL,
+    ADD KEY tasks_workspace (workspace_id, created_at),
+    ADD CONSTRAINT tasks_workspace FOREIGN KEY (workspace_id) REFERENCES workspaces (id);
+
+-- The workspace each login has switched to
+ALTER TABLE user_sessions
+    ADD COLUMN workspace_id INT NULL,
+    ADD CONSTRAINT user_sessions_workspace FOREIGN KEY (workspace_id) REFERENCES workspaces (id) ON DELETE SET NULL;
@@ -0,0 +1,7 @@
+-- What each member may do in a workspace: admins everything, members everything except
+-- deleting tasks and managing projects and members, viewers only read. Every member could do
+-- everything before, so the existing ones become admins.
+ALTER TABLE workspace_members
+    ADD COLUMN role ENUM('admin', 'member', 'viewer') NOT NULL DEFAULT 'member';
+
+UPDATE workspace_members SET role = 'admin';
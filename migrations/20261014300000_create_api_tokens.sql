@@ -0,0 +1,18 @@
+-- Personal access tokens for `task serve`. As with logins only a SHA-256 hash is kept; the token
+-- is shown once, when it is created. A token works in the workspace it was created in (NULL for
+-- the personal space), and a 'read' token can't change anything.
+CREATE TABLE api_tokens (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    user_id INT NOT NULL,
+    workspace_id INT NULL,
+    name VARCHAR(64) NOT NULL,
+    token_hash CHAR(64) NOT NULL,
+    scope ENUM('read', 'write') NOT NULL DEFAULT 'read',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at DATETIME NULL,
+    expires_at DATETIME NULL,
+    UNIQUE KEY api_tokens_hash (token_hash),
+    KEY api_tokens_user (user_id),
+    CONSTRAINT api_tokens_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    CONSTRAINT api_tokens_workspace FOREIGN KEY (workspace_id) REFERENCES workspaces (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
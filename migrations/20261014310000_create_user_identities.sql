@@ -0,0 +1,12 @@
+-- Accounts at an OpenID Connect provider that log in as a user, by the provider's (issuer's)
+-- unchanging id for them. Users created on their first login this way get the password hash
+-- '!', which no password matches, so they can only log in through the provider.
+CREATE TABLE user_identities (
+    issuer VARCHAR(255) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    user_id INT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (issuer, subject),
+    KEY user_identities_user (user_id),
+    CONSTRAINT user_identities_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
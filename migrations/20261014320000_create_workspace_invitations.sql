@@ -0,0 +1,19 @@
+-- Codes that let someone without an account create one as a member of a workspace, with the
+-- role the admin who made the code chose. Only a SHA-256 hash of the code is kept. A code works
+-- once (redeemed_at) and not after expires_at.
+CREATE TABLE workspace_invitations (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    workspace_id INT NOT NULL,
+    code_hash CHAR(64) NOT NULL,
+    role ENUM('admin', 'member', 'viewer') NOT NULL DEFAULT 'member',
+    created_by INT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL,
+    redeemed_at DATETIME NULL,
+    redeemed_by INT NULL,
+    UNIQUE KEY workspace_invitations_code (code_hash),
+    KEY workspace_invitations_workspace (workspace_id),
+    CONSTRAINT workspace_invitations_workspace FOREIGN KEY (workspace_id) REFERENCES workspaces (id) ON DELETE CASCADE,
+    CONSTRAINT workspace_invitations_creator FOREIGN KEY (created_by) REFERENCES users (id) ON DELETE SET NULL,
+    CONSTRAINT workspace_invitations_redeemer FOREIGN KEY (redeemed_by) REFERENCES users (id) ON DELETE SET NULL
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
@@ -0,0 +1,10 @@
+-- Preferences that follow an account from machine to machine, set with `task settings`. Each
+-- fills in what the config file of the machine leaves unset; see settings.rs for the names.
+CREATE TABLE user_settings (
+    user_id INT NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    value VARCHAR(255) NOT NULL,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, name),
+    CONSTRAINT user_settings_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
@@ -0,0 +1,8 @@
+-- Instance admins run `task admin` for everyone sharing the database: they list, disable and
+-- re-enable accounts and reset passwords. The first account becomes one. A disabled account
+-- (disabled_at) can't log in, and its sessions and API tokens stop working.
+ALTER TABLE users
+    ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN disabled_at DATETIME NULL;
+
+UPDATE users SET is_admin = TRUE ORDER BY id LIMIT 1;
@@ -0,0 +1,18 @@
+-- What happened to tasks, and who did it, for `task activity`. Rows keep the task's description
+-- as it was, and stay after the task is deleted (task_id then points nowhere). actor is the name
+-- recorded in tasks.updated_by; user_id the account, once there are accounts.
+CREATE TABLE task_activity (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    task_id INT NOT NULL,
+    description VARCHAR(255) NOT NULL,
+    action ENUM('created', 'updated', 'completed', 'reopened', 'deleted', 'assigned') NOT NULL,
+    actor VARCHAR(64) NULL,
+    user_id INT NULL,
+    workspace_id INT NULL,
+    occurred_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    KEY task_activity_user (user_id, id),
+    KEY task_activity_workspace (workspace_id, id),
+    KEY task_activity_task (task_id),
+    CONSTRAINT task_activity_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE SET NULL,
+    CONSTRAINT task_activity_workspace FOREIGN KEY (workspace_id) REFERENCES workspaces (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
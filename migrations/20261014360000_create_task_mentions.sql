@@ -0,0 +1,15 @@
+-- Who wrote a note, as in tasks.created_by; NULL for notes brought in by imports and syncs
+ALTER TABLE task_notes ADD COLUMN created_by VARCHAR(64) NULL;
+
+-- Users a note names with @username, among those who can see its task. Each channel records the
+-- mentions it told someone about in notifications_sent, as kind 'mention' with the subject
+-- "<note id>/<user id>".
+CREATE TABLE task_mentions (
+    note_id INT NOT NULL,
+    user_id INT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (note_id, user_id),
+    KEY task_mentions_user (user_id, created_at),
+    CONSTRAINT task_mentions_note FOREIGN KEY (note_id) REFERENCES task_notes (id) ON DELETE CASCADE,
+    CONSTRAINT task_mentions_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
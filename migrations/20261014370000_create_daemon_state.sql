@@ -0,0 +1,23 @@
+-- The `task daemon` that runs against this database, if any: a single row (id 1) that the daemon
+-- claims on start and keeps alive with a heartbeat. One whose heartbeat stopped 90 seconds ago
+-- counts as gone, so another may take over after a crash.
+CREATE TABLE daemon_state (
+    id TINYINT UNSIGNED NOT NULL PRIMARY KEY,
+    host VARCHAR(255) NOT NULL,
+    pid INT UNSIGNED NOT NULL,
+    started_at DATETIME NOT NULL,
+    heartbeat_at DATETIME NOT NULL,
+    stopped_at DATETIME NULL
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+-- How each job of the daemon last went, by name, e.g. "notify email" or "sync todoist"
+CREATE TABLE daemon_jobs (
+    job VARCHAR(64) NOT NULL PRIMARY KEY,
+    runs INT UNSIGNED NOT NULL DEFAULT 0,
+    failures INT UNSIGNED NOT NULL DEFAULT 0,
+    last_started_at DATETIME NULL,
+    last_finished_at DATETIME NULL,
+    -- Of the last run, NULL if it succeeded
+    last_error TEXT NULL,
+    next_run_at DATETIME NULL
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
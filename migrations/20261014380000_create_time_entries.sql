@@ -0,0 +1,14 @@
+-- Time spent on tasks, tracked with `task time start` and `task time stop`. An entry without
+-- ended_at is a timer still running; each user has at most one, as starting another stops it.
+-- user_id is NULL without accounts, and once the account is deleted.
+CREATE TABLE time_entries (
+    id INT AUTO_INCREMENT PRIMARY KEY,
+    task_id INT NOT NULL,
+    user_id INT NULL,
+    started_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    ended_at DATETIME NULL,
+    KEY time_entries_started (started_at),
+    KEY time_entries_running (user_id, ended_at),
+    CONSTRAINT time_entries_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE,
+    CONSTRAINT time_entries_user FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE SET NULL
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
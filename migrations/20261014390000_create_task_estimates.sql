@@ -0,0 +1,7 @@
+-- How long a task is expected to take, set with `task estimate`. Kept apart from tasks because
+-- most tasks never get one.
+CREATE TABLE task_estimates (
+    task_id INT NOT NULL PRIMARY KEY,
+    minutes INT NOT NULL,
+    CONSTRAINT task_estimates_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
@@ -0,0 +1,21 @@
+-- Tasks that come back, set with `task recur`: the task is the template, and its due date the
+-- first occurrence. every is e.g. "1 week" or "3 months" (see recurrence::Every); occurrences
+-- before created_at, when it was set, are never created.
+CREATE TABLE task_recurrences (
+    task_id INT NOT NULL PRIMARY KEY,
+    every VARCHAR(16) NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT task_recurrences_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
+
+-- Each occurrence the daemon created a task for, so none is created twice. The row stays when
+-- the task made for it is deleted (task_id becomes NULL), and goes with the recurring task.
+CREATE TABLE task_occurrences (
+    recurring_id INT NOT NULL,
+    due_at DATETIME NOT NULL,
+    task_id INT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (recurring_id, due_at),
+    CONSTRAINT task_occurrences_recurring FOREIGN KEY (recurring_id) REFERENCES tasks (id) ON DELETE CASCADE,
+    CONSTRAINT task_occurrences_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE SET NULL
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
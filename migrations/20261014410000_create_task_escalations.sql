@@ -0,0 +1,16 @@
+-- Overdue tasks escalated by `task escalate run` or the daemon, once per task and due date:
+-- the priority before and after (the same when it wasn't raised), and the channel told, if
+-- any, with when that went out (NULL until it has, e.g. during quiet hours).
+CREATE TABLE task_escalations (
+    task_id INT NOT NULL,
+    due_at DATETIME NOT NULL,
+    escalated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    days_overdue INT NOT NULL,
+    old_priority ENUM('low', 'medium', 'high') NULL,
+    new_priority ENUM('low', 'medium', 'high') NULL,
+    channel VARCHAR(16) NULL,
+    notified_at DATETIME NULL,
+    PRIMARY KEY (task_id, due_at),
+    KEY task_escalations_escalated_at (escalated_at),
+    CONSTRAINT task_escalations_task FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE
+) CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci;
@@ -0,0 +1,127 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::mysql::{MySqlTypeInfo, MySqlValueRef};
+use sqlx::{Decode, Encode, MySql, Type};
+
+use crate::error::{Result, TaskError};
+use crate::repository::{ActivityEntry, TaskRepository};
+
+// Entries per page when --limit isn't given
+const DEFAULT_LIMIT: u32 = 20;
+const MAX_LIMIT: u32 = 500;
+
+// What was done to a task. The repository records one whenever it creates, changes, completes,
+// deletes or assigns tasks; the seeding of test data is left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Created,
+    Updated,
+    Completed,
+    Reopened,
+    Deleted,
+    Assigned,
+}
+
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Created => "created",
+            Action::Updated => "updated",
+            Action::Completed => "completed",
+            Action::Reopened => "reopened",
+            Action::Deleted => "deleted",
+            Action::Assigned => "assigned",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "created" => Ok(Action::Created),
+            "updated" => Ok(Action::Updated),
+            "completed" => Ok(Action::Completed),
+            "reopened" => Ok(Action::Reopened),
+            "deleted" => Ok(Action::Deleted),
+            "assigned" => Ok(Action::Assigned),
+            _ => Err(format!("unknown activity '{}'", s)),
+        }
+    }
+}
+
+// Stored in the ENUM column task_activity.action
+impl Type<MySql> for Action {
+    fn type_info() -> MySqlTypeInfo {
+        <str as Type<MySql>>::type_info()
+    }
+
+    fn compatible(ty: &MySqlTypeInfo) -> bool {
+        <str as Type<MySql>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, MySql> for Action {
+    fn encode_by_ref(&self, buf: &mut Vec<u8>) -> IsNull {
+        <&str as Encode<MySql>>::encode(self.as_str(), buf)
+    }
+}
+
+impl<'r> Decode<'r, MySql> for Action {
+    fn decode(value: MySqlValueRef<'r>) -> std::result::Result<Self, BoxDynError> {
+        Ok(<&str as Decode<MySql>>::decode(value)?.parse()?)
+    }
+}
+
+// `task activity`: newest first, a page at a time. Shows what happened in the current workspace
+// (or the personal space) to tasks the user can see, and everything they did themselves there;
+// workspace admins see all of it. --user narrows it to one person.
+pub async fn run(repo: &TaskRepository, user: Option<&str>, limit: Option<u32>, before: Option<i64>) -> Result<()> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT);
+    if limit == 0 || limit > MAX_LIMIT {
+        return Err(TaskError::InvalidInput(format!("--limit must be between 1 and {}.", MAX_LIMIT)));
+    }
+    let user_id = match user.map(str::trim) {
+        Some(username) => Some(
+            repo.user_by_name(username)
+                .await?
+                .ok_or_else(|| TaskError::InvalidInput(format!("There is no user named {}.", username)))?
+                .id,
+        ),
+        None => None,
+    };
+
+    let page = repo.activity(user_id, before, limit).await?;
+    if page.entries.is_empty() {
+        println!("No activity yet.");
+        return Ok(());
+    }
+    for entry in &page.entries {
+        println!("{}  {}", entry.occurred_at.format("%Y-%m-%d %H:%M"), sentence(entry));
+    }
+    if let Some(next) = page.next {
+        println!("\nOlder entries: task activity --before {}", next);
+    }
+    Ok(())
+}
+
+// "alice completed 'deploy release' (#12)"
+fn sentence(entry: &ActivityEntry) -> String {
+    format!(
+        "{} {} '{}' (#{})",
+        entry.actor.as_deref().unwrap_or("someone"),
+        entry.action,
+        entry.description,
+        entry.task_id
+    )
+}
@@ -0,0 +1,127 @@
+use crate::cli::{AdminCommand, AdminUserCommand};
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::repository::{TaskRepository, User};
+use crate::users;
+
+// Length of the passwords `reset-password` makes up
+const RESET_PASSWORD_LENGTH: usize = 20;
+
+// `task admin`: running a database that several people share. Only instance admins may use it;
+// the first account is one, and admins make others admins. The repository checks this, so the
+// commands here only keep an instance from locking out its last admin.
+pub async fn run(repo: &TaskRepository, config: &Config, command: AdminCommand) -> Result<()> {
+    let Some(user_id) = repo.user() else {
+        return Err(TaskError::InvalidInput(
+            "There are no accounts to manage yet; create the first with `task user add <name>`.".to_string(),
+        ));
+    };
+    match command {
+        AdminCommand::Users { command } => users_command(repo, config, user_id, command).await,
+        AdminCommand::Stats => stats(repo).await,
+    }
+}
+
+async fn users_command(repo: &TaskRepository, config: &Config, user_id: i32, command: AdminUserCommand) -> Result<()> {
+    match command {
+        AdminUserCommand::List => list(repo, user_id).await?,
+        AdminUserCommand::Disable { user } => {
+            let account = account_named(repo, &user).await?;
+            if account.id == user_id {
+                return Err(TaskError::InvalidInput("You can't disable your own account.".to_string()));
+            }
+            if repo.set_disabled(account.id, true).await? {
+                println!("Disabled {}; their sessions and API tokens no longer work.", account.username);
+            } else {
+                println!("{} is already disabled.", account.username);
+            }
+        }
+        AdminUserCommand::Enable { user } => {
+            let account = account_named(repo, &user).await?;
+            if repo.set_disabled(account.id, false).await? {
+                println!("Enabled {}; they can log in again.", account.username);
+            } else {
+                println!("{} is not disabled.", account.username);
+            }
+        }
+        AdminUserCommand::ResetPassword { user } => {
+            if config.ldap.url.is_some() {
+                return Err(TaskError::InvalidInput(
+                    "With [ldap] set, passwords are changed in the directory.".to_string(),
+                ));
+            }
+            let account = account_named(repo, &user).await?;
+            let password: String = users::new_token().chars().take(RESET_PASSWORD_LENGTH).collect();
+            repo.reset_password(account.id, &users::hash_password(&password)?).await?;
+            println!("New password for {}, shown only now:", account.username);
+            println!("{}", password);
+            println!("Their sessions have ended. Ask them to change it with `task user passwd`.");
+        }
+        AdminUserCommand::Promote { user } => {
+            let account = account_named(repo, &user).await?;
+            repo.set_admin(account.id, true).await?;
+            println!("{} is now an instance admin.", account.username);
+        }
+        AdminUserCommand::Demote { user } => {
+            let account = account_named(repo, &user).await?;
+            let admins: Vec<i32> =
+                repo.accounts().await?.iter().filter(|account| account.is_admin).map(|account| account.id).collect();
+            if admins == [account.id] {
+                return Err(TaskError::InvalidInput(
+                    "The instance needs an admin; promote someone else first.".to_string(),
+                ));
+            }
+            repo.set_admin(account.id, false).await?;
+            println!("{} is no longer an instance admin.", account.username);
+        }
+    }
+    Ok(())
+}
+
+async fn list(repo: &TaskRepository, user_id: i32) -> Result<()> {
+    for account in repo.accounts().await? {
+        let mut notes = Vec::new();
+        if account.is_admin {
+            notes.push("admin".to_string());
+        }
+        if let Some(disabled_at) = account.disabled_at {
+            notes.push(format!("disabled {}", disabled_at.format("%Y-%m-%d")));
+        }
+        if account.id == user_id {
+            notes.push("you".to_string());
+        }
+        let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+        println!(
+            "{:<24} created {}, {:>5} task(s), {:>3} workspace(s){}",
+            account.username,
+            account.created_at.format("%Y-%m-%d"),
+            account.tasks,
+            account.workspaces,
+            notes
+        );
+    }
+    Ok(())
+}
+
+async fn stats(repo: &TaskRepository) -> Result<()> {
+    let stats = repo.instance_stats().await?;
+    println!("\n--- Accounts ---");
+    println!("Users:          {} ({} admin(s), {} disabled)", stats.users, stats.admins, stats.disabled);
+    println!("Sessions:       {}", stats.sessions);
+    println!("API tokens:     {}", stats.api_tokens);
+    println!("Invitations:    {}", stats.invitations);
+
+    println!("\n--- Content ---");
+    println!("Workspaces:     {}", stats.workspaces);
+    println!("Projects:       {}", stats.projects);
+    println!("Tasks:          {} ({} open, {} overdue)", stats.tasks, stats.open_tasks, stats.overdue_tasks);
+    Ok(())
+}
+
+// Fails for unknown names. The check for admin rights comes after, in the repository, so anyone
+// may learn whether a username exists; `task user list` shows them all anyway.
+async fn account_named(repo: &TaskRepository, username: &str) -> Result<User> {
+    repo.user_by_name(username.trim())
+        .await?
+        .ok_or_else(|| TaskError::InvalidInput(format!("There is no user named {}.", username.trim())))
+}
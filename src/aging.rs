@@ -0,0 +1,95 @@
+// How old the open tasks are: the average, median and 90th percentile of their ages, now and
+// at earlier times, so a backlog that keeps growing older shows. Earlier times are worked out
+// from when tasks were created and completed; deleted tasks count as if they never were.
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::repository::OpenSpan;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ages {
+    pub open: usize,
+    pub average_secs: i64,
+    pub median_secs: i64,
+    // Nine in ten open tasks are no older than this
+    pub p90_secs: i64,
+}
+
+// The ages of the tasks open at `at`; None if none were
+pub fn ages_at(spans: &[OpenSpan], at: NaiveDateTime) -> Option<Ages> {
+    let mut ages: Vec<i64> = spans
+        .iter()
+        .filter(|span| span.created_at <= at && span.completed_at.is_none_or(|completed| completed > at))
+        .map(|span| (at - span.created_at).num_seconds())
+        .collect();
+    if ages.is_empty() {
+        return None;
+    }
+    ages.sort_unstable();
+    Some(Ages {
+        open: ages.len(),
+        average_secs: ages.iter().sum::<i64>() / ages.len() as i64,
+        median_secs: percentile(&ages, 50),
+        p90_secs: percentile(&ages, 90),
+    })
+}
+
+// The ages at `now` and every week before it, `weeks` back, oldest first
+pub fn weekly(spans: &[OpenSpan], now: NaiveDateTime, weeks: u32) -> Vec<(NaiveDateTime, Option<Ages>)> {
+    (0..=i64::from(weeks))
+        .rev()
+        .map(|week| {
+            let at = now - Duration::weeks(week);
+            (at, ages_at(spans, at))
+        })
+        .collect()
+}
+
+// The nearest-rank percentile of values in order: the smallest that at least `percent` percent
+// of them are no more than
+fn percentile(sorted: &[i64], percent: usize) -> i64 {
+    let rank = (sorted.len() * percent).div_ceil(100).max(1);
+    sorted[rank.min(sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::at;
+
+    fn span(created: u32, completed: Option<u32>) -> OpenSpan {
+        OpenSpan { created_at: at(created), completed_at: completed.map(at) }
+    }
+
+    const DAY: i64 = 24 * 3600;
+
+    #[test]
+    fn only_the_tasks_open_at_the_time_count() {
+        let spans = [span(1, None), span(3, Some(8)), span(5, None), span(9, None)];
+        // On the 10th the one done on the 8th is no longer open
+        let ages = ages_at(&spans, at(10)).unwrap();
+        assert_eq!(ages, Ages { open: 3, average_secs: 5 * DAY, median_secs: 5 * DAY, p90_secs: 9 * DAY });
+        // On the 7th it still was, and the one of the 9th wasn't there yet
+        assert_eq!(ages_at(&spans, at(7)).unwrap().open, 3);
+        assert_eq!(ages_at(&spans, at(1) - Duration::hours(1)), None);
+    }
+
+    #[test]
+    fn percentiles_are_values_that_occur() {
+        let values: Vec<i64> = (1..=10).collect();
+        assert_eq!(percentile(&values, 50), 5);
+        assert_eq!(percentile(&values, 90), 9);
+        assert_eq!(percentile(&[7], 90), 7);
+        assert_eq!(percentile(&[1, 2, 3], 50), 2);
+    }
+
+    #[test]
+    fn the_trend_goes_back_a_week_at_a_time() {
+        let spans = [span(1, None)];
+        let trend = weekly(&spans, at(15), 2);
+        let points: Vec<(NaiveDateTime, Option<usize>)> =
+            trend.iter().map(|(at, ages)| (*at, ages.map(|ages| ages.open))).collect();
+        assert_eq!(points, [(at(1), Some(1)), (at(8), Some(1)), (at(15), Some(1))]);
+        assert_eq!(trend[2].1.unwrap().average_secs, 14 * DAY);
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::mysql::{MySqlTypeInfo, MySqlValueRef};
+use sqlx::{Decode, Encode, MySql, Type};
+
+use crate::cli::ApiTokenCommand;
+use crate::error::{Result, TaskError};
+use crate::repository::TaskRepository;
+use crate::users;
+
+// Length of api_tokens.name
+const MAX_NAME: usize = 64;
+
+// What requests with a token may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Read,
+    Write,
+}
+
+impl Scope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Write => "write",
+        }
+    }
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Scope::Read => "read-only",
+            Scope::Write => "read-write",
+        })
+    }
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Scope::Read),
+            "write" => Ok(Scope::Write),
+            _ => Err(format!("unknown token scope '{}'", s)),
+        }
+    }
+}
+
+// Stored in an ENUM('read', 'write') column, which MySQL sends as a string
+impl Type<MySql> for Scope {
+    fn type_info() -> MySqlTypeInfo {
+        <str as Type<MySql>>::type_info()
+    }
+
+    fn compatible(ty: &MySqlTypeInfo) -> bool {
+        <str as Type<MySql>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, MySql> for Scope {
+    fn encode_by_ref(&self, buf: &mut Vec<u8>) -> IsNull {
+        <&str as Encode<MySql>>::encode(self.as_str(), buf)
+    }
+}
+
+impl<'r> Decode<'r, MySql> for Scope {
+    fn decode(value: MySqlValueRef<'r>) -> std::result::Result<Self, BoxDynError> {
+        Ok(<&str as Decode<MySql>>::decode(value)?.parse()?)
+    }
+}
+
+// Personal access tokens let scripts and other programs use the API of `task serve` as the
+// user, in the workspace the token was created in. Only a hash is stored, so a token can't be
+// shown again; a lost one is revoked and replaced.
+pub async fn run(repo: &TaskRepository, command: ApiTokenCommand) -> Result<()> {
+    if repo.user().is_none() {
+        return Err(TaskError::InvalidInput(
+            "API tokens need accounts; create the first with `task user add <name>`.".to_string(),
+        ));
+    }
+    match command {
+        ApiTokenCommand::Create { name, write, days } => {
+            let name = name.trim();
+            if name.is_empty() || name.chars().count() > MAX_NAME {
+                return Err(TaskError::InvalidInput(format!("Token names have 1 to {} characters.", MAX_NAME)));
+            }
+            let scope = if write { Scope::Write } else { Scope::Read };
+            let token = users::new_token();
+            let id = repo.create_api_token(name, &users::hash_token(&token), scope, days.map(i64::from)).await?;
+            println!("Created {} token {} ({}). It won't be shown again:", scope, id, name);
+            println!("{}", token);
+            println!("Send it as `Authorization: Bearer <token>` with each request.");
+        }
+        ApiTokenCommand::List => list(repo).await?,
+        ApiTokenCommand::Revoke { id } => {
+            if repo.delete_api_token(id).await? {
+                println!("Revoked token {}.", id);
+            } else {
+                return Err(TaskError::InvalidInput(format!("You have no token {}.", id)));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn list(repo: &TaskRepository) -> Result<()> {
+    let tokens = repo.api_tokens().await?;
+    if tokens.is_empty() {
+        println!("No API tokens. Create one with `task api-token create <name>`.");
+        return Ok(());
+    }
+    let now = chrono::Local::now().naive_local();
+    for token in &tokens {
+        let expires = match token.expires_at {
+            Some(at) if at <= now => "expired".to_string(),
+            Some(at) => format!("expires {}", at.format("%Y-%m-%d")),
+            None => "no expiry".to_string(),
+        };
+        let used = match token.last_used_at {
+            Some(at) => format!("last used {}", at.format("%Y-%m-%d %H:%M")),
+            None => "never used".to_string(),
+        };
+        println!(
+            "{:>4}  {:<24} {:<10} {:<20} created {}, {}, {}",
+            token.id,
+            token.name,
+            token.scope,
+            token.workspace.as_deref().unwrap_or("(personal space)"),
+            token.created_at.format("%Y-%m-%d"),
+            expires,
+            used
+        );
+    }
+    Ok(())
+}
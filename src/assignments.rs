@@ -0,0 +1,65 @@
+use crate::error::{Result, TaskError};
+use crate::repository::{ListCursor, TaskFilter, TaskRepository};
+use crate::shares;
+
+// Tasks fetched at a time for `task assigned`
+const PAGE_SIZE: u32 = 100;
+
+// A task can be assigned to one user besides its owner. The assignee sees it among their own
+// tasks and may change and complete it; with [telegram] notify_assignments they also get a
+// message in their chat (see telegram::spawn_notices).
+
+// `task assign <id> [user]`: anyone who may change the task may assign it
+pub async fn assign(repo: &TaskRepository, id: i32, username: Option<&str>) -> Result<()> {
+    if repo.user().is_none() {
+        return Err(TaskError::InvalidInput(
+            "Tasks are assigned to accounts; create the first with `task user add <name>`.".to_string(),
+        ));
+    }
+    let assignee = match username.map(str::trim) {
+        Some(username) => Some(
+            repo.user_by_name(username)
+                .await?
+                .ok_or_else(|| TaskError::InvalidInput(format!("There is no user named {}.", username)))?,
+        ),
+        None => None,
+    };
+    if let Some(assignee) = &assignee {
+        shares::in_workspace(repo, assignee).await?;
+    }
+
+    if !repo.assign(id, assignee.as_ref().map(|user| user.id)).await? {
+        return Err(TaskError::InvalidInput(format!("No task with ID {} that you may change.", id)));
+    }
+    match assignee {
+        Some(user) => println!("Assigned task {} to {}.", id, user.username),
+        None => println!("Task {} is no longer assigned to anyone.", id),
+    }
+    Ok(())
+}
+
+// `task assigned`: the "assigned to me" view, newest first
+pub async fn list(repo: &TaskRepository) -> Result<()> {
+    let Some(user) = repo.user() else {
+        println!("No users yet, so nothing is assigned. Create the first with `task user add <name>`.");
+        return Ok(());
+    };
+    let filter = TaskFilter { completed: Some(false), assignee: Some(user), ..TaskFilter::default() };
+    let mut cursor: Option<ListCursor> = None;
+    let mut count = 0;
+    loop {
+        let page = repo.filter_page(&filter, cursor, PAGE_SIZE).await?;
+        count += page.tasks.len();
+        for task in &page.tasks {
+            println!("{}", crate::format_task(task));
+        }
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    if count == 0 {
+        println!("No pending tasks are assigned to you.");
+    }
+    Ok(())
+}
@@ -0,0 +1,539 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::ChaCha20Poly1305;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::mysql::MySqlRow;
+use sqlx::{Executor, FromRow, MySqlConnection, MySqlPool};
+
+use crate::cli::BackupArgs;
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::priority::Priority;
+use crate::repository::task_columns;
+use crate::roles::Role;
+use crate::s3;
+use crate::secrets::{self, Service};
+use crate::Task;
+
+// Bump whenever the layout of the backup file changes, so restore can refuse files it
+// doesn't understand.
+pub const BACKUP_FORMAT: &str = "task-backup";
+// Version 2 added the audit columns (created_by, updated_by, updated_at) to tasks.
+// Version 3 added due_at and priority to tasks, and the tags and task_tags tables.
+// Version 4 added project_id to tasks, and the projects and checklist_items tables.
+// Version 5 added the task_notes and external_ids tables.
+// Version 6 added the caldav_resources and caldav_collections tables.
+// Version 7 added the users table and owner_id to tasks. Logins (user_sessions) are not backed
+// up, so everyone logs in again after a restore, and neither are API tokens (api_tokens).
+// Version 8 added the task_shares and project_shares tables.
+// Version 9 added assignee_id to tasks.
+// Version 10 added the workspaces and workspace_members tables, and workspace_id to projects
+// and tasks.
+// Version 11 added role to workspace_members.
+// Version 12 added the user_identities table.
+// Version 13 added the user_settings table.
+// Version 14 added is_admin and disabled_at to users.
+// Version 15 added created_by to task_notes.
+// Version 16 added the time_entries table.
+// Version 17 added the task_estimates table.
+// Version 18 added the task_recurrences and task_occurrences tables.
+// Version 19 added the escalation log (task_escalations), so restored tasks aren't escalated again.
+// Version 20 added completed_at to tasks, the activity feed (task_activity), the SMS opt-ins
+// (task_sms_alerts), workspace invitations (workspace_invitations), so codes not yet redeemed
+// still work after a restore, who notes mention (task_mentions), and the record of sent
+// notifications (notifications_sent), so nobody is told about a mention or reminded of a task
+// again after a restore.
+// The webhook delivery log (webhook_deliveries) is not backed up. Nor is the Notion sync
+// position (notion_databases), so the first Notion sync after a restore reads every page again.
+// Nor is the state of `task daemon` (daemon_state, daemon_jobs).
+pub const BACKUP_VERSION: u32 = 20;
+
+// Names of uploaded backups, with the UTC time in between
+const REMOTE_PREFIX: &str = "task-backup-";
+const REMOTE_SUFFIX: &str = ".json.enc";
+// Uploaded backups kept when [backup] doesn't say
+const DEFAULT_KEEP: usize = 14;
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct UserRow {
+    pub id: i32,
+    pub username: String,
+    pub password_hash: String,
+    pub created_at: chrono::NaiveDateTime,
+    // Missing before version 14; restore then makes the first account the admin
+    #[serde(default)]
+    pub is_admin: bool,
+    #[serde(default)]
+    pub disabled_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct UserIdentityRow {
+    pub issuer: String,
+    pub subject: String,
+    pub user_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct UserSettingRow {
+    pub user_id: i32,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct WorkspaceRow {
+    pub id: i32,
+    pub name: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct WorkspaceMemberRow {
+    pub workspace_id: i32,
+    pub user_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+    // Members of older backups could do everything, as admins can now
+    #[serde(default = "admin_role")]
+    pub role: String,
+}
+
+fn admin_role() -> String {
+    Role::Admin.to_string()
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct InvitationRow {
+    pub id: i32,
+    pub workspace_id: i32,
+    pub code_hash: String,
+    pub role: String,
+    pub created_by: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+    pub expires_at: chrono::NaiveDateTime,
+    pub redeemed_at: Option<chrono::NaiveDateTime>,
+    pub redeemed_by: Option<i32>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct Project {
+    pub id: i32,
+    pub name: String,
+    #[serde(default)]
+    pub workspace_id: Option<i32>,
+}
+
+// A task with when it was completed, which `Task` leaves out
+#[derive(Debug, FromRow, Serialize)]
+pub struct TaskRow {
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub task: Task,
+    pub completed_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct TaskTag {
+    pub task_id: i32,
+    pub tag_id: i32,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct ChecklistRow {
+    pub id: i32,
+    pub task_id: i32,
+    pub position: i32,
+    pub text: String,
+    pub done: bool,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct NoteRow {
+    pub id: i32,
+    pub task_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+    pub body: String,
+    #[serde(default)]
+    pub created_by: Option<String>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct MentionRow {
+    pub note_id: i32,
+    pub user_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct SentNotificationRow {
+    pub channel: String,
+    pub kind: String,
+    pub subject: String,
+    pub sent_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct ExternalIdRow {
+    pub task_id: i32,
+    pub source: String,
+    pub external_id: String,
+    #[serde(default)]
+    pub synced_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct CaldavResourceRow {
+    pub href: String,
+    pub uid: String,
+    pub task_id: Option<i32>,
+    pub etag: Option<String>,
+    pub synced_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct CaldavCollectionRow {
+    pub url: String,
+    pub ctag: Option<String>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct TaskShareRow {
+    pub task_id: i32,
+    pub user_id: i32,
+    pub can_write: bool,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct ProjectShareRow {
+    pub owner_id: i32,
+    pub project_id: i32,
+    pub user_id: i32,
+    pub can_write: bool,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct TimeEntryRow {
+    pub id: i32,
+    pub task_id: i32,
+    pub user_id: Option<i32>,
+    pub started_at: chrono::NaiveDateTime,
+    pub ended_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct EstimateRow {
+    pub task_id: i32,
+    pub minutes: i32,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct RecurrenceRow {
+    pub task_id: i32,
+    pub every: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct EscalationRow {
+    pub task_id: i32,
+    pub due_at: chrono::NaiveDateTime,
+    pub escalated_at: chrono::NaiveDateTime,
+    pub days_overdue: i32,
+    pub old_priority: Option<Priority>,
+    pub new_priority: Option<Priority>,
+    pub channel: Option<String>,
+    pub notified_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct SmsAlertRow {
+    pub task_id: i32,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct ActivityRow {
+    pub id: i64,
+    pub task_id: i32,
+    pub description: String,
+    pub action: String,
+    pub actor: Option<String>,
+    pub user_id: Option<i32>,
+    pub workspace_id: Option<i32>,
+    pub occurred_at: chrono::NaiveDateTime,
+}
+
+#[derive(Debug, FromRow, Serialize, Deserialize)]
+pub struct OccurrenceRow {
+    pub recurring_id: i32,
+    pub due_at: chrono::NaiveDateTime,
+    pub task_id: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+// The file is a single JSON document:
+//
+//   {"format":"task-backup","version":14,"created_at":"...",
+//    "tables":{"users":[...],"user_identities":[...],"user_settings":[...],"workspaces":[...],"projects":[...],
+//              "tasks":[{...},...],"tags":[...],"task_tags":[...],...}}
+//
+// Rows are written one at a time while the query result is streamed, so memory use does
+// not grow with the size of the database.
+pub async fn run(pool: &MySqlPool, config: &Config, args: BackupArgs) -> Result<()> {
+    if let Some(url) = &args.to {
+        return upload(pool, config, url).await;
+    }
+    // clap requires one of the two
+    let Some(path) = &args.file else {
+        return Ok(());
+    };
+
+    let file = File::create(path).map_err(|e| {
+        TaskError::InvalidInput(format!("Could not create {}: {}", path.display(), e))
+    })?;
+    let mut out = BufWriter::new(file);
+    let tasks = write_backup(pool, &mut out).await?;
+    out.flush()?;
+
+    println!("Backed up {} tasks to {}", tasks, path.display());
+    Ok(())
+}
+
+// `task backup --to s3://bucket/path`: uploads an encrypted backup as
+// <path>/task-backup-<UTC time>.json.enc, then deletes all but the newest [backup] keep of
+// them. Other objects under the path are left alone. The backup is built in memory, since it
+// is encrypted as a whole.
+async fn upload(pool: &MySqlPool, config: &Config, url: &str) -> Result<()> {
+    let location = s3::Location::parse(url)?;
+    let client = s3::Client::new(config)?;
+    let passphrase = secrets::token(config, Service::Backup)?;
+
+    let mut plain = Vec::new();
+    let tasks = write_backup(pool, &mut plain).await?;
+    let encrypted = encrypt(&passphrase, &plain)?;
+
+    let name = format!("{}{}{}", REMOTE_PREFIX, chrono::Utc::now().format("%Y%m%dT%H%M%SZ"), REMOTE_SUFFIX);
+    let key = location.key(&name);
+    client.put(&location.bucket, &key, encrypted).await?;
+    println!("Backed up {} tasks to s3://{}/{}", tasks, location.bucket, key);
+
+    let keep = config.backup.keep.unwrap_or(DEFAULT_KEEP).max(1);
+    let mut backups: Vec<String> = client
+        .list(&location.bucket, &location.key(REMOTE_PREFIX))
+        .await?
+        .into_iter()
+        .filter(|key| key.ends_with(REMOTE_SUFFIX))
+        .collect();
+    // The UTC time in the name makes the newest sort last
+    backups.sort();
+    let old = backups.len().saturating_sub(keep);
+    for key in &backups[..old] {
+        client.delete(&location.bucket, key).await?;
+        println!("Deleted old backup s3://{}/{}", location.bucket, key);
+    }
+    Ok(())
+}
+
+// Writes the whole backup document and returns the number of tasks in it. Every table is read
+// on one connection from one snapshot, so what the server or the daemon write meanwhile is in
+// the backup entirely or not at all, and the foreign keys hold when it is restored.
+async fn write_backup(pool: &MySqlPool, out: impl Write) -> Result<u64> {
+    let mut conn = pool.acquire().await?;
+    conn.execute("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ").await?;
+    conn.execute("START TRANSACTION WITH CONSISTENT SNAPSHOT").await?;
+    let written = write_tables(&mut conn, out).await;
+    // Only read, so nothing to keep either way; a failed backup's error comes first
+    let ended = conn.execute(if written.is_ok() { "COMMIT" } else { "ROLLBACK" }).await;
+    let tasks = written?;
+    ended?;
+    Ok(tasks)
+}
+
+async fn write_tables(conn: &mut MySqlConnection, mut out: impl Write) -> Result<u64> {
+    let created_at = chrono::Local::now().to_rfc3339();
+    write!(
+        out,
+        "{{\"format\":{},\"version\":{},\"created_at\":{},\"tables\":{{",
+        json(&BACKUP_FORMAT)?,
+        BACKUP_VERSION,
+        json(&created_at)?
+    )?;
+
+    let users_sql = "SELECT id, username, password_hash, created_at, is_admin, disabled_at FROM users ORDER BY id";
+    write_table::<UserRow>(&mut out, conn, "users", users_sql).await?;
+    out.write_all(b",")?;
+    let identities_sql = "SELECT issuer, subject, user_id, created_at FROM user_identities ORDER BY user_id, issuer";
+    write_table::<UserIdentityRow>(&mut out, conn, "user_identities", identities_sql).await?;
+    out.write_all(b",")?;
+    let settings_sql = "SELECT user_id, name, value FROM user_settings ORDER BY user_id, name";
+    write_table::<UserSettingRow>(&mut out, conn, "user_settings", settings_sql).await?;
+    out.write_all(b",")?;
+    let workspaces_sql = "SELECT id, name, created_at FROM workspaces ORDER BY id";
+    write_table::<WorkspaceRow>(&mut out, conn, "workspaces", workspaces_sql).await?;
+    out.write_all(b",")?;
+    let members_sql = "SELECT workspace_id, user_id, created_at, role FROM workspace_members ORDER BY workspace_id, user_id";
+    write_table::<WorkspaceMemberRow>(&mut out, conn, "workspace_members", members_sql).await?;
+    out.write_all(b",")?;
+    let invitations_sql = "SELECT id, workspace_id, code_hash, role, created_by, created_at, expires_at, redeemed_at, \
+                           redeemed_by FROM workspace_invitations ORDER BY id";
+    write_table::<InvitationRow>(&mut out, conn, "workspace_invitations", invitations_sql).await?;
+    out.write_all(b",")?;
+    let projects_sql = "SELECT id, name, workspace_id FROM projects ORDER BY id";
+    write_table::<Project>(&mut out, conn, "projects", projects_sql).await?;
+    out.write_all(b",")?;
+    let tasks_sql = concat!("SELECT ", task_columns!(), ", completed_at FROM tasks ORDER BY id");
+    let tasks = write_table::<TaskRow>(&mut out, conn, "tasks", tasks_sql).await?;
+    out.write_all(b",")?;
+    write_table::<Tag>(&mut out, conn, "tags", "SELECT id, name FROM tags ORDER BY id").await?;
+    out.write_all(b",")?;
+    let task_tags_sql = "SELECT task_id, tag_id FROM task_tags ORDER BY task_id, tag_id";
+    write_table::<TaskTag>(&mut out, conn, "task_tags", task_tags_sql).await?;
+    out.write_all(b",")?;
+    let checklist_sql = "SELECT id, task_id, position, text, done FROM checklist_items ORDER BY id";
+    write_table::<ChecklistRow>(&mut out, conn, "checklist_items", checklist_sql).await?;
+    out.write_all(b",")?;
+    let notes_sql = "SELECT id, task_id, created_at, body, created_by FROM task_notes ORDER BY id";
+    write_table::<NoteRow>(&mut out, conn, "task_notes", notes_sql).await?;
+    out.write_all(b",")?;
+    let mentions_sql = "SELECT note_id, user_id, created_at FROM task_mentions ORDER BY note_id, user_id";
+    write_table::<MentionRow>(&mut out, conn, "task_mentions", mentions_sql).await?;
+    out.write_all(b",")?;
+    let sent_sql = "SELECT channel, kind, subject, sent_at FROM notifications_sent ORDER BY channel, kind, subject";
+    write_table::<SentNotificationRow>(&mut out, conn, "notifications_sent", sent_sql).await?;
+    out.write_all(b",")?;
+    let external_ids_sql = "SELECT task_id, source, external_id, synced_at FROM external_ids ORDER BY task_id, source";
+    write_table::<ExternalIdRow>(&mut out, conn, "external_ids", external_ids_sql).await?;
+    out.write_all(b",")?;
+    let caldav_resources_sql = "SELECT href, uid, task_id, etag, synced_at FROM caldav_resources ORDER BY href";
+    write_table::<CaldavResourceRow>(&mut out, conn, "caldav_resources", caldav_resources_sql).await?;
+    out.write_all(b",")?;
+    let caldav_collections_sql = "SELECT url, ctag FROM caldav_collections ORDER BY url";
+    write_table::<CaldavCollectionRow>(&mut out, conn, "caldav_collections", caldav_collections_sql).await?;
+    out.write_all(b",")?;
+    let task_shares_sql = "SELECT task_id, user_id, can_write, created_at FROM task_shares ORDER BY task_id, user_id";
+    write_table::<TaskShareRow>(&mut out, conn, "task_shares", task_shares_sql).await?;
+    out.write_all(b",")?;
+    let project_shares_sql = "SELECT owner_id, project_id, user_id, can_write, created_at FROM project_shares \
+                              ORDER BY owner_id, project_id, user_id";
+    write_table::<ProjectShareRow>(&mut out, conn, "project_shares", project_shares_sql).await?;
+    out.write_all(b",")?;
+    let time_entries_sql = "SELECT id, task_id, user_id, started_at, ended_at FROM time_entries ORDER BY id";
+    write_table::<TimeEntryRow>(&mut out, conn, "time_entries", time_entries_sql).await?;
+    out.write_all(b",")?;
+    let estimates_sql = "SELECT task_id, minutes FROM task_estimates ORDER BY task_id";
+    write_table::<EstimateRow>(&mut out, conn, "task_estimates", estimates_sql).await?;
+    out.write_all(b",")?;
+    let recurrences_sql = "SELECT task_id, every, created_at FROM task_recurrences ORDER BY task_id";
+    write_table::<RecurrenceRow>(&mut out, conn, "task_recurrences", recurrences_sql).await?;
+    out.write_all(b",")?;
+    let occurrences_sql =
+        "SELECT recurring_id, due_at, task_id, created_at FROM task_occurrences ORDER BY recurring_id, due_at";
+    write_table::<OccurrenceRow>(&mut out, conn, "task_occurrences", occurrences_sql).await?;
+    out.write_all(b",")?;
+    let escalations_sql = "SELECT task_id, due_at, escalated_at, days_overdue, old_priority, new_priority, channel, \
+                           notified_at FROM task_escalations ORDER BY task_id, due_at";
+    write_table::<EscalationRow>(&mut out, conn, "task_escalations", escalations_sql).await?;
+    out.write_all(b",")?;
+    let activity_sql = "SELECT id, task_id, description, action, actor, user_id, workspace_id, occurred_at \
+                        FROM task_activity ORDER BY id";
+    write_table::<ActivityRow>(&mut out, conn, "task_activity", activity_sql).await?;
+    out.write_all(b",")?;
+    let sms_alerts_sql = "SELECT task_id, created_at FROM task_sms_alerts ORDER BY task_id";
+    write_table::<SmsAlertRow>(&mut out, conn, "task_sms_alerts", sms_alerts_sql).await?;
+
+    writeln!(out, "}}}}")?;
+    Ok(tasks)
+}
+
+// Encrypted backups start with MAGIC, then the salt of the key derivation and the nonce,
+// followed by the backup encrypted with ChaCha20-Poly1305. The key is derived from the
+// passphrase with Argon2id, so the passphrase alone is enough to restore.
+const MAGIC: &[u8] = b"TASKENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+pub fn is_encrypted(data: &[u8]) -> bool {
+    data.starts_with(MAGIC)
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| TaskError::Config(format!("Could not derive the backup key: {}", e)))?;
+    Ok(ChaCha20Poly1305::new(&key.into()))
+}
+
+pub fn encrypt(passphrase: &str, plain: &[u8]) -> Result<Vec<u8>> {
+    let salt: [u8; SALT_LEN] = rand_bytes();
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher(passphrase, &salt)?
+        .encrypt(&nonce, plain)
+        .map_err(|_| TaskError::Config("Could not encrypt the backup.".to_string()))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+pub fn decrypt(passphrase: &str, data: &[u8]) -> Result<Vec<u8>> {
+    let header = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if !is_encrypted(data) || data.len() < header {
+        return Err(TaskError::InvalidInput("Not an encrypted backup.".to_string()));
+    }
+    let salt = &data[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = &data[MAGIC.len() + SALT_LEN..header];
+    cipher(passphrase, salt)?
+        .decrypt(nonce.into(), &data[header..])
+        // The authentication tag doesn't tell a wrong passphrase from a damaged file
+        .map_err(|_| TaskError::InvalidInput("Could not decrypt the backup: wrong passphrase, or the file is damaged.".to_string()))
+}
+
+fn rand_bytes<const N: usize>() -> [u8; N] {
+    use chacha20poly1305::aead::rand_core::RngCore;
+    let mut bytes = [0u8; N];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+// Streams one table into the document as `"name":[row,...]` and returns the number of rows
+async fn write_table<T>(out: &mut impl Write, conn: &mut MySqlConnection, name: &str, sql: &'static str) -> Result<u64>
+where
+    T: for<'r> FromRow<'r, MySqlRow> + Serialize + Send + Unpin,
+{
+    write!(out, "{}:[", json(&name)?)?;
+
+    let mut rows = sqlx::query_as::<_, T>(sql).fetch(&mut *conn);
+    let mut count: u64 = 0;
+    while let Some(row) = rows.try_next().await? {
+        if count > 0 {
+            out.write_all(b",")?;
+        }
+        out.write_all(b"\n")?;
+        serde_json::to_writer(&mut *out, &row).map_err(io::Error::from)?;
+        count += 1;
+    }
+    write!(out, "\n]")?;
+
+    Ok(count)
+}
+
+fn json<T: serde::Serialize>(value: &T) -> io::Result<String> {
+    serde_json::to_string(value).map_err(io::Error::from)
+}
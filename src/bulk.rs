@@ -0,0 +1,154 @@
+// Bulk changes of hundreds of tasks, split into chunks that run side by side, each in its own
+// transaction on its own pool connection. A chunk that fails is rolled back on its own and
+// reported, and the others go ahead, so unlike `complete_many` and friends a bulk change as a
+// whole is not all or nothing.
+
+use std::future::Future;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+
+// Configured in the `[bulk]` section of the config file; every field is optional
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BulkPolicy {
+    // Chunks running at once; never more than the pool has connections
+    pub concurrency: usize,
+    // Tasks per chunk
+    pub chunk_size: usize,
+}
+
+impl Default for BulkPolicy {
+    fn default() -> Self {
+        BulkPolicy { concurrency: 4, chunk_size: 100 }
+    }
+}
+
+// A chunk that was rolled back: which of the items it had, and why
+#[derive(Debug)]
+pub struct ChunkFailure {
+    pub items: Range<usize>,
+    pub error: sqlx::Error,
+}
+
+#[derive(Debug)]
+pub struct BulkReport<T> {
+    // What the chunks that went through returned, in the order of the items
+    pub done: Vec<T>,
+    // In the order of the items
+    pub failures: Vec<ChunkFailure>,
+    pub chunks: usize,
+    // From the first chunk starting to the last one finishing
+    pub elapsed: Duration,
+    // The chunks' own durations added up; more than `elapsed` by as much as running them side
+    // by side saved
+    pub busy: Duration,
+}
+
+impl<T> BulkReport<T> {
+    // E.g. "in 1.20 s (4 chunks, 3.85 s of work)"
+    pub fn timing(&self) -> String {
+        format!(
+            "in {:.2} s ({} chunk{}, {:.2} s of work)",
+            self.elapsed.as_secs_f64(),
+            self.chunks,
+            if self.chunks == 1 { "" } else { "s" },
+            self.busy.as_secs_f64()
+        )
+    }
+}
+
+// Runs `op` on `chunk_size` items at a time, up to `concurrency` chunks at once
+pub async fn in_chunks<'a, I, T, F, Fut>(items: &'a [I], chunk_size: usize, concurrency: usize, op: F) -> BulkReport<T>
+where
+    F: Fn(&'a [I]) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, sqlx::Error>>,
+{
+    let started = Instant::now();
+    let size = chunk_size.max(1);
+    // Made up front rather than in a `map` on the stream, whose closure the server's handlers
+    // can't prove to be general enough over the chunks' lifetimes
+    let chunks: Vec<_> = items
+        .chunks(size)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let op = &op;
+            async move {
+                let chunk_started = Instant::now();
+                let result = op(chunk).await;
+                (i * size, chunk.len(), chunk_started.elapsed(), result)
+            }
+        })
+        .collect();
+    let count = chunks.len();
+
+    let mut results: Vec<_> = stream::iter(chunks).buffer_unordered(concurrency.max(1)).collect().await;
+    results.sort_by_key(|(start, ..)| *start);
+
+    let mut report = BulkReport {
+        done: Vec::new(),
+        failures: Vec::new(),
+        chunks: count,
+        elapsed: Duration::ZERO,
+        busy: Duration::ZERO,
+    };
+    for (start, len, took, result) in results {
+        report.busy += took;
+        match result {
+            Ok(done) => report.done.extend(done),
+            Err(error) => report.failures.push(ChunkFailure { items: start..start + len, error }),
+        }
+    }
+    report.elapsed = started.elapsed();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn chunks_run_side_by_side_up_to_the_limit() {
+        let ids: Vec<i32> = (1..=10).collect();
+        let (running, most) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let report = in_chunks(&ids, 3, 2, |chunk| {
+            let chunk = chunk.to_vec();
+            let (running, most) = (&running, &most);
+            async move {
+                most.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                // The first chunk finishes last, so the report has to put it back in front
+                let delay = if chunk[0] == 1 { 30 } else { 5 };
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(chunk)
+            }
+        })
+        .await;
+
+        assert_eq!(report.done, ids);
+        assert_eq!(report.chunks, 4);
+        assert!(report.failures.is_empty());
+        assert_eq!(most.load(Ordering::SeqCst), 2);
+        assert!(report.busy >= report.elapsed);
+    }
+
+    #[tokio::test]
+    async fn a_failing_chunk_is_reported_and_the_others_go_ahead() {
+        let ids: Vec<i32> = (1..=5).collect();
+        let report = in_chunks(&ids, 2, 4, |chunk| {
+            let result = if chunk.contains(&3) { Err(sqlx::Error::PoolTimedOut) } else { Ok(chunk.to_vec()) };
+            async move { result }
+        })
+        .await;
+
+        assert_eq!(report.done, [1, 2, 5]);
+        assert_eq!(report.failures.len(), 1);
+        assert_eq!(report.failures[0].items, 2..4);
+        assert!(matches!(report.failures[0].error, sqlx::Error::PoolTimedOut));
+        assert!(report.timing().contains("3 chunks"), "{}", report.timing());
+    }
+}
@@ -0,0 +1,242 @@
+// The most recent results of `list_page` and `filter_page`, so clients that ask for the same
+// list over and over, like API pollers and the bot, don't cost a query each time. Every change
+// made through the repository empties it. Changes made by other processes can't be seen, so an
+// entry is also only used until it is [cache] ttl_secs old. Clones share the entries and the
+// counters, like `Metrics`.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::repository::{Access, ListCursor, Page, TaskFilter};
+
+// Configured in the `[cache]` section of the config file; every field is optional
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CachePolicy {
+    // How long a result may be used for; 0 turns the cache off
+    pub ttl_secs: u64,
+    // Results kept; the least recently used goes first
+    pub entries: usize,
+}
+
+impl Default for CachePolicy {
+    fn default() -> Self {
+        CachePolicy { ttl_secs: 5, entries: 64 }
+    }
+}
+
+// Which list a result is of: who asked, which filter (None for the plain list) and which page
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListKey {
+    access: Access,
+    filter: Option<TaskFilter>,
+    after: Option<ListCursor>,
+    limit: u32,
+}
+
+impl ListKey {
+    pub fn new(access: Access, filter: Option<&TaskFilter>, after: Option<ListCursor>, limit: u32) -> Self {
+        ListKey { access, filter: filter.cloned(), after, limit }
+    }
+}
+
+// A lookup that found nothing. Handing it back to `put` stores the result only if nothing
+// changed since, so a query that raced a change doesn't put the old state back.
+#[derive(Debug)]
+pub struct Miss {
+    generation: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ListCache {
+    ttl: Duration,
+    capacity: usize,
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    // Most recently used last
+    entries: VecDeque<Entry>,
+    // Counts changes, so a result fetched before one can be told apart
+    generation: u64,
+    hits: u64,
+    misses: u64,
+    invalidations: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    key: ListKey,
+    page: Page<ListCursor>,
+    stored: Instant,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CacheStats {
+    pub enabled: bool,
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub invalidations: u64,
+}
+
+impl Default for ListCache {
+    fn default() -> Self {
+        ListCache::new(CachePolicy::default())
+    }
+}
+
+impl ListCache {
+    pub fn new(policy: CachePolicy) -> Self {
+        ListCache {
+            ttl: Duration::from_secs(policy.ttl_secs),
+            capacity: policy.entries,
+            inner: Arc::default(),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        !self.ttl.is_zero() && self.capacity > 0
+    }
+
+    pub fn get(&self, key: &ListKey) -> Result<Page<ListCursor>, Miss> {
+        self.get_at(key, Instant::now())
+    }
+
+    fn get_at(&self, key: &ListKey, now: Instant) -> Result<Page<ListCursor>, Miss> {
+        let mut inner = self.lock();
+        let miss = Miss { generation: inner.generation };
+        if !self.enabled() {
+            return Err(miss);
+        }
+        let ttl = self.ttl;
+        inner.entries.retain(|entry| now.saturating_duration_since(entry.stored) < ttl);
+        let Some(position) = inner.entries.iter().position(|entry| entry.key == *key) else {
+            inner.misses += 1;
+            return Err(miss);
+        };
+        inner.hits += 1;
+        let entry = inner.entries.remove(position).expect("position is in range");
+        let page = entry.page.clone();
+        inner.entries.push_back(entry);
+        Ok(page)
+    }
+
+    pub fn put(&self, miss: Miss, key: ListKey, page: &Page<ListCursor>) {
+        self.put_at(miss, key, page, Instant::now());
+    }
+
+    fn put_at(&self, miss: Miss, key: ListKey, page: &Page<ListCursor>, now: Instant) {
+        let mut inner = self.lock();
+        if !self.enabled() || miss.generation != inner.generation {
+            return;
+        }
+        inner.entries.retain(|entry| entry.key != key);
+        if inner.entries.len() >= self.capacity {
+            inner.entries.pop_front();
+        }
+        inner.entries.push_back(Entry { key, page: page.clone(), stored: now });
+    }
+
+    // Forgets every result, after something changed
+    pub fn invalidate(&self) {
+        let mut inner = self.lock();
+        inner.generation += 1;
+        if !inner.entries.is_empty() {
+            inner.entries.clear();
+            inner.invalidations += 1;
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let inner = self.lock();
+        CacheStats {
+            enabled: self.enabled(),
+            entries: inner.entries.len(),
+            hits: inner.hits,
+            misses: inner.misses,
+            invalidations: inner.invalidations,
+        }
+    }
+
+    // Stays usable even if a thread panicked while holding the lock
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn key(limit: u32) -> ListKey {
+        ListKey::new(Access::default(), None, None, limit)
+    }
+
+    fn page(description: &str) -> Page<ListCursor> {
+        Page { tasks: vec![fixtures::task(1, description, 1)], next: None }
+    }
+
+    fn cache(ttl_secs: u64, entries: usize) -> ListCache {
+        ListCache::new(CachePolicy { ttl_secs, entries })
+    }
+
+    #[test]
+    fn results_are_reused_until_something_changes() {
+        let cache = cache(5, 8);
+        let miss = cache.get(&key(10)).unwrap_err();
+        cache.put(miss, key(10), &page("Buy milk"));
+        assert_eq!(cache.get(&key(10)).unwrap().tasks[0].description, "Buy milk");
+        assert!(cache.get(&key(20)).is_err(), "another page size is another list");
+
+        let filtered = ListKey::new(Access::default(), Some(&TaskFilter::default()), None, 10);
+        assert!(cache.get(&filtered).is_err(), "a filter is another list");
+
+        cache.invalidate();
+        assert!(cache.get(&key(10)).is_err());
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.invalidations), (1, 4, 1));
+    }
+
+    #[test]
+    fn a_result_fetched_before_a_change_is_not_kept() {
+        let cache = cache(5, 8);
+        let miss = cache.get(&key(10)).unwrap_err();
+        cache.invalidate();
+        cache.put(miss, key(10), &page("Buy milk"));
+        assert!(cache.get(&key(10)).is_err());
+    }
+
+    #[test]
+    fn results_expire_and_the_least_recently_used_goes_first() {
+        let cache = cache(5, 2);
+        let now = Instant::now();
+        for limit in [1, 2] {
+            let miss = cache.get_at(&key(limit), now).unwrap_err();
+            cache.put_at(miss, key(limit), &page("Buy milk"), now);
+        }
+        assert!(cache.get_at(&key(1), now).is_ok());
+        let miss = cache.get_at(&key(3), now).unwrap_err();
+        cache.put_at(miss, key(3), &page("Buy milk"), now);
+        assert!(cache.get_at(&key(2), now).is_err(), "the least recently used was dropped");
+        assert!(cache.get_at(&key(1), now).is_ok());
+
+        assert!(cache.get_at(&key(1), now + Duration::from_secs(5)).is_err());
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn a_zero_ttl_turns_the_cache_off() {
+        let cache = cache(0, 8);
+        let miss = cache.get(&key(10)).unwrap_err();
+        cache.put(miss, key(10), &page("Buy milk"));
+        assert!(cache.get(&key(10)).is_err());
+        assert!(!cache.stats().enabled);
+        assert_eq!(cache.stats().misses, 0);
+    }
+}
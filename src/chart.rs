@@ -0,0 +1,186 @@
+// Charts for the terminal. Line charts are drawn with braille characters, two dots across and
+// four down in each, so a trend shows at four times the resolution of plain characters;
+// heatmaps shade a character per day, and bars are blocks up to a mark.
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, Days, NaiveDate};
+
+// The braille character without any dots; the dots are bits on top of it
+const BLANK: u32 = 0x2800;
+// The bit of each dot in a character, by row from the top and then column
+const DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+// Shades of a heatmap day, from none to as many as the busiest day
+pub const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+const WEEKDAYS: [&str; 7] = ["Mon", "", "Wed", "", "Fri", "", ""];
+
+// `values`, evenly spread over `width` characters and `height` lines, as lines to print. The y
+// axis goes from 0, or the lowest value if that is below, to the highest, which are written on
+// the left of the first and last line.
+pub fn line(values: &[i64], width: usize, height: usize) -> Vec<String> {
+    let (width, height) = (width.max(1), height.max(1));
+    let (columns, rows) = (width * 2, height * 4);
+    let low = values.iter().copied().min().unwrap_or(0).min(0);
+    let high = values.iter().copied().max().unwrap_or(0).max(low + 1);
+
+    let mut cells = vec![vec![0u8; width]; height];
+    let mut previous: Option<usize> = None;
+    for x in 0..columns {
+        let Some(value) = sample(values, x, columns) else {
+            break;
+        };
+        // Dots up from the bottom
+        let y = ((value - low as f64) / (high - low) as f64 * (rows - 1) as f64).round() as usize;
+        // A steep step fills the dots in between, so the line stays joined up
+        let (from, to) = previous.map_or((y, y), |before| (before.min(y), before.max(y)));
+        for dot in from..=to {
+            let row = rows - 1 - dot;
+            cells[row / 4][x / 2] |= DOTS[row % 4][x % 2];
+        }
+        previous = Some(y);
+    }
+
+    let (top, bottom) = (high.to_string(), low.to_string());
+    let pad = top.len().max(bottom.len());
+    cells
+        .iter()
+        .enumerate()
+        .map(|(i, cells)| {
+            let (label, axis) = match i {
+                0 => (top.as_str(), '┤'),
+                _ if i == height - 1 => (bottom.as_str(), '┤'),
+                _ => ("", '│'),
+            };
+            let dots: String =
+                cells.iter().map(|&bits| char::from_u32(BLANK + u32::from(bits)).unwrap_or(' ')).collect();
+            format!("{:>pad$} {}{}", label, axis, dots)
+        })
+        .collect()
+}
+
+// `counts` per day as a calendar like GitHub's: a column for each of the `weeks` weeks up to
+// and including the one of `last`, a line per weekday and the months on top
+pub fn heatmap(counts: &HashMap<NaiveDate, i64>, last: NaiveDate, weeks: u32) -> Vec<String> {
+    let weeks = weeks.max(1);
+    let monday = last - Days::new(u64::from(last.weekday().num_days_from_monday()));
+    let first = monday - Days::new(u64::from(weeks - 1) * 7);
+    let mondays: Vec<NaiveDate> = (0..weeks).map(|week| first + Days::new(u64::from(week) * 7)).collect();
+    let busiest = counts.values().copied().max().unwrap_or(0);
+
+    // A month's name goes over the first week starting in it, or the week after if the name
+    // before is in the way
+    let mut months = " ".repeat(4);
+    let mut named = None;
+    for (i, monday) in mondays.iter().enumerate() {
+        let column = 4 + i * 2;
+        if named != Some(monday.month()) && months.chars().count() <= column {
+            months.push_str(&" ".repeat(column - months.chars().count()));
+            months.push_str(&monday.format("%b").to_string());
+            named = Some(monday.month());
+        }
+    }
+
+    let mut lines = vec![months];
+    for (weekday, label) in WEEKDAYS.iter().enumerate() {
+        let mut line = format!("{:<4}", label);
+        for monday in &mondays {
+            let day = *monday + Days::new(weekday as u64);
+            if day > last {
+                break;
+            }
+            line.push(SHADES[shade(counts.get(&day).copied().unwrap_or(0), busiest)]);
+            line.push(' ');
+        }
+        lines.push(line.trim_end().to_string());
+    }
+    lines
+}
+
+// A bar of `value` out of `scale` over `width` characters, with a mark at `limit` and what goes
+// past it shaded lighter. Always `width` + 1 characters, so whatever follows bars lines up.
+pub fn bar(value: i64, limit: i64, scale: i64, width: usize) -> String {
+    let cells = |value: i64| ((value.max(0) as f64 * width as f64 / scale.max(1) as f64).round() as usize).min(width);
+    let (filled, mark) = (cells(value), cells(limit));
+    (0..=width)
+        .map(|i| match i {
+            _ if i < filled && i < mark => '█',
+            _ if i < filled => '▒',
+            _ if i == mark => '│',
+            _ => ' ',
+        })
+        .collect()
+}
+
+// Which of the shades `count` gets: none for none, else by its share of `busiest`
+fn shade(count: i64, busiest: i64) -> usize {
+    if count <= 0 || busiest <= 0 {
+        return 0;
+    }
+    let steps = (SHADES.len() - 1) as i64;
+    ((count * steps + busiest - 1) / busiest).clamp(1, steps) as usize
+}
+
+// The value at dot column `x` of `columns`, in between the two values either side of it
+fn sample(values: &[i64], x: usize, columns: usize) -> Option<f64> {
+    match values {
+        [] => None,
+        [only] => Some(*only as f64),
+        _ => {
+            let at = x as f64 * (values.len() - 1) as f64 / (columns - 1) as f64;
+            let i = at.floor() as usize;
+            let next = values[(i + 1).min(values.len() - 1)];
+            Some(values[i] as f64 + (next - values[i]) as f64 * at.fract())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_rise_is_joined_up() {
+        // Bottom left dot, then the whole right column
+        assert_eq!(line(&[0, 4], 1, 1), ["4 ┤⣸"]);
+    }
+
+    #[test]
+    fn a_flat_line_runs_along_the_top_with_the_axis_from_zero() {
+        assert_eq!(line(&[2, 2, 2], 3, 3), ["2 ┤⠉⠉⠉", "  │⠀⠀⠀", "0 ┤⠀⠀⠀"]);
+    }
+
+    #[test]
+    fn a_heatmap_has_a_column_per_week_and_stops_at_the_last_day() {
+        let day = |day| NaiveDate::from_ymd_opt(2026, 3, day).unwrap();
+        let counts = HashMap::from([(day(2), 1), (day(3), 4), (day(10), 2)]);
+        // The 11th is a Wednesday
+        assert_eq!(
+            heatmap(&counts, day(11), 2),
+            ["    Mar", "Mon ░ ·", "    █ ▒", "Wed · ·", "    ·", "Fri ·", "    ·", "    ·"]
+        );
+    }
+
+    #[test]
+    fn months_are_named_where_they_start() {
+        let last = NaiveDate::from_ymd_opt(2026, 4, 15).unwrap();
+        let lines = heatmap(&HashMap::new(), last, 8);
+        assert_eq!(lines[0], "    Feb Mar     Apr");
+        assert!(lines[1].starts_with("Mon · ·"), "{:?}", lines);
+    }
+
+    #[test]
+    fn bars_shade_what_goes_past_the_mark() {
+        assert_eq!(bar(3, 4, 8, 8), "███ │    ");
+        assert_eq!(bar(6, 4, 8, 8), "████▒▒   ");
+        assert_eq!(bar(0, 4, 8, 8), "    │    ");
+        assert_eq!(bar(8, 8, 8, 4), "████│");
+    }
+
+    #[test]
+    fn negative_values_move_the_bottom_of_the_axis() {
+        let lines = line(&[-10, 5], 4, 2);
+        assert!(lines[0].starts_with("  5 ┤"), "{:?}", lines);
+        assert!(lines[1].starts_with("-10 ┤"), "{:?}", lines);
+        assert_eq!(line(&[], 2, 1), ["1 ┤⠀⠀"]);
+    }
+}
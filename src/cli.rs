@@ -0,0 +1,1054 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+
+use crate::roles::Role;
+use crate::secrets::Service;
+use crate::settings::Setting;
+
+// Command-line interface. Running without a subcommand starts the interactive menu, or shows
+// what `default_view` asks for.
+#[derive(Debug, Parser)]
+#[command(name = "task", about = "Task management CLI backed by MySQL")]
+pub struct Cli {
+    /// Connection profile from the config file to use
+    #[arg(long, global = true, env = "TASK_PROFILE")]
+    pub profile: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Apply pending database migrations
+    Migrate,
+
+    /// Check the database setup for problems such as charsets that mangle text
+    Doctor,
+
+    /// List or test the configured database profiles
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+
+    /// Populate or clear the demo data set
+    Seed(SeedArgs),
+
+    /// Export every table to a portable JSON backup file, or upload an encrypted one to S3
+    Backup(BackupArgs),
+
+    /// Load data from a backup file created by `task backup`
+    Restore(RestoreArgs),
+
+    /// Print every task, newest first
+    List,
+
+    /// Show all details of a single task
+    Show {
+        /// ID of the task
+        id: i32,
+    },
+
+    /// Search task descriptions, best matches first
+    Search {
+        /// Words to search for
+        #[arg(required = true)]
+        terms: Vec<String>,
+    },
+
+    /// Export tasks in a format other tools (or another machine) can read
+    Export {
+        #[command(subcommand)]
+        command: ExportCommand,
+    },
+
+    /// Import tasks from another format
+    Import {
+        #[command(subcommand)]
+        command: ImportCommand,
+    },
+
+    /// Set how long a task is expected to take
+    Estimate {
+        /// ID of the task
+        id: i32,
+        /// E.g. 45m, 2h or 1h30m; leave out to remove the estimate
+        duration: Option<String>,
+    },
+
+    /// Make a task come back, from its due date on; `task daemon` creates the occurrences
+    Recur {
+        /// ID of the task
+        id: i32,
+        /// E.g. daily, weekly, 2 weeks or 3 months; leave out to stop it recurring
+        every: Option<String>,
+    },
+
+    /// Track the time spent on tasks and report it
+    Time {
+        #[command(subcommand)]
+        command: TimeCommand,
+    },
+
+    /// What got done this week, what slipped, what is due next week and what has gone stale
+    Review(ReviewArgs),
+
+    /// Run a report defined as [reports.<name>] in the config file, or list the reports
+    Report(CustomReportArgs),
+
+    /// Show how many tasks are pending, overdue and getting done, or runtime statistics
+    Stats {
+        #[command(subcommand)]
+        command: Option<StatsCommand>,
+    },
+
+    /// Two-way sync with another task service
+    Sync {
+        #[command(subcommand)]
+        command: SyncCommand,
+    },
+
+    /// Link tasks to Jira issues
+    Jira {
+        #[command(subcommand)]
+        command: JiraCommand,
+    },
+
+    /// Send reminders and digests for tasks that are due
+    Notify {
+        #[command(subcommand)]
+        command: NotifyCommand,
+    },
+
+    /// Keep running and show desktop notifications for tasks that are due soon or overdue
+    Watch(WatchArgs),
+
+    /// Run scripts that read and change tasks
+    Script {
+        #[command(subcommand)]
+        command: ScriptCommand,
+    },
+
+    /// Keep running and send the reminders and do the syncs listed under [daemon]
+    Daemon {
+        #[command(subcommand)]
+        command: Option<DaemonCommand>,
+    },
+
+    /// Commit the current tasks to the git mirror of [mirror], e.g. after an import or sync
+    Mirror,
+
+    /// Run a Telegram bot that adds, lists and completes tasks
+    Telegram,
+
+    /// Choose which tasks send SMS reminders
+    Sms {
+        #[command(subcommand)]
+        command: SmsCommand,
+    },
+
+    /// Raise the priority of long overdue tasks and tell someone, as [escalation] says
+    Escalate {
+        #[command(subcommand)]
+        command: EscalateCommand,
+    },
+
+    /// Inspect and resend outgoing webhook requests
+    Webhook {
+        #[command(subcommand)]
+        command: WebhookCommand,
+    },
+
+    /// Serve a JSON API for tasks over HTTP
+    Serve(ServeArgs),
+
+    /// Store or remove API tokens in the system keyring
+    Token {
+        #[command(subcommand)]
+        command: TokenCommand,
+    },
+
+    /// Create and revoke personal access tokens for the API of `task serve`
+    ApiToken {
+        #[command(subcommand)]
+        command: ApiTokenCommand,
+    },
+
+    /// Log in, so that commands work on your own tasks
+    Login {
+        /// User to log in as; asked for if omitted
+        username: Option<String>,
+    },
+
+    /// End the login on this machine
+    Logout,
+
+    /// Create and list user accounts
+    User {
+        #[command(subcommand)]
+        command: UserCommand,
+    },
+
+    /// Assign a task to a user, or unassign it if no user is given
+    Assign {
+        /// ID of the task
+        id: i32,
+        /// User who should do it
+        user: Option<String>,
+    },
+
+    /// Show the pending tasks assigned to you
+    Assigned,
+
+    /// Add a note to a task; @username mentions notify that user
+    Note {
+        /// ID of the task
+        id: i32,
+        /// Text of the note
+        #[arg(required = true)]
+        text: Vec<String>,
+    },
+
+    /// Share tasks and projects with other users
+    Share {
+        #[command(subcommand)]
+        command: ShareCommand,
+    },
+
+    /// Create, join and switch between workspaces
+    Workspace {
+        #[command(subcommand)]
+        command: WorkspaceCommand,
+    },
+
+    /// What was done to tasks and by whom, newest first
+    Activity {
+        /// Only what this user did
+        #[arg(long)]
+        user: Option<String>,
+        /// Entries per page (default 20)
+        #[arg(long)]
+        limit: Option<u32>,
+        /// Continue with the entries older than this one, as the previous page suggests
+        #[arg(long)]
+        before: Option<i64>,
+    },
+
+    /// Manage the accounts of a shared database (instance admins only)
+    Admin {
+        #[command(subcommand)]
+        command: AdminCommand,
+    },
+
+    /// Preferences stored with your account, used on every machine you log in from
+    Settings {
+        #[command(subcommand)]
+        command: SettingsCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ExportCommand {
+    /// Every task with its project, tags and checklist, as JSON
+    Json {
+        /// File to write; prints to stdout if omitted
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Tasks with a due date as an iCalendar (.ics) file for calendar apps
+    Ics {
+        /// File to write; prints to stdout if omitted
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+
+        /// Export calendar events instead of to-dos, for apps that don't show to-dos
+        #[arg(long)]
+        events: bool,
+    },
+
+    /// Every task as a line of a todo.txt file
+    Todotxt {
+        /// File to write; prints to stdout if omitted
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Every task as an Org file with TODO/DONE headings per project, for the Emacs agenda
+    Org {
+        /// File to write; prints to stdout if omitted
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// An Excel workbook with one sheet per project and overdue tasks highlighted
+    Xlsx {
+        /// File to write; writes to stdout if omitted
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// A styled HTML (or PDF) report of what was done in a time range, e.g. for a weekly status
+    Report(ReportArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ReportArgs {
+    /// Start of the range, YYYY-MM-DD [HH:MM]; defaults to a week before its end
+    #[arg(long)]
+    pub from: Option<String>,
+
+    /// End of the range; a date alone includes that whole day. Defaults to now.
+    #[arg(long)]
+    pub to: Option<String>,
+
+    /// File to write, as PDF if it ends in .pdf (needs wkhtmltopdf or Chromium); prints HTML to stdout if omitted
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct CustomReportArgs {
+    /// Name of the report; lists them if omitted
+    pub name: Option<String>,
+
+    /// Show it this way instead of the report's own format
+    #[arg(long, value_enum)]
+    pub format: Option<ReportFormat>,
+
+    /// File to write; prints to stdout if omitted
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TimeCommand {
+    /// Start a timer on a task, stopping the one running
+    Start {
+        /// ID of the task
+        id: i32,
+    },
+
+    /// Stop the running timer
+    Stop,
+
+    /// Show the running timer
+    Status,
+
+    /// Time tracked in a range per task, project, day or week, or every entry as CSV
+    Report(TimeReportArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct TimeReportArgs {
+    /// Start of the range, YYYY-MM-DD [HH:MM]; defaults to a week before its end
+    #[arg(long)]
+    pub from: Option<String>,
+
+    /// End of the range; a date alone includes that whole day. Defaults to now.
+    #[arg(long)]
+    pub to: Option<String>,
+
+    /// What to add the time up by
+    #[arg(long, value_enum, default_value_t = TimeGroup::Project)]
+    pub by: TimeGroup,
+
+    /// Write every entry, with its hours, to this CSV file instead, e.g. for an invoice
+    #[arg(long)]
+    pub csv: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TimeGroup {
+    Task,
+    Project,
+    Day,
+    Week,
+}
+
+#[derive(Debug, Args)]
+pub struct ReviewArgs {
+    /// Open tasks unchanged for this many days count as stale (default 30)
+    #[arg(long)]
+    pub stale_days: Option<u32>,
+
+    /// Print markdown instead of text
+    #[arg(long)]
+    pub markdown: bool,
+
+    /// Markdown file to write; prints to stdout if omitted
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCommand {
+    /// Import tasks from a CSV file with a header row
+    Csv(CsvImportArgs),
+
+    /// Import tasks from a file written by `task export json`
+    Json(FileImportArgs),
+
+    /// Import tasks from a todo.txt file
+    Todotxt(FileImportArgs),
+
+    /// Import tasks from the JSON written by Taskwarrior's `task export`
+    Taskwarrior(FileImportArgs),
+
+    /// Import the cards of a Trello board from its JSON export
+    Trello(FileImportArgs),
+
+    /// Import the to-dos of an .ics file, e.g. a list exported from Apple Reminders
+    Ics(FileImportArgs),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum StatsCommand {
+    /// Connection pool state, query counts and the slowest recent queries
+    Db,
+
+    /// Chart of how many tasks were open each day, from the activity log
+    Burndown {
+        /// Weeks back to go (default 4)
+        #[arg(long)]
+        weeks: Option<u32>,
+    },
+
+    /// Open and completed tasks with their estimates, by project and by tag
+    Breakdown,
+
+    /// Tasks completed per week, the average lately and how long the pending ones would take
+    Velocity {
+        /// Weeks to list (default 12)
+        #[arg(long)]
+        weeks: Option<u32>,
+    },
+
+    /// Calendar of the tasks completed each day over the last year
+    Heatmap {
+        /// Only the tasks of this project
+        #[arg(long)]
+        project: Option<String>,
+    },
+
+    /// How old the open tasks are, on average and by percentile, week by week
+    Age {
+        /// Weeks back to go (default 12)
+        #[arg(long)]
+        weeks: Option<u32>,
+    },
+
+    /// Estimated work due each day ahead, against the capacity of [forecast] in the config file
+    Forecast {
+        /// Days to look ahead, or weeks with --weekly (default 14 days or 8 weeks)
+        #[arg(long)]
+        ahead: Option<u32>,
+
+        /// Add up the work per week instead
+        #[arg(long)]
+        weekly: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SyncCommand {
+    /// Pull tasks from Todoist and push local changes back
+    Todoist(SyncArgs),
+
+    /// Create tasks for GitHub issues assigned to you and close issues whose task is done
+    Github(SyncArgs),
+
+    /// Create tasks for GitLab issues assigned to you, per mapped project, and close issues whose task is done
+    Gitlab(GitlabSyncArgs),
+
+    /// Two-way sync with a CalDAV task list (Nextcloud, Fastmail, ...)
+    Caldav(CaldavSyncArgs),
+
+    /// Put tasks with due dates on a Google Calendar and keep the events up to date
+    Google(SyncArgs),
+
+    /// Two-way sync with a Notion database: title, status, due date and tags
+    Notion(NotionSyncArgs),
+
+    /// Write each project's tasks to a markdown file in a notes vault (Obsidian) and read edits back
+    Vault(SyncArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct SyncArgs {
+    /// Show what would change on either side without changing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct GitlabSyncArgs {
+    /// Only sync this local project (as named in [gitlab.projects])
+    #[arg(long)]
+    pub project: Option<String>,
+
+    #[command(flatten)]
+    pub options: SyncArgs,
+}
+
+#[derive(Debug, Args)]
+pub struct NotionSyncArgs {
+    /// Read every page of the database, not only those edited since the last sync
+    #[arg(long)]
+    pub full: bool,
+
+    #[command(flatten)]
+    pub options: SyncArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum JiraCommand {
+    /// Link a task to a Jira issue
+    Link {
+        /// ID of the task
+        id: i32,
+        /// Issue key, e.g. PROJ-123
+        key: String,
+    },
+
+    /// Remove a task's link to a Jira issue (all its Jira links if no key is given)
+    Unlink {
+        /// ID of the task
+        id: i32,
+        /// Issue key, e.g. PROJ-123
+        key: Option<String>,
+    },
+
+    /// Fetch the summary and status of a task's Jira issues
+    Status {
+        /// ID of the task
+        id: i32,
+    },
+
+    /// Apply the configured transition to issues of tasks completed since the last run
+    Transition {
+        /// Show which issues would be transitioned without changing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NotifyCommand {
+    /// Email reminders for tasks due soon, and the daily digest once its time has come
+    Email(NotifyArgs),
+
+    /// Post tasks that became overdue to Slack
+    Slack(NotifyArgs),
+
+    /// Post tasks that became overdue to Discord
+    Discord(NotifyArgs),
+
+    /// Text about opted-in tasks that are due soon or overdue, within the [sms] daily limit
+    Sms(NotifyArgs),
+
+    /// Send a test notification to check the settings of a channel
+    Test {
+        /// Channel to test
+        channel: Channel,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Channel {
+    Email,
+    Slack,
+    Discord,
+    Desktop,
+    Sms,
+}
+
+#[derive(Debug, Args)]
+pub struct NotifyArgs {
+    /// Show what would be sent without sending anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SmsCommand {
+    /// Also send this task's reminders by SMS
+    Enable {
+        /// ID of the task
+        id: i32,
+    },
+
+    /// Stop sending this task's reminders by SMS
+    Disable {
+        /// ID of the task
+        id: i32,
+    },
+
+    /// Show the tasks that send SMS reminders
+    List,
+}
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    /// Seconds between checks
+    #[arg(long, default_value_t = 60)]
+    pub interval: u64,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ScriptCommand {
+    /// Run a Rhai script, e.g. a custom report or a bulk change
+    Run(ScriptArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct ScriptArgs {
+    /// The script to run
+    pub file: PathBuf,
+
+    /// Print the changes the script would make instead of making them
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DaemonCommand {
+    /// Show whether a daemon is running and how its jobs last went
+    Status,
+
+    /// Have the daemon run jobs now instead of at their time, or run them here if no daemon is running
+    Run {
+        /// Jobs to run, by name ("sync todoist") or by either word of it ("sync", "email"); all of them if left out
+        jobs: Vec<String>,
+    },
+
+    /// List the reminders the channels under [daemon] notify are yet to send
+    Reminders,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum EscalateCommand {
+    /// Escalate the tasks overdue for longer than after_days that weren't yet
+    Run {
+        /// Show what would be escalated without changing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Show the most recent escalations and what was done
+    Log {
+        /// Number of escalations to show
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WebhookCommand {
+    /// Show the most recent webhook requests and whether they were delivered (instance admins only)
+    Log {
+        /// Number of requests to show
+        #[arg(long, default_value_t = 20)]
+        limit: u32,
+    },
+
+    /// Send requests that failed after all retries again
+    Retry,
+}
+
+#[derive(Debug, Args)]
+pub struct CaldavSyncArgs {
+    /// Which version to keep when a task changed both here and on the server
+    #[arg(long, value_enum, default_value_t = Prefer::Local)]
+    pub prefer: Prefer,
+
+    #[command(flatten)]
+    pub options: SyncArgs,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Prefer {
+    Local,
+    Remote,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TokenCommand {
+    /// Save a token (read from stdin) in the system keyring
+    Set {
+        /// Service the token is for
+        service: Service,
+    },
+
+    /// Remove a saved token from the system keyring
+    Delete {
+        /// Service the token is for
+        service: Service,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ApiTokenCommand {
+    /// Create a token for the current workspace; it is shown only once
+    Create {
+        /// What the token is for, e.g. the script that uses it
+        name: String,
+        /// Let the token change tasks; without this it can only read them
+        #[arg(long)]
+        write: bool,
+        /// Days until the token expires; it doesn't if omitted
+        #[arg(long)]
+        days: Option<u32>,
+    },
+
+    /// Show your tokens
+    List,
+
+    /// Revoke a token, so it stops working at once
+    Revoke {
+        /// Id shown by `task api-token list`
+        id: i32,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum UserCommand {
+    /// Create an account; the password is asked for
+    Add {
+        /// Name to log in with
+        username: String,
+    },
+
+    /// Show every account
+    List,
+
+    /// Change the password of the logged-in user
+    Passwd,
+
+    /// Create an account with an invitation code from a workspace admin, and log in with it
+    Join {
+        /// The code from `task workspace invite`
+        code: String,
+        /// Name to log in with; asked for if omitted
+        username: Option<String>,
+    },
+
+    /// Write everything stored about the logged-in user to one JSON file
+    Export {
+        /// File to write; prints to stdout if omitted
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+
+    /// Delete the logged-in user's account and tasks, and remove their name from everything else
+    Erase {
+        /// Don't ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ShareCommand {
+    /// Share one of your tasks with a user (read-only unless --write)
+    Task {
+        /// ID of the task
+        id: i32,
+        /// User to share it with
+        user: String,
+        /// Let them edit, complete and delete it as well
+        #[arg(long)]
+        write: bool,
+    },
+
+    /// Share your tasks in a project, including ones added to it later
+    Project {
+        /// Name of the project
+        name: String,
+        /// User to share them with
+        user: String,
+        /// Let them edit, complete and delete the tasks as well
+        #[arg(long)]
+        write: bool,
+    },
+
+    /// Stop sharing a task or project with a user
+    Remove {
+        /// ID of the task
+        #[arg(long, conflicts_with = "project", required_unless_present = "project")]
+        task: Option<i32>,
+        /// Name of the project
+        #[arg(long)]
+        project: Option<String>,
+        /// User to stop sharing with
+        user: String,
+    },
+
+    /// Show what you shared and what others shared with you
+    List,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum WorkspaceCommand {
+    /// Create a workspace, with you as its first member
+    Create {
+        /// Name of the workspace
+        name: String,
+    },
+
+    /// Show the workspaces you are in; the current one is marked with '*'
+    List,
+
+    /// Work in a workspace from now on, or in your personal space if none is given
+    Switch {
+        /// Name of the workspace
+        name: Option<String>,
+    },
+
+    /// Show who is in a workspace and their roles
+    Members {
+        /// Name of the workspace
+        workspace: String,
+    },
+
+    /// Let a user into a workspace (admins only)
+    Add {
+        /// Name of the workspace
+        workspace: String,
+        /// User to add
+        user: String,
+        /// admin (everything), member (change tasks) or viewer (read only)
+        #[arg(long, default_value = "member")]
+        role: Role,
+    },
+
+    /// Change a member's role (admins only)
+    Role {
+        /// Name of the workspace
+        workspace: String,
+        /// The member
+        user: String,
+        /// admin (everything), member (change tasks) or viewer (read only)
+        role: Role,
+    },
+
+    /// Take a user out of a workspace (admins only), or leave it yourself
+    Remove {
+        /// Name of the workspace
+        workspace: String,
+        /// User to remove
+        user: String,
+    },
+
+    /// Make a code someone without an account can use once to join the workspace (admins only)
+    Invite {
+        /// Name of the workspace
+        workspace: String,
+        /// admin (everything), member (change tasks) or viewer (read only)
+        #[arg(long, default_value = "member")]
+        role: Role,
+        /// Days until the code stops working
+        #[arg(long, default_value_t = 7)]
+        days: u32,
+        /// Address `task serve` is reachable at, e.g. https://tasks.example.com, to print a link as well
+        #[arg(long)]
+        url: Option<String>,
+    },
+
+    /// Show the invitations of a workspace that are still open (admins only)
+    Invitations {
+        /// Name of the workspace
+        workspace: String,
+    },
+
+    /// Withdraw an invitation before it is used (admins only)
+    Uninvite {
+        /// Name of the workspace
+        workspace: String,
+        /// ID of the invitation, as shown by `task workspace invitations`
+        id: i32,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AdminCommand {
+    /// List, disable and enable accounts, reset passwords and choose admins
+    Users {
+        #[command(subcommand)]
+        command: AdminUserCommand,
+    },
+
+    /// Counts of accounts, workspaces and tasks across the whole database
+    Stats,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AdminUserCommand {
+    /// Show every account, with what it owns and whether it is disabled
+    List,
+
+    /// Keep a user from logging in, and stop their sessions and API tokens from working
+    Disable {
+        /// The user
+        user: String,
+    },
+
+    /// Let a disabled user log in again
+    Enable {
+        /// The user
+        user: String,
+    },
+
+    /// Give a user a new random password and end their sessions
+    ResetPassword {
+        /// The user
+        user: String,
+    },
+
+    /// Make a user an instance admin
+    Promote {
+        /// The user
+        user: String,
+    },
+
+    /// Take away a user's instance admin rights
+    Demote {
+        /// The user
+        user: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SettingsCommand {
+    /// Show each setting and where its value comes from
+    List,
+
+    /// Store a setting with your account
+    Set {
+        /// The setting
+        setting: Setting,
+        /// Its new value
+        value: String,
+    },
+
+    /// Remove a setting from your account
+    Unset {
+        /// The setting
+        setting: Setting,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProfileCommand {
+    /// Show configured profiles; the active one is marked with '*'
+    List,
+
+    /// Check that a profile can connect (all profiles if none is given or selected)
+    Test {
+        /// Profile to test
+        name: Option<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080", env = "TASK_LISTEN")]
+    pub listen: std::net::SocketAddr,
+
+    /// Also serve the gRPC TaskService on this address, e.g. 127.0.0.1:50051
+    #[arg(long, value_name = "ADDR", env = "TASK_GRPC_LISTEN")]
+    pub grpc: Option<std::net::SocketAddr>,
+}
+
+#[derive(Debug, Args)]
+pub struct SeedArgs {
+    /// Insert sample tasks, in sample projects and with tags, for trying out the tool
+    #[arg(long, conflicts_with = "wipe", required_unless_present = "wipe")]
+    pub demo: bool,
+
+    /// Remove the sample tasks inserted by --demo, and the sample projects and tags left unused
+    #[arg(long)]
+    pub wipe: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct BackupArgs {
+    /// Where to write the backup
+    #[arg(required_unless_present = "to", conflicts_with = "to")]
+    pub file: Option<PathBuf>,
+
+    /// Upload to S3 or MinIO instead, e.g. s3://bucket/task-backups; encrypted with [backup]
+    /// passphrase, keeping the newest [backup] keep uploads
+    #[arg(long, value_name = "URL")]
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct RestoreArgs {
+    /// Backup file to restore from, or the s3:// URL of an uploaded backup
+    pub file: PathBuf,
+
+    /// Delete all existing tasks before restoring
+    #[arg(long)]
+    pub wipe: bool,
+
+    /// Don't ask for confirmation before wiping
+    #[arg(long, short)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct CsvImportArgs {
+    /// CSV file to import; the first row must name the columns
+    pub file: PathBuf,
+
+    /// Read a task field from a differently named column, e.g. --column due="Due Date".
+    /// Fields: description, due, priority, tags.
+    #[arg(long = "column", value_name = "FIELD=HEADER")]
+    pub columns: Vec<String>,
+
+    /// Field delimiter
+    #[arg(long, default_value_t = ',')]
+    pub delimiter: char,
+
+    /// Separator between tags within the tags column
+    #[arg(long, default_value_t = ',')]
+    pub tag_separator: char,
+
+    #[command(flatten)]
+    pub options: ImportOptions,
+}
+
+#[derive(Debug, Args)]
+pub struct FileImportArgs {
+    /// File to import
+    pub file: PathBuf,
+
+    #[command(flatten)]
+    pub options: ImportOptions,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportOptions {
+    /// Show what would be imported without changing anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Don't ask for confirmation before importing
+    #[arg(long, short)]
+    pub yes: bool,
+}
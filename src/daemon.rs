@@ -0,0 +1,874 @@
+// `task daemon`: one process that keeps running and does on a schedule what the reminder and
+// sync commands do when run from cron, as listed under [daemon]. It claims the database in
+// daemon_state and keeps the claim alive with a heartbeat, so a second daemon refuses to start
+// while the first is alive, and records how each job went in daemon_jobs for `task daemon
+// status`. Jobs run one after the other; each is as safe to repeat as its command, since the
+// notify commands never send twice and the syncs pick up where they left off. While it runs it
+// answers the CLI on a socket (see ipc.rs), e.g. to run a sync right away, and with [daemon]
+// metrics serves Prometheus metrics over HTTP. A change to the config file is picked up
+// between jobs: the new schedules, notification settings and profile are checked in full
+// first, including a connection when the profile now points at another database, and only
+// then take over; a config that fails any check is reported and the old one stays.
+
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use chrono::{Local, NaiveDateTime};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::cli::{Channel, WebhookCommand};
+#[cfg(feature = "notifications")]
+use crate::cli::NotifyArgs;
+#[cfg(feature = "integrations")]
+use crate::cli::{CaldavSyncArgs, GitlabSyncArgs, NotionSyncArgs, Prefer, SyncArgs};
+use crate::config::{Config, ConnectionSettings, DaemonConfig, SyncService};
+use crate::error::{Result, TaskError};
+use crate::export::ics;
+use crate::ipc::{self, Request, Response};
+use crate::metrics;
+#[cfg(feature = "notifications")]
+use crate::notify::{self, desktop::Reminders};
+use crate::notify::Pending;
+use crate::repository::{DaemonJob, TaskRepository};
+#[cfg(feature = "integrations")]
+use crate::sync;
+use crate::{db, repository, schema, settings, users};
+use crate::{escalation, format_due, format_timestamp, recurrence, webhooks};
+
+const HEARTBEAT: Duration = Duration::from_secs(30);
+// How often the config file is checked for changes
+const RELOAD_CHECK: Duration = Duration::from_secs(5);
+// A daemon whose heartbeat is older than this counts as gone
+pub const STALE_SECS: u32 = 90;
+
+const DEFAULT_NOTIFY_INTERVAL: u64 = 60;
+const DEFAULT_SYNC_INTERVAL: u64 = 900;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Job {
+    Notify(Channel),
+    Digest,
+    Sync(SyncService),
+    WebhookRetry,
+    Recur,
+    Escalate,
+}
+
+impl Job {
+    // `job` in daemon_jobs, e.g. "notify email" or "sync todoist"
+    fn name(self) -> String {
+        match self {
+            Job::Notify(channel) => format!("notify {:?}", channel).to_lowercase(),
+            Job::Digest => "email digest".to_string(),
+            Job::Sync(service) => format!("sync {:?}", service).to_lowercase(),
+            Job::WebhookRetry => "webhook retry".to_string(),
+            Job::Recur => "recurring tasks".to_string(),
+            Job::Escalate => "overdue escalation".to_string(),
+        }
+    }
+
+    // Fails for a job of a subsystem this build was made without
+    fn check_built(self) -> Result<()> {
+        match self {
+            Job::Notify(_) | Job::Digest if !cfg!(feature = "notifications") => {
+                Err(TaskError::not_built("notifications", "notifications"))
+            }
+            Job::Sync(_) if !cfg!(feature = "integrations") => Err(TaskError::not_built("sync", "integrations")),
+            _ => Ok(()),
+        }
+    }
+
+    async fn run(self, repo: &TaskRepository, config: &Config, reminders: &Reminders) -> Result<()> {
+        #[cfg(feature = "notifications")]
+        let notify = NotifyArgs { dry_run: false };
+        #[cfg(feature = "integrations")]
+        let options = SyncArgs { dry_run: false };
+        match self {
+            #[cfg(feature = "notifications")]
+            Job::Notify(Channel::Email) => notify::email::run(repo, config, notify).await,
+            #[cfg(feature = "notifications")]
+            Job::Notify(Channel::Sms) => notify::sms::run(repo, config, notify).await,
+            #[cfg(feature = "notifications")]
+            Job::Notify(Channel::Desktop) => reminders.check(repo).await,
+            #[cfg(feature = "notifications")]
+            Job::Notify(channel @ (Channel::Slack | Channel::Discord)) => {
+                notify::post_overdue(repo, config, channel, false).await
+            }
+            #[cfg(feature = "notifications")]
+            Job::Digest => notify::email::send_digests(repo, config).await,
+            #[cfg(not(feature = "notifications"))]
+            Job::Notify(_) | Job::Digest => {
+                let _ = reminders;
+                Err(TaskError::not_built("notifications", "notifications"))
+            }
+            #[cfg(feature = "integrations")]
+            Job::Sync(SyncService::Todoist) => sync::todoist::run(repo, config, options).await,
+            #[cfg(feature = "integrations")]
+            Job::Sync(SyncService::Github) => sync::github::run(repo, config, options).await,
+            #[cfg(feature = "integrations")]
+            Job::Sync(SyncService::Gitlab) => {
+                sync::gitlab::run(repo, config, GitlabSyncArgs { project: None, options }).await
+            }
+            #[cfg(feature = "integrations")]
+            Job::Sync(SyncService::Caldav) => {
+                sync::caldav::run(repo, config, CaldavSyncArgs { prefer: Prefer::Local, options }).await
+            }
+            #[cfg(feature = "integrations")]
+            Job::Sync(SyncService::Google) => sync::google::run(repo, config, options).await,
+            #[cfg(feature = "integrations")]
+            Job::Sync(SyncService::Notion) => {
+                sync::notion::run(repo, config, NotionSyncArgs { full: false, options }).await
+            }
+            #[cfg(feature = "integrations")]
+            Job::Sync(SyncService::Vault) => sync::vault::run(repo, config, options).await,
+            #[cfg(not(feature = "integrations"))]
+            Job::Sync(_) => Err(TaskError::not_built("sync", "integrations")),
+            Job::WebhookRetry => webhooks::run(repo, config, WebhookCommand::Retry).await,
+            Job::Recur => recurrence::create_upcoming(repo, &config.daemon).await,
+            Job::Escalate => escalation::escalate(repo, config, false).await,
+        }
+    }
+}
+
+// Stands in for the desktop reminders in builds without notifications, where no job uses them
+#[cfg(not(feature = "notifications"))]
+struct Reminders;
+
+#[cfg(not(feature = "notifications"))]
+impl Reminders {
+    fn new(_config: &Config) -> Result<Self> {
+        Ok(Reminders)
+    }
+}
+
+#[derive(Debug)]
+struct Scheduled {
+    job: Job,
+    every: Duration,
+    next: Instant,
+}
+
+// The jobs of [daemon] with how often they run, in the order they are listed. A job listed
+// twice runs once. Failed webhook requests are sent again, recurring tasks get their coming
+// occurrences and overdue tasks are escalated as often as the syncs run.
+fn jobs(config: &DaemonConfig) -> Vec<(Job, Duration)> {
+    let notify = Duration::from_secs(config.notify_interval.unwrap_or(DEFAULT_NOTIFY_INTERVAL).max(1));
+    let sync = Duration::from_secs(config.sync_interval.unwrap_or(DEFAULT_SYNC_INTERVAL).max(1));
+
+    let mut listed: Vec<(Job, Duration)> = Vec::new();
+    for channel in &config.notify {
+        listed.push((Job::Notify(*channel), notify));
+    }
+    if config.digest {
+        listed.push((Job::Digest, notify));
+    }
+    for service in &config.sync {
+        listed.push((Job::Sync(*service), sync));
+    }
+    if config.webhook_retry {
+        listed.push((Job::WebhookRetry, sync));
+    }
+    if config.recur {
+        listed.push((Job::Recur, sync));
+    }
+    if config.escalate {
+        listed.push((Job::Escalate, sync));
+    }
+
+    let mut jobs: Vec<(Job, Duration)> = Vec::with_capacity(listed.len());
+    for (job, every) in listed {
+        if !jobs.iter().any(|(other, _)| *other == job) {
+            jobs.push((job, every));
+        }
+    }
+    jobs
+}
+
+// Every job, due at `start`
+fn schedule(config: &DaemonConfig, start: Instant) -> Vec<Scheduled> {
+    jobs(config).into_iter().map(|(job, every)| Scheduled { job, every, next: start }).collect()
+}
+
+// The jobs `names` ask for. A name matches a job by its full name ("sync todoist") or by
+// either word of it ("sync", "todoist"); no names ask for every job.
+fn select(jobs: &[Job], names: &[String]) -> std::result::Result<Vec<Job>, String> {
+    if names.is_empty() {
+        return Ok(jobs.to_vec());
+    }
+    let mut selected = Vec::new();
+    for name in names {
+        let name = name.trim().to_lowercase();
+        let matches = |job: &&Job| job.name() == name || job.name().split(' ').any(|word| word == name);
+        let matching: Vec<Job> = jobs.iter().filter(matches).copied().collect();
+        if matching.is_empty() {
+            let known: Vec<String> = jobs.iter().map(|job| job.name()).collect();
+            let known = if known.is_empty() { "none".to_string() } else { known.join(", ") };
+            return Err(format!("No job of the daemon matches '{}'; its jobs are: {}.", name, known));
+        }
+        for job in matching {
+            if !selected.contains(&job) {
+                selected.push(job);
+            }
+        }
+    }
+    Ok(selected)
+}
+
+// The job that is due first; of jobs due at the same time, the one listed first
+fn next_due(schedule: &[Scheduled]) -> Option<usize> {
+    (0..schedule.len()).min_by_key(|&index| (schedule[index].next, index))
+}
+
+// `task daemon`: runs until Ctrl-C or SIGTERM, after the job that is running has finished.
+// `profile` is the one asked for with --profile or TASK_PROFILE, for reloads.
+pub async fn run(repo: &TaskRepository, config: &Config, profile: Option<&str>) -> Result<()> {
+    let mut schedule = planned(config, Instant::now())?;
+    let mut reminders = Reminders::new(config)?;
+    let metrics = serve_metrics(config).await?;
+    let (mut repo, mut config) = (repo.clone(), config.clone());
+    let mut database = config.resolve(profile)?;
+    // Set once a reload moved the daemon to another database, whose pool is then ours to close
+    let mut switched = false;
+
+    let (host, pid) = (ics::hostname(), std::process::id());
+    if !repo.claim_daemon(&host, pid, STALE_SECS).await? {
+        let running = match repo.daemon_state(STALE_SECS).await? {
+            Some(other) => format!(" on {} (pid {})", other.host, other.pid),
+            None => String::new(),
+        };
+        return Err(TaskError::InvalidInput(format!(
+            "A daemon is already running{}; stop it first, or check with `task daemon status`.",
+            running
+        )));
+    }
+
+    println!("Daemon running on {} (pid {}): {}. Press Ctrl-C to stop.", host, pid, job_names(&schedule));
+    tracing::info!(host, pid, jobs = schedule.len(), "daemon started");
+
+    let (triggers, mut triggered) = mpsc::unbounded_channel();
+    let mut listener = listen(&repo, &config, scheduled_jobs(&schedule), triggers.clone());
+
+    let path = Config::path();
+    let mut watched = modified(&path);
+    let mut check = tokio::time::interval_at(Instant::now() + RELOAD_CHECK, RELOAD_CHECK);
+    let mut heartbeat = tokio::spawn(keep_alive(repo.clone(), host.clone(), pid));
+    let shutdown = crate::shutdown_signal();
+    tokio::pin!(shutdown);
+    let result = loop {
+        let index = next_due(&schedule).expect("the schedule isn't empty");
+        tokio::select! {
+            _ = tokio::time::sleep_until(schedule[index].next) => {},
+            Some(jobs) = triggered.recv() => {
+                let now = Instant::now();
+                for scheduled in schedule.iter_mut().filter(|scheduled| jobs.contains(&scheduled.job)) {
+                    scheduled.next = now;
+                }
+                continue;
+            }
+            _ = check.tick() => {
+                let now = modified(&path);
+                if now == watched {
+                    continue;
+                }
+                watched = now;
+                let reload = match Config::load() {
+                    Ok(loaded) => prepare_reload(&repo, &database, loaded, profile, &host, pid).await,
+                    Err(e) => Err(e),
+                };
+                let reload = match reload {
+                    Ok(reload) => reload,
+                    Err(e) => {
+                        tracing::warn!(error = %e, "config not reloaded");
+                        println!(
+                            "Warning: not reloading {}, keeping the config the daemon runs with: {}",
+                            path.display(),
+                            e
+                        );
+                        continue;
+                    }
+                };
+                if reload.config.daemon.metrics != config.daemon.metrics || reload.config.timezone != config.timezone {
+                    tracing::warn!("[daemon] metrics and timezone only change when the daemon is restarted");
+                }
+
+                if let Some(moved) = reload.repo {
+                    if let Err(e) = repo.release_daemon(&host, pid).await {
+                        tracing::warn!(error = %e, "could not release the claim on the previous database");
+                    }
+                    if switched {
+                        repo.pool().close().await;
+                    }
+                    heartbeat.abort();
+                    heartbeat = tokio::spawn(keep_alive(moved.clone(), host.clone(), pid));
+                    (repo, switched) = (moved, true);
+                } else {
+                    repo = repo
+                        .with_retry_policy(reload.config.retry)
+                        .with_query_timeout(reload.config.query_timeout())
+                        .with_bulk_policy(reload.config.bulk)
+                        .with_cache_policy(reload.config.cache);
+                }
+                schedule = carry_over(&schedule, reload.schedule, Instant::now());
+                (config, database, reminders) = (reload.config, reload.database, reload.reminders);
+
+                if let Some((socket, answers)) = listener.take() {
+                    answers.abort();
+                    if ipc::socket_path(&config).as_ref() != Some(&socket) {
+                        let _ = std::fs::remove_file(socket);
+                    }
+                }
+                listener = listen(&repo, &config, scheduled_jobs(&schedule), triggers.clone());
+                tracing::info!(jobs = schedule.len(), "config reloaded");
+                println!("Reloaded {}: {}.", path.display(), job_names(&schedule));
+                continue;
+            }
+            beat = &mut heartbeat => break beat.unwrap_or_else(|e| Err(std::io::Error::other(e).into())),
+            _ = &mut shutdown => break Ok(()),
+        }
+
+        let scheduled = &mut schedule[index];
+        let name = scheduled.job.name();
+        let started_at = Local::now().naive_local();
+        let outcome = scheduled.job.run(&repo, &config, &reminders).await;
+        scheduled.next = Instant::now() + scheduled.every;
+        metrics::record_command(name.clone(), outcome.is_ok());
+
+        let finished_at = Local::now().naive_local();
+        let error = outcome.err().map(|e| e.to_string());
+        if let Some(error) = &error {
+            tracing::warn!(job = %name, error = %error, "daemon job failed");
+        }
+        let next_run_at = finished_at + chrono::Duration::from_std(scheduled.every).unwrap_or_default();
+        repo.record_daemon_job(&name, started_at, finished_at, error.as_deref(), next_run_at).await?;
+    };
+
+    heartbeat.abort();
+    if let Some(metrics) = metrics {
+        metrics.abort();
+    }
+    if let Some((path, listener)) = listener {
+        listener.abort();
+        let _ = std::fs::remove_file(path);
+    }
+    repo.release_daemon(&host, pid).await?;
+    if switched {
+        repo.pool().close().await;
+    }
+    tracing::info!(host, pid, "daemon stopped");
+    println!("Daemon stopped.");
+    result
+}
+
+// The schedule of a config, if the daemon can run it
+fn planned(config: &Config, start: Instant) -> Result<Vec<Scheduled>> {
+    let schedule = schedule(&config.daemon, start);
+    if schedule.is_empty() {
+        return Err(TaskError::Config(
+            "Nothing for the daemon to do; list channels under notify or services under sync, or set digest, \
+             recur or escalate, in the [daemon] section of the config file."
+                .to_string(),
+        ));
+    }
+    for scheduled in &schedule {
+        scheduled.job.check_built()?;
+    }
+    Ok(schedule)
+}
+
+fn scheduled_jobs(schedule: &[Scheduled]) -> Vec<Job> {
+    schedule.iter().map(|scheduled| scheduled.job).collect()
+}
+
+fn job_names(schedule: &[Scheduled]) -> String {
+    schedule.iter().map(|scheduled| scheduled.job.name()).collect::<Vec<_>>().join(", ")
+}
+
+// When the config file was last changed; None while there is none
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+// A changed config, checked in full before the running daemon takes any of it, so a config
+// that doesn't work leaves the daemon running as it was
+struct Reload {
+    config: Config,
+    database: ConnectionSettings,
+    schedule: Vec<Scheduled>,
+    reminders: Reminders,
+    // Set when the profile now points at another database, which is already claimed for this
+    // daemon
+    repo: Option<TaskRepository>,
+}
+
+async fn prepare_reload(
+    repo: &TaskRepository,
+    database: &ConnectionSettings,
+    loaded: Config,
+    profile: Option<&str>,
+    host: &str,
+    pid: u32,
+) -> Result<Reload> {
+    let settings = loaded.resolve(profile)?;
+    let moved = settings.database_url != database.database_url || settings.max_connections != database.max_connections;
+    let target = if moved { Some(connect(&loaded, &settings).await?) } else { None };
+
+    let checked = async {
+        // The account's settings fill in what the file leaves out, as at startup
+        let config = settings::merge(target.as_ref().unwrap_or(repo), &loaded).await?;
+        let schedule = planned(&config, Instant::now())?;
+        let reminders = Reminders::new(&config)?;
+        if let Some(target) = &target
+            && !target.claim_daemon(host, pid, STALE_SECS).await?
+        {
+            return Err(TaskError::InvalidInput(format!(
+                "a daemon is already running against the database of the profile {}",
+                settings.profile.as_deref().unwrap_or("in DATABASE_URL")
+            )));
+        }
+        Ok((config, schedule, reminders))
+    }
+    .await;
+
+    match checked {
+        Ok((config, schedule, reminders)) => {
+            Ok(Reload { config, database: settings, schedule, reminders, repo: target })
+        }
+        Err(e) => {
+            if let Some(target) = target {
+                target.pool().close().await;
+            }
+            Err(e)
+        }
+    }
+}
+
+// The repository on another database, set up the way `task` sets it up at startup
+async fn connect(config: &Config, settings: &ConnectionSettings) -> Result<TaskRepository> {
+    let pool = db::connect(settings).await?;
+    let opened = async {
+        schema::check_version(&pool).await?;
+        let repo = TaskRepository::new(pool.clone(), repository::os_user())
+            .with_retry_policy(config.retry)
+            .with_query_timeout(config.query_timeout())
+            .with_bulk_policy(config.bulk)
+            .with_cache_policy(config.cache);
+        users::authenticate(repo).await
+    }
+    .await;
+    if opened.is_err() {
+        pool.close().await;
+    }
+    opened
+}
+
+// The schedule after a reload: a job that stays keeps its next run, unless its new interval
+// would have it run sooner; a new one is due right away
+fn carry_over(old: &[Scheduled], new: Vec<Scheduled>, now: Instant) -> Vec<Scheduled> {
+    new.into_iter()
+        .map(|mut scheduled| {
+            if let Some(before) = old.iter().find(|before| before.job == scheduled.job) {
+                scheduled.next = before.next.min(now + scheduled.every);
+            }
+            scheduled
+        })
+        .collect()
+}
+
+// Serves /metrics on the address of [daemon] metrics, if there is one
+#[cfg(feature = "server")]
+async fn serve_metrics(config: &Config) -> Result<Option<JoinHandle<()>>> {
+    let Some(addr) = config.daemon.metrics else { return Ok(None) };
+    metrics::install_prometheus()?;
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| TaskError::Config(format!("Could not listen on {}: {}", addr, e)))?;
+    println!("Serving metrics on http://{}/metrics", listener.local_addr().unwrap_or(addr));
+    Ok(Some(tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, metrics::routes()).await {
+            tracing::warn!(error = %e, "stopped serving metrics");
+        }
+    })))
+}
+
+#[cfg(not(feature = "server"))]
+async fn serve_metrics(config: &Config) -> Result<Option<JoinHandle<()>>> {
+    match config.daemon.metrics {
+        Some(_) => Err(TaskError::not_built("metrics endpoint", "server")),
+        None => Ok(None),
+    }
+}
+
+// Starts answering the CLI on the socket of ipc.rs. Returns the socket and the task that
+// answers on it; None if there is nowhere to listen, which is only warned about, since the
+// daemon does its jobs all the same.
+#[cfg(unix)]
+fn listen(
+    repo: &TaskRepository,
+    config: &Config,
+    jobs: Vec<Job>,
+    triggers: mpsc::UnboundedSender<Vec<Job>>,
+) -> Option<(std::path::PathBuf, JoinHandle<()>)> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = ipc::socket_path(config)?;
+    let bind = || -> std::io::Result<tokio::net::UnixListener> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Holding the claim on the database, so a socket that is there was left behind
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        let listener = tokio::net::UnixListener::bind(&path)?;
+        // Only the user the daemon runs as may talk to it
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+        Ok(listener)
+    };
+    match bind() {
+        Ok(listener) => {
+            let config = std::sync::Arc::new(config.clone());
+            let answers = tokio::spawn(answer_all(listener, repo.clone(), config, jobs, triggers));
+            Some((path, answers))
+        }
+        Err(e) => {
+            tracing::warn!(path = %path.display(), error = %e, "not listening for daemon commands");
+            None
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn listen(
+    _repo: &TaskRepository,
+    _config: &Config,
+    _jobs: Vec<Job>,
+    _triggers: mpsc::UnboundedSender<Vec<Job>>,
+) -> Option<(std::path::PathBuf, JoinHandle<()>)> {
+    None
+}
+
+// One request per connection; a client that doesn't finish its request in time is dropped
+#[cfg(unix)]
+async fn answer_all(
+    listener: tokio::net::UnixListener,
+    repo: TaskRepository,
+    config: std::sync::Arc<Config>,
+    jobs: Vec<Job>,
+    triggers: mpsc::UnboundedSender<Vec<Job>>,
+) {
+    loop {
+        let mut stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                tracing::warn!(error = %e, "could not accept a connection on the daemon socket");
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                continue;
+            }
+        };
+        let exchange = async {
+            let request = ipc::read_message(&mut stream).await?;
+            let response = answer(&repo, &config, &jobs, &triggers, request).await;
+            ipc::write_message(&mut stream, &response).await
+        };
+        match tokio::time::timeout(ipc::TIMEOUT, exchange).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => tracing::warn!(error = %e, "bad request on the daemon socket"),
+            Err(_) => tracing::warn!("request on the daemon socket timed out"),
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn answer(
+    repo: &TaskRepository,
+    config: &Config,
+    jobs: &[Job],
+    triggers: &mpsc::UnboundedSender<Vec<Job>>,
+    request: Request,
+) -> Response {
+    match request {
+        Request::RunNow { jobs: names } => match select(jobs, &names) {
+            Ok(selected) => {
+                let jobs = selected.iter().map(|job| job.name()).collect();
+                let _ = triggers.send(selected);
+                Response::Queued { jobs }
+            }
+            Err(message) => Response::Error { message },
+        },
+        Request::Reminders => match pending_reminders(repo, config).await {
+            Ok(reminders) => Response::Reminders { reminders },
+            Err(e) => Response::Error { message: e.to_string() },
+        },
+    }
+}
+
+// What the channels of [daemon] are yet to send, channel by channel
+#[cfg(feature = "notifications")]
+async fn pending_reminders(repo: &TaskRepository, config: &Config) -> Result<Vec<Pending>> {
+    let mut reminders = Vec::new();
+    for (job, _) in jobs(&config.daemon) {
+        if let Job::Notify(channel) = job {
+            reminders.extend(notify::pending(repo, config, channel).await?);
+        }
+    }
+    Ok(reminders)
+}
+
+#[cfg(not(feature = "notifications"))]
+async fn pending_reminders(_repo: &TaskRepository, _config: &Config) -> Result<Vec<Pending>> {
+    Err(TaskError::not_built("notifications", "notifications"))
+}
+
+// `task daemon run`: the daemon runs the jobs as soon as it is free. Without a daemon they run
+// here and now, from the [daemon] section of this config file.
+pub async fn run_now(repo: &TaskRepository, config: &Config, names: Vec<String>) -> Result<()> {
+    match ipc::request(config, &Request::RunNow { jobs: names.clone() }).await? {
+        Some(Response::Queued { jobs }) => println!("The daemon runs {} next.", jobs.join(", ")),
+        Some(Response::Error { message }) => return Err(TaskError::InvalidInput(message)),
+        Some(other) => return Err(unexpected(other)),
+        None => {
+            let listed: Vec<Job> = jobs(&config.daemon).into_iter().map(|(job, _)| job).collect();
+            let selected = select(&listed, &names).map_err(TaskError::InvalidInput)?;
+            if selected.is_empty() {
+                println!("No daemon is running, and [daemon] lists no jobs to run here.");
+                return Ok(());
+            }
+            println!("No daemon is running; running the jobs here.");
+            let reminders = Reminders::new(config)?;
+            for job in selected {
+                if let Err(e) = job.run(repo, config, &reminders).await {
+                    tracing::warn!(job = job.name(), error = %e, "daemon job failed");
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// `task daemon reminders`: asks the daemon, or works them out here if none is running
+pub async fn reminders(repo: &TaskRepository, config: &Config) -> Result<()> {
+    let reminders = match ipc::request(config, &Request::Reminders).await? {
+        Some(Response::Reminders { reminders }) => reminders,
+        Some(Response::Error { message }) => return Err(TaskError::Remote { service: "Daemon", message }),
+        Some(other) => return Err(unexpected(other)),
+        None => pending_reminders(repo, config).await?,
+    };
+
+    if reminders.is_empty() {
+        println!("No reminders waiting to be sent.");
+        return Ok(());
+    }
+    for reminder in &reminders {
+        let when = if reminder.overdue { "overdue since" } else { "due" };
+        println!(
+            "{:<8} [{}] {} ({} {})",
+            reminder.channel,
+            reminder.task_id,
+            reminder.description,
+            when,
+            format_due(&reminder.due_at)
+        );
+    }
+    Ok(())
+}
+
+fn unexpected(response: Response) -> TaskError {
+    TaskError::Remote { service: "Daemon", message: format!("unexpected answer {:?}", response) }
+}
+
+// Beats until the claim is lost; only returns then, or when the database can't be reached
+async fn keep_alive(repo: TaskRepository, host: String, pid: u32) -> Result<()> {
+    let mut interval = tokio::time::interval_at(Instant::now() + HEARTBEAT, HEARTBEAT);
+    loop {
+        interval.tick().await;
+        if !repo.daemon_heartbeat(&host, pid).await? {
+            return Err(TaskError::InvalidInput(
+                "Another daemon took over after this one missed its heartbeats; stopping.".to_string(),
+            ));
+        }
+    }
+}
+
+// `task daemon status`
+pub async fn status(repo: &TaskRepository) -> Result<()> {
+    match repo.daemon_state(STALE_SECS).await? {
+        None => println!("No daemon has run against this database yet."),
+        Some(state) if state.running => println!(
+            "Daemon running on {} (pid {}) since {}, last heartbeat at {}.",
+            state.host,
+            state.pid,
+            format_timestamp(&state.started_at),
+            format_timestamp(&state.heartbeat_at)
+        ),
+        Some(state) => {
+            let ended = match &state.stopped_at {
+                Some(stopped_at) => format!("stopped at {}", format_timestamp(stopped_at)),
+                None => format!("stopped answering at {}", format_timestamp(&state.heartbeat_at)),
+            };
+            println!("No daemon is running. The last one, on {} (pid {}), {}.", state.host, state.pid, ended);
+        }
+    }
+
+    let jobs = repo.daemon_jobs().await?;
+    if !jobs.is_empty() {
+        println!("\nJobs:");
+        for job in &jobs {
+            println!("{}", format_job(job));
+        }
+    }
+    Ok(())
+}
+
+fn format_job(job: &DaemonJob) -> String {
+    let failures = if job.failures > 0 { format!(", {} failed", job.failures) } else { String::new() };
+    let at = |label: &str, at: &Option<NaiveDateTime>| {
+        at.as_ref().map(|at| format!(", {} at {}", label, format_timestamp(at))).unwrap_or_default()
+    };
+    let error = job.last_error.as_deref().map(|error| format!("\n    last run failed: {}", error)).unwrap_or_default();
+    let (last, next) = (at("last", &job.last_finished_at), at("next", &job.next_run_at));
+    format!("  {:<14} {} run(s){}{}{}{}", job.job, job.runs, failures, last, next, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_need_their_subsystem_in_the_build() {
+        assert!(Job::WebhookRetry.check_built().is_ok());
+        assert_eq!(Job::Sync(SyncService::Todoist).check_built().is_ok(), cfg!(feature = "integrations"));
+        let notify = Job::Notify(Channel::Email).check_built();
+        assert_eq!(notify.is_ok(), cfg!(feature = "notifications"));
+        if let Err(e) = notify {
+            assert!(e.to_string().contains("--features notifications"), "{}", e);
+        }
+    }
+
+    #[test]
+    fn jobs_come_from_the_config() {
+        let config = DaemonConfig {
+            notify: vec![Channel::Email, Channel::Desktop, Channel::Email],
+            notify_interval: Some(30),
+            digest: true,
+            sync: vec![SyncService::Caldav],
+            sync_interval: None,
+            webhook_retry: true,
+            recur: true,
+            recur_horizon: None,
+            escalate: true,
+            socket: None,
+            metrics: None,
+        };
+        let start = Instant::now();
+        let schedule = schedule(&config, start);
+
+        let jobs: Vec<(String, u64)> =
+            schedule.iter().map(|scheduled| (scheduled.job.name(), scheduled.every.as_secs())).collect();
+        assert_eq!(
+            jobs,
+            [
+                ("notify email".to_string(), 30),
+                ("notify desktop".to_string(), 30),
+                ("email digest".to_string(), 30),
+                ("sync caldav".to_string(), DEFAULT_SYNC_INTERVAL),
+                ("webhook retry".to_string(), DEFAULT_SYNC_INTERVAL),
+                ("recurring tasks".to_string(), DEFAULT_SYNC_INTERVAL),
+                ("overdue escalation".to_string(), DEFAULT_SYNC_INTERVAL),
+            ]
+        );
+        assert!(schedule.iter().all(|scheduled| scheduled.next == start));
+        assert!(super::schedule(&DaemonConfig::default(), start).is_empty());
+    }
+
+    #[test]
+    fn the_earliest_job_runs_next() {
+        let config = DaemonConfig { notify: vec![Channel::Slack, Channel::Sms], ..Default::default() };
+        let start = Instant::now();
+        let mut schedule = schedule(&config, start);
+        assert_eq!(next_due(&schedule), Some(0));
+
+        schedule[0].next = start + schedule[0].every;
+        assert_eq!(next_due(&schedule), Some(1));
+        schedule[1].next = start + Duration::from_secs(1);
+        assert_eq!(next_due(&schedule), Some(1));
+        assert_eq!(next_due(&[]), None);
+    }
+
+    #[test]
+    fn a_reload_keeps_the_timing_of_jobs_that_stay() {
+        let start = Instant::now();
+        let before =
+            DaemonConfig { notify: vec![Channel::Email], sync: vec![SyncService::Vault], ..Default::default() };
+        let mut old = schedule(&before, start);
+        old[0].next = start + Duration::from_secs(50);
+        old[1].next = start + Duration::from_secs(800);
+
+        let after = DaemonConfig {
+            notify: vec![Channel::Email, Channel::Slack],
+            sync: vec![SyncService::Vault],
+            sync_interval: Some(120),
+            ..Default::default()
+        };
+        let now = start + Duration::from_secs(10);
+        let new = carry_over(&old, schedule(&after, now), now);
+        let next: Vec<(String, Duration)> =
+            new.iter().map(|scheduled| (scheduled.job.name(), scheduled.next - start)).collect();
+        assert_eq!(
+            next,
+            [
+                ("notify email".to_string(), Duration::from_secs(50)),
+                ("notify slack".to_string(), Duration::from_secs(10)),
+                ("sync vault".to_string(), Duration::from_secs(130)),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_config_without_jobs_is_not_taken() {
+        let error = planned(&Config::default(), Instant::now()).unwrap_err();
+        assert!(error.to_string().contains("Nothing for the daemon to do"), "{}", error);
+    }
+
+    #[test]
+    fn jobs_are_asked_for_by_name_or_word() {
+        let jobs = [Job::Notify(Channel::Email), Job::Sync(SyncService::Todoist), Job::Sync(SyncService::Github)];
+        let names = |selected: Vec<Job>| selected.into_iter().map(Job::name).collect::<Vec<_>>();
+
+        assert_eq!(names(select(&jobs, &[]).unwrap()), ["notify email", "sync todoist", "sync github"]);
+        assert_eq!(names(select(&jobs, &["sync".to_string()]).unwrap()), ["sync todoist", "sync github"]);
+        let asked = ["Email".to_string(), "sync github".to_string(), "github".to_string()];
+        assert_eq!(names(select(&jobs, &asked).unwrap()), ["notify email", "sync github"]);
+
+        let error = select(&jobs, &["caldav".to_string()]).unwrap_err();
+        assert!(error.contains("'caldav'") && error.contains("sync todoist"), "{}", error);
+    }
+
+    // A request from the CLI reaches the daemon's loop; nothing here needs the database
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn the_cli_queues_jobs_over_the_socket() {
+        let path = std::env::temp_dir().join(format!("task-daemon-{}.sock", std::process::id()));
+        let mut config = Config::default();
+        config.daemon.socket = Some(path.clone());
+        let pool = sqlx::MySqlPool::connect_lazy("mysql://localhost/unused").unwrap();
+        let repo = TaskRepository::new(pool, "tester".to_string());
+
+        let jobs = vec![Job::Notify(Channel::Desktop), Job::Sync(SyncService::Vault)];
+        let (triggers, mut triggered) = mpsc::unbounded_channel();
+        let (socket, listener) = listen(&repo, &config, jobs, triggers).expect("listening");
+
+        let response = ipc::request(&config, &Request::RunNow { jobs: vec!["vault".to_string()] }).await.unwrap();
+        assert!(matches!(response, Some(Response::Queued { ref jobs }) if jobs == &["sync vault"]), "{:?}", response);
+        assert_eq!(triggered.recv().await, Some(vec![Job::Sync(SyncService::Vault)]));
+
+        let response = ipc::request(&config, &Request::RunNow { jobs: vec!["email".to_string()] }).await.unwrap();
+        assert!(matches!(response, Some(Response::Error { .. })), "{:?}", response);
+
+        listener.abort();
+        std::fs::remove_file(socket).unwrap();
+        assert!(ipc::request(&config, &Request::Reminders).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,167 @@
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+
+use serde::Deserialize;
+use sqlx::mysql::{MySqlConnectOptions, MySqlDatabaseError, MySqlPoolOptions};
+use sqlx::MySqlPool;
+
+use crate::config::ConnectionSettings;
+use crate::error::{self, Result, TaskError};
+
+// Connection character set and collation. utf8mb4 is MySQL's only encoding that covers all of
+// Unicode (its "utf8" stops at 3 bytes, which rules out emoji); the schema uses the same pair.
+pub const CHARSET: &str = "utf8mb4";
+pub const COLLATION: &str = "utf8mb4_unicode_ci";
+
+pub async fn connect(settings: &ConnectionSettings) -> Result<MySqlPool> {
+    let options: MySqlConnectOptions = settings.database_url.parse().map_err(|e| {
+        TaskError::Config(format!("Invalid database URL for {}: {}", error::url_host(&settings.database_url), e))
+    })?;
+
+    MySqlPoolOptions::new()
+        .max_connections(settings.max_connections)
+        .connect_with(options.charset(CHARSET).collation(COLLATION))
+        .await
+        .map_err(|source| TaskError::Connect { host: error::url_host(&settings.database_url), source })
+}
+
+// MySQL server errors sent right before the server drops a connection:
+// 1053 = server shutdown in progress, 1927 = connection was killed,
+// 4031 = disconnected by the server because of inactivity (8.0.24+)
+const DISCONNECT_ERRORS: [u16; 3] = [1053, 1927, 4031];
+
+// Whether the error means the connection died (server restarted, idle timeout, network drop)
+// rather than the statement itself being wrong
+pub fn is_connection_lost(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Io(io) => matches!(
+            io.kind(),
+            io::ErrorKind::BrokenPipe
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::UnexpectedEof
+        ),
+        sqlx::Error::Database(db) => db
+            .try_downcast_ref::<MySqlDatabaseError>()
+            .is_some_and(|e| DISCONNECT_ERRORS.contains(&e.number())),
+        _ => false,
+    }
+}
+
+// Runs `op`, and if it fails because the connection was lost, runs it once more. The pool
+// discards the dead connection and hands out a fresh one, so a long interactive session
+// survives a MySQL restart. Only use this for statements that are safe to repeat: the first
+// attempt may have been applied before the connection dropped.
+pub async fn retry_on_disconnect<T, F, Fut>(mut op: F) -> std::result::Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, sqlx::Error>>,
+{
+    match op().await {
+        Err(e) if is_connection_lost(&e) => {
+            tracing::warn!(error = %e, "database connection lost, retrying on a new connection");
+            op().await
+        }
+        result => result,
+    }
+}
+
+// MySQL errors that mean the transaction lost a lock conflict and can simply be run again:
+// 1213 = deadlock found (InnoDB rolled the transaction back), 1205 = lock wait timeout exceeded
+const LOCK_CONFLICT_ERRORS: [u16; 2] = [1213, 1205];
+
+// How write transactions react to lock conflicts. Configured in the `[retry]` section of the
+// config file; every field is optional.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetryPolicy {
+    // Retries after the first attempt; 0 disables retrying
+    pub max_retries: u32,
+    // Wait before the first retry, doubled for each one after that
+    pub initial_backoff_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { max_retries: 3, initial_backoff_ms: 50 }
+    }
+}
+
+pub fn is_lock_conflict(e: &sqlx::Error) -> bool {
+    match e {
+        sqlx::Error::Database(db) => db
+            .try_downcast_ref::<MySqlDatabaseError>()
+            .is_some_and(|e| LOCK_CONFLICT_ERRORS.contains(&e.number())),
+        _ => false,
+    }
+}
+
+// Runs a whole write transaction, starting it over with exponential backoff when it fails on a
+// deadlock or lock wait timeout. `op` must begin and commit its own transaction so that every
+// attempt starts from a clean slate.
+pub async fn retry_lock_conflicts<T, F, Fut>(policy: &RetryPolicy, mut op: F) -> std::result::Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, sqlx::Error>>,
+{
+    let mut delay = Duration::from_millis(policy.initial_backoff_ms);
+    let mut retries = 0;
+
+    loop {
+        match op().await {
+            Err(e) if retries < policy.max_retries && is_lock_conflict(&e) => {
+                retries += 1;
+                tracing::warn!(error = %e, retries, "lock conflict, retrying transaction");
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            result => return result,
+        }
+    }
+}
+
+// How long a repository operation may take, retries included, before it is given up on;
+// `query_timeout` of the config file
+pub const DEFAULT_QUERY_TIMEOUT_SECS: u64 = 30;
+
+// A repository operation that ran out of time, e.g. on a server that stopped answering. Like
+// the permission errors of roles.rs it travels boxed in a sqlx::Error.
+#[derive(Debug, thiserror::Error)]
+#[error("query {operation} timed out after {} s", .after.as_secs())]
+pub struct QueryTimeout {
+    pub operation: &'static str,
+    pub after: Duration,
+}
+
+// Runs `op`, giving up on it with a `QueryTimeout` once it takes longer than `limit`. What was
+// left of a transaction is rolled back when the connection goes back to the pool.
+pub async fn with_timeout<T>(
+    limit: Option<Duration>,
+    operation: &'static str,
+    op: impl Future<Output = std::result::Result<T, sqlx::Error>>,
+) -> std::result::Result<T, sqlx::Error> {
+    let Some(limit) = limit else { return op.await };
+    match tokio::time::timeout(limit, op).await {
+        Ok(result) => result,
+        Err(_) => Err(sqlx::Error::AnyDriverError(Box::new(QueryTimeout { operation, after: limit }))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn a_hung_query_times_out() {
+        let limit = Some(Duration::from_secs(1));
+        let error = with_timeout(limit, "list_page", std::future::pending::<std::result::Result<(), _>>())
+            .await
+            .unwrap_err();
+        let message = TaskError::from(error).to_string();
+        assert!(message.contains("query list_page timed out after 1 s"), "{}", message);
+
+        assert_eq!(with_timeout(limit, "ping", async { Ok(1) }).await.unwrap(), 1);
+        assert_eq!(with_timeout(None, "ping", async { Ok(2) }).await.unwrap(), 2);
+    }
+}
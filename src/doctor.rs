@@ -0,0 +1,128 @@
+use sqlx::MySqlPool;
+
+use crate::db::CHARSET;
+use crate::error::{Result, TaskError};
+use crate::schema;
+
+// Tables whose text columns must be able to hold any Unicode text
+const TEXT_TABLES: &[&str] = &["tasks"];
+
+#[derive(sqlx::FromRow)]
+struct SessionCharset {
+    client: String,
+    connection: String,
+    results: String,
+    collation: String,
+}
+
+#[derive(sqlx::FromRow)]
+struct TableCollation {
+    table_name: String,
+    collation: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ColumnCharset {
+    table_name: String,
+    column_name: String,
+    charset: Option<String>,
+    collation: Option<String>,
+}
+
+// Checks the setup for problems that don't cause errors right away but silently damage data.
+// Prints one line per check and fails if any of them found a problem.
+pub async fn run(pool: &MySqlPool) -> Result<()> {
+    let mut problems = 0;
+
+    match schema::check_version(pool).await {
+        Ok(()) => ok("schema is up to date"),
+        Err(e) => {
+            warn(&e.to_string());
+            problems += 1;
+        }
+    }
+
+    let session = sqlx::query_as::<_, SessionCharset>(
+        "SELECT @@character_set_client AS client, \
+         @@character_set_connection AS connection, \
+         @@character_set_results AS results, \
+         @@collation_connection AS collation"
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if [&session.client, &session.connection, &session.results].iter().all(|c| c.as_str() == CHARSET) {
+        ok(&format!("connection uses {} ({})", CHARSET, session.collation));
+    } else {
+        warn(&format!(
+            "connection character sets are client={}, connection={}, results={}; expected {} everywhere",
+            session.client, session.connection, session.results, CHARSET
+        ));
+        problems += 1;
+    }
+
+    let tables = sqlx::query_as::<_, TableCollation>(
+        "SELECT TABLE_NAME AS table_name, TABLE_COLLATION AS collation \
+         FROM information_schema.TABLES WHERE TABLE_SCHEMA = DATABASE() ORDER BY TABLE_NAME"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for table in tables.iter().filter(|t| TEXT_TABLES.contains(&t.table_name.as_str())) {
+        match table.collation.as_deref() {
+            Some(collation) if is_utf8mb4(collation) => {
+                ok(&format!("table {} uses collation {}", table.table_name, collation))
+            }
+            collation => {
+                warn(&format!(
+                    "table {} uses collation {}; emoji and other 4-byte characters will be mangled. Run `task migrate`.",
+                    table.table_name,
+                    collation.unwrap_or("(none)")
+                ));
+                problems += 1;
+            }
+        }
+    }
+
+    let columns = sqlx::query_as::<_, ColumnCharset>(
+        "SELECT TABLE_NAME AS table_name, COLUMN_NAME AS column_name, \
+         CHARACTER_SET_NAME AS charset, COLLATION_NAME AS collation \
+         FROM information_schema.COLUMNS \
+         WHERE TABLE_SCHEMA = DATABASE() AND CHARACTER_SET_NAME IS NOT NULL \
+         ORDER BY TABLE_NAME, ORDINAL_POSITION"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    for column in columns.iter().filter(|c| TEXT_TABLES.contains(&c.table_name.as_str())) {
+        if column.charset.as_deref() != Some(CHARSET) {
+            warn(&format!(
+                "column {}.{} uses character set {} (collation {}); it cannot store all Unicode text. Run `task migrate`.",
+                column.table_name,
+                column.column_name,
+                column.charset.as_deref().unwrap_or("(none)"),
+                column.collation.as_deref().unwrap_or("(none)")
+            ));
+            problems += 1;
+        }
+    }
+
+    if problems > 0 {
+        return Err(TaskError::Config(format!("doctor found {} problem(s).", problems)));
+    }
+
+    println!("No problems found.");
+    Ok(())
+}
+
+fn is_utf8mb4(collation: &str) -> bool {
+    collation.starts_with("utf8mb4_")
+}
+
+fn ok(message: &str) {
+    println!("[ok]   {}", message);
+}
+
+fn warn(message: &str) {
+    println!("[warn] {}", message);
+}
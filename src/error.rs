@@ -0,0 +1,128 @@
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use sqlx::mysql::MySqlDatabaseError;
+
+use crate::db::QueryTimeout;
+use crate::roles::Forbidden;
+
+// Top-level error for everything a command can run into. `Display` is what the user sees,
+// so every variant renders as a complete sentence, with a hint where one is useful.
+#[derive(Debug, thiserror::Error)]
+pub enum TaskError {
+    // Missing or malformed settings (environment variables, connection URL)
+    #[error("{0}")]
+    Config(String),
+    // The initial connection to the database failed
+    #[error("Could not reach database at {host}: {}", describe_sqlx_error(source))]
+    Connect { host: String, source: sqlx::Error },
+    // A query failed after we were connected
+    #[error("Database error: {}", describe_sqlx_error(.0))]
+    Database(#[source] sqlx::Error),
+    // The database schema is older than this binary expects
+    #[error("{}", describe_schema(*applied, *expected))]
+    SchemaOutdated { applied: Option<i64>, expected: i64 },
+    // User-supplied data (files, arguments) could not be used
+    #[error("{0}")]
+    InvalidInput(String),
+    // A file given to a command isn't in the format it expects; `expected` reads like
+    // "a valid backup file"
+    #[error("{path} is not {expected}: {message}")]
+    Parse { path: String, expected: &'static str, message: String },
+    // The user's role in the workspace doesn't allow the operation
+    #[error("{0}")]
+    Forbidden(String),
+    // A call to another service's API failed
+    #[error("{service} request failed: {message}")]
+    Remote { service: &'static str, message: String },
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, TaskError>;
+
+impl TaskError {
+    pub fn parse(path: &Path, expected: &'static str, error: impl fmt::Display) -> Self {
+        TaskError::Parse { path: path.display().to_string(), expected, message: error.to_string() }
+    }
+
+    // For a command of a subsystem this build was made without; see [features] in Cargo.toml
+    pub fn not_built(what: &str, feature: &str) -> Self {
+        TaskError::Config(format!(
+            "This build of task has no {} (feature `{}`); build it again with `--features {}`.",
+            what, feature, feature
+        ))
+    }
+
+    // What `task` exits with, so scripts can tell failures apart. The codes follow sysexits.h;
+    // clap exits with 2 for a command line it can't parse.
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            TaskError::InvalidInput(_) => 64, // EX_USAGE
+            TaskError::Parse { .. } => 65, // EX_DATAERR
+            TaskError::Connect { .. } => 69, // EX_UNAVAILABLE
+            TaskError::Database(_) => 70, // EX_SOFTWARE
+            TaskError::Io(_) => 74, // EX_IOERR
+            TaskError::SchemaOutdated { .. } => 75, // EX_TEMPFAIL: works again after `task migrate`
+            TaskError::Remote { .. } => 76, // EX_PROTOCOL
+            TaskError::Forbidden(_) => 77, // EX_NOPERM
+            TaskError::Config(_) => 78, // EX_CONFIG
+        }
+    }
+}
+
+fn describe_schema(applied: Option<i64>, expected: i64) -> String {
+    match applied {
+        Some(applied) => format!(
+            "Database schema is at version {} but this binary needs version {}. Run `task migrate` to upgrade.",
+            applied, expected
+        ),
+        None => "Database schema version is unknown. Run `task migrate` to set up the database.".to_string(),
+    }
+}
+
+impl From<sqlx::Error> for TaskError {
+    fn from(e: sqlx::Error) -> Self {
+        // Permission checks in the repository fail with a boxed `Forbidden` (see roles.rs)
+        if let sqlx::Error::AnyDriverError(inner) = &e
+            && let Some(forbidden) = inner.downcast_ref::<Forbidden>()
+        {
+            return TaskError::Forbidden(forbidden.to_string());
+        }
+        TaskError::Database(e)
+    }
+}
+
+// Turns the common sqlx failures into something actionable; falls back to sqlx's own message
+pub(crate) fn describe_sqlx_error(e: &sqlx::Error) -> String {
+    match e {
+        sqlx::Error::Io(io) => match io.kind() {
+            io::ErrorKind::ConnectionRefused => "connection refused. Is the MySQL server running?".to_string(),
+            io::ErrorKind::TimedOut => "connection timed out. Check the host and port in DATABASE_URL.".to_string(),
+            _ => format!("lost connection to the server ({})", io),
+        },
+        sqlx::Error::Tls(e) => format!("TLS handshake failed ({})", e),
+        sqlx::Error::PoolTimedOut => {
+            "timed out waiting for a free database connection. The server may be overloaded.".to_string()
+        }
+        sqlx::Error::Configuration(e) => format!("invalid connection settings ({})", e),
+        sqlx::Error::AnyDriverError(inner) if inner.is::<QueryTimeout>() => {
+            format!("{}. Is the MySQL server overloaded or hung? query_timeout in the config sets the limit.", inner)
+        }
+        sqlx::Error::Database(db) => match db.try_downcast_ref::<MySqlDatabaseError>().map(|e| e.number()) {
+            Some(1045) => "access denied. Check the user name and password in DATABASE_URL.".to_string(),
+            Some(1049) => format!("{}. Create the database, then run `task migrate`.", db.message()),
+            Some(1146) => format!("{}. Run `task migrate` to create missing tables.", db.message()),
+            _ => db.message().to_string(),
+        },
+        other => other.to_string(),
+    }
+}
+
+// Host (and port) part of a connection URL, without credentials, for error messages
+pub fn url_host(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.rsplit_once('@').map_or(rest, |(_, rest)| rest);
+    rest.split(['/', '?']).next().unwrap_or(rest).to_string()
+}
@@ -0,0 +1,214 @@
+// `task escalate`: tasks that stay overdue get attention. Once a task is more than [escalation]
+// after_days overdue, its priority goes up a step, the notify channel is told, or both; once
+// per due date, so moving the due date starts over. Each escalation is logged in
+// task_escalations for `task escalate log`. A message the notify rules hold back, or that
+// fails to send, is tried again on the next run.
+
+use chrono::{Duration, Local};
+
+use crate::cli::{Channel, EscalateCommand};
+use crate::config::{Config, EscalationConfig};
+use crate::error::{Result, TaskError};
+use crate::notify;
+use crate::priority::Priority;
+use crate::repository::{Escalation, TaskRepository};
+
+#[cfg(feature = "notifications")]
+const DEFAULT_MESSAGE: &str = "Task {id} ({description}) is {days} days overdue; it was due {due}.";
+
+pub async fn run(repo: &TaskRepository, config: &Config, command: EscalateCommand) -> Result<()> {
+    match command {
+        EscalateCommand::Run { dry_run } => escalate(repo, config, dry_run).await,
+        EscalateCommand::Log { limit } => log(repo, limit).await,
+    }
+}
+
+// What [escalation] asks for
+#[derive(Debug, PartialEq, Eq)]
+struct Policy {
+    after_days: u32,
+    raise: bool,
+    notify: Option<Channel>,
+}
+
+fn policy(config: &EscalationConfig) -> Result<Policy> {
+    let after_days = config.after_days.ok_or_else(|| {
+        TaskError::Config("Set after_days in the [escalation] section of the config file to escalate.".to_string())
+    })?;
+    if !config.raise_priority && config.notify.is_none() {
+        return Err(TaskError::Config(
+            "[escalation] neither raises priorities nor notifies anyone; set raise_priority or notify.".to_string(),
+        ));
+    }
+    if config.notify.is_some() && !cfg!(feature = "notifications") {
+        return Err(TaskError::not_built("notifications", "notifications"));
+    }
+    Ok(Policy { after_days, raise: config.raise_priority, notify: config.notify })
+}
+
+// One step up; no priority counts as below low, so it becomes medium as low does
+fn raised(priority: Option<Priority>) -> Priority {
+    match priority {
+        None | Some(Priority::Low) => Priority::Medium,
+        Some(Priority::Medium | Priority::High) => Priority::High,
+    }
+}
+
+// `task escalate run`, and the daemon's job
+pub async fn escalate(repo: &TaskRepository, config: &Config, dry_run: bool) -> Result<()> {
+    let policy = policy(&config.escalation)?;
+    let now = Local::now().naive_local();
+    let channel = policy.notify.map(notify::channel_key);
+
+    let mut escalated = 0;
+    for task in repo.unescalated_overdue(now - Duration::days(policy.after_days.into())).await? {
+        let Some(due_at) = task.due_at else { continue };
+        let overdue = (now - due_at).num_days();
+        let raise_to = policy.raise.then(|| raised(task.priority));
+        if dry_run {
+            let raise = raise_to.map(|priority| format!(", to {} priority", priority)).unwrap_or_default();
+            println!("Would escalate task {} ({}), {} overdue{}", task.id, task.description, days(overdue), raise);
+            continue;
+        }
+        if repo.escalate(task.id, due_at, overdue, raise_to, channel).await? {
+            escalated += 1;
+        }
+    }
+    if dry_run {
+        return Ok(());
+    }
+
+    let told = notify_escalations(repo, config).await?;
+    match channel {
+        Some(channel) => println!("Escalated {} overdue task(s); told {} about {}.", escalated, channel, told),
+        None => println!("Escalated {} overdue task(s).", escalated),
+    }
+    Ok(())
+}
+
+// Tells the channel of each escalation that is yet to be told. Returns how many were told.
+#[cfg(feature = "notifications")]
+async fn notify_escalations(repo: &TaskRepository, config: &Config) -> Result<u32> {
+    use clap::ValueEnum;
+
+    let template = config.escalation.message.as_deref().unwrap_or(DEFAULT_MESSAGE);
+    let mut told = 0;
+    for escalation in repo.unnotified_escalations().await? {
+        let channel = escalation.channel.as_deref().and_then(|channel| Channel::from_str(channel, true).ok());
+        let (Some(channel), Some(task)) = (channel, repo.get(escalation.task_id).await?) else {
+            continue;
+        };
+        let mut values = notify::task_values(repo, &task).await?;
+        values.push(("days", escalation.days_overdue.to_string()));
+        let text = notify::render(template, &values);
+        let subject = format!("Overdue for {}: {}", days(escalation.days_overdue.into()), task.description);
+        let event = notify::Event::Escalation;
+        match notify::send_message(config, channel, event, task.priority, &subject, &text).await {
+            Ok(true) => {
+                repo.mark_escalation_notified(task.id, escalation.due_at).await?;
+                told += 1;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                let channel = notify::channel_key(channel);
+                println!("Warning: could not tell {} about task {}: {}", channel, task.id, e);
+            }
+        }
+    }
+    Ok(told)
+}
+
+// `policy` refuses a channel to tell in builds without notifications
+#[cfg(not(feature = "notifications"))]
+async fn notify_escalations(_repo: &TaskRepository, _config: &Config) -> Result<u32> {
+    Ok(0)
+}
+
+async fn log(repo: &TaskRepository, limit: u32) -> Result<()> {
+    let escalations = repo.escalations(limit).await?;
+    if escalations.is_empty() {
+        println!("No escalations yet.");
+        return Ok(());
+    }
+    for escalation in &escalations {
+        println!("{}", format_escalation(escalation));
+    }
+    Ok(())
+}
+
+// E.g. "2026-03-10 09:00  task 12 (Write report), 4 days overdue: priority medium to high, told slack"
+fn format_escalation(escalation: &Escalation) -> String {
+    let name = |priority: Option<Priority>| priority.map_or("none", Priority::as_str);
+    let mut done = Vec::new();
+    if escalation.new_priority != escalation.old_priority {
+        done.push(format!("priority {} to {}", name(escalation.old_priority), name(escalation.new_priority)));
+    }
+    match (&escalation.channel, escalation.notified_at) {
+        (Some(channel), Some(_)) => done.push(format!("told {}", channel)),
+        (Some(channel), None) => done.push(format!("{} not told yet", channel)),
+        (None, _) => {}
+    }
+    if done.is_empty() {
+        done.push("priority left as it was".to_string());
+    }
+    format!(
+        "{}  task {} ({}), {} overdue: {}",
+        escalation.escalated_at.format("%Y-%m-%d %H:%M"),
+        escalation.task_id,
+        escalation.description,
+        days(escalation.days_overdue.into()),
+        done.join(", ")
+    )
+}
+
+fn days(days: i64) -> String {
+    format!("{} day{}", days, if days == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::at;
+
+    #[test]
+    fn priorities_go_up_a_step() {
+        assert_eq!(raised(None), Priority::Medium);
+        assert_eq!(raised(Some(Priority::Low)), Priority::Medium);
+        assert_eq!(raised(Some(Priority::Medium)), Priority::High);
+        assert_eq!(raised(Some(Priority::High)), Priority::High);
+    }
+
+    #[test]
+    fn a_policy_needs_a_threshold_and_something_to_do() {
+        let config = EscalationConfig { after_days: Some(3), raise_priority: true, ..Default::default() };
+        assert_eq!(policy(&config).unwrap(), Policy { after_days: 3, raise: true, notify: None });
+        assert!(policy(&EscalationConfig { after_days: None, ..config.clone() }).is_err());
+        let idle = policy(&EscalationConfig { raise_priority: false, ..config }).unwrap_err();
+        assert!(idle.to_string().contains("set raise_priority or notify"), "{}", idle);
+    }
+
+    #[test]
+    fn the_log_says_what_was_done() {
+        let mut escalation = Escalation {
+            task_id: 12,
+            description: "Write report".to_string(),
+            due_at: at(6),
+            escalated_at: at(10),
+            days_overdue: 4,
+            old_priority: Some(Priority::Medium),
+            new_priority: Some(Priority::High),
+            channel: Some("slack".to_string()),
+            notified_at: Some(at(10)),
+        };
+        assert_eq!(
+            format_escalation(&escalation),
+            "2026-03-10 09:00  task 12 (Write report), 4 days overdue: priority medium to high, told slack"
+        );
+        escalation.new_priority = escalation.old_priority;
+        escalation.notified_at = None;
+        assert!(format_escalation(&escalation).ends_with("overdue: slack not told yet"));
+        escalation.channel = None;
+        escalation.days_overdue = 1;
+        assert!(format_escalation(&escalation).ends_with("1 day overdue: priority left as it was"));
+    }
+}
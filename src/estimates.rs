@@ -0,0 +1,63 @@
+// `task estimate`: how long a task is expected to take, which the breakdown and forecast
+// reports add up.
+
+use crate::error::{Result, TaskError};
+use crate::repository::TaskRepository;
+use crate::timesheet::format_duration;
+
+pub async fn run(repo: &TaskRepository, id: i32, duration: Option<&str>) -> Result<()> {
+    let minutes = duration.map(parse_minutes).transpose()?;
+    if !repo.set_estimate(id, minutes).await? {
+        return Err(TaskError::InvalidInput(format!("You can't change a task with ID {}.", id)));
+    }
+    match minutes {
+        Some(minutes) => println!("Task {} is estimated at {}.", id, format_duration(i64::from(minutes) * 60)),
+        None => println!("Removed the estimate of task {}.", id),
+    }
+    Ok(())
+}
+
+// "90", "90m", "2h", "1.5h" or "1h30m", in minutes
+pub fn parse_minutes(duration: &str) -> Result<u32> {
+    let invalid = || {
+        TaskError::InvalidInput(format!(
+            "'{}' is not a duration; use minutes or hours, e.g. 45m, 2h or 1h30m.",
+            duration.trim()
+        ))
+    };
+    let text = duration.trim().to_lowercase();
+    let (hours, minutes) = match text.split_once('h') {
+        Some((hours, minutes)) => (hours, minutes),
+        None => ("", text.as_str()),
+    };
+    let minutes = minutes.trim().trim_end_matches("min").trim_end_matches('m').trim();
+    let hours: f64 = if hours.is_empty() { 0.0 } else { hours.trim().parse().map_err(|_| invalid())? };
+    let minutes: f64 = if minutes.is_empty() { 0.0 } else { minutes.parse().map_err(|_| invalid())? };
+    let total = (hours * 60.0 + minutes).round();
+    if !total.is_finite() || total <= 0.0 || total > f64::from(u32::MAX) {
+        return Err(invalid());
+    }
+    Ok(total as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_are_read_in_minutes_or_hours() {
+        assert_eq!(parse_minutes("90").unwrap(), 90);
+        assert_eq!(parse_minutes("45m").unwrap(), 45);
+        assert_eq!(parse_minutes("45 min").unwrap(), 45);
+        assert_eq!(parse_minutes("2h").unwrap(), 120);
+        assert_eq!(parse_minutes("1.5h").unwrap(), 90);
+        assert_eq!(parse_minutes(" 1H30m ").unwrap(), 90);
+    }
+
+    #[test]
+    fn nonsense_and_nothing_are_refused() {
+        for duration in ["", "0", "soon", "h", "-2h", "1h30x"] {
+            assert!(parse_minutes(duration).is_err(), "{:?}", duration);
+        }
+    }
+}
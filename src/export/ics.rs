@@ -0,0 +1,176 @@
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{Days, NaiveDateTime, NaiveTime, Utc};
+use futures::TryStreamExt;
+
+use crate::error::Result;
+use crate::priority::Priority;
+use crate::repository::{readable, task_columns, BindAccess, TaskRepository};
+use crate::Task;
+
+// RFC 5545 limits content lines to 75 octets; longer ones are folded onto continuation lines
+const MAX_LINE_OCTETS: usize = 75;
+
+// Tasks with a due date as an iCalendar file. By default each becomes a VTODO, which task-aware
+// calendar apps show in their to-do list; with `events` they become VEVENTs on the due date
+// instead, for apps that only display events.
+pub async fn run(repo: &TaskRepository, path: Option<&Path>, events: bool) -> Result<()> {
+    let pool = repo.pool();
+    let mut tags = super::load_tags(pool).await?;
+    let mut out = super::open_output(path)?;
+
+    let stamp = timestamp();
+    let host = hostname();
+
+    write_calendar_header(&mut out)?;
+
+    let mut tasks = sqlx::query_as::<_, Task>(concat!(
+        "SELECT ", task_columns!(), " FROM tasks WHERE due_at IS NOT NULL AND ", readable!(), " ORDER BY due_at, id"
+    ))
+    .bind_access(repo.access())
+    .fetch(pool);
+
+    let mut count: u64 = 0;
+    while let Some(task) = tasks.try_next().await? {
+        let tags = tags.remove(&task.id).unwrap_or_default();
+        write_component(&mut out, &task, &default_uid(task.id, &host), &tags, &stamp, events)?;
+        count += 1;
+    }
+
+    write_line(&mut out, "END:VCALENDAR")?;
+    out.flush()?;
+
+    if let Some(path) = path {
+        println!("Exported {} tasks with due dates to {}", count, path.display());
+    }
+    Ok(())
+}
+
+pub fn write_calendar_header(out: &mut impl Write) -> std::io::Result<()> {
+    write_line(out, "BEGIN:VCALENDAR")?;
+    write_line(out, "VERSION:2.0")?;
+    write_line(out, "PRODID:-//task//Task CLI//EN")?;
+    write_line(out, "CALSCALE:GREGORIAN")
+}
+
+// One task as a VTODO, or as a VEVENT on its due date with `events` (tasks without a due
+// date can't be events and must not be passed then). `stamp` is the DTSTAMP, from `timestamp`.
+pub fn write_component(
+    out: &mut impl Write,
+    task: &Task,
+    uid: &str,
+    tags: &[String],
+    stamp: &str,
+    events: bool,
+) -> std::io::Result<()> {
+    let component = if events { "VEVENT" } else { "VTODO" };
+
+    write_line(out, &format!("BEGIN:{}", component))?;
+    write_line(out, &format!("UID:{}", uid))?;
+    write_line(out, &format!("DTSTAMP:{}", stamp))?;
+    write_line(out, &format!("CREATED:{}", local_time(&task.created_at)))?;
+    write_line(out, &format!("LAST-MODIFIED:{}", local_time(&task.updated_at)))?;
+    write_line(out, &format!("SUMMARY:{}", escape_text(&task.description)))?;
+
+    if events {
+        if let Some(due_at) = &task.due_at {
+            write_line(out, &format!("DTSTART{}", date_or_time(due_at)))?;
+            // All-day events end (exclusively) on the next day
+            if due_at.time() == NaiveTime::MIN
+                && let Some(next_day) = due_at.checked_add_days(Days::new(1))
+            {
+                write_line(out, &format!("DTEND{}", date_or_time(&next_day)))?;
+            }
+        }
+    } else {
+        if let Some(due_at) = &task.due_at {
+            write_line(out, &format!("DUE{}", date_or_time(due_at)))?;
+        }
+        write_line(out, if task.completed { "STATUS:COMPLETED" } else { "STATUS:NEEDS-ACTION" })?;
+    }
+
+    if let Some(priority) = task.priority {
+        write_line(out, &format!("PRIORITY:{}", ical_priority(priority)))?;
+    }
+    if !tags.is_empty() {
+        let categories: Vec<String> = tags.iter().map(|tag| escape_text(tag)).collect();
+        write_line(out, &format!("CATEGORIES:{}", categories.join(",")))?;
+    }
+
+    write_line(out, &format!("END:{}", component))
+}
+
+// Stable per database and host, so re-importing a file updates entries instead of duplicating them
+pub fn default_uid(task_id: i32, host: &str) -> String {
+    format!("task-{}@{}", task_id, host)
+}
+
+pub fn hostname() -> String {
+    whoami::fallible::hostname().unwrap_or_else(|_| "localhost".to_string())
+}
+
+// Current time in UTC, as used for DTSTAMP
+pub fn timestamp() -> String {
+    Utc::now().format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// Due dates without a time become all-day values. Times are written as "floating" local
+// times, which is how they are stored.
+fn date_or_time(value: &NaiveDateTime) -> String {
+    if value.time() == NaiveTime::MIN {
+        format!(";VALUE=DATE:{}", value.format("%Y%m%d"))
+    } else {
+        format!(":{}", local_time(value))
+    }
+}
+
+fn local_time(value: &NaiveDateTime) -> String {
+    value.format("%Y%m%dT%H%M%S").to_string()
+}
+
+// iCalendar priorities run from 1 (highest) to 9 (lowest)
+pub fn ical_priority(priority: Priority) -> u8 {
+    match priority {
+        Priority::High => 1,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | ';' | ',' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Writes one content line with CRLF, folding it without splitting UTF-8 characters
+pub fn write_line(out: &mut impl Write, line: &str) -> std::io::Result<()> {
+    let mut rest = line;
+    let mut limit = MAX_LINE_OCTETS;
+
+    while rest.len() > limit {
+        let mut split = limit;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        out.write_all(&rest.as_bytes()[..split])?;
+        out.write_all(b"\r\n ")?;
+        rest = &rest[split..];
+        // Continuation lines start with a space, which counts towards their length
+        limit = MAX_LINE_OCTETS - 1;
+    }
+
+    out.write_all(rest.as_bytes())?;
+    out.write_all(b"\r\n")
+}
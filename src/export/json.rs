@@ -0,0 +1,113 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::priority::Priority;
+use crate::repository::{readable, task_columns, BindAccess, ChecklistItem, Note, TaskRepository};
+use crate::Task;
+
+pub const EXPORT_FORMAT: &str = "task-export";
+// Bump only for changes old readers can't ignore; new optional fields don't need it
+pub const EXPORT_VERSION: u32 = 1;
+
+// The exchange format. Unlike a backup it contains no database ids: projects and tags are
+// referenced by name, so the file can be imported into any database, and one task per line
+// keeps snapshots under version control readable in diffs.
+//
+//   {"format":"task-export","version":1,"exported_at":"...","tasks":[
+//   {"description":"...","completed":false,...,"project":"home","tags":["a"],"checklist":[...]},
+//   ...
+//   ]}
+#[derive(Debug, Deserialize)]
+pub struct Document {
+    pub format: String,
+    pub version: u32,
+    pub tasks: Vec<ExportTask>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportTask {
+    pub description: String,
+    #[serde(default)]
+    pub completed: bool,
+    pub created_at: NaiveDateTime,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_at: Option<NaiveDateTime>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Priority>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checklist: Vec<ChecklistItem>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<Note>,
+}
+
+// Tags, checklists, notes and project names are loaded up front, keyed by task; the tasks themselves
+// are streamed, oldest first so repeated exports only grow at the end.
+pub async fn run(repo: &TaskRepository, path: Option<&Path>) -> Result<()> {
+    let pool = repo.pool();
+    let projects = super::load_project_names(pool).await?;
+
+    let mut tags = super::load_tags(pool).await?;
+    let mut checklists = super::load_checklists(pool).await?;
+    let mut notes = super::load_notes(pool).await?;
+
+    let mut out = super::open_output(path)?;
+    write!(
+        out,
+        "{{\"format\":{},\"version\":{},\"exported_at\":{},\"tasks\":[",
+        json(&EXPORT_FORMAT)?,
+        EXPORT_VERSION,
+        json(&chrono::Local::now().to_rfc3339())?
+    )?;
+
+    let mut tasks =
+        sqlx::query_as::<_, Task>(concat!("SELECT ", task_columns!(), " FROM tasks WHERE ", readable!(), " ORDER BY id"))
+            .bind_access(repo.access())
+            .fetch(pool);
+    let mut count: u64 = 0;
+    while let Some(task) = tasks.try_next().await? {
+        let entry = ExportTask {
+            project: task.project_id.and_then(|id| projects.get(&id).cloned()),
+            tags: tags.remove(&task.id).unwrap_or_default(),
+            checklist: checklists.remove(&task.id).unwrap_or_default(),
+            notes: notes.remove(&task.id).unwrap_or_default(),
+            description: task.description,
+            completed: task.completed,
+            created_at: task.created_at,
+            created_by: task.created_by,
+            updated_at: Some(task.updated_at),
+            updated_by: task.updated_by,
+            due_at: task.due_at,
+            priority: task.priority,
+        };
+
+        out.write_all(if count > 0 { b",\n" } else { b"\n" })?;
+        serde_json::to_writer(&mut out, &entry).map_err(io::Error::from)?;
+        count += 1;
+    }
+    writeln!(out, "\n]}}")?;
+    out.flush()?;
+
+    if let Some(path) = path {
+        println!("Exported {} tasks to {}", count, path.display());
+    }
+    Ok(())
+}
+
+fn json<T: Serialize>(value: &T) -> io::Result<String> {
+    serde_json::to_string(value).map_err(io::Error::from)
+}
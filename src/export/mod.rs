@@ -0,0 +1,97 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use sqlx::MySqlPool;
+
+use crate::error::{Result, TaskError};
+use crate::repository::{ChecklistItem, Note};
+
+pub mod ics;
+pub mod json;
+pub mod org;
+pub mod report;
+pub mod todotxt;
+pub mod xlsx;
+
+// Where an export goes: the given file, or stdout so it can be piped
+pub fn open_output(path: Option<&Path>) -> Result<Box<dyn Write>> {
+    match path {
+        Some(path) => {
+            let file = File::create(path).map_err(|e| {
+                TaskError::InvalidInput(format!("Could not create {}: {}", path.display(), e))
+            })?;
+            Ok(Box::new(BufWriter::new(file)))
+        }
+        None => Ok(Box::new(BufWriter::new(io::stdout().lock()))),
+    }
+}
+
+pub async fn load_project_names(pool: &MySqlPool) -> Result<HashMap<i32, String>> {
+    let rows = sqlx::query_as::<_, (i32, String)>("SELECT id, name FROM projects").fetch_all(pool).await?;
+    Ok(rows.into_iter().collect())
+}
+
+// Tag names of every task that has any, alphabetically, keyed by task id
+pub async fn load_tags(pool: &MySqlPool) -> Result<HashMap<i32, Vec<String>>> {
+    let rows = sqlx::query_as::<_, (i32, String)>(
+        "SELECT task_tags.task_id, tags.name FROM task_tags JOIN tags ON tags.id = task_tags.tag_id \
+         ORDER BY task_tags.task_id, tags.name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut tags: HashMap<i32, Vec<String>> = HashMap::new();
+    for (task_id, name) in rows {
+        tags.entry(task_id).or_default().push(name);
+    }
+    Ok(tags)
+}
+
+#[derive(sqlx::FromRow)]
+struct TaskChecklistItem {
+    task_id: i32,
+    text: String,
+    done: bool,
+}
+
+// Checklist of every task that has one, in order, keyed by task id
+pub async fn load_checklists(pool: &MySqlPool) -> Result<HashMap<i32, Vec<ChecklistItem>>> {
+    let rows = sqlx::query_as::<_, TaskChecklistItem>(
+        "SELECT task_id, text, done FROM checklist_items ORDER BY task_id, position, id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut checklists: HashMap<i32, Vec<ChecklistItem>> = HashMap::new();
+    for item in rows {
+        checklists.entry(item.task_id).or_default().push(ChecklistItem { text: item.text, done: item.done });
+    }
+    Ok(checklists)
+}
+
+#[derive(sqlx::FromRow)]
+struct TaskNote {
+    task_id: i32,
+    created_at: NaiveDateTime,
+    body: String,
+    created_by: Option<String>,
+}
+
+// Notes of every task that has any, oldest first, keyed by task id
+pub async fn load_notes(pool: &MySqlPool) -> Result<HashMap<i32, Vec<Note>>> {
+    let rows = sqlx::query_as::<_, TaskNote>(
+        "SELECT task_id, created_at, body, created_by FROM task_notes ORDER BY task_id, created_at, id",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut notes: HashMap<i32, Vec<Note>> = HashMap::new();
+    for note in rows {
+        let (created_at, body, created_by) = (note.created_at, note.body, note.created_by);
+        notes.entry(note.task_id).or_default().push(Note { created_at, body, created_by });
+    }
+    Ok(notes)
+}
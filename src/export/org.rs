@@ -0,0 +1,141 @@
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{NaiveDateTime, NaiveTime};
+
+use crate::error::Result;
+use crate::priority::Priority;
+use crate::repository::{readable, task_columns, BindAccess, TaskRepository};
+use crate::Task;
+
+// Heading for tasks without a project
+const INBOX: &str = "Inbox";
+
+#[derive(sqlx::FromRow)]
+struct OrgTask {
+    #[sqlx(flatten)]
+    task: Task,
+    closed_at: Option<NaiveDateTime>,
+}
+
+// Every task as an Org heading under its project, for Emacs' agenda:
+//
+//   * Work
+//   ** TODO [#A] Draft quarterly report [1/3]                         :office:
+//      DEADLINE: <2026-10-20 Tue 14:00>
+//      :PROPERTIES:
+//      :TASK_ID: 12
+//      :CREATED: [2026-10-01 Thu 09:00]
+//      :END:
+//      - [X] Collect numbers
+//
+// The due date becomes the DEADLINE; there are no start dates here to make a SCHEDULED from.
+// Body lines are indented so that notes starting with '*' can't turn into headings.
+pub async fn run(repo: &TaskRepository, path: Option<&Path>) -> Result<()> {
+    let pool = repo.pool();
+    let projects = super::load_project_names(pool).await?;
+    let mut tags = super::load_tags(pool).await?;
+    let mut checklists = super::load_checklists(pool).await?;
+    let mut notes = super::load_notes(pool).await?;
+
+    let tasks = sqlx::query_as::<_, OrgTask>(concat!(
+        "SELECT ", task_columns!(), ", IF(completed, COALESCE(completed_at, updated_at), NULL) AS closed_at ",
+        "FROM tasks WHERE ", readable!(), " ORDER BY completed, due_at IS NULL, due_at, id"
+    ))
+    .bind_access(repo.access())
+    .fetch_all(pool)
+    .await?;
+    let count = tasks.len();
+
+    // Projects by name, then the tasks without one
+    let mut by_project: BTreeMap<(bool, Option<&str>), Vec<OrgTask>> = BTreeMap::new();
+    for task in tasks {
+        let project = task.task.project_id.and_then(|id| projects.get(&id)).map(String::as_str);
+        by_project.entry((project.is_none(), project)).or_default().push(task);
+    }
+
+    let mut out = super::open_output(path)?;
+    writeln!(out, "#+TITLE: Tasks")?;
+    writeln!(out, "#+TODO: TODO | DONE")?;
+    for ((_, project), tasks) in &by_project {
+        writeln!(out, "\n* {}", one_line(project.unwrap_or(INBOX)))?;
+        for OrgTask { task, closed_at } in tasks {
+            let checklist = checklists.remove(&task.id).unwrap_or_default();
+            let mut heading = format!("** {}", if task.completed { "DONE" } else { "TODO" });
+            if let Some(priority) = task.priority {
+                heading.push_str(&format!(" [#{}]", letter(priority)));
+            }
+            heading.push(' ');
+            heading.push_str(&one_line(&task.description));
+            if !checklist.is_empty() {
+                let done = checklist.iter().filter(|item| item.done).count();
+                heading.push_str(&format!(" [{}/{}]", done, checklist.len()));
+            }
+            let tags: Vec<String> = tags.remove(&task.id).unwrap_or_default().iter().map(|tag| tag_word(tag)).collect();
+            if !tags.is_empty() {
+                heading.push_str(&format!(" :{}:", tags.join(":")));
+            }
+            writeln!(out, "{}", heading)?;
+
+            let mut planning = Vec::new();
+            if let Some(closed_at) = closed_at {
+                planning.push(format!("CLOSED: {}", timestamp(closed_at, false)));
+            }
+            if let Some(due_at) = &task.due_at {
+                planning.push(format!("DEADLINE: {}", timestamp(due_at, true)));
+            }
+            if !planning.is_empty() {
+                writeln!(out, "   {}", planning.join(" "))?;
+            }
+            writeln!(out, "   :PROPERTIES:")?;
+            writeln!(out, "   :TASK_ID: {}", task.id)?;
+            writeln!(out, "   :CREATED: {}", timestamp(&task.created_at, false))?;
+            writeln!(out, "   :END:")?;
+
+            for item in &checklist {
+                writeln!(out, "   - [{}] {}", if item.done { "X" } else { " " }, one_line(&item.text))?;
+            }
+            for note in notes.remove(&task.id).unwrap_or_default() {
+                writeln!(out, "   {}", timestamp(&note.created_at, false))?;
+                for line in note.body.lines() {
+                    writeln!(out, "   {}", line.trim_end())?;
+                }
+            }
+        }
+    }
+    out.flush()?;
+
+    if let Some(path) = path {
+        println!("Exported {} tasks to {}", count, path.display());
+    }
+    Ok(())
+}
+
+fn letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+// Active timestamps (<...>) show up in the agenda, inactive ones ([...]) don't. Midnight means
+// a date without a time, as elsewhere.
+fn timestamp(time: &NaiveDateTime, active: bool) -> String {
+    let inner = if time.time() == NaiveTime::MIN {
+        time.format("%Y-%m-%d %a").to_string()
+    } else {
+        time.format("%Y-%m-%d %a %H:%M").to_string()
+    };
+    if active { format!("<{}>", inner) } else { format!("[{}]", inner) }
+}
+
+// Org tags may only contain letters, digits, '_', '@', '#' and '%'
+fn tag_word(tag: &str) -> String {
+    tag.chars().map(|c| if c.is_alphanumeric() || "_@#%".contains(c) { c } else { '_' }).collect()
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,370 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+use chrono::{Days, Local, NaiveDateTime, NaiveTime};
+use quick_xml::escape::escape;
+
+use crate::cli::ReportArgs;
+use crate::error::{Result, TaskError};
+use crate::import::parse_due;
+use crate::repository::{readable, task_columns, BindAccess, TaskRepository};
+use crate::Task;
+
+// Length of the default range, and how far past its end "coming up" looks
+const DEFAULT_DAYS: u64 = 7;
+
+// Converters tried in turn for PDF output. Each gets the HTML file and the PDF path.
+const PDF_CONVERTERS: [&str; 4] = ["wkhtmltopdf", "chromium", "chromium-browser", "google-chrome"];
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; color: #222; }
+body { margin: 2em auto; max-width: 60em; }
+h1 { margin-bottom: 0; }
+.range { color: #666; margin-top: 0.2em; }
+.stats { display: flex; gap: 1em; margin: 1.5em 0; }
+.stat { border: 1px solid #ddd; border-radius: 6px; padding: 0.8em 1.2em; flex: 1; }
+.stat .value { font-size: 1.8em; font-weight: bold; }
+.stat .label { color: #666; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2em; }
+th, td { text-align: left; padding: 0.35em 0.6em; border-bottom: 1px solid #eee; }
+th { background: #f5f5f5; }
+td.number { text-align: right; }
+.overdue { color: #b00020; }
+.empty { color: #888; font-style: italic; }
+";
+
+#[derive(sqlx::FromRow)]
+struct CompletedTask {
+    #[sqlx(flatten)]
+    task: Task,
+    done_at: NaiveDateTime,
+}
+
+// Per project (None for tasks without one); BTreeMap puts them in name order
+#[derive(Default)]
+struct ProjectStats {
+    created: u64,
+    completed: u64,
+    open: i64,
+}
+
+// What the report shows, as read from the database
+struct Report {
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+    completed: Vec<CompletedTask>,
+    created: Vec<Task>,
+    overdue: Vec<Task>,
+    upcoming: Vec<Task>,
+    // Open tasks per project
+    open: Vec<(Option<i32>, i64)>,
+    projects: HashMap<i32, String>,
+}
+
+// `task export report`: what was done and added in a time range, what is overdue at its end and
+// what comes up in the week after, as a self-contained HTML page; as PDF if the output file
+// ends in .pdf.
+pub async fn run(repo: &TaskRepository, args: ReportArgs) -> Result<()> {
+    let (pool, access) = (repo.pool(), repo.access());
+    let (from, to) = range(args.from.as_deref(), args.to.as_deref())?;
+    let upcoming_until = to + chrono::Duration::days(DEFAULT_DAYS as i64);
+    let projects = super::load_project_names(pool).await?;
+
+    let completed = sqlx::query_as::<_, CompletedTask>(concat!(
+        "SELECT ", task_columns!(), ", COALESCE(completed_at, updated_at) AS done_at FROM tasks ",
+        "WHERE completed = TRUE AND COALESCE(completed_at, updated_at) >= ? ",
+        "AND COALESCE(completed_at, updated_at) < ? AND ", readable!(), " ORDER BY done_at, id"
+    ))
+    .bind(from)
+    .bind(to)
+    .bind_access(access)
+    .fetch_all(pool)
+    .await?;
+    let created = sqlx::query_as::<_, Task>(concat!(
+        "SELECT ", task_columns!(), " FROM tasks WHERE created_at >= ? AND created_at < ? AND ", readable!(), " ",
+        "ORDER BY created_at, id"
+    ))
+    .bind(from)
+    .bind(to)
+    .bind_access(access)
+    .fetch_all(pool)
+    .await?;
+    let pending_due = |since: Option<NaiveDateTime>, until: NaiveDateTime| {
+        sqlx::query_as::<_, Task>(concat!(
+            "SELECT ", task_columns!(), " FROM tasks ",
+            "WHERE completed = FALSE AND due_at IS NOT NULL AND (? IS NULL OR due_at >= ?) AND due_at < ? ",
+            "AND ", readable!(), " ORDER BY due_at, id"
+        ))
+        .bind(since)
+        .bind(since)
+        .bind(until)
+        .bind_access(access)
+        .fetch_all(pool)
+    };
+    let overdue = pending_due(None, to).await?;
+    let upcoming = pending_due(Some(to), upcoming_until).await?;
+    let open = sqlx::query_as::<_, (Option<i32>, i64)>(concat!(
+        "SELECT project_id, COUNT(*) FROM tasks WHERE completed = FALSE AND ", readable!(), " GROUP BY project_id"
+    ))
+    .bind_access(access)
+    .fetch_all(pool)
+    .await?;
+
+    let report = Report { from, to, completed, created, overdue, upcoming, open, projects };
+    let html = render(&report, Local::now().naive_local());
+
+    match args.output.as_deref() {
+        Some(path) if is_pdf(path) => write_pdf(&html, path)?,
+        path => {
+            let mut out = super::open_output(path)?;
+            out.write_all(html.as_bytes())?;
+            out.flush()?;
+        }
+    }
+    if let Some(path) = &args.output {
+        println!("Wrote the report for {} to {} to {}", from.format("%Y-%m-%d"), to.format("%Y-%m-%d"), path.display());
+    }
+    Ok(())
+}
+
+// The page for `report`, generated at `generated`
+fn render(report: &Report, generated: NaiveDateTime) -> String {
+    let Report { from, to, completed, created, overdue, upcoming, open, projects } = report;
+    let project_name = |id: Option<i32>| id.and_then(|id| projects.get(&id).cloned());
+    let mut by_project: BTreeMap<Option<String>, ProjectStats> = BTreeMap::new();
+    for task in created {
+        by_project.entry(project_name(task.project_id)).or_default().created += 1;
+    }
+    for done in completed {
+        by_project.entry(project_name(done.task.project_id)).or_default().completed += 1;
+    }
+    for &(project_id, count) in open {
+        by_project.entry(project_name(project_id)).or_default().open = count;
+    }
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Task report</title>\n\
+         <style>{}</style>\n</head>\n<body>\n<h1>Task report</h1>\n<p class=\"range\">{} to {}</p>\n",
+        STYLE,
+        from.format("%Y-%m-%d %H:%M"),
+        to.format("%Y-%m-%d %H:%M")
+    );
+
+    html.push_str("<div class=\"stats\">\n");
+    for (value, label) in [
+        (completed.len(), "completed"),
+        (created.len(), "added"),
+        (overdue.len(), "overdue at the end"),
+        (upcoming.len(), "due in the next week"),
+    ] {
+        let _ = writeln!(
+            html,
+            "<div class=\"stat\"><div class=\"value\">{}</div><div class=\"label\">{}</div></div>",
+            value, label
+        );
+    }
+    html.push_str("</div>\n");
+
+    html.push_str("<h2>By project</h2>\n");
+    if by_project.is_empty() {
+        html.push_str("<p class=\"empty\">No tasks.</p>\n");
+    } else {
+        html.push_str("<table>\n<tr><th>Project</th><th>Completed</th><th>Added</th><th>Open now</th></tr>\n");
+        for (name, stats) in &by_project {
+            let _ = writeln!(
+                html,
+                "<tr><td>{}</td><td class=\"number\">{}</td><td class=\"number\">{}</td>\
+                 <td class=\"number\">{}</td></tr>",
+                escape(name.as_deref().unwrap_or("(no project)")),
+                stats.completed,
+                stats.created,
+                stats.open
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    let rows: Vec<(String, &Task)> =
+        completed.iter().map(|done| (done.done_at.format("%Y-%m-%d").to_string(), &done.task)).collect();
+    push_table(&mut html, "Completed", "Completed on", &rows, projects, false);
+    let rows: Vec<(String, &Task)> = overdue.iter().map(|task| (due(task), task)).collect();
+    push_table(&mut html, "Overdue", "Due", &rows, projects, true);
+    let rows: Vec<(String, &Task)> = upcoming.iter().map(|task| (due(task), task)).collect();
+    push_table(&mut html, "Coming up", "Due", &rows, projects, false);
+    let rows: Vec<(String, &Task)> =
+        created.iter().map(|task| (task.created_at.format("%Y-%m-%d").to_string(), task)).collect();
+    push_table(&mut html, "Added", "Added on", &rows, projects, false);
+
+    let _ = write!(html, "<p class=\"range\">Generated {}</p>\n</body>\n</html>\n", generated.format("%Y-%m-%d %H:%M"));
+    html
+
+}
+
+// A date alone means the whole day, so `--from 2026-10-05 --to 2026-10-11` covers a week
+pub(crate) fn range(from: Option<&str>, to: Option<&str>) -> Result<(NaiveDateTime, NaiveDateTime)> {
+    let parse = |value: &str, end: bool| -> Result<NaiveDateTime> {
+        let time = parse_due(value).map_err(TaskError::InvalidInput)?;
+        let date_only = !value.trim().contains([' ', 'T']);
+        Ok(if end && date_only { time + Days::new(1) } else { time })
+    };
+    let to = match to {
+        Some(to) => parse(to, true)?,
+        None => Local::now().naive_local(),
+    };
+    let from = match from {
+        Some(from) => parse(from, false)?,
+        None => (to.date() - Days::new(DEFAULT_DAYS)).and_time(NaiveTime::MIN),
+    };
+    if from >= to {
+        return Err(TaskError::InvalidInput("The report range must end after it starts.".to_string()));
+    }
+    Ok((from, to))
+}
+
+fn push_table(
+    html: &mut String,
+    title: &str,
+    date_header: &str,
+    rows: &[(String, &Task)],
+    projects: &HashMap<i32, String>,
+    overdue: bool,
+) {
+    let _ = writeln!(html, "<h2>{}</h2>", title);
+    if rows.is_empty() {
+        html.push_str("<p class=\"empty\">None.</p>\n");
+        return;
+    }
+    let _ = writeln!(html, "<table>\n<tr><th>{}</th><th>Task</th><th>Project</th><th>Priority</th></tr>", date_header);
+    let class = if overdue { " class=\"overdue\"" } else { "" };
+    for (date, task) in rows {
+        let project = task.project_id.and_then(|id| projects.get(&id)).map(String::as_str).unwrap_or("");
+        let priority = task.priority.map(|priority| priority.as_str()).unwrap_or("");
+        let _ = writeln!(
+            html,
+            "<tr><td{}>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            class,
+            date,
+            escape(task.description.as_str()),
+            escape(project),
+            priority
+        );
+    }
+    html.push_str("</table>\n");
+}
+
+fn due(task: &Task) -> String {
+    task.due_at.map(|due| due.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default()
+}
+
+fn is_pdf(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"))
+}
+
+// There is no PDF renderer in here; the page is printed by wkhtmltopdf or a headless Chromium,
+// whichever is installed
+fn write_pdf(html: &str, path: &Path) -> Result<()> {
+    let source = std::env::temp_dir().join(format!("task-report-{}.html", std::process::id()));
+    std::fs::write(&source, html)?;
+
+    let mut result = Err(TaskError::Config(format!(
+        "Writing PDF needs one of {} on the PATH. Write the report to an .html file instead and print it \
+         from a browser.",
+        PDF_CONVERTERS.join(", ")
+    )));
+    for converter in PDF_CONVERTERS {
+        let mut command = Command::new(converter);
+        if converter == "wkhtmltopdf" {
+            command.arg("--quiet").arg(&source).arg(path);
+        } else {
+            command
+                .args(["--headless", "--disable-gpu", "--no-pdf-header-footer"])
+                .arg(format!("--print-to-pdf={}", path.display()))
+                .arg(format!("file://{}", source.display()));
+        }
+        match command.output() {
+            Ok(output) if output.status.success() => {
+                result = Ok(());
+                break;
+            }
+            Ok(output) => {
+                result = Err(TaskError::Io(std::io::Error::other(format!(
+                    "{} could not write the PDF: {}",
+                    converter,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))));
+                break;
+            }
+            // Not installed; try the next one
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => {
+                result = Err(TaskError::Io(e));
+                break;
+            }
+        }
+    }
+    let _ = std::fs::remove_file(&source);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{at, at_time, task, LONG, MARKUP, UNICODE};
+    use crate::priority::Priority;
+
+    // A week with something in every section: added, done, overdue at its end and coming up
+    fn week() -> Report {
+        let mut overdue = task(3, "Renew the passport", 2);
+        overdue.due_at = Some(at(8));
+        overdue.priority = Some(Priority::High);
+        overdue.project_id = Some(1);
+        let mut unicode = task(4, UNICODE, 3);
+        unicode.due_at = Some(at_time(12, 0, 0));
+        unicode.project_id = Some(2);
+        let mut done = task(5, MARKUP, 4);
+        done.completed = true;
+
+        Report {
+            from: at_time(3, 0, 0),
+            to: at_time(10, 0, 0),
+            completed: vec![CompletedTask { task: done.clone(), done_at: at_time(9, 18, 30) }],
+            created: vec![task(2, LONG, 3), unicode.clone(), done],
+            overdue: vec![overdue],
+            upcoming: vec![unicode],
+            open: vec![(None, 2), (Some(1), 1), (Some(2), 1)],
+            projects: HashMap::from([(1, "Admin".to_string()), (2, "Reisen ✈".to_string())]),
+        }
+    }
+
+    #[test]
+    fn report_snapshot() {
+        insta::assert_snapshot!(render(&week(), at_time(10, 8, 15)));
+    }
+
+    #[test]
+    fn empty_report_snapshot() {
+        let report = Report {
+            from: at_time(3, 0, 0),
+            to: at_time(10, 0, 0),
+            completed: Vec::new(),
+            created: Vec::new(),
+            overdue: Vec::new(),
+            upcoming: Vec::new(),
+            open: Vec::new(),
+            projects: HashMap::new(),
+        };
+        insta::assert_snapshot!(render(&report, at_time(10, 8, 15)));
+    }
+
+    #[test]
+    fn markup_in_descriptions_is_escaped() {
+        let html = render(&week(), at(10));
+        assert!(html.contains("Fix &lt;b&gt;bold&lt;/b&gt; &amp; &quot;quoted&quot; titles"), "{}", html);
+        assert!(!html.contains("<b>bold</b>"));
+    }
+}
@@ -0,0 +1,91 @@
+use std::io::Write;
+use std::path::Path;
+
+use futures::TryStreamExt;
+
+use crate::error::Result;
+use crate::priority::Priority;
+use crate::repository::{readable, task_columns, BindAccess, TaskRepository};
+use crate::Task;
+
+// One task per line in the todo.txt format (https://github.com/todotxt/todo.txt):
+//
+//   x 2026-10-12 2026-10-01 Renew domain name +admin @online pri:A
+//   (B) 2026-10-03 Draft quarterly report +work @office due:2026-10-20
+//
+// Projects become +project and tags @context. Completed tasks carry their last change as the
+// completion date and keep their priority as a pri: tag, as the format recommends.
+pub async fn run(repo: &TaskRepository, path: Option<&Path>) -> Result<()> {
+    let mut out = super::open_output(path)?;
+    let count = write(repo, &mut out).await?;
+    out.flush()?;
+
+    if let Some(path) = path {
+        println!("Exported {} tasks to {}", count, path.display());
+    }
+    Ok(())
+}
+
+// Writes every task the repository's user can see, in id order, and returns how many there
+// were. Also the format of the git mirror, where the stable order keeps diffs small.
+pub async fn write(repo: &TaskRepository, out: &mut impl Write) -> Result<u64> {
+    let pool = repo.pool();
+    let projects = super::load_project_names(pool).await?;
+    let mut tags = super::load_tags(pool).await?;
+
+    let mut tasks =
+        sqlx::query_as::<_, Task>(concat!("SELECT ", task_columns!(), " FROM tasks WHERE ", readable!(), " ORDER BY id"))
+            .bind_access(repo.access())
+            .fetch(pool);
+    let mut count: u64 = 0;
+    while let Some(task) = tasks.try_next().await? {
+        let project = task.project_id.and_then(|id| projects.get(&id)).map(String::as_str);
+        let task_tags = tags.remove(&task.id).unwrap_or_default();
+        writeln!(out, "{}", format_line(&task, project, &task_tags))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+// One task as a todo.txt line, which import::todotxt::parse_line reads back
+pub fn format_line(task: &Task, project: Option<&str>, tags: &[String]) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    if task.completed {
+        parts.push("x".to_string());
+        parts.push(task.updated_at.format("%Y-%m-%d").to_string());
+    } else if let Some(priority) = task.priority {
+        parts.push(format!("({})", letter(priority)));
+    }
+    parts.push(task.created_at.format("%Y-%m-%d").to_string());
+    parts.push(task.description.split_whitespace().collect::<Vec<_>>().join(" "));
+
+    if let Some(project) = project {
+        parts.push(format!("+{}", word(project)));
+    }
+    for tag in tags {
+        parts.push(format!("@{}", word(tag)));
+    }
+    if let Some(due_at) = task.due_at {
+        parts.push(format!("due:{}", due_at.format("%Y-%m-%d")));
+    }
+    if task.completed
+        && let Some(priority) = task.priority
+    {
+        parts.push(format!("pri:{}", letter(priority)));
+    }
+    parts.join(" ")
+}
+
+fn letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+// Projects and contexts end at the first space, so spaces inside names become underscores
+fn word(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
@@ -0,0 +1,160 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+use std::path::Path;
+
+use rust_xlsxwriter::{ConditionalFormatFormula, Format, Workbook, Worksheet, XlsxError};
+
+use crate::error::{Result, TaskError};
+use crate::repository::{readable, task_columns, BindAccess, TaskRepository};
+use crate::Task;
+
+// Excel's limit on sheet names, which also may not contain any of INVALID_SHEET_CHARS
+const MAX_SHEET_NAME: usize = 31;
+const INVALID_SHEET_CHARS: [char; 7] = ['[', ']', ':', '*', '?', '/', '\\'];
+const NO_PROJECT: &str = "No project";
+
+const HEADERS: [(&str, f64); 8] = [
+    ("ID", 7.0),
+    ("Description", 50.0),
+    ("Status", 10.0),
+    ("Priority", 10.0),
+    ("Due", 17.0),
+    ("Tags", 25.0),
+    ("Created", 17.0),
+    ("Updated", 17.0),
+];
+// Relative to the first data row: Status in C, Due in E
+const OVERDUE_RULE: &str = "=AND($C2=\"Open\", $E2<>\"\", $E2<NOW())";
+
+// An .xlsx workbook with one sheet per project (projects by name, then tasks without one),
+// dates as real Excel dates so they sort and filter, and pending tasks past their due date
+// shown in red. The highlight is a formula against NOW(), so it stays right when the file is
+// opened days later.
+pub async fn run(repo: &TaskRepository, path: Option<&Path>) -> Result<()> {
+    let pool = repo.pool();
+    let projects = super::load_project_names(pool).await?;
+    let mut tags = super::load_tags(pool).await?;
+
+    let tasks = sqlx::query_as::<_, Task>(concat!(
+        "SELECT ", task_columns!(), " FROM tasks WHERE ", readable!(), " ORDER BY completed, due_at IS NULL, due_at, id"
+    ))
+    .bind_access(repo.access())
+    .fetch_all(pool)
+    .await?;
+    let count = tasks.len();
+
+    // None sorts first in a BTreeMap, but the sheet without a project goes last
+    let mut by_project: BTreeMap<(bool, Option<&str>), Vec<Task>> = BTreeMap::new();
+    for task in tasks {
+        let project = task.project_id.and_then(|id| projects.get(&id)).map(String::as_str);
+        by_project.entry((project.is_none(), project)).or_default().push(task);
+    }
+
+    let mut workbook = Workbook::new();
+    let formats = Formats::new();
+    let mut sheet_names = HashSet::new();
+    for ((_, project), tasks) in &by_project {
+        let name = sheet_name(project.unwrap_or(NO_PROJECT), &mut sheet_names);
+        let sheet = workbook.add_worksheet();
+        write_sheet(sheet, &name, tasks, &mut tags, &formats).map_err(xlsx_error)?;
+    }
+    if by_project.is_empty() {
+        // A workbook needs at least one sheet
+        write_sheet(workbook.add_worksheet(), NO_PROJECT, &[], &mut tags, &formats).map_err(xlsx_error)?;
+    }
+
+    let buffer = workbook.save_to_buffer().map_err(xlsx_error)?;
+    let mut out = super::open_output(path)?;
+    out.write_all(&buffer)?;
+    out.flush()?;
+
+    if let Some(path) = path {
+        println!("Exported {} tasks in {} sheets to {}", count, by_project.len().max(1), path.display());
+    }
+    Ok(())
+}
+
+struct Formats {
+    header: Format,
+    date: Format,
+    overdue: Format,
+}
+
+impl Formats {
+    fn new() -> Self {
+        Formats {
+            header: Format::new().set_bold().set_background_color("D9E1F2"),
+            date: Format::new().set_num_format("yyyy-mm-dd hh:mm"),
+            overdue: Format::new().set_font_color("9C0006").set_background_color("FFC7CE"),
+        }
+    }
+}
+
+fn write_sheet(
+    sheet: &mut Worksheet,
+    name: &str,
+    tasks: &[Task],
+    tags: &mut HashMap<i32, Vec<String>>,
+    formats: &Formats,
+) -> std::result::Result<(), XlsxError> {
+    sheet.set_name(name)?;
+    for (column, (header, width)) in HEADERS.iter().enumerate() {
+        let column = column as u16;
+        sheet.write_string_with_format(0, column, *header, &formats.header)?;
+        sheet.set_column_width(column, *width)?;
+    }
+    sheet.set_freeze_panes(1, 0)?;
+
+    for (index, task) in tasks.iter().enumerate() {
+        let row = index as u32 + 1;
+        sheet.write_number(row, 0, task.id)?;
+        sheet.write_string(row, 1, &task.description)?;
+        sheet.write_string(row, 2, if task.completed { "Done" } else { "Open" })?;
+        if let Some(priority) = task.priority {
+            sheet.write_string(row, 3, priority.as_str())?;
+        }
+        if let Some(due_at) = task.due_at {
+            sheet.write_datetime_with_format(row, 4, due_at, &formats.date)?;
+        }
+        if let Some(tags) = tags.remove(&task.id) {
+            sheet.write_string(row, 5, tags.join(", "))?;
+        }
+        sheet.write_datetime_with_format(row, 6, task.created_at, &formats.date)?;
+        sheet.write_datetime_with_format(row, 7, task.updated_at, &formats.date)?;
+    }
+
+    let last_column = HEADERS.len() as u16 - 1;
+    let last_row = tasks.len() as u32;
+    sheet.autofilter(0, 0, last_row, last_column)?;
+    if !tasks.is_empty() {
+        let overdue = ConditionalFormatFormula::new().set_rule(OVERDUE_RULE).set_format(&formats.overdue);
+        sheet.add_conditional_format(1, 0, last_row, last_column, &overdue)?;
+    }
+    Ok(())
+}
+
+// A valid sheet name for a project that differs from the ones already used; Excel compares
+// them ignoring case
+fn sheet_name(project: &str, used: &mut HashSet<String>) -> String {
+    let cleaned: String = project
+        .chars()
+        .map(|c| if INVALID_SHEET_CHARS.contains(&c) { '_' } else { c })
+        .collect::<String>()
+        .trim_matches('\'')
+        .trim()
+        .to_string();
+    let base = if cleaned.is_empty() { "Project".to_string() } else { cleaned };
+
+    let mut name: String = base.chars().take(MAX_SHEET_NAME).collect();
+    let mut number = 2;
+    while !used.insert(name.to_lowercase()) {
+        let suffix = format!(" ({})", number);
+        name = base.chars().take(MAX_SHEET_NAME - suffix.len()).collect::<String>() + suffix.as_str();
+        number += 1;
+    }
+    name
+}
+
+fn xlsx_error(e: XlsxError) -> TaskError {
+    TaskError::Io(std::io::Error::other(e))
+}
@@ -0,0 +1,114 @@
+// Tasks for the unit tests of the views and exports, and proptest strategies for the parsers'.
+// The tasks live in a fixed week of March 2026, so snapshots don't change with the day they
+// are taken.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use proptest::prelude::*;
+
+use crate::priority::Priority;
+use crate::repository::{NewTask, TaskBundle};
+use crate::Task;
+
+// Longer than any terminal is wide
+pub const LONG: &str = "Go through every receipt from the trip, match each one against the card statement, scan the \
+                        ones the accounting system is missing and file the expense report before the end of the \
+                        quarter so it is paid out with the April salary";
+
+// Accents, CJK, emoji and a combining mark
+pub const UNICODE: &str = "Café ☕ mit Zoë — 東京の予定 🗓️ (résumé, nai\u{308}ve)";
+
+// Characters that need escaping in HTML
+pub const MARKUP: &str = "Fix <b>bold</b> & \"quoted\" titles";
+
+// 9:00 on the given day of March 2026
+pub fn at(day: u32) -> NaiveDateTime {
+    at_time(day, 9, 0)
+}
+
+pub fn at_time(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2026, 3, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+}
+
+// A pending task created (and last changed) by alice on `day`
+pub fn task(id: i32, description: &str, day: u32) -> Task {
+    Task {
+        id,
+        description: description.to_string(),
+        completed: false,
+        created_at: at(day),
+        created_by: Some("alice".to_string()),
+        updated_by: Some("alice".to_string()),
+        updated_at: at(day),
+        due_at: None,
+        priority: None,
+        project_id: None,
+        owner_id: None,
+        assignee_id: None,
+        workspace_id: None,
+    }
+}
+
+// Like `task`, for importing into a `MemoryStore`, without an id of its own
+pub fn bundle(description: &str, day: u32) -> TaskBundle {
+    let task = task(0, description, day);
+    TaskBundle {
+        task: NewTask {
+            id: None,
+            description: task.description,
+            completed: task.completed,
+            created_at: task.created_at,
+            created_by: task.created_by,
+            updated_by: task.updated_by,
+            updated_at: task.updated_at,
+            completed_at: None,
+            due_at: None,
+            priority: None,
+            project_id: None,
+            owner_id: None,
+            assignee_id: None,
+            workspace_id: None,
+        },
+        tags: Vec::new(),
+        project: None,
+        checklist: Vec::new(),
+        notes: Vec::new(),
+        external: None,
+    }
+}
+
+// One of each kind of task the snapshots cover, oldest first: a plain one, a long one, one in
+// unicode, one with markup, one due on the 8th (overdue by the end of the week, the 10th) and
+// one done.
+pub fn mixed() -> Vec<TaskBundle> {
+    let mut overdue = bundle("Renew the passport", 5);
+    overdue.task.due_at = Some(at(8));
+    overdue.task.priority = Some(Priority::High);
+    overdue.project = Some("Admin".to_string());
+    overdue.tags = vec!["errand".to_string()];
+
+    let mut done = bundle("Water the plants", 6);
+    done.task.completed = true;
+    done.task.updated_at = at_time(9, 18, 30);
+    done.task.updated_by = Some("bob".to_string());
+
+    let mut unicode = bundle(UNICODE, 3);
+    unicode.task.due_at = Some(at_time(12, 0, 0));
+    unicode.project = Some("Reisen ✈".to_string());
+
+    vec![bundle("Buy milk", 1), bundle(LONG, 2), unicode, bundle(MARKUP, 4), overdue, done]
+}
+
+// Dates the parsers are tried on; days stop at 28 so every month has them
+pub fn date() -> impl Strategy<Value = NaiveDate> {
+    (1970i32..2100, 1u32..=12, 1u32..=28)
+        .prop_map(|(year, month, day)| NaiveDate::from_ymd_opt(year, month, day).unwrap())
+}
+
+pub fn date_time() -> impl Strategy<Value = NaiveDateTime> {
+    (date(), 0u32..24, 0u32..60, 0u32..60)
+        .prop_map(|(date, hour, minute, second)| date.and_time(NaiveTime::from_hms_opt(hour, minute, second).unwrap()))
+}
+
+pub fn priority() -> impl Strategy<Value = Priority> {
+    prop_oneof![Just(Priority::Low), Just(Priority::Medium), Just(Priority::High)]
+}
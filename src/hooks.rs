@@ -0,0 +1,187 @@
+// Lifecycle hooks: commands from [hooks] and plugins registered in code that hear when a task
+// is added, completed or deleted, next to the webhooks of webhooks.rs. A command runs through
+// the shell with the webhook body on stdin, and TASK_EVENT and TASK_ID in its environment; a
+// plugin is a `Plugin` that a program built on taskcore hands to `Webhooks::with_plugin`. The
+// change is already made when either runs, so a failing one is only reported.
+
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::webhooks::WebhookEvent;
+use crate::Task;
+
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+// Something built into a program that uses taskcore and wants to hear about task changes
+pub trait Plugin: Send + Sync {
+    // For warnings, e.g. "audit log"
+    fn name(&self) -> &str;
+
+    // `task` is the task after the event, None for `Deleted`
+    fn on_event<'a>(&'a self, event: WebhookEvent, id: i32, task: Option<&'a Task>) -> BoxFuture<'a, Result<()>>;
+}
+
+#[derive(Clone, Default)]
+pub struct Hooks {
+    on_add: Vec<String>,
+    on_complete: Vec<String>,
+    on_delete: Vec<String>,
+    timeout: Duration,
+    plugins: Vec<Arc<dyn Plugin>>,
+}
+
+impl Hooks {
+    pub fn new(config: &Config) -> Self {
+        let hooks = &config.hooks;
+        Hooks {
+            on_add: hooks.on_add.clone(),
+            on_complete: hooks.on_complete.clone(),
+            on_delete: hooks.on_delete.clone(),
+            timeout: Duration::from_secs(hooks.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS).max(1)),
+            plugins: Vec::new(),
+        }
+    }
+
+    pub fn register(&mut self, plugin: Arc<dyn Plugin>) {
+        self.plugins.push(plugin);
+    }
+
+    fn commands(&self, event: WebhookEvent) -> &[String] {
+        match event {
+            WebhookEvent::Created => &self.on_add,
+            WebhookEvent::Completed => &self.on_complete,
+            WebhookEvent::Deleted => &self.on_delete,
+        }
+    }
+
+    // Whether anything listens for `event`
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        !self.commands(event).is_empty() || !self.plugins.is_empty()
+    }
+
+    // Runs the commands of `event` one after the other, then the plugins; `body` is what the
+    // webhooks are sent
+    pub async fn run(&self, event: WebhookEvent, id: i32, task: Option<&Task>, body: &str) {
+        for command in self.commands(event) {
+            if let Err(message) = self.run_command(command, event, id, body).await {
+                tracing::warn!(task_id = id, command = %command, error = %message, "hook failed");
+            }
+        }
+        for plugin in &self.plugins {
+            if let Err(e) = plugin.on_event(event, id, task).await {
+                tracing::warn!(task_id = id, plugin = plugin.name(), error = %e, "plugin failed");
+            }
+        }
+    }
+
+    async fn run_command(
+        &self,
+        command: &str,
+        event: WebhookEvent,
+        id: i32,
+        body: &str,
+    ) -> std::result::Result<(), String> {
+        let mut child = shell(command)
+            .env("TASK_EVENT", event.as_str())
+            .env("TASK_ID", id.to_string())
+            .stdin(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| e.to_string())?;
+
+        let run = async {
+            if let Some(mut stdin) = child.stdin.take() {
+                // A command that doesn't read its input closes the pipe early; that's fine
+                let _ = stdin.write_all(body.as_bytes()).await;
+            }
+            child.wait().await
+        };
+        match tokio::time::timeout(self.timeout, run).await {
+            Ok(Ok(status)) if status.success() => Ok(()),
+            Ok(Ok(status)) => Err(format!("exited with {}", status)),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err(format!("still running after {} s, stopped", self.timeout.as_secs())),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::config::HooksConfig;
+
+    fn hooks(config: HooksConfig) -> Hooks {
+        Hooks::new(&Config { hooks: config, ..Default::default() })
+    }
+
+    #[tokio::test]
+    async fn commands_get_the_event_on_stdin() {
+        let out = std::env::temp_dir().join(format!("task-hook-{}.json", std::process::id()));
+        let command = format!("printf '%s %s ' \"$TASK_EVENT\" \"$TASK_ID\" > {0}; cat >> {0}", out.display());
+        let hooks = hooks(HooksConfig { on_complete: vec![command], ..Default::default() });
+        assert!(hooks.wants(WebhookEvent::Completed) && !hooks.wants(WebhookEvent::Created));
+
+        hooks.run(WebhookEvent::Completed, 7, None, r#"{"event":"completed"}"#).await;
+        let written = std::fs::read_to_string(&out).unwrap();
+        std::fs::remove_file(&out).unwrap();
+        assert_eq!(written, r#"completed 7 {"event":"completed"}"#);
+    }
+
+    #[tokio::test]
+    async fn failing_and_slow_commands_are_reported() {
+        let hooks = hooks(HooksConfig { timeout: Some(1), ..Default::default() });
+        let failed = hooks.run_command("exit 3", WebhookEvent::Created, 1, "{}").await.unwrap_err();
+        assert!(failed.contains('3'), "{}", failed);
+        let slow = hooks.run_command("sleep 5", WebhookEvent::Created, 1, "{}").await.unwrap_err();
+        assert!(slow.contains("still running"), "{}", slow);
+    }
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<(WebhookEvent, i32)>>);
+
+    impl Plugin for Recorder {
+        fn name(&self) -> &str {
+            "recorder"
+        }
+
+        fn on_event<'a>(&'a self, event: WebhookEvent, id: i32, _: Option<&'a Task>) -> BoxFuture<'a, Result<()>> {
+            self.0.lock().unwrap().push((event, id));
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn plugins_hear_every_event() {
+        let recorder = Arc::new(Recorder::default());
+        let mut hooks = hooks(HooksConfig::default());
+        assert!(!hooks.wants(WebhookEvent::Deleted));
+        hooks.register(recorder.clone());
+
+        hooks.run(WebhookEvent::Created, 1, None, "{}").await;
+        hooks.run(WebhookEvent::Deleted, 1, None, "{}").await;
+        assert_eq!(*recorder.0.lock().unwrap(), [(WebhookEvent::Created, 1), (WebhookEvent::Deleted, 1)]);
+    }
+}
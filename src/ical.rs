@@ -0,0 +1,116 @@
+// Reading iCalendar (RFC 5545) content lines, shared by CalDAV sync and the .ics import
+
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+
+use crate::priority::Priority;
+
+// Joins folded content lines (continuations start with a space or tab)
+pub fn unfold(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+// Dates become midnight; UTC times are converted to local time. Times with a TZID are taken
+// as local time, which is right as long as the calendar and this machine share a time zone.
+pub fn parse_time(value: &str, params: &str) -> Option<NaiveDateTime> {
+    let value = value.trim();
+    if params.to_ascii_uppercase().contains("VALUE=DATE") && !value.contains('T') {
+        return NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(|date| date.and_time(NaiveTime::MIN));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let utc = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&utc).with_timezone(&Local).naive_local());
+    }
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(|date| date.and_time(NaiveTime::MIN)))
+}
+
+// 1-4 is high, 5 medium, 6-9 low and 0 undefined, matching export::ics::ical_priority
+pub fn priority_from_ical(priority: u8) -> Option<Priority> {
+    match priority {
+        1..=4 => Some(Priority::High),
+        5 => Some(Priority::Medium),
+        6..=9 => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+pub fn unescape_text(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+    text
+}
+
+// Splits a list value on the commas that aren't escaped, unescaping each item
+pub fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            ',' if !escaped => {
+                items.push(unescape_text(&value[start..index]));
+                start = index + 1;
+            }
+            _ => escaped = false,
+        }
+    }
+    items.push(unescape_text(&value[start..]));
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::fixtures;
+
+    proptest! {
+        #[test]
+        fn dates_read_back(date in fixtures::date()) {
+            let value = date.format("%Y%m%d").to_string();
+            prop_assert_eq!(parse_time(&value, ";VALUE=DATE"), Some(date.and_time(NaiveTime::MIN)));
+        }
+
+        // Floating and TZID times are kept as they are
+        #[test]
+        fn local_times_read_back(
+            time in fixtures::date_time(),
+            params in prop_oneof![Just(""), Just(";TZID=Europe/Berlin")],
+        ) {
+            prop_assert_eq!(parse_time(&time.format("%Y%m%dT%H%M%S").to_string(), params), Some(time));
+        }
+
+        #[test]
+        fn utc_times_become_local(time in fixtures::date_time()) {
+            let expected = Utc.from_utc_datetime(&time).with_timezone(&Local).naive_local();
+            prop_assert_eq!(parse_time(&time.format("%Y%m%dT%H%M%SZ").to_string(), ""), Some(expected));
+        }
+
+        #[test]
+        fn any_value_parses_or_is_rejected(value in "\\PC*|[0-9TZ]{0,20}", params in "\\PC*|;VALUE=DATE") {
+            let _ = parse_time(&value, &params);
+        }
+    }
+}
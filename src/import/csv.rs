@@ -0,0 +1,128 @@
+use std::fs::File;
+
+use crate::cli::CsvImportArgs;
+use crate::error::{Result, TaskError};
+use crate::repository::{TaskBundle, TaskRepository};
+
+use super::Parsed;
+
+// Task fields a CSV column can be mapped to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Description,
+    Due,
+    Priority,
+    Tags,
+}
+
+const FIELDS: [(&str, Field); 4] = [
+    ("description", Field::Description),
+    ("due", Field::Due),
+    ("priority", Field::Priority),
+    ("tags", Field::Tags),
+];
+
+// Column index of each field in the file; only the description is required
+#[derive(Debug)]
+struct Columns {
+    description: usize,
+    due: Option<usize>,
+    priority: Option<usize>,
+    tags: Option<usize>,
+}
+
+pub async fn run(repo: &TaskRepository, args: CsvImportArgs) -> Result<()> {
+    let file = File::open(&args.file).map_err(|e| {
+        TaskError::InvalidInput(format!("Could not open {}: {}", args.file.display(), e))
+    })?;
+    let delimiter = u8::try_from(args.delimiter)
+        .map_err(|_| TaskError::InvalidInput("The delimiter must be a single ASCII character.".to_string()))?;
+
+    let mut reader = ::csv::ReaderBuilder::new().delimiter(delimiter).flexible(true).from_reader(file);
+    let invalid = |e: ::csv::Error| TaskError::parse(&args.file, "valid CSV", e);
+
+    let headers = reader.headers().map_err(invalid)?.clone();
+    let columns = resolve_columns(&headers, &args.columns)?;
+
+    let mut parsed = Parsed::new("Line");
+    for record in reader.records() {
+        let record = record.map_err(invalid)?;
+        let line = record.position().map_or(0, |position| position.line());
+
+        match parse_record(&record, &columns, args.tag_separator) {
+            Ok(bundle) => parsed.push(line, bundle),
+            Err(reason) => parsed.reject(line, reason),
+        }
+    }
+
+    super::finish(repo, parsed, &args.options).await
+}
+
+// Maps fields to header positions. `--column field=Header` overrides the default, which is a
+// header named like the field; headers compare case-insensitively.
+fn resolve_columns(headers: &::csv::StringRecord, overrides: &[String]) -> Result<Columns> {
+    let mut names: Vec<(Field, String)> = FIELDS.iter().map(|&(name, field)| (field, name.to_string())).collect();
+
+    for mapping in overrides {
+        let Some((field, header)) = mapping.split_once('=') else {
+            return Err(TaskError::InvalidInput(format!(
+                "Invalid column mapping '{}'; expected FIELD=HEADER, e.g. due=\"Due Date\".",
+                mapping
+            )));
+        };
+        let Some(&(_, field)) = FIELDS.iter().find(|(name, _)| name.eq_ignore_ascii_case(field.trim())) else {
+            return Err(TaskError::InvalidInput(format!(
+                "Unknown field '{}' in column mapping; expected description, due, priority or tags.",
+                field.trim()
+            )));
+        };
+        if let Some(entry) = names.iter_mut().find(|(f, _)| *f == field) {
+            entry.1 = header.trim().to_string();
+        }
+    }
+
+    let position = |field: Field| {
+        names
+            .iter()
+            .find(|(f, _)| *f == field)
+            .and_then(|(_, name)| headers.iter().position(|h| h.trim().eq_ignore_ascii_case(name)))
+    };
+
+    let Some(description) = position(Field::Description) else {
+        return Err(TaskError::InvalidInput(
+            "No description column found. Name a header 'description' or map one with --column description=HEADER."
+                .to_string(),
+        ));
+    };
+
+    Ok(Columns {
+        description,
+        due: position(Field::Due),
+        priority: position(Field::Priority),
+        tags: position(Field::Tags),
+    })
+}
+
+fn parse_record(
+    record: &::csv::StringRecord,
+    columns: &Columns,
+    tag_separator: char,
+) -> std::result::Result<TaskBundle, String> {
+    let value = |index: Option<usize>| index.and_then(|i| record.get(i)).map(str::trim).filter(|v| !v.is_empty());
+
+    let mut task = super::new_task(value(Some(columns.description)).unwrap_or_default().to_string());
+    task.due_at = value(columns.due).map(super::parse_due).transpose()?;
+    task.priority = value(columns.priority).map(str::parse).transpose()?;
+
+    let tags: Vec<String> = value(columns.tags)
+        .map(|tags| {
+            tags.split(tag_separator)
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(TaskBundle { tags, ..super::bundle(task) })
+}
@@ -0,0 +1,123 @@
+use std::fs;
+
+use crate::cli::FileImportArgs;
+use crate::error::{Result, TaskError};
+use crate::ical::{parse_time, priority_from_ical, split_list, unescape_text, unfold};
+use crate::repository::{ExternalId, Note, TaskBundle, TaskRepository};
+
+use super::Parsed;
+
+// Recorded with imported to-dos (by UID), so importing the same file again skips them
+const SOURCE: &str = "ics";
+
+// A VTODO as read so far, with the name of the calendar (list) it is in
+#[derive(Debug, Default)]
+struct Todo {
+    list: Option<String>,
+    uid: Option<String>,
+    summary: Option<String>,
+    description: Option<String>,
+    completed: bool,
+    due: Option<(String, String)>,
+    created: Option<(String, String)>,
+    priority: Option<u8>,
+    categories: Vec<String>,
+}
+
+// Reads the VTODOs of an .ics file, such as a list exported from Apple Reminders. A file may
+// hold several calendars one after another; each calendar's name (X-WR-CALNAME) becomes the
+// project of its to-dos, and categories become tags. Reminders of the to-dos (VALARMs) are
+// ignored, and so are events.
+pub async fn run(repo: &TaskRepository, args: FileImportArgs) -> Result<()> {
+    let content = fs::read_to_string(&args.file).map_err(|e| {
+        TaskError::InvalidInput(format!("Could not read {}: {}", args.file.display(), e))
+    })?;
+
+    let mut parsed = Parsed::new("To-do");
+    for (index, todo) in parse(&content).into_iter().enumerate() {
+        match to_bundle(todo) {
+            Ok(bundle) => parsed.push(index as u64 + 1, bundle),
+            Err(reason) => parsed.reject(index as u64 + 1, reason),
+        }
+    }
+
+    super::finish(repo, parsed, &args.options).await
+}
+
+fn parse(ics: &str) -> Vec<Todo> {
+    let mut todos = Vec::new();
+    let mut list: Option<String> = None;
+    let mut current: Option<Todo> = None;
+    // Components nested in the current VTODO, such as alarms
+    let mut depth = 0;
+
+    for line in unfold(ics) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        let name = name.to_ascii_uppercase();
+
+        match (name.as_str(), current.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VCALENDAR") => list = None,
+            ("X-WR-CALNAME", None) => list = Some(unescape_text(value.trim())).filter(|name| !name.is_empty()),
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => {
+                current = Some(Todo { list: list.clone(), ..Todo::default() });
+                depth = 0;
+            }
+            ("BEGIN", Some(_)) => depth += 1,
+            ("END", Some(_)) if depth > 0 => depth -= 1,
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VTODO") => todos.extend(current.take()),
+            (_, Some(todo)) if depth == 0 => {
+                let value = value.trim();
+                match name.as_str() {
+                    "UID" => todo.uid = Some(value.to_string()).filter(|uid| !uid.is_empty()),
+                    "SUMMARY" => todo.summary = Some(unescape_text(value)),
+                    "DESCRIPTION" => todo.description = Some(unescape_text(value)),
+                    "STATUS" => todo.completed = value.eq_ignore_ascii_case("COMPLETED"),
+                    // Some apps only set the completion time
+                    "COMPLETED" => todo.completed = true,
+                    "DUE" => todo.due = Some((value.to_string(), params.to_string())),
+                    "CREATED" => todo.created = Some((value.to_string(), params.to_string())),
+                    "PRIORITY" => todo.priority = value.parse().ok(),
+                    "CATEGORIES" => todo.categories.extend(
+                        split_list(value).into_iter().map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()),
+                    ),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    todos
+}
+
+fn to_bundle(todo: Todo) -> std::result::Result<TaskBundle, String> {
+    let summary = todo.summary.as_deref().map(str::trim).unwrap_or_default();
+    if summary.is_empty() {
+        return Err("to-do has no summary".to_string());
+    }
+
+    let mut task = super::new_task(summary.to_string());
+    task.completed = todo.completed;
+    task.priority = todo.priority.and_then(priority_from_ical);
+    if let Some((value, params)) = &todo.due {
+        task.due_at = Some(parse_time(value, params).ok_or_else(|| format!("invalid DUE '{}'", value))?);
+    }
+    if let Some(created_at) = todo.created.as_ref().and_then(|(value, params)| parse_time(value, params)) {
+        task.created_at = created_at;
+        task.updated_at = created_at;
+    }
+
+    let notes = match todo.description.as_deref().map(str::trim) {
+        None | Some("") => Vec::new(),
+        Some(body) => vec![Note { created_at: task.created_at, body: body.to_string(), created_by: None }],
+    };
+    Ok(TaskBundle {
+        tags: todo.categories,
+        project: todo.list,
+        notes,
+        external: todo.uid.map(|id| ExternalId { source: SOURCE, id }),
+        ..super::bundle(task)
+    })
+}
@@ -0,0 +1,60 @@
+use std::fs::File;
+use std::io::BufReader;
+
+use crate::cli::FileImportArgs;
+use crate::error::{Result, TaskError};
+use crate::export::json::{Document, ExportTask, EXPORT_FORMAT, EXPORT_VERSION};
+use crate::repository::{TaskBundle, TaskRepository};
+
+use super::Parsed;
+
+// Reads a file written by `task export json`. Timestamps, authors and completion state are
+// kept as they were exported.
+pub async fn run(repo: &TaskRepository, args: FileImportArgs) -> Result<()> {
+    let file = File::open(&args.file).map_err(|e| {
+        TaskError::InvalidInput(format!("Could not open {}: {}", args.file.display(), e))
+    })?;
+    let document: Document = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| TaskError::parse(&args.file, "a valid export file", e))?;
+
+    if document.format != EXPORT_FORMAT {
+        return Err(TaskError::InvalidInput(format!(
+            "{} is not a task export (format '{}').",
+            args.file.display(),
+            document.format
+        )));
+    }
+    if document.version > EXPORT_VERSION {
+        return Err(TaskError::InvalidInput(format!(
+            "Export version {} is newer than this binary supports (version {}). Please upgrade first.",
+            document.version, EXPORT_VERSION
+        )));
+    }
+
+    let mut parsed = Parsed::new("Entry");
+    for (index, task) in document.tasks.into_iter().enumerate() {
+        parsed.push(index as u64 + 1, to_bundle(task));
+    }
+
+    super::finish(repo, parsed, &args.options).await
+}
+
+fn to_bundle(task: ExportTask) -> TaskBundle {
+    let mut new = super::new_task(task.description);
+    new.completed = task.completed;
+    new.created_at = task.created_at;
+    new.created_by = task.created_by;
+    new.updated_at = task.updated_at.unwrap_or(task.created_at);
+    new.updated_by = task.updated_by;
+    new.due_at = task.due_at;
+    new.priority = task.priority;
+
+    TaskBundle {
+        task: new,
+        tags: task.tags,
+        project: task.project,
+        checklist: task.checklist,
+        notes: task.notes,
+        external: None,
+    }
+}
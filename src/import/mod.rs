@@ -0,0 +1,277 @@
+use std::collections::HashSet;
+
+use chrono::{Local, NaiveDate, NaiveDateTime};
+
+use crate::cli::ImportOptions;
+use crate::error::{describe_sqlx_error, Result};
+use crate::input::confirm;
+use crate::repository::{NewTask, TaskBundle, TaskRepository};
+
+pub mod csv;
+pub mod ics;
+pub mod json;
+pub mod taskwarrior;
+pub mod todotxt;
+pub mod trello;
+
+// Rows shown before asking for confirmation
+const PREVIEW_ROWS: usize = 10;
+
+// Column limits from the schema, checked up front so one bad row doesn't abort the whole import
+pub const MAX_TEXT_CHARS: usize = 255;
+const MAX_NAME_CHARS: usize = 64;
+
+// A task read from an import file, with where it came from for messages
+#[derive(Debug)]
+pub struct ImportRow {
+    pub position: u64,
+    pub bundle: TaskBundle,
+}
+
+// A row that could not be turned into a task
+#[derive(Debug)]
+pub struct Rejected {
+    pub position: u64,
+    pub reason: String,
+}
+
+#[derive(Debug)]
+pub struct Parsed {
+    // What `position` counts in this format, e.g. "Line" or "Entry"
+    pub unit: &'static str,
+    pub rows: Vec<ImportRow>,
+    pub rejected: Vec<Rejected>,
+}
+
+impl Parsed {
+    pub fn new(unit: &'static str) -> Self {
+        Parsed { unit, rows: Vec::new(), rejected: Vec::new() }
+    }
+
+    // Keeps the row if it passes `validate`, otherwise records why not
+    pub fn push(&mut self, position: u64, bundle: TaskBundle) {
+        match validate(&bundle) {
+            Ok(()) => self.rows.push(ImportRow { position, bundle }),
+            Err(reason) => self.rejected.push(Rejected { position, reason }),
+        }
+    }
+
+    pub fn reject(&mut self, position: u64, reason: String) {
+        self.rejected.push(Rejected { position, reason });
+    }
+}
+
+// Pending task created now by the current user; importers fill in what their format has
+pub fn new_task(description: String) -> NewTask {
+    let now = Local::now().naive_local();
+    NewTask {
+        id: None,
+        description,
+        completed: false,
+        created_at: now,
+        created_by: None,
+        updated_by: None,
+        updated_at: now,
+        completed_at: None,
+        due_at: None,
+        priority: None,
+        project_id: None,
+        owner_id: None,
+        assignee_id: None,
+        workspace_id: None,
+    }
+}
+
+pub fn bundle(task: NewTask) -> TaskBundle {
+    TaskBundle { task, tags: Vec::new(), project: None, checklist: Vec::new(), notes: Vec::new(), external: None }
+}
+
+// Checks a row against the column limits
+pub fn validate(bundle: &TaskBundle) -> std::result::Result<(), String> {
+    let too_long = |text: &str, limit: usize| text.chars().count() > limit;
+
+    if bundle.task.description.trim().is_empty() {
+        return Err("description is empty".to_string());
+    }
+    if too_long(&bundle.task.description, MAX_TEXT_CHARS) {
+        return Err(format!("description is longer than {} characters", MAX_TEXT_CHARS));
+    }
+    if let Some(tag) = bundle.tags.iter().find(|tag| too_long(tag, MAX_NAME_CHARS)) {
+        return Err(format!("tag '{}' is longer than {} characters", tag, MAX_NAME_CHARS));
+    }
+    if let Some(project) = bundle.project.as_deref().filter(|project| too_long(project, MAX_NAME_CHARS)) {
+        return Err(format!("project '{}' is longer than {} characters", project, MAX_NAME_CHARS));
+    }
+    if bundle.checklist.iter().any(|item| too_long(&item.text, MAX_TEXT_CHARS)) {
+        return Err(format!("a checklist item is longer than {} characters", MAX_TEXT_CHARS));
+    }
+    Ok(())
+}
+
+// Dates as YYYY-MM-DD (stored at midnight) or with a time, separated by a space or 'T'
+pub fn parse_due(value: &str) -> std::result::Result<NaiveDateTime, String> {
+    let value = value.trim();
+    for format in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(due) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(due);
+        }
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.and_time(chrono::NaiveTime::MIN))
+        .map_err(|_| format!("invalid due date '{}' (expected YYYY-MM-DD or YYYY-MM-DD HH:MM)", value))
+}
+
+// Shared second half of every import: drop duplicates, show a preview, ask, insert, summarize.
+// A row is a duplicate if a task with the same description already exists or an earlier row
+// in the same file has it; descriptions compare case-insensitively, like the column collation.
+// Rows that carry an external id are instead matched on that id, so re-importing from the
+// same source skips what came over last time but keeps distinct tasks that share a name.
+pub async fn finish(repo: &TaskRepository, parsed: Parsed, options: &ImportOptions) -> Result<()> {
+    let Parsed { unit, rows, rejected } = parsed;
+
+    let mut seen = HashSet::new();
+    let mut new_rows = Vec::new();
+    let mut duplicates = Vec::new();
+    for row in rows {
+        let duplicate = match &row.bundle.external {
+            Some(external) => {
+                !seen.insert(format!("{}:{}", external.source, external.id)) || repo.external_id_exists(external).await?
+            }
+            None => {
+                let description = &row.bundle.task.description;
+                !seen.insert(description.trim().to_lowercase()) || repo.description_exists(description).await?
+            }
+        };
+        if duplicate {
+            duplicates.push(row);
+        } else {
+            new_rows.push(row);
+        }
+    }
+
+    print_preview(unit, &new_rows);
+    for row in &rejected {
+        println!("{} {}: skipped, {}", unit, row.position, row.reason);
+    }
+    for row in &duplicates {
+        println!("{} {}: skipped, duplicate of '{}'", unit, row.position, row.bundle.task.description);
+    }
+
+    if options.dry_run {
+        println!(
+            "Dry run: would import {} tasks, skip {} duplicates and {} invalid rows.",
+            new_rows.len(),
+            duplicates.len(),
+            rejected.len()
+        );
+        return Ok(());
+    }
+    if new_rows.is_empty() {
+        println!("Nothing to import ({} duplicates, {} invalid rows).", duplicates.len(), rejected.len());
+        return Ok(());
+    }
+    if !options.yes && !confirm(&format!("Import {} tasks? [y/N] ", new_rows.len())) {
+        println!("Import cancelled.");
+        return Ok(());
+    }
+
+    let positions: Vec<u64> = new_rows.iter().map(|row| row.position).collect();
+    let bundles: Vec<TaskBundle> = new_rows.into_iter().map(|row| row.bundle).collect();
+    let mut report = repo.import_in_chunks(&bundles).await;
+    for failure in &report.failures {
+        let rows = &positions[failure.items.clone()];
+        println!(
+            "{}s {} to {}: not imported, {}",
+            unit,
+            rows[0],
+            rows[rows.len() - 1],
+            describe_sqlx_error(&failure.error)
+        );
+    }
+
+    println!(
+        "Imported {} tasks {}; skipped {} duplicates and {} invalid rows.",
+        report.done.len(),
+        report.timing(),
+        duplicates.len(),
+        rejected.len()
+    );
+    if !report.failures.is_empty() {
+        return Err(report.failures.remove(0).error.into());
+    }
+    Ok(())
+}
+
+fn print_preview(unit: &str, rows: &[ImportRow]) {
+    if rows.is_empty() {
+        return;
+    }
+
+    println!("\n--- Preview ---");
+    for row in rows.iter().take(PREVIEW_ROWS) {
+        let TaskBundle { task, tags, project, checklist, notes, .. } = &row.bundle;
+
+        let mut details = Vec::new();
+        if task.completed {
+            details.push("completed".to_string());
+        }
+        if let Some(due_at) = task.due_at {
+            details.push(format!("due {}", due_at.format("%Y-%m-%d %H:%M")));
+        }
+        if let Some(priority) = task.priority {
+            details.push(format!("{} priority", priority));
+        }
+        if let Some(project) = project {
+            details.push(format!("project {}", project));
+        }
+        if !tags.is_empty() {
+            details.push(format!("tags: {}", tags.join(", ")));
+        }
+        if !checklist.is_empty() {
+            details.push(format!("{} checklist items", checklist.len()));
+        }
+        if !notes.is_empty() {
+            details.push(format!("{} notes", notes.len()));
+        }
+
+        if details.is_empty() {
+            println!("{} {}: '{}'", unit, row.position, task.description);
+        } else {
+            println!("{} {}: '{}' ({})", unit, row.position, task.description, details.join("; "));
+        }
+    }
+    if rows.len() > PREVIEW_ROWS {
+        println!("... and {} more", rows.len() - PREVIEW_ROWS);
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Timelike;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::fixtures;
+
+    proptest! {
+        #[test]
+        fn dates_read_back(date in fixtures::date()) {
+            let midnight = date.and_time(chrono::NaiveTime::MIN);
+            prop_assert_eq!(parse_due(&date.format("%Y-%m-%d").to_string()), Ok(midnight));
+        }
+
+        #[test]
+        fn times_read_back(time in fixtures::date_time(), separator in prop_oneof![Just(' '), Just('T')]) {
+            let seconds = time.format(&format!("%Y-%m-%d{}%H:%M:%S", separator)).to_string();
+            prop_assert_eq!(parse_due(&seconds), Ok(time));
+            let minutes = time.format(&format!("  %Y-%m-%d{}%H:%M ", separator)).to_string();
+            prop_assert_eq!(parse_due(&minutes), Ok(time.with_second(0).unwrap()));
+        }
+
+        #[test]
+        fn any_text_parses_or_is_rejected(value in "\\PC*") {
+            let _ = parse_due(&value);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use std::fs;
+
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::cli::FileImportArgs;
+use crate::error::{Result, TaskError};
+use crate::priority::Priority;
+use crate::repository::{ExternalId, Note, TaskBundle, TaskRepository};
+
+use super::Parsed;
+
+// Recorded with imported tasks, so importing the same export again skips them
+const SOURCE: &str = "taskwarrior";
+
+// One task from `task export`. Only the attributes we map are listed; serde ignores the rest
+// (urgency, UDAs, ...).
+#[derive(Debug, Deserialize)]
+struct TwTask {
+    uuid: String,
+    description: String,
+    status: String,
+    entry: Option<String>,
+    modified: Option<String>,
+    end: Option<String>,
+    due: Option<String>,
+    project: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    priority: Option<String>,
+    #[serde(default)]
+    annotations: Vec<TwAnnotation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TwAnnotation {
+    entry: Option<String>,
+    description: String,
+}
+
+// Reads the JSON written by `task export`: a JSON array in Taskwarrior 2.5 and later, one
+// object per line in older versions. Deleted tasks and recurrence templates are skipped;
+// annotations become notes and the UUID is kept as the task's external id.
+pub async fn run(repo: &TaskRepository, args: FileImportArgs) -> Result<()> {
+    let content = fs::read_to_string(&args.file).map_err(|e| {
+        TaskError::InvalidInput(format!("Could not read {}: {}", args.file.display(), e))
+    })?;
+    let tasks = parse_export(&content).map_err(|e| TaskError::parse(&args.file, "a Taskwarrior export", e))?;
+
+    let mut parsed = Parsed::new("Entry");
+    for (index, task) in tasks.into_iter().enumerate() {
+        let position = index as u64 + 1;
+        match to_bundle(task) {
+            Ok(Some(bundle)) => parsed.push(position, bundle),
+            Ok(None) => {}
+            Err(reason) => parsed.reject(position, reason),
+        }
+    }
+
+    super::finish(repo, parsed, &args.options).await
+}
+
+fn parse_export(content: &str) -> serde_json::Result<Vec<TwTask>> {
+    if content.trim_start().starts_with('[') {
+        return serde_json::from_str(content);
+    }
+
+    content
+        .lines()
+        .map(|line| line.trim().trim_end_matches(','))
+        .filter(|line| !line.is_empty())
+        .map(serde_json::from_str)
+        .collect()
+}
+
+// Ok(None) for tasks that have no counterpart here
+fn to_bundle(tw: TwTask) -> std::result::Result<Option<TaskBundle>, String> {
+    let completed = match tw.status.as_str() {
+        "pending" | "waiting" => false,
+        "completed" => true,
+        "deleted" | "recurring" => return Ok(None),
+        other => return Err(format!("unknown status '{}'", other)),
+    };
+
+    let mut task = super::new_task(tw.description);
+    task.completed = completed;
+    if let Some(entry) = &tw.entry {
+        task.created_at = parse_timestamp(entry)?;
+        task.updated_at = task.created_at;
+    }
+    if let Some(changed) = tw.end.as_ref().or(tw.modified.as_ref()) {
+        task.updated_at = parse_timestamp(changed)?;
+    }
+    task.due_at = tw.due.as_deref().map(parse_timestamp).transpose()?;
+    task.priority = tw.priority.as_deref().map(str::parse::<Priority>).transpose()?;
+
+    let notes = tw
+        .annotations
+        .into_iter()
+        .map(|annotation| {
+            let created_at = match &annotation.entry {
+                Some(entry) => parse_timestamp(entry)?,
+                None => task.created_at,
+            };
+            Ok(Note { created_at, body: annotation.description, created_by: None })
+        })
+        .collect::<std::result::Result<Vec<Note>, String>>()?;
+
+    Ok(Some(TaskBundle {
+        tags: tw.tags,
+        project: tw.project,
+        notes,
+        external: Some(ExternalId { source: SOURCE, id: tw.uuid }),
+        ..super::bundle(task)
+    }))
+}
+
+// Taskwarrior timestamps are UTC in ISO 8601 basic format, e.g. 20261014T093000Z.
+// Tasks here are stored in local time.
+fn parse_timestamp(value: &str) -> std::result::Result<NaiveDateTime, String> {
+    let utc = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .map_err(|_| format!("invalid timestamp '{}'", value))?;
+    Ok(Utc.from_utc_datetime(&utc).with_timezone(&Local).naive_local())
+}
@@ -0,0 +1,165 @@
+use std::fs;
+
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::cli::FileImportArgs;
+use crate::error::{Result, TaskError};
+use crate::priority::Priority;
+use crate::repository::{TaskBundle, TaskRepository};
+
+use super::Parsed;
+
+// Reads a todo.txt file. The first +project of a line becomes the task's project, further
+// projects and all @contexts become tags, and due:/pri: tags set the due date and priority.
+// Other key:value tags stay part of the description.
+pub async fn run(repo: &TaskRepository, args: FileImportArgs) -> Result<()> {
+    let content = fs::read_to_string(&args.file).map_err(|e| {
+        TaskError::InvalidInput(format!("Could not read {}: {}", args.file.display(), e))
+    })?;
+
+    let mut parsed = Parsed::new("Line");
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_line(line) {
+            Ok(bundle) => parsed.push(index as u64 + 1, bundle),
+            Err(reason) => parsed.reject(index as u64 + 1, reason),
+        }
+    }
+
+    super::finish(repo, parsed, &args.options).await
+}
+
+// Also the quick-add syntax of the Telegram bot
+pub fn parse_line(line: &str) -> std::result::Result<TaskBundle, String> {
+    let mut tokens = line.split_whitespace().peekable();
+    let mut bundle = super::bundle(super::new_task(String::new()));
+    let task = &mut bundle.task;
+
+    if tokens.next_if_eq(&"x").is_some() {
+        task.completed = true;
+        // Completion date, then creation date
+        if let Some(completed_on) = tokens.next_if(|token| parse_date(token).is_some()) {
+            task.updated_at = parse_date(completed_on).unwrap_or(task.updated_at);
+        }
+    }
+    if let Some(priority) = tokens.next_if(|token| parse_priority(token).is_some()) {
+        task.priority = parse_priority(priority);
+    }
+    if let Some(created_on) = tokens.next_if(|token| parse_date(token).is_some()) {
+        task.created_at = parse_date(created_on).unwrap_or(task.created_at);
+        if !task.completed {
+            task.updated_at = task.created_at;
+        }
+    }
+
+    let mut words = Vec::new();
+    for token in tokens {
+        if let Some(project) = token.strip_prefix('+').filter(|name| !name.is_empty()) {
+            if bundle.project.is_none() {
+                bundle.project = Some(project.to_string());
+            } else {
+                bundle.tags.push(project.to_string());
+            }
+        } else if let Some(context) = token.strip_prefix('@').filter(|name| !name.is_empty()) {
+            bundle.tags.push(context.to_string());
+        } else if let Some(due) = token.strip_prefix("due:") {
+            bundle.task.due_at = Some(super::parse_due(due)?);
+        } else if let Some(letter) = token.strip_prefix("pri:") {
+            let priority = priority_from_letter(letter).ok_or_else(|| format!("invalid priority '{}'", token))?;
+            bundle.task.priority = Some(priority);
+        } else {
+            words.push(token);
+        }
+    }
+
+    bundle.task.description = words.join(" ");
+    Ok(bundle)
+}
+
+fn parse_date(token: &str) -> Option<chrono::NaiveDateTime> {
+    NaiveDate::parse_from_str(token, "%Y-%m-%d").ok().map(|date| date.and_time(NaiveTime::MIN))
+}
+
+// "(A)" style priorities at the start of a line
+fn parse_priority(token: &str) -> Option<Priority> {
+    token.strip_prefix('(')?.strip_suffix(')').and_then(priority_from_letter)
+}
+
+// todo.txt has 26 priorities; A and B map to high and medium, everything below to low
+fn priority_from_letter(letter: &str) -> Option<Priority> {
+    match letter {
+        "A" => Some(Priority::High),
+        "B" => Some(Priority::Medium),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::export::todotxt::format_line;
+    use crate::fixtures::{self, task};
+
+    // Words that stay part of the description: never "x", a (A) priority, a date, a +project,
+    // an @context or a key:value tag
+    const WORD: &str = "[a-z][a-z0-9]{1,8}";
+    const NAME: &str = "[A-Za-z][A-Za-z0-9_-]{0,11}";
+
+    proptest! {
+        // What `task export todotxt` writes imports as the same task
+        #[test]
+        fn exported_lines_read_back(
+            words in prop::collection::vec(WORD, 1..8),
+            completed in any::<bool>(),
+            priority in prop::option::of(fixtures::priority()),
+            created in fixtures::date(),
+            done in fixtures::date(),
+            due in prop::option::of(fixtures::date()),
+            project in prop::option::of(NAME),
+            tags in prop::collection::vec(NAME, 0..4),
+        ) {
+            let mut task = task(1, &words.join(" "), 1);
+            task.completed = completed;
+            task.priority = priority;
+            task.created_at = created.and_time(NaiveTime::MIN);
+            task.updated_at = done.and_time(NaiveTime::MIN);
+            task.due_at = due.map(|due| due.and_time(NaiveTime::MIN));
+
+            let line = format_line(&task, project.as_deref(), &tags);
+            let bundle = parse_line(&line).map_err(TestCaseError::fail)?;
+            prop_assert_eq!(&bundle.task.description, &task.description, "{}", line);
+            prop_assert_eq!(bundle.task.completed, completed, "{}", line);
+            prop_assert_eq!(bundle.task.priority, priority, "{}", line);
+            prop_assert_eq!(bundle.task.created_at, task.created_at, "{}", line);
+            prop_assert_eq!(bundle.task.due_at, task.due_at, "{}", line);
+            if completed {
+                prop_assert_eq!(bundle.task.updated_at, task.updated_at, "{}", line);
+            }
+            prop_assert_eq!(&bundle.project, &project, "{}", line);
+            prop_assert_eq!(&bundle.tags, &tags, "{}", line);
+
+            // And writing it again gives the same line
+            let mut again = task.clone();
+            again.description = bundle.task.description.clone();
+            prop_assert_eq!(format_line(&again, bundle.project.as_deref(), &bundle.tags), line);
+        }
+
+        #[test]
+        fn any_line_parses_or_is_rejected(line in "\\PC*") {
+            let _ = parse_line(&line);
+        }
+
+        // Mostly the syntax's own characters, so its branches are hit far more often
+        #[test]
+        fn syntax_soup_parses_or_is_rejected(
+            line in "(x |\\([A-Z]\\) |[+@]|due:|pri:|2026-0[1-9]-[0-3][0-9]|[a-zA-Z0-9: ]){0,12}",
+        ) {
+            let _ = parse_line(&line);
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::Deserialize;
+
+use crate::cli::FileImportArgs;
+use crate::error::{Result, TaskError};
+use crate::input::{ask, confirm};
+use crate::repository::{ChecklistItem, ExternalId, Note, TaskBundle, TaskRepository};
+
+use super::Parsed;
+
+// Recorded with imported cards, so importing the same board again skips them
+const SOURCE: &str = "trello";
+
+// Lists with these names hold finished cards unless the user says otherwise
+const DONE_LISTS: [&str; 4] = ["done", "complete", "completed", "finished"];
+
+// The parts of a board export (Board menu → Print, export and share → Export as JSON) that
+// we map; serde ignores the rest (actions, members, plugin data, ...)
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Board {
+    #[serde(default)]
+    lists: Vec<TrelloList>,
+    #[serde(default)]
+    cards: Vec<Card>,
+    #[serde(default)]
+    checklists: Vec<Checklist>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TrelloList {
+    id: String,
+    name: String,
+    #[serde(default)]
+    closed: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Card {
+    id: String,
+    name: String,
+    #[serde(default)]
+    desc: String,
+    #[serde(default)]
+    closed: bool,
+    id_list: String,
+    due: Option<String>,
+    #[serde(default)]
+    due_complete: bool,
+    #[serde(default)]
+    labels: Vec<Label>,
+    #[serde(default)]
+    id_checklists: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Label {
+    #[serde(default)]
+    name: String,
+    color: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Checklist {
+    id: String,
+    #[serde(default)]
+    check_items: Vec<CheckItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CheckItem {
+    name: String,
+    state: String,
+    #[serde(default)]
+    pos: f64,
+}
+
+// What the cards of one list become
+#[derive(Debug)]
+struct ListMapping {
+    project: Option<String>,
+    completed: bool,
+}
+
+// Imports the cards of a Trello board. Each open list is mapped to a project and a status;
+// the defaults (project named after the list, completed for lists like "Done") are shown
+// and can be changed list by list before anything is imported. Archived cards and lists
+// are skipped. Labels become tags, the card description a note, and the card id is kept
+// as the task's external id.
+pub async fn run(repo: &TaskRepository, args: FileImportArgs) -> Result<()> {
+    let file = File::open(&args.file).map_err(|e| {
+        TaskError::InvalidInput(format!("Could not open {}: {}", args.file.display(), e))
+    })?;
+    let board: Board = serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| TaskError::parse(&args.file, "a Trello board export", e))?;
+
+    let mut mappings = default_mappings(&board);
+    print_mappings(&board, &mappings);
+    if !args.options.yes && !mappings.is_empty() && !confirm("Use this mapping? [y/N] ") {
+        edit_mappings(&board, &mut mappings);
+        print_mappings(&board, &mappings);
+    }
+
+    let checklists: HashMap<&str, &Checklist> = board.checklists.iter().map(|list| (list.id.as_str(), list)).collect();
+
+    let mut parsed = Parsed::new("Card");
+    for (index, card) in board.cards.iter().enumerate() {
+        let position = index as u64 + 1;
+        let Some(mapping) = mappings.get(card.id_list.as_str()) else {
+            continue; // Archived list
+        };
+        if card.closed {
+            continue;
+        }
+        match to_bundle(card, mapping, &checklists) {
+            Ok(bundle) => parsed.push(position, bundle),
+            Err(reason) => parsed.reject(position, reason),
+        }
+    }
+
+    super::finish(repo, parsed, &args.options).await
+}
+
+fn default_mappings(board: &Board) -> HashMap<&str, ListMapping> {
+    board
+        .lists
+        .iter()
+        .filter(|list| !list.closed)
+        .map(|list| {
+            let completed = DONE_LISTS.contains(&list.name.trim().to_lowercase().as_str());
+            (list.id.as_str(), ListMapping { project: Some(list.name.trim().to_string()), completed })
+        })
+        .collect()
+}
+
+// Lists in board order, as the user sees them in Trello
+fn open_lists(board: &Board) -> impl Iterator<Item = &TrelloList> {
+    board.lists.iter().filter(|list| !list.closed)
+}
+
+fn print_mappings(board: &Board, mappings: &HashMap<&str, ListMapping>) {
+    println!("\n--- List mapping ---");
+    for list in open_lists(board) {
+        let mapping = &mappings[list.id.as_str()];
+        let cards = board.cards.iter().filter(|card| card.id_list == list.id && !card.closed).count();
+        println!(
+            "'{}' ({} cards) -> {}, {}",
+            list.name,
+            cards,
+            mapping.project.as_deref().map_or("no project".to_string(), |project| format!("project '{}'", project)),
+            if mapping.completed { "completed" } else { "pending" }
+        );
+    }
+    println!();
+}
+
+// Asks for the project and status of each list; an empty answer keeps the current value
+fn edit_mappings(board: &Board, mappings: &mut HashMap<&str, ListMapping>) {
+    println!("For each list, press Enter to keep the value in brackets. Use '-' for no project.");
+    for list in open_lists(board) {
+        let Some(mapping) = mappings.get_mut(list.id.as_str()) else {
+            continue;
+        };
+
+        let current = mapping.project.as_deref().unwrap_or("-");
+        match ask(&format!("'{}' project [{}]: ", list.name, current)).as_deref().map(str::trim) {
+            None | Some("") => {}
+            Some("-") => mapping.project = None,
+            Some(project) => mapping.project = Some(project.to_string()),
+        }
+
+        let current = if mapping.completed { "y" } else { "n" };
+        match ask(&format!("'{}' cards are completed? [{}]: ", list.name, current))
+            .map(|answer| answer.trim().to_lowercase())
+            .as_deref()
+        {
+            Some("y" | "yes") => mapping.completed = true,
+            Some("n" | "no") => mapping.completed = false,
+            _ => {}
+        }
+    }
+}
+
+fn to_bundle(
+    card: &Card,
+    mapping: &ListMapping,
+    checklists: &HashMap<&str, &Checklist>,
+) -> std::result::Result<TaskBundle, String> {
+    let mut task = super::new_task(card.name.trim().to_string());
+    task.completed = mapping.completed || card.due_complete;
+    task.due_at = card.due.as_deref().map(parse_timestamp).transpose()?;
+
+    // Unnamed labels are known only by their colour
+    let tags = card
+        .labels
+        .iter()
+        .filter_map(|label| match label.name.trim() {
+            "" => label.color.clone(),
+            name => Some(name.to_string()),
+        })
+        .collect();
+
+    let mut checklist = Vec::new();
+    for id in &card.id_checklists {
+        let Some(list) = checklists.get(id.as_str()) else {
+            continue;
+        };
+        let mut items: Vec<&CheckItem> = list.check_items.iter().collect();
+        items.sort_by(|a, b| a.pos.total_cmp(&b.pos));
+        checklist.extend(items.into_iter().map(|item| ChecklistItem {
+            text: item.name.clone(),
+            done: item.state == "complete",
+        }));
+    }
+
+    let notes = match card.desc.trim() {
+        "" => Vec::new(),
+        desc => vec![Note { created_at: task.created_at, body: desc.to_string(), created_by: None }],
+    };
+
+    Ok(TaskBundle {
+        tags,
+        project: mapping.project.clone(),
+        checklist,
+        notes,
+        external: Some(ExternalId { source: SOURCE, id: card.id.clone() }),
+        ..super::bundle(task)
+    })
+}
+
+// Trello timestamps are UTC with milliseconds, e.g. 2026-10-14T09:30:00.000Z.
+// Tasks here are stored in local time.
+fn parse_timestamp(value: &str) -> std::result::Result<NaiveDateTime, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.with_timezone(&Local).naive_local())
+        .map_err(|_| format!("invalid due date '{}'", value))
+}
@@ -0,0 +1,60 @@
+use std::io::{self, Write};
+use tokio::sync::{mpsc, watch};
+
+// Reads stdin line by line on a dedicated OS thread and hands the lines over a channel.
+// A blocked read on the runtime's blocking pool would keep the process alive after a
+// shutdown signal until the user pressed Enter; a plain thread is simply dropped on exit.
+pub struct Input {
+    lines: mpsc::UnboundedReceiver<String>,
+    shutdown: watch::Receiver<bool>,
+}
+
+impl Input {
+    pub fn new(shutdown: watch::Receiver<bool>) -> Self {
+        let (tx, lines) = mpsc::unbounded_channel();
+
+        std::thread::spawn(move || {
+            for line in io::stdin().lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Input { lines, shutdown }
+    }
+
+    // Prints the prompt and waits for the next line.
+    // Returns `None` if stdin was closed or a shutdown was requested while waiting.
+    pub async fn prompt(&mut self, message: &str) -> Option<String> {
+        print!("{}", message);
+        let _ = io::stdout().flush(); // Ensure the prompt is displayed; nothing to do if stdout is gone
+
+        if *self.shutdown.borrow() {
+            return None;
+        }
+
+        tokio::select! {
+            line = self.lines.recv() => line,
+            _ = self.shutdown.changed() => None,
+        }
+    }
+}
+
+// Blocking question for one-shot commands, before any interactive session exists.
+// Returns `None` if stdin was closed.
+pub fn ask(message: &str) -> Option<String> {
+    print!("{}", message);
+    let _ = io::stdout().flush();
+
+    let mut answer = String::new();
+    match io::stdin().read_line(&mut answer) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(answer.trim_end_matches(['\r', '\n']).to_string()),
+    }
+}
+
+// Blocking yes/no question. Anything but "y"/"yes" (or a closed stdin) counts as no.
+pub fn confirm(message: &str) -> bool {
+    ask(message).is_some_and(|answer| matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
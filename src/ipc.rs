@@ -0,0 +1,134 @@
+// How the CLI talks to a running `task daemon`: over a Unix socket, one request and one
+// response per connection. Each message is a JSON document preceded by its length as a 4-byte
+// big-endian number. Where there are no Unix sockets the daemon doesn't listen and every
+// request finds no daemon, so the commands fall back to doing the work themselves.
+
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::config::Config;
+use crate::error::Result;
+use crate::notify::Pending;
+
+// Longer messages are refused rather than read into memory
+pub const MAX_MESSAGE: u32 = 1 << 20;
+// How long either side waits for the other
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum Request {
+    // Run these jobs as soon as the daemon is free; see daemon::select for what matches
+    RunNow { jobs: Vec<String> },
+    // The reminders the daemon's channels are yet to send
+    Reminders,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum Response {
+    Queued { jobs: Vec<String> },
+    Reminders { reminders: Vec<Pending> },
+    Error { message: String },
+}
+
+// [daemon] socket, else daemon.sock in the data directory
+pub fn socket_path(config: &Config) -> Option<PathBuf> {
+    config.daemon.socket.clone().or_else(|| Config::data_dir().map(|dir| dir.join("daemon.sock")))
+}
+
+pub async fn write_message<T: Serialize>(stream: &mut (impl AsyncWrite + Unpin), message: &T) -> io::Result<()> {
+    let body = serde_json::to_vec(message)?;
+    let length = u32::try_from(body.len()).ok().filter(|length| *length <= MAX_MESSAGE).ok_or_else(too_long)?;
+    stream.write_all(&length.to_be_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.flush().await
+}
+
+pub async fn read_message<T: DeserializeOwned>(stream: &mut (impl AsyncRead + Unpin)) -> io::Result<T> {
+    let length = stream.read_u32().await?;
+    if length > MAX_MESSAGE {
+        return Err(too_long());
+    }
+    let mut body = vec![0; length as usize];
+    stream.read_exact(&mut body).await?;
+    Ok(serde_json::from_slice(&body)?)
+}
+
+fn too_long() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("message longer than {} bytes", MAX_MESSAGE))
+}
+
+// Sends a request to the daemon and returns its answer; None if no daemon is listening
+#[cfg(unix)]
+pub async fn request(config: &Config, request: &Request) -> Result<Option<Response>> {
+    let Some(path) = socket_path(config) else { return Ok(None) };
+    let mut stream = match tokio::net::UnixStream::connect(&path).await {
+        Ok(stream) => stream,
+        // No socket, or one left behind by a daemon that is gone
+        Err(e) if matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused) => return Ok(None),
+        Err(e) => return Err(no_answer(&path, e)),
+    };
+    let exchange = async {
+        write_message(&mut stream, request).await?;
+        read_message(&mut stream).await
+    };
+    match tokio::time::timeout(TIMEOUT, exchange).await {
+        Ok(Ok(response)) => Ok(Some(response)),
+        Ok(Err(e)) => Err(no_answer(&path, e)),
+        Err(_) => Err(no_answer(&path, io::ErrorKind::TimedOut.into())),
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn request(_config: &Config, _request: &Request) -> Result<Option<Response>> {
+    Ok(None)
+}
+
+#[cfg(unix)]
+fn no_answer(path: &std::path::Path, e: io::Error) -> crate::error::TaskError {
+    let message = format!("could not talk to the daemon at {}: {}", path.display(), e);
+    io::Error::new(e.kind(), message).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn messages_come_through_whole() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let request = Request::RunNow { jobs: vec!["sync".to_string(), "notify email".to_string()] };
+        let sent = request.clone();
+        let writer = tokio::spawn(async move { write_message(&mut client, &sent).await });
+
+        let received: Request = read_message(&mut server).await.unwrap();
+        writer.await.unwrap().unwrap();
+        assert_eq!(received, request);
+    }
+
+    #[tokio::test]
+    async fn messages_are_length_prefixed_json() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &Request::Reminders).await.unwrap();
+        let body = br#"{"request":"reminders"}"#;
+        assert_eq!(&buffer[..4], (body.len() as u32).to_be_bytes());
+        assert_eq!(&buffer[4..], body);
+    }
+
+    #[tokio::test]
+    async fn overlong_messages_are_refused() {
+        let mut buffer = (MAX_MESSAGE + 1).to_be_bytes().to_vec();
+        buffer.extend_from_slice(b"{}");
+        let error = read_message::<Request>(&mut buffer.as_slice()).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+
+        let error = read_message::<Request>(&mut &b"\0\0\0\x10{}"[..]).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,250 @@
+use serde::{Deserialize, Serialize};
+
+use crate::cli::JiraCommand;
+use crate::config::{Config, JiraConfig};
+use crate::error::{Result, TaskError};
+use crate::repository::{ExternalId, TaskRepository};
+use crate::secrets::{self, Service};
+use crate::sync::{http_client, send, send_json};
+
+const SERVICE: &str = "Jira";
+// `source` of the external ids; the id itself is the issue key, e.g. PROJ-123
+const SOURCE: &str = "jira";
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    key: String,
+    fields: IssueFields,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueFields {
+    summary: String,
+    status: Status,
+}
+
+#[derive(Debug, Deserialize)]
+struct Status {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Transitions {
+    transitions: Vec<Transition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Transition {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TransitionRequest<'a> {
+    transition: TransitionId<'a>,
+}
+
+#[derive(Debug, Serialize)]
+struct TransitionId<'a> {
+    id: &'a str,
+}
+
+struct Client {
+    http: reqwest::Client,
+    api: String,
+    email: Option<String>,
+    token: String,
+}
+
+impl Client {
+    fn new(config: &Config) -> Result<Self> {
+        let url = config.jira.url.as_deref().ok_or_else(|| {
+            TaskError::Config(format!(
+                "No Jira site configured. Set `url` in the [jira] section of {}.",
+                Config::path().display()
+            ))
+        })?;
+        Ok(Client {
+            http: http_client(SERVICE)?,
+            api: format!("{}/rest/api/2", url.trim_end_matches('/')),
+            email: config.jira.email.clone(),
+            token: secrets::token(config, Service::Jira)?,
+        })
+    }
+
+    // Jira Cloud takes the account email with an API token; Server and Data Center take a
+    // personal access token on its own
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.email {
+            Some(email) => request.basic_auth(email, Some(&self.token)),
+            None => request.bearer_auth(&self.token),
+        }
+    }
+
+    async fn issue(&self, key: &str) -> Result<Issue> {
+        let request = self.http.get(format!("{}/issue/{}", self.api, key)).query(&[("fields", "summary,status")]);
+        send_json(SERVICE, self.authorized(request)).await
+    }
+
+    // Moves the issue with the transition named `name` (e.g. "Done"), if the workflow offers it
+    async fn transition(&self, key: &str, name: &str) -> Result<()> {
+        let url = format!("{}/issue/{}/transitions", self.api, key);
+        let available: Transitions = send_json(SERVICE, self.authorized(self.http.get(&url))).await?;
+
+        let transition = available.transitions.iter().find(|t| t.name.eq_ignore_ascii_case(name)).ok_or_else(|| {
+            let names: Vec<&str> = available.transitions.iter().map(|t| t.name.as_str()).collect();
+            TaskError::Remote {
+                service: SERVICE,
+                message: format!("{} has no transition '{}' right now (available: {})", key, name, names.join(", ")),
+            }
+        })?;
+
+        let body = TransitionRequest { transition: TransitionId { id: &transition.id } };
+        send(SERVICE, self.authorized(self.http.post(&url)).json(&body)).await?;
+        Ok(())
+    }
+}
+
+pub async fn run(repo: &TaskRepository, config: &Config, command: JiraCommand) -> Result<()> {
+    match command {
+        JiraCommand::Link { id, key } => link(repo, config, id, &key).await,
+        JiraCommand::Unlink { id, key } => {
+            let key = key.as_deref().map(parse_key).transpose()?;
+            let removed = repo.unlink_external(id, SOURCE, key.as_deref()).await?;
+            match removed {
+                0 => println!("Task {} has no matching Jira link.", id),
+                n => println!("Removed {} Jira link(s) from task {}.", n, id),
+            }
+            Ok(())
+        }
+        JiraCommand::Status { id } => status(repo, config, id).await,
+        JiraCommand::Transition { dry_run } => transition_completed(repo, config, dry_run).await,
+    }
+}
+
+async fn link(repo: &TaskRepository, config: &Config, id: i32, key: &str) -> Result<()> {
+    let key = parse_key(key)?;
+    if repo.get(id).await?.is_none() {
+        println!("No task found with ID {}.", id);
+        return Ok(());
+    }
+    let external = ExternalId { source: SOURCE, id: key };
+    if repo.external_id_exists(&external).await? {
+        println!("{} is already linked to a task.", external.id);
+        return Ok(());
+    }
+
+    // Fetching the issue first catches typos in the key
+    let issue = Client::new(config)?.issue(&external.id).await?;
+    repo.link_external(id, &external).await?;
+    println!("Linked task {} to {} '{}' ({}).", id, issue.key, issue.fields.summary, issue.fields.status.name);
+    Ok(())
+}
+
+async fn status(repo: &TaskRepository, config: &Config, id: i32) -> Result<()> {
+    let keys = jira_keys(repo, id).await?;
+    if keys.is_empty() {
+        println!("Task {} is not linked to a Jira issue.", id);
+        return Ok(());
+    }
+
+    let client = Client::new(config)?;
+    for key in keys {
+        let issue = client.issue(&key).await?;
+        println!("{}  {}  [{}]", issue.key, issue.fields.summary, issue.fields.status.name);
+    }
+    Ok(())
+}
+
+// Applies the configured transition to the issues of tasks completed since they were linked
+// or last transitioned, for completions made outside the interactive menu
+async fn transition_completed(repo: &TaskRepository, config: &Config, dry_run: bool) -> Result<()> {
+    let name = transition_name(&config.jira)?;
+    let pending: Vec<_> = repo
+        .linked_tasks(SOURCE)
+        .await?
+        .into_iter()
+        .filter(|linked| linked.task.completed && linked.changed)
+        .collect();
+
+    if pending.is_empty() {
+        println!("No completed tasks with Jira issues to transition.");
+        return Ok(());
+    }
+    for linked in &pending {
+        println!("{} -> '{}' (task {} is completed)", linked.external_id, name, linked.task.id);
+    }
+    if dry_run {
+        println!("Dry run: {} issues not transitioned.", pending.len());
+        return Ok(());
+    }
+
+    let client = Client::new(config)?;
+    for linked in &pending {
+        client.transition(&linked.external_id, name).await?;
+        repo.mark_synced(&ExternalId { source: SOURCE, id: linked.external_id.clone() }).await?;
+    }
+    println!("Transitioned {} Jira issues.", pending.len());
+    Ok(())
+}
+
+// Called after a task was completed in the interactive menu. Does nothing unless a
+// transition is configured. A Jira failure is only reported, since the task itself is done;
+// `task jira transition` retries it later.
+pub async fn task_completed(repo: &TaskRepository, config: &Config, id: i32) -> Result<()> {
+    let Some(name) = config.jira.transition.as_deref() else {
+        return Ok(());
+    };
+    let keys = jira_keys(repo, id).await?;
+    if keys.is_empty() {
+        return Ok(());
+    }
+
+    let client = Client::new(config)?;
+    for key in keys {
+        match client.transition(&key, name).await {
+            Ok(()) => {
+                repo.mark_synced(&ExternalId { source: SOURCE, id: key.clone() }).await?;
+                println!("Moved {} to '{}'.", key, name);
+            }
+            Err(e) => println!("Warning: could not transition {}: {}", key, e),
+        }
+    }
+    Ok(())
+}
+
+async fn jira_keys(repo: &TaskRepository, id: i32) -> Result<Vec<String>> {
+    Ok(repo
+        .links(id)
+        .await?
+        .into_iter()
+        .filter(|(source, _)| source == SOURCE)
+        .map(|(_, key)| key)
+        .collect())
+}
+
+fn transition_name(config: &JiraConfig) -> Result<&str> {
+    config.transition.as_deref().ok_or_else(|| {
+        TaskError::Config(format!(
+            "No Jira transition configured. Set e.g. `transition = \"Done\"` in the [jira] section of {}.",
+            Config::path().display()
+        ))
+    })
+}
+
+// Issue keys are a project key (a letter, then letters, digits or '_') and a number,
+// e.g. PROJ-123. Lowercase input is accepted.
+fn parse_key(key: &str) -> Result<String> {
+    let key = key.trim().to_uppercase();
+    let valid = key.split_once('-').is_some_and(|(project, number)| {
+        project.starts_with(|c: char| c.is_ascii_alphabetic())
+            && project.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !number.is_empty()
+            && number.chars().all(|c| c.is_ascii_digit())
+    });
+    if valid {
+        Ok(key)
+    } else {
+        Err(TaskError::InvalidInput(format!("'{}' is not a Jira issue key like PROJ-123.", key)))
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeMap;
+
+use ldap3::{dn_escape, ldap_escape, LdapConnAsync, LdapConnSettings, Scope, SearchEntry};
+
+use crate::config::{Config, LdapGroup};
+use crate::error::{Result, TaskError};
+use crate::repository::{TaskRepository, User};
+use crate::roles::Role;
+
+const SERVICE: &str = "LDAP";
+const DEFAULT_FILTER: &str = "(|(uid={username})(sAMAccountName={username}))";
+// Result code of a bind with a wrong name or password
+const INVALID_CREDENTIALS: u32 = 49;
+
+// A directory (OpenLDAP, Active Directory) that checks passwords in place of the local hashes,
+// for organizations whose people can't have accounts of their own here. Someone logging in for
+// the first time gets a local user of the same name without a password, and on every login the
+// workspaces named in [ldap.groups] are brought in line with their groups.
+#[derive(Debug, Clone)]
+pub struct Directory {
+    url: String,
+    bind_dn: String,
+    base_dn: Option<String>,
+    user_filter: String,
+    starttls: bool,
+    groups: BTreeMap<String, LdapGroup>,
+}
+
+impl Directory {
+    // None unless [ldap] names a server
+    pub fn new(config: &Config) -> Result<Option<Self>> {
+        let ldap = &config.ldap;
+        let Some(url) = &ldap.url else {
+            return Ok(None);
+        };
+        let Some(bind_dn) = ldap.bind_dn.clone().filter(|dn| dn.contains("{username}")) else {
+            return Err(TaskError::Config(format!(
+                "Logins through LDAP need `bind_dn` with a {{username}} placeholder in the [ldap] section of {}.",
+                Config::path().display()
+            )));
+        };
+        Ok(Some(Directory {
+            url: url.clone(),
+            bind_dn,
+            base_dn: ldap.base_dn.clone(),
+            user_filter: ldap.user_filter.clone().unwrap_or_else(|| DEFAULT_FILTER.to_string()),
+            starttls: ldap.starttls,
+            groups: ldap.groups.clone(),
+        }))
+    }
+
+    // The local user for a name and password the directory accepts; None if it doesn't
+    pub async fn login(&self, repo: &TaskRepository, username: &str, password: &str) -> Result<Option<User>> {
+        // An empty password makes an unauthenticated bind, which servers accept for any name
+        if username.is_empty() || password.is_empty() {
+            return Ok(None);
+        }
+        let Some(groups) = self.bind(username, password).await? else {
+            return Ok(None);
+        };
+        let user = match repo.user_by_name(username).await? {
+            Some(user) => user,
+            None => {
+                // A hash no password matches (see the user_identities migration)
+                repo.create_user(username, "!").await?;
+                repo.user_by_name(username)
+                    .await?
+                    .ok_or_else(|| TaskError::Config(format!("The new user {} has disappeared.", username)))?
+            }
+        };
+        self.sync_roles(repo, &user, &groups).await?;
+        Ok(Some(user))
+    }
+
+    // Binds as the user and returns the DNs of their groups, or None for wrong credentials
+    async fn bind(&self, username: &str, password: &str) -> Result<Option<Vec<String>>> {
+        let settings = LdapConnSettings::new().set_starttls(self.starttls);
+        let (conn, mut ldap) = LdapConnAsync::with_settings(settings, &self.url).await.map_err(remote)?;
+        ldap3::drive!(conn);
+
+        let dn = self.bind_dn.replace("{username}", &dn_escape(username));
+        let result = ldap.simple_bind(&dn, password).await.map_err(remote)?;
+        if result.rc == INVALID_CREDENTIALS {
+            return Ok(None);
+        }
+        result.success().map_err(remote)?;
+
+        let mut groups = Vec::new();
+        if let Some(base_dn) = &self.base_dn
+            && !self.groups.is_empty()
+        {
+            let filter = self.user_filter.replace("{username}", &ldap_escape(username));
+            let search = ldap.search(base_dn, Scope::Subtree, &filter, vec!["memberOf"]).await.map_err(remote)?;
+            let (entries, _) = search.success().map_err(remote)?;
+            for entry in entries {
+                let mut entry = SearchEntry::construct(entry);
+                groups.extend(entry.attrs.remove("memberOf").unwrap_or_default());
+            }
+        }
+        let _ = ldap.unbind().await;
+        Ok(Some(groups))
+    }
+
+    // The workspaces of [ldap.groups] follow the directory: the user is in each with the highest
+    // role of their groups mapped to it, or not in it at all
+    async fn sync_roles(&self, repo: &TaskRepository, user: &User, groups: &[String]) -> Result<()> {
+        let mut roles: BTreeMap<&str, Option<Role>> = BTreeMap::new();
+        for (dn, group) in &self.groups {
+            let role = roles.entry(group.workspace.as_str()).or_insert(None);
+            if groups.iter().any(|member_of| member_of.eq_ignore_ascii_case(dn)) {
+                *role = Some(match *role {
+                    Some(current) if rank(current) >= rank(group.role) => current,
+                    _ => group.role,
+                });
+            }
+        }
+
+        // Made by the directory rather than by anyone's role
+        let repo = repo.clone().with_user(None);
+        for (workspace, role) in roles {
+            let Some(workspace_id) = repo.workspace_id(workspace).await? else {
+                println!("Warning: [ldap.groups] names workspace {}, which doesn't exist.", workspace);
+                continue;
+            };
+            match role {
+                Some(role) => {
+                    repo.add_member(workspace_id, user.id, role).await?;
+                    repo.set_role(workspace_id, user.id, role).await?;
+                }
+                None => {
+                    repo.remove_member(workspace_id, user.id).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn rank(role: Role) -> u8 {
+    match role {
+        Role::Viewer => 0,
+        Role::Member => 1,
+        Role::Admin => 2,
+    }
+}
+
+fn remote(e: ldap3::LdapError) -> TaskError {
+    TaskError::Remote { service: SERVICE, message: e.to_string() }
+}
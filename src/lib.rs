@@ -0,0 +1,127 @@
+// The tasks library: models, the repository over MySQL, and the services built on it (sync,
+// notifications, the server). The `task` binary is a thin CLI around it; anything else that
+// needs tasks, such as the tests, links it the same way. The server, the integrations and the
+// notification channels are cargo features, all on by default; see [features] in Cargo.toml.
+pub mod activity;
+pub mod admin;
+pub mod aging;
+pub mod api_tokens;
+pub mod assignments;
+pub mod backup;
+pub mod bulk;
+pub mod cache;
+pub mod chart;
+pub mod cli;
+pub mod config;
+pub mod daemon;
+pub mod db;
+pub mod doctor;
+pub mod error;
+pub mod escalation;
+pub mod estimates;
+pub mod export;
+#[cfg(test)]
+mod fixtures;
+pub mod hooks;
+pub mod ical;
+pub mod import;
+pub mod input;
+pub mod ipc;
+#[cfg(feature = "integrations")]
+pub mod jira;
+pub mod ldap;
+pub mod memory;
+pub mod logging;
+pub mod metrics;
+pub mod mirror;
+pub mod models;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod notes;
+pub mod notify;
+pub mod priority;
+pub mod privacy;
+pub mod profiles;
+pub mod repository;
+pub mod recurrence;
+pub mod reports;
+pub mod restore;
+pub mod review;
+pub mod roles;
+pub mod s3;
+pub mod schema;
+pub mod script;
+pub mod secrets;
+pub mod seed;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod settings;
+pub mod shares;
+pub mod stats;
+pub mod store;
+pub mod streaks;
+pub mod sync;
+#[cfg(feature = "integrations")]
+pub mod telegram;
+pub mod timesheet;
+pub mod users;
+pub mod views;
+pub mod webhooks;
+pub mod workspaces;
+
+use chrono::{Local, NaiveDateTime, NaiveTime, TimeZone};
+
+pub use models::Task;
+
+pub fn format_task(task: &Task) -> String {
+    let status = if task.completed { "[COMPLETED]" } else { "[PENDING]" };
+    
+    format!("ID: {}, {} Description: '{}' (Created: {})", task.id, status, task.description, format_timestamp(&task.created_at))
+}
+
+// FIX: Correctly converting NaiveDateTime from DB to DateTime<Local>
+pub fn format_timestamp(timestamp: &NaiveDateTime) -> String {
+    match Local.from_local_datetime(timestamp).earliest() { // Handles potential DST ambiguities by picking the earlier time
+        Some(local) => local.format("%Y-%m-%d %H:%M:%S").to_string(),
+        // The time falls into a DST gap and doesn't exist locally; show it as stored
+        None => timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+    }
+}
+
+// Due dates given without a time are stored at midnight; show just the date for those
+pub fn format_due(due_at: &NaiveDateTime) -> String {
+    if due_at.time() == NaiveTime::MIN {
+        due_at.format("%Y-%m-%d").to_string()
+    } else {
+        format_timestamp(due_at)
+    }
+}
+
+// Resolves on Ctrl-C or SIGTERM
+pub async fn shutdown_signal() {
+    // If a handler can't be installed we simply never see that signal; the default
+    // behaviour (terminating the process) still applies in that case.
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,71 @@
+use std::io;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+use crate::config::{Config, LogConfig, LogFormat};
+use crate::error::{Result, TaskError};
+
+// What is logged when neither TASK_LOG nor [log] level says: the retries of db.rs and the
+// failed requests of `task serve`, nothing from commands that go well
+const DEFAULT_FILTER: &str = "warn";
+
+// Log files are named task.log.YYYY-MM-DD, a new one each day
+const FILE_PREFIX: &str = "task.log";
+
+// Diagnostics go to stderr, so they never mix with what commands print or export to stdout.
+// Each command runs in a `command` span (see main.rs); repository calls log their operation,
+// the ids of the tasks they touch and how long they took at the debug level.
+//
+// The returned guard flushes the log file when dropped; keep it until the command is done.
+pub fn init(config: &LogConfig) -> Result<Option<WorkerGuard>> {
+    let directives = std::env::var("TASK_LOG")
+        .ok()
+        .or_else(|| config.level.clone())
+        .unwrap_or_else(|| DEFAULT_FILTER.to_string());
+    let filter = EnvFilter::try_new(&directives)
+        .map_err(|e| TaskError::Config(format!("Invalid log level '{}': {}", directives, e)))?;
+    let format = match std::env::var("TASK_LOG_FORMAT") {
+        Ok(format) => match format.trim().to_ascii_lowercase().as_str() {
+            "text" => LogFormat::Text,
+            "json" => LogFormat::Json,
+            _ => return Err(TaskError::Config(format!("TASK_LOG_FORMAT is '{}'; expected text or json.", format))),
+        },
+        Err(_) => config.format.unwrap_or_default(),
+    };
+
+    let console = match format {
+        LogFormat::Text => tracing_subscriber::fmt::layer().with_writer(io::stderr).boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer().json().with_writer(io::stderr).boxed(),
+    };
+
+    let mut guard = None;
+    let file = if config.file {
+        let directory = match config.directory.clone().or_else(Config::log_dir) {
+            Some(directory) => directory,
+            None => {
+                return Err(TaskError::Config(
+                    "[log] file is set but there is no log directory; set [log] directory or TASK_DATA_DIR."
+                        .to_string(),
+                ));
+            }
+        };
+        std::fs::create_dir_all(&directory).map_err(|e| {
+            TaskError::Config(format!("Could not create the log directory {}: {}", directory.display(), e))
+        })?;
+        let (writer, flush) = tracing_appender::non_blocking(tracing_appender::rolling::daily(directory, FILE_PREFIX));
+        guard = Some(flush);
+        Some(match format {
+            LogFormat::Text => tracing_subscriber::fmt::layer().with_ansi(false).with_writer(writer).boxed(),
+            LogFormat::Json => tracing_subscriber::fmt::layer().json().with_writer(writer).boxed(),
+        })
+    } else {
+        None
+    };
+
+    // Fails only if a subscriber is already set, as when a test set up its own
+    let _ = tracing_subscriber::registry().with(console).with(file).with(filter).try_init();
+    Ok(guard)
+}
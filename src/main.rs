@@ -1,68 +1,363 @@
-use sqlx::{mysql::MySqlPoolOptions, MySqlPool}; // `Row` import removed
+// The `task` command: parses the command line and runs it against the taskcore library. The
+// interactive menu lives here too, since nothing but the terminal uses it.
+
+use sqlx::MySqlPool; // `Row` import removed
 use dotenv::dotenv;
-use std::io::{self, Write};
-use chrono::{NaiveDateTime, Local, TimeZone}; // `TimeZone` imported for Local.from_local_datetime
-
-// Define a struct to represent our Task
-#[derive(Debug, sqlx::FromRow)]
-struct Task {
-    id: i32, // Corrected to i32 to match MySQL's INT
-    description: String,
-    completed: bool, // Correctly mapped from MySQL's TINYINT(1)
-    created_at: NaiveDateTime,
-}
+use std::io::{self, BufWriter};
+use std::process::ExitCode;
+use std::time::Instant;
+use clap::{ArgMatches, CommandFactory, FromArgMatches};
+use tracing::Instrument;
+use tokio::sync::watch;
+use taskcore::cli::{
+    Cli, Command, DaemonCommand, ExportCommand, ImportCommand, ScriptCommand, StatsCommand, TokenCommand,
+};
+#[cfg(feature = "notifications")]
+use taskcore::cli::{Channel, NotifyCommand, SmsCommand};
+#[cfg(feature = "integrations")]
+use taskcore::cli::SyncCommand;
+use taskcore::config::Config;
+use taskcore::error::Result;
+use taskcore::input::Input;
+use taskcore::repository::{self, TaskRepository, Updated};
+use taskcore::settings::{self, View};
+use taskcore::webhooks::{self, WebhookEvent, Webhooks};
+use taskcore::{
+    activity, admin, api_tokens, assignments, backup, daemon, db, doctor, escalation, estimates, export, import,
+    logging, mirror, notes, notify, profiles, recurrence, reports, restore, review, schema, script, secrets, seed,
+    shares, stats, timesheet, users, views, workspaces,
+};
+#[cfg(feature = "server")]
+use taskcore::server;
+#[cfg(feature = "integrations")]
+use taskcore::{jira, sync, telegram};
+use taskcore::shutdown_signal;
 
 #[tokio::main]
-async fn main() -> Result<(), sqlx::Error> {
+async fn main() -> ExitCode {
+    let matches = Cli::command().get_matches();
+    let name = command_name(&matches);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    match run(cli, &name).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+// The subcommands given, as in "export json", for the span of the command
+fn command_name(matches: &ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut matches = matches;
+    while let Some((name, sub)) = matches.subcommand() {
+        names.push(name);
+        matches = sub;
+    }
+    if names.is_empty() { "menu".to_string() } else { names.join(" ") }
+}
+
+async fn run(cli: Cli, name: &str) -> Result<()> {
     dotenv().ok(); // Load environment variables from .env file
+    // then the user's own, for what the one in the working directory doesn't set
+    if let Some(dir) = Config::config_dir() {
+        dotenv::from_path(dir.join(".env")).ok();
+    }
+
+    let config = Config::load()?;
+    let _log = logging::init(&config.log)?;
+
+    let span = tracing::info_span!("command", command = name);
+    let started = Instant::now();
+    let result = run_with_config(cli, &config).instrument(span.clone()).await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+    span.in_scope(|| match &result {
+        Ok(()) => tracing::info!(elapsed_ms, "command finished"),
+        Err(e) => tracing::info!(elapsed_ms, error = %e, exit_code = e.exit_code(), "command failed"),
+    });
+    result
+}
+
+async fn run_with_config(cli: Cli, config: &Config) -> Result<()> {
+    // Profile commands manage connections themselves
+    if let Some(Command::Profile { command }) = &cli.command {
+        return profiles::run(config, cli.profile.as_deref(), command).await;
+    }
 
-    let database_url = std::env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set in .env file");
+    // Tokens live in the keyring and don't need the database
+    if let Some(Command::Token { command }) = &cli.command {
+        return match command {
+            TokenCommand::Set { service } => secrets::set(*service),
+            TokenCommand::Delete { service } => secrets::delete(*service),
+        };
+    }
 
     // Create a connection pool
-    let pool = MySqlPoolOptions::new()
-        .max_connections(5) // Max 5 connections in the pool
-        .connect(&database_url)
-        .await?;
+    let settings = config.resolve(cli.profile.as_deref())?;
+    let pool = db::connect(&settings).await?;
 
+    let result = run_command(cli.command, &pool, config, cli.profile.as_deref()).await;
+
+    // Wait for checked-out connections to be returned and close them properly
+    pool.close().await;
+
+    result
+}
+
+async fn run_command(command: Option<Command>, pool: &MySqlPool, config: &Config, profile: Option<&str>) -> Result<()> {
+    // Refuse to run against an outdated schema; queries would otherwise fail in confusing ways.
+    // `doctor` reports the schema version itself.
+    if !matches!(command, Some(Command::Migrate | Command::Doctor)) {
+        schema::check_version(pool).await?;
+    }
+
+    let repo = TaskRepository::new(pool.clone(), repository::os_user())
+        .with_retry_policy(config.retry)
+        .with_query_timeout(config.query_timeout())
+        .with_bulk_policy(config.bulk)
+        .with_cache_policy(config.cache);
+    // Once there are accounts, commands on tasks run as the logged-in user. Admin commands see
+    // the whole database, the Telegram bot acts as the accounts its chats are mapped to, and
+    // the account commands check for themselves.
+    let repo = match &command {
+        Some(
+            Command::Migrate
+            | Command::Doctor
+            | Command::Backup(_)
+            | Command::Restore(_)
+            | Command::Telegram
+            | Command::Login { .. }
+            | Command::Logout
+            | Command::User { .. },
+        ) => repo,
+        _ => users::authenticate(repo).await?,
+    };
+    // Settings of the account fill in what the config file leaves out; `task settings` shows
+    // the two apart
+    let local = config;
+    let config = &settings::merge(&repo, config).await?;
+    settings::apply_timezone(config);
+
+    match command {
+        Some(Command::Migrate) => schema::migrate(pool).await?,
+        Some(Command::Doctor) => doctor::run(pool).await?,
+        Some(Command::Profile { .. } | Command::Token { .. }) => unreachable!("handled before connecting"),
+        Some(Command::Seed(args)) => seed::run(pool, &repo, &config.retry, args).await?,
+        Some(Command::Backup(args)) => backup::run(pool, config, args).await?,
+        Some(Command::Restore(args)) => restore::run(pool, config, args).await?,
+        Some(Command::List) => views::all(&repo, &mut BufWriter::new(io::stdout().lock())).await?,
+        Some(Command::Show { id }) => views::details(&repo, id, &mut io::stdout()).await?,
+        Some(Command::Search { terms }) => views::search(&repo, &terms.join(" "), None, &mut io::stdout()).await?,
+        Some(Command::Export { command: ExportCommand::Json { output } }) => {
+            export::json::run(&repo, output.as_deref()).await?
+        }
+        Some(Command::Export { command: ExportCommand::Ics { output, events } }) => {
+            export::ics::run(&repo, output.as_deref(), events).await?
+        }
+        Some(Command::Export { command: ExportCommand::Todotxt { output } }) => {
+            export::todotxt::run(&repo, output.as_deref()).await?
+        }
+        Some(Command::Export { command: ExportCommand::Org { output } }) => {
+            export::org::run(&repo, output.as_deref()).await?
+        }
+        Some(Command::Export { command: ExportCommand::Xlsx { output } }) => {
+            export::xlsx::run(&repo, output.as_deref()).await?
+        }
+        Some(Command::Export { command: ExportCommand::Report(args) }) => export::report::run(&repo, args).await?,
+        Some(Command::Import { command: ImportCommand::Csv(args) }) => import::csv::run(&repo, args).await?,
+        Some(Command::Import { command: ImportCommand::Json(args) }) => import::json::run(&repo, args).await?,
+        Some(Command::Import { command: ImportCommand::Todotxt(args) }) => import::todotxt::run(&repo, args).await?,
+        Some(Command::Import { command: ImportCommand::Taskwarrior(args) }) => {
+            import::taskwarrior::run(&repo, args).await?
+        }
+        Some(Command::Import { command: ImportCommand::Trello(args) }) => import::trello::run(&repo, args).await?,
+        Some(Command::Import { command: ImportCommand::Ics(args) }) => import::ics::run(&repo, args).await?,
+        Some(Command::Estimate { id, duration }) => estimates::run(&repo, id, duration.as_deref()).await?,
+        Some(Command::Recur { id, every }) => recurrence::run(&repo, id, every.as_deref()).await?,
+        Some(Command::Time { command }) => timesheet::run(&repo, command).await?,
+        Some(Command::Review(args)) => review::run(&repo, args).await?,
+        Some(Command::Report(args)) => reports::run(&repo, config, args).await?,
+        Some(Command::Stats { command: None }) => stats::tasks(&repo).await?,
+        Some(Command::Stats { command: Some(StatsCommand::Db) }) => stats::db(&repo).await?,
+        Some(Command::Stats { command: Some(StatsCommand::Burndown { weeks }) }) => {
+            stats::burndown(&repo, weeks).await?
+        }
+        Some(Command::Stats { command: Some(StatsCommand::Breakdown) }) => stats::breakdown(&repo).await?,
+        Some(Command::Stats { command: Some(StatsCommand::Velocity { weeks }) }) => {
+            stats::velocity(&repo, weeks).await?
+        }
+        Some(Command::Stats { command: Some(StatsCommand::Heatmap { project }) }) => {
+            stats::heatmap(&repo, project.as_deref()).await?
+        }
+        Some(Command::Stats { command: Some(StatsCommand::Age { weeks }) }) => stats::age(&repo, weeks).await?,
+        Some(Command::Stats { command: Some(StatsCommand::Forecast { ahead, weekly }) }) => {
+            stats::forecast(&repo, &config.forecast, ahead, weekly).await?
+        }
+        Some(command @ (Command::Sync { .. } | Command::Jira { .. } | Command::Telegram)) => {
+            run_integration(&repo, config, command).await?
+        }
+        Some(command @ (Command::Notify { .. } | Command::Watch(_) | Command::Sms { .. })) => {
+            run_notification(&repo, config, command).await?
+        }
+        Some(Command::Script { command: ScriptCommand::Run(args) }) => script::run(&repo, config, args).await?,
+        Some(Command::Daemon { command: None }) => daemon::run(&repo, config, profile).await?,
+        Some(Command::Daemon { command: Some(DaemonCommand::Status) }) => daemon::status(&repo).await?,
+        Some(Command::Daemon { command: Some(DaemonCommand::Run { jobs }) }) => {
+            daemon::run_now(&repo, config, jobs).await?
+        }
+        Some(Command::Daemon { command: Some(DaemonCommand::Reminders) }) => daemon::reminders(&repo, config).await?,
+        Some(Command::Mirror) => mirror::run(&repo, config).await?,
+        Some(Command::Escalate { command }) => escalation::run(&repo, config, command).await?,
+        Some(Command::Webhook { command }) => webhooks::run(&repo, config, command).await?,
+        #[cfg(feature = "server")]
+        Some(Command::Serve(args)) => server::run(repo, config, args).await?,
+        #[cfg(not(feature = "server"))]
+        Some(Command::Serve(_)) => return Err(taskcore::error::TaskError::not_built("server", "server")),
+        Some(Command::Login { username }) => users::login(&repo, config, username).await?,
+        Some(Command::Logout) => users::logout(&repo).await?,
+        Some(Command::User { command }) => users::run(repo, config, command).await?,
+        Some(Command::Assign { id, user }) => assignments::assign(&repo, id, user.as_deref()).await?,
+        Some(Command::Assigned) => assignments::list(&repo).await?,
+        Some(Command::Note { id, text }) => notes::add(&repo, id, &text.join(" ")).await?,
+        Some(Command::Share { command }) => shares::run(&repo, command).await?,
+        Some(Command::Workspace { command }) => workspaces::run(&repo, command).await?,
+        Some(Command::ApiToken { command }) => api_tokens::run(&repo, command).await?,
+        Some(Command::Activity { user, limit, before }) => activity::run(&repo, user.as_deref(), limit, before).await?,
+        Some(Command::Admin { command }) => admin::run(&repo, config, command).await?,
+        Some(Command::Settings { command }) => settings::run(&repo, local, command).await?,
+        None => match config.default_view.unwrap_or(View::Menu) {
+            View::Menu => run_interactive(&repo, config).await?,
+            View::List => views::all(&repo, &mut BufWriter::new(io::stdout().lock())).await?,
+            View::Assigned => assignments::list(&repo).await?,
+        },
+    }
+
+    Ok(())
+}
+
+// `task sync`, `task jira` and `task telegram`
+#[cfg(feature = "integrations")]
+async fn run_integration(repo: &TaskRepository, config: &Config, command: Command) -> Result<()> {
+    match command {
+        Command::Sync { command: SyncCommand::Todoist(args) } => sync::todoist::run(repo, config, args).await,
+        Command::Sync { command: SyncCommand::Github(args) } => sync::github::run(repo, config, args).await,
+        Command::Sync { command: SyncCommand::Gitlab(args) } => sync::gitlab::run(repo, config, args).await,
+        Command::Sync { command: SyncCommand::Caldav(args) } => sync::caldav::run(repo, config, args).await,
+        Command::Sync { command: SyncCommand::Google(args) } => sync::google::run(repo, config, args).await,
+        Command::Sync { command: SyncCommand::Notion(args) } => sync::notion::run(repo, config, args).await,
+        Command::Sync { command: SyncCommand::Vault(args) } => sync::vault::run(repo, config, args).await,
+        Command::Jira { command } => jira::run(repo, config, command).await,
+        Command::Telegram => telegram::run(repo, config).await,
+        _ => unreachable!("not a command of the integrations"),
+    }
+}
+
+#[cfg(not(feature = "integrations"))]
+async fn run_integration(_repo: &TaskRepository, _config: &Config, _command: Command) -> Result<()> {
+    Err(taskcore::error::TaskError::not_built("integrations", "integrations"))
+}
+
+// `task notify`, `task watch` and `task sms`
+#[cfg(feature = "notifications")]
+async fn run_notification(repo: &TaskRepository, config: &Config, command: Command) -> Result<()> {
+    match command {
+        Command::Notify { command: NotifyCommand::Email(args) } => notify::email::run(repo, config, args).await,
+        Command::Notify { command: NotifyCommand::Slack(args) } => {
+            notify::post_overdue(repo, config, Channel::Slack, args.dry_run).await
+        }
+        Command::Notify { command: NotifyCommand::Discord(args) } => {
+            notify::post_overdue(repo, config, Channel::Discord, args.dry_run).await
+        }
+        Command::Notify { command: NotifyCommand::Sms(args) } => notify::sms::run(repo, config, args).await,
+        Command::Notify { command: NotifyCommand::Test { channel } } => notify::test(config, channel).await,
+        Command::Watch(args) => notify::desktop::watch(repo, config, args).await,
+        Command::Sms { command: SmsCommand::Enable { id } } => notify::sms::set_alert(repo, id, true).await,
+        Command::Sms { command: SmsCommand::Disable { id } } => notify::sms::set_alert(repo, id, false).await,
+        Command::Sms { command: SmsCommand::List } => notify::sms::list(repo).await,
+        _ => unreachable!("not a command of the notifications"),
+    }
+}
+
+#[cfg(not(feature = "notifications"))]
+async fn run_notification(_repo: &TaskRepository, _config: &Config, _command: Command) -> Result<()> {
+    Err(taskcore::error::TaskError::not_built("notifications", "notifications"))
+}
+
+async fn run_interactive(repo: &TaskRepository, config: &Config) -> Result<()> {
     println!("Connected to MySQL database!");
 
+    // Flip the shutdown flag on Ctrl-C/SIGTERM. Pending prompts are cancelled right away,
+    // while a query that is already running is allowed to finish before we exit.
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = shutdown_tx.send(true);
+    });
+
+    let mut input = Input::new(shutdown_rx.clone());
+    let webhooks = Webhooks::new(config)?;
+
     loop {
         println!("\n--- Task Management CLI ---");
         println!("1. Add Task");
         println!("2. List Tasks");
         println!("3. Mark Task as Completed");
         println!("4. Delete Task");
-        println!("5. Exit");
-        print!("Enter your choice: ");
-        io::stdout().flush().unwrap(); // Ensure the prompt is displayed
-
-        let mut choice = String::new();
-        io::stdin().read_line(&mut choice).expect("Failed to read line");
-        let choice = choice.trim();
-
-        match choice {
-            "1" => add_task(&pool).await?,
-            "2" => list_tasks(&pool).await?,
-            "3" => mark_task_completed(&pool).await?,
-            "4" => delete_task(&pool).await?,
-            "5" => {
+        println!("5. Search Tasks");
+        println!("6. Show Task Details");
+        println!("7. Database Statistics");
+        println!("8. Exit");
+
+        // `None` means stdin was closed or a shutdown signal arrived
+        let Some(choice) = input.prompt("Enter your choice: ").await else {
+            break;
+        };
+
+        let result = match choice.trim() {
+            "1" => add_task(repo, &webhooks, &mut input).await,
+            "2" => views::list(repo, Some(&mut input), &mut io::stdout()).await,
+            "3" => mark_task_completed(repo, config, &webhooks, &mut input).await,
+            "4" => delete_task(repo, &webhooks, &mut input).await,
+            "5" => search_tasks(repo, &mut input).await,
+            "6" => show_task(repo, &mut input).await,
+            "7" => {
+                stats::print_db_stats(repo);
+                Ok(())
+            },
+            "8" => {
                 println!("Exiting application. Goodbye!");
                 break;
             },
-            _ => println!("Invalid choice. Please try again."),
+            _ => {
+                println!("Invalid choice. Please try again.");
+                Ok(())
+            },
+        };
+
+        // A failed command shouldn't end the whole session
+        if let Err(e) = result {
+            println!("Error: {}", e);
+        }
+
+        if *shutdown_rx.borrow() {
+            break;
         }
     }
 
+    if *shutdown_rx.borrow() {
+        println!("\nShutdown requested, closing database connections...");
+    }
+
     Ok(())
 }
 
-async fn add_task(pool: &MySqlPool) -> Result<(), sqlx::Error> {
-    print!("Enter task description: ");
-    io::stdout().flush().unwrap();
 
-    let mut description = String::new();
-    io::stdin().read_line(&mut description).expect("Failed to read line");
+async fn add_task(repo: &TaskRepository, webhooks: &Webhooks, input: &mut Input) -> Result<()> {
+    let Some(description) = input.prompt("Enter task description: ").await else {
+        return Ok(());
+    };
     let description = description.trim();
 
     if description.is_empty() {
@@ -70,53 +365,49 @@ async fn add_task(pool: &MySqlPool) -> Result<(), sqlx::Error> {
         return Ok(());
     }
 
-    let query = sqlx::query!(
-        "INSERT INTO tasks (description) VALUES (?)",
-        description
-    );
+    let id = repo.add(description).await?;
+    println!("Task '{}' added successfully!", description);
+    webhooks.task_event(repo, WebhookEvent::Created, id).await
+}
 
-    let result = query.execute(pool).await?;
+async fn search_tasks(repo: &TaskRepository, input: &mut Input) -> Result<()> {
+    let Some(query) = input.prompt("Enter search terms: ").await else {
+        return Ok(());
+    };
+    let query = query.trim();
 
-    if result.rows_affected() > 0 {
-        println!("Task '{}' added successfully!", description);
-    } else {
-        println!("Failed to add task.");
+    if query.is_empty() {
+        println!("Search terms cannot be empty.");
+        return Ok(());
     }
-    Ok(())
-}
-
-async fn list_tasks(pool: &MySqlPool) -> Result<(), sqlx::Error> {
-    let tasks: Vec<Task> = sqlx::query_as!(
-        Task,
-        "SELECT id, description, completed AS 'completed!: bool', created_at FROM tasks ORDER BY created_at DESC"
-    )
-    .fetch_all(pool)
-    .await?;
 
-    if tasks.is_empty() {
-        println!("No tasks found.");
-    } else {
-        println!("\n--- Your Tasks ---");
-        for task in tasks {
-            let status = if task.completed { "[COMPLETED]" } else { "[PENDING]" };
-            
-            // FIX: Correctly converting NaiveDateTime from DB to DateTime<Local>
-            let created_at_local: chrono::DateTime<Local> = Local.from_local_datetime(&task.created_at)
-                .earliest() // Handles potential DST ambiguities by picking the earlier time
-                .expect("Failed to convert naive datetime to local datetime"); // Will panic if conversion is impossible (e.g., non-existent time during DST)
+    views::search(repo, query, Some(input), &mut io::stdout()).await
+}
 
-            println!("ID: {}, {} Description: '{}' (Created: {})", task.id, status, task.description, created_at_local.format("%Y-%m-%d %H:%M:%S"));
+async fn show_task(repo: &TaskRepository, input: &mut Input) -> Result<()> {
+    let Some(task_id_str) = input.prompt("Enter the ID of the task to show: ").await else {
+        return Ok(());
+    };
+    let task_id: i32 = match task_id_str.trim().parse() {
+        Ok(num) => num,
+        Err(_) => {
+            println!("Invalid task ID. Please enter a number.");
+            return Ok(());
         }
-    }
-    Ok(())
-}
+    };
 
-async fn mark_task_completed(pool: &MySqlPool) -> Result<(), sqlx::Error> {
-    print!("Enter the ID of the task to mark as completed: ");
-    io::stdout().flush().unwrap();
+    views::details(repo, task_id, &mut io::stdout()).await
+}
 
-    let mut task_id_str = String::new();
-    io::stdin().read_line(&mut task_id_str).expect("Failed to read line");
+async fn mark_task_completed(
+    repo: &TaskRepository,
+    config: &Config,
+    webhooks: &Webhooks,
+    input: &mut Input,
+) -> Result<()> {
+    let Some(task_id_str) = input.prompt("Enter the ID of the task to mark as completed: ").await else {
+        return Ok(());
+    };
     // FIX: Parsing target changed to i32 for consistency with Task.id
     let task_id: i32 = match task_id_str.trim().parse() {
         Ok(num) => num,
@@ -126,27 +417,24 @@ async fn mark_task_completed(pool: &MySqlPool) -> Result<(), sqlx::Error> {
         }
     };
 
-    let query = sqlx::query!(
-        "UPDATE tasks SET completed = TRUE WHERE id = ?",
-        task_id
-    );
-
-    let result = query.execute(pool).await?;
-
-    if result.rows_affected() > 0 {
-        println!("Task with ID {} marked as completed.", task_id);
-    } else {
-        println!("No task found with ID {}. Nothing updated.", task_id);
+    match repo.complete(task_id).await? {
+        Some(Updated { completed: true }) => {
+            println!("Task with ID {} marked as completed.", task_id);
+            #[cfg(feature = "integrations")]
+            jira::task_completed(repo, config, task_id).await?;
+            notify::task_completed(repo, config, task_id).await?;
+            webhooks.task_event(repo, WebhookEvent::Completed, task_id).await?;
+        }
+        Some(_) => println!("Task with ID {} was already completed.", task_id),
+        None => println!("No task found with ID {}. Nothing updated.", task_id),
     }
     Ok(())
 }
 
-async fn delete_task(pool: &MySqlPool) -> Result<(), sqlx::Error> {
-    print!("Enter the ID of the task to delete: ");
-    io::stdout().flush().unwrap();
-
-    let mut task_id_str = String::new();
-    io::stdin().read_line(&mut task_id_str).expect("Failed to read line");
+async fn delete_task(repo: &TaskRepository, webhooks: &Webhooks, input: &mut Input) -> Result<()> {
+    let Some(task_id_str) = input.prompt("Enter the ID of the task to delete: ").await else {
+        return Ok(());
+    };
     // FIX: Parsing target changed to i32 for consistency with Task.id
     let task_id: i32 = match task_id_str.trim().parse() {
         Ok(num) => num,
@@ -156,15 +444,9 @@ async fn delete_task(pool: &MySqlPool) -> Result<(), sqlx::Error> {
         }
     };
 
-    let query = sqlx::query!(
-        "DELETE FROM tasks WHERE id = ?",
-        task_id
-    );
-
-    let result = query.execute(pool).await?;
-
-    if result.rows_affected() > 0 {
+    if repo.delete(task_id).await? {
         println!("Task with ID {} deleted successfully.", task_id);
+        webhooks.task_event(repo, WebhookEvent::Deleted, task_id).await?;
     } else {
         println!("No task found with ID {}. Nothing deleted.", task_id);
     }
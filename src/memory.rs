@@ -0,0 +1,323 @@
+// A `TaskStore` that keeps its tasks in memory, for unit tests of the command layer. It
+// follows the repository where the views can tell: tasks come newest first, pages continue
+// after their cursor, search matches a substring regardless of case and a store working as an
+// account sees the tasks that account owns or is assigned. Shares, workspaces, roles and the
+// activity log aren't modelled.
+
+use std::sync::Mutex;
+
+use chrono::{Local, NaiveDateTime, Timelike};
+use futures::stream::{self, BoxStream, StreamExt};
+
+use crate::repository::{
+    ChecklistItem, ListCursor, Note, Page, SearchCursor, TaskBundle, TaskChanges, TaskFilter, Updated,
+};
+use crate::store::TaskStore;
+use crate::Task;
+
+#[derive(Debug, Default)]
+pub struct MemoryStore {
+    state: Mutex<State>,
+    user: Option<i32>,
+    actor: String,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    tasks: Vec<Stored>,
+    // (id, name)
+    projects: Vec<(i32, String)>,
+    users: Vec<(i32, String)>,
+    last_id: i32,
+}
+
+#[derive(Debug)]
+struct Stored {
+    task: Task,
+    tags: Vec<String>,
+    checklist: Vec<ChecklistItem>,
+    notes: Vec<Note>,
+    links: Vec<(String, String)>,
+}
+
+impl MemoryStore {
+    // An empty store whose changes are recorded as made by `actor`
+    pub fn new(actor: &str) -> Self {
+        MemoryStore { actor: actor.to_string(), ..Default::default() }
+    }
+
+    pub fn with_user(mut self, user: Option<i32>) -> Self {
+        self.user = user;
+        self
+    }
+
+    // Creates an account and returns its id
+    pub fn add_user(&self, username: &str) -> i32 {
+        let mut state = self.state.lock().unwrap();
+        let id = state.users.len() as i32 + 1;
+        state.users.push((id, username.to_string()));
+        id
+    }
+
+    // Stores tasks with everything that hangs off them, as an import would; projects are
+    // created by name as needed. Returns the new ids in order.
+    pub fn import(&self, bundles: &[TaskBundle]) -> Vec<i32> {
+        let mut state = self.state.lock().unwrap();
+        let mut ids = Vec::with_capacity(bundles.len());
+        for bundle in bundles {
+            let project_id = match &bundle.project {
+                Some(name) => Some(state.project_id(name)),
+                None => bundle.task.project_id,
+            };
+            let id = bundle.task.id.unwrap_or(state.last_id + 1);
+            state.last_id = state.last_id.max(id);
+            let new = &bundle.task;
+            state.tasks.push(Stored {
+                task: Task {
+                    id,
+                    description: new.description.clone(),
+                    completed: new.completed,
+                    created_at: new.created_at,
+                    created_by: new.created_by.clone(),
+                    updated_by: new.updated_by.clone(),
+                    updated_at: new.updated_at,
+                    due_at: new.due_at,
+                    priority: new.priority,
+                    project_id,
+                    owner_id: new.owner_id.or(self.user),
+                    assignee_id: new.assignee_id,
+                    workspace_id: new.workspace_id,
+                },
+                tags: bundle.tags.clone(),
+                checklist: bundle.checklist.clone(),
+                notes: bundle.notes.clone(),
+                links: bundle
+                    .external
+                    .iter()
+                    .map(|external| (external.source.to_string(), external.id.clone()))
+                    .collect(),
+            });
+            ids.push(id);
+        }
+        ids
+    }
+
+    fn visible(&self, task: &Task) -> bool {
+        match self.user {
+            None => true,
+            Some(user) => task.owner_id == Some(user) || task.assignee_id == Some(user),
+        }
+    }
+
+    // The visible tasks for which `matches` holds, newest first, strictly after `after`
+    fn page(
+        &self,
+        after: Option<ListCursor>,
+        limit: u32,
+        matches: impl Fn(&State, &Stored) -> bool,
+    ) -> Page<ListCursor> {
+        let state = self.state.lock().unwrap();
+        let mut tasks: Vec<Task> = state
+            .tasks
+            .iter()
+            .filter(|stored| self.visible(&stored.task) && matches(&state, stored))
+            .filter(|stored| match after {
+                Some(cursor) => (stored.task.created_at, stored.task.id) < (cursor.created_at, cursor.id),
+                None => true,
+            })
+            .map(|stored| stored.task.clone())
+            .collect();
+        tasks.sort_by_key(|task| std::cmp::Reverse((task.created_at, task.id)));
+
+        let next = if tasks.len() > limit as usize {
+            tasks.truncate(limit as usize);
+            tasks.last().map(|task| ListCursor { created_at: task.created_at, id: task.id })
+        } else {
+            None
+        };
+        Page { tasks, next }
+    }
+
+    // Runs `f` on a visible task; None if there is no such task
+    fn with_task<T>(&self, id: i32, f: impl FnOnce(&mut Stored) -> T) -> Option<T> {
+        let mut state = self.state.lock().unwrap();
+        state.tasks.iter_mut().find(|stored| stored.task.id == id && self.visible(&stored.task)).map(f)
+    }
+
+    fn touch(&self, task: &mut Task) {
+        task.updated_at = now();
+        task.updated_by = Some(self.actor.clone());
+    }
+}
+
+impl State {
+    fn project_id(&mut self, name: &str) -> i32 {
+        if let Some((id, _)) = self.projects.iter().find(|(_, project)| project == name) {
+            return *id;
+        }
+        let id = self.projects.len() as i32 + 1;
+        self.projects.push((id, name.to_string()));
+        id
+    }
+}
+
+// MySQL's DATETIME keeps whole seconds
+fn now() -> NaiveDateTime {
+    let now = Local::now().naive_local();
+    now.with_nanosecond(0).unwrap_or(now)
+}
+
+impl TaskStore for MemoryStore {
+    fn user(&self) -> Option<i32> {
+        self.user
+    }
+
+    async fn add(&self, description: &str) -> Result<i32, sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.last_id += 1;
+        let id = state.last_id;
+        let now = now();
+        state.tasks.push(Stored {
+            task: Task {
+                id,
+                description: description.to_string(),
+                completed: false,
+                created_at: now,
+                created_by: Some(self.actor.clone()),
+                updated_by: Some(self.actor.clone()),
+                updated_at: now,
+                due_at: None,
+                priority: None,
+                project_id: None,
+                owner_id: self.user,
+                assignee_id: None,
+                workspace_id: None,
+            },
+            tags: Vec::new(),
+            checklist: Vec::new(),
+            notes: Vec::new(),
+            links: Vec::new(),
+        });
+        Ok(id)
+    }
+
+    async fn get(&self, id: i32) -> Result<Option<Task>, sqlx::Error> {
+        Ok(self.with_task(id, |stored| stored.task.clone()))
+    }
+
+    async fn complete(&self, id: i32) -> Result<Option<Updated>, sqlx::Error> {
+        Ok(self.with_task(id, |stored| {
+            let completed = !stored.task.completed;
+            if completed {
+                stored.task.completed = true;
+                self.touch(&mut stored.task);
+            }
+            Updated { completed }
+        }))
+    }
+
+    async fn delete(&self, id: i32) -> Result<bool, sqlx::Error> {
+        let mut state = self.state.lock().unwrap();
+        let before = state.tasks.len();
+        state.tasks.retain(|stored| stored.task.id != id || !self.visible(&stored.task));
+        Ok(state.tasks.len() < before)
+    }
+
+    async fn update(&self, id: i32, changes: &TaskChanges) -> Result<bool, sqlx::Error> {
+        Ok(self
+            .with_task(id, |stored| {
+                if changes.is_empty() {
+                    return;
+                }
+                let task = &mut stored.task;
+                if let Some(description) = &changes.description {
+                    task.description = description.clone();
+                }
+                if let Some(completed) = changes.completed {
+                    task.completed = completed;
+                }
+                if let Some(due_at) = changes.due_at {
+                    task.due_at = due_at;
+                }
+                if let Some(priority) = changes.priority {
+                    task.priority = priority;
+                }
+                self.touch(task);
+            })
+            .is_some())
+    }
+
+    fn stream_all(&self) -> BoxStream<'_, Result<Task, sqlx::Error>> {
+        let page = self.page(None, u32::MAX, |_, _| true);
+        stream::iter(page.tasks.into_iter().map(Ok)).boxed()
+    }
+
+    async fn list_page(&self, after: Option<ListCursor>, limit: u32) -> Result<Page<ListCursor>, sqlx::Error> {
+        Ok(self.page(after, limit, |_, _| true))
+    }
+
+    async fn filter_page(
+        &self,
+        filter: &TaskFilter,
+        after: Option<ListCursor>,
+        limit: u32,
+    ) -> Result<Page<ListCursor>, sqlx::Error> {
+        Ok(self.page(after, limit, |state, stored| {
+            let task = &stored.task;
+            let project = |name: &String| {
+                state.projects.iter().any(|(id, project)| project == name && task.project_id == Some(*id))
+            };
+            filter.completed.is_none_or(|completed| task.completed == completed)
+                && filter.project.as_ref().is_none_or(project)
+                && filter.tag.as_ref().is_none_or(|tag| stored.tags.contains(tag))
+                && filter.priority.is_none_or(|priority| task.priority == Some(priority))
+                && filter.due_before.is_none_or(|before| task.due_at.is_some_and(|due| due < before))
+                && filter.due_after.is_none_or(|after| task.due_at.is_some_and(|due| due >= after))
+                && filter.assignee.is_none_or(|assignee| task.assignee_id == Some(assignee))
+        }))
+    }
+
+    async fn search_page(
+        &self,
+        query: &str,
+        after: Option<SearchCursor>,
+        limit: u32,
+    ) -> Result<Page<SearchCursor>, sqlx::Error> {
+        // Like the repository's LIKE fallback: no relevance, so results keep the list order
+        let after = match after {
+            Some(SearchCursor::Recency(cursor)) => Some(cursor),
+            Some(SearchCursor::Relevance { .. }) | None => None,
+        };
+        let query = query.to_lowercase();
+        let page = self.page(after, limit, |_, stored| stored.task.description.to_lowercase().contains(&query));
+        Ok(Page { tasks: page.tasks, next: page.next.map(SearchCursor::Recency) })
+    }
+
+    async fn tags(&self, id: i32) -> Result<Vec<String>, sqlx::Error> {
+        let mut tags = self.with_task(id, |stored| stored.tags.clone()).unwrap_or_default();
+        tags.sort();
+        Ok(tags)
+    }
+
+    async fn checklist(&self, id: i32) -> Result<Vec<ChecklistItem>, sqlx::Error> {
+        Ok(self.with_task(id, |stored| stored.checklist.clone()).unwrap_or_default())
+    }
+
+    async fn notes(&self, id: i32) -> Result<Vec<Note>, sqlx::Error> {
+        Ok(self.with_task(id, |stored| stored.notes.clone()).unwrap_or_default())
+    }
+
+    async fn links(&self, id: i32) -> Result<Vec<(String, String)>, sqlx::Error> {
+        Ok(self.with_task(id, |stored| stored.links.clone()).unwrap_or_default())
+    }
+
+    async fn project_name(&self, project_id: i32) -> Result<Option<String>, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        Ok(state.projects.iter().find(|(id, _)| *id == project_id).map(|(_, name)| name.clone()))
+    }
+
+    async fn username(&self, user_id: i32) -> Result<Option<String>, sqlx::Error> {
+        let state = self.state.lock().unwrap();
+        Ok(state.users.iter().find(|(id, _)| *id == user_id).map(|(_, name)| name.clone()))
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::{BTreeMap, VecDeque};
+#[cfg(feature = "server")]
+use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+#[cfg(feature = "server")]
+use axum::{http::header, response::IntoResponse, routing::get, Router};
+use chrono::{DateTime, Local};
+#[cfg(feature = "server")]
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+#[cfg(feature = "server")]
+use crate::error::{Result, TaskError};
+
+// Number of most recent queries kept for the "slowest queries" report
+const RECENT_QUERIES: usize = 100;
+
+// What `task serve` and `task daemon` expose at /metrics
+const COMMANDS: &str = "task_commands_total";
+const TASKS_CREATED: &str = "task_tasks_created_total";
+const TASKS_COMPLETED: &str = "task_tasks_completed_total";
+const QUERY_SECONDS: &str = "task_query_duration_seconds";
+const NOTIFICATIONS: &str = "task_notifications_total";
+
+// Upper bounds of the query latency buckets, in seconds
+#[cfg(feature = "server")]
+const QUERY_BUCKETS: [f64; 12] = [0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+// How often samples are folded into the buckets between scrapes
+#[cfg(feature = "server")]
+const UPKEEP: Duration = Duration::from_secs(5);
+
+#[cfg(feature = "server")]
+static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
+
+// Counters for database access through the repository. They live in memory and cover the
+// current process only. Clones share the same counters, so every copy of a repository
+// reports into one place.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    inner: Arc<Mutex<Inner>>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    acquires: u64,
+    acquire_time: Duration,
+    operations: BTreeMap<&'static str, OperationStats>,
+    recent: VecDeque<QuerySample>,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OperationStats {
+    pub calls: u64,
+    pub failures: u64,
+    pub total_time: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct QuerySample {
+    pub operation: &'static str,
+    pub duration: Duration,
+    pub ok: bool,
+    pub finished_at: DateTime<Local>,
+}
+
+#[derive(Debug)]
+pub struct Snapshot {
+    pub acquires: u64,
+    pub average_acquire: Option<Duration>,
+    pub operations: Vec<(&'static str, OperationStats)>,
+    // Slowest of the recent queries, slowest first
+    pub slowest: Vec<QuerySample>,
+}
+
+impl Metrics {
+    // Time spent waiting for a connection from the pool
+    pub fn record_acquire(&self, waited: Duration) {
+        let mut inner = self.lock();
+        inner.acquires += 1;
+        inner.acquire_time += waited;
+    }
+
+    // One repository operation, including any retries it needed
+    pub fn record_operation(&self, operation: &'static str, duration: Duration, ok: bool) {
+        let mut inner = self.lock();
+
+        let stats = inner.operations.entry(operation).or_default();
+        stats.calls += 1;
+        stats.total_time += duration;
+        if !ok {
+            stats.failures += 1;
+        }
+
+        if inner.recent.len() == RECENT_QUERIES {
+            inner.recent.pop_front();
+        }
+        inner.recent.push_back(QuerySample { operation, duration, ok, finished_at: Local::now() });
+        drop(inner);
+
+        ::metrics::histogram!(QUERY_SECONDS, "operation" => operation).record(duration.as_secs_f64());
+    }
+
+    pub fn snapshot(&self, slowest: usize) -> Snapshot {
+        let inner = self.lock();
+
+        let average_acquire = u32::try_from(inner.acquires)
+            .ok()
+            .filter(|&n| n > 0)
+            .map(|n| inner.acquire_time / n);
+
+        let mut recent: Vec<QuerySample> = inner.recent.iter().cloned().collect();
+        recent.sort_by_key(|sample| std::cmp::Reverse(sample.duration));
+        recent.truncate(slowest);
+
+        Snapshot {
+            acquires: inner.acquires,
+            average_acquire,
+            operations: inner.operations.iter().map(|(name, stats)| (*name, *stats)).collect(),
+            slowest: recent,
+        }
+    }
+
+    // The counters stay usable even if a thread panicked while holding the lock
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// Prometheus metrics of this process, for /metrics. Everything below is recorded through the
+// `metrics` crate, which drops it until `install_prometheus` has run; only `task serve` and
+// `task daemon` call that, so the other commands pay nothing for the counters. Builds without
+// the `server` feature have nothing to expose them with and only drop them.
+#[cfg(feature = "server")]
+pub fn install_prometheus() -> Result<()> {
+    if PROMETHEUS.get().is_some() {
+        return Ok(());
+    }
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(QUERY_SECONDS.to_string()), &QUERY_BUCKETS)
+        .and_then(|builder| builder.install_recorder())
+        .map_err(|e| TaskError::Config(format!("Could not set up the metrics: {}", e)))?;
+
+    ::metrics::describe_counter!(COMMANDS, "API requests answered and daemon jobs run, by outcome");
+    ::metrics::describe_counter!(TASKS_CREATED, "Tasks created through the API");
+    ::metrics::describe_counter!(TASKS_COMPLETED, "Tasks completed through the API");
+    ::metrics::describe_histogram!(QUERY_SECONDS, ::metrics::Unit::Seconds, "Repository calls, retries included");
+    ::metrics::describe_counter!(NOTIFICATIONS, "Reminders and messages sent, by channel and outcome");
+
+    let upkeep = handle.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(UPKEEP).await;
+            upkeep.run_upkeep();
+        }
+    });
+    let _ = PROMETHEUS.set(handle);
+    Ok(())
+}
+
+// GET /metrics in the text format Prometheus scrapes
+#[cfg(feature = "server")]
+pub fn routes<S: Clone + Send + Sync + 'static>() -> Router<S> {
+    Router::new().route("/metrics", get(render))
+}
+
+#[cfg(feature = "server")]
+async fn render() -> impl IntoResponse {
+    let body = PROMETHEUS.get().map(PrometheusHandle::render).unwrap_or_default();
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+// An API request ("GET /tasks/:id") or daemon job ("sync todoist")
+pub fn record_command(command: String, ok: bool) {
+    ::metrics::counter!(COMMANDS, "command" => command, "outcome" => outcome(ok)).increment(1);
+}
+
+pub fn record_created() {
+    ::metrics::counter!(TASKS_CREATED).increment(1);
+}
+
+pub fn record_completed() {
+    ::metrics::counter!(TASKS_COMPLETED).increment(1);
+}
+
+// `channel` as in notifications_sent, e.g. "slack"
+pub fn record_notification(channel: &'static str, delivered: bool) {
+    ::metrics::counter!(NOTIFICATIONS, "channel" => channel, "outcome" => outcome(delivered)).increment(1);
+}
+
+fn outcome(ok: bool) -> &'static str {
+    if ok { "ok" } else { "failed" }
+}
+
+#[cfg(all(test, feature = "server"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn metrics_render_for_prometheus() {
+        install_prometheus().unwrap();
+        install_prometheus().unwrap();
+        record_command("GET /tasks/:id".to_string(), true);
+        record_notification("slack", false);
+        Metrics::default().record_operation("list", Duration::from_millis(3), true);
+
+        let response = render().await.into_response();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/plain; version=0.0.4");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains(r#"task_commands_total{command="GET /tasks/:id",outcome="ok"} "#), "{}", body);
+        assert!(body.contains(r#"task_notifications_total{channel="slack",outcome="failed"} "#), "{}", body);
+        assert!(body.contains(r#"task_query_duration_seconds_bucket{operation="list",le="0.005"} "#), "{}", body);
+    }
+}
@@ -0,0 +1,109 @@
+use std::path::PathBuf;
+
+use tokio::process::Command;
+
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::export;
+use crate::notify::render;
+use crate::repository::TaskRepository;
+use crate::webhooks::WebhookEvent;
+
+const DEFAULT_FILE: &str = "tasks.txt";
+// Commit message for a change to one task; placeholders {event} and {id}
+const DEFAULT_MESSAGE: &str = "Task {id} {event}";
+// For `task mirror`, which doesn't know what changed
+const CATCH_UP_MESSAGE: &str = "Update tasks";
+
+// Keeps every task, in the todo.txt format, in a file of a git repository and commits the file
+// whenever it changes. git is run as a command, so its usual configuration (author, signing,
+// remotes) applies.
+#[derive(Debug, Clone)]
+pub struct GitMirror {
+    repository: PathBuf,
+    file: PathBuf,
+    message: String,
+    push: bool,
+}
+
+impl GitMirror {
+    // None unless [mirror] names a repository
+    pub fn new(config: &Config) -> Option<Self> {
+        let mirror = &config.mirror;
+        let repository = mirror.repository.clone()?;
+        Some(GitMirror {
+            repository,
+            file: mirror.file.clone().unwrap_or_else(|| PathBuf::from(DEFAULT_FILE)),
+            message: mirror.message.clone().unwrap_or_else(|| DEFAULT_MESSAGE.to_string()),
+            push: mirror.push,
+        })
+    }
+
+    // Rewrites the file and commits it, with a message about the event if there was one.
+    // Returns false when the tasks were unchanged and there was nothing to commit.
+    pub async fn update(&self, repo: &TaskRepository, event: Option<(WebhookEvent, i32)>) -> Result<bool> {
+        let mut contents = Vec::new();
+        export::todotxt::write(repo, &mut contents).await?;
+
+        let path = self.repository.join(&self.file);
+        if std::fs::read(&path).is_ok_and(|existing| existing == contents) {
+            return Ok(false);
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Written next to the file and renamed, so git never sees half of it
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, &contents)?;
+        std::fs::rename(&partial, &path)?;
+
+        let message = match event {
+            Some((event, id)) => render(&self.message, &[("event", event.as_str().to_string()), ("id", id.to_string())]),
+            None => CATCH_UP_MESSAGE.to_string(),
+        };
+        let file = self.file.to_string_lossy().into_owned();
+        self.git(&["add", "--", &file]).await?;
+        // Only this file, even if other changes are staged in the repository
+        self.git(&["commit", "--quiet", "-m", &message, "--", &file]).await?;
+        if self.push {
+            self.git(&["push", "--quiet"]).await?;
+        }
+        Ok(true)
+    }
+
+    async fn git(&self, args: &[&str]) -> Result<()> {
+        let output = Command::new("git").arg("-C").arg(&self.repository).args(args).output().await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                TaskError::Config("[mirror] needs git on the PATH.".to_string())
+            } else {
+                TaskError::Io(e)
+            }
+        })?;
+        if output.status.success() {
+            return Ok(());
+        }
+        Err(TaskError::Io(std::io::Error::other(format!(
+            "git {} failed in {}: {}",
+            args[0],
+            self.repository.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))))
+    }
+}
+
+// `task mirror`: brings the mirror up to date, e.g. after imports and syncs, which don't
+// update it themselves
+pub async fn run(repo: &TaskRepository, config: &Config) -> Result<()> {
+    let mirror = GitMirror::new(config).ok_or_else(|| {
+        TaskError::Config(format!(
+            "No git mirror configured. Set `repository` in the [mirror] section of {}.",
+            Config::path().display()
+        ))
+    })?;
+    if mirror.update(repo, None).await? {
+        println!("Committed the tasks to {}.", mirror.repository.join(&mirror.file).display());
+    } else {
+        println!("The mirror in {} is up to date.", mirror.repository.display());
+    }
+    Ok(())
+}
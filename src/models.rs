@@ -0,0 +1,26 @@
+use chrono::NaiveDateTime;
+
+use crate::priority::Priority;
+
+// Define a struct to represent our Task
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Task {
+    pub id: i32, // Corrected to i32 to match MySQL's INT
+    pub description: String,
+    pub completed: bool, // Correctly mapped from MySQL's TINYINT(1)
+    pub created_at: NaiveDateTime,
+    // Audit trail; NULL for rows created before these columns existed
+    pub created_by: Option<String>,
+    pub updated_by: Option<String>,
+    pub updated_at: NaiveDateTime,
+    pub due_at: Option<NaiveDateTime>,
+    pub priority: Option<Priority>,
+    pub project_id: Option<i32>,
+    // The account the task belongs to; NULL until the first account is created
+    pub owner_id: Option<i32>,
+    // The user who should do it, if not the owner
+    pub assignee_id: Option<i32>,
+    // NULL for the personal space
+    pub workspace_id: Option<i32>,
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::secrets::{self, Service};
+use crate::webhooks::WebhookEvent;
+
+const DEFAULT_PORT: u16 = 1883;
+const DEFAULT_PREFIX: &str = "task";
+// Messages waiting while the broker is unreachable; more than that are dropped
+const QUEUE: usize = 64;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+// Publishes task events to an MQTT broker, one topic per event ("task/created", "task/completed",
+// "task/deleted"), with the webhook JSON body as payload. The connection runs in the background
+// and reconnects by itself; publishing never waits for the broker.
+#[derive(Clone)]
+pub struct Mqtt {
+    client: AsyncClient,
+    prefix: String,
+}
+
+impl Mqtt {
+    // None if the config has no [mqtt] host
+    pub fn connect(config: &Config) -> Result<Option<Self>> {
+        let mqtt = &config.mqtt;
+        let Some(host) = mqtt.host.as_deref() else {
+            return Ok(None);
+        };
+        let client_id = format!("task-{}", std::process::id());
+        let mut options = MqttOptions::new(client_id, host, mqtt.port.unwrap_or(DEFAULT_PORT));
+        options.set_keep_alive(Duration::from_secs(30));
+        if let Some(username) = &mqtt.username {
+            let password = secrets::lookup(config, Service::Mqtt)?.unwrap_or_default();
+            options.set_credentials(username, password);
+        }
+        let prefix = mqtt.topic_prefix.as_deref().unwrap_or(DEFAULT_PREFIX).trim_end_matches('/').to_string();
+        if prefix.is_empty() || prefix.contains(['+', '#']) {
+            return Err(TaskError::Config(format!(
+                "Invalid topic_prefix '{}' in the [mqtt] section; it must not be empty or contain + or #.",
+                prefix
+            )));
+        }
+
+        let (client, mut events) = AsyncClient::new(options, QUEUE);
+        let broker = format!("{}:{}", host, mqtt.port.unwrap_or(DEFAULT_PORT));
+        tokio::spawn(async move {
+            // Report each outage once, not on every reconnect attempt
+            let mut connected = true;
+            loop {
+                match events.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => connected = true,
+                    Ok(_) => {}
+                    Err(e) => {
+                        if connected {
+                            println!("Warning: lost the connection to the MQTT broker at {}: {}", broker, e);
+                            connected = false;
+                        }
+                        tokio::time::sleep(RECONNECT_DELAY).await;
+                    }
+                }
+            }
+        });
+        Ok(Some(Mqtt { client, prefix }))
+    }
+
+    pub fn publish(&self, event: WebhookEvent, body: &str) {
+        let topic = format!("{}/{}", self.prefix, event.as_str());
+        if let Err(e) = self.client.try_publish(&topic, QoS::AtLeastOnce, false, body.as_bytes().to_vec()) {
+            println!("Warning: could not publish to {}: {}", topic, e);
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use crate::error::{Result, TaskError};
+use crate::repository::TaskRepository;
+
+// Characters that end a sentence rather than a username, as in "thanks @bob!"
+const TRAILING: &[char] = &['.', ',', ';', ':', '!', '?', ')', ']', '}', '"', '\''];
+
+// Notes are the comments on a task. One that names someone with @username tells them about it
+// on whichever channels they get notifications on: the desktop while `task watch` runs with
+// their login, email from `task notify email` run with it, and their Telegram chat with
+// [telegram] notify_mentions. They are only told if they can see the task.
+
+// `task note <id> <text>`
+pub async fn add(repo: &TaskRepository, id: i32, text: &str) -> Result<()> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err(TaskError::InvalidInput("A note needs some text.".to_string()));
+    }
+    let Some(mentioned) = repo.add_comment(id, text, &mentions(text)).await? else {
+        return Err(TaskError::InvalidInput(format!("No task with ID {} that you may change.", id)));
+    };
+    println!("Added a note to task {}.", id);
+    if !mentioned.notified.is_empty() {
+        println!("Mentioned {}; they will be notified.", mentioned.notified.join(", "));
+    }
+    if !mentioned.unseen.is_empty() {
+        println!(
+            "Warning: {} can't see task {}, so won't be notified; share it with `task share task {} <user>`.",
+            mentioned.unseen.join(", "),
+            id,
+            id
+        );
+    }
+    Ok(())
+}
+
+// The names after each "@" that starts a word, in order and without repeats. An "@" inside a
+// word, as in an email address, is not a mention.
+pub fn mentions(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut previous = ' ';
+    for (i, c) in text.char_indices() {
+        if c == '@' && !previous.is_alphanumeric() {
+            let rest = &text[i + 1..];
+            let name = rest[..rest.find(char::is_whitespace).unwrap_or(rest.len())].trim_end_matches(TRAILING);
+            if !name.is_empty() && !names.iter().any(|known| known == name) {
+                names.push(name.to_string());
+            }
+        }
+        previous = c;
+    }
+    names
+}
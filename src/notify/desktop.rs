@@ -0,0 +1,108 @@
+use std::time::Duration;
+
+use chrono::Local;
+use futures::future::BoxFuture;
+
+use crate::cli::{Channel, WatchArgs};
+use crate::config::{ChatTemplates, Config};
+use crate::error::{Result, TaskError};
+use crate::notify::routing::Dispatcher;
+use crate::notify::{post_due, post_mentions, Notice, Notifier};
+use crate::repository::TaskRepository;
+
+const DEFAULT_REMIND_MINUTES: u32 = 15;
+
+const DUE_SOON: &str = "Due {due}: {description}";
+const OVERDUE: &str = "Overdue since {due}: {description}";
+const COMPLETED: &str = "Completed: {description}";
+
+// Notifications through the desktop's notification service (D-Bus on Linux)
+pub struct Desktop {
+    templates: ChatTemplates,
+}
+
+impl Desktop {
+    pub fn new(config: &Config) -> Self {
+        Desktop { templates: config.desktop.templates.clone() }
+    }
+}
+
+impl Notifier for Desktop {
+    fn name(&self) -> &'static str {
+        "the desktop"
+    }
+
+    fn template(&self, notice: Notice) -> &str {
+        self.templates.get(notice).unwrap_or(match notice {
+            Notice::DueSoon => DUE_SOON,
+            Notice::Overdue => OVERDUE,
+            Notice::Completed => COMPLETED,
+        })
+    }
+
+    fn post<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<()>> {
+        let text = text.to_string();
+        Box::pin(async move {
+            // Talking to the notification service blocks
+            tokio::task::spawn_blocking(move || {
+                notify_rust::Notification::new().appname("task").summary("task").body(&text).show().map(drop)
+            })
+            .await
+            .map_err(std::io::Error::other)?
+            .map_err(|e| TaskError::Remote { service: "Desktop notification", message: e.to_string() })
+        })
+    }
+}
+
+// How long before a task is due it is shown
+pub(super) fn remind_ahead(config: &Config) -> chrono::Duration {
+    chrono::Duration::minutes(config.desktop.remind_minutes.unwrap_or(DEFAULT_REMIND_MINUTES).into())
+}
+
+// Desktop reminders as [desktop] has them
+pub struct Reminders {
+    desktop: Desktop,
+    remind: chrono::Duration,
+    dispatcher: Dispatcher,
+}
+
+impl Reminders {
+    pub fn new(config: &Config) -> Result<Self> {
+        let dispatcher = Dispatcher::new(config)?;
+        Ok(Reminders { desktop: Desktop::new(config), remind: remind_ahead(config), dispatcher })
+    }
+
+    // Shows what became overdue or due soon since the last check, and once logged in the notes
+    // that mention the user, as far as the notify rules and quiet hours let it
+    pub async fn check(&self, repo: &TaskRepository) -> Result<()> {
+        let now = Local::now().naive_local();
+        let (desktop, dispatcher) = (&self.desktop, &self.dispatcher);
+        post_due(repo, desktop, dispatcher, Channel::Desktop, Notice::Overdue, now, false).await?;
+        post_due(repo, desktop, dispatcher, Channel::Desktop, Notice::DueSoon, now + self.remind, false).await?;
+        if repo.user().is_some() {
+            post_mentions(repo, desktop, dispatcher, Channel::Desktop).await?;
+        }
+        Ok(())
+    }
+}
+
+// `task watch`: checks every `interval` seconds until Ctrl-C or SIGTERM. What came due during
+// quiet hours is shown once they are over.
+pub async fn watch(repo: &TaskRepository, config: &Config, args: WatchArgs) -> Result<()> {
+    let reminders = Reminders::new(config)?;
+    let interval = Duration::from_secs(args.interval.max(1));
+
+    println!("Watching for due tasks every {} s. Press Ctrl-C to stop.", interval.as_secs());
+    let shutdown = crate::shutdown_signal();
+    tokio::pin!(shutdown);
+    loop {
+        reminders.check(repo).await?;
+
+        tokio::select! {
+            _ = tokio::time::sleep(interval) => {},
+            _ = &mut shutdown => break,
+        }
+    }
+    println!("Stopped watching.");
+    Ok(())
+}
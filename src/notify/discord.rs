@@ -0,0 +1,66 @@
+use futures::future::BoxFuture;
+use serde::Serialize;
+
+use crate::config::{ChatTemplates, Config};
+use crate::error::Result;
+use crate::notify::{Notice, Notifier};
+use crate::secrets::{self, Service};
+use crate::sync::{http_client, send};
+
+const SERVICE: &str = "Discord";
+
+// Shortcodes like :warning: are only expanded by the Discord client, not for webhooks
+const DUE_SOON: &str = "\u{23f3} Task {id} is due {due}: {description}";
+const OVERDUE: &str = "\u{26a0}\u{fe0f} Task {id} is overdue (due {due}): {description}";
+const COMPLETED: &str = "\u{2705} Task {id} completed: {description}";
+
+#[derive(Debug, Serialize)]
+struct DiscordMessage<'a> {
+    content: &'a str,
+    // Task descriptions are user text; don't let "@everyone" in one ping the whole server
+    allowed_mentions: AllowedMentions,
+}
+
+#[derive(Debug, Serialize)]
+struct AllowedMentions {
+    parse: [&'static str; 0],
+}
+
+// Posts through a webhook of a Discord channel (Channel settings > Integrations > Webhooks)
+pub struct Discord {
+    http: reqwest::Client,
+    webhook_url: String,
+    templates: ChatTemplates,
+}
+
+impl Discord {
+    pub fn new(config: &Config) -> Result<Self> {
+        Ok(Discord {
+            http: http_client(SERVICE)?,
+            webhook_url: secrets::token(config, Service::Discord)?,
+            templates: config.discord.templates.clone(),
+        })
+    }
+}
+
+impl Notifier for Discord {
+    fn name(&self) -> &'static str {
+        SERVICE
+    }
+
+    fn template(&self, notice: Notice) -> &str {
+        self.templates.get(notice).unwrap_or(match notice {
+            Notice::DueSoon => DUE_SOON,
+            Notice::Overdue => OVERDUE,
+            Notice::Completed => COMPLETED,
+        })
+    }
+
+    fn post<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let message = DiscordMessage { content: text, allowed_mentions: AllowedMentions { parse: [] } };
+            send(SERVICE, self.http.post(&self.webhook_url).json(&message)).await?;
+            Ok(())
+        })
+    }
+}
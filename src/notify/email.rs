@@ -0,0 +1,375 @@
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use chrono_tz::Tz;
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+use crate::cli::{Channel, NotifyArgs};
+use crate::config::{Config, EmailConfig};
+use crate::error::{Result, TaskError};
+use crate::metrics;
+use crate::notify::{due_subject, mention_subject, mention_values, render, task_line, task_values};
+use crate::notify::routing::Dispatcher;
+use crate::notify::{Event, MENTION_DAYS, MENTION_KIND};
+use crate::repository::TaskRepository;
+use crate::secrets::{self, Service};
+use crate::streaks;
+use crate::sync::remote_error;
+use crate::Task;
+
+const SERVICE: &str = "SMTP";
+// `channel` in notifications_sent
+const CHANNEL: &str = "email";
+const DEFAULT_REMIND_HOURS: u32 = 24;
+// Port for implicit TLS; every other port uses STARTTLS
+const SMTPS_PORT: u16 = 465;
+
+// Built-in templates. Reminders can use the task placeholders of `notify::task_values`, and
+// mentions those and {author} and {note}; digests can use {date}, {count} (pending tasks with a
+// due date up to today), {overdue} and {today} (one task per line, or "none"), and {streak} (with
+// streak_warning, a paragraph about the streak that is about to end, else nothing).
+const REMINDER_SUBJECT: &str = "Due soon: {description}";
+const REMINDER_BODY: &str = "Task {id} is due {due}:\n\n    {description}\n";
+const DIGEST_SUBJECT: &str = "Tasks for {date}";
+const DIGEST_BODY: &str = "Overdue:\n{overdue}\n\nDue today:\n{today}\n{streak}";
+// When the daemon sends users their digest if neither they nor [email] say
+const DIGEST_TIME: &str = "07:00";
+const MENTION_SUBJECT: &str = "{author} mentioned you: {description}";
+const MENTION_BODY: &str = "{author} wrote on task {id}, {description}:\n\n    {note}\n";
+
+struct Mailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Mailbox,
+}
+
+impl Mailer {
+    fn new(config: &Config) -> Result<Self> {
+        let to = parse_mailbox("to", config.email.to.as_deref().ok_or_else(|| missing("to"))?)?;
+        Mailer::sending_to(config, to)
+    }
+
+    // Like `new`, for mail to someone else than [email] to
+    fn sending_to(config: &Config, to: Mailbox) -> Result<Self> {
+        let email = &config.email;
+        let host = email.smtp_host.as_deref().ok_or_else(|| missing("smtp_host"))?;
+        let from = parse_mailbox("from", email.from.as_deref().ok_or_else(|| missing("from"))?)?;
+
+        let port = email.smtp_port;
+        let builder = if port == Some(SMTPS_PORT) {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(host)
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+        }
+        .map_err(|e| remote_error(SERVICE, e))?;
+        let builder = match port {
+            Some(port) => builder.port(port),
+            None => builder,
+        };
+        let builder = match &email.username {
+            Some(username) => {
+                let password = secrets::token(config, Service::Email)?;
+                builder.credentials(Credentials::new(username.clone(), password))
+            }
+            None => builder,
+        };
+
+        Ok(Mailer { transport: builder.build(), from, to })
+    }
+
+    async fn send(&self, subject: &str, body: String) -> Result<()> {
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(self.to.clone())
+            .subject(subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(body)
+            .map_err(|e| remote_error(SERVICE, e))?;
+        self.transport.send(message).await.map_err(|e| remote_error(SERVICE, e))?;
+        Ok(())
+    }
+}
+
+// A single message to [email] to, see notify::send_message
+pub(super) async fn send_message(config: &Config, subject: &str, text: &str) -> Result<()> {
+    Mailer::new(config)?.send(subject, format!("{}\n", text)).await
+}
+
+// `task notify test email`
+pub async fn test(config: &Config) -> Result<()> {
+    let mailer = Mailer::new(config)?;
+    let body = "Email notifications from `task` are working.\n".to_string();
+    mailer.send("Test notification from task", body).await?;
+    println!("Sent a test email to {}.", mailer.to);
+    Ok(())
+}
+
+fn missing(key: &str) -> TaskError {
+    TaskError::Config(format!(
+        "No email {} configured. Set `{}` in the [email] section of {}.",
+        key.replace('_', " "),
+        key,
+        Config::path().display()
+    ))
+}
+
+fn parse_mailbox(key: &str, value: &str) -> Result<Mailbox> {
+    value
+        .parse()
+        .map_err(|e| TaskError::Config(format!("Invalid email address '{}' for `{}`: {}", value, key, e)))
+}
+
+// `task notify email`: reminders for tasks due soon, and the daily digest once its time has
+// come. Meant to run regularly, e.g. from cron every 15 minutes; nothing is sent twice. With a
+// login it also sends the notes that mention the user.
+pub async fn run(repo: &TaskRepository, config: &Config, args: NotifyArgs) -> Result<()> {
+    let email = &config.email;
+    let mailer = if args.dry_run { None } else { Some(Mailer::new(config)?) };
+    let dispatcher = Dispatcher::new(config)?;
+    let now = Local::now().naive_local();
+    let allows = |event, task: &Task| dispatcher.allows(Channel::Email, event, task.priority, now.time());
+
+    let due_soon = repo.pending_due_before(now + remind_ahead(config)).await?;
+    let mut reminded = 0;
+    for task in &due_soon {
+        let subject = due_subject(task);
+        let event = if task.due_at.is_some_and(|due| due <= now) { Event::Overdue } else { Event::DueSoon };
+        if repo.notification_sent(CHANNEL, "reminder", &subject).await? || !allows(event, task) {
+            continue;
+        }
+
+        let values = task_values(repo, task).await?;
+        let title = render(template(&email.templates.reminder_subject, REMINDER_SUBJECT), &values);
+        let body = render(template(&email.templates.reminder_body, REMINDER_BODY), &values);
+        match &mailer {
+            None => println!("Would send reminder for task {}: {}", task.id, title),
+            Some(mailer) => {
+                if send_once(repo, mailer, "reminder", &subject, &title, body).await? {
+                    reminded += 1;
+                }
+            }
+        }
+    }
+
+    let mut mentions = 0;
+    if repo.user().is_some() {
+        for mention in &repo.mentions(MENTION_DAYS).await? {
+            let subject = mention_subject(mention);
+            if repo.notification_sent(CHANNEL, MENTION_KIND, &subject).await?
+                || !allows(Event::Mention, &mention.task)
+            {
+                continue;
+            }
+            let values = mention_values(repo, mention).await?;
+            let title = render(template(&email.templates.mention_subject, MENTION_SUBJECT), &values);
+            let body = render(template(&email.templates.mention_body, MENTION_BODY), &values);
+            match &mailer {
+                None => println!("Would send mention on task {}: {}", mention.task.id, title),
+                Some(mailer) => {
+                    if send_once(repo, mailer, MENTION_KIND, &subject, &title, body).await? {
+                        mentions += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    let digest = match digest_due(email, now)? {
+        Some(_) if !dispatcher.allows(Channel::Email, Event::Digest, None, now.time()) => false,
+        Some(date) => send_digest(repo, email, mailer.as_ref(), now, &date).await?,
+        None => false,
+    };
+
+    if !args.dry_run {
+        let digest = if digest { ", and the daily digest" } else { "" };
+        println!("Sent {} reminder(s), {} mention(s){}.", reminded, mentions, digest);
+    }
+    Ok(())
+}
+
+// How long before a task is due its reminder goes out
+pub(super) fn remind_ahead(config: &Config) -> Duration {
+    Duration::hours(config.email.remind_hours.unwrap_or(DEFAULT_REMIND_HOURS).into())
+}
+
+// Today's date once the configured digest time has passed
+fn digest_due(email: &EmailConfig, now: NaiveDateTime) -> Result<Option<String>> {
+    let Some(time) = email.digest_time.as_deref() else {
+        return Ok(None);
+    };
+    let time = NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| {
+        TaskError::Config(format!("Invalid digest_time '{}' in the [email] section; expected HH:MM.", time))
+    })?;
+    Ok((now.time() >= time).then(|| now.date().to_string()))
+}
+
+async fn send_digest(
+    repo: &TaskRepository,
+    email: &EmailConfig,
+    mailer: Option<&Mailer>,
+    now: NaiveDateTime,
+    date: &str,
+) -> Result<bool> {
+    if repo.notification_sent(CHANNEL, "digest", date).await? {
+        return Ok(false);
+    }
+    let (title, body) = digest(repo, email, now, date).await?;
+    match mailer {
+        None => {
+            println!("Would send the digest for {}: {}", date, title);
+            Ok(false)
+        }
+        Some(mailer) => send_once(repo, mailer, "digest", date, &title, body).await,
+    }
+}
+
+// The subject and body of the digest of the tasks `repo` sees, for the day of `now`
+async fn digest(
+    repo: &TaskRepository,
+    email: &EmailConfig,
+    now: NaiveDateTime,
+    date: &str,
+) -> Result<(String, String)> {
+    let start_of_day = now.date().and_time(NaiveTime::MIN);
+    let tasks = repo.pending_due_before(start_of_day + Duration::days(1)).await?;
+    let (overdue, today): (Vec<&Task>, Vec<&Task>) =
+        tasks.iter().partition(|task| task.due_at.is_some_and(|due| due < start_of_day));
+    let lines = |tasks: &[&Task]| {
+        if tasks.is_empty() {
+            "none".to_string()
+        } else {
+            tasks.iter().map(|task| format!("- {}", task_line(task))).collect::<Vec<_>>().join("\n")
+        }
+    };
+    let streak = if email.streak_warning { streak_warning(repo, now.date()).await? } else { String::new() };
+    let values = [
+        ("date", date.to_string()),
+        ("count", tasks.len().to_string()),
+        ("overdue", lines(&overdue)),
+        ("today", lines(&today)),
+        ("streak", streak),
+    ];
+    let title = render(template(&email.templates.digest_subject, DIGEST_SUBJECT), &values);
+    let body = render(template(&email.templates.digest_body, DIGEST_BODY), &values);
+    Ok((title, body))
+}
+
+// The daemon's digests: to each user with an email setting, once their digest_time has come in
+// their timezone, of the tasks of their own space and shared with or assigned to them. Without
+// settings of their own, users get [email] digest_time, else DIGEST_TIME, and this machine's
+// timezone. A user whose settings can't be read is skipped with a warning, and each gets one
+// digest a day whatever `task notify email` sends.
+pub async fn send_digests(repo: &TaskRepository, config: &Config) -> Result<()> {
+    let email = &config.email;
+    let dispatcher = Dispatcher::new(config)?;
+    let mut sent = 0;
+    for user in repo.digest_recipients().await? {
+        let checked = digest_time(user.digest_time.as_deref().or(email.digest_time.as_deref()))
+            .and_then(|time| local_now(user.timezone.as_deref(), Utc::now()).map(|now| (time, now)))
+            .and_then(|(time, now)| parse_mailbox("email", &user.email).map(|to| (time, now, to)));
+        let (time, now, to) = match checked {
+            Ok(checked) => checked,
+            Err(e) => {
+                println!("Warning: no digest for {}: {}", user.username, e);
+                continue;
+            }
+        };
+        // Quiet hours are those of the user's own clock
+        if now.time() < time || !dispatcher.allows(Channel::Email, Event::Digest, None, now.time()) {
+            continue;
+        }
+        let date = now.date().to_string();
+        // One a day per user, apart from the digest of `task notify email`
+        let subject = format!("{} {}", date, user.username);
+        if repo.notification_sent(CHANNEL, "digest", &subject).await? {
+            continue;
+        }
+        let repo = repo.clone().with_user(Some(user.id)).with_workspace(None).with_actor(user.username.clone());
+        let (title, body) = digest(&repo, email, now, &date).await?;
+        if send_once(&repo, &Mailer::sending_to(config, to)?, "digest", &subject, &title, body).await? {
+            sent += 1;
+        }
+    }
+    if sent > 0 {
+        println!("Sent the digest to {} user(s).", sent);
+    }
+    Ok(())
+}
+
+// A time of day as in digest_time, or DIGEST_TIME for none
+fn digest_time(time: Option<&str>) -> Result<NaiveTime> {
+    let time = time.unwrap_or(DIGEST_TIME);
+    NaiveTime::parse_from_str(time.trim(), "%H:%M")
+        .map_err(|_| TaskError::Config(format!("Invalid digest_time '{}'; expected HH:MM.", time)))
+}
+
+// The time of `utc` in an IANA timezone, or in this machine's for none
+fn local_now(timezone: Option<&str>, utc: DateTime<Utc>) -> Result<NaiveDateTime> {
+    match timezone {
+        Some(name) => {
+            let timezone: Tz =
+                name.parse().map_err(|_| TaskError::Config(format!("Invalid timezone '{}'.", name)))?;
+            Ok(utc.with_timezone(&timezone).naive_local())
+        }
+        None => Ok(utc.with_timezone(&Local).naive_local()),
+    }
+}
+
+// E.g. "\nComplete a task today to keep your 5-day streak going.\n", or nothing if no streak
+// is at risk
+async fn streak_warning(repo: &TaskRepository, today: NaiveDate) -> Result<String> {
+    let streaks = streaks::load(repo, today).await?;
+    if !streaks.at_risk() {
+        return Ok(String::new());
+    }
+    Ok(format!("\nComplete a task today to keep your {}-day streak going.\n", streaks.current))
+}
+
+// Sends unless another run got there first. A failed send is reported and given back, so the
+// next run tries again.
+async fn send_once(
+    repo: &TaskRepository,
+    mailer: &Mailer,
+    kind: &str,
+    subject: &str,
+    title: &str,
+    body: String,
+) -> Result<bool> {
+    if !repo.claim_notification(CHANNEL, kind, subject).await? {
+        return Ok(false);
+    }
+    let result = mailer.send(title, body).await;
+    metrics::record_notification(CHANNEL, result.is_ok());
+    match result {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            repo.release_notification(CHANNEL, kind, subject).await?;
+            println!("Warning: could not send '{}': {}", title, e);
+            Ok(false)
+        }
+    }
+}
+
+fn template<'a>(configured: &'a Option<String>, default: &'a str) -> &'a str {
+    configured.as_deref().unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digests_go_out_at_the_users_time_in_their_timezone() {
+        assert_eq!(digest_time(Some("06:45")).unwrap(), NaiveTime::from_hms_opt(6, 45, 0).unwrap());
+        assert_eq!(digest_time(None).unwrap(), NaiveTime::from_hms_opt(7, 0, 0).unwrap());
+        assert!(digest_time(Some("7am")).is_err());
+
+        let utc = "2026-03-10T05:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        let berlin = local_now(Some("Europe/Berlin"), utc).unwrap();
+        assert_eq!(berlin.to_string(), "2026-03-10 06:30:00");
+        let honolulu = local_now(Some("Pacific/Honolulu"), utc).unwrap();
+        assert_eq!(honolulu.to_string(), "2026-03-09 19:30:00");
+        assert!(local_now(Some("Mars/Olympus"), utc).is_err());
+    }
+}
@@ -0,0 +1,423 @@
+use chrono::NaiveDateTime;
+#[cfg(feature = "notifications")]
+use chrono::{Duration, Local};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::Channel;
+use crate::config::Config;
+use crate::error::Result;
+#[cfg(feature = "notifications")]
+use crate::metrics;
+#[cfg(feature = "notifications")]
+use crate::notify::routing::{Dispatcher, Route};
+use crate::repository::{Mention, TaskRepository};
+#[cfg(feature = "notifications")]
+use crate::secrets::{self, Service};
+use crate::Task;
+
+// The channels; what is left without them (templates, which tasks are due, not sending
+// twice) is shared with the Telegram bot and the git mirror
+#[cfg(feature = "notifications")]
+pub mod desktop;
+#[cfg(feature = "notifications")]
+pub mod discord;
+#[cfg(feature = "notifications")]
+pub mod email;
+#[cfg(feature = "notifications")]
+pub mod routing;
+#[cfg(feature = "notifications")]
+pub mod slack;
+#[cfg(feature = "notifications")]
+pub mod sms;
+
+// What a message is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notice {
+    DueSoon,
+    Overdue,
+    Completed,
+}
+
+impl Notice {
+    // `kind` in notifications_sent
+    #[cfg(feature = "notifications")]
+    fn kind(self) -> &'static str {
+        match self {
+            Notice::DueSoon => "due_soon",
+            Notice::Overdue => "overdue",
+            Notice::Completed => "completed",
+        }
+    }
+}
+
+// What [[notify_rules]] route: the notices, and the mentions and digests besides
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    DueSoon,
+    Overdue,
+    Completed,
+    Mention,
+    Digest,
+    Escalation,
+}
+
+impl From<Notice> for Event {
+    fn from(notice: Notice) -> Self {
+        match notice {
+            Notice::DueSoon => Event::DueSoon,
+            Notice::Overdue => Event::Overdue,
+            Notice::Completed => Event::Completed,
+        }
+    }
+}
+
+// A channel that takes short text messages about tasks, such as a chat webhook or the desktop.
+// Everything else (which tasks, when, not posting twice) is handled here, the same for every channel.
+pub trait Notifier: Send + Sync {
+    // For messages to the user, e.g. "Slack"
+    fn name(&self) -> &'static str;
+
+    // Template for the message, with the placeholders of `task_values`
+    fn template(&self, notice: Notice) -> &str;
+
+    fn post<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<()>>;
+}
+
+// Mentions older than this are not told about, e.g. on a channel that was only just set up
+pub const MENTION_DAYS: u32 = 7;
+// `kind` in notifications_sent
+pub const MENTION_KIND: &str = "mention";
+pub const MENTIONED: &str = "{author} mentioned you on task {id} ({description}): {note}";
+
+// Chat channels, each with the service its webhook URL is stored under
+#[cfg(feature = "notifications")]
+const CHAT_CHANNELS: [(Channel, Service); 2] = [(Channel::Slack, Service::Slack), (Channel::Discord, Service::Discord)];
+
+// The notifier of a channel; fails if the channel isn't set up
+#[cfg(feature = "notifications")]
+fn notifier(config: &Config, channel: Channel) -> Result<Box<dyn Notifier>> {
+    Ok(match channel {
+        Channel::Slack => Box::new(slack::Slack::new(config)?),
+        Channel::Discord => Box::new(discord::Discord::new(config)?),
+        Channel::Desktop => Box::new(desktop::Desktop::new(config)),
+        Channel::Sms => Box::new(sms::Sms::new(config)?),
+        Channel::Email => unreachable!("email has its own subjects and digests"),
+    })
+}
+
+// Every chat channel that is set up
+#[cfg(feature = "notifications")]
+fn chat_notifiers(config: &Config) -> Result<Vec<(Channel, Box<dyn Notifier>)>> {
+    let mut notifiers = Vec::new();
+    for (channel, service) in CHAT_CHANNELS {
+        if secrets::lookup(config, service)?.is_some() {
+            notifiers.push((channel, notifier(config, channel)?));
+        }
+    }
+    Ok(notifiers)
+}
+
+// `task notify test`
+#[cfg(feature = "notifications")]
+pub async fn test(config: &Config, channel: Channel) -> Result<()> {
+    if channel == Channel::Email {
+        return email::test(config).await;
+    }
+    let notifier = notifier(config, channel)?;
+    notifier.post("Test notification from `task`: notifications to this channel are working.").await?;
+    println!("Posted a test message to {}.", notifier.name());
+    Ok(())
+}
+
+// `task notify slack|discord`: posts tasks that became overdue since the last run. Meant to run
+// regularly, e.g. from cron; each task is posted once per due date.
+#[cfg(feature = "notifications")]
+pub async fn post_overdue(repo: &TaskRepository, config: &Config, channel: Channel, dry_run: bool) -> Result<()> {
+    let notifier = notifier(config, channel)?;
+    let dispatcher = Dispatcher::new(config)?;
+    let now = Local::now().naive_local();
+    let posted = post_due(repo, notifier.as_ref(), &dispatcher, channel, Notice::Overdue, now, dry_run).await?;
+    if !dry_run {
+        println!("Posted {} overdue task(s) to {}.", posted, notifier.name());
+    }
+    Ok(())
+}
+
+// Posts each pending task due before `until` that wasn't posted for this notice and due date
+// yet, and that the notify rules let through; for DueSoon only tasks that aren't overdue yet.
+// Returns how many were posted.
+#[cfg(feature = "notifications")]
+async fn post_due(
+    repo: &TaskRepository,
+    notifier: &dyn Notifier,
+    dispatcher: &Dispatcher,
+    channel: Channel,
+    notice: Notice,
+    until: NaiveDateTime,
+    dry_run: bool,
+) -> Result<u32> {
+    let (key, kind) = (channel_key(channel), notice.kind());
+    let now = Local::now().naive_local();
+    let mut posted = 0;
+    for task in &repo.pending_due_before(until).await? {
+        if notice == Notice::DueSoon && task.due_at.is_some_and(|due| due <= now) {
+            continue;
+        }
+        let subject = due_subject(task);
+        if repo.notification_sent(key, kind, &subject).await?
+            || !dispatcher.allows(channel, notice.into(), task.priority, now.time())
+        {
+            continue;
+        }
+
+        let text = render(notifier.template(notice), &task_values(repo, task).await?);
+        if dry_run {
+            println!("Would post to {}: {}", notifier.name(), text);
+            continue;
+        }
+        if !repo.claim_notification(key, kind, &subject).await? {
+            continue;
+        }
+        let result = notifier.post(&text).await;
+        metrics::record_notification(key, result.is_ok());
+        match result {
+            Ok(()) => posted += 1,
+            Err(e) => {
+                // Given back, so the next run tries again
+                repo.release_notification(key, kind, &subject).await?;
+                tracing::warn!(task_id = task.id, notifier = notifier.name(), error = %e, "could not post task");
+            }
+        }
+    }
+    Ok(posted)
+}
+
+// Sends a message that isn't a reminder, such as an escalation, on `channel` if the notify rules
+// let it now; by email `subject` is the subject. Returns whether it went out.
+#[cfg(feature = "notifications")]
+pub async fn send_message(
+    config: &Config,
+    channel: Channel,
+    event: Event,
+    priority: Option<crate::priority::Priority>,
+    subject: &str,
+    text: &str,
+) -> Result<bool> {
+    if !Dispatcher::new(config)?.allows(channel, event, priority, Local::now().time()) {
+        return Ok(false);
+    }
+    let result = match channel {
+        Channel::Email => email::send_message(config, subject, text).await,
+        _ => notifier(config, channel)?.post(text).await,
+    };
+    metrics::record_notification(channel_key(channel), result.is_ok());
+    result.map(|()| true)
+}
+
+// Called after a task was completed in the interactive menu or through `task serve`. Posts to
+// every chat channel that is set up and the notify rules allow; a failed post is only reported.
+#[cfg(feature = "notifications")]
+pub async fn task_completed(repo: &TaskRepository, config: &Config, id: i32) -> Result<()> {
+    let notifiers = chat_notifiers(config)?;
+    if notifiers.is_empty() {
+        return Ok(());
+    }
+    let Some(task) = repo.get(id).await? else {
+        return Ok(());
+    };
+
+    let dispatcher = Dispatcher::new(config)?;
+    let time = Local::now().time();
+    let values = task_values(repo, &task).await?;
+    for (channel, notifier) in &notifiers {
+        if !dispatcher.allows(*channel, Event::Completed, task.priority, time) {
+            continue;
+        }
+        let text = render(notifier.template(Notice::Completed), &values);
+        let result = notifier.post(&text).await;
+        metrics::record_notification(channel_key(*channel), result.is_ok());
+        if let Err(e) = result {
+            tracing::warn!(task_id = id, notifier = notifier.name(), error = %e, "could not post the completed task");
+        }
+    }
+    Ok(())
+}
+
+// Without the channels there is nobody to tell
+#[cfg(not(feature = "notifications"))]
+pub async fn task_completed(_repo: &TaskRepository, _config: &Config, _id: i32) -> Result<()> {
+    Ok(())
+}
+
+// A reminder about a task that a channel is yet to send
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pending {
+    // As in notifications_sent, e.g. "email"
+    pub channel: String,
+    pub task_id: i32,
+    pub description: String,
+    pub due_at: NaiveDateTime,
+    pub overdue: bool,
+}
+
+// The reminders `channel` sends on its next run, as its command would: email reminds of every
+// task due within remind_hours, Slack and Discord only of overdue ones, the desktop and SMS of
+// those due within their remind_minutes (SMS only for opted-in tasks). Mentions, digests and
+// the SMS daily limit aren't taken into account; the notify rules are, but for their quiet hours,
+// which only delay a reminder.
+#[cfg(feature = "notifications")]
+pub async fn pending(repo: &TaskRepository, config: &Config, channel: Channel) -> Result<Vec<Pending>> {
+    let dispatcher = Dispatcher::new(config)?;
+    let now = Local::now().naive_local();
+    let (ahead, opted_in) = match channel {
+        Channel::Email => (email::remind_ahead(config), None),
+        Channel::Slack | Channel::Discord => (Duration::zero(), None),
+        Channel::Desktop => (desktop::remind_ahead(config), None),
+        Channel::Sms => (sms::remind_ahead(config), Some(repo.sms_alert_tasks().await?)),
+    };
+
+    let key = channel_key(channel);
+    let mut pending = Vec::new();
+    for task in repo.pending_due_before(now + ahead).await? {
+        let Some(due_at) = task.due_at else { continue };
+        if opted_in.as_ref().is_some_and(|ids| !ids.contains(&task.id)) {
+            continue;
+        }
+        let overdue = due_at <= now;
+        let event = if overdue { Event::Overdue } else { Event::DueSoon };
+        if dispatcher.route(channel, event, task.priority, now.time()) == Route::Filtered {
+            continue;
+        }
+        // Email sends one reminder per due date, the others one before and one after it
+        let kind = match (channel, overdue) {
+            (Channel::Email, _) => "reminder",
+            (_, true) => Notice::Overdue.kind(),
+            (_, false) => Notice::DueSoon.kind(),
+        };
+        if repo.notification_sent(key, kind, &due_subject(&task)).await? {
+            continue;
+        }
+        let description = task.description;
+        pending.push(Pending { channel: key.to_string(), task_id: task.id, description, due_at, overdue });
+    }
+    Ok(pending)
+}
+
+// Posts the mentions of the repository's user that this channel hasn't told them about yet, as
+// far as the notify rules let it. Returns how many were posted.
+#[cfg(feature = "notifications")]
+pub async fn post_mentions(
+    repo: &TaskRepository,
+    notifier: &dyn Notifier,
+    dispatcher: &Dispatcher,
+    channel: Channel,
+) -> Result<u32> {
+    let key = channel_key(channel);
+    let time = Local::now().time();
+    let mut posted = 0;
+    for mention in &repo.mentions(MENTION_DAYS).await? {
+        let subject = mention_subject(mention);
+        if repo.notification_sent(key, MENTION_KIND, &subject).await?
+            || !dispatcher.allows(channel, Event::Mention, mention.task.priority, time)
+            || !repo.claim_notification(key, MENTION_KIND, &subject).await?
+        {
+            continue;
+        }
+        let text = render(MENTIONED, &mention_values(repo, mention).await?);
+        let result = notifier.post(&text).await;
+        metrics::record_notification(key, result.is_ok());
+        match result {
+            Ok(()) => posted += 1,
+            Err(e) => {
+                repo.release_notification(key, MENTION_KIND, &subject).await?;
+                let task_id = mention.task.id;
+                tracing::warn!(task_id, notifier = notifier.name(), error = %e, "could not post a mention");
+            }
+        }
+    }
+    Ok(posted)
+}
+
+// Each mention is told about once per channel and user
+pub fn mention_subject(mention: &Mention) -> String {
+    format!("{}/{}", mention.note_id, mention.user_id)
+}
+
+// The task placeholders of `task_values`, plus {author} and {note}
+pub async fn mention_values(repo: &TaskRepository, mention: &Mention) -> Result<Vec<(&'static str, String)>> {
+    let mut values = task_values(repo, &mention.task).await?;
+    values.push(("author", mention.author.clone().unwrap_or_else(|| "Someone".to_string())));
+    values.push(("note", mention.body.clone()));
+    Ok(values)
+}
+
+// `channel` in notifications_sent and task_escalations
+pub fn channel_key(channel: Channel) -> &'static str {
+    match channel {
+        Channel::Email => "email",
+        Channel::Slack => "slack",
+        Channel::Discord => "discord",
+        Channel::Desktop => "desktop",
+        Channel::Sms => "sms",
+    }
+}
+
+// Notifications about a due date are per task and due time, so moving the due date brings a
+// new one
+pub fn due_subject(task: &Task) -> String {
+    let due = task.due_at.map(|due| due.format("%Y%m%dT%H%M%S").to_string()).unwrap_or_default();
+    format!("{}@{}", task.id, due)
+}
+
+// Fills in `{name}` placeholders. Unknown names are left as they are, so a typo in a template
+// shows up in the message instead of silently disappearing.
+pub fn render(template: &str, values: &[(&str, String)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let value = after.find('}').and_then(|end| {
+            let name = &after[..end];
+            values.iter().find(|(key, _)| *key == name).map(|(_, value)| (value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                out.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('{');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Placeholders every template about a single task can use:
+// {id}, {description}, {due}, {priority} and {project}
+pub async fn task_values(repo: &TaskRepository, task: &Task) -> Result<Vec<(&'static str, String)>> {
+    let project = match task.project_id {
+        Some(project_id) => repo.project_name(project_id).await?,
+        None => None,
+    };
+    Ok(vec![
+        ("id", task.id.to_string()),
+        ("description", task.description.clone()),
+        ("due", task.due_at.as_ref().map(crate::format_due).unwrap_or_default()),
+        ("priority", task.priority.map(|priority| priority.to_string()).unwrap_or_default()),
+        ("project", project.unwrap_or_default()),
+    ])
+}
+
+// "[12] Write report (due 2026-10-15)", for lists of tasks in digests
+pub fn task_line(task: &Task) -> String {
+    match &task.due_at {
+        Some(due_at) => format!("[{}] {} (due {})", task.id, task.description, crate::format_due(due_at)),
+        None => format!("[{}] {}", task.id, task.description),
+    }
+}
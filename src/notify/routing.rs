@@ -0,0 +1,195 @@
+// The rules of [[notify_rules]], which every notification of `task notify`, `task watch` and
+// the daemon passes before it goes out. Without rules everything goes out as before. With them,
+// a notification goes out on a channel only if a rule for that channel and event lets it: the
+// task has at least the rule's min_priority (digests, which have none, always do), and it isn't
+// the rule's quiet hours. The deprecated [desktop] quiet_hours, or the account's quiet_hours
+// setting, hold back everything for the desktop during them, rules or not. A reminder held back
+// by quiet hours goes out on the first run after them; the news of a task completed during them
+// is not posted.
+
+use chrono::NaiveTime;
+
+use crate::cli::Channel;
+use crate::config::{Config, NotifyRule};
+use crate::error::{Result, TaskError};
+use crate::notify::Event;
+use crate::priority::Priority;
+
+// "HH:MM-HH:MM". The end may be earlier than the start, for quiet hours through midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuietHours {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietHours {
+    // `section` names where it was set in the config file, for the error
+    pub fn parse(value: &str, section: &str) -> Result<Self> {
+        let invalid =
+            || TaskError::Config(format!("Invalid quiet_hours '{}' in {}; expected HH:MM-HH:MM.", value, section));
+        let (start, end) = value.split_once('-').ok_or_else(invalid)?;
+        let time = |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| invalid());
+        Ok(QuietHours { start: time(start)?, end: time(end)? })
+    }
+
+    pub fn contains(self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+// What becomes of a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    Send,
+    // Only the quiet hours of the rules that would send it stand in the way
+    Quiet,
+    // No rule sends it at all
+    Filtered,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    // Empty for every channel or event
+    channels: Vec<Channel>,
+    events: Vec<Event>,
+    min_priority: Option<Priority>,
+    quiet: Option<QuietHours>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Dispatcher {
+    rules: Vec<Rule>,
+    // [desktop] quiet_hours, or the account's quiet_hours setting
+    desktop_quiet: Option<QuietHours>,
+}
+
+impl Dispatcher {
+    // Fails on quiet hours that can't be read, so a broken rule is found before anything is sent
+    pub fn new(config: &Config) -> Result<Self> {
+        let rules = config.notify_rules.iter().map(rule).collect::<Result<_>>()?;
+        let desktop_quiet = config.desktop.quiet_hours.as_deref();
+        let desktop_quiet = desktop_quiet.map(|hours| QuietHours::parse(hours, "the [desktop] section")).transpose()?;
+        Ok(Dispatcher { rules, desktop_quiet })
+    }
+
+    pub fn route(&self, channel: Channel, event: Event, priority: Option<Priority>, time: NaiveTime) -> Route {
+        if channel == Channel::Desktop && self.desktop_quiet.is_some_and(|hours| hours.contains(time)) {
+            return Route::Quiet;
+        }
+        if self.rules.is_empty() {
+            return Route::Send;
+        }
+        let mut quiet = false;
+        for rule in &self.rules {
+            let applies = (rule.channels.is_empty() || rule.channels.contains(&channel))
+                && (rule.events.is_empty() || rule.events.contains(&event));
+            let important = event == Event::Digest || rule.min_priority.is_none_or(|min| priority >= Some(min));
+            if !applies || !important {
+                continue;
+            }
+            if rule.quiet.is_some_and(|hours| hours.contains(time)) {
+                quiet = true;
+                continue;
+            }
+            return Route::Send;
+        }
+        if quiet { Route::Quiet } else { Route::Filtered }
+    }
+
+    // Whether to send it now; otherwise it is logged why not
+    pub fn allows(&self, channel: Channel, event: Event, priority: Option<Priority>, time: NaiveTime) -> bool {
+        let route = self.route(channel, event, priority, time);
+        if route != Route::Send {
+            tracing::debug!(?channel, ?event, ?route, "notification held back by the notify rules");
+        }
+        route == Route::Send
+    }
+}
+
+fn rule(rule: &NotifyRule) -> Result<Rule> {
+    let quiet = rule.quiet_hours.as_deref().map(|hours| QuietHours::parse(hours, "[[notify_rules]]")).transpose()?;
+    Ok(Rule { channels: rule.channels.clone(), events: rule.events.clone(), min_priority: rule.min_priority, quiet })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn dispatcher(rules: Vec<NotifyRule>) -> Dispatcher {
+        Dispatcher::new(&Config { notify_rules: rules, ..Default::default() }).unwrap()
+    }
+
+    #[test]
+    fn without_rules_everything_goes_out() {
+        let route = Dispatcher::default().route(Channel::Sms, Event::Overdue, None, time(3));
+        assert_eq!(route, Route::Send);
+    }
+
+    #[test]
+    fn a_channel_gets_only_what_a_rule_sends_it() {
+        let dispatcher = dispatcher(vec![
+            NotifyRule {
+                channels: vec![Channel::Sms],
+                events: vec![Event::Overdue],
+                min_priority: Some(Priority::High),
+                quiet_hours: None,
+            },
+            NotifyRule { channels: vec![Channel::Email], ..Default::default() },
+        ]);
+        let route = |channel, event, priority| dispatcher.route(channel, event, priority, time(12));
+        assert_eq!(route(Channel::Sms, Event::Overdue, Some(Priority::High)), Route::Send);
+        assert_eq!(route(Channel::Sms, Event::Overdue, Some(Priority::Medium)), Route::Filtered);
+        assert_eq!(route(Channel::Sms, Event::Overdue, None), Route::Filtered);
+        assert_eq!(route(Channel::Sms, Event::DueSoon, Some(Priority::High)), Route::Filtered);
+        assert_eq!(route(Channel::Email, Event::Mention, None), Route::Send);
+        // Slack has no rule, so it gets nothing
+        assert_eq!(route(Channel::Slack, Event::Completed, Some(Priority::High)), Route::Filtered);
+    }
+
+    #[test]
+    fn quiet_hours_hold_back_what_no_other_rule_sends() {
+        let dispatcher = dispatcher(vec![
+            NotifyRule { quiet_hours: Some("22:00-07:00".to_string()), ..Default::default() },
+            NotifyRule {
+                channels: vec![Channel::Desktop],
+                min_priority: Some(Priority::High),
+                ..Default::default()
+            },
+        ]);
+        let route = |channel, priority, hour| dispatcher.route(channel, Event::Overdue, priority, time(hour));
+        assert_eq!(route(Channel::Slack, None, 23), Route::Quiet);
+        assert_eq!(route(Channel::Slack, None, 7), Route::Send);
+        // The second rule sends urgent tasks to the desktop at any time
+        assert_eq!(route(Channel::Desktop, Some(Priority::High), 3), Route::Send);
+        assert_eq!(route(Channel::Desktop, Some(Priority::Low), 3), Route::Quiet);
+        // A digest has no priority to fall short of
+        let digest = dispatcher.route(Channel::Desktop, Event::Digest, None, time(12));
+        assert_eq!(digest, Route::Send);
+    }
+
+    #[test]
+    fn desktop_quiet_hours_hold_back_only_the_desktop() {
+        let mut config = Config { notify_rules: vec![NotifyRule::default()], ..Default::default() };
+        config.desktop.quiet_hours = Some("22:00-07:00".to_string());
+        let dispatcher = Dispatcher::new(&config).unwrap();
+        assert_eq!(dispatcher.route(Channel::Desktop, Event::Mention, None, time(23)), Route::Quiet);
+        assert_eq!(dispatcher.route(Channel::Desktop, Event::Mention, None, time(12)), Route::Send);
+        assert_eq!(dispatcher.route(Channel::Slack, Event::Mention, None, time(23)), Route::Send);
+    }
+
+    #[test]
+    fn quiet_hours_are_read_as_a_time_range() {
+        let hours = QuietHours::parse("22:00 - 07:30", "[desktop]").unwrap();
+        assert!(hours.contains(time(23)) && hours.contains(time(7)) && !hours.contains(time(8)));
+        let error = QuietHours::parse("after ten", "[[notify_rules]]").unwrap_err();
+        assert!(error.to_string().contains("in [[notify_rules]]"), "{}", error);
+    }
+}
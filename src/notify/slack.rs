@@ -0,0 +1,58 @@
+use futures::future::BoxFuture;
+use serde::Serialize;
+
+use crate::config::{ChatTemplates, Config};
+use crate::error::Result;
+use crate::notify::{Notice, Notifier};
+use crate::secrets::{self, Service};
+use crate::sync::{http_client, send};
+
+const SERVICE: &str = "Slack";
+
+// Slack turns :name: into emoji
+const DUE_SOON: &str = ":hourglass: Task {id} is due {due}: {description}";
+const OVERDUE: &str = ":warning: Task {id} is overdue (due {due}): {description}";
+const COMPLETED: &str = ":white_check_mark: Task {id} completed: {description}";
+
+#[derive(Debug, Serialize)]
+struct SlackMessage<'a> {
+    text: &'a str,
+}
+
+// Posts through an incoming webhook of a Slack channel
+pub struct Slack {
+    http: reqwest::Client,
+    webhook_url: String,
+    templates: ChatTemplates,
+}
+
+impl Slack {
+    pub fn new(config: &Config) -> Result<Self> {
+        Ok(Slack {
+            http: http_client(SERVICE)?,
+            webhook_url: secrets::token(config, Service::Slack)?,
+            templates: config.slack.templates.clone(),
+        })
+    }
+}
+
+impl Notifier for Slack {
+    fn name(&self) -> &'static str {
+        SERVICE
+    }
+
+    fn template(&self, notice: Notice) -> &str {
+        self.templates.get(notice).unwrap_or(match notice {
+            Notice::DueSoon => DUE_SOON,
+            Notice::Overdue => OVERDUE,
+            Notice::Completed => COMPLETED,
+        })
+    }
+
+    fn post<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            send(SERVICE, self.http.post(&self.webhook_url).json(&SlackMessage { text })).await?;
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,204 @@
+use chrono::{Duration, Local};
+use futures::future::BoxFuture;
+use serde::Serialize;
+
+use crate::cli::{Channel, NotifyArgs};
+use crate::config::{ChatTemplates, Config};
+use crate::error::{Result, TaskError};
+use crate::metrics;
+use crate::notify::routing::Dispatcher;
+use crate::notify::{due_subject, render, task_values, Notice, Notifier};
+use crate::repository::TaskRepository;
+use crate::secrets::{self, Service};
+use crate::sync::{http_client, send};
+
+const SERVICE: &str = "Twilio";
+// `channel` in notifications_sent
+const CHANNEL: &str = "sms";
+const API_URL: &str = "https://api.twilio.com/2010-04-01";
+const DEFAULT_REMIND_MINUTES: u32 = 60;
+// Texts cost money per message; a mistake (or a flood of overdue tasks) shouldn't cost much
+const DEFAULT_MAX_PER_DAY: u32 = 5;
+
+// Kept short: longer texts are billed as several messages
+const DUE_SOON: &str = "Due {due}: {description}";
+const OVERDUE: &str = "Overdue since {due}: {description}";
+const COMPLETED: &str = "Done: {description}";
+
+#[derive(Debug, Serialize)]
+struct TwilioMessage<'a> {
+    #[serde(rename = "From")]
+    from: &'a str,
+    #[serde(rename = "To")]
+    to: &'a str,
+    #[serde(rename = "Body")]
+    body: &'a str,
+}
+
+// Sends text messages through Twilio's Messages API
+pub struct Sms {
+    http: reqwest::Client,
+    account_sid: String,
+    auth_token: String,
+    from: String,
+    to: String,
+    templates: ChatTemplates,
+}
+
+impl Sms {
+    pub fn new(config: &Config) -> Result<Self> {
+        let sms = &config.sms;
+        let missing = |key: &str| {
+            TaskError::Config(format!(
+                "No SMS {} configured. Set `{}` in the [sms] section of {}.",
+                key.replace('_', " "),
+                key,
+                Config::path().display()
+            ))
+        };
+        Ok(Sms {
+            http: http_client(SERVICE)?,
+            account_sid: sms.account_sid.clone().ok_or_else(|| missing("account_sid"))?,
+            auth_token: secrets::token(config, Service::Sms)?,
+            from: sms.from.clone().ok_or_else(|| missing("from"))?,
+            to: sms.to.clone().ok_or_else(|| missing("to"))?,
+            templates: sms.templates.clone(),
+        })
+    }
+}
+
+impl Notifier for Sms {
+    fn name(&self) -> &'static str {
+        "SMS"
+    }
+
+    fn template(&self, notice: Notice) -> &str {
+        template(&self.templates, notice)
+    }
+
+    fn post<'a>(&'a self, text: &'a str) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move {
+            let url = format!("{}/Accounts/{}/Messages.json", API_URL, self.account_sid);
+            let message = TwilioMessage { from: &self.from, to: &self.to, body: text };
+            let request = self.http.post(url).basic_auth(&self.account_sid, Some(&self.auth_token)).form(&message);
+            send(SERVICE, request).await?;
+            Ok(())
+        })
+    }
+}
+
+// How long before a task is due its text goes out
+pub(super) fn remind_ahead(config: &Config) -> Duration {
+    Duration::minutes(config.sms.remind_minutes.unwrap_or(DEFAULT_REMIND_MINUTES).into())
+}
+
+// `task notify sms`: texts about opted-in tasks that became overdue or are due within
+// remind_minutes, once per notice and due date. At most max_per_day texts go out in any 24
+// hours, counting earlier runs; overdue tasks go first when that limit cuts a run short.
+pub async fn run(repo: &TaskRepository, config: &Config, args: NotifyArgs) -> Result<()> {
+    let sms = if args.dry_run { None } else { Some(Sms::new(config)?) };
+    let dispatcher = Dispatcher::new(config)?;
+    let now = Local::now().naive_local();
+    let remind = remind_ahead(config);
+
+    let max_per_day = config.sms.max_per_day.unwrap_or(DEFAULT_MAX_PER_DAY);
+    let sent_today = repo.notifications_sent_since(CHANNEL, now - Duration::days(1)).await?;
+    let mut budget = max_per_day.saturating_sub(u32::try_from(sent_today).unwrap_or(u32::MAX));
+
+    let opted_in = repo.sms_alert_tasks().await?;
+    let mut due = Vec::new();
+    for task in repo.pending_due_before(now + remind).await? {
+        if !opted_in.contains(&task.id) {
+            continue;
+        }
+        let notice = if task.due_at.is_some_and(|due| due <= now) { Notice::Overdue } else { Notice::DueSoon };
+        if !dispatcher.allows(Channel::Sms, notice.into(), task.priority, now.time()) {
+            continue;
+        }
+        due.push((notice, task));
+    }
+    due.sort_by_key(|(notice, task)| (*notice != Notice::Overdue, task.due_at));
+
+    let (mut sent, mut held_back) = (0, 0);
+    for (notice, task) in &due {
+        let (kind, subject) = (notice.kind(), due_subject(task));
+        if repo.notification_sent(CHANNEL, kind, &subject).await? {
+            continue;
+        }
+        if budget == 0 {
+            held_back += 1;
+            continue;
+        }
+
+        let text = render(template(&config.sms.templates, *notice), &task_values(repo, task).await?);
+        let Some(sms) = &sms else {
+            println!("Would text: {}", text);
+            budget -= 1;
+            continue;
+        };
+        if !repo.claim_notification(CHANNEL, kind, &subject).await? {
+            continue;
+        }
+        let result = sms.post(&text).await;
+        metrics::record_notification(CHANNEL, result.is_ok());
+        match result {
+            Ok(()) => {
+                sent += 1;
+                budget -= 1;
+            }
+            Err(e) => {
+                // Given back, so the next run tries again
+                repo.release_notification(CHANNEL, kind, &subject).await?;
+                println!("Warning: could not text about task {}: {}", task.id, e);
+            }
+        }
+    }
+
+    if held_back > 0 {
+        println!(
+            "Warning: {} text(s) held back; [sms] max_per_day ({}) was reached. They go out on a later run.",
+            held_back, max_per_day
+        );
+    }
+    if !args.dry_run {
+        println!("Sent {} text(s).", sent);
+    }
+    Ok(())
+}
+
+// Also needed for dry runs, where there are no credentials for an `Sms`
+fn template(templates: &ChatTemplates, notice: Notice) -> &str {
+    templates.get(notice).unwrap_or(match notice {
+        Notice::DueSoon => DUE_SOON,
+        Notice::Overdue => OVERDUE,
+        Notice::Completed => COMPLETED,
+    })
+}
+
+// `task sms enable|disable`
+pub async fn set_alert(repo: &TaskRepository, id: i32, enabled: bool) -> Result<()> {
+    if !repo.set_sms_alert(id, enabled).await? {
+        return Err(TaskError::InvalidInput(format!("No task with id {}.", id)));
+    }
+    if enabled {
+        println!("Reminders for task {} will also be sent by SMS.", id);
+    } else {
+        println!("No more SMS reminders for task {}.", id);
+    }
+    Ok(())
+}
+
+// `task sms list`
+pub async fn list(repo: &TaskRepository) -> Result<()> {
+    let ids = repo.sms_alert_tasks().await?;
+    if ids.is_empty() {
+        println!("No tasks have SMS reminders. Turn them on with `task sms enable <id>`.");
+        return Ok(());
+    }
+    for id in ids {
+        if let Some(task) = repo.get(id).await? {
+            println!("{}", crate::notify::task_line(&task));
+        }
+    }
+    Ok(())
+}
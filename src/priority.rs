@@ -0,0 +1,98 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::mysql::{MySqlTypeInfo, MySqlValueRef};
+use sqlx::{Decode, Encode, MySql, Type};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema, async_graphql::Enum))]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+}
+
+impl fmt::Display for Priority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+// Accepts the full names and their first letters, in any case
+impl FromStr for Priority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "l" | "low" => Ok(Priority::Low),
+            "m" | "medium" => Ok(Priority::Medium),
+            "h" | "high" => Ok(Priority::High),
+            _ => Err(format!("unknown priority '{}' (expected low, medium or high)", s.trim())),
+        }
+    }
+}
+
+// Stored in an ENUM('low', 'medium', 'high') column, which MySQL sends as a string
+impl Type<MySql> for Priority {
+    fn type_info() -> MySqlTypeInfo {
+        <str as Type<MySql>>::type_info()
+    }
+
+    fn compatible(ty: &MySqlTypeInfo) -> bool {
+        <str as Type<MySql>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, MySql> for Priority {
+    fn encode_by_ref(&self, buf: &mut Vec<u8>) -> IsNull {
+        <&str as Encode<MySql>>::encode(self.as_str(), buf)
+    }
+}
+
+impl<'r> Decode<'r, MySql> for Priority {
+    fn decode(value: MySqlValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(<&str as Decode<MySql>>::decode(value)?.parse()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::fixtures;
+
+    proptest! {
+        // Given as the full name or its first letter, in any case and with spaces around
+        #[test]
+        fn names_read_back(priority in fixtures::priority(), upper in any::<bool>(), short in any::<bool>()) {
+            let mut name = priority.to_string();
+            if short {
+                name.truncate(1);
+            }
+            if upper {
+                name = name.to_uppercase();
+            }
+            prop_assert_eq!(format!(" {} ", name).parse::<Priority>(), Ok(priority));
+        }
+
+        #[test]
+        fn any_text_parses_or_is_rejected(value in "\\PC*") {
+            let _ = value.parse::<Priority>();
+        }
+    }
+}
@@ -0,0 +1,445 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::export::json::ExportTask;
+use crate::export::open_output;
+use crate::input::confirm;
+use crate::repository::{task_columns, ChecklistItem, Note, TaskRepository};
+use crate::roles::Role;
+use crate::secrets;
+use crate::Task;
+
+pub const ARCHIVE_FORMAT: &str = "task-user-data";
+pub const ARCHIVE_VERSION: u32 = 1;
+
+// `task user export` and `task user erase`: what a user may ask for under data protection law,
+// a copy of everything stored about them and its deletion. The archive is one JSON document:
+//
+//   {"format":"task-user-data","version":1,"exported_at":"...","account":{...},"settings":{...},
+//    "identities":[...],"sessions":[...],"api_tokens":[...],"workspaces":[...],"tasks":[...],
+//    "assigned":[...],"notes":[...],"shared_by_you":[...],"shared_with_you":[...],"activity":[...]}
+//
+// Tasks are in the format of `task export json`, with their ids so activity entries can be
+// matched to them. Hashes of passwords and tokens are left out; they are of no use to anyone.
+#[derive(Debug, Serialize)]
+struct Archive {
+    format: &'static str,
+    version: u32,
+    exported_at: String,
+    account: AccountInfo,
+    settings: BTreeMap<String, String>,
+    identities: Vec<Identity>,
+    sessions: Vec<Session>,
+    api_tokens: Vec<ApiToken>,
+    workspaces: Vec<Membership>,
+    tasks: Vec<ArchiveTask>,
+    // Other people's tasks the user should do
+    assigned: Vec<AssignedTask>,
+    // Notes the user wrote on other people's tasks
+    notes: Vec<NoteRow>,
+    shared_by_you: Vec<Share>,
+    shared_with_you: Vec<Share>,
+    // What the user did, to their own tasks and others'
+    activity: Vec<ActivityRow>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct AccountInfo {
+    username: String,
+    created_at: NaiveDateTime,
+    is_admin: bool,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Identity {
+    issuer: String,
+    subject: String,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Session {
+    created_at: NaiveDateTime,
+    expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ApiToken {
+    name: String,
+    workspace: Option<String>,
+    scope: String,
+    created_at: NaiveDateTime,
+    last_used_at: Option<NaiveDateTime>,
+    expires_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Membership {
+    workspace: String,
+    role: String,
+    joined_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize)]
+struct ArchiveTask {
+    id: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    workspace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    assignee: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    external_ids: Vec<ExternalId>,
+    #[serde(flatten)]
+    task: ExportTask,
+}
+
+#[derive(Debug, Serialize)]
+struct ExternalId {
+    source: String,
+    id: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct NoteRow {
+    task_id: i32,
+    created_at: NaiveDateTime,
+    body: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct AssignedTask {
+    id: i32,
+    description: String,
+    owner: String,
+}
+
+// A task or a project's tasks shared between the user and another one
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct Share {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    task_id: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    // The other user
+    user: String,
+    can_write: bool,
+    created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct ActivityRow {
+    task_id: i32,
+    description: String,
+    action: String,
+    occurred_at: NaiveDateTime,
+}
+
+fn account(repo: &TaskRepository) -> Result<i32> {
+    repo.user().ok_or_else(|| {
+        TaskError::InvalidInput("There are no accounts yet, so nothing is stored about anyone.".to_string())
+    })
+}
+
+// Read in one transaction, so the archive is a consistent snapshot even while others work
+pub async fn export(repo: &TaskRepository, path: Option<&Path>) -> Result<()> {
+    let user_id = account(repo)?;
+    let mut tx = repo.pool().begin().await?;
+
+    let account = sqlx::query_as::<_, AccountInfo>("SELECT username, created_at, is_admin FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    let settings: Vec<(String, String)> =
+        sqlx::query_as("SELECT name, value FROM user_settings WHERE user_id = ? ORDER BY name")
+            .bind(user_id)
+            .fetch_all(&mut *tx)
+            .await?;
+    let identities = sqlx::query_as::<_, Identity>(
+        "SELECT issuer, subject, created_at FROM user_identities WHERE user_id = ? ORDER BY created_at",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let sessions = sqlx::query_as::<_, Session>(
+        "SELECT created_at, expires_at FROM user_sessions WHERE user_id = ? ORDER BY created_at",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let api_tokens = sqlx::query_as::<_, ApiToken>(
+        "SELECT api_tokens.name, workspaces.name AS workspace, api_tokens.scope, api_tokens.created_at, \
+         api_tokens.last_used_at, api_tokens.expires_at FROM api_tokens \
+         LEFT JOIN workspaces ON workspaces.id = api_tokens.workspace_id \
+         WHERE api_tokens.user_id = ? ORDER BY api_tokens.created_at",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let workspaces = sqlx::query_as::<_, Membership>(
+        "SELECT workspaces.name AS workspace, workspace_members.role, workspace_members.created_at AS joined_at \
+         FROM workspace_members JOIN workspaces ON workspaces.id = workspace_members.workspace_id \
+         WHERE workspace_members.user_id = ? ORDER BY workspaces.name",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    // The user's tasks, each with what hangs off it
+    let rows: Vec<(i32, String)> = sqlx::query_as(
+        "SELECT task_tags.task_id, tags.name FROM task_tags JOIN tags ON tags.id = task_tags.tag_id \
+         JOIN tasks ON tasks.id = task_tags.task_id WHERE tasks.owner_id = ? ORDER BY task_tags.task_id, tags.name",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut tags = group(rows);
+    let rows: Vec<(i32, String, bool)> = sqlx::query_as(
+        "SELECT checklist_items.task_id, checklist_items.text, checklist_items.done FROM checklist_items \
+         JOIN tasks ON tasks.id = checklist_items.task_id WHERE tasks.owner_id = ? \
+         ORDER BY checklist_items.task_id, checklist_items.position, checklist_items.id",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut checklists =
+        group(rows.into_iter().map(|(task_id, text, done)| (task_id, ChecklistItem { text, done })).collect());
+    let rows: Vec<(i32, NaiveDateTime, String, Option<String>)> = sqlx::query_as(
+        "SELECT task_notes.task_id, task_notes.created_at, task_notes.body, task_notes.created_by FROM task_notes \
+         JOIN tasks ON tasks.id = task_notes.task_id WHERE tasks.owner_id = ? \
+         ORDER BY task_notes.task_id, task_notes.created_at, task_notes.id",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let rows = rows.into_iter().map(|(task_id, created_at, body, created_by)| {
+        (task_id, Note { created_at, body, created_by })
+    });
+    let mut notes = group(rows.collect());
+    let rows: Vec<(i32, String, String)> = sqlx::query_as(
+        "SELECT external_ids.task_id, external_ids.source, external_ids.external_id FROM external_ids \
+         JOIN tasks ON tasks.id = external_ids.task_id WHERE tasks.owner_id = ? \
+         ORDER BY external_ids.task_id, external_ids.source",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut external_ids =
+        group(rows.into_iter().map(|(task_id, source, id)| (task_id, ExternalId { source, id })).collect());
+
+    let names: Vec<(i32, String)> = sqlx::query_as("SELECT id, name FROM projects").fetch_all(&mut *tx).await?;
+    let projects: HashMap<i32, String> = names.into_iter().collect();
+    let names: Vec<(i32, String)> = sqlx::query_as("SELECT id, name FROM workspaces").fetch_all(&mut *tx).await?;
+    let workspace_names: HashMap<i32, String> = names.into_iter().collect();
+    let names: Vec<(i32, String)> = sqlx::query_as("SELECT id, username FROM users").fetch_all(&mut *tx).await?;
+    let usernames: HashMap<i32, String> = names.into_iter().collect();
+
+    let tasks =
+        sqlx::query_as::<_, Task>(concat!("SELECT ", task_columns!(), " FROM tasks WHERE owner_id = ? ORDER BY id"))
+            .bind(user_id)
+            .fetch_all(&mut *tx)
+            .await?;
+    let tasks: Vec<ArchiveTask> = tasks
+        .into_iter()
+        .map(|task| ArchiveTask {
+            id: task.id,
+            workspace: task.workspace_id.and_then(|id| workspace_names.get(&id).cloned()),
+            assignee: task.assignee_id.and_then(|id| usernames.get(&id).cloned()),
+            external_ids: external_ids.remove(&task.id).unwrap_or_default(),
+            task: ExportTask {
+                project: task.project_id.and_then(|id| projects.get(&id).cloned()),
+                tags: tags.remove(&task.id).unwrap_or_default(),
+                checklist: checklists.remove(&task.id).unwrap_or_default(),
+                notes: notes.remove(&task.id).unwrap_or_default(),
+                description: task.description,
+                completed: task.completed,
+                created_at: task.created_at,
+                created_by: task.created_by,
+                updated_at: Some(task.updated_at),
+                updated_by: task.updated_by,
+                due_at: task.due_at,
+                priority: task.priority,
+            },
+        })
+        .collect();
+
+    let assigned = sqlx::query_as::<_, AssignedTask>(
+        "SELECT tasks.id, tasks.description, owners.username AS owner FROM tasks \
+         JOIN users owners ON owners.id = tasks.owner_id \
+         WHERE tasks.assignee_id = ? AND tasks.owner_id <> ? ORDER BY tasks.id",
+    )
+    .bind(user_id)
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let notes_elsewhere = sqlx::query_as::<_, NoteRow>(
+        "SELECT task_notes.task_id, task_notes.created_at, task_notes.body FROM task_notes \
+         JOIN tasks ON tasks.id = task_notes.task_id \
+         WHERE task_notes.created_by = ? AND NOT tasks.owner_id <=> ? ORDER BY task_notes.id",
+    )
+    .bind(&account.username)
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut shared_by_you = sqlx::query_as::<_, Share>(
+        "SELECT task_shares.task_id, NULL AS project, users.username AS user, task_shares.can_write, \
+         task_shares.created_at FROM task_shares JOIN tasks ON tasks.id = task_shares.task_id \
+         JOIN users ON users.id = task_shares.user_id WHERE tasks.owner_id = ? ORDER BY task_shares.created_at",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    shared_by_you.extend(
+        sqlx::query_as::<_, Share>(
+            "SELECT NULL AS task_id, projects.name AS project, users.username AS user, project_shares.can_write, \
+             project_shares.created_at FROM project_shares JOIN projects ON projects.id = project_shares.project_id \
+             JOIN users ON users.id = project_shares.user_id WHERE project_shares.owner_id = ? \
+             ORDER BY project_shares.created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?,
+    );
+    let mut shared_with_you = sqlx::query_as::<_, Share>(
+        "SELECT task_shares.task_id, NULL AS project, owners.username AS user, task_shares.can_write, \
+         task_shares.created_at FROM task_shares JOIN tasks ON tasks.id = task_shares.task_id \
+         JOIN users owners ON owners.id = tasks.owner_id WHERE task_shares.user_id = ? \
+         ORDER BY task_shares.created_at",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    shared_with_you.extend(
+        sqlx::query_as::<_, Share>(
+            "SELECT NULL AS task_id, projects.name AS project, owners.username AS user, project_shares.can_write, \
+             project_shares.created_at FROM project_shares JOIN projects ON projects.id = project_shares.project_id \
+             JOIN users owners ON owners.id = project_shares.owner_id WHERE project_shares.user_id = ? \
+             ORDER BY project_shares.created_at",
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?,
+    );
+
+    let activity = sqlx::query_as::<_, ActivityRow>(
+        "SELECT task_id, description, action, occurred_at FROM task_activity WHERE user_id = ? ORDER BY id",
+    )
+    .bind(user_id)
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    let count = tasks.len();
+    let archive = Archive {
+        format: ARCHIVE_FORMAT,
+        version: ARCHIVE_VERSION,
+        exported_at: chrono::Local::now().to_rfc3339(),
+        account,
+        settings: settings.into_iter().collect(),
+        identities,
+        sessions,
+        api_tokens,
+        workspaces,
+        tasks,
+        assigned,
+        notes: notes_elsewhere,
+        shared_by_you,
+        shared_with_you,
+        activity,
+    };
+    let mut out = open_output(path)?;
+    serde_json::to_writer_pretty(&mut out, &archive).map_err(io::Error::from)?;
+    writeln!(out)?;
+    out.flush()?;
+
+    if let Some(path) = path {
+        println!("Exported your account and {} tasks to {}", count, path.display());
+    }
+    Ok(())
+}
+
+fn group<T>(rows: Vec<(i32, T)>) -> HashMap<i32, Vec<T>> {
+    let mut groups: HashMap<i32, Vec<T>> = HashMap::new();
+    for (task_id, row) in rows {
+        groups.entry(task_id).or_default().push(row);
+    }
+    groups
+}
+
+#[derive(sqlx::FromRow)]
+struct Standing {
+    is_admin: bool,
+    other_admins: i64,
+    others: i64,
+    tasks: i64,
+}
+
+// The repository deletes everything in one transaction. Before that the user has to hand over
+// what others depend on: workspaces they alone manage, and the instance if they are its only
+// admin. With [ldap] or OpenID Connect the account comes back, empty, at its next login.
+pub async fn erase(repo: &TaskRepository, config: &Config, yes: bool) -> Result<()> {
+    let user_id = account(repo)?;
+
+    let mut sole_admin = Vec::new();
+    for workspace in repo.workspaces().await? {
+        if workspace.role != Role::Admin || workspace.members < 2 {
+            continue;
+        }
+        let members = repo.members(workspace.id).await?;
+        if !members.iter().any(|member| member.role == Role::Admin && member.user_id != user_id) {
+            sole_admin.push(workspace.name);
+        }
+    }
+    if !sole_admin.is_empty() {
+        return Err(TaskError::InvalidInput(format!(
+            "You are the only admin of {}; make someone else admin there first.",
+            sole_admin.join(", ")
+        )));
+    }
+
+    let standing = sqlx::query_as::<_, Standing>(
+        "SELECT users.is_admin, \
+         (SELECT COUNT(*) FROM users admins WHERE admins.is_admin AND admins.id <> users.id) AS other_admins, \
+         (SELECT COUNT(*) FROM users others WHERE others.id <> users.id) AS others, \
+         (SELECT COUNT(*) FROM tasks WHERE tasks.owner_id = users.id) AS tasks \
+         FROM users WHERE users.id = ?",
+    )
+    .bind(user_id)
+    .fetch_one(repo.pool())
+    .await?;
+    if standing.is_admin && standing.other_admins == 0 && standing.others > 0 {
+        return Err(TaskError::InvalidInput(
+            "The instance needs an admin; promote someone else with `task admin users promote` first.".to_string(),
+        ));
+    }
+
+    if !yes
+        && !confirm(&format!(
+            "This deletes your account and your {} tasks with their notes and history, and removes your \
+             name from everything else. It can't be undone. Continue? [y/N] ",
+            standing.tasks
+        ))
+    {
+        println!("Erase cancelled.");
+        return Ok(());
+    }
+
+    let erased = repo.erase_account().await?;
+    // The session went with the account; only the token in the keyring is left
+    secrets::delete_session()?;
+    println!(
+        "Erased your account and {} tasks; {} entries of yours in others' activity no longer name you.",
+        erased.tasks, erased.activity
+    );
+    if config.ldap.url.is_some() || config.oidc.issuer.is_some() {
+        println!("Logging in again through the directory or identity provider creates a new, empty account.");
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::time::Instant;
+
+use crate::cli::ProfileCommand;
+use crate::config::{Config, ConnectionSettings};
+use crate::db;
+use crate::error::{self, Result, TaskError};
+
+pub async fn run(config: &Config, active: Option<&str>, command: &ProfileCommand) -> Result<()> {
+    match command {
+        ProfileCommand::List => {
+            list(config, active);
+            Ok(())
+        }
+        ProfileCommand::Test { name } => match name.as_deref().or(active) {
+            Some(name) => {
+                if test(&config.profile_settings(name)?).await {
+                    Ok(())
+                } else {
+                    Err(TaskError::Config(format!("Profile '{}' could not connect.", name)))
+                }
+            }
+            None => test_all(config).await,
+        },
+    }
+}
+
+fn list(config: &Config, active: Option<&str>) {
+    if config.profiles.is_empty() {
+        println!("No profiles configured in {}. Using DATABASE_URL.", Config::path().display());
+        return;
+    }
+
+    let active = active.or(config.default_profile.as_deref());
+    for (name, profile) in &config.profiles {
+        let marker = if Some(name.as_str()) == active { "*" } else { " " };
+        println!("{} {} ({})", marker, name, error::url_host(&profile.database_url));
+    }
+}
+
+async fn test_all(config: &Config) -> Result<()> {
+    if config.profiles.is_empty() {
+        if test(&config.resolve(None)?).await {
+            return Ok(());
+        }
+        return Err(TaskError::Config("Could not connect using DATABASE_URL.".to_string()));
+    }
+
+    let mut failed = 0;
+    for name in config.profiles.keys() {
+        if !test(&config.profile_settings(name)?).await {
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(TaskError::Config(format!("{} of {} profiles could not connect.", failed, config.profiles.len())));
+    }
+    Ok(())
+}
+
+// Connects, asks the server for its version and prints how long that took.
+// Returns whether the connection worked.
+async fn test(settings: &ConnectionSettings) -> bool {
+    let label = settings.profile.as_deref().unwrap_or("DATABASE_URL");
+    let started = Instant::now();
+
+    let result = async {
+        let pool = db::connect(settings).await?;
+        let version = sqlx::query_scalar("SELECT VERSION() AS version").fetch_one(&pool).await;
+        pool.close().await;
+        Ok::<String, TaskError>(version?)
+    }
+    .await;
+
+    match result {
+        Ok(version) => {
+            println!("{}: ok (MySQL {}, {} ms)", label, version, started.elapsed().as_millis());
+            true
+        }
+        Err(e) => {
+            println!("{}: FAILED - {}", label, e);
+            false
+        }
+    }
+}
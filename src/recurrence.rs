@@ -0,0 +1,218 @@
+// `task recur`: tasks that come back, e.g. every week or every 3 months. The task set to recur
+// is the template and its due date the first occurrence; the daemon's recurring tasks job
+// creates a copy of it for each later occurrence up to [daemon] recur_horizon days ahead,
+// whether or not the earlier ones were done. task_occurrences keeps which occurrences were
+// created, so none is created twice, even once its copy is deleted.
+
+use std::fmt;
+
+use chrono::{Days, Local, Months, NaiveDateTime};
+
+use crate::config::DaemonConfig;
+use crate::error::{Result, TaskError};
+use crate::format_due;
+use crate::repository::TaskRepository;
+
+// Days ahead that occurrences are created for
+const DEFAULT_HORIZON: u32 = 14;
+// Of one task in one run, so a daily task isn't copied without end
+const MAX_PER_RUN: usize = 366;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+impl Unit {
+    fn as_str(self) -> &'static str {
+        match self {
+            Unit::Day => "day",
+            Unit::Week => "week",
+            Unit::Month => "month",
+            Unit::Year => "year",
+        }
+    }
+}
+
+// How often a task recurs, e.g. every 2 weeks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Every {
+    pub count: u32,
+    pub unit: Unit,
+}
+
+impl Every {
+    // The `n`th occurrence after `first`. Months and years keep the day of the month, or take
+    // the last day of a month that is too short.
+    pub fn nth(self, first: NaiveDateTime, n: u32) -> Option<NaiveDateTime> {
+        let steps = self.count.checked_mul(n)?;
+        match self.unit {
+            Unit::Day => first.checked_add_days(Days::new(steps.into())),
+            Unit::Week => first.checked_add_days(Days::new(u64::from(steps) * 7)),
+            Unit::Month => first.checked_add_months(Months::new(steps)),
+            Unit::Year => first.checked_add_months(Months::new(steps.checked_mul(12)?)),
+        }
+    }
+}
+
+// As stored in task_recurrences, e.g. "1 week" or "3 months"
+impl fmt::Display for Every {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}{}", self.count, self.unit.as_str(), if self.count == 1 { "" } else { "s" })
+    }
+}
+
+// "daily", "weekly", "monthly", "yearly", or a number of days, weeks, months or years, e.g.
+// "2 weeks", "3 months" or "10d"
+pub fn parse(every: &str) -> Result<Every> {
+    let invalid = || {
+        TaskError::InvalidInput(format!(
+            "'{}' is not how often a task can recur; use e.g. daily, weekly, 2 weeks or 3 months.",
+            every.trim()
+        ))
+    };
+    let text = every.trim().to_lowercase();
+    let text = text.strip_prefix("every ").unwrap_or(&text).trim();
+    let named = match text {
+        "daily" => Some(Unit::Day),
+        "weekly" => Some(Unit::Week),
+        "monthly" => Some(Unit::Month),
+        "yearly" | "annually" => Some(Unit::Year),
+        _ => None,
+    };
+    if let Some(unit) = named {
+        return Ok(Every { count: 1, unit });
+    }
+    let digits = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (count, unit) = text.split_at(digits);
+    let count = if count.is_empty() { 1 } else { count.parse().map_err(|_| invalid())? };
+    let unit = match unit.trim().trim_end_matches('s') {
+        "d" | "day" => Unit::Day,
+        "w" | "week" => Unit::Week,
+        "m" | "month" => Unit::Month,
+        "y" | "year" => Unit::Year,
+        _ => return Err(invalid()),
+    };
+    if count == 0 {
+        return Err(invalid());
+    }
+    Ok(Every { count, unit })
+}
+
+pub async fn run(repo: &TaskRepository, id: i32, every: Option<&str>) -> Result<()> {
+    let every = every.map(parse).transpose()?;
+    let Some(task) = repo.get(id).await? else {
+        return Err(TaskError::InvalidInput(format!("There is no task with ID {}.", id)));
+    };
+    if every.is_some() && task.due_at.is_none() {
+        return Err(TaskError::InvalidInput(format!(
+            "Task {} has no due date; give it one first, as the first time it is due.",
+            id
+        )));
+    }
+    let stored = every.map(|every| every.to_string());
+    if !repo.set_recurrence(id, stored.as_deref()).await? {
+        return Err(TaskError::InvalidInput(format!("You can't change a task with ID {}.", id)));
+    }
+    match (every, task.due_at) {
+        (Some(every), Some(first)) => {
+            let every = if every.count == 1 { every.unit.as_str().to_string() } else { every.to_string() };
+            println!("Task {} recurs every {}, starting {}.", id, every, format_due(&first));
+        }
+        _ => println!("Task {} no longer recurs.", id),
+    }
+    Ok(())
+}
+
+// The daemon's job: the occurrences of every recurring task that fall within the horizon
+pub async fn create_upcoming(repo: &TaskRepository, config: &DaemonConfig) -> Result<()> {
+    let now = Local::now().naive_local();
+    let until = now + Days::new(config.recur_horizon.unwrap_or(DEFAULT_HORIZON).into());
+    for recurring in repo.recurring_tasks().await? {
+        let Some(first) = recurring.due_at else {
+            continue;
+        };
+        let every = match parse(&recurring.every) {
+            Ok(every) => every,
+            Err(e) => {
+                tracing::warn!(task_id = recurring.task_id, error = %e, "recurring task skipped");
+                continue;
+            }
+        };
+        let after = recurring.last_occurrence.map_or(recurring.since, |last| last.max(recurring.since));
+        let due = occurrences(first, every, after, until);
+        if due.is_empty() {
+            continue;
+        }
+        let created = repo.create_occurrences(recurring.task_id, &due).await?;
+        if !created.is_empty() {
+            let count = created.len();
+            tracing::info!(task_id = recurring.task_id, created = count, "recurring task occurrences created");
+            let plural = if count == 1 { "" } else { "s" };
+            let (id, description) = (recurring.task_id, &recurring.description);
+            println!("Created {} occurrence{} of task {} ({}).", count, plural, id, description);
+        }
+    }
+    Ok(())
+}
+
+// The occurrences after `first` that are later than `after` and no later than `until`, oldest
+// first and at most MAX_PER_RUN of them
+pub fn occurrences(
+    first: NaiveDateTime,
+    every: Every,
+    after: NaiveDateTime,
+    until: NaiveDateTime,
+) -> Vec<NaiveDateTime> {
+    (1..)
+        .map_while(|n| every.nth(first, n))
+        .skip_while(|&at| at <= after)
+        .take_while(|&at| at <= until)
+        .take(MAX_PER_RUN)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::at;
+
+    #[test]
+    fn how_often_is_read_in_words_or_short() {
+        let every = |text: &str| parse(text).unwrap();
+        assert_eq!(every("weekly"), Every { count: 1, unit: Unit::Week });
+        assert_eq!(every("Every 2 weeks"), Every { count: 2, unit: Unit::Week });
+        assert_eq!(every("3 months"), Every { count: 3, unit: Unit::Month });
+        assert_eq!(every("10d"), Every { count: 10, unit: Unit::Day });
+        assert_eq!(every("year"), Every { count: 1, unit: Unit::Year });
+        for nonsense in ["", "0 days", "fortnightly", "2 fortnights", "-1 week"] {
+            assert!(parse(nonsense).is_err(), "{:?}", nonsense);
+        }
+        // What is stored reads back the same
+        assert_eq!(every(&every("2w").to_string()), every("2w"));
+        assert_eq!(every("monthly").to_string(), "1 month");
+    }
+
+    #[test]
+    fn months_keep_their_day_or_take_the_last() {
+        let monthly = Every { count: 1, unit: Unit::Month };
+        let end_of_january = chrono::NaiveDate::from_ymd_opt(2026, 1, 31).unwrap().and_hms_opt(9, 0, 0).unwrap();
+        assert_eq!(monthly.nth(end_of_january, 1).unwrap().format("%m-%d").to_string(), "02-28");
+        // Counted from the first, so March gets its 31st back
+        assert_eq!(monthly.nth(end_of_january, 2).unwrap().format("%m-%d").to_string(), "03-31");
+    }
+
+    #[test]
+    fn only_occurrences_in_the_window_are_created() {
+        let weekly = Every { count: 1, unit: Unit::Week };
+        // Due on the 2nd, last created for the 9th, looking ahead to the 24th
+        assert_eq!(occurrences(at(2), weekly, at(9), at(24)), [at(16), at(23)]);
+        assert_eq!(occurrences(at(2), weekly, at(9), at(16)), [at(16)]);
+        assert!(occurrences(at(2), weekly, at(23), at(24)).is_empty());
+        let daily = Every { count: 1, unit: Unit::Day };
+        assert_eq!(occurrences(at(1), daily, at(1), at(1) + Days::new(1000)).len(), MAX_PER_RUN);
+    }
+}
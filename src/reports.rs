@@ -0,0 +1,581 @@
+// `task report <name>`: reports defined in the config file, like Taskwarrior's. Each is a
+// filter, which columns to show, how to sort and how to group the tasks, shown as a table or
+// written as JSON or CSV.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use chrono::{Days, Local, NaiveTime};
+use serde::ser::{SerializeMap, Serializer};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::cli::{CustomReportArgs, ReportFormat};
+use crate::config::{Config, ReportConfig};
+use crate::error::{Result, TaskError};
+use crate::export;
+use crate::priority::Priority;
+use crate::repository::{ListCursor, TaskFilter, TaskRepository};
+use crate::{format_due, format_timestamp, Task};
+
+// Tasks read per query
+const FETCH: u32 = 500;
+// How times are written in JSON, as serde writes them elsewhere
+const JSON_TIME: &str = "%Y-%m-%dT%H:%M:%S";
+const DEFAULT_COLUMNS: [ReportColumn; 5] =
+    [ReportColumn::Id, ReportColumn::Description, ReportColumn::Due, ReportColumn::Priority, ReportColumn::Project];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportColumn {
+    Id,
+    Description,
+    Status,
+    Due,
+    Priority,
+    Project,
+    Tags,
+    Created,
+    Updated,
+}
+
+impl ReportColumn {
+    const ALL: [ReportColumn; 9] = [
+        ReportColumn::Id,
+        ReportColumn::Description,
+        ReportColumn::Status,
+        ReportColumn::Due,
+        ReportColumn::Priority,
+        ReportColumn::Project,
+        ReportColumn::Tags,
+        ReportColumn::Created,
+        ReportColumn::Updated,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ReportColumn::Id => "id",
+            ReportColumn::Description => "description",
+            ReportColumn::Status => "status",
+            ReportColumn::Due => "due",
+            ReportColumn::Priority => "priority",
+            ReportColumn::Project => "project",
+            ReportColumn::Tags => "tags",
+            ReportColumn::Created => "created",
+            ReportColumn::Updated => "updated",
+        }
+    }
+
+    fn heading(self) -> &'static str {
+        match self {
+            ReportColumn::Id => "ID",
+            ReportColumn::Description => "Description",
+            ReportColumn::Status => "Status",
+            ReportColumn::Due => "Due",
+            ReportColumn::Priority => "Priority",
+            ReportColumn::Project => "Project",
+            ReportColumn::Tags => "Tags",
+            ReportColumn::Created => "Created",
+            ReportColumn::Updated => "Updated",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportGroup {
+    Project,
+    // A task with several tags is in the group of each
+    Tag,
+    Priority,
+    Status,
+    // The day it is due
+    Due,
+}
+
+// A task with what the columns show of it besides its own fields
+struct Row<'a> {
+    task: &'a Task,
+    project: Option<&'a str>,
+    tags: &'a [String],
+}
+
+// A column to sort by, and whether the other way round
+type SortKey = (ReportColumn, bool);
+// Where a group goes: after the others if it is for the tasks without a value, by priority if
+// grouped by that, else by its name
+type GroupKey = (bool, Option<Reverse<Priority>>, String);
+
+pub async fn run(repo: &TaskRepository, config: &Config, args: CustomReportArgs) -> Result<()> {
+    let Some(name) = args.name else {
+        list(config);
+        return Ok(());
+    };
+    let report = config.reports.get(&name).ok_or_else(|| {
+        TaskError::InvalidInput(format!("There is no report named {}; `task report` lists them.", name))
+    })?;
+    let sort = sort_keys(&name, &report.sort)?;
+    let tasks = load(repo, &filter(report)).await?;
+    let projects = export::load_project_names(repo.pool()).await?;
+    let tags = export::load_tags(repo.pool()).await?;
+
+    let mut rows: Vec<Row> = tasks
+        .iter()
+        .map(|task| Row {
+            task,
+            project: task.project_id.and_then(|id| projects.get(&id)).map(String::as_str),
+            tags: tags.get(&task.id).map_or(&[], Vec::as_slice),
+        })
+        .collect();
+    sort_rows(&mut rows, &sort);
+    if let Some(limit) = report.limit {
+        rows.truncate(limit);
+    }
+
+    let columns = if report.columns.is_empty() { &DEFAULT_COLUMNS[..] } else { &report.columns[..] };
+    let title = report.description.as_deref().unwrap_or(&name);
+    let (groups, grouped) = (groups(&rows, report.group_by), report.group_by.is_some());
+    let mut out = export::open_output(args.output.as_deref())?;
+    match args.format.unwrap_or(report.format) {
+        ReportFormat::Table => out.write_all(table(title, columns, &groups, grouped).as_bytes())?,
+        ReportFormat::Json => write_json(columns, &groups, grouped, &mut out)?,
+        ReportFormat::Csv => write_csv(columns, &groups, grouped, &mut out)?,
+    }
+    out.flush()?;
+    drop(out);
+    if let Some(path) = &args.output {
+        println!("Wrote the {} tasks of report {} to {}", rows.len(), name, path.display());
+    }
+    Ok(())
+}
+
+// `task report` alone: the reports there are
+fn list(config: &Config) {
+    println!("\n--- Reports ---");
+    if config.reports.is_empty() {
+        println!("None yet; define them as [reports.<name>] in the config file.");
+        return;
+    }
+    let width = config.reports.keys().map(|name| name.chars().count()).max().unwrap_or(0);
+    for (name, report) in &config.reports {
+        println!("{:<width$}  {}", name, report.description.as_deref().unwrap_or(""));
+    }
+}
+
+// The days of `due_before` and `due_after` count from the start of today
+fn filter(report: &ReportConfig) -> TaskFilter {
+    let today = Local::now().date_naive().and_time(NaiveTime::MIN);
+    let day = |days: i64| match u64::try_from(days) {
+        Ok(days) => today + Days::new(days),
+        Err(_) => today - Days::new(days.unsigned_abs()),
+    };
+    TaskFilter {
+        completed: report.completed,
+        project: report.project.clone(),
+        tag: report.tag.clone(),
+        priority: report.priority,
+        due_before: report.due_before.map(day),
+        due_after: report.due_after.map(day),
+        assignee: None,
+    }
+}
+
+// Every task matching `filter`, newest first
+async fn load(repo: &TaskRepository, filter: &TaskFilter) -> Result<Vec<Task>> {
+    let mut tasks = Vec::new();
+    let mut cursor: Option<ListCursor> = None;
+    loop {
+        let page = repo.filter_page(filter, cursor, FETCH).await?;
+        tasks.extend(page.tasks);
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => return Ok(tasks),
+        }
+    }
+}
+
+// "due" and "-priority", as in the config file
+fn sort_keys(report: &str, sort: &[String]) -> Result<Vec<SortKey>> {
+    sort.iter()
+        .map(|key| {
+            let (name, descending) = match key.trim().strip_prefix('-') {
+                Some(name) => (name, true),
+                None => (key.trim(), false),
+            };
+            let column = ReportColumn::ALL.into_iter().find(|column| column.name() == name).ok_or_else(|| {
+                let names: Vec<&str> = ReportColumn::ALL.iter().map(|column| column.name()).collect();
+                TaskError::Config(format!(
+                    "Invalid sort '{}' in [reports.{}]; expected one of {}, with a - first to reverse it.",
+                    key,
+                    report,
+                    names.join(", ")
+                ))
+            })?;
+            Ok((column, descending))
+        })
+        .collect()
+}
+
+// By each key in turn; rows equal in all of them stay newest first. Empty values go last
+// either way round.
+fn sort_rows(rows: &mut [Row], sort: &[SortKey]) {
+    rows.sort_by(|a, b| {
+        let mut orders = sort.iter().map(|&(column, descending)| compare(a, b, column, descending));
+        orders.find(|order| order.is_ne()).unwrap_or(Ordering::Equal)
+    });
+}
+
+fn compare(a: &Row, b: &Row, column: ReportColumn, descending: bool) -> Ordering {
+    let (x, y) = (a.task, b.task);
+    match column {
+        ReportColumn::Id => order(Some(x.id), Some(y.id), descending),
+        ReportColumn::Description => {
+            order(Some(x.description.to_lowercase()), Some(y.description.to_lowercase()), descending)
+        }
+        ReportColumn::Status => order(Some(x.completed), Some(y.completed), descending),
+        ReportColumn::Due => order(x.due_at, y.due_at, descending),
+        ReportColumn::Priority => order(x.priority, y.priority, descending),
+        ReportColumn::Project => order(a.project, b.project, descending),
+        ReportColumn::Tags => order(a.tags.first(), b.tags.first(), descending),
+        ReportColumn::Created => order(Some(x.created_at), Some(y.created_at), descending),
+        ReportColumn::Updated => order(Some(x.updated_at), Some(y.updated_at), descending),
+    }
+}
+
+fn order<T: Ord>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+    match (a, b) {
+        (Some(a), Some(b)) if descending => b.cmp(&a),
+        (Some(a), Some(b)) => a.cmp(&b),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    }
+}
+
+// The rows per group, each in the order sorted; groups by name, the highest priority first and
+// the tasks without a value last. Not grouped, all rows are in one group without a name.
+fn groups<'a>(rows: &'a [Row<'a>], by: Option<ReportGroup>) -> Vec<(String, Vec<&'a Row<'a>>)> {
+    let Some(by) = by else {
+        return vec![(String::new(), rows.iter().collect())];
+    };
+    let mut groups: BTreeMap<GroupKey, Vec<&Row>> = BTreeMap::new();
+    for row in rows {
+        let task = row.task;
+        let keys = match by {
+            ReportGroup::Project => {
+                vec![(row.project.is_none(), None, row.project.unwrap_or("(no project)").to_string())]
+            }
+            ReportGroup::Tag if row.tags.is_empty() => vec![(true, None, "(no tag)".to_string())],
+            ReportGroup::Tag => row.tags.iter().map(|tag| (false, None, tag.clone())).collect(),
+            ReportGroup::Priority => vec![(
+                task.priority.is_none(),
+                task.priority.map(Reverse),
+                task.priority.map_or("(no priority)", |priority| priority.as_str()).to_string(),
+            )],
+            ReportGroup::Status => vec![(false, None, status(task).to_string())],
+            ReportGroup::Due => vec![(
+                task.due_at.is_none(),
+                None,
+                task.due_at.map_or("(no due date)".to_string(), |due| due.format("%Y-%m-%d").to_string()),
+            )],
+        };
+        for key in keys {
+            groups.entry(key).or_default().push(row);
+        }
+    }
+    groups.into_iter().map(|((_, _, name), rows)| (name, rows)).collect()
+}
+
+fn status(task: &Task) -> &'static str {
+    if task.completed { "completed" } else { "pending" }
+}
+
+// What a column shows of a row, empty for nothing
+fn text(row: &Row, column: ReportColumn) -> String {
+    let task = row.task;
+    match column {
+        ReportColumn::Id => task.id.to_string(),
+        ReportColumn::Description => task.description.clone(),
+        ReportColumn::Status => status(task).to_string(),
+        ReportColumn::Due => task.due_at.as_ref().map(format_due).unwrap_or_default(),
+        ReportColumn::Priority => task.priority.map(|priority| priority.as_str().to_string()).unwrap_or_default(),
+        ReportColumn::Project => row.project.unwrap_or_default().to_string(),
+        ReportColumn::Tags => row.tags.join(", "),
+        ReportColumn::Created => format_timestamp(&task.created_at),
+        ReportColumn::Updated => format_timestamp(&task.updated_at),
+    }
+}
+
+// Like `text`, with numbers, lists and nulls as such
+fn json(row: &Row, column: ReportColumn) -> Value {
+    let task = row.task;
+    match column {
+        ReportColumn::Id => Value::from(task.id),
+        ReportColumn::Tags => Value::from(row.tags.to_vec()),
+        ReportColumn::Due => task.due_at.map_or(Value::Null, |due| Value::from(due.format(JSON_TIME).to_string())),
+        ReportColumn::Priority => task.priority.map_or(Value::Null, |priority| Value::from(priority.as_str())),
+        ReportColumn::Project => row.project.map_or(Value::Null, Value::from),
+        ReportColumn::Created => Value::from(task.created_at.format(JSON_TIME).to_string()),
+        ReportColumn::Updated => Value::from(task.updated_at.format(JSON_TIME).to_string()),
+        ReportColumn::Description | ReportColumn::Status => Value::from(text(row, column)),
+    }
+}
+
+type Groups<'a> = [(String, Vec<&'a Row<'a>>)];
+
+// A section per group, with the columns lined up across all of them
+fn table(title: &str, columns: &[ReportColumn], groups: &Groups, grouped: bool) -> String {
+    let mut out = String::new();
+    let cells = |row: &Row| -> Vec<String> {
+        columns
+            .iter()
+            .map(|&column| match text(row, column) {
+                text if text.is_empty() => "-".to_string(),
+                text => text,
+            })
+            .collect()
+    };
+    let mut widths: Vec<usize> = columns.iter().map(|column| column.heading().len()).collect();
+    for row in groups.iter().flat_map(|(_, rows)| rows) {
+        for (width, cell) in widths.iter_mut().zip(cells(row)) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |cells: Vec<String>| {
+        let cells: Vec<String> = cells.iter().zip(&widths).map(|(cell, &width)| format!("{:<width$}", cell)).collect();
+        cells.join("  ").trim_end().to_string()
+    };
+
+    let count: usize = groups.iter().map(|(_, rows)| rows.len()).sum();
+    if count == 0 {
+        let _ = writeln!(out, "\n--- {} (0) ---\nNo tasks found.", title);
+        return out;
+    }
+    for (name, rows) in groups {
+        if grouped {
+            let _ = writeln!(out, "\n--- {}: {} ({}) ---", title, name, rows.len());
+        } else {
+            let _ = writeln!(out, "\n--- {} ({}) ---", title, rows.len());
+        }
+        let _ = writeln!(out, "{}", line(columns.iter().map(|column| column.heading().to_string()).collect()));
+        for row in rows {
+            let _ = writeln!(out, "{}", line(cells(row)));
+        }
+    }
+    out
+}
+
+// A task as a JSON object with its columns in order
+struct Record<'a>(&'a [ReportColumn], &'a Row<'a>);
+
+impl Serialize for Record<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for &column in self.0 {
+            map.serialize_entry(column.name(), &json(self.1, column))?;
+        }
+        map.end()
+    }
+}
+
+fn records<'a>(columns: &'a [ReportColumn], rows: &'a [&'a Row<'a>]) -> Vec<Record<'a>> {
+    rows.iter().map(|row| Record(columns, row)).collect()
+}
+
+#[derive(Serialize)]
+struct Group<'a> {
+    group: &'a str,
+    tasks: Vec<Record<'a>>,
+}
+
+// An array of tasks, or grouped an array of {"group": ..., "tasks": [...]}
+fn write_json(columns: &[ReportColumn], groups: &Groups, grouped: bool, mut out: impl Write) -> Result<()> {
+    if grouped {
+        let groups: Vec<Group> =
+            groups.iter().map(|(name, rows)| Group { group: name, tasks: records(columns, rows) }).collect();
+        serde_json::to_writer_pretty(&mut out, &groups).map_err(std::io::Error::from)?;
+    } else {
+        let records: Vec<Record> = groups.iter().flat_map(|(_, rows)| records(columns, rows)).collect();
+        serde_json::to_writer_pretty(&mut out, &records).map_err(std::io::Error::from)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+// A row per task, grouped with the group's name first
+fn write_csv(columns: &[ReportColumn], groups: &Groups, grouped: bool, out: impl Write) -> Result<()> {
+    let mut writer = ::csv::Writer::from_writer(out);
+    let invalid = |e: ::csv::Error| TaskError::Io(std::io::Error::other(e));
+    let group = if grouped { vec!["group"] } else { Vec::new() };
+    writer.write_record(group.into_iter().chain(columns.iter().map(|column| column.name()))).map_err(invalid)?;
+    for (name, rows) in groups {
+        for row in rows {
+            let group = if grouped { vec![name.clone()] } else { Vec::new() };
+            writer
+                .write_record(group.into_iter().chain(columns.iter().map(|&column| text(row, column))))
+                .map_err(invalid)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{at, at_time, task, UNICODE};
+
+    // Tasks in two projects and none, with tags, priorities and due dates in different orders
+    fn tasks() -> Vec<Task> {
+        let mut report = task(1, "Write the report", 2);
+        report.due_at = Some(at(12));
+        report.priority = Some(Priority::Medium);
+        report.project_id = Some(1);
+        let mut call = task(2, "Call the bank", 3);
+        call.due_at = Some(at_time(10, 0, 0));
+        call.priority = Some(Priority::High);
+        let mut trip = task(3, UNICODE, 4);
+        trip.project_id = Some(2);
+        let mut done = task(4, "Book the flights", 5);
+        done.completed = true;
+        done.due_at = Some(at(9));
+        done.project_id = Some(2);
+        vec![report, call, trip, done]
+    }
+
+    fn rows<'a>(tasks: &'a [Task], projects: &'a [&'a str], tags: &'a [Vec<String>]) -> Vec<Row<'a>> {
+        tasks
+            .iter()
+            .map(|task| Row {
+                task,
+                project: task.project_id.map(|id| projects[id as usize - 1]),
+                tags: &tags[task.id as usize - 1],
+            })
+            .collect()
+    }
+
+    fn ids(rows: &[Row]) -> Vec<i32> {
+        rows.iter().map(|row| row.task.id).collect()
+    }
+
+    const PROJECTS: [&str; 2] = ["Work", "Reisen ✈"];
+
+    fn tags() -> Vec<Vec<String>> {
+        vec![vec!["writing".to_string()], vec!["calls".to_string(), "money".to_string()], Vec::new(), Vec::new()]
+    }
+
+    #[test]
+    fn rows_sort_by_each_key_in_turn_with_empty_values_last() {
+        let (tasks, tags) = (tasks(), tags());
+        let mut rows = rows(&tasks, &PROJECTS, &tags);
+        sort_rows(&mut rows, &sort_keys("test", &["due".to_string()]).unwrap());
+        assert_eq!(ids(&rows), [4, 2, 1, 3]);
+        sort_rows(&mut rows, &sort_keys("test", &["-priority".to_string(), "id".to_string()]).unwrap());
+        assert_eq!(ids(&rows), [2, 1, 3, 4]);
+        sort_rows(&mut rows, &sort_keys("test", &[" -project ".to_string(), "-id".to_string()]).unwrap());
+        assert_eq!(ids(&rows), [1, 4, 3, 2]);
+    }
+
+    #[test]
+    fn unknown_sort_columns_are_refused() {
+        let error = sort_keys("week", &["due".to_string(), "-urgency".to_string()]).unwrap_err().to_string();
+        assert!(error.contains("Invalid sort '-urgency' in [reports.week]"), "{}", error);
+    }
+
+    #[test]
+    fn groups_come_in_order_with_the_tasks_without_a_value_last() {
+        let (tasks, tags) = (tasks(), tags());
+        let rows = rows(&tasks, &PROJECTS, &tags);
+        let names = |by| {
+            let groups = groups(&rows, Some(by));
+            groups.into_iter().map(|(name, rows)| (name, rows.len())).collect::<Vec<_>>()
+        };
+        let count = |name: &str, count| (name.to_string(), count);
+        assert_eq!(names(ReportGroup::Project), [count("Reisen ✈", 2), count("Work", 1), count("(no project)", 1)]);
+        assert_eq!(names(ReportGroup::Priority), [count("high", 1), count("medium", 1), count("(no priority)", 2)]);
+        // The bank call is in the groups of both its tags
+        let by_tag = [count("calls", 1), count("money", 1), count("writing", 1), count("(no tag)", 2)];
+        assert_eq!(names(ReportGroup::Tag), by_tag);
+        assert_eq!(groups(&rows, None).len(), 1);
+    }
+
+    #[test]
+    fn table_snapshot() {
+        let (tasks, tags) = (tasks(), tags());
+        let mut rows = rows(&tasks, &PROJECTS, &tags);
+        sort_rows(&mut rows, &[(ReportColumn::Due, false)]);
+        let columns = [ReportColumn::Id, ReportColumn::Description, ReportColumn::Due, ReportColumn::Tags];
+        insta::assert_snapshot!(table("Due soon", &columns, &groups(&rows, Some(ReportGroup::Project)), true));
+    }
+
+    #[test]
+    fn an_empty_table_says_so() {
+        let table = table("Overdue", &DEFAULT_COLUMNS, &groups(&[], None), false);
+        assert_eq!(table, "\n--- Overdue (0) ---\nNo tasks found.\n");
+    }
+
+    #[test]
+    fn json_keeps_the_columns_in_order_with_their_types() {
+        let (tasks, tags) = (tasks(), tags());
+        let rows = rows(&tasks, &PROJECTS, &tags);
+        let columns = [ReportColumn::Id, ReportColumn::Tags, ReportColumn::Due, ReportColumn::Project];
+        let mut out = Vec::new();
+        write_json(&columns, &groups(&rows[1..2], None), false, &mut out).unwrap();
+        let json = String::from_utf8(out).unwrap();
+        let expected = r#"[
+  {
+    "id": 2,
+    "tags": [
+      "calls",
+      "money"
+    ],
+    "due": "2026-03-10T00:00:00",
+    "project": null
+  }
+]
+"#;
+        assert_eq!(json, expected);
+
+        let mut out = Vec::new();
+        write_json(&[ReportColumn::Id], &groups(&rows, Some(ReportGroup::Status)), true, &mut out).unwrap();
+        let value: Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value[0]["group"], "completed");
+        assert_eq!(value[1]["tasks"].as_array().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn csv_puts_the_group_first() {
+        let (tasks, tags) = (tasks(), tags());
+        let rows = rows(&tasks, &PROJECTS, &tags);
+        let mut out = Vec::new();
+        let columns = [ReportColumn::Id, ReportColumn::Tags, ReportColumn::Status];
+        write_csv(&columns, &groups(&rows, Some(ReportGroup::Priority)), true, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "group,id,tags,status");
+        assert_eq!(lines[1..3], ["high,2,\"calls, money\",pending", "medium,1,writing,pending"]);
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn reports_are_read_from_the_config_file() {
+        let config: Config = toml::from_str(
+            r#"
+            [reports.week]
+            completed = false
+            priority = "high"
+            due_before = 7
+            columns = ["id", "description", "tags"]
+            group_by = "tag"
+            sort = ["-due"]
+            format = "csv"
+            "#,
+        )
+        .unwrap();
+        let week = &config.reports["week"];
+        assert_eq!(week.columns, [ReportColumn::Id, ReportColumn::Description, ReportColumn::Tags]);
+        assert_eq!((week.group_by, week.format), (Some(ReportGroup::Tag), ReportFormat::Csv));
+        assert_eq!((week.priority, week.due_before), (Some(Priority::High), Some(7)));
+        assert!(toml::from_str::<Config>("[reports.week]\ncolumns = [\"urgency\"]").is_err());
+    }
+}
@@ -0,0 +1,4035 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use futures::stream::BoxStream;
+use sqlx::mysql::{MySqlArguments, MySqlDatabaseError};
+use sqlx::pool::PoolConnection;
+use sqlx::query::{Query, QueryAs, QueryScalar};
+use sqlx::{Connection, MySql, MySqlConnection, MySqlPool, QueryBuilder};
+
+use crate::activity::Action;
+use crate::api_tokens::Scope;
+use crate::bulk::{self, BulkPolicy, BulkReport};
+use crate::cache::{CachePolicy, ListCache, ListKey};
+use crate::db::{self, RetryPolicy};
+use crate::metrics::Metrics;
+use crate::priority::Priority;
+use crate::roles::{forbidden, Forbidden, Permission, Role};
+use crate::Task;
+
+// Queries throughout the crate use sqlx's runtime API (`query`, `query_as` + `FromRow`) rather
+// than the `query!` macros, so building needs neither a live database nor prepared query
+// metadata. Column lists in SELECTs must therefore match the `FromRow` structs by name.
+
+// Columns of `Task`, for building SELECTs with `concat!`
+macro_rules! task_columns {
+    () => {
+        "id, description, completed, created_at, created_by, updated_by, updated_at, due_at, priority, \
+         project_id, owner_id, assignee_id, workspace_id"
+    };
+}
+pub(crate) use task_columns;
+
+// Limits a query on `tasks` to those of the repository's user in its workspace (see
+// `TaskRepository::user`), bound with `BindAccess::bind_owned`. For what only the owner deals
+// with, like syncs and reminders.
+macro_rules! owned {
+    () => {
+        "(tasks.workspace_id <=> ? AND (? IS NULL OR tasks.owner_id = ?))"
+    };
+}
+pub(crate) use owned;
+
+// Limits a query on `tasks` to those in the repository's workspace that the user owns, is
+// assigned or that were shared with them, on their own or with their project. Bound with
+// `BindAccess::bind_access`.
+macro_rules! readable {
+    () => {
+        "(tasks.workspace_id <=> ? AND (? IS NULL OR tasks.owner_id = ? OR tasks.assignee_id = ? \
+          OR tasks.id IN (SELECT task_id FROM task_shares WHERE user_id = ?) \
+          OR (tasks.owner_id, tasks.project_id) IN (SELECT owner_id, project_id FROM project_shares WHERE user_id = ?)))"
+    };
+}
+pub(crate) use readable;
+
+// Columns of a `TimeEntry`, from time_entries joined with tasks and users
+macro_rules! time_entry_columns {
+    () => {
+        "time_entries.task_id, tasks.description, tasks.project_id, users.username, time_entries.started_at, \
+         TIMESTAMPDIFF(SECOND, time_entries.started_at, COALESCE(time_entries.ended_at, NOW())) AS seconds"
+    };
+}
+
+// Counts of a `Breakdown`, over tasks joined with task_estimates
+macro_rules! breakdown_columns {
+    () => {
+        "COUNT(CASE WHEN NOT tasks.completed THEN 1 END) AS open, \
+         COUNT(CASE WHEN tasks.completed THEN 1 END) AS completed, \
+         CAST(COALESCE(SUM(CASE WHEN NOT tasks.completed THEN task_estimates.minutes END), 0) AS SIGNED) \
+           AS estimate_minutes, \
+         COUNT(CASE WHEN NOT tasks.completed AND task_estimates.minutes IS NULL THEN 1 END) AS unestimated"
+    };
+}
+
+// Like `readable`, but only read-write shares count. The subqueries don't read `tasks`, so
+// this also works in UPDATE and DELETE on it.
+macro_rules! writable {
+    () => {
+        "(tasks.workspace_id <=> ? AND (? IS NULL OR tasks.owner_id = ? OR tasks.assignee_id = ? \
+          OR tasks.id IN (SELECT task_id FROM task_shares WHERE user_id = ? AND can_write) \
+          OR (tasks.owner_id, tasks.project_id) IN \
+             (SELECT owner_id, project_id FROM project_shares WHERE user_id = ? AND can_write)))"
+    };
+}
+
+// Binds the workspace and user for `owned`, `readable` and `writable`
+pub(crate) trait BindAccess {
+    fn bind_owned(self, access: Access) -> Self;
+    fn bind_access(self, access: Access) -> Self;
+}
+
+impl<'q> BindAccess for Query<'q, MySql, MySqlArguments> {
+    fn bind_owned(self, access: Access) -> Self {
+        self.bind(access.workspace).bind(access.user).bind(access.user)
+    }
+
+    fn bind_access(self, access: Access) -> Self {
+        let user = access.user;
+        self.bind(access.workspace).bind(user).bind(user).bind(user).bind(user).bind(user)
+    }
+}
+
+impl<'q, O> BindAccess for QueryAs<'q, MySql, O, MySqlArguments> {
+    fn bind_owned(self, access: Access) -> Self {
+        self.bind(access.workspace).bind(access.user).bind(access.user)
+    }
+
+    fn bind_access(self, access: Access) -> Self {
+        let user = access.user;
+        self.bind(access.workspace).bind(user).bind(user).bind(user).bind(user).bind(user)
+    }
+}
+
+impl<'q, O> BindAccess for QueryScalar<'q, MySql, O, MySqlArguments> {
+    fn bind_owned(self, access: Access) -> Self {
+        self.bind(access.workspace).bind(access.user).bind(access.user)
+    }
+
+    fn bind_access(self, access: Access) -> Self {
+        let user = access.user;
+        self.bind(access.workspace).bind(user).bind(user).bind(user).bind(user).bind(user)
+    }
+}
+
+// Columns of an `Escalation`, from task_escalations joined with tasks
+macro_rules! escalation_columns {
+    () => {
+        "task_escalations.task_id, tasks.description, task_escalations.due_at, task_escalations.escalated_at, \
+         task_escalations.days_overdue, task_escalations.old_priority, task_escalations.new_priority, \
+         task_escalations.channel, task_escalations.notified_at"
+    };
+}
+
+// Columns of `WebhookDelivery`
+macro_rules! webhook_delivery_columns {
+    () => {
+        "id, url, event, task_id, payload, status, attempts, response_code, error, created_at, last_attempt_at"
+    };
+}
+
+// MySQL error numbers meaning FULLTEXT search isn't available for this table:
+// 1191 = no FULLTEXT index matching the column list, 1214 = storage engine lacks FULLTEXT support
+const NO_FULLTEXT_ERRORS: [u16; 2] = [1191, 1214];
+
+// Operations that leave what `list_page` and `filter_page` return as it is: the reads, and the
+// bookkeeping writes of sessions, the daemon, notifications and webhooks. Every other one empties
+// the list cache, so an operation missing here only costs a query.
+const KEEPS_LISTS: &[&str] = &[
+    "accounts", "activity", "api_token_login", "api_tokens", "assignments", "breakdown_by_project", "breakdown_by_tag",
+    "caldav_ctag", "caldav_resources", "caldav_unlinked_pending", "checklist", "claim_daemon", "claim_notification",
+    "completion_stats", "completions_by_day", "create_session", "daemon_heartbeat", "daemon_jobs", "daemon_state",
+    "delete_session", "description_exists", "digest_recipients", "due_load", "escalations", "external_id_exists",
+    "filter_page", "fulltext_search", "get", "identity_user", "instance_stats", "invitation_by_code", "invitations",
+    "is_member", "like_search", "linked_tasks", "links", "list_page", "log_webhook_delivery",
+    "mark_escalation_notified", "members", "mentions", "notes", "notification_sent", "notifications_sent_since",
+    "notion_last_edited", "open_changes", "open_spans", "password_hash", "pending_due_before", "ping", "project_id",
+    "project_name", "record_daemon_job", "record_webhook_attempt", "recurring_tasks", "release_daemon",
+    "release_notification", "rotate_session", "running_timer", "session", "settings", "shares", "sms_alert_tasks",
+    "start_timer", "stop_timer", "tags", "time_entries", "undelivered_webhook_deliveries", "unescalated_overdue",
+    "unlinked_pending", "unnotified_escalations", "user_by_name", "user_count", "user_disabled", "username", "users",
+    "webhook_deliveries", "workspace_id", "workspaces",
+];
+
+// Rows per multi-row INSERT in bulk inserts. 14 placeholders per row keeps each statement far
+// below MySQL's 65535 placeholder limit while still replacing hundreds of round trips with one.
+pub const BATCH_SIZE: usize = 500;
+
+// Task to be inserted in bulk. With `id: None` MySQL assigns the next auto-increment id.
+// Missing audit fields are filled in with the repository's actor by `insert_batch` and `import`.
+#[derive(Debug, Clone)]
+pub struct NewTask {
+    pub id: Option<i32>,
+    pub description: String,
+    pub completed: bool,
+    pub created_at: NaiveDateTime,
+    pub created_by: Option<String>,
+    pub updated_by: Option<String>,
+    pub updated_at: NaiveDateTime,
+    // Of a completed task; see `NewTask::completed_at`
+    pub completed_at: Option<NaiveDateTime>,
+    pub due_at: Option<NaiveDateTime>,
+    pub priority: Option<Priority>,
+    pub project_id: Option<i32>,
+    // Filled in with the repository's user when missing
+    pub owner_id: Option<i32>,
+    pub assignee_id: Option<i32>,
+    // Filled in with the repository's workspace when missing
+    pub workspace_id: Option<i32>,
+}
+
+impl NewTask {
+    // What is stored as completed_at: NULL while pending, and for a completed task that doesn't
+    // say when, its last change, as the migration that added the column guessed
+    fn completed_at(&self) -> Option<NaiveDateTime> {
+        self.completed.then(|| self.completed_at.unwrap_or(self.updated_at))
+    }
+}
+
+// A task together with what hangs off it, referenced by name rather than id so it can come
+// from another database. Used by the importers.
+#[derive(Debug, Clone)]
+pub struct TaskBundle {
+    pub task: NewTask,
+    pub tags: Vec<String>,
+    pub project: Option<String>,
+    pub checklist: Vec<ChecklistItem>,
+    pub notes: Vec<Note>,
+    // Identity in the system the task was imported from
+    pub external: Option<ExternalId>,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct Note {
+    pub created_at: NaiveDateTime,
+    pub body: String,
+    // Who wrote it; None for notes from imports and syncs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+}
+
+// What `add_comment` made of the names a note mentions
+#[derive(Debug, Clone, Default)]
+pub struct Mentioned {
+    // Users who will be told about the note
+    pub notified: Vec<String>,
+    // Users who can't see the task, so aren't told
+    pub unseen: Vec<String>,
+}
+
+// A note that names a user, for telling them about it
+#[derive(Debug, sqlx::FromRow)]
+pub struct Mention {
+    pub note_id: i32,
+    pub user_id: i32,
+    // The user mentioned
+    pub username: String,
+    pub author: Option<String>,
+    pub body: String,
+    #[sqlx(flatten)]
+    pub task: Task,
+}
+
+// A task linked to another system, as seen by a sync
+#[derive(Debug, sqlx::FromRow)]
+pub struct LinkedTask {
+    #[sqlx(flatten)]
+    pub task: Task,
+    pub external_id: String,
+    // Changed here since the last sync (or never synced)
+    pub changed: bool,
+}
+
+// One entry of the webhook delivery log
+#[derive(Debug, sqlx::FromRow)]
+pub struct WebhookDelivery {
+    pub id: u64,
+    pub url: String,
+    pub event: String,
+    pub task_id: Option<i32>,
+    pub payload: String,
+    // pending, delivered or failed
+    pub status: String,
+    pub attempts: i32,
+    // HTTP status of the last attempt, if there was a response
+    pub response_code: Option<u16>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_attempt_at: Option<NaiveDateTime>,
+}
+
+// The row of daemon_state: the `task daemon` that last claimed the database
+#[derive(Debug, sqlx::FromRow)]
+pub struct DaemonState {
+    pub host: String,
+    pub pid: u32,
+    pub started_at: NaiveDateTime,
+    pub heartbeat_at: NaiveDateTime,
+    pub stopped_at: Option<NaiveDateTime>,
+    // Not stopped, and the heartbeat is recent
+    pub running: bool,
+}
+
+// How one job of the daemon last went
+#[derive(Debug, sqlx::FromRow)]
+pub struct DaemonJob {
+    pub job: String,
+    pub runs: u32,
+    pub failures: u32,
+    pub last_started_at: Option<NaiveDateTime>,
+    pub last_finished_at: Option<NaiveDateTime>,
+    // None if the last run succeeded
+    pub last_error: Option<String>,
+    pub next_run_at: Option<NaiveDateTime>,
+}
+
+// A VTODO resource on a CalDAV server and the task it belongs to
+#[derive(Debug, sqlx::FromRow)]
+pub struct CaldavResource {
+    pub href: String,
+    pub uid: String,
+    // Entity tag of the resource at the last sync
+    pub etag: Option<String>,
+    // None once the task was deleted here
+    pub task_id: Option<i32>,
+    // The task changed here since the last sync
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExternalId {
+    pub source: &'static str,
+    pub id: String,
+}
+
+#[derive(Debug, Clone, sqlx::FromRow, serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
+pub struct ChecklistItem {
+    pub text: String,
+    pub done: bool,
+}
+
+// One page of results plus the cursor to pass back in for the next page (`None` on the last page).
+// Pages are fetched with keyset pagination: instead of an OFFSET, each query continues strictly
+// after the last row of the previous page, so page 500 is as cheap as page 1.
+#[derive(Debug, Clone)]
+pub struct Page<C> {
+    pub tasks: Vec<Task>,
+    pub next: Option<C>,
+}
+
+// One thing done to a task, for `task activity`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ActivityEntry {
+    pub id: i64,
+    pub task_id: i32,
+    // As it was at the time
+    pub description: String,
+    pub action: Action,
+    pub actor: Option<String>,
+    pub occurred_at: NaiveDateTime,
+}
+
+// A change to how many tasks are open, for `task stats burndown`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct OpenChange {
+    pub action: Action,
+    pub occurred_at: NaiveDateTime,
+    // For a deletion, the task's last created, completed or reopened entry before it, which
+    // tells whether an open task went; None if there is none
+    pub before: Option<Action>,
+}
+
+// Time tracked on a task, for `task time`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct TimeEntry {
+    pub task_id: i32,
+    pub description: String,
+    pub project_id: Option<i32>,
+    // None without accounts, or once the account was deleted
+    pub username: Option<String>,
+    pub started_at: NaiveDateTime,
+    // Until now for a timer that is still running
+    pub seconds: i64,
+}
+
+// Open and completed tasks of one project or tag, for `task stats breakdown`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Breakdown {
+    // None for the tasks without a project, or without tags
+    pub name: Option<String>,
+    pub open: i64,
+    pub completed: i64,
+    // Estimates of the open tasks added up
+    pub estimate_minutes: i64,
+    // Open tasks without an estimate
+    pub unestimated: i64,
+}
+
+// When a task was created and, if it is, completed, for how old the open tasks were at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, sqlx::FromRow)]
+pub struct OpenSpan {
+    pub created_at: NaiveDateTime,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+// A user the daemon emails the digest to, with the settings that say when
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct DigestRecipient {
+    pub id: i32,
+    pub username: String,
+    pub email: String,
+    pub digest_time: Option<String>,
+    pub timezone: Option<String>,
+}
+
+// Open tasks due on one day and what they are estimated to take, for `task stats forecast`
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct DueLoad {
+    pub day: NaiveDate,
+    pub tasks: i64,
+    pub estimate_minutes: i64,
+    // Tasks without an estimate
+    pub unestimated: i64,
+}
+
+// A task set to recur, with what the daemon needs to create its next occurrences
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Recurring {
+    pub task_id: i32,
+    pub description: String,
+    // The first occurrence; NULL once the due date was removed, which pauses it
+    pub due_at: Option<NaiveDateTime>,
+    pub every: String,
+    pub since: NaiveDateTime,
+    // The latest occurrence created so far
+    pub last_occurrence: Option<NaiveDateTime>,
+}
+
+// An entry of the escalation log, with the task's description
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Escalation {
+    pub task_id: i32,
+    pub description: String,
+    pub due_at: NaiveDateTime,
+    pub escalated_at: NaiveDateTime,
+    pub days_overdue: i32,
+    pub old_priority: Option<Priority>,
+    pub new_priority: Option<Priority>,
+    // The channel to tell, e.g. "slack", and when that was done
+    pub channel: Option<String>,
+    pub notified_at: Option<NaiveDateTime>,
+}
+
+// A page of activity, newest first; `next` is the id to pass as `before` for the one after
+#[derive(Debug)]
+pub struct ActivityPage {
+    pub entries: Vec<ActivityEntry>,
+    pub next: Option<i64>,
+}
+
+// Conditions for `filter_page`; every field that is set must match
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TaskFilter {
+    pub completed: Option<bool>,
+    // Project and tag by name
+    pub project: Option<String>,
+    pub tag: Option<String>,
+    pub priority: Option<Priority>,
+    pub due_before: Option<NaiveDateTime>,
+    pub due_after: Option<NaiveDateTime>,
+    pub assignee: Option<i32>,
+}
+
+// Fields to change with `update`; `None` leaves a field as it is. The nullable columns take
+// `Some(None)` to clear them.
+#[derive(Debug, Default, Clone)]
+pub struct TaskChanges {
+    pub description: Option<String>,
+    pub completed: Option<bool>,
+    pub due_at: Option<Option<NaiveDateTime>>,
+    pub priority: Option<Option<Priority>>,
+}
+
+impl TaskChanges {
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.completed.is_none() && self.due_at.is_none() && self.priority.is_none()
+    }
+}
+
+// What `update` did to a task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Updated {
+    // It was open and is done now; not for one that was done already
+    pub completed: bool,
+}
+
+// Position in the default "newest first" ordering, (created_at DESC, id DESC)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListCursor {
+    pub created_at: NaiveDateTime,
+    pub id: i32,
+}
+
+// Position in a search result. FULLTEXT results are ordered by (relevance DESC, id DESC);
+// the LIKE fallback has no relevance and uses the list ordering instead.
+#[derive(Debug, Clone, Copy)]
+pub enum SearchCursor {
+    Relevance { score: f64, id: i32 },
+    Recency(ListCursor),
+}
+
+// Search hit as returned by the FULLTEXT query, before the score is dropped
+#[derive(sqlx::FromRow)]
+struct ScoredTask {
+    id: i32,
+    description: String,
+    completed: bool,
+    created_at: NaiveDateTime,
+    created_by: Option<String>,
+    updated_by: Option<String>,
+    updated_at: NaiveDateTime,
+    due_at: Option<NaiveDateTime>,
+    priority: Option<Priority>,
+    project_id: Option<i32>,
+    owner_id: Option<i32>,
+    assignee_id: Option<i32>,
+    workspace_id: Option<i32>,
+    relevance: f64,
+}
+
+// An account, without its password hash
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct User {
+    pub id: i32,
+    pub username: String,
+    pub created_at: NaiveDateTime,
+}
+
+// A pending task and the name of the user it is assigned to
+#[derive(Debug, sqlx::FromRow)]
+pub struct Assignment {
+    #[sqlx(flatten)]
+    pub task: Task,
+    pub assignee: String,
+}
+
+// A task or project shared by its owner with another user
+#[derive(Debug, sqlx::FromRow)]
+pub struct Share {
+    // "task" or "project"
+    pub kind: String,
+    // Task id or project name
+    pub subject: String,
+    pub owner: String,
+    pub user: String,
+    pub can_write: bool,
+}
+
+// A workspace the user is a member of
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Workspace {
+    pub id: i32,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub members: i64,
+    // The user's role in it
+    pub role: Role,
+}
+
+// A member of a workspace
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Member {
+    pub user_id: i32,
+    pub username: String,
+    pub role: Role,
+}
+
+// An account as `task admin users list` shows it
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Account {
+    pub id: i32,
+    pub username: String,
+    pub created_at: NaiveDateTime,
+    pub is_admin: bool,
+    pub disabled_at: Option<NaiveDateTime>,
+    // Tasks they own, in any workspace
+    pub tasks: i64,
+    pub workspaces: i64,
+}
+
+// Counts over the tasks the repository sees, for `task stats`. Weeks start on Monday, by the
+// database server's clock like completed_at.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct CompletionStats {
+    pub pending: i64,
+    pub completed: i64,
+    pub overdue: i64,
+    pub completed_this_week: i64,
+    pub completed_last_week: i64,
+    // From creation to completion, over the completed tasks with a completion time
+    pub average_secs: Option<i64>,
+}
+
+// Counts over the whole database, for `task admin stats`
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct InstanceStats {
+    pub users: i64,
+    pub admins: i64,
+    pub disabled: i64,
+    pub workspaces: i64,
+    pub projects: i64,
+    pub tasks: i64,
+    pub open_tasks: i64,
+    pub overdue_tasks: i64,
+    pub sessions: i64,
+    pub api_tokens: i64,
+    pub invitations: i64,
+}
+
+// What `erase_account` removed
+#[derive(Debug, Clone, Default)]
+pub struct Erased {
+    pub tasks: u64,
+    // Entries of the user's in other people's activity, now without a name
+    pub activity: u64,
+}
+
+// An invitation into a workspace that can still be redeemed, without its code
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Invitation {
+    pub id: i32,
+    pub role: Role,
+    // None if that account was deleted
+    pub created_by: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+// What an invitation code offers, for showing it before it is redeemed
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PendingInvitation {
+    pub workspace: String,
+    pub role: Role,
+    pub expires_at: NaiveDateTime,
+}
+
+// The account an invitation created, and where it joined
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Redeemed {
+    pub user_id: i32,
+    pub workspace_id: i32,
+    pub workspace: String,
+    pub role: Role,
+}
+
+// A login: its user and the workspace it switched to, if any, with the user's role there
+#[derive(Debug, sqlx::FromRow)]
+pub struct Session {
+    #[sqlx(flatten)]
+    pub user: User,
+    pub workspace_id: Option<i32>,
+    pub role: Option<Role>,
+}
+
+// A personal access token of the user, without the token itself
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct ApiToken {
+    pub id: i32,
+    pub name: String,
+    pub scope: Scope,
+    // None for the personal space
+    pub workspace: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+// What a request with an API token works as: like a login, plus the token's scope
+#[derive(Debug, sqlx::FromRow)]
+pub struct TokenLogin {
+    #[sqlx(flatten)]
+    pub session: Session,
+    pub scope: Scope,
+}
+
+// Whose tasks, in which workspace, a repository works on; see `TaskRepository::user`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Access {
+    pub user: Option<i32>,
+    pub workspace: Option<i32>,
+}
+
+// Connection counts of the pool at one moment
+#[derive(Debug, Clone, Copy)]
+pub struct PoolStatus {
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+}
+
+// Name recorded in the audit columns when no user is logged in: the OS account running the CLI
+pub fn os_user() -> String {
+    whoami::username()
+}
+
+#[derive(Debug, Clone)]
+pub struct TaskRepository {
+    pool: MySqlPool,
+    // Recorded as created_by/updated_by on every mutation
+    actor: String,
+    // The logged-in user. Queries on tasks only see the user's own tasks, those assigned to them
+    // and those shared with them, and changes to shared ones need a read-write share; new tasks
+    // belong to the user. None (no accounts yet, or admin commands) sees and changes every task.
+    user: Option<i32>,
+    // The workspace switched to; None is the personal space. Tasks and projects are limited to
+    // it whoever the user is.
+    workspace: Option<i32>,
+    // The user's role in the workspace, which limits what they may change there. None outside a
+    // workspace, where only shares limit them.
+    role: Option<Role>,
+    // Set for requests with a read-only API token: every change is refused
+    read_only: bool,
+    retry: RetryPolicy,
+    // How long an operation may take; None waits for as long as it takes
+    timeout: Option<Duration>,
+    bulk: BulkPolicy,
+    metrics: Metrics,
+    // Recent list and filter results; see cache.rs
+    cache: ListCache,
+}
+
+impl TaskRepository {
+    pub fn new(pool: MySqlPool, actor: String) -> Self {
+        TaskRepository {
+            pool,
+            actor,
+            user: None,
+            workspace: None,
+            role: None,
+            read_only: false,
+            retry: RetryPolicy::default(),
+            timeout: Some(Duration::from_secs(db::DEFAULT_QUERY_TIMEOUT_SECS)),
+            bulk: BulkPolicy::default(),
+            metrics: Metrics::default(),
+            cache: ListCache::default(),
+        }
+    }
+
+    pub fn with_retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_query_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_bulk_policy(mut self, bulk: BulkPolicy) -> Self {
+        self.bulk = bulk;
+        self
+    }
+
+    pub fn with_cache_policy(mut self, policy: CachePolicy) -> Self {
+        self.cache = ListCache::new(policy);
+        self
+    }
+
+    // For changes made on someone else's behalf, e.g. from a chat the bot maps to a name
+    pub fn with_actor(mut self, actor: String) -> Self {
+        self.actor = actor;
+        self
+    }
+
+    pub fn with_user(mut self, user: Option<i32>) -> Self {
+        self.user = user;
+        self
+    }
+
+    pub fn user(&self) -> Option<i32> {
+        self.user
+    }
+
+    pub fn with_workspace(mut self, workspace: Option<(i32, Role)>) -> Self {
+        self.workspace = workspace.map(|(id, _)| id);
+        self.role = workspace.map(|(_, role)| role);
+        self
+    }
+
+    pub fn workspace(&self) -> Option<i32> {
+        self.workspace
+    }
+
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn access(&self) -> Access {
+        Access { user: self.user, workspace: self.workspace }
+    }
+
+    // The same repository working on the tasks of `access`, e.g. to follow up on a change
+    // someone else made through another one
+    pub fn with_access(mut self, access: Access) -> Self {
+        self.user = access.user;
+        self.workspace = access.workspace;
+        self
+    }
+
+    // Every change goes through here first, so a role's limits hold whichever command, bot or
+    // API makes it
+    fn require(&self, permission: Permission) -> Result<(), sqlx::Error> {
+        if self.read_only {
+            return Err(forbidden(Forbidden::ReadOnly));
+        }
+        match self.role {
+            Some(role) if !role.allows(permission) => Err(forbidden(Forbidden::Role(permission))),
+            _ => Ok(()),
+        }
+    }
+
+    // For code that reads whole tables, like the exports, which apply `user` themselves
+    pub fn pool(&self) -> &MySqlPool {
+        &self.pool
+    }
+
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    pub fn cache(&self) -> &ListCache {
+        &self.cache
+    }
+
+    pub fn pool_status(&self) -> PoolStatus {
+        PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max: self.pool.options().get_max_connections(),
+        }
+    }
+
+    // Round trip to the server, so the pool and metrics have something to report
+    pub async fn ping(&self) -> Result<(), sqlx::Error> {
+        self.timed("ping", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            conn.ping().await
+        }))
+        .await
+    }
+
+    // Returns the id of the new task. Not retried on a lost connection: the insert may already
+    // have happened, and repeating it would create a duplicate task. A lock conflict rolls it
+    // back, so that is.
+    pub async fn add(&self, description: &str) -> Result<i32, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("add", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let id = sqlx::query(
+                "INSERT INTO tasks (description, created_by, updated_by, owner_id, workspace_id) \
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(description)
+            .bind(&self.actor)
+            .bind(&self.actor)
+            .bind(self.user)
+            .bind(self.workspace)
+            .execute(&mut *tx)
+            .await?
+            .last_insert_id() as i32;
+            record_activity(&mut tx, Action::Created, &[id], &self.actor, self.user).await?;
+            tx.commit().await?;
+            tracing::debug!(task_id = id, "task added");
+            Ok(id)
+        }))
+        .await
+    }
+
+    #[tracing::instrument(level = "debug", skip_all, fields(task_id = id))]
+    pub async fn get(&self, id: i32) -> Result<Option<Task>, sqlx::Error> {
+        self.timed("get", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Task>(concat!(
+                "SELECT ", task_columns!(), " FROM tasks WHERE id = ? AND ", readable!()
+            ))
+            .bind(id)
+            .bind_access(self.access())
+            .fetch_optional(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Inserts all tasks atomically, BATCH_SIZE rows per statement. Returns the number of rows inserted.
+    pub async fn insert_batch(&self, tasks: &[NewTask]) -> Result<u64, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("insert_batch", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let inserted = insert_tasks(&mut tx, tasks, Some(&self.actor), self.access()).await?;
+            tx.commit().await?;
+            Ok(inserted)
+        }))
+        .await
+    }
+
+    // Inserts tasks with their tags, projects and checklists, all or nothing. Projects and tags
+    // are matched by name and created as needed, projects only if the role allows. Returns the
+    // ids of the new tasks, in order. The tasks go in BATCH_SIZE rows per statement, and what
+    // hangs off them once their ids are known.
+    pub async fn import(&self, bundles: &[TaskBundle]) -> Result<Vec<i32>, sqlx::Error> {
+        self.insert_bundles("import", bundles, true).await
+    }
+
+    // `import` for the sample tasks of `task seed`, which stay out of the activity feed
+    pub async fn seed(&self, bundles: &[TaskBundle]) -> Result<Vec<i32>, sqlx::Error> {
+        self.insert_bundles("seed", bundles, false).await
+    }
+
+    async fn insert_bundles(
+        &self,
+        operation: &'static str,
+        bundles: &[TaskBundle],
+        recorded: bool,
+    ) -> Result<Vec<i32>, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed(operation, db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let mut tasks = Vec::with_capacity(bundles.len());
+            for bundle in bundles {
+                let mut task = bundle.task.clone();
+                if let Some(project) = &bundle.project {
+                    task.project_id = Some(match find_project(&mut tx, project, self.workspace).await? {
+                        Some(id) => id,
+                        None => {
+                            self.require(Permission::ManageProjects)?;
+                            ensure_project(&mut tx, project, self.workspace).await?
+                        }
+                    });
+                }
+                tasks.push(task);
+            }
+
+            let mut ids = Vec::with_capacity(bundles.len());
+            for chunk in tasks.chunks(BATCH_SIZE) {
+                ids.extend(insert_chunk(&mut tx, chunk, Some(&self.actor), self.access()).await?);
+            }
+            for (bundle, &id) in bundles.iter().zip(&ids) {
+                add_tags(&mut tx, id, &bundle.tags).await?;
+                add_checklist(&mut tx, id, &bundle.checklist).await?;
+                for note in &bundle.notes {
+                    add_note(&mut tx, id, note).await?;
+                }
+                if let Some(external) = &bundle.external {
+                    link_external_id(&mut tx, id, external).await?;
+                }
+            }
+            if recorded {
+                record_activity(&mut tx, Action::Created, &ids, &self.actor, self.user).await?;
+            }
+            tx.commit().await?;
+            Ok(ids)
+        }))
+        .await
+    }
+
+    // Whether a task with this description exists, compared like the column's collation does
+    // (case- and accent-insensitive)
+    pub async fn description_exists(&self, description: &str) -> Result<bool, sqlx::Error> {
+        self.timed("description_exists", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar(concat!("SELECT EXISTS (SELECT 1 FROM tasks WHERE description = ? AND ", readable!(), ")"))
+                .bind(description)
+                .bind_access(self.access())
+                .fetch_one(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // Whether a task was already imported from this source under this id
+    pub async fn external_id_exists(&self, external: &ExternalId) -> Result<bool, sqlx::Error> {
+        self.timed("external_id_exists", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM external_ids WHERE source = ? AND external_id = ?)")
+                .bind(external.source)
+                .bind(&external.id)
+                .fetch_one(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // Tasks linked to `source`, with whether each changed locally since it was last synced
+    pub async fn linked_tasks(&self, source: &str) -> Result<Vec<LinkedTask>, sqlx::Error> {
+        self.timed("linked_tasks", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, LinkedTask>(concat!(
+                "SELECT ", task_columns!(), ", external_ids.external_id, \
+                 (external_ids.synced_at IS NULL OR tasks.updated_at > external_ids.synced_at) AS changed \
+                 FROM tasks JOIN external_ids ON external_ids.task_id = tasks.id \
+                 WHERE external_ids.source = ? AND ", owned!(), " ORDER BY tasks.id"
+            ))
+            .bind(source)
+            .bind_owned(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Pending tasks that have no counterpart in `source` yet
+    pub async fn unlinked_pending(&self, source: &str) -> Result<Vec<Task>, sqlx::Error> {
+        self.timed("unlinked_pending", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Task>(concat!(
+                "SELECT ", task_columns!(), " FROM tasks WHERE completed = FALSE AND NOT EXISTS \
+                 (SELECT 1 FROM external_ids WHERE external_ids.task_id = tasks.id AND external_ids.source = ?) \
+                 AND ", owned!(), " ORDER BY id"
+            ))
+            .bind(source)
+            .bind_owned(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Overwrites the fields another system can change
+    pub async fn update_synced_fields(
+        &self,
+        id: i32,
+        description: &str,
+        completed: bool,
+        due_at: Option<NaiveDateTime>,
+        priority: Option<Priority>,
+    ) -> Result<(), sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("update_synced_fields", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            // A task that was already done keeps its completion time
+            sqlx::query(concat!(
+                "UPDATE tasks SET description = ?, completed = ?, \
+                 completed_at = IF(?, COALESCE(completed_at, NOW()), NULL), \
+                 due_at = ?, priority = ?, updated_by = ? WHERE id = ? AND ", writable!()
+            ))
+            .bind(description)
+            .bind(completed)
+            .bind(completed)
+            .bind(due_at)
+            .bind(priority)
+            .bind(&self.actor)
+            .bind(id)
+            .bind_access(self.access())
+            .execute(&mut *conn)
+            .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Replaces the task's tags, creating tags that don't exist yet. Returns false if there is no
+    // such task the user may change.
+    pub async fn set_tags(&self, id: i32, tags: &[String]) -> Result<bool, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("set_tags", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            if lock_existing(&mut tx, &[id], self.access()).await?.is_empty() {
+                return Ok(false);
+            }
+            sqlx::query("DELETE FROM task_tags WHERE task_id = ?").bind(id).execute(&mut *tx).await?;
+            add_tags(&mut tx, id, tags).await?;
+            tx.commit().await?;
+            Ok(true)
+        }))
+        .await
+    }
+
+    // Returns false if there is no such task the user may change
+    pub async fn link_external(&self, id: i32, external: &ExternalId) -> Result<bool, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("link_external", async {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            if lock_existing(&mut tx, &[id], self.access()).await?.is_empty() {
+                return Ok(false);
+            }
+            link_external_id(&mut tx, id, external).await?;
+            tx.commit().await?;
+            Ok(true)
+        })
+        .await
+    }
+
+    // Removes the task's links to `source`, or only the one to `external_id`. None are removed
+    // from a task the user may not change.
+    pub async fn unlink_external(&self, id: i32, source: &str, external_id: Option<&str>) -> Result<u64, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("unlink_external", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            if lock_existing(&mut tx, &[id], self.access()).await?.is_empty() {
+                return Ok(0);
+            }
+            let removed = sqlx::query(
+                "DELETE FROM external_ids WHERE task_id = ? AND source = ? AND (? IS NULL OR external_id = ?)",
+            )
+            .bind(id)
+            .bind(source)
+            .bind(external_id)
+            .bind(external_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            tx.commit().await?;
+            Ok(removed)
+        }))
+        .await
+    }
+
+    // Records that the task and its counterpart in `source` now agree
+    pub async fn mark_synced(&self, external: &ExternalId) -> Result<(), sqlx::Error> {
+        self.timed("mark_synced", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("UPDATE external_ids SET synced_at = NOW() WHERE source = ? AND external_id = ?")
+                .bind(external.source)
+                .bind(&external.id)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Resources tracked for the CalDAV collection at `path`, including those whose task was deleted here
+    pub async fn caldav_resources(&self, path: &str) -> Result<Vec<CaldavResource>, sqlx::Error> {
+        let pattern = format!("{}%", escape_like(path));
+        let pattern = &pattern;
+        self.timed("caldav_resources", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            // Resources of other users' tasks are left out rather than reported as deleted
+            sqlx::query_as::<_, CaldavResource>(concat!(
+                "SELECT caldav_resources.href, caldav_resources.uid, caldav_resources.etag, caldav_resources.task_id, \
+                 (tasks.id IS NOT NULL AND (caldav_resources.synced_at IS NULL \
+                  OR tasks.updated_at > caldav_resources.synced_at)) AS changed \
+                 FROM caldav_resources LEFT JOIN tasks ON tasks.id = caldav_resources.task_id \
+                 WHERE caldav_resources.href LIKE ? AND (tasks.id IS NULL OR ", owned!(), ") \
+                 ORDER BY caldav_resources.href"
+            ))
+            .bind(pattern)
+            .bind_owned(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Records a resource as in sync: the task and the server agree as of `etag`
+    pub async fn save_caldav_resource(
+        &self,
+        href: &str,
+        uid: &str,
+        etag: Option<&str>,
+        task_id: i32,
+    ) -> Result<(), sqlx::Error> {
+        self.timed("save_caldav_resource", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query(
+                "INSERT INTO caldav_resources (href, uid, task_id, etag, synced_at) VALUES (?, ?, ?, ?, NOW()) \
+                 ON DUPLICATE KEY UPDATE uid = VALUES(uid), task_id = VALUES(task_id), etag = VALUES(etag), synced_at = NOW()",
+            )
+            .bind(href)
+            .bind(uid)
+            .bind(task_id)
+            .bind(etag)
+            .execute(&mut *conn)
+            .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_caldav_resource(&self, href: &str) -> Result<(), sqlx::Error> {
+        self.timed("delete_caldav_resource", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("DELETE FROM caldav_resources WHERE href = ?").bind(href).execute(&mut *conn).await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Pending tasks that are not on a CalDAV server yet
+    pub async fn caldav_unlinked_pending(&self) -> Result<Vec<Task>, sqlx::Error> {
+        self.timed("caldav_unlinked_pending", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Task>(concat!(
+                "SELECT ", task_columns!(), " FROM tasks WHERE completed = FALSE AND NOT EXISTS \
+                 (SELECT 1 FROM caldav_resources WHERE caldav_resources.task_id = tasks.id) \
+                 AND ", owned!(), " ORDER BY id"
+            ))
+            .bind_owned(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    pub async fn caldav_ctag(&self, url: &str) -> Result<Option<String>, sqlx::Error> {
+        self.timed("caldav_ctag", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT ctag FROM caldav_collections WHERE url = ?")
+                .bind(url)
+                .fetch_optional(&mut *conn)
+                .await
+                .map(Option::flatten)
+        }))
+        .await
+    }
+
+    pub async fn save_caldav_ctag(&self, url: &str, ctag: Option<&str>) -> Result<(), sqlx::Error> {
+        self.timed("save_caldav_ctag", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("INSERT INTO caldav_collections (url, ctag) VALUES (?, ?) ON DUPLICATE KEY UPDATE ctag = VALUES(ctag)")
+                .bind(url)
+                .bind(ctag)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    pub async fn notion_last_edited(&self, database_id: &str) -> Result<Option<String>, sqlx::Error> {
+        self.timed("notion_last_edited", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT last_edited_time FROM notion_databases WHERE database_id = ?")
+                .bind(database_id)
+                .fetch_optional(&mut *conn)
+                .await
+                .map(Option::flatten)
+        }))
+        .await
+    }
+
+    pub async fn save_notion_last_edited(&self, database_id: &str, last_edited: &str) -> Result<(), sqlx::Error> {
+        self.timed("save_notion_last_edited", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query(
+                "INSERT INTO notion_databases (database_id, last_edited_time) VALUES (?, ?) \
+                 ON DUPLICATE KEY UPDATE last_edited_time = VALUES(last_edited_time)",
+            )
+            .bind(database_id)
+            .bind(last_edited)
+            .execute(&mut *conn)
+            .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Pending tasks due before `until` (overdue ones included), soonest first
+    pub async fn pending_due_before(&self, until: NaiveDateTime) -> Result<Vec<Task>, sqlx::Error> {
+        self.timed("pending_due_before", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Task>(concat!(
+                "SELECT ", task_columns!(), " FROM tasks ",
+                "WHERE completed = FALSE AND due_at IS NOT NULL AND due_at < ? AND ", owned!(), " ORDER BY due_at, id"
+            ))
+            .bind(until)
+            .bind_owned(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Records that a notification is being sent. Returns false if it was sent before, so two
+    // runs at the same time can't both send it.
+    pub async fn claim_notification(&self, channel: &str, kind: &str, subject: &str) -> Result<bool, sqlx::Error> {
+        let result = self
+            .timed("claim_notification", db::retry_on_disconnect(|| async move {
+                let mut conn = self.acquire().await?;
+                sqlx::query("INSERT IGNORE INTO notifications_sent (channel, kind, subject) VALUES (?, ?, ?)")
+                    .bind(channel)
+                    .bind(kind)
+                    .bind(subject)
+                    .execute(&mut *conn)
+                    .await
+            }))
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn notification_sent(&self, channel: &str, kind: &str, subject: &str) -> Result<bool, sqlx::Error> {
+        self.timed("notification_sent", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM notifications_sent WHERE channel = ? AND kind = ? AND subject = ?)")
+                .bind(channel)
+                .bind(kind)
+                .bind(subject)
+                .fetch_one(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // Undoes a claim after sending failed, so the next run tries again
+    pub async fn release_notification(&self, channel: &str, kind: &str, subject: &str) -> Result<(), sqlx::Error> {
+        self.timed("release_notification", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("DELETE FROM notifications_sent WHERE channel = ? AND kind = ? AND subject = ?")
+                .bind(channel)
+                .bind(kind)
+                .bind(subject)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Notifications sent on a channel since `since`, for rate limits
+    pub async fn notifications_sent_since(&self, channel: &str, since: NaiveDateTime) -> Result<i64, sqlx::Error> {
+        self.timed("notifications_sent_since", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT COUNT(*) FROM notifications_sent WHERE channel = ? AND sent_at >= ?")
+                .bind(channel)
+                .bind(since)
+                .fetch_one(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // Ids of the tasks opted in to SMS reminders
+    pub async fn sms_alert_tasks(&self) -> Result<Vec<i32>, sqlx::Error> {
+        self.timed("sms_alert_tasks", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar(concat!(
+                "SELECT task_sms_alerts.task_id FROM task_sms_alerts JOIN tasks ON tasks.id = task_sms_alerts.task_id \
+                 WHERE ", owned!(), " ORDER BY task_sms_alerts.task_id"
+            ))
+            .bind_owned(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Opts the task in to or out of SMS reminders. Returns false if there is no such task the
+    // user may change.
+    pub async fn set_sms_alert(&self, id: i32, enabled: bool) -> Result<bool, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        let sql = if enabled {
+            "INSERT IGNORE INTO task_sms_alerts (task_id) VALUES (?)"
+        } else {
+            "DELETE FROM task_sms_alerts WHERE task_id = ?"
+        };
+        self.timed("set_sms_alert", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            if lock_existing(&mut tx, &[id], self.access()).await?.is_empty() {
+                return Ok(false);
+            }
+            sqlx::query(sql).bind(id).execute(&mut *tx).await?;
+            tx.commit().await?;
+            Ok(true)
+        }))
+        .await
+    }
+
+    // Starts a log entry for a webhook request and returns its id
+    pub async fn log_webhook_delivery(
+        &self,
+        url: &str,
+        event: &str,
+        task_id: i32,
+        payload: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let result = self
+            .timed("log_webhook_delivery", async {
+                let mut conn = self.acquire().await?;
+                sqlx::query("INSERT INTO webhook_deliveries (url, event, task_id, payload) VALUES (?, ?, ?, ?)")
+                    .bind(url)
+                    .bind(event)
+                    .bind(task_id)
+                    .bind(payload)
+                    .execute(&mut *conn)
+                    .await
+            })
+            .await?;
+        Ok(result.last_insert_id())
+    }
+
+    // Records one attempt at sending a logged webhook request. `status` is "delivered" on
+    // success, otherwise "pending" while retries remain and "failed" after the last one.
+    pub async fn record_webhook_attempt(
+        &self,
+        id: u64,
+        status: &str,
+        response_code: Option<u16>,
+        error: Option<&str>,
+    ) -> Result<(), sqlx::Error> {
+        self.timed("record_webhook_attempt", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query(
+                "UPDATE webhook_deliveries SET status = ?, attempts = attempts + 1, response_code = ?, \
+                 error = LEFT(?, 1024), last_attempt_at = NOW() WHERE id = ?",
+            )
+            .bind(status)
+            .bind(response_code)
+            .bind(error)
+            .bind(id)
+            .execute(&mut *conn)
+            .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // The most recent webhook requests, newest first. Their payloads are about everyone's tasks,
+    // so only instance admins see them.
+    pub async fn webhook_deliveries(&self, limit: u32) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        self.timed("webhook_deliveries", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            require_admin(&mut conn, self.user).await?;
+            sqlx::query_as::<_, WebhookDelivery>(concat!(
+                "SELECT ", webhook_delivery_columns!(), " FROM webhook_deliveries ORDER BY id DESC LIMIT ?"
+            ))
+            .bind(limit)
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Webhook requests that were tried, not taken and not given up on, oldest first. A "failed"
+    // one has had all its attempts.
+    pub async fn undelivered_webhook_deliveries(&self) -> Result<Vec<WebhookDelivery>, sqlx::Error> {
+        self.timed("undelivered_webhook_deliveries", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, WebhookDelivery>(concat!(
+                "SELECT ", webhook_delivery_columns!(), " FROM webhook_deliveries \
+                 WHERE status = 'pending' AND attempts > 0 ORDER BY id"
+            ))
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Makes this process the daemon of the database, unless another one holds it and its
+    // heartbeat is younger than `stale_secs`. Returns whether it did.
+    pub async fn claim_daemon(&self, host: &str, pid: u32, stale_secs: u32) -> Result<bool, sqlx::Error> {
+        self.timed("claim_daemon", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let live: Option<bool> = sqlx::query_scalar(
+                "SELECT stopped_at IS NULL AND heartbeat_at >= NOW() - INTERVAL ? SECOND FROM daemon_state \
+                 WHERE id = 1 FOR UPDATE",
+            )
+            .bind(stale_secs)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if live == Some(true) {
+                return Ok(false);
+            }
+            sqlx::query(
+                "INSERT INTO daemon_state (id, host, pid, started_at, heartbeat_at) VALUES (1, ?, ?, NOW(), NOW()) \
+                 ON DUPLICATE KEY UPDATE host = VALUES(host), pid = VALUES(pid), started_at = NOW(), \
+                 heartbeat_at = NOW(), stopped_at = NULL",
+            )
+            .bind(host)
+            .bind(pid)
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            Ok(true)
+        }))
+        .await
+    }
+
+    // Returns false once another daemon has taken over, e.g. after this one hung for too long
+    pub async fn daemon_heartbeat(&self, host: &str, pid: u32) -> Result<bool, sqlx::Error> {
+        let result = self
+            .timed("daemon_heartbeat", db::retry_on_disconnect(|| async move {
+                let mut conn = self.acquire().await?;
+                sqlx::query(
+                    "UPDATE daemon_state SET heartbeat_at = NOW() \
+                     WHERE id = 1 AND host = ? AND pid = ? AND stopped_at IS NULL",
+                )
+                .bind(host)
+                .bind(pid)
+                .execute(&mut *conn)
+                .await
+            }))
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn release_daemon(&self, host: &str, pid: u32) -> Result<(), sqlx::Error> {
+        self.timed("release_daemon", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("UPDATE daemon_state SET stopped_at = NOW() WHERE id = 1 AND host = ? AND pid = ?")
+                .bind(host)
+                .bind(pid)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // The daemon that last ran, None if there never was one
+    pub async fn daemon_state(&self, stale_secs: u32) -> Result<Option<DaemonState>, sqlx::Error> {
+        self.timed("daemon_state", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, DaemonState>(
+                "SELECT host, pid, started_at, heartbeat_at, stopped_at, \
+                 stopped_at IS NULL AND heartbeat_at >= NOW() - INTERVAL ? SECOND AS running \
+                 FROM daemon_state WHERE id = 1",
+            )
+            .bind(stale_secs)
+            .fetch_optional(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Counts a run of a daemon job and when it is due next; `error` is None if it succeeded
+    pub async fn record_daemon_job(
+        &self,
+        job: &str,
+        started_at: NaiveDateTime,
+        finished_at: NaiveDateTime,
+        error: Option<&str>,
+        next_run_at: NaiveDateTime,
+    ) -> Result<(), sqlx::Error> {
+        self.timed("record_daemon_job", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query(
+                "INSERT INTO daemon_jobs (job, runs, failures, last_started_at, last_finished_at, last_error, \
+                 next_run_at) VALUES (?, 1, ?, ?, ?, ?, ?) \
+                 ON DUPLICATE KEY UPDATE runs = runs + 1, failures = failures + VALUES(failures), \
+                 last_started_at = VALUES(last_started_at), last_finished_at = VALUES(last_finished_at), \
+                 last_error = VALUES(last_error), next_run_at = VALUES(next_run_at)",
+            )
+            .bind(job)
+            .bind(u32::from(error.is_some()))
+            .bind(started_at)
+            .bind(finished_at)
+            .bind(error)
+            .bind(next_run_at)
+            .execute(&mut *conn)
+            .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Every job a daemon ever ran, by name
+    pub async fn daemon_jobs(&self) -> Result<Vec<DaemonJob>, sqlx::Error> {
+        self.timed("daemon_jobs", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, DaemonJob>(
+                "SELECT job, runs, failures, last_started_at, last_finished_at, last_error, next_run_at \
+                 FROM daemon_jobs ORDER BY job",
+            )
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Notes of a task, oldest first
+    pub async fn notes(&self, id: i32) -> Result<Vec<Note>, sqlx::Error> {
+        self.timed("notes", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Note>(concat!(
+                "SELECT task_notes.created_at, task_notes.body, task_notes.created_by FROM task_notes \
+                 JOIN tasks ON tasks.id = task_notes.task_id WHERE task_notes.task_id = ? AND ", readable!(), " \
+                 ORDER BY task_notes.created_at, task_notes.id"
+            ))
+            .bind(id)
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // Adds a note by the actor. Of the users `mentions` names, those who can see the task are
+    // recorded for notifying; unknown names are ignored, as "@" has other uses. None if the task
+    // doesn't exist or the user may not change it.
+    #[tracing::instrument(level = "debug", skip_all, fields(task_id = id))]
+    pub async fn add_comment(
+        &self,
+        id: i32,
+        body: &str,
+        mentions: &[String],
+    ) -> Result<Option<Mentioned>, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("add_comment", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            if lock_existing(&mut tx, &[id], self.access()).await?.is_empty() {
+                return Ok(None);
+            }
+            let note_id = sqlx::query("INSERT INTO task_notes (task_id, body, created_by) VALUES (?, ?, ?)")
+                .bind(id)
+                .bind(body)
+                .bind(&self.actor)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_id();
+
+            let mut mentioned = Mentioned::default();
+            for name in mentions {
+                let user: Option<(i32, String)> =
+                    sqlx::query_as("SELECT id, username FROM users WHERE username = ? AND disabled_at IS NULL")
+                        .bind(name)
+                        .fetch_optional(&mut *tx)
+                        .await?;
+                let Some((user_id, username)) = user.filter(|(user_id, _)| Some(*user_id) != self.user) else {
+                    continue;
+                };
+                // As the mentioned user would see it
+                let access = Access { user: Some(user_id), workspace: self.workspace };
+                let readable: bool = sqlx::query_scalar(concat!(
+                    "SELECT EXISTS (SELECT 1 FROM tasks WHERE id = ? AND ", readable!(), ")"
+                ))
+                .bind(id)
+                .bind_access(access)
+                .fetch_one(&mut *tx)
+                .await?;
+                if !readable {
+                    mentioned.unseen.push(username);
+                    continue;
+                }
+                sqlx::query("INSERT IGNORE INTO task_mentions (note_id, user_id) VALUES (?, ?)")
+                    .bind(note_id)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await?;
+                mentioned.notified.push(username);
+            }
+            tx.commit().await?;
+            Ok(Some(mentioned))
+        }))
+        .await
+    }
+
+    // Mentions of the user in the last `days` days, oldest first; of every user without one
+    pub async fn mentions(&self, days: u32) -> Result<Vec<Mention>, sqlx::Error> {
+        self.timed("mentions", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Mention>(concat!(
+                "SELECT mentions.note_id, mentions.user_id, mentions.username, mentions.author, mentions.body, ",
+                task_columns!(), " FROM tasks JOIN (SELECT task_mentions.note_id, task_mentions.user_id, \
+                 task_mentions.created_at AS mentioned_at, users.username, task_notes.task_id, \
+                 task_notes.created_by AS author, task_notes.body FROM task_mentions \
+                 JOIN users ON users.id = task_mentions.user_id \
+                 JOIN task_notes ON task_notes.id = task_mentions.note_id \
+                 WHERE (? IS NULL OR task_mentions.user_id = ?) \
+                 AND task_mentions.created_at >= NOW() - INTERVAL ? DAY) \
+                 mentions ON mentions.task_id = tasks.id ORDER BY mentions.mentioned_at, mentions.note_id"
+            ))
+            .bind(self.user)
+            .bind(self.user)
+            .bind(days)
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // (source, external id) of every system the task is linked to
+    pub async fn links(&self, id: i32) -> Result<Vec<(String, String)>, sqlx::Error> {
+        self.timed("links", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as(concat!(
+                "SELECT external_ids.source, external_ids.external_id FROM external_ids \
+                 JOIN tasks ON tasks.id = external_ids.task_id WHERE external_ids.task_id = ? AND ", readable!(), " \
+                 ORDER BY external_ids.source"
+            ))
+            .bind(id)
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // Tag names of a task, alphabetically
+    pub async fn tags(&self, id: i32) -> Result<Vec<String>, sqlx::Error> {
+        self.timed("tags", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar(concat!(
+                "SELECT tags.name FROM tags JOIN task_tags ON task_tags.tag_id = tags.id \
+                 JOIN tasks ON tasks.id = task_tags.task_id WHERE task_tags.task_id = ? AND ", readable!(), " \
+                 ORDER BY tags.name"
+            ))
+            .bind(id)
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    pub async fn project_name(&self, project_id: i32) -> Result<Option<String>, sqlx::Error> {
+        self.timed("project_name", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT name FROM projects WHERE id = ?")
+                .bind(project_id)
+                .fetch_optional(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    pub async fn project_id(&self, name: &str) -> Result<Option<i32>, sqlx::Error> {
+        self.timed("project_id", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            find_project(&mut conn, name, self.workspace).await
+        }))
+        .await
+    }
+
+    // Checklist of a task, in order
+    pub async fn checklist(&self, id: i32) -> Result<Vec<ChecklistItem>, sqlx::Error> {
+        self.timed("checklist", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, ChecklistItem>(concat!(
+                "SELECT checklist_items.text, checklist_items.done FROM checklist_items \
+                 JOIN tasks ON tasks.id = checklist_items.task_id WHERE checklist_items.task_id = ? AND ", readable!(), " \
+                 ORDER BY checklist_items.position, checklist_items.id"
+            ))
+            .bind(id)
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Returns None if no task has this id. Completing a completed task, or again after a commit
+    // whose answer was lost, succeeds without changing it; `completed` says whether this did.
+    #[tracing::instrument(level = "debug", skip_all, fields(task_id = id))]
+    pub async fn complete(&self, id: i32) -> Result<Option<Updated>, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("complete", db::retry_on_disconnect(|| db::retry_lock_conflicts(&self.retry, move || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            if lock_existing(&mut tx, &[id], self.access()).await?.is_empty() {
+                return Ok(None);
+            }
+            let completed = sqlx::query(
+                "UPDATE tasks SET completed = TRUE, completed_at = COALESCE(completed_at, NOW()), updated_by = ? \
+                 WHERE id = ? AND NOT completed",
+            )
+            .bind(&self.actor)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected()
+                > 0;
+            if completed {
+                record_activity(&mut tx, Action::Completed, &[id], &self.actor, self.user).await?;
+            }
+            tx.commit().await?;
+            Ok(Some(Updated { completed }))
+        })))
+        .await
+    }
+
+    // Returns false if no task has this id
+    #[tracing::instrument(level = "debug", skip_all, fields(task_id = id))]
+    pub async fn delete(&self, id: i32) -> Result<bool, sqlx::Error> {
+        self.require(Permission::DeleteTasks)?;
+        self.timed("delete", db::retry_on_disconnect(|| db::retry_lock_conflicts(&self.retry, move || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            // Recorded first, while the description is still there
+            if lock_existing(&mut tx, &[id], self.access()).await?.is_empty() {
+                return Ok(false);
+            }
+            record_activity(&mut tx, Action::Deleted, &[id], &self.actor, self.user).await?;
+            sqlx::query("DELETE FROM tasks WHERE id = ?").bind(id).execute(&mut *tx).await?;
+            tx.commit().await?;
+            Ok(true)
+        })))
+        .await
+    }
+
+    // Every task, newest first, as a row-by-row stream. Rows are decoded only as the consumer
+    // polls, so memory use is constant and a slow consumer slows the query down instead of
+    // buffering the whole result. Not timed, as its duration depends on the consumer.
+    pub fn stream_all(&self) -> BoxStream<'_, Result<Task, sqlx::Error>> {
+        sqlx::query_as::<_, Task>(concat!(
+            "SELECT ", task_columns!(), " FROM tasks WHERE ", readable!(), " \
+             ORDER BY created_at DESC, id DESC"
+        ))
+        .bind_access(self.access())
+        .fetch(&self.pool)
+    }
+
+    // All tasks, newest first
+    pub async fn list_page(
+        &self,
+        after: Option<ListCursor>,
+        limit: u32,
+    ) -> Result<Page<ListCursor>, sqlx::Error> {
+        let key = ListKey::new(self.access(), None, after, limit);
+        let miss = match self.cache.get(&key) {
+            Ok(page) => return Ok(page),
+            Err(miss) => miss,
+        };
+        // One extra row tells us whether another page follows
+        let fetch = i64::from(limit) + 1;
+
+        let tasks = match after {
+            None => {
+                self.timed("list_page", db::retry_on_disconnect(|| async move {
+                    let mut conn = self.acquire().await?;
+                    sqlx::query_as::<_, Task>(concat!(
+                        "SELECT ", task_columns!(), " FROM tasks WHERE ", readable!(), " \
+                         ORDER BY created_at DESC, id DESC LIMIT ?"
+                    ))
+                    .bind_access(self.access())
+                    .bind(fetch)
+                    .fetch_all(&mut *conn)
+                    .await
+                }))
+                .await?
+            }
+            Some(cursor) => {
+                self.timed("list_page", db::retry_on_disconnect(|| async move {
+                    let mut conn = self.acquire().await?;
+                    sqlx::query_as::<_, Task>(concat!(
+                        "SELECT ", task_columns!(), " FROM tasks \
+                         WHERE (created_at < ? OR (created_at = ? AND id < ?)) AND ", readable!(), " \
+                         ORDER BY created_at DESC, id DESC LIMIT ?"
+                    ))
+                    .bind(cursor.created_at)
+                    .bind(cursor.created_at)
+                    .bind(cursor.id)
+                    .bind_access(self.access())
+                    .bind(fetch)
+                    .fetch_all(&mut *conn)
+                    .await
+                }))
+                .await?
+            }
+        };
+
+        let page = recency_page(tasks, limit);
+        self.cache.put(miss, key, &page);
+        Ok(page)
+    }
+
+    // Tasks matching `filter`, newest first
+    pub async fn filter_page(
+        &self,
+        filter: &TaskFilter,
+        after: Option<ListCursor>,
+        limit: u32,
+    ) -> Result<Page<ListCursor>, sqlx::Error> {
+        let key = ListKey::new(self.access(), Some(filter), after, limit);
+        let miss = match self.cache.get(&key) {
+            Ok(page) => return Ok(page),
+            Err(miss) => miss,
+        };
+        let fetch = i64::from(limit) + 1;
+
+        let tasks = self
+            .timed("filter_page", db::retry_on_disconnect(|| async move {
+                let mut conn = self.acquire().await?;
+                let mut query = QueryBuilder::<MySql>::new(concat!("SELECT ", task_columns!(), " FROM tasks WHERE TRUE"));
+                self.push_readable(&mut query);
+                if let Some(completed) = filter.completed {
+                    query.push(" AND completed = ").push_bind(completed);
+                }
+                if let Some(project) = &filter.project {
+                    query.push(" AND project_id = (SELECT id FROM projects WHERE name = ").push_bind(project);
+                    query.push(" AND workspace_id <=> ").push_bind(self.workspace).push(")");
+                }
+                if let Some(tag) = &filter.tag {
+                    query
+                        .push(
+                            " AND id IN (SELECT task_tags.task_id FROM task_tags \
+                             JOIN tags ON tags.id = task_tags.tag_id WHERE tags.name = ",
+                        )
+                        .push_bind(tag)
+                        .push(")");
+                }
+                if let Some(priority) = filter.priority {
+                    query.push(" AND priority = ").push_bind(priority);
+                }
+                if let Some(due_before) = filter.due_before {
+                    query.push(" AND due_at < ").push_bind(due_before);
+                }
+                if let Some(due_after) = filter.due_after {
+                    query.push(" AND due_at >= ").push_bind(due_after);
+                }
+                if let Some(assignee) = filter.assignee {
+                    query.push(" AND assignee_id = ").push_bind(assignee);
+                }
+                if let Some(cursor) = after {
+                    query
+                        .push(" AND (created_at < ")
+                        .push_bind(cursor.created_at)
+                        .push(" OR (created_at = ")
+                        .push_bind(cursor.created_at)
+                        .push(" AND id < ")
+                        .push_bind(cursor.id)
+                        .push("))");
+                }
+                query.push(" ORDER BY created_at DESC, id DESC LIMIT ").push_bind(fetch);
+
+                query.build_query_as::<Task>().fetch_all(&mut *conn).await
+            }))
+            .await?;
+
+        let page = recency_page(tasks, limit);
+        self.cache.put(miss, key, &page);
+        Ok(page)
+    }
+
+    // Applies the fields set in `changes`. Returns None if no task has this id.
+    #[tracing::instrument(level = "debug", skip_all, fields(task_id = id))]
+    pub async fn update(&self, id: i32, changes: &TaskChanges) -> Result<Option<Updated>, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        if changes.is_empty() {
+            return self.get(id).await.map(|task| task.map(|_| Updated { completed: false }));
+        }
+
+        let edited = changes.description.is_some() || changes.due_at.is_some() || changes.priority.is_some();
+        self.timed("update", db::retry_on_disconnect(|| db::retry_lock_conflicts(&self.retry, move || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            if lock_existing(&mut tx, &[id], self.access()).await?.is_empty() {
+                return Ok(None);
+            }
+            let was_completed: bool =
+                sqlx::query_scalar("SELECT completed FROM tasks WHERE id = ?").bind(id).fetch_one(&mut *tx).await?;
+            let mut query = QueryBuilder::<MySql>::new("UPDATE tasks SET updated_by = ");
+            query.push_bind(&self.actor);
+            if let Some(description) = &changes.description {
+                query.push(", description = ").push_bind(description);
+            }
+            if let Some(completed) = changes.completed {
+                query.push(", completed = ").push_bind(completed);
+                // A task that was already done keeps its completion time
+                query.push(", completed_at = IF(").push_bind(completed);
+                query.push(", COALESCE(completed_at, NOW()), NULL)");
+            }
+            if let Some(due_at) = changes.due_at {
+                query.push(", due_at = ").push_bind(due_at);
+            }
+            if let Some(priority) = changes.priority {
+                query.push(", priority = ").push_bind(priority);
+            }
+            query.push(" WHERE id = ").push_bind(id);
+            query.build().execute(&mut *tx).await?;
+            // Completing a done task or reopening an open one isn't news
+            let action = match changes.completed {
+                Some(true) if !was_completed => Some(Action::Completed),
+                Some(false) if was_completed => Some(Action::Reopened),
+                _ => edited.then_some(Action::Updated),
+            };
+            if let Some(action) = action {
+                record_activity(&mut tx, action, &[id], &self.actor, self.user).await?;
+            }
+            tx.commit().await?;
+            Ok(Some(Updated { completed: action == Some(Action::Completed) }))
+        })))
+        .await
+    }
+
+    // Marks all the given tasks completed. Returns the ids of those that were open, leaving out
+    // those already done and those that don't exist.
+    pub async fn complete_many(&self, ids: &[i32]) -> Result<Vec<i32>, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.timed("complete_many", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let existing = lock_existing(&mut tx, ids, self.access()).await?;
+            let mut open = Vec::new();
+            if !existing.is_empty() {
+                let mut query = QueryBuilder::<MySql>::new("SELECT id FROM tasks WHERE NOT completed AND id IN (");
+                push_ids(&mut query, &existing);
+                open = query.build_query_scalar().fetch_all(&mut *tx).await?;
+            }
+            if !open.is_empty() {
+                let mut query = QueryBuilder::<MySql>::new(
+                    "UPDATE tasks SET completed = TRUE, completed_at = COALESCE(completed_at, NOW()), updated_by = ",
+                );
+                query.push_bind(&self.actor).push(" WHERE id IN (");
+                push_ids(&mut query, &open);
+                query.build().execute(&mut *tx).await?;
+                record_activity(&mut tx, Action::Completed, &open, &self.actor, self.user).await?;
+            }
+            tx.commit().await?;
+            Ok(open)
+        }))
+        .await
+    }
+
+    // Deletes all the given tasks. Returns the ids of those there were.
+    pub async fn delete_many(&self, ids: &[i32]) -> Result<Vec<i32>, sqlx::Error> {
+        self.require(Permission::DeleteTasks)?;
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.timed("delete_many", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let existing = lock_existing(&mut tx, ids, self.access()).await?;
+            if !existing.is_empty() {
+                record_activity(&mut tx, Action::Deleted, &existing, &self.actor, self.user).await?;
+                let mut query = QueryBuilder::<MySql>::new("DELETE FROM tasks WHERE id IN (");
+                push_ids(&mut query, &existing);
+                query.build().execute(&mut *tx).await?;
+            }
+            tx.commit().await?;
+            Ok(existing)
+        }))
+        .await
+    }
+
+    // `complete_many`, `delete_many` and `import` for hundreds of tasks: chunks of [bulk]
+    // chunk_size, up to [bulk] concurrency of them side by side; see bulk.rs
+    pub async fn complete_in_chunks(&self, ids: &[i32]) -> BulkReport<i32> {
+        bulk::in_chunks(ids, self.bulk.chunk_size, self.bulk_concurrency(), |chunk| self.complete_many(chunk)).await
+    }
+
+    pub async fn delete_in_chunks(&self, ids: &[i32]) -> BulkReport<i32> {
+        bulk::in_chunks(ids, self.bulk.chunk_size, self.bulk_concurrency(), |chunk| self.delete_many(chunk)).await
+    }
+
+    pub async fn import_in_chunks(&self, bundles: &[TaskBundle]) -> BulkReport<i32> {
+        bulk::in_chunks(bundles, self.bulk.chunk_size, self.bulk_concurrency(), |chunk| self.import(chunk)).await
+    }
+
+    // Each chunk holds a connection while it runs, so more than the pool has would only wait
+    fn bulk_concurrency(&self) -> usize {
+        let connections = self.pool.options().get_max_connections() as usize;
+        self.bulk.concurrency.clamp(1, connections.max(1))
+    }
+
+    // Searches task descriptions, best matches first. Uses the FULLTEXT index when the backend
+    // has one and falls back to a plain LIKE scan otherwise.
+    pub async fn search_page(
+        &self,
+        query: &str,
+        after: Option<SearchCursor>,
+        limit: u32,
+    ) -> Result<Page<SearchCursor>, sqlx::Error> {
+        match after {
+            Some(SearchCursor::Recency(cursor)) => self.like_search_page(query, Some(cursor), limit).await,
+            Some(SearchCursor::Relevance { score, id }) => {
+                self.fulltext_search_page(query, Some((score, id)), limit).await
+            }
+            None => match self.fulltext_search_page(query, None, limit).await {
+                Err(sqlx::Error::Database(e))
+                    if e.try_downcast_ref::<MySqlDatabaseError>()
+                        .is_some_and(|e| NO_FULLTEXT_ERRORS.contains(&e.number())) =>
+                {
+                    self.like_search_page(query, None, limit).await
+                }
+                result => result,
+            },
+        }
+    }
+
+    async fn fulltext_search_page(
+        &self,
+        query: &str,
+        after: Option<(f64, i32)>,
+        limit: u32,
+    ) -> Result<Page<SearchCursor>, sqlx::Error> {
+        let fetch = i64::from(limit) + 1;
+
+        let mut hits = match after {
+            None => {
+                self.timed("fulltext_search", db::retry_on_disconnect(|| async move {
+                    let mut conn = self.acquire().await?;
+                    sqlx::query_as::<_, ScoredTask>(concat!(
+                        "SELECT ", task_columns!(), ", \
+                         MATCH(description) AGAINST (? IN NATURAL LANGUAGE MODE) AS relevance \
+                         FROM tasks WHERE MATCH(description) AGAINST (? IN NATURAL LANGUAGE MODE) AND ", readable!(), " \
+                         ORDER BY MATCH(description) AGAINST (? IN NATURAL LANGUAGE MODE) DESC, id DESC LIMIT ?"
+                    ))
+                    .bind(query)
+                    .bind(query)
+                    .bind_access(self.access())
+                    .bind(query)
+                    .bind(fetch)
+                    .fetch_all(&mut *conn)
+                    .await
+                }))
+                .await?
+            }
+            Some((score, id)) => {
+                self.timed("fulltext_search", db::retry_on_disconnect(|| async move {
+                    let mut conn = self.acquire().await?;
+                    sqlx::query_as::<_, ScoredTask>(concat!(
+                        "SELECT ", task_columns!(), ", \
+                         MATCH(description) AGAINST (? IN NATURAL LANGUAGE MODE) AS relevance \
+                         FROM tasks WHERE MATCH(description) AGAINST (? IN NATURAL LANGUAGE MODE) \
+                         AND (MATCH(description) AGAINST (? IN NATURAL LANGUAGE MODE) < ? \
+                              OR (MATCH(description) AGAINST (? IN NATURAL LANGUAGE MODE) = ? AND id < ?)) \
+                         AND ", readable!(), " \
+                         ORDER BY MATCH(description) AGAINST (? IN NATURAL LANGUAGE MODE) DESC, id DESC LIMIT ?"
+                    ))
+                    .bind(query)
+                    .bind(query)
+                    .bind(query)
+                    .bind(score)
+                    .bind(query)
+                    .bind(score)
+                    .bind(id)
+                    .bind_access(self.access())
+                    .bind(query)
+                    .bind(fetch)
+                    .fetch_all(&mut *conn)
+                    .await
+                }))
+                .await?
+            }
+        };
+
+        let next = if hits.len() > limit as usize {
+            hits.truncate(limit as usize);
+            hits.last().map(|hit| SearchCursor::Relevance { score: hit.relevance, id: hit.id })
+        } else {
+            None
+        };
+
+        let tasks = hits
+            .into_iter()
+            .map(|hit| Task {
+                id: hit.id,
+                description: hit.description,
+                completed: hit.completed,
+                created_at: hit.created_at,
+                created_by: hit.created_by,
+                updated_by: hit.updated_by,
+                updated_at: hit.updated_at,
+                due_at: hit.due_at,
+                priority: hit.priority,
+                project_id: hit.project_id,
+                owner_id: hit.owner_id,
+                assignee_id: hit.assignee_id,
+                workspace_id: hit.workspace_id,
+            })
+            .collect();
+
+        Ok(Page { tasks, next })
+    }
+
+    async fn like_search_page(
+        &self,
+        query: &str,
+        after: Option<ListCursor>,
+        limit: u32,
+    ) -> Result<Page<SearchCursor>, sqlx::Error> {
+        let pattern = format!("%{}%", escape_like(query));
+        let pattern = pattern.as_str();
+        let fetch = i64::from(limit) + 1;
+
+        let tasks = match after {
+            None => {
+                self.timed("like_search", db::retry_on_disconnect(|| async move {
+                    let mut conn = self.acquire().await?;
+                    sqlx::query_as::<_, Task>(concat!(
+                        "SELECT ", task_columns!(), " FROM tasks \
+                         WHERE description LIKE ? AND ", readable!(), " \
+                         ORDER BY created_at DESC, id DESC LIMIT ?"
+                    ))
+                    .bind(pattern)
+                    .bind_access(self.access())
+                    .bind(fetch)
+                    .fetch_all(&mut *conn)
+                    .await
+                }))
+                .await?
+            }
+            Some(cursor) => {
+                self.timed("like_search", db::retry_on_disconnect(|| async move {
+                    let mut conn = self.acquire().await?;
+                    sqlx::query_as::<_, Task>(concat!(
+                        "SELECT ", task_columns!(), " FROM tasks \
+                         WHERE description LIKE ? AND (created_at < ? OR (created_at = ? AND id < ?)) \
+                         AND ", readable!(), " ORDER BY created_at DESC, id DESC LIMIT ?"
+                    ))
+                    .bind(pattern)
+                    .bind(cursor.created_at)
+                    .bind(cursor.created_at)
+                    .bind(cursor.id)
+                    .bind_access(self.access())
+                    .bind(fetch)
+                    .fetch_all(&mut *conn)
+                    .await
+                }))
+                .await?
+            }
+        };
+
+        let page = recency_page(tasks, limit);
+        Ok(Page { tasks: page.tasks, next: page.next.map(SearchCursor::Recency) })
+    }
+
+    pub async fn user_count(&self) -> Result<i64, sqlx::Error> {
+        self.timed("user_count", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT COUNT(*) FROM users").fetch_one(&mut *conn).await
+        }))
+        .await
+    }
+
+    pub async fn users(&self) -> Result<Vec<User>, sqlx::Error> {
+        self.timed("users", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, User>("SELECT id, username, created_at FROM users ORDER BY username")
+                .fetch_all(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    pub async fn user_by_name(&self, username: &str) -> Result<Option<User>, sqlx::Error> {
+        self.timed("user_by_name", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, User>("SELECT id, username, created_at FROM users WHERE username = ?")
+                .bind(username)
+                .fetch_optional(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    pub async fn username(&self, user_id: i32) -> Result<Option<String>, sqlx::Error> {
+        self.timed("username", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT username FROM users WHERE id = ?")
+                .bind(user_id)
+                .fetch_optional(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    pub async fn password_hash(&self, user_id: i32) -> Result<Option<String>, sqlx::Error> {
+        self.timed("password_hash", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT password_hash FROM users WHERE id = ?")
+                .bind(user_id)
+                .fetch_optional(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // Creates an account and returns its id and the number of tasks it took over: the first
+    // account gets every task without an owner, so the tasks from before there were accounts
+    // don't disappear. It also becomes the first instance admin.
+    pub async fn create_user(&self, username: &str, password_hash: &str) -> Result<(i32, u64), sqlx::Error> {
+        self.timed("create_user", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let existing: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users FOR UPDATE").fetch_one(&mut *tx).await?;
+            let id = sqlx::query("INSERT INTO users (username, password_hash, is_admin) VALUES (?, ?, ?)")
+                .bind(username)
+                .bind(password_hash)
+                .bind(existing == 0)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_id() as i32;
+            let claimed = if existing == 0 {
+                sqlx::query("UPDATE tasks SET owner_id = ? WHERE owner_id IS NULL")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected()
+            } else {
+                0
+            };
+            tx.commit().await?;
+            Ok((id, claimed))
+        }))
+        .await
+    }
+
+    pub async fn set_password_hash(&self, user_id: i32, password_hash: &str) -> Result<(), sqlx::Error> {
+        self.timed("set_password_hash", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("UPDATE users SET password_hash = ? WHERE id = ?")
+                .bind(password_hash)
+                .bind(user_id)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    pub async fn create_session(&self, token_hash: &str, user_id: i32, days: i64) -> Result<(), sqlx::Error> {
+        self.timed("create_session", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            let created = sqlx::query(
+                "INSERT INTO user_sessions (token_hash, user_id, expires_at) \
+                 SELECT ?, id, NOW() + INTERVAL ? DAY FROM users WHERE id = ? AND disabled_at IS NULL",
+            )
+            .bind(token_hash)
+            .bind(days)
+            .bind(user_id)
+            .execute(&mut *conn)
+            .await?
+            .rows_affected();
+            if created == 0 {
+                return Err(forbidden(Forbidden::Disabled));
+            }
+            Ok(())
+        }))
+        .await
+    }
+
+    // Replaces a session that hasn't expired with a new one for the same user and workspace,
+    // lasting `days` from now. Returns false if there was no such session.
+    pub async fn rotate_session(&self, old_hash: &str, new_hash: &str, days: i64) -> Result<bool, sqlx::Error> {
+        self.timed("rotate_session", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let created = sqlx::query(
+                "INSERT INTO user_sessions (token_hash, user_id, workspace_id, expires_at) \
+                 SELECT ?, user_id, workspace_id, NOW() + INTERVAL ? DAY FROM user_sessions \
+                 WHERE token_hash = ? AND expires_at > NOW()",
+            )
+            .bind(new_hash)
+            .bind(days)
+            .bind(old_hash)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            sqlx::query("DELETE FROM user_sessions WHERE token_hash = ?").bind(old_hash).execute(&mut *tx).await?;
+            tx.commit().await?;
+            Ok(created > 0)
+        }))
+        .await
+    }
+
+    // The user a session belongs to and its workspace, unless it expired. A workspace the user
+    // has left since switching to it is dropped.
+    pub async fn session(&self, token_hash: &str) -> Result<Option<Session>, sqlx::Error> {
+        self.timed("session", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Session>(
+                "SELECT users.id, users.username, users.created_at, workspace_members.workspace_id, workspace_members.role \
+                 FROM user_sessions \
+                 JOIN users ON users.id = user_sessions.user_id \
+                 LEFT JOIN workspace_members ON workspace_members.workspace_id = user_sessions.workspace_id \
+                 AND workspace_members.user_id = user_sessions.user_id \
+                 WHERE user_sessions.token_hash = ? AND user_sessions.expires_at > NOW() \
+                 AND users.disabled_at IS NULL",
+            )
+            .bind(token_hash)
+            .fetch_optional(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Switches a session to a workspace, or back to the personal space with None
+    pub async fn set_session_workspace(&self, token_hash: &str, workspace: Option<i32>) -> Result<(), sqlx::Error> {
+        self.timed("set_session_workspace", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("UPDATE user_sessions SET workspace_id = ? WHERE token_hash = ?")
+                .bind(workspace)
+                .bind(token_hash)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Creates a workspace with the user as its first member, an admin, and returns its id
+    pub async fn create_workspace(&self, name: &str) -> Result<i32, sqlx::Error> {
+        self.timed("create_workspace", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let id = sqlx::query("INSERT INTO workspaces (name) VALUES (?)")
+                .bind(name)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_id() as i32;
+            sqlx::query("INSERT INTO workspace_members (workspace_id, user_id, role) VALUES (?, ?, ?)")
+                .bind(id)
+                .bind(self.user)
+                .bind(Role::Admin)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(id)
+        }))
+        .await
+    }
+
+    // The workspaces the user is a member of
+    pub async fn workspaces(&self) -> Result<Vec<Workspace>, sqlx::Error> {
+        self.timed("workspaces", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Workspace>(
+                "SELECT workspaces.id, workspaces.name, workspaces.created_at, \
+                 (SELECT COUNT(*) FROM workspace_members others WHERE others.workspace_id = workspaces.id) AS members, \
+                 workspace_members.role \
+                 FROM workspaces JOIN workspace_members ON workspace_members.workspace_id = workspaces.id \
+                 WHERE workspace_members.user_id = ? ORDER BY workspaces.name",
+            )
+            .bind(self.user)
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Id of a workspace by name, whether or not the user is in it
+    pub async fn workspace_id(&self, name: &str) -> Result<Option<i32>, sqlx::Error> {
+        self.timed("workspace_id", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT id FROM workspaces WHERE name = ?")
+                .bind(name)
+                .fetch_optional(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // Everyone in a workspace, admins first
+    pub async fn members(&self, workspace_id: i32) -> Result<Vec<Member>, sqlx::Error> {
+        self.timed("members", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Member>(
+                "SELECT users.id AS user_id, users.username, workspace_members.role FROM workspace_members \
+                 JOIN users ON users.id = workspace_members.user_id \
+                 WHERE workspace_members.workspace_id = ? ORDER BY workspace_members.role, users.username",
+            )
+            .bind(workspace_id)
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    pub async fn is_member(&self, workspace_id: i32, user_id: i32) -> Result<bool, sqlx::Error> {
+        self.timed("is_member", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM workspace_members WHERE workspace_id = ? AND user_id = ?)")
+                .bind(workspace_id)
+                .bind(user_id)
+                .fetch_one(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // Returns false if they were a member already, whatever their role
+    pub async fn add_member(&self, workspace_id: i32, user_id: i32, role: Role) -> Result<bool, sqlx::Error> {
+        self.timed("add_member", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            require_member_admin(&mut conn, workspace_id, self.user).await?;
+            sqlx::query("INSERT IGNORE INTO workspace_members (workspace_id, user_id, role) VALUES (?, ?, ?)")
+                .bind(workspace_id)
+                .bind(user_id)
+                .bind(role)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    pub async fn set_role(&self, workspace_id: i32, user_id: i32, role: Role) -> Result<(), sqlx::Error> {
+        self.timed("set_role", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            require_member_admin(&mut conn, workspace_id, self.user).await?;
+            sqlx::query("UPDATE workspace_members SET role = ? WHERE workspace_id = ? AND user_id = ?")
+                .bind(role)
+                .bind(workspace_id)
+                .bind(user_id)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Returns false if they weren't a member. Anyone may leave; taking others out needs a role
+    // that manages members.
+    pub async fn remove_member(&self, workspace_id: i32, user_id: i32) -> Result<bool, sqlx::Error> {
+        self.timed("remove_member", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            if self.user != Some(user_id) {
+                require_member_admin(&mut conn, workspace_id, self.user).await?;
+            }
+            sqlx::query("DELETE FROM workspace_members WHERE workspace_id = ? AND user_id = ?")
+                .bind(workspace_id)
+                .bind(user_id)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    pub async fn user_disabled(&self, user_id: i32) -> Result<bool, sqlx::Error> {
+        self.timed("user_disabled", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = ? AND disabled_at IS NOT NULL)")
+                .bind(user_id)
+                .fetch_one(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // Every account with what it owns, for instance admins
+    pub async fn accounts(&self) -> Result<Vec<Account>, sqlx::Error> {
+        self.timed("accounts", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            require_admin(&mut conn, self.user).await?;
+            sqlx::query_as::<_, Account>(
+                "SELECT users.id, users.username, users.created_at, users.is_admin, users.disabled_at, \
+                 (SELECT COUNT(*) FROM tasks WHERE tasks.owner_id = users.id) AS tasks, \
+                 (SELECT COUNT(*) FROM workspace_members WHERE workspace_members.user_id = users.id) AS workspaces \
+                 FROM users ORDER BY users.username",
+            )
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Disables an account and ends its sessions, or enables it again. Returns false if it
+    // already was that way.
+    pub async fn set_disabled(&self, user_id: i32, disabled: bool) -> Result<bool, sqlx::Error> {
+        self.timed("set_disabled", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            require_admin(&mut tx, self.user).await?;
+            let changed = sqlx::query(
+                "UPDATE users SET disabled_at = IF(?, NOW(), NULL) WHERE id = ? AND (disabled_at IS NULL) = ?",
+            )
+            .bind(disabled)
+            .bind(user_id)
+            .bind(disabled)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if disabled {
+                sqlx::query("DELETE FROM user_sessions WHERE user_id = ?").bind(user_id).execute(&mut *tx).await?;
+            }
+            tx.commit().await?;
+            Ok(changed > 0)
+        }))
+        .await
+    }
+
+    pub async fn set_admin(&self, user_id: i32, admin: bool) -> Result<(), sqlx::Error> {
+        self.timed("set_admin", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            require_admin(&mut conn, self.user).await?;
+            sqlx::query("UPDATE users SET is_admin = ? WHERE id = ?")
+                .bind(admin)
+                .bind(user_id)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Gives someone else's account a new password and ends its sessions
+    pub async fn reset_password(&self, user_id: i32, password_hash: &str) -> Result<(), sqlx::Error> {
+        self.timed("reset_password", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            require_admin(&mut tx, self.user).await?;
+            sqlx::query("UPDATE users SET password_hash = ? WHERE id = ?")
+                .bind(password_hash)
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM user_sessions WHERE user_id = ?").bind(user_id).execute(&mut *tx).await?;
+            tx.commit().await
+        }))
+        .await
+    }
+
+    pub async fn completion_stats(&self) -> Result<CompletionStats, sqlx::Error> {
+        self.timed("completion_stats", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, CompletionStats>(concat!(
+                "SELECT COUNT(CASE WHEN NOT completed THEN 1 END) AS pending, \
+                 COUNT(CASE WHEN completed THEN 1 END) AS completed, \
+                 COUNT(CASE WHEN NOT completed AND due_at < NOW() THEN 1 END) AS overdue, \
+                 COUNT(CASE WHEN completed AND completed_at >= week.started THEN 1 END) AS completed_this_week, \
+                 COUNT(CASE WHEN completed AND completed_at >= week.started - INTERVAL 7 DAY \
+                   AND completed_at < week.started THEN 1 END) AS completed_last_week, \
+                 CAST(AVG(CASE WHEN completed THEN TIMESTAMPDIFF(SECOND, created_at, completed_at) END) AS SIGNED) \
+                   AS average_secs \
+                 FROM tasks, (SELECT CURDATE() - INTERVAL WEEKDAY(CURDATE()) DAY AS started) AS week \
+                 WHERE ", readable!()
+            ))
+            .bind_access(self.access())
+            .fetch_one(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Tasks completed on each day since `since`, of one project or all; days without any are left out
+    pub async fn completions_by_day(
+        &self,
+        since: NaiveDateTime,
+        project: Option<i32>,
+    ) -> Result<Vec<(NaiveDate, i64)>, sqlx::Error> {
+        self.timed("completions_by_day", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, (NaiveDate, i64)>(concat!(
+                "SELECT DATE(completed_at) AS day, COUNT(*) FROM tasks \
+                 WHERE completed AND completed_at >= ? AND (? IS NULL OR project_id = ?) AND ", readable!(), " \
+                 GROUP BY day ORDER BY day"
+            ))
+            .bind(since)
+            .bind(project)
+            .bind(project)
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    pub async fn instance_stats(&self) -> Result<InstanceStats, sqlx::Error> {
+        self.timed("instance_stats", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            require_admin(&mut conn, self.user).await?;
+            sqlx::query_as::<_, InstanceStats>(
+                "SELECT (SELECT COUNT(*) FROM users) AS users, \
+                 (SELECT COUNT(*) FROM users WHERE is_admin) AS admins, \
+                 (SELECT COUNT(*) FROM users WHERE disabled_at IS NOT NULL) AS disabled, \
+                 (SELECT COUNT(*) FROM workspaces) AS workspaces, \
+                 (SELECT COUNT(*) FROM projects) AS projects, \
+                 (SELECT COUNT(*) FROM tasks) AS tasks, \
+                 (SELECT COUNT(*) FROM tasks WHERE NOT completed) AS open_tasks, \
+                 (SELECT COUNT(*) FROM tasks WHERE NOT completed AND due_at < NOW()) AS overdue_tasks, \
+                 (SELECT COUNT(*) FROM user_sessions WHERE expires_at > NOW()) AS sessions, \
+                 (SELECT COUNT(*) FROM api_tokens WHERE expires_at IS NULL OR expires_at > NOW()) AS api_tokens, \
+                 (SELECT COUNT(*) FROM workspace_invitations WHERE redeemed_at IS NULL AND expires_at > NOW()) \
+                 AS invitations",
+            )
+            .fetch_one(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Deletes the user's account with their tasks and everything hanging off them (tags, notes,
+    // checklists, shares, their activity and webhook log), and takes their name off what stays:
+    // what they did to other people's tasks, their notes there, and the audit columns. Sessions, tokens, settings
+    // and memberships go with the account. updated_at is kept, so syncs don't push the change.
+    pub async fn erase_account(&self) -> Result<Erased, sqlx::Error> {
+        let Some(user_id) = self.user else {
+            return Ok(Erased::default());
+        };
+        self.timed("erase_account", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let username: Option<String> = sqlx::query_scalar("SELECT username FROM users WHERE id = ? FOR UPDATE")
+                .bind(user_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+            let Some(username) = username else {
+                return Ok(Erased::default());
+            };
+
+            sqlx::query("DELETE FROM task_activity WHERE task_id IN (SELECT id FROM tasks WHERE owner_id = ?)")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM webhook_deliveries WHERE task_id IN (SELECT id FROM tasks WHERE owner_id = ?)")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?;
+            let tasks = sqlx::query("DELETE FROM tasks WHERE owner_id = ?")
+                .bind(user_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            let activity =
+                sqlx::query("UPDATE task_activity SET actor = NULL, user_id = NULL WHERE user_id = ? OR actor = ?")
+                    .bind(user_id)
+                    .bind(&username)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected();
+            sqlx::query(
+                "UPDATE tasks SET created_by = IF(created_by = ?, NULL, created_by), \
+                 updated_by = IF(updated_by = ?, NULL, updated_by), updated_at = updated_at \
+                 WHERE created_by = ? OR updated_by = ?",
+            )
+            .bind(&username)
+            .bind(&username)
+            .bind(&username)
+            .bind(&username)
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query("UPDATE task_notes SET created_by = NULL WHERE created_by = ?")
+                .bind(&username)
+                .execute(&mut *tx)
+                .await?;
+
+            sqlx::query("DELETE FROM users WHERE id = ?").bind(user_id).execute(&mut *tx).await?;
+            tx.commit().await?;
+            Ok(Erased { tasks, activity })
+        }))
+        .await
+    }
+
+    // The enabled users with an email setting, with their digest_time and timezone settings
+    pub async fn digest_recipients(&self) -> Result<Vec<DigestRecipient>, sqlx::Error> {
+        self.timed("digest_recipients", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, DigestRecipient>(
+                "SELECT users.id, users.username, \
+                 MAX(CASE WHEN user_settings.name = 'email' THEN user_settings.value END) AS email, \
+                 MAX(CASE WHEN user_settings.name = 'digest_time' THEN user_settings.value END) AS digest_time, \
+                 MAX(CASE WHEN user_settings.name = 'timezone' THEN user_settings.value END) AS timezone \
+                 FROM users JOIN user_settings ON user_settings.user_id = users.id \
+                 WHERE users.disabled_at IS NULL GROUP BY users.id, users.username \
+                 HAVING email IS NOT NULL ORDER BY users.username",
+            )
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // The user's stored settings, by name
+    pub async fn settings(&self) -> Result<Vec<(String, String)>, sqlx::Error> {
+        self.timed("settings", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as("SELECT name, value FROM user_settings WHERE user_id = ? ORDER BY name")
+                .bind(self.user)
+                .fetch_all(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    pub async fn set_setting(&self, name: &str, value: &str) -> Result<(), sqlx::Error> {
+        self.timed("set_setting", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query(
+                "INSERT INTO user_settings (user_id, name, value) VALUES (?, ?, ?) \
+                 ON DUPLICATE KEY UPDATE value = VALUES(value)",
+            )
+            .bind(self.user)
+            .bind(name)
+            .bind(value)
+            .execute(&mut *conn)
+            .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Returns false if the user had no such setting
+    pub async fn delete_setting(&self, name: &str) -> Result<bool, sqlx::Error> {
+        self.timed("delete_setting", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("DELETE FROM user_settings WHERE user_id = ? AND name = ?")
+                .bind(self.user)
+                .bind(name)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    // Stores the hash of a new invitation code for the workspace, valid for `days`. Needs a role
+    // that manages members there.
+    pub async fn create_invitation(
+        &self,
+        workspace_id: i32,
+        code_hash: &str,
+        role: Role,
+        days: i64,
+    ) -> Result<NaiveDateTime, sqlx::Error> {
+        self.timed("create_invitation", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            require_member_admin(&mut conn, workspace_id, self.user).await?;
+            let id = sqlx::query(
+                "INSERT INTO workspace_invitations (workspace_id, code_hash, role, created_by, expires_at) \
+                 VALUES (?, ?, ?, ?, NOW() + INTERVAL ? DAY)",
+            )
+            .bind(workspace_id)
+            .bind(code_hash)
+            .bind(role)
+            .bind(self.user)
+            .bind(days)
+            .execute(&mut *conn)
+            .await?
+            .last_insert_id();
+            sqlx::query_scalar("SELECT expires_at FROM workspace_invitations WHERE id = ?")
+                .bind(id)
+                .fetch_one(&mut *conn)
+                .await
+        }))
+        .await
+    }
+
+    // The workspace's invitations that are neither used nor expired, newest first
+    pub async fn invitations(&self, workspace_id: i32) -> Result<Vec<Invitation>, sqlx::Error> {
+        self.timed("invitations", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            require_member_admin(&mut conn, workspace_id, self.user).await?;
+            sqlx::query_as::<_, Invitation>(
+                "SELECT workspace_invitations.id, workspace_invitations.role, users.username AS created_by, \
+                 workspace_invitations.created_at, workspace_invitations.expires_at FROM workspace_invitations \
+                 LEFT JOIN users ON users.id = workspace_invitations.created_by \
+                 WHERE workspace_invitations.workspace_id = ? AND workspace_invitations.redeemed_at IS NULL \
+                 AND workspace_invitations.expires_at > NOW() ORDER BY workspace_invitations.id DESC",
+            )
+            .bind(workspace_id)
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Returns false unless the workspace has an invitation with this id that hasn't been used
+    pub async fn delete_invitation(&self, workspace_id: i32, id: i32) -> Result<bool, sqlx::Error> {
+        self.timed("delete_invitation", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            require_member_admin(&mut conn, workspace_id, self.user).await?;
+            sqlx::query("DELETE FROM workspace_invitations WHERE id = ? AND workspace_id = ? AND redeemed_at IS NULL")
+                .bind(id)
+                .bind(workspace_id)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    // Where an invitation code leads, if it can still be redeemed
+    pub async fn invitation_by_code(&self, code_hash: &str) -> Result<Option<PendingInvitation>, sqlx::Error> {
+        self.timed("invitation_by_code", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, PendingInvitation>(
+                "SELECT workspaces.name AS workspace, workspace_invitations.role, workspace_invitations.expires_at \
+                 FROM workspace_invitations JOIN workspaces ON workspaces.id = workspace_invitations.workspace_id \
+                 WHERE workspace_invitations.code_hash = ? AND workspace_invitations.redeemed_at IS NULL \
+                 AND workspace_invitations.expires_at > NOW()",
+            )
+            .bind(code_hash)
+            .fetch_optional(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Creates an account in the invitation's workspace, with its role, and marks the invitation
+    // used. None if there is no such code, or it was used or has expired. The invitation row is
+    // locked first, so two people redeeming the same code can't both get in.
+    pub async fn redeem_invitation(
+        &self,
+        code_hash: &str,
+        username: &str,
+        password_hash: &str,
+    ) -> Result<Option<Redeemed>, sqlx::Error> {
+        self.timed("redeem_invitation", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let invitation: Option<(i32, i32, String, Role)> = sqlx::query_as(
+                "SELECT workspace_invitations.id, workspaces.id, workspaces.name, workspace_invitations.role \
+                 FROM workspace_invitations JOIN workspaces ON workspaces.id = workspace_invitations.workspace_id \
+                 WHERE workspace_invitations.code_hash = ? AND workspace_invitations.redeemed_at IS NULL \
+                 AND workspace_invitations.expires_at > NOW() FOR UPDATE",
+            )
+            .bind(code_hash)
+            .fetch_optional(&mut *tx)
+            .await?;
+            let Some((id, workspace_id, workspace, role)) = invitation else {
+                return Ok(None);
+            };
+            let user_id = sqlx::query("INSERT INTO users (username, password_hash) VALUES (?, ?)")
+                .bind(username)
+                .bind(password_hash)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_id() as i32;
+            sqlx::query("INSERT INTO workspace_members (workspace_id, user_id, role) VALUES (?, ?, ?)")
+                .bind(workspace_id)
+                .bind(user_id)
+                .bind(role)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("UPDATE workspace_invitations SET redeemed_at = NOW(), redeemed_by = ? WHERE id = ?")
+                .bind(user_id)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(Some(Redeemed { user_id, workspace_id, workspace, role }))
+        }))
+        .await
+    }
+
+    // Ends a session, and clears out the expired ones while at it
+    pub async fn delete_session(&self, token_hash: &str) -> Result<(), sqlx::Error> {
+        self.timed("delete_session", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("DELETE FROM user_sessions WHERE token_hash = ? OR expires_at <= NOW()")
+                .bind(token_hash)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // The user an identity provider's account logs in as, if it has before
+    pub async fn identity_user(&self, issuer: &str, subject: &str) -> Result<Option<User>, sqlx::Error> {
+        self.timed("identity_user", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, User>(
+                "SELECT users.id, users.username, users.created_at FROM user_identities \
+                 JOIN users ON users.id = user_identities.user_id \
+                 WHERE user_identities.issuer = ? AND user_identities.subject = ?",
+            )
+            .bind(issuer)
+            .bind(subject)
+            .fetch_optional(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    pub async fn link_identity(&self, issuer: &str, subject: &str, user_id: i32) -> Result<(), sqlx::Error> {
+        self.timed("link_identity", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("INSERT INTO user_identities (issuer, subject, user_id) VALUES (?, ?, ?)")
+                .bind(issuer)
+                .bind(subject)
+                .bind(user_id)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    // Stores the hash of a new token of the user, for their current workspace. Returns its id.
+    pub async fn create_api_token(
+        &self,
+        name: &str,
+        token_hash: &str,
+        scope: Scope,
+        days: Option<i64>,
+    ) -> Result<i32, sqlx::Error> {
+        self.timed("create_api_token", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query(
+                "INSERT INTO api_tokens (user_id, workspace_id, name, token_hash, scope, expires_at) \
+                 VALUES (?, ?, ?, ?, ?, NOW() + INTERVAL ? DAY)",
+            )
+            .bind(self.user)
+            .bind(self.workspace)
+            .bind(name)
+            .bind(token_hash)
+            .bind(scope)
+            .bind(days)
+            .execute(&mut *conn)
+            .await
+        }))
+        .await
+        .map(|result| result.last_insert_id() as i32)
+    }
+
+    // The user's tokens, expired ones included, newest first
+    pub async fn api_tokens(&self) -> Result<Vec<ApiToken>, sqlx::Error> {
+        self.timed("api_tokens", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, ApiToken>(
+                "SELECT api_tokens.id, api_tokens.name, api_tokens.scope, workspaces.name AS workspace, \
+                 api_tokens.created_at, api_tokens.last_used_at, api_tokens.expires_at FROM api_tokens \
+                 LEFT JOIN workspaces ON workspaces.id = api_tokens.workspace_id \
+                 WHERE api_tokens.user_id = ? ORDER BY api_tokens.id DESC",
+            )
+            .bind(self.user)
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Returns false unless the user has a token with this id
+    pub async fn delete_api_token(&self, id: i32) -> Result<bool, sqlx::Error> {
+        self.timed("delete_api_token", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("DELETE FROM api_tokens WHERE id = ? AND user_id = ?")
+                .bind(id)
+                .bind(self.user)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await
+        .map(|result| result.rows_affected() > 0)
+    }
+
+    // Who a token belongs to, unless it expired, and notes that it was used. Unlike a login, a
+    // token for a workspace the user has left stops working rather than falling back to the
+    // personal space.
+    pub async fn api_token_login(&self, token_hash: &str) -> Result<Option<TokenLogin>, sqlx::Error> {
+        self.timed("api_token_login", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            let login = sqlx::query_as::<_, TokenLogin>(
+                "SELECT users.id, users.username, users.created_at, api_tokens.workspace_id, workspace_members.role, \
+                 api_tokens.scope FROM api_tokens \
+                 JOIN users ON users.id = api_tokens.user_id \
+                 LEFT JOIN workspace_members ON workspace_members.workspace_id = api_tokens.workspace_id \
+                 AND workspace_members.user_id = api_tokens.user_id \
+                 WHERE api_tokens.token_hash = ? AND (api_tokens.expires_at IS NULL OR api_tokens.expires_at > NOW()) \
+                 AND (api_tokens.workspace_id IS NULL OR workspace_members.user_id IS NOT NULL) \
+                 AND users.disabled_at IS NULL",
+            )
+            .bind(token_hash)
+            .fetch_optional(&mut *conn)
+            .await?;
+            if login.is_some() {
+                sqlx::query("UPDATE api_tokens SET last_used_at = NOW() WHERE token_hash = ?")
+                    .bind(token_hash)
+                    .execute(&mut *conn)
+                    .await?;
+            }
+            Ok(login)
+        }))
+        .await
+    }
+
+    // Shares one of the user's tasks with `with`, or changes whether they may write to it.
+    // Returns false unless the user owns the task.
+    pub async fn share_task(&self, id: i32, with: i32, can_write: bool) -> Result<bool, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("share_task", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            let owns: bool = sqlx::query_scalar(
+                "SELECT EXISTS (SELECT 1 FROM tasks WHERE id = ? AND owner_id = ? AND workspace_id <=> ?)",
+            )
+            .bind(id)
+            .bind(self.user)
+            .bind(self.workspace)
+            .fetch_one(&mut *conn)
+            .await?;
+            if owns {
+                sqlx::query(
+                    "INSERT INTO task_shares (task_id, user_id, can_write) VALUES (?, ?, ?) \
+                     ON DUPLICATE KEY UPDATE can_write = VALUES(can_write)",
+                )
+                .bind(id)
+                .bind(with)
+                .bind(can_write)
+                .execute(&mut *conn)
+                .await?;
+            }
+            Ok(owns)
+        }))
+        .await
+    }
+
+    // Returns the number of shares removed (0 or 1)
+    pub async fn unshare_task(&self, id: i32, with: i32) -> Result<u64, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("unshare_task", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query(
+                "DELETE FROM task_shares WHERE task_id = ? AND user_id = ? \
+                 AND task_id IN (SELECT id FROM tasks WHERE owner_id = ?)",
+            )
+            .bind(id)
+            .bind(with)
+            .bind(self.user)
+            .execute(&mut *conn)
+            .await
+        }))
+        .await
+        .map(|result| result.rows_affected())
+    }
+
+    // Shares the user's tasks in a project with `with`, including those added to it later
+    pub async fn share_project(&self, project_id: i32, with: i32, can_write: bool) -> Result<(), sqlx::Error> {
+        self.require(Permission::ManageProjects)?;
+        self.timed("share_project", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query(
+                "INSERT INTO project_shares (owner_id, project_id, user_id, can_write) VALUES (?, ?, ?, ?) \
+                 ON DUPLICATE KEY UPDATE can_write = VALUES(can_write)",
+            )
+            .bind(self.user)
+            .bind(project_id)
+            .bind(with)
+            .bind(can_write)
+            .execute(&mut *conn)
+            .await
+        }))
+        .await?;
+        Ok(())
+    }
+
+    pub async fn unshare_project(&self, project_id: i32, with: i32) -> Result<u64, sqlx::Error> {
+        self.require(Permission::ManageProjects)?;
+        self.timed("unshare_project", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("DELETE FROM project_shares WHERE owner_id = ? AND project_id = ? AND user_id = ?")
+                .bind(self.user)
+                .bind(project_id)
+                .bind(with)
+                .execute(&mut *conn)
+                .await
+        }))
+        .await
+        .map(|result| result.rows_affected())
+    }
+
+    // What the user shared and what was shared with them
+    pub async fn shares(&self) -> Result<Vec<Share>, sqlx::Error> {
+        self.timed("shares", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Share>(
+                "SELECT 'task' AS kind, CAST(task_shares.task_id AS CHAR) AS subject, owners.username AS owner, \
+                 users.username AS user, task_shares.can_write FROM task_shares \
+                 JOIN tasks ON tasks.id = task_shares.task_id JOIN users owners ON owners.id = tasks.owner_id \
+                 JOIN users ON users.id = task_shares.user_id WHERE tasks.owner_id = ? OR task_shares.user_id = ? \
+                 UNION ALL \
+                 SELECT 'project', projects.name, owners.username, users.username, project_shares.can_write \
+                 FROM project_shares JOIN projects ON projects.id = project_shares.project_id \
+                 JOIN users owners ON owners.id = project_shares.owner_id \
+                 JOIN users ON users.id = project_shares.user_id \
+                 WHERE project_shares.owner_id = ? OR project_shares.user_id = ? \
+                 ORDER BY kind, owner, subject, user",
+            )
+            .bind(self.user)
+            .bind(self.user)
+            .bind(self.user)
+            .bind(self.user)
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Assigns a task the user may change to `assignee`, or unassigns it with None. Returns false
+    // if there is no such task.
+    #[tracing::instrument(level = "debug", skip_all, fields(task_id = id))]
+    pub async fn assign(&self, id: i32, assignee: Option<i32>) -> Result<bool, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("assign", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let assigned =
+                sqlx::query(concat!("UPDATE tasks SET assignee_id = ?, updated_by = ? WHERE id = ? AND ", writable!()))
+                    .bind(assignee)
+                    .bind(&self.actor)
+                    .bind(id)
+                    .bind_access(self.access())
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected()
+                    > 0;
+            if assigned {
+                record_activity(&mut tx, Action::Assigned, &[id], &self.actor, self.user).await?;
+            }
+            tx.commit().await?;
+            Ok(assigned)
+        }))
+        .await
+    }
+
+    // A page of activity in the workspace (or personal space): what happened to tasks the user
+    // can see now, and what they did themselves. Workspace admins see everything in it. With
+    // `by`, only what that user did.
+    pub async fn activity(
+        &self,
+        by: Option<i32>,
+        before: Option<i64>,
+        limit: u32,
+    ) -> Result<ActivityPage, sqlx::Error> {
+        let admin = self.role == Some(Role::Admin);
+        let mut entries = self
+            .timed("activity", db::retry_on_disconnect(|| async move {
+                let mut conn = self.acquire().await?;
+                sqlx::query_as::<_, ActivityEntry>(concat!(
+                    "SELECT task_activity.id, task_activity.task_id, task_activity.description, task_activity.action, \
+                     task_activity.actor, task_activity.occurred_at FROM task_activity \
+                     WHERE task_activity.workspace_id <=> ? AND (? OR ? IS NULL OR task_activity.user_id = ? \
+                     OR task_activity.task_id IN (SELECT id FROM tasks WHERE ", readable!(), ")) \
+                     AND (? IS NULL OR task_activity.user_id = ?) AND (? IS NULL OR task_activity.id < ?) \
+                     ORDER BY task_activity.id DESC LIMIT ?"
+                ))
+                .bind(self.workspace)
+                .bind(admin)
+                .bind(self.user)
+                .bind(self.user)
+                .bind_access(self.access())
+                .bind(by)
+                .bind(by)
+                .bind(before)
+                .bind(before)
+                .bind(limit + 1)
+                .fetch_all(&mut *conn)
+                .await
+            }))
+            .await?;
+        let next = if entries.len() > limit as usize {
+            entries.truncate(limit as usize);
+            entries.last().map(|entry| entry.id)
+        } else {
+            None
+        };
+        Ok(ActivityPage { entries, next })
+    }
+
+    // Tasks created, completed, reopened and deleted since `since`, oldest first, with the same
+    // visibility as `activity`
+    pub async fn open_changes(&self, since: NaiveDateTime) -> Result<Vec<OpenChange>, sqlx::Error> {
+        let admin = self.role == Some(Role::Admin);
+        self.timed("open_changes", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, OpenChange>(concat!(
+                "SELECT task_activity.action, task_activity.occurred_at, \
+                 (SELECT prior.action FROM task_activity AS prior WHERE prior.task_id = task_activity.task_id \
+                   AND prior.id < task_activity.id AND prior.action IN ('created', 'completed', 'reopened') \
+                   ORDER BY prior.id DESC LIMIT 1) AS `before` FROM task_activity \
+                 WHERE task_activity.workspace_id <=> ? AND (? OR ? IS NULL OR task_activity.user_id = ? \
+                 OR task_activity.task_id IN (SELECT id FROM tasks WHERE ", readable!(), ")) \
+                 AND task_activity.action IN ('created', 'completed', 'reopened', 'deleted') \
+                 AND task_activity.occurred_at >= ? ORDER BY task_activity.id"
+            ))
+            .bind(self.workspace)
+            .bind(admin)
+            .bind(self.user)
+            .bind(self.user)
+            .bind_access(self.access())
+            .bind(since)
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // The user's timer that is still running, if any, in whichever workspace
+    pub async fn running_timer(&self) -> Result<Option<TimeEntry>, sqlx::Error> {
+        self.timed("running_timer", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, TimeEntry>(concat!(
+                "SELECT ", time_entry_columns!(), " FROM time_entries JOIN tasks ON tasks.id = time_entries.task_id \
+                 LEFT JOIN users ON users.id = time_entries.user_id \
+                 WHERE time_entries.user_id <=> ? AND time_entries.ended_at IS NULL"
+            ))
+            .bind(self.user)
+            .fetch_optional(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Starts a timer on a task the user can see, stopping the one running. False if there is no
+    // such task.
+    pub async fn start_timer(&self, task_id: i32) -> Result<bool, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("start_timer", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let visible: bool =
+                sqlx::query_scalar(concat!("SELECT EXISTS (SELECT 1 FROM tasks WHERE id = ? AND ", readable!(), ")"))
+                    .bind(task_id)
+                    .bind_access(self.access())
+                    .fetch_one(&mut *tx)
+                    .await?;
+            if !visible {
+                return Ok(false);
+            }
+            stop_timers(&mut tx, self.user).await?;
+            sqlx::query("INSERT INTO time_entries (task_id, user_id, started_at) VALUES (?, ?, NOW())")
+                .bind(task_id)
+                .bind(self.user)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(true)
+        }))
+        .await
+    }
+
+    // Stops the user's running timer, returning it as it ended; None if none was running
+    pub async fn stop_timer(&self) -> Result<Option<TimeEntry>, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        let Some(running) = self.running_timer().await? else {
+            return Ok(None);
+        };
+        self.timed("stop_timer", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            stop_timers(&mut conn, self.user).await
+        }))
+        .await?;
+        Ok(Some(running))
+    }
+
+    // Time tracked from `from` until `to` on the tasks the user can see, by anyone, oldest first.
+    // An entry counts where it started.
+    pub async fn time_entries(&self, from: NaiveDateTime, to: NaiveDateTime) -> Result<Vec<TimeEntry>, sqlx::Error> {
+        self.timed("time_entries", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, TimeEntry>(concat!(
+                "SELECT ", time_entry_columns!(), " FROM time_entries JOIN tasks ON tasks.id = time_entries.task_id \
+                 LEFT JOIN users ON users.id = time_entries.user_id \
+                 WHERE time_entries.started_at >= ? AND time_entries.started_at < ? AND ", readable!(), " \
+                 ORDER BY time_entries.started_at, time_entries.id"
+            ))
+            .bind(from)
+            .bind(to)
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Sets or, with None, removes the estimate of a task the user may change. False if there is
+    // no such task.
+    pub async fn set_estimate(&self, id: i32, minutes: Option<u32>) -> Result<bool, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("set_estimate", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            let writable: bool =
+                sqlx::query_scalar(concat!("SELECT EXISTS (SELECT 1 FROM tasks WHERE id = ? AND ", writable!(), ")"))
+                    .bind(id)
+                    .bind_access(self.access())
+                    .fetch_one(&mut *conn)
+                    .await?;
+            if !writable {
+                return Ok(false);
+            }
+            match minutes {
+                Some(minutes) => {
+                    sqlx::query(
+                        "INSERT INTO task_estimates (task_id, minutes) VALUES (?, ?) \
+                         ON DUPLICATE KEY UPDATE minutes = VALUES(minutes)",
+                    )
+                    .bind(id)
+                    .bind(minutes)
+                    .execute(&mut *conn)
+                    .await?
+                }
+                None => {
+                    sqlx::query("DELETE FROM task_estimates WHERE task_id = ?").bind(id).execute(&mut *conn).await?
+                }
+            };
+            Ok(true)
+        }))
+        .await
+    }
+
+    // Makes a task recur, or with None stop recurring; false if the user can't change it. A new
+    // rule starts counting occurrences from now.
+    pub async fn set_recurrence(&self, id: i32, every: Option<&str>) -> Result<bool, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("set_recurrence", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            let writable: bool =
+                sqlx::query_scalar(concat!("SELECT EXISTS (SELECT 1 FROM tasks WHERE id = ? AND ", writable!(), ")"))
+                    .bind(id)
+                    .bind_access(self.access())
+                    .fetch_one(&mut *conn)
+                    .await?;
+            if !writable {
+                return Ok(false);
+            }
+            match every {
+                Some(every) => {
+                    sqlx::query(
+                        "INSERT INTO task_recurrences (task_id, every) VALUES (?, ?) \
+                         ON DUPLICATE KEY UPDATE created_at = IF(every = VALUES(every), created_at, NOW()), \
+                         every = VALUES(every)",
+                    )
+                    .bind(id)
+                    .bind(every)
+                    .execute(&mut *conn)
+                    .await?
+                }
+                None => {
+                    sqlx::query("DELETE FROM task_recurrences WHERE task_id = ?").bind(id).execute(&mut *conn).await?
+                }
+            };
+            Ok(true)
+        }))
+        .await
+    }
+
+    // The visible recurring tasks, in order of id
+    pub async fn recurring_tasks(&self) -> Result<Vec<Recurring>, sqlx::Error> {
+        self.timed("recurring_tasks", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Recurring>(concat!(
+                "SELECT tasks.id AS task_id, tasks.description, tasks.due_at, task_recurrences.every, \
+                 task_recurrences.created_at AS since, \
+                 (SELECT MAX(due_at) FROM task_occurrences WHERE recurring_id = tasks.id) AS last_occurrence \
+                 FROM tasks JOIN task_recurrences ON task_recurrences.task_id = tasks.id \
+                 WHERE ", readable!(), " ORDER BY tasks.id"
+            ))
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Creates a copy of a recurring task, with its tags and estimate, for each of the occurrences
+    // `due` that has none yet. Returns the ids of the new tasks. Safe to repeat, as an occurrence
+    // once recorded is never created again.
+    pub async fn create_occurrences(&self, id: i32, due: &[NaiveDateTime]) -> Result<Vec<i32>, sqlx::Error> {
+        self.require(Permission::EditTasks)?;
+        self.timed("create_occurrences", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let mut ids = Vec::new();
+            for &due_at in due {
+                let claimed = sqlx::query("INSERT IGNORE INTO task_occurrences (recurring_id, due_at) VALUES (?, ?)")
+                    .bind(id)
+                    .bind(due_at)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected();
+                if claimed == 0 {
+                    continue;
+                }
+                let copy = sqlx::query(
+                    "INSERT INTO tasks (description, created_by, updated_by, due_at, priority, project_id, owner_id, \
+                     assignee_id, workspace_id) \
+                     SELECT description, ?, ?, ?, priority, project_id, owner_id, assignee_id, workspace_id \
+                     FROM tasks WHERE id = ?",
+                )
+                .bind(&self.actor)
+                .bind(&self.actor)
+                .bind(due_at)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?
+                .last_insert_id() as i32;
+                sqlx::query(
+                    "INSERT INTO task_tags (task_id, tag_id) SELECT ?, tag_id FROM task_tags WHERE task_id = ?",
+                )
+                .bind(copy)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query(
+                    "INSERT INTO task_estimates (task_id, minutes) \
+                     SELECT ?, minutes FROM task_estimates WHERE task_id = ?",
+                )
+                .bind(copy)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+                sqlx::query("UPDATE task_occurrences SET task_id = ? WHERE recurring_id = ? AND due_at = ?")
+                    .bind(copy)
+                    .bind(id)
+                    .bind(due_at)
+                    .execute(&mut *tx)
+                    .await?;
+                ids.push(copy);
+            }
+            record_activity(&mut tx, Action::Created, &ids, &self.actor, self.user).await?;
+            tx.commit().await?;
+            Ok(ids)
+        }))
+        .await
+    }
+
+    // The visible tasks per project, the most open first
+    pub async fn breakdown_by_project(&self) -> Result<Vec<Breakdown>, sqlx::Error> {
+        self.timed("breakdown_by_project", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Breakdown>(concat!(
+                "SELECT projects.name, ", breakdown_columns!(), " FROM tasks \
+                 LEFT JOIN projects ON projects.id = tasks.project_id \
+                 LEFT JOIN task_estimates ON task_estimates.task_id = tasks.id \
+                 WHERE ", readable!(), " GROUP BY tasks.project_id, projects.name ORDER BY open DESC, projects.name"
+            ))
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // The visible tasks that are open, or were open at some point since `since`
+    pub async fn open_spans(&self, since: NaiveDateTime) -> Result<Vec<OpenSpan>, sqlx::Error> {
+        self.timed("open_spans", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, OpenSpan>(concat!(
+                "SELECT created_at, CASE WHEN completed THEN COALESCE(completed_at, updated_at) END AS completed_at \
+                 FROM tasks WHERE (NOT completed OR COALESCE(completed_at, updated_at) >= ?) AND ", readable!()
+            ))
+            .bind(since)
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // The open tasks the user sees that are due before `before` (overdue ones too), per day
+    pub async fn due_load(&self, before: NaiveDateTime) -> Result<Vec<DueLoad>, sqlx::Error> {
+        self.timed("due_load", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, DueLoad>(concat!(
+                "SELECT DATE(tasks.due_at) AS day, COUNT(*) AS tasks, \
+                 CAST(COALESCE(SUM(task_estimates.minutes), 0) AS SIGNED) AS estimate_minutes, \
+                 COUNT(CASE WHEN task_estimates.minutes IS NULL THEN 1 END) AS unestimated FROM tasks \
+                 LEFT JOIN task_estimates ON task_estimates.task_id = tasks.id \
+                 WHERE NOT tasks.completed AND tasks.due_at < ? AND ", readable!(), " GROUP BY day ORDER BY day"
+            ))
+            .bind(before)
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // The visible tasks per tag, the most open first; a task with several tags counts for each
+    pub async fn breakdown_by_tag(&self) -> Result<Vec<Breakdown>, sqlx::Error> {
+        self.timed("breakdown_by_tag", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Breakdown>(concat!(
+                "SELECT tags.name, ", breakdown_columns!(), " FROM tasks \
+                 LEFT JOIN task_tags ON task_tags.task_id = tasks.id LEFT JOIN tags ON tags.id = task_tags.tag_id \
+                 LEFT JOIN task_estimates ON task_estimates.task_id = tasks.id \
+                 WHERE ", readable!(), " GROUP BY tags.name ORDER BY open DESC, tags.name"
+            ))
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Visible open tasks due before `before` that weren't escalated for their due date yet,
+    // the longest overdue first
+    pub async fn unescalated_overdue(&self, before: NaiveDateTime) -> Result<Vec<Task>, sqlx::Error> {
+        self.timed("unescalated_overdue", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Task>(concat!(
+                "SELECT ", task_columns!(), " FROM tasks WHERE completed = FALSE AND due_at < ? AND NOT EXISTS \
+                 (SELECT 1 FROM task_escalations WHERE task_id = tasks.id AND due_at = tasks.due_at) AND ",
+                readable!(), " ORDER BY due_at, id"
+            ))
+            .bind(before)
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Logs the escalation of a task for its due date `due_at` and, given a priority, raises it
+    // to that where the user may change the task. False if it was escalated for this due date
+    // already, or its due date has moved since. Only raising needs a role that edits tasks; a
+    // role that may read the task can log a notice about it.
+    pub async fn escalate(
+        &self,
+        id: i32,
+        due_at: NaiveDateTime,
+        days_overdue: i64,
+        raise_to: Option<Priority>,
+        channel: Option<&str>,
+    ) -> Result<bool, sqlx::Error> {
+        if raise_to.is_some() {
+            self.require(Permission::EditTasks)?;
+        }
+        self.timed("escalate", db::retry_lock_conflicts(&self.retry, || async move {
+            let mut conn = self.acquire().await?;
+            let mut tx = conn.begin().await?;
+            let logged = sqlx::query(concat!(
+                "INSERT IGNORE INTO task_escalations \
+                 (task_id, due_at, days_overdue, old_priority, new_priority, channel) \
+                 SELECT id, due_at, ?, priority, priority, ? FROM tasks WHERE id = ? AND due_at = ? AND ",
+                readable!()
+            ))
+            .bind(days_overdue)
+            .bind(channel)
+            .bind(id)
+            .bind(due_at)
+            .bind_access(self.access())
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            if logged == 0 {
+                return Ok(false);
+            }
+            if let Some(priority) = raise_to {
+                let raised = sqlx::query(concat!(
+                    "UPDATE tasks SET priority = ?, updated_by = ? WHERE id = ? AND NOT priority <=> ? AND ",
+                    writable!()
+                ))
+                .bind(priority)
+                .bind(&self.actor)
+                .bind(id)
+                .bind(priority)
+                .bind_access(self.access())
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+                if raised > 0 {
+                    sqlx::query("UPDATE task_escalations SET new_priority = ? WHERE task_id = ? AND due_at = ?")
+                        .bind(priority)
+                        .bind(id)
+                        .bind(due_at)
+                        .execute(&mut *tx)
+                        .await?;
+                    record_activity(&mut tx, Action::Updated, &[id], &self.actor, self.user).await?;
+                }
+            }
+            tx.commit().await?;
+            Ok(true)
+        }))
+        .await
+    }
+
+    // The latest escalations of visible tasks, newest first
+    pub async fn escalations(&self, limit: u32) -> Result<Vec<Escalation>, sqlx::Error> {
+        self.timed("escalations", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Escalation>(concat!(
+                "SELECT ", escalation_columns!(), " FROM task_escalations \
+                 JOIN tasks ON tasks.id = task_escalations.task_id WHERE ", readable!(), " \
+                 ORDER BY task_escalations.escalated_at DESC, task_escalations.task_id DESC LIMIT ?"
+            ))
+            .bind_access(self.access())
+            .bind(limit)
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // Escalations of visible tasks whose channel is yet to be told, oldest first
+    pub async fn unnotified_escalations(&self) -> Result<Vec<Escalation>, sqlx::Error> {
+        self.timed("unnotified_escalations", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Escalation>(concat!(
+                "SELECT ", escalation_columns!(), " FROM task_escalations \
+                 JOIN tasks ON tasks.id = task_escalations.task_id \
+                 WHERE task_escalations.channel IS NOT NULL AND task_escalations.notified_at IS NULL AND ",
+                readable!(), " ORDER BY task_escalations.escalated_at, task_escalations.task_id"
+            ))
+            .bind_access(self.access())
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    pub async fn mark_escalation_notified(&self, id: i32, due_at: NaiveDateTime) -> Result<(), sqlx::Error> {
+        self.timed("mark_escalation_notified", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query("UPDATE task_escalations SET notified_at = NOW() WHERE task_id = ? AND due_at = ?")
+                .bind(id)
+                .bind(due_at)
+                .execute(&mut *conn)
+                .await?;
+            Ok(())
+        }))
+        .await
+    }
+
+    // Pending assigned tasks of every user, for telling assignees about them
+    pub async fn assignments(&self) -> Result<Vec<Assignment>, sqlx::Error> {
+        self.timed("assignments", db::retry_on_disconnect(|| async move {
+            let mut conn = self.acquire().await?;
+            sqlx::query_as::<_, Assignment>(concat!(
+                "SELECT ", task_columns!(), ", (SELECT username FROM users WHERE users.id = tasks.assignee_id) AS assignee \
+                 FROM tasks WHERE completed = FALSE AND assignee_id IS NOT NULL ORDER BY id"
+            ))
+            .fetch_all(&mut *conn)
+            .await
+        }))
+        .await
+    }
+
+    // " AND <readable>" for a query being built
+    fn push_readable(&self, query: &mut QueryBuilder<'_, MySql>) {
+        push_access(query, self.access(), false);
+    }
+
+    // Checks out a connection, recording how long the pool made us wait for it
+    async fn acquire(&self) -> Result<PoolConnection<MySql>, sqlx::Error> {
+        let started = Instant::now();
+        let conn = self.pool.acquire().await;
+        self.metrics.record_acquire(started.elapsed());
+        conn
+    }
+
+    // Runs one repository operation, for no longer than `query_timeout`, and records its
+    // duration and outcome, in the metrics and as a debug event in the span of the caller.
+    // Anything but a known read empties the list cache once it is done, whether or not it
+    // succeeded, since it may have changed what a list shows.
+    async fn timed<T>(
+        &self,
+        operation: &'static str,
+        op: impl Future<Output = Result<T, sqlx::Error>>,
+    ) -> Result<T, sqlx::Error> {
+        let started = Instant::now();
+        let result = db::with_timeout(self.timeout, operation, op).await;
+        let elapsed = started.elapsed();
+        if !KEEPS_LISTS.contains(&operation) {
+            self.cache.invalidate();
+        }
+        self.metrics.record_operation(operation, elapsed, result.is_ok());
+        tracing::debug!(operation, elapsed_ms = elapsed.as_millis() as u64, ok = result.is_ok(), "repository call");
+        result
+    }
+}
+
+// Records `action` for the tasks with these ids, with their descriptions as they are now
+async fn record_activity(
+    conn: &mut MySqlConnection,
+    action: Action,
+    ids: &[i32],
+    actor: &str,
+    user: Option<i32>,
+) -> Result<(), sqlx::Error> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let mut query = QueryBuilder::<MySql>::new(
+        "INSERT INTO task_activity (task_id, description, action, actor, user_id, workspace_id) \
+         SELECT id, description, ",
+    );
+    query.push_bind(action).push(", ").push_bind(actor).push(", ").push_bind(user);
+    query.push(", workspace_id FROM tasks WHERE id IN (");
+    push_ids(&mut query, ids);
+    query.build().execute(conn).await?;
+    Ok(())
+}
+
+// Which of the ids belong to a task the user may change, locking those rows until the
+// transaction ends
+async fn lock_existing(conn: &mut MySqlConnection, ids: &[i32], access: Access) -> Result<Vec<i32>, sqlx::Error> {
+    let mut query = QueryBuilder::<MySql>::new("SELECT id FROM tasks WHERE id IN (");
+    push_ids(&mut query, ids);
+    push_access(&mut query, access, true);
+    query.push(" ORDER BY id FOR UPDATE");
+    query.build_query_scalar().fetch_all(conn).await
+}
+
+// `readable!()` or `writable!()` for a query being built, after an AND
+fn push_access(query: &mut QueryBuilder<'_, MySql>, access: Access, write: bool) {
+    let (user, can_write) = (access.user, if write { " AND can_write" } else { "" });
+    query.push(" AND tasks.workspace_id <=> ").push_bind(access.workspace);
+    query.push(" AND (").push_bind(user).push(" IS NULL OR tasks.owner_id = ").push_bind(user);
+    query.push(" OR tasks.assignee_id = ").push_bind(user);
+    query.push(" OR tasks.id IN (SELECT task_id FROM task_shares WHERE user_id = ").push_bind(user);
+    query.push(can_write).push(")");
+    query.push(" OR (tasks.owner_id, tasks.project_id) IN (SELECT owner_id, project_id FROM project_shares WHERE user_id = ");
+    query.push_bind(user).push(can_write).push("))");
+}
+
+// "?, ?, ...)" for an `IN (` list
+fn push_ids(query: &mut QueryBuilder<'_, MySql>, ids: &[i32]) {
+    let mut list = query.separated(", ");
+    for id in ids {
+        list.push_bind(*id);
+    }
+    query.push(")");
+}
+
+// Bulk insert on an existing connection or transaction, for callers that need it to be part of
+// a larger unit of work (e.g. restore wiping the table first). `actor` fills in missing audit
+// names, and `scope` missing owners and workspaces.
+pub async fn insert_tasks(
+    conn: &mut MySqlConnection,
+    tasks: &[NewTask],
+    actor: Option<&str>,
+    scope: Access,
+) -> Result<u64, sqlx::Error> {
+    let mut inserted = 0;
+
+    for chunk in tasks.chunks(BATCH_SIZE) {
+        inserted += insert_query(chunk, actor, scope).build().execute(&mut *conn).await?.rows_affected();
+    }
+
+    Ok(inserted)
+}
+
+// The multi-row INSERT for one chunk of `insert_tasks`
+pub fn insert_query<'a>(chunk: &'a [NewTask], actor: Option<&'a str>, scope: Access) -> QueryBuilder<'a, MySql> {
+    let mut query: QueryBuilder<MySql> = QueryBuilder::new(
+        "INSERT INTO tasks \
+         (id, description, completed, created_at, created_by, updated_by, updated_at, completed_at, due_at, priority, \
+         project_id, owner_id, assignee_id, workspace_id) ",
+    );
+    query.push_values(chunk, |mut row, task| {
+        row.push_bind(task.id)
+            .push_bind(&task.description)
+            .push_bind(task.completed)
+            .push_bind(task.created_at)
+            .push_bind(task.created_by.as_deref().or(actor))
+            .push_bind(task.updated_by.as_deref().or(actor))
+            .push_bind(task.updated_at)
+            .push_bind(task.completed_at())
+            .push_bind(task.due_at)
+            .push_bind(task.priority)
+            .push_bind(task.project_id)
+            .push_bind(task.owner_id.or(scope.user))
+            .push_bind(task.assignee_id)
+            .push_bind(task.workspace_id.or(scope.workspace));
+    });
+    query
+}
+
+// Inserts one chunk of tasks with the multi-row INSERT of `insert_query` and returns their ids,
+// in order. MySQL gives the rows of such a statement consecutive auto-increment ids, each
+// auto_increment_increment apart, from the one LAST_INSERT_ID() reports. Tasks that bring their
+// own ids would throw that off, so a chunk with any goes in one row at a time.
+async fn insert_chunk(
+    conn: &mut MySqlConnection,
+    chunk: &[NewTask],
+    actor: Option<&str>,
+    scope: Access,
+) -> Result<Vec<i32>, sqlx::Error> {
+    if chunk.iter().any(|task| task.id.is_some()) {
+        let mut ids = Vec::with_capacity(chunk.len());
+        for task in chunk {
+            ids.push(insert_task(conn, task, actor, scope).await?);
+        }
+        return Ok(ids);
+    }
+
+    let step: u64 = sqlx::query_scalar("SELECT @@SESSION.auto_increment_increment").fetch_one(&mut *conn).await?;
+    let first = insert_query(chunk, actor, scope).build().execute(&mut *conn).await?.last_insert_id();
+    // Auto-increment ids are INT, so these always fit
+    Ok((0..chunk.len() as u64).map(|row| (first + row * step) as i32).collect())
+}
+
+// Inserts a single task and returns its id, for callers that need the id right away
+pub async fn insert_task(
+    conn: &mut MySqlConnection,
+    task: &NewTask,
+    actor: Option<&str>,
+    scope: Access,
+) -> Result<i32, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO tasks \
+         (id, description, completed, created_at, created_by, updated_by, updated_at, completed_at, due_at, priority, \
+         project_id, owner_id, assignee_id, workspace_id) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(task.id)
+    .bind(&task.description)
+    .bind(task.completed)
+    .bind(task.created_at)
+    .bind(task.created_by.as_deref().or(actor))
+    .bind(task.updated_by.as_deref().or(actor))
+    .bind(task.updated_at)
+    .bind(task.completed_at())
+    .bind(task.due_at)
+    .bind(task.priority)
+    .bind(task.project_id)
+    .bind(task.owner_id.or(scope.user))
+    .bind(task.assignee_id)
+    .bind(task.workspace_id.or(scope.workspace))
+    .execute(&mut *conn)
+    .await?;
+
+    // Auto-increment ids are INT, so this always fits
+    Ok(result.last_insert_id() as i32)
+}
+
+// Adds tags to a task, creating tags that don't exist yet. Tags it already has are left alone.
+pub async fn add_tags(conn: &mut MySqlConnection, task_id: i32, tags: &[String]) -> Result<(), sqlx::Error> {
+    for tag in tags {
+        // LAST_INSERT_ID(id) makes an existing tag report its id as if it had just been inserted
+        let tag_id = sqlx::query("INSERT INTO tags (name) VALUES (?) ON DUPLICATE KEY UPDATE id = LAST_INSERT_ID(id)")
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?
+            .last_insert_id();
+
+        sqlx::query("INSERT IGNORE INTO task_tags (task_id, tag_id) VALUES (?, ?)")
+            .bind(task_id)
+            .bind(tag_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+// Id of the project with this name, if there is one
+async fn find_project(conn: &mut MySqlConnection, name: &str, workspace: Option<i32>) -> Result<Option<i32>, sqlx::Error> {
+    sqlx::query_scalar("SELECT id FROM projects WHERE name = ? AND workspace_id <=> ?")
+        .bind(name)
+        .bind(workspace)
+        .fetch_optional(&mut *conn)
+        .await
+}
+
+// Fails unless the user is an instance admin. Without a user (no accounts yet) it passes, as
+// in require_member_admin.
+async fn require_admin(conn: &mut MySqlConnection, user: Option<i32>) -> Result<(), sqlx::Error> {
+    let Some(user) = user else {
+        return Ok(());
+    };
+    let admin: Option<bool> = sqlx::query_scalar("SELECT is_admin FROM users WHERE id = ?")
+        .bind(user)
+        .fetch_optional(&mut *conn)
+        .await?;
+    if admin != Some(true) {
+        return Err(forbidden(Forbidden::NotAdmin));
+    }
+    Ok(())
+}
+
+// Fails unless the user may manage the members of the workspace. Checked against the workspace
+// named, which need not be the one the repository works in. No user (admin commands) may.
+async fn require_member_admin(
+    conn: &mut MySqlConnection,
+    workspace_id: i32,
+    user: Option<i32>,
+) -> Result<(), sqlx::Error> {
+    let Some(user) = user else {
+        return Ok(());
+    };
+    let role: Option<Role> = sqlx::query_scalar("SELECT role FROM workspace_members WHERE workspace_id = ? AND user_id = ?")
+        .bind(workspace_id)
+        .bind(user)
+        .fetch_optional(&mut *conn)
+        .await?;
+    match role {
+        Some(role) if role.allows(Permission::ManageMembers) => Ok(()),
+        _ => Err(forbidden(Forbidden::Role(Permission::ManageMembers))),
+    }
+}
+
+// Id of the project with this name, creating it if needed
+pub async fn ensure_project(conn: &mut MySqlConnection, name: &str, workspace: Option<i32>) -> Result<i32, sqlx::Error> {
+    let id = sqlx::query(
+        "INSERT INTO projects (name, workspace_id) VALUES (?, ?) ON DUPLICATE KEY UPDATE id = LAST_INSERT_ID(id)",
+    )
+    .bind(name)
+    .bind(workspace)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_id();
+    Ok(id as i32)
+}
+
+// Appends checklist items to a task, after any it already has
+pub async fn add_checklist(conn: &mut MySqlConnection, task_id: i32, items: &[ChecklistItem]) -> Result<(), sqlx::Error> {
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    let next: i64 = sqlx::query_scalar("SELECT COALESCE(MAX(position) + 1, 0) FROM checklist_items WHERE task_id = ?")
+        .bind(task_id)
+        .fetch_one(&mut *conn)
+        .await?;
+    let start = next as i32;
+
+    let mut query: QueryBuilder<MySql> = QueryBuilder::new("INSERT INTO checklist_items (task_id, position, text, done) ");
+    query.push_values(items.iter().zip(start..), |mut row, (item, position)| {
+        row.push_bind(task_id).push_bind(position).push_bind(&item.text).push_bind(item.done);
+    });
+    query.build().execute(&mut *conn).await?;
+    Ok(())
+}
+
+pub async fn add_note(conn: &mut MySqlConnection, task_id: i32, note: &Note) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO task_notes (task_id, created_at, body, created_by) VALUES (?, ?, ?, ?)")
+        .bind(task_id)
+        .bind(note.created_at)
+        .bind(&note.body)
+        .bind(&note.created_by)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+pub async fn link_external_id(conn: &mut MySqlConnection, task_id: i32, external: &ExternalId) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO external_ids (task_id, source, external_id, synced_at) VALUES (?, ?, ?, NOW())")
+        .bind(task_id)
+        .bind(external.source)
+        .bind(&external.id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+// Ends the running timer of `user`
+async fn stop_timers(conn: &mut MySqlConnection, user: Option<i32>) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("UPDATE time_entries SET ended_at = NOW() WHERE user_id <=> ? AND ended_at IS NULL")
+        .bind(user)
+        .execute(&mut *conn)
+        .await?;
+    Ok(result.rows_affected())
+}
+
+// Trims the look-ahead row off a (created_at DESC, id DESC) result and derives the next cursor
+fn recency_page(mut tasks: Vec<Task>, limit: u32) -> Page<ListCursor> {
+    let next = if tasks.len() > limit as usize {
+        tasks.truncate(limit as usize);
+        tasks.last().map(|task| ListCursor { created_at: task.created_at, id: task.id })
+    } else {
+        None
+    };
+
+    Page { tasks, next }
+}
+
+// Escapes LIKE wildcards so the query is matched literally
+fn escape_like(query: &str) -> String {
+    let mut escaped = String::with_capacity(query.len());
+    for c in query.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
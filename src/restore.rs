@@ -0,0 +1,536 @@
+use std::io::{self, Write};
+
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use sqlx::query_builder::Separated;
+use sqlx::{MySql, MySqlConnection, MySqlPool, QueryBuilder};
+
+use crate::backup::{
+    self, ActivityRow, CaldavCollectionRow, CaldavResourceRow, ChecklistRow, EscalationRow, EstimateRow, ExternalIdRow,
+    InvitationRow, MentionRow, NoteRow, OccurrenceRow, Project, ProjectShareRow, RecurrenceRow, SentNotificationRow,
+    SmsAlertRow, Tag, TaskShareRow, TaskTag, TimeEntryRow, UserIdentityRow, UserRow, UserSettingRow, WorkspaceMemberRow,
+    WorkspaceRow, BACKUP_FORMAT, BACKUP_VERSION,
+};
+use crate::cli::RestoreArgs;
+use crate::config::Config;
+use crate::db;
+use crate::error::{Result, TaskError};
+use crate::input::confirm;
+use crate::priority::Priority;
+use crate::repository::{self, Access, NewTask, BATCH_SIZE};
+use crate::s3;
+use crate::secrets::{self, Service};
+
+#[derive(Debug, Deserialize)]
+struct Backup {
+    format: String,
+    version: u32,
+    tables: Tables,
+}
+
+// Tables added after version 1 are missing from older files
+#[derive(Debug, Deserialize)]
+struct Tables {
+    #[serde(default)]
+    users: Vec<UserRow>,
+    #[serde(default)]
+    user_identities: Vec<UserIdentityRow>,
+    #[serde(default)]
+    user_settings: Vec<UserSettingRow>,
+    #[serde(default)]
+    workspaces: Vec<WorkspaceRow>,
+    #[serde(default)]
+    workspace_members: Vec<WorkspaceMemberRow>,
+    #[serde(default)]
+    workspace_invitations: Vec<InvitationRow>,
+    #[serde(default)]
+    projects: Vec<Project>,
+    tasks: Vec<BackupTask>,
+    #[serde(default)]
+    tags: Vec<Tag>,
+    #[serde(default)]
+    task_tags: Vec<TaskTag>,
+    #[serde(default)]
+    checklist_items: Vec<ChecklistRow>,
+    #[serde(default)]
+    task_notes: Vec<NoteRow>,
+    #[serde(default)]
+    task_mentions: Vec<MentionRow>,
+    #[serde(default)]
+    notifications_sent: Vec<SentNotificationRow>,
+    #[serde(default)]
+    external_ids: Vec<ExternalIdRow>,
+    #[serde(default)]
+    caldav_resources: Vec<CaldavResourceRow>,
+    #[serde(default)]
+    caldav_collections: Vec<CaldavCollectionRow>,
+    #[serde(default)]
+    task_shares: Vec<TaskShareRow>,
+    #[serde(default)]
+    project_shares: Vec<ProjectShareRow>,
+    #[serde(default)]
+    time_entries: Vec<TimeEntryRow>,
+    #[serde(default)]
+    task_estimates: Vec<EstimateRow>,
+    #[serde(default)]
+    task_recurrences: Vec<RecurrenceRow>,
+    #[serde(default)]
+    task_occurrences: Vec<OccurrenceRow>,
+    #[serde(default)]
+    task_escalations: Vec<EscalationRow>,
+    #[serde(default)]
+    task_activity: Vec<ActivityRow>,
+    #[serde(default)]
+    task_sms_alerts: Vec<SmsAlertRow>,
+}
+
+// A task as stored in the backup. Columns added after version 1 are optional.
+#[derive(Debug, Deserialize)]
+struct BackupTask {
+    id: i32,
+    description: String,
+    completed: bool,
+    created_at: NaiveDateTime,
+    #[serde(default)]
+    created_by: Option<String>,
+    #[serde(default)]
+    updated_by: Option<String>,
+    #[serde(default)]
+    updated_at: Option<NaiveDateTime>,
+    // Missing before version 20; completed tasks then count as completed at updated_at
+    #[serde(default)]
+    completed_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    due_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    priority: Option<Priority>,
+    #[serde(default)]
+    project_id: Option<i32>,
+    #[serde(default)]
+    owner_id: Option<i32>,
+    #[serde(default)]
+    assignee_id: Option<i32>,
+    #[serde(default)]
+    workspace_id: Option<i32>,
+}
+
+impl From<BackupTask> for NewTask {
+    fn from(task: BackupTask) -> Self {
+        NewTask {
+            id: Some(task.id),
+            description: task.description,
+            completed: task.completed,
+            created_at: task.created_at,
+            created_by: task.created_by,
+            updated_by: task.updated_by,
+            updated_at: task.updated_at.unwrap_or(task.created_at),
+            completed_at: task.completed_at,
+            due_at: task.due_at,
+            priority: task.priority,
+            project_id: task.project_id,
+            owner_id: task.owner_id,
+            assignee_id: task.assignee_id,
+            workspace_id: task.workspace_id,
+        }
+    }
+}
+
+pub async fn run(pool: &MySqlPool, config: &Config, args: RestoreArgs) -> Result<()> {
+    let retry = &config.retry;
+    let mut data = match args.file.to_str().filter(|file| file.starts_with("s3://")) {
+        Some(url) => {
+            let location = s3::Location::parse(url)?;
+            s3::Client::new(config)?.get(&location.bucket, &location.prefix).await?
+        }
+        None => std::fs::read(&args.file).map_err(|e| {
+            TaskError::InvalidInput(format!("Could not open {}: {}", args.file.display(), e))
+        })?,
+    };
+    // Backups uploaded with `task backup --to` are encrypted
+    if backup::is_encrypted(&data) {
+        data = backup::decrypt(&secrets::token(config, Service::Backup)?, &data)?;
+    }
+    let backup: Backup = serde_json::from_slice(&data)
+        .map_err(|e| TaskError::parse(&args.file, "a valid backup file", e))?;
+
+    if backup.format != BACKUP_FORMAT {
+        return Err(TaskError::InvalidInput(format!(
+            "{} is not a task backup (format '{}').",
+            args.file.display(),
+            backup.format
+        )));
+    }
+    if backup.version > BACKUP_VERSION {
+        return Err(TaskError::InvalidInput(format!(
+            "Backup version {} is newer than this binary supports (version {}). Please upgrade first.",
+            backup.version, BACKUP_VERSION
+        )));
+    }
+
+    let Tables {
+        users,
+        user_identities,
+        user_settings,
+        workspaces,
+        workspace_members,
+        workspace_invitations,
+        projects,
+        tasks,
+        tags,
+        task_tags,
+        checklist_items,
+        task_notes,
+        task_mentions,
+        notifications_sent,
+        external_ids,
+        caldav_resources,
+        caldav_collections,
+        task_shares,
+        project_shares,
+        time_entries,
+        task_estimates,
+        task_recurrences,
+        task_occurrences,
+        task_escalations,
+        task_activity,
+        task_sms_alerts,
+    } = backup.tables;
+    let tasks: Vec<NewTask> = tasks.into_iter().map(NewTask::from).collect();
+
+    if args.wipe && !args.yes {
+        let existing: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM tasks")
+            .fetch_one(pool)
+            .await?;
+        if !confirm(&format!(
+            "This will delete all {} existing tasks before restoring. Continue? [y/N] ",
+            existing
+        )) {
+            println!("Restore cancelled.");
+            return Ok(());
+        }
+    }
+
+    // Everything happens in one transaction, so a failed restore leaves the database untouched.
+    // A deadlock or lock timeout rolls it back completely and it is started over.
+    let total = tasks.len();
+    let (users, projects, tasks, tags, task_tags) = (&users, &projects, &tasks, &tags, &task_tags);
+    let (checklist_items, task_notes, task_mentions) = (&checklist_items, &task_notes, &task_mentions);
+    let (notifications_sent, external_ids) = (&notifications_sent, &external_ids);
+    let (caldav_resources, caldav_collections) = (&caldav_resources, &caldav_collections);
+    let (task_shares, project_shares) = (&task_shares, &project_shares);
+    let (time_entries, task_estimates) = (&time_entries, &task_estimates);
+    let (task_recurrences, task_occurrences) = (&task_recurrences, &task_occurrences);
+    let (task_escalations, task_activity, task_sms_alerts) = (&task_escalations, &task_activity, &task_sms_alerts);
+    let (workspaces, workspace_members) = (&workspaces, &workspace_members);
+    let workspace_invitations = &workspace_invitations;
+    let (user_identities, user_settings) = (&user_identities, &user_settings);
+    let wipe = args.wipe;
+
+    db::retry_lock_conflicts(retry, || async move {
+        let mut tx = pool.begin().await?;
+
+        if wipe {
+            // Links to the deleted rows go with them (ON DELETE CASCADE)
+            sqlx::query("DELETE FROM tasks").execute(&mut *tx).await?;
+            sqlx::query("DELETE FROM tags").execute(&mut *tx).await?;
+            sqlx::query("DELETE FROM projects").execute(&mut *tx).await?;
+            // Sync state outlives deleted tasks, so it has to go separately
+            sqlx::query("DELETE FROM caldav_resources").execute(&mut *tx).await?;
+            sqlx::query("DELETE FROM caldav_collections").execute(&mut *tx).await?;
+            // And so does what happened to them
+            sqlx::query("DELETE FROM task_activity").execute(&mut *tx).await?;
+            // Once their tasks are gone; logins, identities, settings and memberships go with them
+            sqlx::query("DELETE FROM users").execute(&mut *tx).await?;
+            sqlx::query("DELETE FROM workspaces").execute(&mut *tx).await?;
+        }
+
+        // Tasks refer to users, workspaces and projects, everything else refers to tasks
+        insert_rows(
+            &mut tx,
+            "INSERT INTO users (id, username, password_hash, created_at, is_admin, disabled_at) ",
+            users,
+            |mut row, user| {
+                row.push_bind(user.id)
+                    .push_bind(&user.username)
+                    .push_bind(&user.password_hash)
+                    .push_bind(user.created_at)
+                    .push_bind(user.is_admin)
+                    .push_bind(user.disabled_at);
+            },
+        )
+        .await?;
+        // As the migration that added instance admins did
+        if !users.is_empty() && !users.iter().any(|user| user.is_admin) {
+            sqlx::query("UPDATE users SET is_admin = TRUE ORDER BY id LIMIT 1").execute(&mut *tx).await?;
+        }
+        insert_rows(
+            &mut tx,
+            "INSERT INTO user_identities (issuer, subject, user_id, created_at) ",
+            user_identities,
+            |mut row, identity| {
+                row.push_bind(&identity.issuer)
+                    .push_bind(&identity.subject)
+                    .push_bind(identity.user_id)
+                    .push_bind(identity.created_at);
+            },
+        )
+        .await?;
+        insert_rows(&mut tx, "INSERT INTO user_settings (user_id, name, value) ", user_settings, |mut row, setting| {
+            row.push_bind(setting.user_id).push_bind(&setting.name).push_bind(&setting.value);
+        })
+        .await?;
+        insert_rows(&mut tx, "INSERT INTO workspaces (id, name, created_at) ", workspaces, |mut row, workspace| {
+            row.push_bind(workspace.id).push_bind(&workspace.name).push_bind(workspace.created_at);
+        })
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO workspace_members (workspace_id, user_id, created_at, role) ",
+            workspace_members,
+            |mut row, member| {
+                row.push_bind(member.workspace_id)
+                    .push_bind(member.user_id)
+                    .push_bind(member.created_at)
+                    .push_bind(&member.role);
+            },
+        )
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO workspace_invitations \
+             (id, workspace_id, code_hash, role, created_by, created_at, expires_at, redeemed_at, redeemed_by) ",
+            workspace_invitations,
+            |mut row, invitation| {
+                row.push_bind(invitation.id)
+                    .push_bind(invitation.workspace_id)
+                    .push_bind(&invitation.code_hash)
+                    .push_bind(&invitation.role)
+                    .push_bind(invitation.created_by)
+                    .push_bind(invitation.created_at)
+                    .push_bind(invitation.expires_at)
+                    .push_bind(invitation.redeemed_at)
+                    .push_bind(invitation.redeemed_by);
+            },
+        )
+        .await?;
+        insert_rows(&mut tx, "INSERT INTO projects (id, name, workspace_id) ", projects, |mut row, project| {
+            row.push_bind(project.id).push_bind(&project.name).push_bind(project.workspace_id);
+        })
+        .await?;
+
+        let mut restored = 0;
+        for chunk in tasks.chunks(BATCH_SIZE) {
+            restored += repository::insert_tasks(&mut tx, chunk, None, Access::default()).await?;
+
+            print!("\rRestored {}/{} tasks", restored, total);
+            let _ = io::stdout().flush();
+        }
+        if total > 0 {
+            println!();
+        }
+
+        insert_rows(&mut tx, "INSERT INTO tags (id, name) ", tags, |mut row, tag| {
+            row.push_bind(tag.id).push_bind(&tag.name);
+        })
+        .await?;
+        insert_rows(&mut tx, "INSERT INTO task_tags (task_id, tag_id) ", task_tags, |mut row, link| {
+            row.push_bind(link.task_id).push_bind(link.tag_id);
+        })
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO checklist_items (id, task_id, position, text, done) ",
+            checklist_items,
+            |mut row, item| {
+                row.push_bind(item.id)
+                    .push_bind(item.task_id)
+                    .push_bind(item.position)
+                    .push_bind(&item.text)
+                    .push_bind(item.done);
+            },
+        )
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO task_notes (id, task_id, created_at, body, created_by) ",
+            task_notes,
+            |mut row, note| {
+                row.push_bind(note.id)
+                    .push_bind(note.task_id)
+                    .push_bind(note.created_at)
+                    .push_bind(&note.body)
+                    .push_bind(&note.created_by);
+            },
+        )
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO task_mentions (note_id, user_id, created_at) ",
+            task_mentions,
+            |mut row, mention| {
+                row.push_bind(mention.note_id).push_bind(mention.user_id).push_bind(mention.created_at);
+            },
+        )
+        .await?;
+        // Kept through a wipe, as nothing refers to it; what was already recorded stays as it is
+        insert_rows(
+            &mut tx,
+            "INSERT IGNORE INTO notifications_sent (channel, kind, subject, sent_at) ",
+            notifications_sent,
+            |mut row, sent| {
+                row.push_bind(&sent.channel).push_bind(&sent.kind).push_bind(&sent.subject).push_bind(sent.sent_at);
+            },
+        )
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO external_ids (task_id, source, external_id, synced_at) ",
+            external_ids,
+            |mut row, link| {
+                row.push_bind(link.task_id)
+                    .push_bind(&link.source)
+                    .push_bind(&link.external_id)
+                    .push_bind(link.synced_at);
+            },
+        )
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO caldav_resources (href, uid, task_id, etag, synced_at) ",
+            caldav_resources,
+            |mut row, resource| {
+                row.push_bind(&resource.href)
+                    .push_bind(&resource.uid)
+                    .push_bind(resource.task_id)
+                    .push_bind(&resource.etag)
+                    .push_bind(resource.synced_at);
+            },
+        )
+        .await?;
+        insert_rows(&mut tx, "INSERT INTO caldav_collections (url, ctag) ", caldav_collections, |mut row, collection| {
+            row.push_bind(&collection.url).push_bind(&collection.ctag);
+        })
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO task_shares (task_id, user_id, can_write, created_at) ",
+            task_shares,
+            |mut row, share| {
+                row.push_bind(share.task_id)
+                    .push_bind(share.user_id)
+                    .push_bind(share.can_write)
+                    .push_bind(share.created_at);
+            },
+        )
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO project_shares (owner_id, project_id, user_id, can_write, created_at) ",
+            project_shares,
+            |mut row, share| {
+                row.push_bind(share.owner_id)
+                    .push_bind(share.project_id)
+                    .push_bind(share.user_id)
+                    .push_bind(share.can_write)
+                    .push_bind(share.created_at);
+            },
+        )
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO time_entries (id, task_id, user_id, started_at, ended_at) ",
+            time_entries,
+            |mut row, entry| {
+                row.push_bind(entry.id)
+                    .push_bind(entry.task_id)
+                    .push_bind(entry.user_id)
+                    .push_bind(entry.started_at)
+                    .push_bind(entry.ended_at);
+            },
+        )
+        .await?;
+        insert_rows(&mut tx, "INSERT INTO task_estimates (task_id, minutes) ", task_estimates, |mut row, estimate| {
+            row.push_bind(estimate.task_id).push_bind(estimate.minutes);
+        })
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO task_recurrences (task_id, every, created_at) ",
+            task_recurrences,
+            |mut row, recurrence| {
+                row.push_bind(recurrence.task_id).push_bind(&recurrence.every).push_bind(recurrence.created_at);
+            },
+        )
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO task_occurrences (recurring_id, due_at, task_id, created_at) ",
+            task_occurrences,
+            |mut row, occurrence| {
+                row.push_bind(occurrence.recurring_id)
+                    .push_bind(occurrence.due_at)
+                    .push_bind(occurrence.task_id)
+                    .push_bind(occurrence.created_at);
+            },
+        )
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO task_escalations \
+             (task_id, due_at, escalated_at, days_overdue, old_priority, new_priority, channel, notified_at) ",
+            task_escalations,
+            |mut row, escalation| {
+                row.push_bind(escalation.task_id)
+                    .push_bind(escalation.due_at)
+                    .push_bind(escalation.escalated_at)
+                    .push_bind(escalation.days_overdue)
+                    .push_bind(escalation.old_priority)
+                    .push_bind(escalation.new_priority)
+                    .push_bind(&escalation.channel)
+                    .push_bind(escalation.notified_at);
+            },
+        )
+        .await?;
+        insert_rows(
+            &mut tx,
+            "INSERT INTO task_activity (id, task_id, description, action, actor, user_id, workspace_id, occurred_at) ",
+            task_activity,
+            |mut row, activity| {
+                row.push_bind(activity.id)
+                    .push_bind(activity.task_id)
+                    .push_bind(&activity.description)
+                    .push_bind(&activity.action)
+                    .push_bind(&activity.actor)
+                    .push_bind(activity.user_id)
+                    .push_bind(activity.workspace_id)
+                    .push_bind(activity.occurred_at);
+            },
+        )
+        .await?;
+        insert_rows(&mut tx, "INSERT INTO task_sms_alerts (task_id, created_at) ", task_sms_alerts, |mut row, alert| {
+            row.push_bind(alert.task_id).push_bind(alert.created_at);
+        })
+        .await?;
+
+        tx.commit().await
+    })
+    .await?;
+
+    println!("Restore of {} completed ({} tasks).", args.file.display(), total);
+    Ok(())
+}
+
+// Multi-row inserts of backup rows, which keep their original ids so references stay intact
+async fn insert_rows<'a, T>(
+    conn: &mut MySqlConnection,
+    insert: &str,
+    rows: &'a [T],
+    mut push: impl FnMut(Separated<'_, 'a, MySql, &'static str>, &'a T),
+) -> std::result::Result<(), sqlx::Error> {
+    for chunk in rows.chunks(BATCH_SIZE) {
+        let mut query: QueryBuilder<'a, MySql> = QueryBuilder::new(insert);
+        query.push_values(chunk, &mut push);
+        query.build().execute(&mut *conn).await?;
+    }
+    Ok(())
+}
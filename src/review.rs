@@ -0,0 +1,277 @@
+// `task review`: the weekly look back and ahead. What got done in the last seven days, what is
+// past its due date, what is due in the coming seven and what nobody has touched in a while,
+// as text for the terminal or as markdown to keep with other notes.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use chrono::{Days, Local, NaiveDateTime, NaiveTime};
+
+use crate::aging::{self, Ages};
+use crate::cli::ReviewArgs;
+use crate::error::Result;
+use crate::export;
+use crate::repository::{readable, task_columns, BindAccess, TaskRepository};
+use crate::stats::format_age;
+use crate::Task;
+
+// Length of the week looked back on and ahead to
+const DAYS: u64 = 7;
+// Open tasks unchanged for this long count as stale
+const STALE_DAYS: u32 = 30;
+
+#[derive(sqlx::FromRow)]
+struct CompletedTask {
+    #[sqlx(flatten)]
+    task: Task,
+    done_at: NaiveDateTime,
+}
+
+// What the review shows, as read from the database
+struct Review {
+    from: NaiveDateTime,
+    now: NaiveDateTime,
+    completed: Vec<CompletedTask>,
+    // Open and past due, the longest overdue first
+    slipped: Vec<Task>,
+    due_next: Vec<Task>,
+    // Open, not overdue and unchanged for `stale_days`, the longest first
+    stale: Vec<Task>,
+    stale_days: u32,
+    projects: HashMap<i32, String>,
+    // How old the open tasks are now and were a week ago
+    age: Option<Ages>,
+    age_week_ago: Option<Ages>,
+}
+
+pub async fn run(repo: &TaskRepository, args: ReviewArgs) -> Result<()> {
+    let (pool, access) = (repo.pool(), repo.access());
+    let now = Local::now().naive_local();
+    let from = (now.date() - Days::new(DAYS)).and_time(NaiveTime::MIN);
+    let stale_days = args.stale_days.unwrap_or(STALE_DAYS);
+    let stale_since = now - chrono::Duration::days(i64::from(stale_days));
+    let projects = export::load_project_names(pool).await?;
+
+    let completed = sqlx::query_as::<_, CompletedTask>(concat!(
+        "SELECT ", task_columns!(), ", COALESCE(completed_at, updated_at) AS done_at FROM tasks ",
+        "WHERE completed = TRUE AND COALESCE(completed_at, updated_at) >= ? AND ", readable!(), " ",
+        "ORDER BY done_at, id"
+    ))
+    .bind(from)
+    .bind_access(access)
+    .fetch_all(pool)
+    .await?;
+    let slipped = sqlx::query_as::<_, Task>(concat!(
+        "SELECT ", task_columns!(), " FROM tasks WHERE completed = FALSE AND due_at < ? AND ", readable!(), " ",
+        "ORDER BY due_at, id"
+    ))
+    .bind(now)
+    .bind_access(access)
+    .fetch_all(pool)
+    .await?;
+    let due_next = sqlx::query_as::<_, Task>(concat!(
+        "SELECT ", task_columns!(), " FROM tasks WHERE completed = FALSE AND due_at >= ? AND due_at < ? AND ",
+        readable!(), " ORDER BY due_at, id"
+    ))
+    .bind(now)
+    .bind(now + Days::new(DAYS))
+    .bind_access(access)
+    .fetch_all(pool)
+    .await?;
+    let stale = sqlx::query_as::<_, Task>(concat!(
+        "SELECT ", task_columns!(), " FROM tasks WHERE completed = FALSE AND updated_at < ? ",
+        "AND (due_at IS NULL OR due_at >= ?) AND ", readable!(), " ORDER BY updated_at, id"
+    ))
+    .bind(stale_since)
+    .bind(now)
+    .bind_access(access)
+    .fetch_all(pool)
+    .await?;
+
+    let week_ago = now - chrono::Duration::days(DAYS as i64);
+    let spans = repo.open_spans(week_ago).await?;
+    let (age, age_week_ago) = (aging::ages_at(&spans, now), aging::ages_at(&spans, week_ago));
+
+    let review =
+        Review { from, now, completed, slipped, due_next, stale, stale_days, projects, age, age_week_ago };
+    // A file is for keeping, so it gets markdown whether asked for or not
+    let text = if args.markdown || args.output.is_some() { markdown(&review) } else { text(&review) };
+    let mut out = export::open_output(args.output.as_deref())?;
+    out.write_all(text.as_bytes())?;
+    out.flush()?;
+    drop(out);
+    if let Some(path) = &args.output {
+        println!("Wrote the review of {} to {}", now.format("%Y-%m-%d"), path.display());
+    }
+    Ok(())
+}
+
+// One line of a section: its date, the task and what else there is to know about it
+struct Row<'a> {
+    date: String,
+    description: &'a str,
+    // Project and priority, e.g. "Admin, high"
+    detail: String,
+}
+
+// The sections in the order they are shown, with their rows
+fn sections(review: &Review) -> Vec<(String, Vec<Row<'_>>)> {
+    let projects = &review.projects;
+    let due = |task: &Task| task.due_at.unwrap_or(task.created_at);
+    vec![
+        (
+            "Completed".to_string(),
+            review.completed.iter().map(|done| row(&done.done_at, &done.task, projects)).collect(),
+        ),
+        ("Slipped".to_string(), review.slipped.iter().map(|task| row(&due(task), task, projects)).collect()),
+        ("Due Next Week".to_string(), review.due_next.iter().map(|task| row(&due(task), task, projects)).collect()),
+        (
+            format!("Unchanged for {}+ Days", review.stale_days),
+            review.stale.iter().map(|task| row(&task.updated_at, task, projects)).collect(),
+        ),
+    ]
+}
+
+// E.g. "5, 12 d 4 h old on average (median 5 d 2 h, 90% under 40 d 1 h; a week ago 9 d 3 h)"
+fn age_summary(review: &Review) -> String {
+    let Some(age) = review.age else {
+        return "none".to_string();
+    };
+    let week_ago = match review.age_week_ago {
+        Some(then) => format_age(then.average_secs),
+        None => "none open".to_string(),
+    };
+    format!(
+        "{}, {} old on average (median {}, 90% under {}; a week ago {})",
+        age.open,
+        format_age(age.average_secs),
+        format_age(age.median_secs),
+        format_age(age.p90_secs),
+        week_ago
+    )
+}
+
+fn row<'a>(date: &NaiveDateTime, task: &'a Task, projects: &HashMap<i32, String>) -> Row<'a> {
+    let project = task.project_id.and_then(|id| projects.get(&id)).map(String::as_str);
+    let priority = task.priority.map(|priority| priority.as_str());
+    let detail: Vec<&str> = [project, priority].into_iter().flatten().collect();
+    Row { date: date.format("%Y-%m-%d").to_string(), description: &task.description, detail: detail.join(", ") }
+}
+
+fn text(review: &Review) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "\n--- Weekly Review, {} to {} ---",
+        review.from.format("%Y-%m-%d"),
+        review.now.format("%Y-%m-%d")
+    );
+    let _ = writeln!(out, "\nOpen tasks: {}", age_summary(review));
+    for (title, rows) in sections(review) {
+        let _ = writeln!(out, "\n--- {} ({}) ---", title, rows.len());
+        if rows.is_empty() {
+            out.push_str("None.\n");
+        }
+        for row in rows {
+            let detail = if row.detail.is_empty() { String::new() } else { format!(" ({})", row.detail) };
+            let _ = writeln!(out, "{}  {}{}", row.date, row.description, detail);
+        }
+    }
+    out
+}
+
+fn markdown(review: &Review) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# Weekly review\n\n{} to {}",
+        review.from.format("%Y-%m-%d"),
+        review.now.format("%Y-%m-%d")
+    );
+    let _ = writeln!(out, "\n**Open tasks:** {}", age_summary(review));
+    for (title, rows) in sections(review) {
+        let _ = writeln!(out, "\n## {} ({})\n", title, rows.len());
+        if rows.is_empty() {
+            out.push_str("_None._\n");
+        }
+        for row in rows {
+            let detail = if row.detail.is_empty() { String::new() } else { format!(" _({})_", row.detail) };
+            let _ = writeln!(out, "- {} {}{}", row.date, escape_markdown(row.description), detail);
+        }
+    }
+    out
+}
+
+// Backslashes the characters that would otherwise turn parts of a description into markup
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(if c == '\n' { ' ' } else { c });
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{at, at_time, task, LONG, MARKUP, UNICODE};
+    use crate::priority::Priority;
+
+    // A week with something in every section
+    fn week() -> Review {
+        let mut done = task(1, MARKUP, 4);
+        done.completed = true;
+        let mut slipped = task(2, "Renew the passport", 1);
+        slipped.due_at = Some(at(8));
+        slipped.priority = Some(Priority::High);
+        slipped.project_id = Some(1);
+        let mut due = task(3, UNICODE, 2);
+        due.due_at = Some(at_time(12, 0, 0));
+        due.project_id = Some(2);
+
+        Review {
+            from: at_time(3, 0, 0),
+            now: at_time(10, 8, 15),
+            completed: vec![CompletedTask { task: done, done_at: at_time(9, 18, 30) }],
+            slipped: vec![slipped],
+            due_next: vec![due],
+            stale: vec![task(4, LONG, 1)],
+            stale_days: 7,
+            projects: HashMap::from([(1, "Admin".to_string()), (2, "Reisen ✈".to_string())]),
+            age: Some(Ages { open: 3, average_secs: 800_000, median_secs: 190_000, p90_secs: 2_000_000 }),
+            age_week_ago: None,
+        }
+    }
+
+    #[test]
+    fn text_snapshot() {
+        insta::assert_snapshot!(text(&week()));
+    }
+
+    #[test]
+    fn markdown_snapshot() {
+        insta::assert_snapshot!(markdown(&week()));
+    }
+
+    #[test]
+    fn an_empty_week_says_so() {
+        let review = Review {
+            completed: Vec::new(),
+            slipped: Vec::new(),
+            due_next: Vec::new(),
+            stale: Vec::new(),
+            ..week()
+        };
+        assert_eq!(text(&review).matches("None.").count(), 4);
+        assert!(markdown(&review).contains("## Due Next Week (0)\n\n_None._"));
+    }
+
+    #[test]
+    fn markup_in_descriptions_stays_text() {
+        assert_eq!(escape_markdown("Fix *all* the [links] #1"), "Fix \\*all\\* the \\[links\\] \\#1");
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt;
+use std::str::FromStr;
+
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::mysql::{MySqlTypeInfo, MySqlValueRef};
+use sqlx::{Decode, Encode, MySql, Type};
+
+// A member's role in a workspace. The personal space has no roles: there the user may do
+// everything to the tasks they can write to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Admin,
+    Member,
+    Viewer,
+}
+
+// What a role may or may not do, checked by the repository before it changes anything
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    EditTasks,
+    DeleteTasks,
+    ManageProjects,
+    ManageMembers,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Member => "member",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    pub fn allows(self, permission: Permission) -> bool {
+        match self {
+            Role::Admin => true,
+            Role::Member => permission == Permission::EditTasks,
+            Role::Viewer => false,
+        }
+    }
+}
+
+impl Permission {
+    fn describe(self) -> &'static str {
+        match self {
+            Permission::EditTasks => "changing tasks",
+            Permission::DeleteTasks => "deleting tasks",
+            Permission::ManageProjects => "creating or sharing projects",
+            Permission::ManageMembers => "managing members",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "admin" => Ok(Role::Admin),
+            "member" => Ok(Role::Member),
+            "viewer" => Ok(Role::Viewer),
+            _ => Err(format!("unknown role '{}' (expected admin, member or viewer)", s.trim())),
+        }
+    }
+}
+
+// Stored in an ENUM('admin', 'member', 'viewer') column, which MySQL sends as a string
+impl Type<MySql> for Role {
+    fn type_info() -> MySqlTypeInfo {
+        <str as Type<MySql>>::type_info()
+    }
+
+    fn compatible(ty: &MySqlTypeInfo) -> bool {
+        <str as Type<MySql>>::compatible(ty)
+    }
+}
+
+impl Encode<'_, MySql> for Role {
+    fn encode_by_ref(&self, buf: &mut Vec<u8>) -> IsNull {
+        <&str as Encode<MySql>>::encode(self.as_str(), buf)
+    }
+}
+
+impl<'r> Decode<'r, MySql> for Role {
+    fn decode(value: MySqlValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(<&str as Decode<MySql>>::decode(value)?.parse()?)
+    }
+}
+
+// A denied permission: by the user's role, a read-only API token, a disabled account, or a
+// user who isn't an instance admin (`task admin`). Repository methods fail with sqlx errors, so
+// it travels boxed inside one (see `forbidden`) and `TaskError::from` turns it back into
+// `TaskError::Forbidden`.
+#[derive(Debug)]
+pub enum Forbidden {
+    Role(Permission),
+    ReadOnly,
+    Disabled,
+    NotAdmin,
+}
+
+impl fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Forbidden::Role(permission) => {
+                write!(f, "Your role in this workspace doesn't allow {}.", permission.describe())
+            }
+            Forbidden::ReadOnly => write!(f, "This API token is read-only."),
+            Forbidden::Disabled => write!(f, "This account is disabled; ask an admin to enable it."),
+            Forbidden::NotAdmin => write!(f, "Only instance admins may do that."),
+        }
+    }
+}
+
+impl std::error::Error for Forbidden {}
+
+pub fn forbidden(reason: Forbidden) -> sqlx::Error {
+    sqlx::Error::AnyDriverError(Box::new(reason))
+}
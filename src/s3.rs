@@ -0,0 +1,227 @@
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::secrets::{self, Service};
+use crate::sync::{http_client, remote_error, send};
+
+const SERVICE: &str = "S3";
+const DEFAULT_REGION: &str = "us-east-1";
+
+// A bucket and key prefix, from "s3://bucket/some/prefix"
+#[derive(Debug, Clone)]
+pub struct Location {
+    pub bucket: String,
+    pub prefix: String,
+}
+
+impl Location {
+    pub fn parse(url: &str) -> Result<Self> {
+        let rest = url.strip_prefix("s3://").ok_or_else(|| {
+            TaskError::InvalidInput(format!("'{}' is not an S3 URL; expected s3://bucket/path.", url))
+        })?;
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(TaskError::InvalidInput(format!("'{}' names no bucket; expected s3://bucket/path.", url)));
+        }
+        Ok(Location { bucket: bucket.to_string(), prefix: prefix.trim_matches('/').to_string() })
+    }
+
+    // Key of an object named `name` under the prefix
+    pub fn key(&self, name: &str) -> String {
+        if self.prefix.is_empty() { name.to_string() } else { format!("{}/{}", self.prefix, name) }
+    }
+}
+
+// The few S3 calls backups need, signed with AWS Signature Version 4. Buckets are addressed by
+// path (https://endpoint/bucket/key), which AWS and MinIO both accept.
+pub struct Client {
+    http: reqwest::Client,
+    endpoint: reqwest::Url,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+}
+
+impl Client {
+    pub fn new(config: &Config) -> Result<Self> {
+        let s3 = &config.s3;
+        let region = s3.region.clone().unwrap_or_else(|| DEFAULT_REGION.to_string());
+        let endpoint = s3.endpoint.clone().unwrap_or_else(|| format!("https://s3.{}.amazonaws.com", region));
+        let endpoint = reqwest::Url::parse(&endpoint)
+            .map_err(|e| TaskError::Config(format!("Invalid [s3] endpoint '{}': {}", endpoint, e)))?;
+        let access_key_id = std::env::var("AWS_ACCESS_KEY_ID")
+            .ok()
+            .filter(|key| !key.is_empty())
+            .or_else(|| s3.access_key_id.clone())
+            .ok_or_else(|| {
+                TaskError::Config(format!(
+                    "No S3 access key id found. Set AWS_ACCESS_KEY_ID or add `access_key_id` to the [s3] section of {}.",
+                    Config::path().display()
+                ))
+            })?;
+        Ok(Client {
+            http: http_client(SERVICE)?,
+            endpoint,
+            region,
+            access_key_id,
+            secret_access_key: secrets::token(config, Service::S3)?,
+        })
+    }
+
+    pub async fn put(&self, bucket: &str, key: &str, body: Vec<u8>) -> Result<()> {
+        send(SERVICE, self.request(reqwest::Method::PUT, bucket, Some(key), &[], &body)?.body(body)).await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, bucket: &str, key: &str) -> Result<Vec<u8>> {
+        let response = send(SERVICE, self.request(reqwest::Method::GET, bucket, Some(key), &[], b"")?).await?;
+        let body = response.bytes().await.map_err(|e| remote_error(SERVICE, e))?;
+        Ok(body.to_vec())
+    }
+
+    pub async fn delete(&self, bucket: &str, key: &str) -> Result<()> {
+        send(SERVICE, self.request(reqwest::Method::DELETE, bucket, Some(key), &[], b"")?).await?;
+        Ok(())
+    }
+
+    // Keys of every object under `prefix`, in the (lexicographic) order S3 lists them
+    pub async fn list(&self, bucket: &str, prefix: &str) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        let mut token: Option<String> = None;
+        loop {
+            let mut query = vec![("list-type", "2".to_string()), ("prefix", prefix.to_string())];
+            if let Some(token) = &token {
+                query.push(("continuation-token", token.clone()));
+            }
+            let request = self.request(reqwest::Method::GET, bucket, None, &query, b"")?;
+            let xml = send(SERVICE, request).await?.text().await.map_err(|e| remote_error(SERVICE, e))?;
+            let page = parse_list(&xml).map_err(|e| remote_error(SERVICE, format!("unexpected listing: {}", e)))?;
+            keys.extend(page.keys);
+            match page.next_token {
+                Some(next) => token = Some(next),
+                None => return Ok(keys),
+            }
+        }
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        bucket: &str,
+        key: Option<&str>,
+        query: &[(&str, String)],
+        body: &[u8],
+    ) -> Result<reqwest::RequestBuilder> {
+        let mut path = format!("{}/{}", self.endpoint.path().trim_end_matches('/'), uri_encode(bucket, false));
+        if let Some(key) = key {
+            path = format!("{}/{}", path, uri_encode(key, true));
+        }
+        let mut query: Vec<(String, String)> =
+            query.iter().map(|(name, value)| (uri_encode(name, false), uri_encode(value, false))).collect();
+        query.sort();
+        let query = query.iter().map(|(name, value)| format!("{}={}", name, value)).collect::<Vec<_>>().join("&");
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+        url.set_query(if query.is_empty() { None } else { Some(&query) });
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err(TaskError::Config("The [s3] endpoint has no host.".to_string())),
+        };
+
+        let now = Utc::now();
+        let (amz_date, date) = (now.format("%Y%m%dT%H%M%SZ").to_string(), now.format("%Y%m%d").to_string());
+        let payload_hash = hex(&Sha256::digest(body));
+        let canonical = format!(
+            "{}\n{}\n{}\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, query, host, payload_hash, amz_date, SIGNED_HEADERS, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, hex(&Sha256::digest(canonical)));
+
+        let mut key = hmac(format!("AWS4{}", self.secret_access_key).as_bytes(), date.as_bytes());
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac(&key, part.as_bytes());
+        }
+        let signature = hex(&hmac(&key, to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, SIGNED_HEADERS, signature
+        );
+
+        Ok(self
+            .http
+            .request(method, url)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("x-amz-date", amz_date)
+            .header(reqwest::header::AUTHORIZATION, authorization))
+    }
+}
+
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// Percent-encoding as SigV4 wants it: everything but unreserved characters (and '/' in keys)
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => out.push(byte as char),
+            b'/' if keep_slash => out.push('/'),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+#[derive(Debug, Default)]
+struct ListPage {
+    keys: Vec<String>,
+    // Set while the listing is truncated
+    next_token: Option<String>,
+}
+
+fn parse_list(xml: &str) -> std::result::Result<ListPage, quick_xml::Error> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut page = ListPage::default();
+    let mut truncated = false;
+    let mut element: Vec<u8> = Vec::new();
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => element = start.local_name().as_ref().to_vec(),
+            Event::Text(text) => {
+                let text = text.unescape()?.into_owned();
+                match element.as_slice() {
+                    b"Key" => page.keys.push(text),
+                    b"IsTruncated" => truncated = text == "true",
+                    b"NextContinuationToken" => page.next_token = Some(text),
+                    _ => {}
+                }
+            }
+            Event::End(_) => element.clear(),
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    if !truncated {
+        page.next_token = None;
+    }
+    Ok(page)
+}
@@ -0,0 +1,50 @@
+use sqlx::migrate::Migrator;
+use sqlx::MySqlPool;
+
+use crate::error::{Result, TaskError};
+
+// Migrations are embedded at compile time, so the binary always knows which schema it expects
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+
+// Version of the newest migration this binary was built with
+pub fn expected_version() -> i64 {
+    MIGRATOR.iter().map(|m| m.version).max().unwrap_or(0)
+}
+
+// Applies any pending migrations and records the resulting schema version
+pub async fn migrate(pool: &MySqlPool) -> Result<()> {
+    MIGRATOR.run(pool).await.map_err(sqlx::Error::from)?;
+
+    let version = expected_version();
+    sqlx::query("REPLACE INTO schema_meta (id, version) VALUES (1, ?)")
+        .bind(version)
+        .execute(pool)
+        .await?;
+
+    println!("Database schema is up to date (version {}).", version);
+    Ok(())
+}
+
+// Version recorded in `schema_meta`, or `None` if the database was never migrated by this tool
+pub async fn applied_version(pool: &MySqlPool) -> std::result::Result<Option<i64>, sqlx::Error> {
+    let result = sqlx::query_scalar("SELECT version FROM schema_meta WHERE id = 1")
+        .fetch_optional(pool)
+        .await;
+
+    match result {
+        Ok(version) => Ok(version),
+        // 42S02: table doesn't exist yet
+        Err(sqlx::Error::Database(e)) if e.code().as_deref() == Some("42S02") => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Fails with `SchemaOutdated` when the database schema is older than this binary expects
+pub async fn check_version(pool: &MySqlPool) -> Result<()> {
+    let expected = expected_version();
+
+    match applied_version(pool).await? {
+        Some(applied) if applied >= expected => Ok(()),
+        applied => Err(TaskError::SchemaOutdated { applied, expected }),
+    }
+}
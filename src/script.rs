@@ -0,0 +1,319 @@
+// `task script run <file>`: runs a Rhai script against the tasks, for reports and bulk changes
+// that no command makes. A script works as the logged-in account like any other command and
+// sees only the functions below; Rhai itself has no access to files, the network or other
+// processes, and scripts that loop for too long are stopped.
+//
+//   let stale = 0;
+//   for task in tasks() {
+//       if !task.completed && task.description.contains("milk") {
+//           update(task.id, #{ priority: "high", due: "2026-10-20" });
+//           stale += 1;
+//       }
+//   }
+//   print(`${stale} task(s) moved up`);
+//
+// tasks()               every task, newest first, as maps of id, description, completed, due_at,
+//                       priority, project_id and the other columns of a task
+// search(query)         the tasks matching `query`, as `task search` finds them
+// get(id)               one task, or () if there is none
+// tags(id)              its tag names
+// add(description)      creates a task and returns its id
+// complete(id), delete(id)   return whether there was such a task
+// update(id, changes)   changes is a map of description, completed, due ("YYYY-MM-DD[ HH:MM]"
+//                       or () to clear) and priority (low, medium, high or ())
+//
+// With --dry-run the four functions that change tasks only print what they would do. Tasks a
+// script adds, completes or deletes go to the webhooks and hooks like those of the menu.
+
+use std::sync::Arc;
+
+use rhai::{Array, Dynamic, Engine, EvalAltResult, Map};
+use tokio::runtime::Handle;
+
+use crate::cli::ScriptArgs;
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::import::parse_due;
+use crate::priority::Priority;
+use crate::repository::{TaskChanges, TaskRepository};
+use crate::store::TaskStore;
+use crate::webhooks::{WebhookEvent, Webhooks};
+
+// Rhai operations before a script is stopped; enough to go through a few hundred thousand tasks
+const MAX_OPERATIONS: u64 = 50_000_000;
+const SEARCH_PAGE: u32 = 100;
+
+type ScriptResult<T> = std::result::Result<T, Box<EvalAltResult>>;
+
+pub async fn run(repo: &TaskRepository, config: &Config, args: ScriptArgs) -> Result<()> {
+    let source = std::fs::read_to_string(&args.file)
+        .map_err(|e| TaskError::InvalidInput(format!("Could not read {}: {}", args.file.display(), e)))?;
+    let webhooks = Webhooks::new(config)?;
+
+    let store = Arc::new(repo.clone());
+    let events = repo.clone();
+    let runtime = Handle::current();
+    let changed = move |event: WebhookEvent, id: i32| {
+        if let Err(e) = runtime.block_on(webhooks.task_event(&events, event, id)) {
+            println!("Warning: could not send webhooks for task {}: {}", id, e);
+        }
+    };
+    let (path, dry_run) = (args.file, args.dry_run);
+    tokio::task::spawn_blocking(move || execute(store, &source, dry_run, changed))
+        .await
+        .map_err(std::io::Error::other)?
+        .map_err(|e| match e {
+            Failure::Syntax(e) => TaskError::parse(&path, "a valid Rhai script", e),
+            Failure::Runtime(e) => TaskError::InvalidInput(format!("Script {} failed: {}", path.display(), e)),
+        })
+}
+
+#[derive(Debug)]
+enum Failure {
+    Syntax(rhai::ParseError),
+    Runtime(Box<EvalAltResult>),
+}
+
+// Runs the script on the calling thread, which must be one that may block on the runtime.
+// `changed` hears of every task the script adds, completes or deletes.
+fn execute<S: TaskStore + Send + Sync + 'static>(
+    store: Arc<S>,
+    source: &str,
+    dry_run: bool,
+    changed: impl Fn(WebhookEvent, i32) + Send + Sync + 'static,
+) -> std::result::Result<(), Failure> {
+    let engine = engine(store, dry_run, Arc::new(changed));
+    let ast = engine.compile(source).map_err(Failure::Syntax)?;
+    engine.run_ast(&ast).map_err(Failure::Runtime)
+}
+
+fn engine<S: TaskStore + Send + Sync + 'static>(
+    store: Arc<S>,
+    dry_run: bool,
+    changed: Arc<dyn Fn(WebhookEvent, i32) + Send + Sync>,
+) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(64);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(1 << 20);
+    engine.disable_symbol("eval");
+
+    let runtime = Handle::current();
+
+    let (s, rt) = (store.clone(), runtime.clone());
+    engine.register_fn("tasks", move || -> ScriptResult<Array> {
+        use futures::TryStreamExt;
+        let tasks: Vec<crate::Task> = rt.block_on(s.stream_all().try_collect()).map_err(database)?;
+        tasks.iter().map(rhai::serde::to_dynamic).collect()
+    });
+
+    let (s, rt) = (store.clone(), runtime.clone());
+    engine.register_fn("search", move |query: &str| -> ScriptResult<Array> {
+        let mut found = Array::new();
+        let mut cursor = None;
+        loop {
+            let page = rt.block_on(s.search_page(query, cursor, SEARCH_PAGE)).map_err(database)?;
+            for task in &page.tasks {
+                found.push(rhai::serde::to_dynamic(task)?);
+            }
+            match page.next {
+                Some(next) => cursor = Some(next),
+                None => return Ok(found),
+            }
+        }
+    });
+
+    let (s, rt) = (store.clone(), runtime.clone());
+    engine.register_fn("get", move |id: i64| -> ScriptResult<Dynamic> {
+        match rt.block_on(s.get(task_id(id)?)).map_err(database)? {
+            Some(task) => rhai::serde::to_dynamic(&task),
+            None => Ok(Dynamic::UNIT),
+        }
+    });
+
+    let (s, rt) = (store.clone(), runtime.clone());
+    engine.register_fn("tags", move |id: i64| -> ScriptResult<Array> {
+        let tags = rt.block_on(s.tags(task_id(id)?)).map_err(database)?;
+        Ok(tags.into_iter().map(Dynamic::from).collect())
+    });
+
+    let (s, rt, events) = (store.clone(), runtime.clone(), changed.clone());
+    engine.register_fn("add", move |description: &str| -> ScriptResult<i64> {
+        let description = description.trim();
+        if description.is_empty() {
+            return Err("add: the description is empty".into());
+        }
+        if dry_run {
+            println!("Would add task '{}'.", description);
+            return Ok(0);
+        }
+        let id = rt.block_on(s.add(description)).map_err(database)?;
+        events(WebhookEvent::Created, id);
+        Ok(id.into())
+    });
+
+    let (s, rt, events) = (store.clone(), runtime.clone(), changed.clone());
+    engine.register_fn("complete", move |id: i64| -> ScriptResult<bool> {
+        let id = task_id(id)?;
+        if dry_run {
+            println!("Would complete task {}.", id);
+            return Ok(rt.block_on(s.get(id)).map_err(database)?.is_some());
+        }
+        let Some(updated) = rt.block_on(s.complete(id)).map_err(database)? else {
+            return Ok(false);
+        };
+        if updated.completed {
+            events(WebhookEvent::Completed, id);
+        }
+        Ok(true)
+    });
+
+    let (s, rt, events) = (store.clone(), runtime.clone(), changed);
+    engine.register_fn("delete", move |id: i64| -> ScriptResult<bool> {
+        let id = task_id(id)?;
+        if dry_run {
+            println!("Would delete task {}.", id);
+            return Ok(rt.block_on(s.get(id)).map_err(database)?.is_some());
+        }
+        let deleted = rt.block_on(s.delete(id)).map_err(database)?;
+        if deleted {
+            events(WebhookEvent::Deleted, id);
+        }
+        Ok(deleted)
+    });
+
+    let (s, rt) = (store, runtime);
+    engine.register_fn("update", move |id: i64, changes: Map| -> ScriptResult<bool> {
+        let id = task_id(id)?;
+        let asked: Vec<String> = changes.iter().map(|(key, value)| format!("{} = {}", key, value)).collect();
+        let changes = task_changes(changes)?;
+        if dry_run {
+            println!("Would change task {}: {}.", id, asked.join(", "));
+            return Ok(rt.block_on(s.get(id)).map_err(database)?.is_some());
+        }
+        rt.block_on(s.update(id, &changes)).map_err(database)
+    });
+
+    engine
+}
+
+fn task_id(id: i64) -> ScriptResult<i32> {
+    i32::try_from(id).map_err(|_| format!("{} is not a task id", id).into())
+}
+
+fn database(e: sqlx::Error) -> Box<EvalAltResult> {
+    TaskError::from(e).to_string().into()
+}
+
+// The map `update` is given, checked the way `task serve` checks a PATCH
+fn task_changes(changes: Map) -> ScriptResult<TaskChanges> {
+    let mut parsed = TaskChanges::default();
+    for (key, value) in changes {
+        let wrong = |expected: &str| format!("update: {} must be {}, not {}", key, expected, value.type_name());
+        match key.as_str() {
+            "description" => {
+                let description = value.clone().into_string().map_err(|_| wrong("a string"))?;
+                if description.trim().is_empty() {
+                    return Err("update: the description is empty".into());
+                }
+                parsed.description = Some(description.trim().to_string());
+            }
+            "completed" => parsed.completed = Some(value.as_bool().map_err(|_| wrong("true or false"))?),
+            "due" if value.is_unit() => parsed.due_at = Some(None),
+            "due" => {
+                let due = value.clone().into_string().map_err(|_| wrong("a date or ()"))?;
+                parsed.due_at = Some(Some(parse_due(&due).map_err(|e| format!("update: {}", e))?));
+            }
+            "priority" if value.is_unit() => parsed.priority = Some(None),
+            "priority" => {
+                let priority = value.clone().into_string().map_err(|_| wrong("low, medium, high or ()"))?;
+                parsed.priority = Some(Some(priority.parse::<Priority>().map_err(|e| format!("update: {}", e))?));
+            }
+            other => return Err(format!("update: unknown field '{}'", other).into()),
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::fixtures::{self, bundle};
+    use crate::memory::MemoryStore;
+
+    // Runs a script against `store` the way `run` does, and returns the events it caused
+    async fn script(
+        store: &Arc<MemoryStore>,
+        source: &str,
+        dry_run: bool,
+    ) -> std::result::Result<Vec<(WebhookEvent, i32)>, Failure> {
+        let (store, source) = (store.clone(), source.to_string());
+        tokio::task::spawn_blocking(move || {
+            let events = Arc::new(Mutex::new(Vec::new()));
+            let seen = events.clone();
+            execute(store, &source, dry_run, move |event, id| seen.lock().unwrap().push((event, id)))?;
+            Ok(std::mem::take(&mut *events.lock().unwrap()))
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn scripts_change_tasks_in_bulk() {
+        let store = Arc::new(MemoryStore::new("tester"));
+        let ids = store.import(&fixtures::mixed());
+
+        let events = script(
+            &store,
+            r#"
+                for task in tasks() {
+                    if task.due_at != () && !task.completed { complete(task.id); }
+                }
+                let id = add("Follow up");
+                update(id, #{ priority: "high", due: "2026-03-20" });
+                if get(9999) != () { throw "there is no task 9999"; }
+            "#,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let completed = [(WebhookEvent::Completed, ids[4]), (WebhookEvent::Completed, ids[2])];
+        assert_eq!(events, [completed[0], completed[1], (WebhookEvent::Created, 7)]);
+        assert!(store.get(ids[4]).await.unwrap().unwrap().completed);
+        let added = store.get(7).await.unwrap().unwrap();
+        assert_eq!((added.description.as_str(), added.priority), ("Follow up", Some(Priority::High)));
+        assert_eq!(added.due_at, Some(fixtures::at_time(20, 0, 0)));
+    }
+
+    #[tokio::test]
+    async fn dry_runs_change_nothing() {
+        let store = Arc::new(MemoryStore::new("tester"));
+        let ids = store.import(&[bundle("Buy milk", 1)]);
+
+        let events = script(&store, r#"for task in search("MILK") { delete(task.id); } add("More");"#, true)
+            .await
+            .unwrap();
+        assert!(events.is_empty());
+        assert!(store.get(ids[0]).await.unwrap().is_some());
+        assert!(store.get(ids[0] + 1).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn bad_scripts_fail_with_a_reason() {
+        let store = Arc::new(MemoryStore::new("tester"));
+
+        assert!(matches!(script(&store, "let x = ;", false).await, Err(Failure::Syntax(_))));
+        let Err(Failure::Runtime(e)) = script(&store, r#"update(1, #{ colour: "red" })"#, false).await else {
+            panic!("unknown fields should fail");
+        };
+        assert!(e.to_string().contains("unknown field 'colour'"), "{}", e);
+        let Err(Failure::Runtime(e)) = script(&store, "loop {}", false).await else {
+            panic!("endless loops should be stopped");
+        };
+        assert!(matches!(*e, EvalAltResult::ErrorTooManyOperations(_)), "{}", e);
+    }
+}
@@ -0,0 +1,209 @@
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+
+// Keyring entries are stored as (KEYRING_SERVICE, service name)
+const KEYRING_SERVICE: &str = "task";
+// Entry holding the session token of `task login`, and the variable that overrides it
+const SESSION_ENTRY: &str = "session";
+const SESSION_VAR: &str = "TASK_SESSION";
+
+// External services we hold API tokens for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Service {
+    Todoist,
+    Github,
+    Gitlab,
+    Jira,
+    Caldav,
+    Google,
+    Notion,
+    S3,
+    Backup,
+    Email,
+    Slack,
+    Discord,
+    Mqtt,
+    Telegram,
+    Sms,
+    Oidc,
+}
+
+impl Service {
+    pub fn name(self) -> &'static str {
+        match self {
+            Service::Todoist => "todoist",
+            Service::Github => "github",
+            Service::Gitlab => "gitlab",
+            Service::Jira => "jira",
+            Service::Caldav => "caldav",
+            Service::Google => "google",
+            Service::Notion => "notion",
+            Service::S3 => "s3",
+            Service::Backup => "backup",
+            Service::Email => "email",
+            Service::Slack => "slack",
+            Service::Discord => "discord",
+            Service::Mqtt => "mqtt",
+            Service::Telegram => "telegram",
+            Service::Sms => "sms",
+            Service::Oidc => "oidc",
+        }
+    }
+
+    fn env_var(self) -> &'static str {
+        match self {
+            Service::Todoist => "TODOIST_TOKEN",
+            Service::Github => "GITHUB_TOKEN",
+            Service::Gitlab => "GITLAB_TOKEN",
+            Service::Jira => "JIRA_TOKEN",
+            Service::Caldav => "CALDAV_PASSWORD",
+            Service::Google => "GOOGLE_REFRESH_TOKEN",
+            Service::Notion => "NOTION_TOKEN",
+            Service::S3 => "AWS_SECRET_ACCESS_KEY",
+            Service::Backup => "TASK_BACKUP_PASSPHRASE",
+            Service::Email => "SMTP_PASSWORD",
+            Service::Slack => "SLACK_WEBHOOK_URL",
+            Service::Discord => "DISCORD_WEBHOOK_URL",
+            Service::Mqtt => "MQTT_PASSWORD",
+            Service::Telegram => "TELEGRAM_BOT_TOKEN",
+            Service::Sms => "TWILIO_AUTH_TOKEN",
+            Service::Oidc => "OIDC_CLIENT_SECRET",
+        }
+    }
+
+    // Key of the secret in the service's section of the config file
+    fn config_key(self) -> &'static str {
+        match self {
+            Service::Caldav | Service::Email | Service::Mqtt => "password",
+            Service::Slack | Service::Discord => "webhook_url",
+            Service::Sms => "auth_token",
+            Service::Oidc => "client_secret",
+            Service::S3 => "secret_access_key",
+            Service::Backup => "passphrase",
+            _ => "token",
+        }
+    }
+
+    fn configured(self, config: &Config) -> Option<&str> {
+        match self {
+            Service::Todoist => config.todoist.token.as_deref(),
+            Service::Github => config.github.token.as_deref(),
+            Service::Gitlab => config.gitlab.token.as_deref(),
+            Service::Jira => config.jira.token.as_deref(),
+            Service::Caldav => config.caldav.password.as_deref(),
+            // Obtained through the OAuth device flow and kept in the keyring
+            Service::Google => None,
+            Service::Notion => config.notion.token.as_deref(),
+            Service::S3 => config.s3.secret_access_key.as_deref(),
+            Service::Backup => config.backup.passphrase.as_deref(),
+            Service::Email => config.email.password.as_deref(),
+            Service::Slack => config.slack.webhook_url.as_deref(),
+            Service::Discord => config.discord.webhook_url.as_deref(),
+            Service::Mqtt => config.mqtt.password.as_deref(),
+            Service::Telegram => config.telegram.token.as_deref(),
+            Service::Sms => config.sms.auth_token.as_deref(),
+            Service::Oidc => config.oidc.client_secret.as_deref(),
+        }
+    }
+}
+
+// API token for a service: from its environment variable, then the config file, then the
+// system keyring (stored with `task token set`)
+pub fn token(config: &Config, service: Service) -> Result<String> {
+    lookup(config, service)?.ok_or_else(|| {
+        TaskError::Config(format!(
+            "No {0} {3} found. Set {1}, add `{3}` to the [{0}] section of {2}, or run `task token set {0}`.",
+            service.name(),
+            service.env_var(),
+            Config::path().display(),
+            service.config_key()
+        ))
+    })
+}
+
+// Like `token`, but a missing token is not an error
+pub fn lookup(config: &Config, service: Service) -> Result<Option<String>> {
+    if let Some(token) = std::env::var(service.env_var()).ok().filter(|t| !t.is_empty()) {
+        return Ok(Some(token));
+    }
+    if let Some(token) = service.configured(config) {
+        return Ok(Some(token.to_string()));
+    }
+
+    match entry(service.name())?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(TaskError::Config(format!("Could not read the {} token from the keyring: {}", service.name(), e))),
+    }
+}
+
+// Saves a token obtained by the tool itself, e.g. through an OAuth login
+pub fn store(service: Service, token: &str) -> Result<()> {
+    entry(service.name())?
+        .set_password(token)
+        .map_err(|e| TaskError::Config(format!("Could not store the token in the keyring: {}", e)))
+}
+
+// `task token set`: reads the token from stdin so it doesn't end up in the shell history
+pub fn set(service: Service) -> Result<()> {
+    print!("Paste the {} API token and press Enter: ", service.name());
+    let _ = io::stdout().flush();
+
+    let mut token = String::new();
+    io::stdin().read_line(&mut token)?;
+    let token = token.trim();
+    if token.is_empty() {
+        println!("No token entered; nothing stored.");
+        return Ok(());
+    }
+
+    store(service, token)?;
+    println!("Stored the {} token in the system keyring.", service.name());
+    Ok(())
+}
+
+pub fn delete(service: Service) -> Result<()> {
+    match entry(service.name())?.delete_credential() {
+        Ok(()) => println!("Removed the {} token from the system keyring.", service.name()),
+        Err(keyring::Error::NoEntry) => println!("No {} token is stored in the keyring.", service.name()),
+        Err(e) => return Err(TaskError::Config(format!("Could not remove the token from the keyring: {}", e))),
+    }
+    Ok(())
+}
+
+// Session token saved by `task login`, if there is one. TASK_SESSION takes its place on
+// machines without a keyring, e.g. in scripts.
+pub fn session() -> Result<Option<String>> {
+    if let Some(token) = std::env::var(SESSION_VAR).ok().filter(|t| !t.is_empty()) {
+        return Ok(Some(token));
+    }
+    match entry(SESSION_ENTRY)?.get_password() {
+        Ok(token) => Ok(Some(token)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(TaskError::Config(format!("Could not read the login from the keyring: {}", e))),
+    }
+}
+
+pub fn store_session(token: &str) -> Result<()> {
+    entry(SESSION_ENTRY)?
+        .set_password(token)
+        .map_err(|e| TaskError::Config(format!("Could not store the login in the keyring: {}", e)))
+}
+
+// Returns false if there was no session to remove
+pub fn delete_session() -> Result<bool> {
+    match entry(SESSION_ENTRY)?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(TaskError::Config(format!("Could not remove the login from the keyring: {}", e))),
+    }
+}
+
+fn entry(name: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, name)
+        .map_err(|e| TaskError::Config(format!("The system keyring is not available: {}", e)))
+}
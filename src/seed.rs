@@ -0,0 +1,139 @@
+use chrono::{Duration, Local};
+use sqlx::MySqlPool;
+
+use crate::cli::SeedArgs;
+use crate::db::{self, RetryPolicy};
+use crate::error::Result;
+use crate::repository::{owned, Access, BindAccess, NewTask, TaskBundle, TaskRepository};
+
+// Recorded as created_by of the sample tasks. `--wipe` removes only tasks with it, so tasks
+// entered by hand are never touched, whatever they are called; usernames have no spaces.
+const SEED_ACTOR: &str = "task seed";
+
+// A sample task as (description, completed, created this many days ago, project, tags)
+type DemoTask = (&'static str, bool, i64, Option<&'static str>, &'static [&'static str]);
+
+// Both `--demo` and `--wipe` only see the tasks of the logged-in user in the current workspace.
+const DEMO_TASKS: &[DemoTask] = &[
+    ("Set up development environment", true, 30, Some(WEBSITE), &["setup"]),
+    ("Write project README", true, 28, Some(WEBSITE), &["docs"]),
+    ("Design database schema for tasks", true, 27, Some(WEBSITE), &[]),
+    ("Review pull request for login page", true, 21, Some(WEBSITE), &["review"]),
+    ("Book flights for conference", true, 18, None, &["travel"]),
+    ("Renew domain name", true, 14, Some(WEBSITE), &[]),
+    ("Prepare sprint planning agenda", false, 10, Some(WEBSITE), &["meeting"]),
+    ("Fix flaky integration test on CI", false, 9, Some(WEBSITE), &["bug"]),
+    ("Update dependencies to latest versions", false, 7, Some(WEBSITE), &["setup"]),
+    ("Draft quarterly report", false, 5, None, &["docs"]),
+    ("Call dentist to reschedule appointment", false, 4, Some(HOUSEHOLD), &["errand"]),
+    ("Buy groceries: milk, eggs, coffee", false, 2, Some(HOUSEHOLD), &["errand"]),
+    ("Plan team offsite", false, 1, None, &["meeting", "travel"]),
+    ("Reply to customer support tickets", false, 0, None, &[]),
+    ("Back up laptop to external drive", false, 0, Some(HOUSEHOLD), &[]),
+];
+
+const WEBSITE: &str = "Demo: Website relaunch";
+const HOUSEHOLD: &str = "Demo: Household";
+
+// Projects and tags `--demo` uses, created if they don't exist yet
+const DEMO_PROJECTS: &[&str] = &[WEBSITE, HOUSEHOLD];
+const DEMO_TAGS: &[&str] = &["bug", "docs", "errand", "meeting", "review", "setup", "travel"];
+
+pub async fn run(pool: &MySqlPool, repo: &TaskRepository, retry: &RetryPolicy, args: SeedArgs) -> Result<()> {
+    if args.wipe {
+        wipe(pool, retry, repo.access()).await
+    } else {
+        seed_demo(pool, repo).await
+    }
+}
+
+async fn seed_demo(pool: &MySqlPool, repo: &TaskRepository) -> Result<()> {
+    if count_demo_tasks(pool, repo.access()).await? > 0 {
+        println!("Demo data is already present. Run `task seed --wipe` first to reseed.");
+        return Ok(());
+    }
+
+    let now = Local::now().naive_local();
+    let bundles: Vec<TaskBundle> = DEMO_TASKS
+        .iter()
+        .map(|&(description, completed, days_ago, project, tags)| TaskBundle {
+            task: NewTask {
+                id: None,
+                description: description.to_string(),
+                completed,
+                created_at: now - Duration::days(days_ago),
+                created_by: Some(SEED_ACTOR.to_string()),
+                updated_by: None,
+                updated_at: now - Duration::days(days_ago),
+                completed_at: None,
+                due_at: None,
+                priority: None,
+                project_id: None,
+                owner_id: None,
+                assignee_id: None,
+                workspace_id: None,
+            },
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            project: project.map(str::to_string),
+            checklist: Vec::new(),
+            notes: Vec::new(),
+            external: None,
+        })
+        .collect();
+
+    let inserted = repo.seed(&bundles).await?;
+
+    println!("Inserted {} demo tasks in {} projects.", inserted.len(), DEMO_PROJECTS.len());
+    Ok(())
+}
+
+// Removes the sample tasks, then the sample projects and tags that no task uses any more; ones
+// that tasks entered by hand were put in stay.
+async fn wipe(pool: &MySqlPool, retry: &RetryPolicy, access: Access) -> Result<()> {
+    let deleted = db::retry_lock_conflicts(retry, || async move {
+        let mut tx = pool.begin().await?;
+
+        let deleted = sqlx::query(concat!("DELETE FROM tasks WHERE created_by = ? AND ", owned!()))
+            .bind(SEED_ACTOR)
+            .bind_owned(access)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        for &project in DEMO_PROJECTS {
+            sqlx::query(
+                "DELETE FROM projects WHERE name = ? AND workspace_id <=> ? \
+                 AND NOT EXISTS (SELECT 1 FROM tasks WHERE tasks.project_id = projects.id)",
+            )
+            .bind(project)
+            .bind(access.workspace)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for &tag in DEMO_TAGS {
+            sqlx::query(
+                "DELETE FROM tags WHERE name = ? \
+                 AND NOT EXISTS (SELECT 1 FROM task_tags WHERE task_tags.tag_id = tags.id)",
+            )
+            .bind(tag)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(deleted)
+    })
+    .await?;
+
+    println!("Removed {} demo tasks.", deleted);
+    Ok(())
+}
+
+async fn count_demo_tasks(pool: &MySqlPool, access: Access) -> Result<i64> {
+    let count = sqlx::query_scalar(concat!("SELECT COUNT(*) FROM tasks WHERE created_by = ? AND ", owned!()))
+        .bind(SEED_ACTOR)
+        .bind_owned(access)
+        .fetch_one(pool)
+        .await?;
+    Ok(count)
+}
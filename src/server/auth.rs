@@ -0,0 +1,66 @@
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::{header, HeaderMap};
+
+use crate::api_tokens::Scope;
+use crate::repository::TaskRepository;
+use crate::users;
+
+use super::sessions::session_cookie;
+use super::{ApiError, AppState};
+
+// The repository a request works on. Until there are accounts every request sees every task, as
+// the CLI does. After that each needs a personal access token (`task api-token create`), and then
+// works as the token's user, in the token's workspace and with its scope; or the cookie of a
+// session from POST /session, which works like a CLI login.
+pub(super) struct Authed(pub TaskRepository);
+
+#[async_trait]
+impl FromRequestParts<AppState> for Authed {
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, ApiError> {
+        authenticate(&state.repo, bearer(&parts.headers), session_cookie(&parts.headers)).await.map(Authed)
+    }
+}
+
+// A token takes precedence over a session cookie sent along with it
+pub(super) async fn authenticate(
+    repo: &TaskRepository,
+    token: Option<&str>,
+    session: Option<&str>,
+) -> Result<TaskRepository, ApiError> {
+    if repo.user_count().await? == 0 {
+        return Ok(repo.clone());
+    }
+    let Some(token) = token else {
+        let Some(session) = session else {
+            return Err(ApiError::unauthorized(
+                "This server needs an API token (`Authorization: Bearer <token>`) or a login (POST /session).",
+            ));
+        };
+        let Some(session) = repo.session(&users::hash_token(session)).await? else {
+            return Err(ApiError::unauthorized("The session has expired; log in again."));
+        };
+        return Ok(users::as_user(repo.clone(), &session.user).with_workspace(session.workspace_id.zip(session.role)));
+    };
+    let Some(login) = repo.api_token_login(&users::hash_token(token)).await? else {
+        return Err(ApiError::unauthorized("The API token is unknown, revoked or expired."));
+    };
+    let session = login.session;
+    Ok(users::as_user(repo.clone(), &session.user)
+        .with_workspace(session.workspace_id.zip(session.role))
+        .with_read_only(login.scope == Scope::Read))
+}
+
+// The token of an `Authorization: Bearer` header
+pub(super) fn bearer(headers: &HeaderMap) -> Option<&str> {
+    bearer_token(headers.get(header::AUTHORIZATION)?.to_str().ok()?)
+}
+
+// The token of an Authorization value, which gRPC clients send as metadata
+pub(super) fn bearer_token(value: &str) -> Option<&str> {
+    let (scheme, token) = value.trim().split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
@@ -0,0 +1,56 @@
+use tokio::sync::broadcast;
+
+use crate::metrics;
+use crate::repository::Access;
+
+// How far a slow watcher may fall behind before it starts missing events
+const CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskEventKind {
+    Created,
+    Updated,
+    Completed,
+    Deleted,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TaskEvent {
+    pub kind: TaskEventKind,
+    pub id: i32,
+    // Of the user who made the change, so what is sent about it is what they can see
+    pub access: Access,
+}
+
+// Changes made through this server (REST, GraphQL or gRPC), for clients that watch for them.
+// Changes made by other processes, such as the CLI, are not seen here.
+#[derive(Debug, Clone)]
+pub struct Events {
+    sender: broadcast::Sender<TaskEvent>,
+}
+
+impl Events {
+    pub fn new() -> Self {
+        Events { sender: broadcast::channel(CAPACITY).0 }
+    }
+
+    pub fn publish(&self, kind: TaskEventKind, id: i32, access: Access) {
+        match kind {
+            TaskEventKind::Created => metrics::record_created(),
+            TaskEventKind::Completed => metrics::record_completed(),
+            TaskEventKind::Updated | TaskEventKind::Deleted => {}
+        }
+        // An error only means nobody is watching right now
+        let _ = self.sender.send(TaskEvent { kind, id, access });
+    }
+
+    pub fn publish_all(&self, kind: TaskEventKind, ids: &[i32], access: Access) {
+        for id in ids {
+            self.publish(kind, *id, access);
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TaskEvent> {
+        self.sender.subscribe()
+    }
+}
@@ -0,0 +1,141 @@
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderMap};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::Router;
+use chrono::{Local, NaiveDateTime};
+use quick_xml::escape::escape;
+use serde::Deserialize;
+
+use crate::repository::{TaskFilter, TaskRepository};
+use crate::Task;
+
+use super::auth::{authenticate, bearer};
+use super::sessions::session_cookie;
+use super::{ApiError, ApiResult, AppState};
+
+// Feed readers only look at the newest entries anyway
+const MAX_ENTRIES: u32 = 100;
+
+pub(super) fn routes() -> Router<AppState> {
+    Router::new().route("/feed.atom", get(all)).route("/projects/:name/feed.atom", get(project))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeedQuery {
+    // Only tasks that are past their due date
+    #[serde(default)]
+    overdue: bool,
+    // API token, for feed readers that can't send an Authorization header
+    token: Option<String>,
+}
+
+// Pending tasks, newest first; overdue ones have "Overdue:" in front of their title
+async fn all(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<FeedQuery>,
+) -> ApiResult<Response> {
+    let repo = authenticate(&state.repo, bearer(&headers).or(query.token.as_deref()), session_cookie(&headers)).await?;
+    feed(&repo, &headers, None, query.overdue).await
+}
+
+async fn project(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+    Query(query): Query<FeedQuery>,
+) -> ApiResult<Response> {
+    let repo = authenticate(&state.repo, bearer(&headers).or(query.token.as_deref()), session_cookie(&headers)).await?;
+    if repo.project_id(&name).await?.is_none() {
+        return Err(ApiError::not_found(format!("No project named '{}'", name)));
+    }
+    feed(&repo, &headers, Some(name), query.overdue).await
+}
+
+async fn feed(
+    repo: &TaskRepository,
+    headers: &HeaderMap,
+    project: Option<String>,
+    overdue: bool,
+) -> ApiResult<Response> {
+    let now = Local::now().naive_local();
+    let filter = TaskFilter {
+        completed: Some(false),
+        project: project.clone(),
+        due_before: overdue.then_some(now),
+        ..TaskFilter::default()
+    };
+    let tasks = repo.filter_page(&filter, None, MAX_ENTRIES).await?.tasks;
+
+    // Links have to be absolute; the Host header says how the reader reached us
+    let host = headers.get(header::HOST).and_then(|host| host.to_str().ok()).unwrap_or("localhost");
+    let base =
+        reqwest::Url::parse(&format!("http://{}/", host)).map_err(|_| ApiError::bad_request("invalid Host header"))?;
+    let mut self_url = base.clone();
+    if let Ok(mut segments) = self_url.path_segments_mut() {
+        match &project {
+            Some(name) => segments.extend(["projects", name.as_str(), "feed.atom"]),
+            None => segments.push("feed.atom"),
+        };
+    }
+    if overdue {
+        self_url.set_query(Some("overdue=true"));
+    }
+    let title = match (&project, overdue) {
+        (Some(name), true) => format!("Overdue tasks in {}", name),
+        (Some(name), false) => format!("Open tasks in {}", name),
+        (None, true) => "Overdue tasks".to_string(),
+        (None, false) => "Open tasks".to_string(),
+    };
+    // Readers use this to tell whether anything changed
+    let updated = tasks.iter().map(|task| task.updated_at).max().unwrap_or(now);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    xml.push_str(&format!("  <title>{}</title>\n", escape(&title)));
+    xml.push_str(&format!("  <id>{}</id>\n", escape(self_url.as_str())));
+    xml.push_str(&format!("  <link rel=\"self\" href=\"{}\"/>\n", escape(self_url.as_str())));
+    xml.push_str(&format!("  <updated>{}</updated>\n", atom_time(updated)));
+    xml.push_str("  <author><name>task</name></author>\n");
+    for task in &tasks {
+        push_entry(&mut xml, task, &base, now);
+    }
+    xml.push_str("</feed>\n");
+
+    Ok(([(header::CONTENT_TYPE, "application/atom+xml; charset=utf-8")], xml).into_response())
+}
+
+fn push_entry(xml: &mut String, task: &Task, base: &reqwest::Url, now: NaiveDateTime) {
+    let overdue = task.due_at.is_some_and(|due| due < now);
+    let title = if overdue { format!("Overdue: {}", task.description) } else { task.description.clone() };
+    let url = format!("{}tasks/{}", base, task.id);
+
+    let mut summary = Vec::new();
+    if let Some(due_at) = task.due_at {
+        summary.push(format!("Due {}", due_at.format("%Y-%m-%d %H:%M")));
+    }
+    if let Some(priority) = task.priority {
+        summary.push(format!("{} priority", priority));
+    }
+    if summary.is_empty() {
+        summary.push("No due date".to_string());
+    }
+
+    xml.push_str("  <entry>\n");
+    xml.push_str(&format!("    <title>{}</title>\n", escape(&title)));
+    xml.push_str(&format!("    <id>{}</id>\n", escape(&url)));
+    xml.push_str(&format!("    <link href=\"{}\"/>\n", escape(&url)));
+    xml.push_str(&format!("    <published>{}</published>\n", atom_time(task.created_at)));
+    xml.push_str(&format!("    <updated>{}</updated>\n", atom_time(task.updated_at)));
+    xml.push_str(&format!("    <summary>{}</summary>\n", escape(summary.join(", "))));
+    xml.push_str("  </entry>\n");
+}
+
+// Stored times are local; Atom wants RFC 3339 with an offset
+fn atom_time(time: NaiveDateTime) -> String {
+    match time.and_local_timezone(Local).earliest() {
+        Some(time) => time.to_rfc3339(),
+        None => time.and_utc().to_rfc3339(),
+    }
+}
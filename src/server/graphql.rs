@@ -0,0 +1,242 @@
+use async_graphql::{
+    ComplexObject, Context, EmptySubscription, Error, InputObject, Object, Result, Schema, SimpleObject,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::extract::State;
+use axum::response::{Html, IntoResponse};
+use chrono::NaiveDateTime;
+
+use crate::priority::Priority;
+use crate::repository::{ChecklistItem, Note, TaskFilter, TaskRepository};
+use crate::Task;
+
+use super::auth::Authed;
+use super::{format_cursor, limit, new_task_bundle, parse_cursor, AppState, Events, TaskEventKind};
+
+pub(super) type TaskSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+// Each request adds the repository it works on (see `execute`)
+pub(super) fn schema(events: Events) -> TaskSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription).data(events).finish()
+}
+
+pub(super) async fn execute(
+    State(state): State<AppState>,
+    Authed(repo): Authed,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    state.graphql.execute(request.into_inner().data(repo)).await.into()
+}
+
+// GraphiQL in the browser, for trying out queries
+pub(super) async fn playground() -> impl IntoResponse {
+    Html(async_graphql::http::GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+// A task as seen by GraphQL. Project, tags, checklist and notes are only fetched when a
+// query asks for them.
+#[derive(SimpleObject)]
+#[graphql(name = "Task", complex)]
+struct TaskNode {
+    id: i32,
+    description: String,
+    completed: bool,
+    created_at: NaiveDateTime,
+    created_by: Option<String>,
+    updated_by: Option<String>,
+    updated_at: NaiveDateTime,
+    due_at: Option<NaiveDateTime>,
+    priority: Option<Priority>,
+    #[graphql(skip)]
+    project_id: Option<i32>,
+}
+
+impl From<Task> for TaskNode {
+    fn from(task: Task) -> Self {
+        TaskNode {
+            id: task.id,
+            description: task.description,
+            completed: task.completed,
+            created_at: task.created_at,
+            created_by: task.created_by,
+            updated_by: task.updated_by,
+            updated_at: task.updated_at,
+            due_at: task.due_at,
+            priority: task.priority,
+            project_id: task.project_id,
+        }
+    }
+}
+
+#[ComplexObject]
+impl TaskNode {
+    async fn project(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        match self.project_id {
+            Some(project_id) => Ok(repo(ctx).project_name(project_id).await?),
+            None => Ok(None),
+        }
+    }
+
+    async fn tags(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        Ok(repo(ctx).tags(self.id).await?)
+    }
+
+    async fn checklist(&self, ctx: &Context<'_>) -> Result<Vec<ChecklistEntry>> {
+        let items = repo(ctx).checklist(self.id).await?;
+        Ok(items.into_iter().map(ChecklistEntry::from).collect())
+    }
+
+    async fn notes(&self, ctx: &Context<'_>) -> Result<Vec<NoteEntry>> {
+        let notes = repo(ctx).notes(self.id).await?;
+        Ok(notes.into_iter().map(NoteEntry::from).collect())
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "ChecklistItem")]
+struct ChecklistEntry {
+    text: String,
+    done: bool,
+}
+
+impl From<ChecklistItem> for ChecklistEntry {
+    fn from(item: ChecklistItem) -> Self {
+        ChecklistEntry { text: item.text, done: item.done }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Note")]
+struct NoteEntry {
+    created_at: NaiveDateTime,
+    body: String,
+}
+
+impl From<Note> for NoteEntry {
+    fn from(note: Note) -> Self {
+        NoteEntry { created_at: note.created_at, body: note.body }
+    }
+}
+
+#[derive(SimpleObject)]
+struct TaskPage {
+    tasks: Vec<TaskNode>,
+    /// Pass as `after` to get the next page; null on the last page
+    next: Option<String>,
+}
+
+/// Same filters as GET /tasks; all of them are optional
+#[derive(Default, InputObject)]
+struct TaskFilterInput {
+    completed: Option<bool>,
+    project: Option<String>,
+    tag: Option<String>,
+    priority: Option<Priority>,
+    due_before: Option<NaiveDateTime>,
+    due_after: Option<NaiveDateTime>,
+}
+
+#[derive(InputObject)]
+struct NewTaskInput {
+    description: String,
+    due_at: Option<NaiveDateTime>,
+    priority: Option<Priority>,
+    project: Option<String>,
+    #[graphql(default)]
+    tags: Vec<String>,
+}
+
+pub(super) struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Tasks matching the filter, newest first
+    async fn tasks(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<TaskFilterInput>,
+        first: Option<u32>,
+        after: Option<String>,
+    ) -> Result<TaskPage> {
+        let after = after.as_deref().map(parse_cursor).transpose().map_err(Error::new)?;
+        let filter = filter.unwrap_or_default();
+        let filter = TaskFilter {
+            completed: filter.completed,
+            project: filter.project,
+            tag: filter.tag,
+            priority: filter.priority,
+            due_before: filter.due_before,
+            due_after: filter.due_after,
+            assignee: None,
+        };
+
+        let page = repo(ctx).filter_page(&filter, after, limit(first)).await?;
+        Ok(TaskPage {
+            tasks: page.tasks.into_iter().map(TaskNode::from).collect(),
+            next: page.next.map(format_cursor),
+        })
+    }
+
+    async fn task(&self, ctx: &Context<'_>, id: i32) -> Result<Option<TaskNode>> {
+        Ok(repo(ctx).get(id).await?.map(TaskNode::from))
+    }
+
+    /// Tasks whose description matches the words, best matches first
+    async fn search(&self, ctx: &Context<'_>, query: String, first: Option<u32>) -> Result<Vec<TaskNode>> {
+        if query.trim().is_empty() {
+            return Err(Error::new("query must not be empty"));
+        }
+        let page = repo(ctx).search_page(query.trim(), None, limit(first)).await?;
+        Ok(page.tasks.into_iter().map(TaskNode::from).collect())
+    }
+}
+
+pub(super) struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn add_task(&self, ctx: &Context<'_>, input: NewTaskInput) -> Result<TaskNode> {
+        let bundle = new_task_bundle(&input.description, input.due_at, input.priority, input.project, input.tags)
+            .map_err(Error::new)?;
+        let repo = repo(ctx);
+        let ids = repo.import(std::slice::from_ref(&bundle)).await?;
+        events(ctx).publish(TaskEventKind::Created, ids[0], repo.access());
+        let task = repo.get(ids[0]).await?.ok_or_else(|| not_found(ids[0]))?;
+        Ok(task.into())
+    }
+
+    /// The completed task, or an error if there is no task with this id
+    async fn complete_task(&self, ctx: &Context<'_>, id: i32) -> Result<TaskNode> {
+        let repo = repo(ctx);
+        let Some(updated) = repo.complete(id).await? else {
+            return Err(not_found(id));
+        };
+        if updated.completed {
+            events(ctx).publish(TaskEventKind::Completed, id, repo.access());
+        }
+        let task = repo.get(id).await?.ok_or_else(|| not_found(id))?;
+        Ok(task.into())
+    }
+
+    /// Whether a task with this id existed
+    async fn delete_task(&self, ctx: &Context<'_>, id: i32) -> Result<bool> {
+        let repo = repo(ctx);
+        let deleted = repo.delete(id).await?;
+        if deleted {
+            events(ctx).publish(TaskEventKind::Deleted, id, repo.access());
+        }
+        Ok(deleted)
+    }
+}
+
+fn repo<'a>(ctx: &Context<'a>) -> &'a TaskRepository {
+    ctx.data_unchecked::<TaskRepository>()
+}
+
+fn events<'a>(ctx: &Context<'a>) -> &'a Events {
+    ctx.data_unchecked::<Events>()
+}
+
+fn not_found(id: i32) -> Error {
+    Error::new(format!("No task with id {}", id))
+}
@@ -0,0 +1,217 @@
+use std::pin::Pin;
+
+use axum::http::StatusCode;
+use chrono::NaiveDateTime;
+use futures::{Stream, StreamExt};
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tonic::{Request, Response, Status};
+
+use crate::error::TaskError;
+use crate::import;
+use crate::priority::Priority;
+use crate::repository::{TaskFilter, TaskRepository};
+
+use super::auth::{authenticate, bearer_token};
+use super::events::{Events, TaskEvent, TaskEventKind};
+use super::{format_cursor, limit, new_task_bundle, parse_cursor};
+
+mod proto {
+    tonic::include_proto!("task.v1");
+}
+
+use proto::task_service_server::{TaskService, TaskServiceServer};
+
+const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+pub(super) fn service(repo: TaskRepository, events: Events) -> TaskServiceServer<GrpcService> {
+    TaskServiceServer::new(GrpcService { repo, events })
+}
+
+#[derive(Clone)]
+pub(super) struct GrpcService {
+    repo: TaskRepository,
+    events: Events,
+}
+
+type WatchStream = Pin<Box<dyn Stream<Item = Result<proto::TaskEvent, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl TaskService for GrpcService {
+    async fn add(&self, request: Request<proto::AddRequest>) -> Result<Response<proto::Task>, Status> {
+        let service = self.authed(&request).await?;
+        let request = request.into_inner();
+        let due_at = request.due_at.as_deref().map(import::parse_due).transpose().map_err(Status::invalid_argument)?;
+        let priority = priority(request.priority());
+        let bundle = new_task_bundle(&request.description, due_at, priority, request.project, request.tags)
+            .map_err(Status::invalid_argument)?;
+
+        let ids = service.repo.import(std::slice::from_ref(&bundle)).await.map_err(internal)?;
+        self.events.publish(TaskEventKind::Created, ids[0], service.repo.access());
+        Ok(Response::new(service.task(ids[0]).await?))
+    }
+
+    async fn list(&self, request: Request<proto::ListRequest>) -> Result<Response<proto::ListResponse>, Status> {
+        let service = self.authed(&request).await?;
+        let request = request.into_inner();
+        let after = Some(request.cursor.as_str())
+            .filter(|cursor| !cursor.is_empty())
+            .map(parse_cursor)
+            .transpose()
+            .map_err(Status::invalid_argument)?;
+        let due_before = request.due_before.as_deref().map(import::parse_due).transpose();
+        let due_after = request.due_after.as_deref().map(import::parse_due).transpose();
+        let filter = TaskFilter {
+            completed: request.completed,
+            priority: priority(request.priority()),
+            due_before: due_before.map_err(Status::invalid_argument)?,
+            due_after: due_after.map_err(Status::invalid_argument)?,
+            project: request.project,
+            tag: request.tag,
+            assignee: None,
+        };
+
+        let page = self
+            .repo
+            .filter_page(&filter, after, limit(Some(request.limit).filter(|limit| *limit > 0)))
+            .await
+            .map_err(internal)?;
+        let mut tasks = Vec::with_capacity(page.tasks.len());
+        for task in page.tasks {
+            tasks.push(service.to_proto(task).await?);
+        }
+        Ok(Response::new(proto::ListResponse { tasks, next: page.next.map(format_cursor).unwrap_or_default() }))
+    }
+
+    async fn complete(&self, request: Request<proto::TaskRequest>) -> Result<Response<proto::Task>, Status> {
+        let service = self.authed(&request).await?;
+        let id = request.into_inner().id;
+        let Some(updated) = service.repo.complete(id).await.map_err(internal)? else {
+            return Err(not_found(id));
+        };
+        if updated.completed {
+            self.events.publish(TaskEventKind::Completed, id, service.repo.access());
+        }
+        Ok(Response::new(service.task(id).await?))
+    }
+
+    async fn delete(&self, request: Request<proto::TaskRequest>) -> Result<Response<proto::DeleteResponse>, Status> {
+        let service = self.authed(&request).await?;
+        let id = request.into_inner().id;
+        if !service.repo.delete(id).await.map_err(internal)? {
+            return Err(not_found(id));
+        }
+        self.events.publish(TaskEventKind::Deleted, id, service.repo.access());
+        Ok(Response::new(proto::DeleteResponse {}))
+    }
+
+    type WatchStream = WatchStream;
+
+    async fn watch(&self, request: Request<proto::WatchRequest>) -> Result<Response<Self::WatchStream>, Status> {
+        let service = self.authed(&request).await?;
+        let events = BroadcastStream::new(self.events.subscribe());
+
+        let stream = events.then(move |event| {
+            let service = service.clone();
+            async move {
+                match event {
+                    Ok(event) => service.event(event).await,
+                    // The watcher fell too far behind; it has to list again to catch up
+                    Err(BroadcastStreamRecvError::Lagged(missed)) => {
+                        Err(Status::data_loss(format!("missed {} events; list the tasks again", missed)))
+                    }
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
+
+impl GrpcService {
+    // This service with the repository of the request's API token, as for HTTP requests
+    async fn authed<T>(&self, request: &Request<T>) -> Result<GrpcService, Status> {
+        let token = request.metadata().get("authorization").and_then(|value| value.to_str().ok());
+        let repo = authenticate(&self.repo, token.and_then(bearer_token), None).await.map_err(|e| match e.status {
+            StatusCode::UNAUTHORIZED => Status::unauthenticated(e.message),
+            StatusCode::FORBIDDEN => Status::permission_denied(e.message),
+            _ => Status::internal(e.message),
+        })?;
+        Ok(GrpcService { repo, events: self.events.clone() })
+    }
+
+    async fn task(&self, id: i32) -> Result<proto::Task, Status> {
+        let task = self.repo.get(id).await.map_err(internal)?.ok_or_else(|| not_found(id))?;
+        self.to_proto(task).await
+    }
+
+    async fn to_proto(&self, task: crate::Task) -> Result<proto::Task, Status> {
+        let project = match task.project_id {
+            Some(project_id) => self.repo.project_name(project_id).await.map_err(internal)?,
+            None => None,
+        };
+        let mut message = proto::Task {
+            id: task.id,
+            description: task.description,
+            completed: task.completed,
+            created_at: format_time(task.created_at),
+            updated_at: format_time(task.updated_at),
+            due_at: task.due_at.map(format_time),
+            priority: 0,
+            project,
+            tags: self.repo.tags(task.id).await.map_err(internal)?,
+        };
+        message.set_priority(match task.priority {
+            None => proto::Priority::Unspecified,
+            Some(Priority::Low) => proto::Priority::Low,
+            Some(Priority::Medium) => proto::Priority::Medium,
+            Some(Priority::High) => proto::Priority::High,
+        });
+        Ok(message)
+    }
+
+    async fn event(&self, event: TaskEvent) -> Result<proto::TaskEvent, Status> {
+        let kind = match event.kind {
+            TaskEventKind::Created => proto::task_event::Kind::Created,
+            TaskEventKind::Updated => proto::task_event::Kind::Updated,
+            TaskEventKind::Completed => proto::task_event::Kind::Completed,
+            TaskEventKind::Deleted => proto::task_event::Kind::Deleted,
+        };
+        // The task may be gone again by the time the event is sent
+        let task = match event.kind {
+            TaskEventKind::Deleted => None,
+            _ => match self.repo.get(event.id).await.map_err(internal)? {
+                Some(task) => Some(self.to_proto(task).await?),
+                None => None,
+            },
+        };
+        let mut message = proto::TaskEvent { kind: 0, id: event.id, task };
+        message.set_kind(kind);
+        Ok(message)
+    }
+}
+
+fn priority(priority: proto::Priority) -> Option<Priority> {
+    match priority {
+        proto::Priority::Unspecified => None,
+        proto::Priority::Low => Some(Priority::Low),
+        proto::Priority::Medium => Some(Priority::Medium),
+        proto::Priority::High => Some(Priority::High),
+    }
+}
+
+fn format_time(time: NaiveDateTime) -> String {
+    time.format(TIME_FORMAT).to_string()
+}
+
+fn not_found(id: i32) -> Status {
+    Status::not_found(format!("No task with id {}", id))
+}
+
+fn internal(e: sqlx::Error) -> Status {
+    let e = TaskError::from(e);
+    if let TaskError::Forbidden(message) = e {
+        return Status::permission_denied(message);
+    }
+    tracing::error!(error = %e, "gRPC request failed");
+    Status::internal(e.to_string())
+}
@@ -0,0 +1,84 @@
+use axum::extract::{Path, State};
+use axum::response::Response;
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::users;
+
+use super::{sessions, ApiError, ApiResult, AppState};
+
+// The links `task workspace invite --url` prints. Opening one shows where it leads; posting a
+// username and password to it creates the account and logs it in, as `task user join` does.
+pub(super) fn routes() -> Router<AppState> {
+    Router::new().route("/invitations/:code", get(show).post(redeem))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvitationInfo {
+    workspace: String,
+    /// admin, member or viewer
+    role: String,
+    expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RedeemRequest {
+    username: String,
+    password: String,
+}
+
+/// The workspace and role an invitation code joins, if it can still be used
+#[utoipa::path(
+    get,
+    path = "/invitations/{code}",
+    params(("code" = String, Path, description = "Invitation code")),
+    responses((status = 200, body = InvitationInfo), (status = 404, body = super::ErrorBody))
+)]
+pub async fn show(State(state): State<AppState>, Path(code): Path<String>) -> ApiResult<Json<InvitationInfo>> {
+    let invitation = state.repo.invitation_by_code(&users::hash_token(code.trim())).await?;
+    let Some(invitation) = invitation else {
+        return Err(ApiError::not_found(users::invalid_invitation().to_string()));
+    };
+    Ok(Json(InvitationInfo {
+        workspace: invitation.workspace,
+        role: invitation.role.to_string(),
+        expires_at: invitation.expires_at,
+    }))
+}
+
+/// Creates an account in the invitation's workspace and sets the session cookie
+#[utoipa::path(
+    post,
+    path = "/invitations/{code}",
+    params(("code" = String, Path, description = "Invitation code")),
+    request_body = RedeemRequest,
+    responses(
+        (status = 200, body = super::sessions::SessionInfo),
+        (status = 400, body = super::ErrorBody),
+        (status = 404, body = super::ErrorBody)
+    )
+)]
+pub async fn redeem(
+    State(state): State<AppState>,
+    Path(code): Path<String>,
+    Json(request): Json<RedeemRequest>,
+) -> ApiResult<Response> {
+    if state.directory.is_some() {
+        return Err(ApiError::bad_request("Accounts come from the LDAP directory; log in with it instead."));
+    }
+    let repo = &state.repo;
+    let username = request.username.trim();
+    users::check_username(repo, username).await?;
+    if request.password.chars().count() < users::MIN_PASSWORD {
+        return Err(ApiError::bad_request(format!("Passwords need at least {} characters.", users::MIN_PASSWORD)));
+    }
+
+    let hash = users::hash_password(&request.password)?;
+    let Some(redeemed) = repo.redeem_invitation(&users::hash_token(code.trim()), username, &hash).await? else {
+        return Err(ApiError::not_found(users::invalid_invitation().to_string()));
+    };
+    sessions::start(repo, redeemed.user_id, Some(redeemed.workspace_id)).await
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::config::Config;
+use crate::users;
+
+use super::auth::bearer;
+use super::sessions::session_cookie;
+use super::ApiError;
+
+const DEFAULT_REQUESTS_PER_MINUTE: u32 = 120;
+const DEFAULT_TOKEN_REQUESTS_PER_MINUTE: u32 = 600;
+const DEFAULT_MAX_BODY_KB: usize = 1024;
+// Clients tracked before the ones that have been quiet long enough to be back at their full
+// allowance are forgotten
+const PRUNE_AT: usize = 10_000;
+
+// Who a request counts against
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Client {
+    Address(IpAddr),
+    // The hash of the API token or session cookie, so the limiter holds no credentials
+    Credential(String),
+}
+
+// Token buckets of [server]: each client may send a minute's worth of requests at once, and
+// gets them back evenly over the minute. A request with a token or session counts against both
+// its address and its credential, so one token can't get round the limit by changing
+// addresses, and many users behind one address each get their own allowance on top.
+#[derive(Clone)]
+pub(super) struct RateLimits {
+    per_address: u32,
+    per_credential: u32,
+    forwarded_for: bool,
+    buckets: Arc<Mutex<HashMap<Client, Bucket>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    // Requests left, with fractions of one
+    left: f64,
+    updated: Instant,
+}
+
+impl RateLimits {
+    pub fn new(config: &Config) -> Self {
+        let server = &config.server;
+        RateLimits {
+            per_address: server.requests_per_minute.unwrap_or(DEFAULT_REQUESTS_PER_MINUTE),
+            per_credential: server.token_requests_per_minute.unwrap_or(DEFAULT_TOKEN_REQUESTS_PER_MINUTE),
+            forwarded_for: server.forwarded_for,
+            buckets: Arc::default(),
+        }
+    }
+
+    // Takes a request from the buckets of `clients`, each with its limit per minute; None if there
+    // was one left in all of them, else how long until there is. A refused request takes nothing.
+    fn take(&self, clients: &[(Client, u32)], now: Instant) -> Option<Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(PoisonError::into_inner);
+        if buckets.len() >= PRUNE_AT {
+            let (per_address, per_credential) = (self.per_address, self.per_credential);
+            buckets.retain(|client, bucket| {
+                let limit = match client {
+                    Client::Address(_) => per_address,
+                    Client::Credential(_) => per_credential,
+                };
+                bucket.refilled(limit, now) < f64::from(limit)
+            });
+        }
+
+        let mut wait = None;
+        for (client, limit) in clients.iter().filter(|(_, limit)| *limit > 0) {
+            let left = buckets.get(client).map_or(f64::from(*limit), |bucket| bucket.refilled(*limit, now));
+            if left < 1.0 {
+                let needed = Duration::from_secs_f64((1.0 - left) * 60.0 / f64::from(*limit));
+                wait = wait.max(Some(needed));
+            }
+        }
+        if wait.is_some() {
+            return wait;
+        }
+        for (client, limit) in clients.iter().filter(|(_, limit)| *limit > 0) {
+            let left = buckets.get(client).map_or(f64::from(*limit), |bucket| bucket.refilled(*limit, now));
+            buckets.insert(client.clone(), Bucket { left: left - 1.0, updated: now });
+        }
+        None
+    }
+
+    // The address a request came from: the peer, or behind a proxy the one it forwarded for
+    fn address(&self, headers: &HeaderMap, peer: Option<IpAddr>) -> Option<IpAddr> {
+        if self.forwarded_for
+            && let Some(forwarded) = forwarded_for(headers)
+        {
+            return Some(forwarded);
+        }
+        peer
+    }
+}
+
+impl Bucket {
+    fn refilled(self, limit: u32, now: Instant) -> f64 {
+        let earned = now.saturating_duration_since(self.updated).as_secs_f64() * f64::from(limit) / 60.0;
+        (self.left + earned).min(f64::from(limit))
+    }
+}
+
+// The last address of X-Forwarded-For, the one the proxy in front of us added
+fn forwarded_for(headers: &HeaderMap) -> Option<IpAddr> {
+    let value = headers.get_all("x-forwarded-for").iter().next_back()?.to_str().ok()?;
+    value.rsplit(',').next()?.trim().parse().ok()
+}
+
+// Largest request body, from [server] max_body_kb
+pub(super) fn max_body(config: &Config) -> usize {
+    config.server.max_body_kb.unwrap_or(DEFAULT_MAX_BODY_KB).saturating_mul(1024)
+}
+
+// Answers 429 with Retry-After to a client that is over its limit
+pub(super) async fn limit_rate(State(limits): State<RateLimits>, request: Request, next: Next) -> Response {
+    let peer = request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip());
+    let headers = request.headers();
+    let mut clients = Vec::with_capacity(2);
+    if let Some(address) = limits.address(headers, peer) {
+        clients.push((Client::Address(address), limits.per_address));
+    }
+    if let Some(credential) = bearer(headers).or_else(|| session_cookie(headers)) {
+        clients.push((Client::Credential(users::hash_token(credential)), limits.per_credential));
+    }
+
+    let Some(wait) = limits.take(&clients, Instant::now()) else {
+        return next.run(request).await;
+    };
+    let seconds = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    let message = format!("Too many requests; try again in {} s.", seconds);
+    let mut response = ApiError { status: StatusCode::TOO_MANY_REQUESTS, message }.into_response();
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(per_address: u32, per_credential: u32) -> RateLimits {
+        let mut config = Config::default();
+        config.server.requests_per_minute = Some(per_address);
+        config.server.token_requests_per_minute = Some(per_credential);
+        config.server.forwarded_for = true;
+        RateLimits::new(&config)
+    }
+
+    fn address(last: u8) -> Client {
+        Client::Address(IpAddr::from([192, 0, 2, last]))
+    }
+
+    #[test]
+    fn clients_get_a_minutes_worth_back_over_the_minute() {
+        let limits = limits(3, 0);
+        let (start, client) = (Instant::now(), [(address(1), 3)]);
+        for _ in 0..3 {
+            assert_eq!(limits.take(&client, start), None);
+        }
+        assert_eq!(limits.take(&client, start), Some(Duration::from_secs(20)));
+        assert_eq!(limits.take(&[(address(2), 3)], start), None, "other addresses have their own");
+
+        assert_eq!(limits.take(&client, start + Duration::from_secs(15)), Some(Duration::from_secs(5)));
+        assert_eq!(limits.take(&client, start + Duration::from_secs(20)), None);
+        assert!(limits.take(&client, start + Duration::from_secs(20)).is_some());
+    }
+
+    #[test]
+    fn requests_count_against_address_and_credential() {
+        let limits = limits(10, 2);
+        let now = Instant::now();
+        let token = Client::Credential(users::hash_token("secret"));
+        assert_eq!(limits.take(&[(address(1), 10), (token.clone(), 2)], now), None);
+        assert_eq!(limits.take(&[(address(2), 10), (token.clone(), 2)], now), None);
+        assert!(limits.take(&[(address(3), 10), (token, 2)], now).is_some(), "a new address doesn't help");
+
+        // The refused request didn't use up the address's allowance
+        assert_eq!(limits.take(&[(address(3), 1)], now), None);
+    }
+
+    #[test]
+    fn no_limit_lets_everything_through() {
+        let limits = limits(0, 0);
+        let now = Instant::now();
+        for _ in 0..1000 {
+            assert_eq!(limits.take(&[(address(1), 0)], now), None);
+        }
+    }
+
+    #[test]
+    fn the_proxy_says_who_the_client_is() {
+        let limits = limits(1, 1);
+        let peer = Some(IpAddr::from([127, 0, 0, 1]));
+        let mut headers = HeaderMap::new();
+        assert_eq!(limits.address(&headers, peer), peer);
+        headers.insert("x-forwarded-for", HeaderValue::from_static("203.0.113.9, 198.51.100.7"));
+        assert_eq!(limits.address(&headers, peer), Some(IpAddr::from([198, 51, 100, 7])));
+        headers.insert("x-forwarded-for", HeaderValue::from_static("not an address"));
+        assert_eq!(limits.address(&headers, peer), peer);
+    }
+}
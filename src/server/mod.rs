@@ -0,0 +1,377 @@
+use std::net::SocketAddr;
+
+use axum::extract::{MatchedPath, Request};
+use axum::http::StatusCode;
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, NaiveDateTime};
+use serde::Serialize;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, watch};
+use tokio_stream::wrappers::TcpListenerStream;
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::trace::TraceLayer;
+use utoipa::OpenApi;
+
+use crate::cli::ServeArgs;
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::import;
+use crate::ldap::Directory;
+use crate::metrics;
+use crate::notify;
+use crate::priority::Priority;
+use crate::repository::{ListCursor, TaskBundle, TaskFilter, TaskRepository};
+#[cfg(feature = "integrations")]
+use crate::telegram;
+use crate::webhooks::{WebhookEvent, Webhooks};
+
+mod auth;
+mod events;
+mod feed;
+mod graphql;
+mod grpc;
+mod invitations;
+mod limits;
+mod oidc;
+mod sessions;
+mod tasks;
+
+use events::{Events, TaskEventKind};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "task", description = "Tasks stored in MySQL, as served by `task serve`"),
+    paths(
+        tasks::list,
+        tasks::search,
+        tasks::create,
+        tasks::show,
+        tasks::update,
+        tasks::delete,
+        tasks::bulk,
+        tasks::add_note,
+        sessions::login,
+        sessions::refresh,
+        sessions::logout,
+        invitations::show,
+        invitations::redeem,
+    ),
+    components(schemas(
+        crate::Task,
+        crate::priority::Priority,
+        crate::repository::ChecklistItem,
+        crate::repository::Note,
+        tasks::TaskList,
+        tasks::TaskDetails,
+        tasks::NewTaskRequest,
+        tasks::UpdateTaskRequest,
+        tasks::BulkRequest,
+        tasks::BulkAction,
+        tasks::BulkResponse,
+        tasks::NewNoteRequest,
+        sessions::LoginRequest,
+        sessions::SessionInfo,
+        invitations::InvitationInfo,
+        invitations::RedeemRequest,
+        ErrorBody,
+    ))
+)]
+struct ApiDoc;
+
+// Shared by every handler. All of it is cheap to clone; clones share the pool, metrics and
+// event channel.
+#[derive(Clone)]
+struct AppState {
+    // As the user who started the server; requests use their own (see auth.rs)
+    repo: TaskRepository,
+    events: Events,
+    graphql: graphql::TaskSchema,
+    // Set when [oidc] is configured
+    oidc: Option<oidc::Oidc>,
+    // Checks the passwords of POST /session when [ldap] is configured
+    directory: Option<Directory>,
+}
+
+// `task serve`: a JSON API over the same repository the CLI uses, and optionally gRPC on a
+// second port, until Ctrl-C or SIGTERM. Once there are accounts, requests authenticate with API
+// tokens or session cookies (see auth.rs); what is sent about a change shows what its user can see.
+// The HTTP API limits request rates and body sizes as [server] says (see limits.rs).
+pub async fn run(repo: TaskRepository, config: &Config, args: ServeArgs) -> Result<()> {
+    metrics::install_prometheus()?;
+    let events = Events::new();
+    spawn_notifications(repo.clone(), config.clone(), Webhooks::new(config)?, &events);
+    #[cfg(feature = "integrations")]
+    telegram::spawn_notices(&repo, config)?;
+    let graphql = graphql::schema(events.clone());
+    let grpc = grpc::service(repo.clone(), events.clone());
+    let oidc = oidc::Oidc::new(config)?;
+    if let Some(oidc) = &oidc {
+        println!("Logins through {} start at /oidc/login", oidc.issuer());
+    }
+    let directory = Directory::new(config)?;
+    let app = router(AppState { repo, events, graphql, oidc, directory }, config);
+
+    let listener = bind(args.listen).await?;
+    let addr = local_addr(&listener, args.listen);
+    println!(
+        "Serving the task API on http://{} (OpenAPI document at /openapi.json, GraphQL at /graphql, Atom feed at \
+         /feed.atom, metrics at /metrics)",
+        addr
+    );
+    let grpc_listener = match args.grpc {
+        Some(grpc_addr) => {
+            let listener = bind(grpc_addr).await?;
+            println!("Serving gRPC (task.v1.TaskService) on {}", local_addr(&listener, grpc_addr));
+            Some(listener)
+        }
+        None => None,
+    };
+
+    // Both servers stop on the same signal
+    let (stop_tx, stop_rx) = watch::channel(false);
+    tokio::spawn(async move {
+        crate::shutdown_signal().await;
+        let _ = stop_tx.send(true);
+    });
+
+    let http = async {
+        // The peer addresses are for the rate limits
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(stopped(stop_rx.clone()))
+            .await?;
+        Ok::<_, TaskError>(())
+    };
+    let grpc = async {
+        if let Some(listener) = grpc_listener {
+            tonic::transport::Server::builder()
+                .add_service(grpc)
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), stopped(stop_rx.clone()))
+                .await
+                .map_err(|e| TaskError::Io(std::io::Error::other(e)))?;
+        }
+        Ok(())
+    };
+    tokio::try_join!(http, grpc)?;
+
+    println!("Server stopped.");
+    Ok(())
+}
+
+// Passes changes on to the configured webhooks and chat channels in the background, one event at a
+// time so their order is kept. Each is looked at as the user who made the change.
+fn spawn_notifications(repo: TaskRepository, config: Config, webhooks: Webhooks, events: &Events) {
+    let mut events = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let event = match events.recv().await {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "task events were not sent to webhooks (too many at once)");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let kind = match event.kind {
+                TaskEventKind::Created => WebhookEvent::Created,
+                TaskEventKind::Completed => WebhookEvent::Completed,
+                TaskEventKind::Deleted => WebhookEvent::Deleted,
+                TaskEventKind::Updated => continue,
+            };
+            let repo = repo.clone().with_access(event.access);
+            if kind == WebhookEvent::Completed
+                && let Err(e) = notify::task_completed(&repo, &config, event.id).await
+            {
+                tracing::warn!(task_id = event.id, error = %e, "could not post task to chat");
+            }
+            if let Err(e) = webhooks.task_event(&repo, kind, event.id).await {
+                tracing::warn!(task_id = event.id, error = %e, "could not send webhooks for task");
+            }
+        }
+    });
+}
+
+async fn bind(addr: SocketAddr) -> Result<TcpListener> {
+    TcpListener::bind(addr)
+        .await
+        .map_err(|e| TaskError::Config(format!("Could not listen on {}: {}", addr, e)))
+}
+
+async fn stopped(mut stop: watch::Receiver<bool>) {
+    let _ = stop.wait_for(|stop| *stop).await;
+}
+
+fn router(state: AppState, config: &Config) -> Router {
+    Router::new()
+        .route("/openapi.json", get(openapi))
+        .route("/graphql", get(graphql::playground).post(graphql::execute))
+        .merge(tasks::routes())
+        .merge(feed::routes())
+        .merge(sessions::routes())
+        .merge(invitations::routes())
+        .merge(oidc::routes())
+        .route_layer(middleware::from_fn(count_requests))
+        // Not counted itself, so scrapes don't show up in what they scrape
+        .merge(metrics::routes())
+        .with_state(state)
+        // Larger bodies get a 413 before any handler reads them
+        .layer(RequestBodyLimitLayer::new(limits::max_body(config)))
+        .layer(middleware::from_fn_with_state(limits::RateLimits::new(config), limits::limit_rate))
+        // A span per request, with its method and path, around the events of the handler
+        .layer(TraceLayer::new_for_http())
+}
+
+// Counts each request that matched a route, by the route rather than the path, so
+// /tasks/1 and /tasks/2 are one command
+async fn count_requests(request: Request, next: Next) -> Response {
+    let command = match request.extensions().get::<MatchedPath>() {
+        Some(path) => format!("{} {}", request.method(), path.as_str()),
+        None => return next.run(request).await,
+    };
+    let response = next.run(request).await;
+    let status = response.status();
+    metrics::record_command(command, !status.is_client_error() && !status.is_server_error());
+    response
+}
+
+async fn openapi() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+// With port 0 the OS picks one; show the real address
+fn local_addr(listener: &TcpListener, requested: SocketAddr) -> SocketAddr {
+    listener.local_addr().unwrap_or(requested)
+}
+
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+struct ErrorBody {
+    error: String,
+}
+
+// Error responses are `{"error": "..."}` with a matching status code
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn not_found(message: impl Into<String>) -> Self {
+        ApiError { status: StatusCode::NOT_FOUND, message: message.into() }
+    }
+
+    fn bad_request(message: impl Into<String>) -> Self {
+        ApiError { status: StatusCode::BAD_REQUEST, message: message.into() }
+    }
+
+    fn unauthorized(message: impl Into<String>) -> Self {
+        ApiError { status: StatusCode::UNAUTHORIZED, message: message.into() }
+    }
+}
+
+impl From<TaskError> for ApiError {
+    fn from(e: TaskError) -> Self {
+        let status = match e {
+            TaskError::InvalidInput(_) => StatusCode::BAD_REQUEST,
+            TaskError::Forbidden(_) => StatusCode::FORBIDDEN,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        ApiError { status, message: e.to_string() }
+    }
+}
+
+impl From<sqlx::Error> for ApiError {
+    fn from(e: sqlx::Error) -> Self {
+        TaskError::from(e).into()
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if self.status.is_server_error() {
+            tracing::error!(error = %self.message, "request failed");
+        }
+        (self.status, Json(ErrorBody { error: self.message })).into_response()
+    }
+}
+
+type ApiResult<T> = std::result::Result<T, ApiError>;
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 500;
+
+fn limit(requested: Option<u32>) -> u32 {
+    requested.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+}
+
+// Cursors are "<created_at as Unix seconds>.<id>"; clients just pass them back
+fn format_cursor(cursor: ListCursor) -> String {
+    format!("{}.{}", cursor.created_at.and_utc().timestamp(), cursor.id)
+}
+
+fn parse_cursor(value: &str) -> std::result::Result<ListCursor, String> {
+    let invalid = || format!("invalid cursor '{}'", value);
+    let (seconds, id) = value.split_once('.').ok_or_else(invalid)?;
+    let seconds: i64 = seconds.parse().map_err(|_| invalid())?;
+    let created_at = DateTime::from_timestamp(seconds, 0).ok_or_else(invalid)?.naive_utc();
+    Ok(ListCursor { created_at, id: id.parse().map_err(|_| invalid())? })
+}
+
+// Reads a GET /tasks query string as the handler does, for the fuzz targets in fuzz/: whatever
+// a client sends must come back as a filter or an error message, never a panic
+pub fn parse_list_query(query: &str) -> std::result::Result<(TaskFilter, Option<ListCursor>, u32), String> {
+    let query: tasks::ListQuery = serde_urlencoded::from_str(query).map_err(|e| e.to_string())?;
+    query.into_request()
+}
+
+// A task added through the API is checked like an imported row
+fn new_task_bundle(
+    description: &str,
+    due_at: Option<NaiveDateTime>,
+    priority: Option<Priority>,
+    project: Option<String>,
+    tags: Vec<String>,
+) -> std::result::Result<TaskBundle, String> {
+    let mut task = import::new_task(description.trim().to_string());
+    task.due_at = due_at;
+    task.priority = priority;
+    let bundle = TaskBundle { tags, project, ..import::bundle(task) };
+    import::validate(&bundle)?;
+    Ok(bundle)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::fixtures;
+
+    proptest! {
+        // Clients get cursors from `format_cursor` and hand them back unchanged
+        #[test]
+        fn cursors_read_back(created_at in fixtures::date_time(), id in any::<i32>()) {
+            let cursor = parse_cursor(&format_cursor(ListCursor { created_at, id })).map_err(TestCaseError::fail)?;
+            prop_assert_eq!(cursor.created_at, created_at);
+            prop_assert_eq!(cursor.id, id);
+        }
+
+        // Anything else is a 400, never a panic
+        #[test]
+        fn any_cursor_parses_or_is_rejected(value in "\\PC*|-?[0-9]{1,20}\\.-?[0-9]{1,12}") {
+            let _ = parse_cursor(&value);
+        }
+
+        // The filters of GET /tasks, mostly with the parameter names it knows
+        #[test]
+        fn any_list_query_parses_or_is_rejected(
+            query in "\\PC*|((completed|project|tag|priority|due_before|due_after|limit|cursor)=[^&]{0,20}&?){0,6}",
+        ) {
+            if let Ok((_, _, limit)) = parse_list_query(&query) {
+                prop_assert!((1..=MAX_LIMIT).contains(&limit));
+            }
+        }
+    }
+}
@@ -0,0 +1,200 @@
+use axum::extract::{Query, State};
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::response::{IntoResponse, Redirect, Response};
+use axum::routing::get;
+use axum::Router;
+use serde::Deserialize;
+
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::repository::TaskRepository;
+use crate::secrets::{self, Service};
+use crate::sync::{remote_error, send_json};
+use crate::users;
+
+use super::{sessions, ApiError, ApiResult, AppState};
+
+const SERVICE: &str = "OpenID Connect";
+// Holds the `state` of a login between the redirect to the provider and its callback
+const STATE_COOKIE: &str = "task_oidc_state";
+// Time the user has to log in at the provider
+const STATE_MINUTES: i64 = 10;
+// Length of users.username
+const MAX_USERNAME: usize = 64;
+
+// Logins through an OpenID Connect provider (Google, Keycloak, Entra ID and so on), as an
+// alternative to local passwords: GET /oidc/login sends the browser to the provider, which sends
+// it back to /oidc/callback with a code for the user's details. Someone logging in for the first
+// time gets an account named after their provider username or email address.
+#[derive(Debug, Clone)]
+pub(super) struct Oidc {
+    issuer: String,
+    client_id: String,
+    client_secret: String,
+    redirect_url: String,
+    http: reqwest::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct Discovery {
+    authorization_endpoint: String,
+    token_endpoint: String,
+    userinfo_endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserInfo {
+    sub: String,
+    preferred_username: Option<String>,
+    email: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
+
+impl Oidc {
+    // None unless [oidc] names an issuer
+    pub(super) fn new(config: &Config) -> Result<Option<Self>> {
+        let oidc = &config.oidc;
+        let Some(issuer) = &oidc.issuer else {
+            return Ok(None);
+        };
+        let (Some(client_id), Some(redirect_url)) = (&oidc.client_id, &oidc.redirect_url) else {
+            return Err(TaskError::Config(format!(
+                "Logins through {} need `client_id` and `redirect_url` in the [oidc] section of {}.",
+                issuer,
+                Config::path().display()
+            )));
+        };
+        Ok(Some(Oidc {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            client_id: client_id.clone(),
+            client_secret: secrets::token(config, Service::Oidc)?,
+            redirect_url: redirect_url.clone(),
+            http: reqwest::Client::new(),
+        }))
+    }
+
+    pub(super) fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    // The provider's endpoints, fetched for each login so changes at the provider are picked up
+    async fn discover(&self) -> Result<Discovery> {
+        let url = format!("{}/.well-known/openid-configuration", self.issuer);
+        send_json(SERVICE, self.http.get(url)).await
+    }
+
+    // The user's details for the code of a callback. They come straight from the provider over
+    // TLS, so unlike an ID token passed through the browser they need no signature check.
+    async fn user_info(&self, code: &str) -> Result<UserInfo> {
+        let discovery = self.discover().await?;
+        let form = [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_url.as_str()),
+            ("client_id", self.client_id.as_str()),
+            ("client_secret", self.client_secret.as_str()),
+        ];
+        let token: TokenResponse = send_json(SERVICE, self.http.post(&discovery.token_endpoint).form(&form)).await?;
+        send_json(SERVICE, self.http.get(&discovery.userinfo_endpoint).bearer_auth(&token.access_token)).await
+    }
+}
+
+pub(super) fn routes() -> Router<AppState> {
+    Router::new().route("/oidc/login", get(login)).route("/oidc/callback", get(callback))
+}
+
+async fn login(State(state): State<AppState>) -> ApiResult<Response> {
+    let oidc = configured(&state)?;
+    let discovery = oidc.discover().await?;
+    let login_state = users::new_token();
+    let mut url = reqwest::Url::parse(&discovery.authorization_endpoint)
+        .map_err(|e| remote_error(SERVICE, format!("invalid authorization endpoint: {}", e)))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &oidc.client_id)
+        .append_pair("redirect_uri", &oidc.redirect_url)
+        .append_pair("scope", "openid email profile")
+        .append_pair("state", &login_state);
+
+    // Lax rather than Strict: the provider's redirect back is a request from another site
+    let cookie = format!(
+        "{}={}; Path=/oidc; HttpOnly; SameSite=Lax; Max-Age={}",
+        STATE_COOKIE,
+        login_state,
+        STATE_MINUTES * 60
+    );
+    let mut response = Redirect::to(url.as_str()).into_response();
+    if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+        response.headers_mut().insert(header::SET_COOKIE, cookie);
+    }
+    Ok(response)
+}
+
+async fn callback(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<CallbackQuery>,
+) -> ApiResult<Response> {
+    let oidc = configured(&state)?;
+    if let Some(error) = query.error {
+        return Err(ApiError::unauthorized(format!("The identity provider refused the login: {}", error)));
+    }
+    // The state proves the login was started from this browser, not planted by another site
+    let expected = sessions::cookie(&headers, STATE_COOKIE);
+    let (Some(code), Some(login_state)) = (query.code, query.state) else {
+        return Err(ApiError::bad_request("The callback needs `code` and `state`."));
+    };
+    if expected != Some(login_state.as_str()) {
+        return Err(ApiError::unauthorized("The login has expired or was started elsewhere; try again."));
+    }
+
+    let info = oidc.user_info(&code).await?;
+    let user_id = match state.repo.identity_user(&oidc.issuer, &info.sub).await? {
+        Some(user) => user.id,
+        None => provision(&state.repo, oidc, &info).await?,
+    };
+    let mut response = sessions::start(&state.repo, user_id, None).await?;
+    let cleared = format!("{}=; Path=/oidc; HttpOnly; SameSite=Lax; Max-Age=0", STATE_COOKIE);
+    if let Ok(cleared) = HeaderValue::from_str(&cleared) {
+        response.headers_mut().append(header::SET_COOKIE, cleared);
+    }
+    Ok(response)
+}
+
+// A new account for someone logging in for the first time, under the first free name made from
+// their provider username or email address
+async fn provision(repo: &TaskRepository, oidc: &Oidc, info: &UserInfo) -> Result<i32> {
+    let wanted = info
+        .preferred_username
+        .as_deref()
+        .or_else(|| info.email.as_deref().and_then(|email| email.split('@').next()))
+        .unwrap_or("user");
+    let base: String = wanted.chars().filter(|c| !c.is_whitespace()).take(MAX_USERNAME - 4).collect();
+    let base = if base.is_empty() { "user".to_string() } else { base };
+
+    let mut username = base.clone();
+    let mut suffix = 1;
+    while repo.user_by_name(&username).await?.is_some() {
+        suffix += 1;
+        username = format!("{}-{}", base, suffix);
+    }
+    // A hash no password matches (see the user_identities migration)
+    let (id, _) = repo.create_user(&username, "!").await?;
+    repo.link_identity(&oidc.issuer, &info.sub, id).await?;
+    Ok(id)
+}
+
+fn configured(state: &AppState) -> ApiResult<&Oidc> {
+    state.oidc.as_ref().ok_or_else(|| ApiError::not_found("Logins through an identity provider are not set up."))
+}
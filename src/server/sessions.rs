@@ -0,0 +1,130 @@
+use axum::extract::State;
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::{Duration, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::repository::TaskRepository;
+use crate::users;
+
+use super::{ApiError, ApiResult, AppState};
+
+// Holds the session token; the database only has its hash, as for CLI logins
+pub(super) const SESSION_COOKIE: &str = "task_session";
+// Browser sessions are shorter than CLI logins; clients refresh them while in use
+const SESSION_DAYS: i64 = 1;
+
+pub(super) fn routes() -> Router<AppState> {
+    Router::new().route("/session", post(login).delete(logout)).route("/session/refresh", post(refresh))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    username: String,
+    password: String,
+    /// Work in this workspace instead of the personal space
+    workspace: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionInfo {
+    /// When the session ends unless refreshed before
+    expires_at: NaiveDateTime,
+}
+
+/// Logs in and sets the session cookie
+#[utoipa::path(
+    post,
+    path = "/session",
+    request_body = LoginRequest,
+    responses((status = 200, body = SessionInfo), (status = 401, body = super::ErrorBody))
+)]
+pub async fn login(State(state): State<AppState>, Json(request): Json<LoginRequest>) -> ApiResult<Response> {
+    let repo = &state.repo;
+    if repo.user_count().await? == 0 {
+        return Err(ApiError::bad_request("There are no accounts yet, so there is nothing to log in to."));
+    }
+    // The same answer for unknown users and wrong passwords
+    let user = users::verify(repo, state.directory.as_ref(), request.username.trim(), &request.password).await?;
+    let Some(user) = user else {
+        return Err(ApiError::unauthorized("Wrong username or password."));
+    };
+    let workspace = match request.workspace.as_deref().map(str::trim) {
+        Some(name) => match repo.workspace_id(name).await? {
+            Some(id) if repo.is_member(id, user.id).await? => Some(id),
+            _ => return Err(ApiError::bad_request(format!("You are not in a workspace named {}.", name))),
+        },
+        None => None,
+    };
+
+    start(repo, user.id, workspace).await
+}
+
+// Logs the user in: a new session, and its cookie on the response
+pub(super) async fn start(repo: &TaskRepository, user_id: i32, workspace: Option<i32>) -> ApiResult<Response> {
+    let token = users::new_token();
+    let hash = users::hash_token(&token);
+    repo.create_session(&hash, user_id, SESSION_DAYS).await?;
+    if workspace.is_some() {
+        repo.set_session_workspace(&hash, workspace).await?;
+    }
+    Ok(with_cookie(&token))
+}
+
+/// Replaces the session with a new one that lasts another day
+#[utoipa::path(
+    post,
+    path = "/session/refresh",
+    responses((status = 200, body = SessionInfo), (status = 401, body = super::ErrorBody))
+)]
+pub async fn refresh(State(state): State<AppState>, headers: HeaderMap) -> ApiResult<Response> {
+    let Some(old) = session_cookie(&headers) else {
+        return Err(ApiError::unauthorized("Not logged in."));
+    };
+    let token = users::new_token();
+    if !state.repo.rotate_session(&users::hash_token(old), &users::hash_token(&token), SESSION_DAYS).await? {
+        return Err(ApiError::unauthorized("The session has expired; log in again."));
+    }
+    Ok(with_cookie(&token))
+}
+
+/// Ends the session and clears the cookie
+#[utoipa::path(delete, path = "/session", responses((status = 204)))]
+pub async fn logout(State(state): State<AppState>, headers: HeaderMap) -> ApiResult<Response> {
+    if let Some(token) = session_cookie(&headers) {
+        state.repo.delete_session(&users::hash_token(token)).await?;
+    }
+    let cookie = format!("{}=; Path=/; HttpOnly; SameSite=Strict; Max-Age=0", SESSION_COOKIE);
+    Ok((StatusCode::NO_CONTENT, [(header::SET_COOKIE, cookie)]).into_response())
+}
+
+// SameSite=Strict keeps other sites from making requests with the cookie, which would otherwise
+// be a way to change tasks from a page the user happens to visit
+fn with_cookie(token: &str) -> Response {
+    let max_age = SESSION_DAYS * 24 * 60 * 60;
+    let cookie = format!("{}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={}", SESSION_COOKIE, token, max_age);
+    let mut response = Json(SessionInfo { expires_at: Local::now().naive_local() + Duration::days(SESSION_DAYS) })
+        .into_response();
+    if let Ok(cookie) = HeaderValue::from_str(&cookie) {
+        response.headers_mut().insert(header::SET_COOKIE, cookie);
+    }
+    response
+}
+
+// The session token of the request's cookies, if any
+pub(super) fn session_cookie(headers: &HeaderMap) -> Option<&str> {
+    cookie(headers, SESSION_COOKIE)
+}
+
+pub(super) fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|cookie| cookie.trim().strip_prefix(name)?.strip_prefix('='))
+        .filter(|value| !value.is_empty())
+}
@@ -0,0 +1,324 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::import;
+use crate::notes;
+use crate::priority::Priority;
+use crate::repository::{ChecklistItem, ListCursor, Note, TaskChanges, TaskFilter, TaskRepository};
+use crate::Task;
+
+use super::auth::Authed;
+use super::{format_cursor, limit, new_task_bundle, parse_cursor, ApiError, ApiResult, AppState, TaskEventKind};
+
+pub(super) fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/tasks", get(list).post(create))
+        .route("/tasks/search", get(search))
+        .route("/tasks/bulk", post(bulk))
+        .route("/tasks/:id", get(show).patch(update).delete(delete))
+        .route("/tasks/:id/notes", post(add_note))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    /// Only completed (true) or pending (false) tasks
+    completed: Option<bool>,
+    /// Project name
+    project: Option<String>,
+    /// Tag name
+    tag: Option<String>,
+    priority: Option<Priority>,
+    /// Due strictly before this time, e.g. 2026-10-21T00:00:00
+    due_before: Option<NaiveDateTime>,
+    /// Due at or after this time
+    due_after: Option<NaiveDateTime>,
+    /// Page size, at most 500 (default 50)
+    limit: Option<u32>,
+    /// `next` from the previous page
+    cursor: Option<String>,
+}
+
+impl ListQuery {
+    // The filter, where to continue and the page size asked for
+    pub(super) fn into_request(self) -> std::result::Result<(TaskFilter, Option<ListCursor>, u32), String> {
+        let after = self.cursor.as_deref().map(parse_cursor).transpose()?;
+        let filter = TaskFilter {
+            completed: self.completed,
+            project: self.project,
+            tag: self.tag,
+            priority: self.priority,
+            due_before: self.due_before,
+            due_after: self.due_after,
+            assignee: None,
+        };
+        Ok((filter, after, limit(self.limit)))
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Words to search for
+    q: String,
+    /// Number of results, at most 500 (default 50)
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskList {
+    tasks: Vec<Task>,
+    /// Pass as `cursor` to get the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskDetails {
+    #[serde(flatten)]
+    task: Task,
+    project: Option<String>,
+    tags: Vec<String>,
+    checklist: Vec<ChecklistItem>,
+    notes: Vec<Note>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewTaskRequest {
+    description: String,
+    due_at: Option<NaiveDateTime>,
+    priority: Option<Priority>,
+    /// Created if it doesn't exist
+    project: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+// Fields left out stay as they are; `null` clears due_at and priority
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateTaskRequest {
+    description: Option<String>,
+    completed: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<NaiveDateTime>)]
+    due_at: Option<Option<NaiveDateTime>>,
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<Priority>)]
+    priority: Option<Option<Priority>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct NewNoteRequest {
+    /// @username mentions notify users who can see the task
+    body: String,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BulkAction {
+    Complete,
+    Delete,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BulkRequest {
+    action: BulkAction,
+    ids: Vec<i32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BulkResponse {
+    /// Number of the given tasks that existed
+    affected: u64,
+    /// Tasks left as they were because the chunk they were in failed; see [bulk] in the config
+    failed: Vec<i32>,
+}
+
+// Tells a field that is `null` (Some(None)) apart from one that is missing (None, via `default`)
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Tasks matching the filters, newest first
+#[utoipa::path(get, path = "/tasks", params(ListQuery), responses((status = 200, body = TaskList)))]
+pub async fn list(Authed(repo): Authed, Query(query): Query<ListQuery>) -> ApiResult<Json<TaskList>> {
+    let (filter, after, limit) = query.into_request().map_err(ApiError::bad_request)?;
+    let page = repo.filter_page(&filter, after, limit).await?;
+    Ok(Json(TaskList { tasks: page.tasks, next: page.next.map(format_cursor) }))
+}
+
+/// Tasks whose description matches the words, best matches first
+#[utoipa::path(get, path = "/tasks/search", params(SearchQuery), responses((status = 200, body = TaskList)))]
+pub async fn search(Authed(repo): Authed, Query(query): Query<SearchQuery>) -> ApiResult<Json<TaskList>> {
+    if query.q.trim().is_empty() {
+        return Err(ApiError::bad_request("q must not be empty"));
+    }
+    let page = repo.search_page(query.q.trim(), None, limit(query.limit)).await?;
+    Ok(Json(TaskList { tasks: page.tasks, next: None }))
+}
+
+/// Adds a task
+#[utoipa::path(
+    post,
+    path = "/tasks",
+    request_body = NewTaskRequest,
+    responses((status = 201, body = TaskDetails), (status = 400, body = super::ErrorBody))
+)]
+pub async fn create(
+    State(state): State<AppState>,
+    Authed(repo): Authed,
+    Json(request): Json<NewTaskRequest>,
+) -> ApiResult<(StatusCode, Json<TaskDetails>)> {
+    let bundle = new_task_bundle(&request.description, request.due_at, request.priority, request.project, request.tags)
+        .map_err(ApiError::bad_request)?;
+    let ids = repo.import(std::slice::from_ref(&bundle)).await?;
+    state.events.publish(TaskEventKind::Created, ids[0], repo.access());
+    Ok((StatusCode::CREATED, Json(details(&repo, ids[0]).await?)))
+}
+
+/// A task with its project, tags, checklist and notes
+#[utoipa::path(
+    get,
+    path = "/tasks/{id}",
+    params(("id" = i32, Path, description = "Task id")),
+    responses((status = 200, body = TaskDetails), (status = 404, body = super::ErrorBody))
+)]
+pub async fn show(Authed(repo): Authed, Path(id): Path<i32>) -> ApiResult<Json<TaskDetails>> {
+    Ok(Json(details(&repo, id).await?))
+}
+
+/// Changes some fields of a task
+#[utoipa::path(
+    patch,
+    path = "/tasks/{id}",
+    params(("id" = i32, Path, description = "Task id")),
+    request_body = UpdateTaskRequest,
+    responses(
+        (status = 200, body = TaskDetails),
+        (status = 400, body = super::ErrorBody),
+        (status = 404, body = super::ErrorBody)
+    )
+)]
+pub async fn update(
+    State(state): State<AppState>,
+    Authed(repo): Authed,
+    Path(id): Path<i32>,
+    Json(request): Json<UpdateTaskRequest>,
+) -> ApiResult<Json<TaskDetails>> {
+    let description = request.description.map(|description| description.trim().to_string());
+    if let Some(description) = &description {
+        let bundle = import::bundle(import::new_task(description.clone()));
+        import::validate(&bundle).map_err(ApiError::bad_request)?;
+    }
+
+    let changes =
+        TaskChanges { description, completed: request.completed, due_at: request.due_at, priority: request.priority };
+    let Some(updated) = repo.update(id, &changes).await? else {
+        return Err(not_found(id));
+    };
+    if !changes.is_empty() {
+        let kind = if updated.completed { TaskEventKind::Completed } else { TaskEventKind::Updated };
+        state.events.publish(kind, id, repo.access());
+    }
+    Ok(Json(details(&repo, id).await?))
+}
+
+/// Deletes a task
+#[utoipa::path(
+    delete,
+    path = "/tasks/{id}",
+    params(("id" = i32, Path, description = "Task id")),
+    responses((status = 204), (status = 404, body = super::ErrorBody))
+)]
+pub async fn delete(
+    State(state): State<AppState>,
+    Authed(repo): Authed,
+    Path(id): Path<i32>,
+) -> ApiResult<StatusCode> {
+    if repo.delete(id).await? {
+        state.events.publish(TaskEventKind::Deleted, id, repo.access());
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(not_found(id))
+    }
+}
+
+/// Adds a note by the user to a task
+#[utoipa::path(
+    post,
+    path = "/tasks/{id}/notes",
+    params(("id" = i32, Path, description = "Task id")),
+    request_body = NewNoteRequest,
+    responses(
+        (status = 201, body = TaskDetails),
+        (status = 400, body = super::ErrorBody),
+        (status = 404, body = super::ErrorBody)
+    )
+)]
+pub async fn add_note(
+    Authed(repo): Authed,
+    Path(id): Path<i32>,
+    Json(request): Json<NewNoteRequest>,
+) -> ApiResult<(StatusCode, Json<TaskDetails>)> {
+    let body = request.body.trim();
+    if body.is_empty() {
+        return Err(ApiError::bad_request("A note needs some text."));
+    }
+    if repo.add_comment(id, body, &notes::mentions(body)).await?.is_none() {
+        return Err(not_found(id));
+    }
+    Ok((StatusCode::CREATED, Json(details(&repo, id).await?)))
+}
+
+/// Completes or deletes many tasks at once, in chunks that succeed or fail on their own
+#[utoipa::path(post, path = "/tasks/bulk", request_body = BulkRequest, responses((status = 200, body = BulkResponse)))]
+pub async fn bulk(
+    State(state): State<AppState>,
+    Authed(repo): Authed,
+    Json(request): Json<BulkRequest>,
+) -> ApiResult<Json<BulkResponse>> {
+    let (kind, mut report) = match request.action {
+        BulkAction::Complete => (TaskEventKind::Completed, repo.complete_in_chunks(&request.ids).await),
+        BulkAction::Delete => (TaskEventKind::Deleted, repo.delete_in_chunks(&request.ids).await),
+    };
+    // Nothing went through, e.g. with a read-only token: answer as a single change would
+    if report.done.is_empty() && !report.failures.is_empty() {
+        return Err(report.failures.remove(0).error.into());
+    }
+    let mut failed = Vec::new();
+    for failure in &report.failures {
+        tracing::warn!(error = %failure.error, tasks = failure.items.len(), "bulk chunk failed");
+        failed.extend_from_slice(&request.ids[failure.items.clone()]);
+    }
+    tracing::debug!(tasks = request.ids.len(), chunks = report.chunks, timing = %report.timing(), "bulk change");
+    state.events.publish_all(kind, &report.done, repo.access());
+    Ok(Json(BulkResponse { affected: report.done.len() as u64, failed }))
+}
+
+async fn details(repo: &TaskRepository, id: i32) -> ApiResult<TaskDetails> {
+    let task = repo.get(id).await?.ok_or_else(|| not_found(id))?;
+    let project = match task.project_id {
+        Some(project_id) => repo.project_name(project_id).await?,
+        None => None,
+    };
+    Ok(TaskDetails {
+        project,
+        tags: repo.tags(id).await?,
+        checklist: repo.checklist(id).await?,
+        notes: repo.notes(id).await?,
+        task,
+    })
+}
+
+fn not_found(id: i32) -> ApiError {
+    ApiError::not_found(format!("No task with id {}", id))
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::NaiveTime;
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::cli::SettingsCommand;
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::repository::TaskRepository;
+
+// Preferences of the logged-in account, kept in user_settings so they follow it to every
+// machine. Each has a key of the same name in the config file (`timezone` and `default_view` at
+// the top, the rest in [desktop] and [email], where email is `to`); what a machine's file sets
+// wins over the account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Setting {
+    /// IANA name such as Europe/Berlin, for times shown and reminders; TZ overrides it
+    Timezone,
+    /// What `task` without a command shows: menu, list or assigned
+    #[value(name = "default_view")]
+    DefaultView,
+    /// Minutes before a task is due that `task watch` notifies of it
+    #[value(name = "remind_minutes")]
+    RemindMinutes,
+    /// HH:MM-HH:MM during which `task watch` holds notifications back
+    #[value(name = "quiet_hours")]
+    QuietHours,
+    /// Hours before a task is due that `task notify email` reminds of it
+    #[value(name = "email_remind_hours")]
+    EmailRemindHours,
+    /// HH:MM from which `task notify email` and the daemon send the daily digest
+    #[value(name = "digest_time")]
+    DigestTime,
+    /// Address of the account's email, e.g. the daemon's digest; also `task notify email`'s
+    Email,
+}
+
+impl Setting {
+    pub fn name(self) -> &'static str {
+        match self {
+            Setting::Timezone => "timezone",
+            Setting::DefaultView => "default_view",
+            Setting::RemindMinutes => "remind_minutes",
+            Setting::QuietHours => "quiet_hours",
+            Setting::EmailRemindHours => "email_remind_hours",
+            Setting::DigestTime => "digest_time",
+            Setting::Email => "email",
+        }
+    }
+
+    // Where the config file sets it
+    fn config_key(self) -> &'static str {
+        match self {
+            Setting::Timezone => "timezone",
+            Setting::DefaultView => "default_view",
+            Setting::RemindMinutes => "[desktop] remind_minutes",
+            Setting::QuietHours => "[desktop] quiet_hours",
+            Setting::EmailRemindHours => "[email] remind_hours",
+            Setting::DigestTime => "[email] digest_time",
+            Setting::Email => "[email] to",
+        }
+    }
+
+    // The value as it is stored, if it is one the setting can have
+    fn check(self, value: &str) -> std::result::Result<String, String> {
+        let value = value.trim();
+        let number =
+            |value: &str| value.parse::<u32>().map(|_| value.to_string()).map_err(|_| "a whole number".to_string());
+        let time = |value: &str| NaiveTime::parse_from_str(value.trim(), "%H:%M").map_err(|_| "HH:MM".to_string());
+        match self {
+            Setting::Timezone => check_timezone(value).map(|_| value.to_string()),
+            Setting::DefaultView => value.parse::<View>().map(|view| view.to_string()),
+            Setting::RemindMinutes | Setting::EmailRemindHours => number(value),
+            Setting::DigestTime => time(value).map(|_| value.to_string()),
+            Setting::Email => {
+                let address = value.split_once('@').filter(|(user, domain)| !user.is_empty() && !domain.is_empty());
+                match address {
+                    Some(_) if !value.contains(char::is_whitespace) => Ok(value.to_string()),
+                    _ => Err("an email address".to_string()),
+                }
+            }
+            Setting::QuietHours => {
+                let (start, end) = value.split_once('-').ok_or_else(|| "HH:MM-HH:MM".to_string())?;
+                time(start).and(time(end)).map(|_| value.to_string()).map_err(|_| "HH:MM-HH:MM".to_string())
+            }
+        }
+    }
+
+    // What the config has for it
+    fn current(self, config: &Config) -> Option<String> {
+        match self {
+            Setting::Timezone => config.timezone.clone(),
+            Setting::DefaultView => config.default_view.map(|view| view.to_string()),
+            Setting::RemindMinutes => config.desktop.remind_minutes.map(|minutes| minutes.to_string()),
+            Setting::QuietHours => config.desktop.quiet_hours.clone(),
+            Setting::EmailRemindHours => config.email.remind_hours.map(|hours| hours.to_string()),
+            Setting::DigestTime => config.email.digest_time.clone(),
+            Setting::Email => config.email.to.clone(),
+        }
+    }
+
+    // Sets the config's value from a checked one, unless the config file has its own
+    fn fill(self, config: &mut Config, value: &str) {
+        match self {
+            Setting::Timezone => fill(&mut config.timezone, Some(value.to_string())),
+            Setting::DefaultView => fill(&mut config.default_view, value.parse().ok()),
+            Setting::RemindMinutes => fill(&mut config.desktop.remind_minutes, value.parse().ok()),
+            Setting::QuietHours => fill(&mut config.desktop.quiet_hours, Some(value.to_string())),
+            Setting::EmailRemindHours => fill(&mut config.email.remind_hours, value.parse().ok()),
+            Setting::DigestTime => fill(&mut config.email.digest_time, Some(value.to_string())),
+            Setting::Email => fill(&mut config.email.to, Some(value.to_string())),
+        }
+    }
+}
+
+fn fill<T>(slot: &mut Option<T>, value: Option<T>) {
+    if slot.is_none() {
+        *slot = value;
+    }
+}
+
+impl FromStr for Setting {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        Setting::value_variants()
+            .iter()
+            .copied()
+            .find(|setting| setting.name() == value)
+            .ok_or_else(|| format!("unknown setting '{}'", value))
+    }
+}
+
+// What `task` without a command shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum View {
+    // The interactive menu
+    Menu,
+    // Every task, as `task list`
+    List,
+    // The tasks assigned to the user, as `task assigned`
+    Assigned,
+}
+
+impl View {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            View::Menu => "menu",
+            View::List => "list",
+            View::Assigned => "assigned",
+        }
+    }
+}
+
+impl fmt::Display for View {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for View {
+    type Err = String;
+
+    fn from_str(value: &str) -> std::result::Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "menu" => Ok(View::Menu),
+            "list" => Ok(View::List),
+            "assigned" => Ok(View::Assigned),
+            _ => Err("menu, list or assigned".to_string()),
+        }
+    }
+}
+
+fn check_timezone(value: &str) -> std::result::Result<(), String> {
+    value.parse::<chrono_tz::Tz>().map(|_| ()).map_err(|_| "an IANA timezone name such as Europe/Berlin".to_string())
+}
+
+// The config with the account's settings filled in, for a repository that works as a user.
+// A stored value this version can't read, e.g. from a newer one, is skipped with a warning.
+pub async fn merge(repo: &TaskRepository, config: &Config) -> Result<Config> {
+    let mut config = config.clone();
+    if let Some(timezone) = &config.timezone {
+        check_timezone(timezone).map_err(|expected| {
+            let path = Config::path();
+            TaskError::Config(format!("Invalid timezone '{}' in {}; expected {}.", timezone, path.display(), expected))
+        })?;
+    }
+    if repo.user().is_none() {
+        return Ok(config);
+    }
+    for (name, value) in repo.settings().await? {
+        match name.parse::<Setting>().and_then(|setting| setting.check(&value).map(|value| (setting, value))) {
+            Ok((setting, value)) => setting.fill(&mut config, &value),
+            Err(_) => println!("Warning: ignoring the setting {} = '{}' of your account.", name, value),
+        }
+    }
+    Ok(config)
+}
+
+// Uses the configured timezone for local times, unless TZ is set
+pub fn apply_timezone(config: &Config) {
+    if let Some(timezone) = &config.timezone
+        && std::env::var_os("TZ").is_none()
+    {
+        // SAFETY: runs once at startup, as dotenv's changes to the environment do; the runtime
+        // and pool threads that exist by then don't read it
+        unsafe { std::env::set_var("TZ", timezone) };
+    }
+}
+
+// `task settings`: the config as given, before merging, shows where values come from
+pub async fn run(repo: &TaskRepository, config: &Config, command: SettingsCommand) -> Result<()> {
+    if repo.user().is_none() {
+        return Err(TaskError::InvalidInput(
+            "Settings belong to accounts; create the first with `task user add <name>`.".to_string(),
+        ));
+    }
+    match command {
+        SettingsCommand::List => {
+            let stored = repo.settings().await?;
+            for setting in Setting::value_variants() {
+                let account = stored.iter().find(|(name, _)| name == setting.name()).map(|(_, value)| value);
+                let line = match (setting.current(config), account) {
+                    (Some(local), Some(_)) => {
+                        format!("{} (from {}; the account's is ignored here)", local, setting.config_key())
+                    }
+                    (Some(local), None) => format!("{} (from {})", local, setting.config_key()),
+                    (None, Some(account)) => account.clone(),
+                    (None, None) => "(not set)".to_string(),
+                };
+                println!("{:<20} {}", setting.name(), line);
+            }
+        }
+        SettingsCommand::Set { setting, value } => {
+            let value = setting.check(&value).map_err(|expected| {
+                let message = format!("'{}' can't be the {}: expected {}.", value.trim(), setting.name(), expected);
+                TaskError::InvalidInput(message)
+            })?;
+            repo.set_setting(setting.name(), &value).await?;
+            println!("Set {} to {} for your account.", setting.name(), value);
+            if setting.current(config).is_some() {
+                println!("{} in {} still wins on this machine.", setting.config_key(), Config::path().display());
+            }
+        }
+        SettingsCommand::Unset { setting } => {
+            if repo.delete_setting(setting.name()).await? {
+                println!("Unset {} for your account.", setting.name());
+            } else {
+                println!("Your account has no {} setting.", setting.name());
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use crate::cli::ShareCommand;
+use crate::error::{Result, TaskError};
+use crate::repository::{TaskRepository, User};
+
+// Sharing gives another user access to tasks without changing who owns them. Read shares let
+// them see the tasks in lists, searches and exports; write shares also let them edit, complete
+// and delete them. The repository enforces both, so the API and the bot follow them as well.
+// Shared tasks are synced and reminded about only for their owner.
+pub async fn run(repo: &TaskRepository, command: ShareCommand) -> Result<()> {
+    if repo.user().is_none() {
+        return Err(TaskError::InvalidInput(
+            "Sharing needs accounts; create the first with `task user add <name>`.".to_string(),
+        ));
+    }
+    match command {
+        ShareCommand::Task { id, user, write } => {
+            let user = other_user(repo, &user).await?;
+            in_workspace(repo, &user).await?;
+            if !repo.share_task(id, user.id, write).await? {
+                return Err(TaskError::InvalidInput(format!("You have no task with ID {}.", id)));
+            }
+            println!("Shared task {} with {} ({}).", id, user.username, access(write));
+        }
+        ShareCommand::Project { name, user, write } => {
+            let user = other_user(repo, &user).await?;
+            in_workspace(repo, &user).await?;
+            let project_id = project_id(repo, &name).await?;
+            repo.share_project(project_id, user.id, write).await?;
+            println!("Shared your tasks in {} with {} ({}).", name, user.username, access(write));
+        }
+        ShareCommand::Remove { task, project, user } => {
+            let user = other_user(repo, &user).await?;
+            let (removed, subject) = match (task, project) {
+                (Some(id), _) => (repo.unshare_task(id, user.id).await?, format!("task {}", id)),
+                (None, Some(name)) => {
+                    let project_id = project_id(repo, &name).await?;
+                    (repo.unshare_project(project_id, user.id).await?, name)
+                }
+                (None, None) => unreachable!("clap requires --task or --project"),
+            };
+            if removed == 0 {
+                println!("You were not sharing {} with {}.", subject, user.username);
+            } else {
+                println!("Stopped sharing {} with {}.", subject, user.username);
+            }
+        }
+        ShareCommand::List => list(repo).await?,
+    }
+    Ok(())
+}
+
+async fn list(repo: &TaskRepository) -> Result<()> {
+    let shares = repo.shares().await?;
+    if shares.is_empty() {
+        println!("Nothing is shared with you, and you haven't shared anything.");
+        return Ok(());
+    }
+    println!("{:<8} {:<24} {:<16} {:<16} Access", "Kind", "Task/project", "Owner", "Shared with");
+    for share in &shares {
+        println!(
+            "{:<8} {:<24} {:<16} {:<16} {}",
+            share.kind,
+            share.subject,
+            share.owner,
+            share.user,
+            access(share.can_write)
+        );
+    }
+    Ok(())
+}
+
+async fn other_user(repo: &TaskRepository, username: &str) -> Result<User> {
+    let user = repo
+        .user_by_name(username.trim())
+        .await?
+        .ok_or_else(|| TaskError::InvalidInput(format!("There is no user named {}.", username.trim())))?;
+    if repo.user() == Some(user.id) {
+        return Err(TaskError::InvalidInput("Your tasks are already yours.".to_string()));
+    }
+    Ok(user)
+}
+
+// Tasks of a workspace are only shared with its members, who are the only ones to see them
+pub async fn in_workspace(repo: &TaskRepository, user: &User) -> Result<()> {
+    if let Some(workspace) = repo.workspace()
+        && !repo.is_member(workspace, user.id).await?
+    {
+        return Err(TaskError::InvalidInput(format!("{} is not in this workspace.", user.username)));
+    }
+    Ok(())
+}
+
+async fn project_id(repo: &TaskRepository, name: &str) -> Result<i32> {
+    repo.project_id(name)
+        .await?
+        .ok_or_else(|| TaskError::InvalidInput(format!("There is no project named {}.", name)))
+}
+
+fn access(can_write: bool) -> &'static str {
+    if can_write { "read-write" } else { "read-only" }
+}
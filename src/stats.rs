@@ -0,0 +1,597 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use chrono::{Datelike, Days, Local, NaiveDate, NaiveTime};
+
+use crate::activity::Action;
+use crate::aging;
+use crate::chart;
+use crate::config::ForecastConfig;
+use crate::error::{Result, TaskError};
+use crate::estimates;
+use crate::repository::{Breakdown, DueLoad, OpenChange, TaskRepository};
+use crate::streaks;
+use crate::timesheet;
+
+// Number of entries in the "slowest recent queries" list
+const SLOWEST_SHOWN: usize = 5;
+const BURNDOWN_WEEKS: u32 = 4;
+// Characters, for a day every dot column over the default four weeks
+const CHART_WIDTH: usize = 56;
+const CHART_HEIGHT: usize = 10;
+// A year, plus the week it started in
+const HEATMAP_WEEKS: u32 = 53;
+// Full weeks the velocity is the average of
+const VELOCITY_WEEKS: usize = 4;
+// Weeks `task stats velocity` lists
+const VELOCITY_HISTORY: u32 = 12;
+// Weeks `task stats age` goes back, and `task stats` compares the age of open tasks with
+const AGE_WEEKS: u32 = 12;
+const AGE_COMPARED: u32 = 4;
+const FORECAST_DAYS: u32 = 14;
+const FORECAST_WEEKS: u32 = 8;
+// Minutes of work a day when [forecast] doesn't say, and the days a week's capacity is made of
+const DAY_CAPACITY: u32 = 8 * 60;
+const WORKDAYS: u32 = 5;
+const BAR_WIDTH: usize = 40;
+
+// `task stats`: counts over the tasks the user sees, worked out by the database
+pub async fn tasks(repo: &TaskRepository) -> Result<()> {
+    let stats = repo.completion_stats().await?;
+
+    println!("\n--- Tasks ---");
+    println!("Pending:        {}", stats.pending);
+    println!("Overdue:        {}", stats.overdue);
+    println!("Completed:      {}", stats.completed);
+
+    println!("\n--- Completed ---");
+    println!("This week:      {}", stats.completed_this_week);
+    println!(
+        "Last week:      {}{}",
+        stats.completed_last_week,
+        week_change(stats.completed_this_week, stats.completed_last_week)
+    );
+    match stats.average_secs {
+        Some(secs) => println!("Avg time open:  {}", format_age(secs)),
+        None => println!("Avg time open:  -"),
+    }
+
+    let today = Local::now().date_naive();
+    let done = completed_per_week(repo, today, VELOCITY_WEEKS).await?;
+    let velocity = velocity_of(&done[..VELOCITY_WEEKS]);
+    println!("Velocity:       {:.1} a week", velocity);
+    println!("Weeks of work:  {}", weeks_of_work(stats.pending, velocity));
+
+    let streaks = streaks::load(repo, today).await?;
+    let at_risk = if streaks.at_risk() { ", complete a task today to keep it" } else { "" };
+    println!("Streak:         {}{}", days(streaks.current), at_risk);
+    println!("Best streak:    {}", days(streaks.best));
+
+    let now = Local::now().naive_local();
+    let before = now - chrono::Duration::weeks(AGE_COMPARED.into());
+    let spans = repo.open_spans(before).await?;
+    println!("\n--- Age of Open Tasks ---");
+    let Some(ages) = aging::ages_at(&spans, now) else {
+        println!("No open tasks.");
+        return Ok(());
+    };
+    let then = match aging::ages_at(&spans, before) {
+        Some(then) => format_age(then.average_secs),
+        None => "none open".to_string(),
+    };
+    println!("Average:        {} ({} weeks ago: {})", format_age(ages.average_secs), AGE_COMPARED, then);
+    println!("Median:         {}", format_age(ages.median_secs));
+    println!("90% under:      {}", format_age(ages.p90_secs));
+    Ok(())
+}
+
+// `task stats age`: how old the open tasks were at this time of day in each of the last weeks
+pub async fn age(repo: &TaskRepository, weeks: Option<u32>) -> Result<()> {
+    let weeks = weeks.unwrap_or(AGE_WEEKS).max(1);
+    let now = Local::now().naive_local();
+    let spans = repo.open_spans(now - chrono::Duration::weeks(weeks.into())).await?;
+    let trend = aging::weekly(&spans, now, weeks);
+
+    println!("\n--- Average Age of Open Tasks in Days, Last {} Week{} ---", weeks, if weeks == 1 { "" } else { "s" });
+    let days: Vec<i64> = trend.iter().map(|(_, ages)| ages.map_or(0, |ages| ages.average_secs / 86_400)).collect();
+    print_chart(&days, trend[0].0.date(), now.date());
+
+    println!();
+    println!("{:<10}  {:>6}  {:>10}  {:>10}  {:>10}", "Date", "Open", "Average", "Median", "90% under");
+    for (at, ages) in &trend {
+        let date = at.format("%Y-%m-%d");
+        match ages {
+            Some(ages) => println!(
+                "{:<10}  {:>6}  {:>10}  {:>10}  {:>10}",
+                date,
+                ages.open,
+                format_age(ages.average_secs),
+                format_age(ages.median_secs),
+                format_age(ages.p90_secs)
+            ),
+            None => println!("{:<10}  {:>6}", date, 0),
+        }
+    }
+    Ok(())
+}
+
+// `task stats velocity`: tasks completed per week, how many a week lately, and how long the
+// pending ones would take at that pace
+pub async fn velocity(repo: &TaskRepository, weeks: Option<u32>) -> Result<()> {
+    let weeks = weeks.unwrap_or(VELOCITY_HISTORY).max(1) as usize;
+    let today = Local::now().date_naive();
+    // The weeks before the first listed one count towards its velocity
+    let done = completed_per_week(repo, today, weeks + VELOCITY_WEEKS - 1).await?;
+    let pending = repo.completion_stats().await?.pending;
+    let this_monday = monday_of(today);
+
+    println!("\n--- Completed per Week ---");
+    println!("{:<12}  {:>6}  {:>8}", "Week of", "Done", "Velocity");
+    let full = done.len() - 1;
+    for week in VELOCITY_WEEKS - 1..full {
+        let monday = this_monday - Days::new(((full - week) * 7) as u64);
+        let velocity = velocity_of(&done[..=week]);
+        println!("{:<12}  {:>6}  {:>8.1}", monday.format("%Y-%m-%d"), done[week], velocity);
+    }
+    println!("{:<12}  {:>6}  {:>8}", this_monday.format("%Y-%m-%d"), done[full], "(so far)");
+
+    let velocity = velocity_of(&done[..full]);
+    println!();
+    println!("Velocity:       {:.1} a week, over the last {} weeks", velocity, VELOCITY_WEEKS);
+    println!("Pending:        {}", pending);
+    println!("Weeks of work:  {}", weeks_of_work(pending, velocity));
+    Ok(())
+}
+
+// Tasks completed in each of the `weeks` full weeks before this one, oldest first, and then so
+// far this week
+async fn completed_per_week(repo: &TaskRepository, today: NaiveDate, weeks: usize) -> Result<Vec<i64>> {
+    let first = monday_of(today) - Days::new((weeks * 7) as u64);
+    let days = repo.completions_by_day(first.and_time(NaiveTime::MIN), None).await?;
+    Ok(per_week(&days, first, weeks + 1))
+}
+
+fn monday_of(day: NaiveDate) -> NaiveDate {
+    day - Days::new(u64::from(day.weekday().num_days_from_monday()))
+}
+
+// Adds up the counts per day into `weeks` weeks from Monday `first` on
+fn per_week(days: &[(NaiveDate, i64)], first: NaiveDate, weeks: usize) -> Vec<i64> {
+    let mut totals = vec![0; weeks];
+    for (day, count) in days {
+        let week = (*day - first).num_days().div_euclid(7);
+        if let Some(total) = usize::try_from(week).ok().and_then(|week| totals.get_mut(week)) {
+            *total += count;
+        }
+    }
+    totals
+}
+
+// The average of the last VELOCITY_WEEKS of `weeks`, or of all of them if there are fewer
+fn velocity_of(weeks: &[i64]) -> f64 {
+    let last = &weeks[weeks.len().saturating_sub(VELOCITY_WEEKS)..];
+    if last.is_empty() {
+        return 0.0;
+    }
+    last.iter().sum::<i64>() as f64 / last.len() as f64
+}
+
+// E.g. "about 3.5", at `velocity` tasks a week
+fn weeks_of_work(pending: i64, velocity: f64) -> String {
+    if pending == 0 {
+        "none".to_string()
+    } else if velocity <= 0.0 {
+        "- (nothing completed lately)".to_string()
+    } else {
+        format!("about {:.1}", pending as f64 / velocity)
+    }
+}
+
+// `task stats burndown`: open tasks at the end of each day, worked back from how many are open
+// now through the activity log. Tasks deleted without a trace there, or changed before it was
+// kept, count as they are now.
+pub async fn burndown(repo: &TaskRepository, weeks: Option<u32>) -> Result<()> {
+    let weeks = weeks.unwrap_or(BURNDOWN_WEEKS).max(1);
+    let today = Local::now().date_naive();
+    let start = today - Days::new(u64::from(weeks) * 7);
+    let open_now = repo.completion_stats().await?.pending;
+    let changes = repo.open_changes(start.and_time(NaiveTime::MIN)).await?;
+    let open = open_by_day(open_now, &changes, start, today);
+
+    println!("\n--- Open Tasks, Last {} Week{} ---", weeks, if weeks == 1 { "" } else { "s" });
+    print_chart(&open, start, today);
+
+    let count = |action| changes.iter().filter(|change| change.action == action).count();
+    println!();
+    println!("Created:        {}", count(Action::Created) + count(Action::Reopened));
+    println!("Completed:      {}", count(Action::Completed));
+    println!("Deleted:        {}", count(Action::Deleted));
+    println!("Open now:       {}", open_now);
+    Ok(())
+}
+
+// A line chart of `values` from `first` to `last`, with the two dates under its ends
+fn print_chart(values: &[i64], first: NaiveDate, last: NaiveDate) {
+    let lines = chart::line(values, CHART_WIDTH, CHART_HEIGHT);
+    let indent = lines[0].chars().count() - CHART_WIDTH;
+    for line in &lines {
+        println!("{}", line);
+    }
+    let first = first.format("%b %d").to_string();
+    println!("{}{}{:>width$}", " ".repeat(indent), first, last.format("%b %d"), width = CHART_WIDTH - first.len());
+}
+
+// Open tasks at the end of each day from `start` to `today`
+fn open_by_day(open_now: i64, changes: &[OpenChange], start: NaiveDate, today: NaiveDate) -> Vec<i64> {
+    let mut day = start;
+    let mut open = Vec::new();
+    while day <= today {
+        let end = (day + Days::new(1)).and_time(NaiveTime::MIN);
+        let since: i64 = changes.iter().filter(|change| change.occurred_at >= end).map(open_delta).sum();
+        open.push(open_now - since);
+        day = day + Days::new(1);
+    }
+    open
+}
+
+// How a change moved the number of open tasks
+fn open_delta(change: &OpenChange) -> i64 {
+    match change.action {
+        Action::Created | Action::Reopened => 1,
+        Action::Completed => -1,
+        Action::Deleted if change.before != Some(Action::Completed) => -1,
+        _ => 0,
+    }
+}
+
+// `task stats heatmap`: tasks completed on each day of the last year, of one project or all
+pub async fn heatmap(repo: &TaskRepository, project: Option<&str>) -> Result<()> {
+    let project_id = match project {
+        Some(name) => Some(
+            repo.project_id(name)
+                .await?
+                .ok_or_else(|| TaskError::InvalidInput(format!("There is no project named {}.", name)))?,
+        ),
+        None => None,
+    };
+    let today = Local::now().date_naive();
+    // The Monday of the heatmap's first week
+    let days_back = u64::from(today.weekday().num_days_from_monday()) + u64::from(HEATMAP_WEEKS - 1) * 7;
+    let since = today - Days::new(days_back);
+    let counts: HashMap<NaiveDate, i64> =
+        repo.completions_by_day(since.and_time(NaiveTime::MIN), project_id).await?.into_iter().collect();
+
+    match project {
+        Some(name) => println!("\n--- Completed per Day in {} ---", name),
+        None => println!("\n--- Completed per Day ---"),
+    }
+    for line in chart::heatmap(&counts, today, HEATMAP_WEEKS) {
+        println!("{}", line);
+    }
+    let shades: Vec<String> = chart::SHADES.iter().map(char::to_string).collect();
+    println!("    Less {} More", shades.join(" "));
+
+    println!();
+    println!("Completed:      {}", counts.values().sum::<i64>());
+    println!("Days with any:  {}", counts.len());
+    // The earliest of the busiest days
+    let busiest = counts.iter().max_by_key(|&(day, count)| (*count, std::cmp::Reverse(*day)));
+    if let Some((day, count)) = busiest {
+        println!("Busiest day:    {} ({})", day.format("%Y-%m-%d"), count);
+    }
+    Ok(())
+}
+
+// `task stats forecast`: the estimates of the open tasks due on each day, or in each week, from
+// today on, against the capacity of one. Overdue tasks still need doing, so they count for the
+// first; tasks without an estimate can't count and are only mentioned.
+pub async fn forecast(repo: &TaskRepository, config: &ForecastConfig, ahead: Option<u32>, weekly: bool) -> Result<()> {
+    let day_capacity = capacity(config.capacity.as_deref(), "capacity")?.unwrap_or(DAY_CAPACITY);
+    let (capacity, periods, length) = if weekly {
+        let week_capacity = capacity(config.week_capacity.as_deref(), "week_capacity")?;
+        (week_capacity.unwrap_or(day_capacity * WORKDAYS), ahead.unwrap_or(FORECAST_WEEKS), 7)
+    } else {
+        (day_capacity, ahead.unwrap_or(FORECAST_DAYS), 1)
+    };
+    let periods = periods.max(1);
+    let today = Local::now().date_naive();
+    let start = if weekly { monday_of(today) } else { today };
+    let end = start + Days::new(u64::from(periods * length));
+    let loads = forecast_of(&repo.due_load(end.and_time(NaiveTime::MIN)).await?, start, periods, length);
+
+    let (unit, label) = if weekly { ("week", "W%V %m-%d") } else { ("day", "%a %m-%d") };
+    let last = end - Days::new(1);
+    println!("\n--- Forecast per {}, {} to {} ---", unit, start.format("%Y-%m-%d"), last.format("%Y-%m-%d"));
+    let capacity = i64::from(capacity);
+    let scale = loads.iter().map(|load| load.estimate_minutes).max().unwrap_or(0).max(capacity);
+    for load in &loads {
+        let over = load.estimate_minutes - capacity;
+        let tasks = match load.tasks {
+            0 => String::new(),
+            1 => "  (1 task)".to_string(),
+            tasks => format!("  ({} tasks)", tasks),
+        };
+        println!(
+            "{}  {} {:>12}{}{}",
+            load.day.format(label),
+            chart::bar(load.estimate_minutes, capacity, scale, BAR_WIDTH),
+            timesheet::format_duration(load.estimate_minutes * 60),
+            tasks,
+            if over > 0 { format!(", over by {}", timesheet::format_duration(over * 60)) } else { String::new() }
+        );
+    }
+
+    println!();
+    println!("Capacity:       {} a {}", timesheet::format_duration(capacity * 60), unit);
+    let over = loads.iter().filter(|load| load.estimate_minutes > capacity).count();
+    println!("Over capacity:  {} of {} {}s", over, loads.len(), unit);
+    let unestimated: i64 = loads.iter().map(|load| load.unestimated).sum();
+    if unestimated > 0 {
+        println!("No estimate:    {} task{}, not counted; see `task estimate`", unestimated, plural(unestimated));
+    }
+    Ok(())
+}
+
+// A capacity of [forecast], in minutes
+fn capacity(duration: Option<&str>, key: &str) -> Result<Option<u32>> {
+    duration
+        .map(|duration| {
+            estimates::parse_minutes(duration).map_err(|_| {
+                let message = format!("Invalid {} '{}' in the [forecast] section; expected e.g. 6h.", key, duration);
+                TaskError::Config(message)
+            })
+        })
+        .transpose()
+}
+
+// `periods` periods of `length` days from `start` on, each with the tasks due in it; what is
+// due before `start` goes to the first
+fn forecast_of(loads: &[DueLoad], start: NaiveDate, periods: u32, length: u32) -> Vec<DueLoad> {
+    let mut forecast: Vec<DueLoad> = (0..periods)
+        .map(|period| DueLoad {
+            day: start + Days::new(u64::from(period * length)),
+            tasks: 0,
+            estimate_minutes: 0,
+            unestimated: 0,
+        })
+        .collect();
+    for load in loads {
+        let period = (load.day - start).num_days().max(0) / i64::from(length);
+        if let Some(total) = usize::try_from(period).ok().and_then(|period| forecast.get_mut(period)) {
+            total.tasks += load.tasks;
+            total.estimate_minutes += load.estimate_minutes;
+            total.unestimated += load.unestimated;
+        }
+    }
+    forecast
+}
+
+// `task stats breakdown`: where the backlog is, by project and by tag
+pub async fn breakdown(repo: &TaskRepository) -> Result<()> {
+    print_breakdown("Project", "(no project)", &repo.breakdown_by_project().await?);
+    print_breakdown("Tag", "(no tag)", &repo.breakdown_by_tag().await?);
+    Ok(())
+}
+
+fn print_breakdown(kind: &str, none: &str, rows: &[Breakdown]) {
+    println!("\n--- By {} ---", kind);
+    if rows.is_empty() {
+        println!("No tasks found.");
+        return;
+    }
+    let name = |row: &Breakdown| row.name.clone().unwrap_or_else(|| none.to_string());
+    let width = rows.iter().map(|row| name(row).chars().count()).max().unwrap_or(0).max(kind.len());
+    println!("{:<width$}  {:>6}  {:>6}  Estimated", kind, "Open", "Done");
+    for row in rows {
+        println!("{:<width$}  {:>6}  {:>6}  {}", name(row), row.open, row.completed, estimate(row));
+    }
+}
+
+// The open tasks' estimates, e.g. "6 h 30 min (2 without)"
+fn estimate(row: &Breakdown) -> String {
+    let total = timesheet::format_duration(row.estimate_minutes * 60);
+    match row.unestimated {
+        _ if row.estimate_minutes == 0 => "-".to_string(),
+        0 => total,
+        without => format!("{} ({} without)", total, without),
+    }
+}
+
+// E.g. "1 day" or "12 days"
+fn days(count: u32) -> String {
+    format!("{} day{}", count, plural(i64::from(count)))
+}
+
+fn plural(count: i64) -> &'static str {
+    if count == 1 { "" } else { "s" }
+}
+
+// How this week compares so far, e.g. " (3 more this week)"
+fn week_change(this_week: i64, last_week: i64) -> String {
+    match this_week - last_week {
+        0 => String::new(),
+        more if more > 0 => format!(" ({} more this week)", more),
+        fewer => format!(" ({} fewer this week)", -fewer),
+    }
+}
+
+// E.g. "2 d 4 h", "3 h 5 min" or "12 min"; negative times, from clocks set back, count as none
+pub(crate) fn format_age(secs: i64) -> String {
+    let minutes = secs.max(0) / 60;
+    let (days, hours, minutes) = (minutes / (24 * 60), minutes / 60 % 24, minutes % 60);
+    match (days, hours) {
+        (0, 0) => format!("{} min", minutes),
+        (0, _) => format!("{} h {} min", hours, minutes),
+        _ => format!("{} d {} h", days, hours),
+    }
+}
+
+// `task stats db`: the metrics only cover this process, so a fresh CLI run pings the server
+// first to have at least one measured connection checkout and round trip to show.
+pub async fn db(repo: &TaskRepository) -> Result<()> {
+    repo.ping().await?;
+    print_db_stats(repo);
+    Ok(())
+}
+
+// Pool state plus everything the repository has measured so far, and how its list cache did
+pub fn print_db_stats(repo: &TaskRepository) {
+    let pool = repo.pool_status();
+    let metrics = repo.metrics().snapshot(SLOWEST_SHOWN);
+
+    println!("\n--- Connection Pool ---");
+    println!("Size:           {} (max {})", pool.size, pool.max);
+    println!("Idle:           {}", pool.idle);
+    println!("Active:         {}", (pool.size as usize).saturating_sub(pool.idle));
+    println!("Acquires:       {}", metrics.acquires);
+    match metrics.average_acquire {
+        Some(average) => println!("Avg acquire:    {}", format_duration(average)),
+        None => println!("Avg acquire:    -"),
+    }
+
+    println!("\n--- Queries ---");
+    if metrics.operations.is_empty() {
+        println!("No queries recorded yet.");
+    }
+    for (operation, stats) in &metrics.operations {
+        let average = stats.total_time / u32::try_from(stats.calls).unwrap_or(u32::MAX).max(1);
+        println!(
+            "{:<16} {:>6} calls, {:>3} failed, avg {}",
+            operation,
+            stats.calls,
+            stats.failures,
+            format_duration(average)
+        );
+    }
+
+    let cache = repo.cache().stats();
+    println!("\n--- List Cache ---");
+    if cache.enabled {
+        let lookups = cache.hits + cache.misses;
+        println!("Entries:        {}", cache.entries);
+        println!("Hits:           {}", cache.hits);
+        println!("Misses:         {}", cache.misses);
+        match lookups {
+            0 => println!("Hit rate:       -"),
+            _ => println!("Hit rate:       {:.1}%", cache.hits as f64 * 100.0 / lookups as f64),
+        }
+        println!("Invalidations:  {}", cache.invalidations);
+    } else {
+        println!("Off, see [cache] in the config file.");
+    }
+
+    if !metrics.slowest.is_empty() {
+        println!("\n--- Slowest Recent Queries ---");
+        for sample in &metrics.slowest {
+            println!(
+                "{:<16} {:>10}  at {}{}",
+                sample.operation,
+                format_duration(sample.duration),
+                sample.finished_at.format("%H:%M:%S"),
+                if sample.ok { "" } else { " (failed)" }
+            );
+        }
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    format!("{:.2} ms", duration.as_secs_f64() * 1000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(action: Action, day: u32, before: Option<Action>) -> OpenChange {
+        let occurred_at = NaiveDate::from_ymd_opt(2026, 3, day).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        OpenChange { action, occurred_at, before }
+    }
+
+    #[test]
+    fn the_forecast_adds_up_what_is_due_in_each_period() {
+        let day = |day| NaiveDate::from_ymd_opt(2026, 3, day).unwrap();
+        let load = |on, tasks, estimate_minutes, unestimated| {
+            DueLoad { day: day(on), tasks, estimate_minutes, unestimated }
+        };
+        // Overdue on the 9th, then the 11th, the 13th and the 20th
+        let loads = [load(9, 2, 60, 1), load(11, 1, 90, 0), load(13, 3, 240, 1), load(20, 1, 30, 0)];
+
+        let daily = forecast_of(&loads, day(11), 3, 1);
+        assert_eq!(daily, [load(11, 3, 150, 1), load(12, 0, 0, 0), load(13, 3, 240, 1)]);
+        // From Monday the 9th
+        let weekly = forecast_of(&loads, day(9), 2, 7);
+        assert_eq!(weekly, [load(9, 6, 390, 2), load(16, 1, 30, 0)]);
+    }
+
+    #[test]
+    fn capacities_are_durations() {
+        assert_eq!(capacity(Some("6h"), "capacity").unwrap(), Some(360));
+        assert_eq!(capacity(None, "capacity").unwrap(), None);
+        let error = capacity(Some("lots"), "week_capacity").unwrap_err().to_string();
+        assert!(error.contains("week_capacity 'lots'"), "{}", error);
+    }
+
+    #[test]
+    fn velocity_is_the_recent_weekly_average() {
+        let day = |day| NaiveDate::from_ymd_opt(2026, 3, day).unwrap();
+        // Mondays the 2nd, 9th, 16th and 23rd
+        let days = [(day(1), 9), (day(2), 2), (day(8), 1), (day(9), 3), (day(20), 4), (day(23), 5)];
+        let weeks = per_week(&days, day(2), 4);
+        assert_eq!(weeks, [3, 3, 4, 5]);
+        assert_eq!(monday_of(day(22)), day(16));
+
+        assert_eq!(velocity_of(&weeks[..3]), 10.0 / 3.0);
+        assert_eq!(velocity_of(&[1, 2, 3, 4, 5, 6]), 4.5);
+        assert_eq!(velocity_of(&[]), 0.0);
+        assert_eq!(weeks_of_work(9, 4.5), "about 2.0");
+        assert_eq!(weeks_of_work(9, 0.0), "- (nothing completed lately)");
+        assert_eq!(weeks_of_work(0, 0.0), "none");
+    }
+
+    #[test]
+    fn estimates_say_how_many_tasks_have_none() {
+        let row = |estimate_minutes, open, unestimated| Breakdown {
+            name: None,
+            open,
+            completed: 0,
+            estimate_minutes,
+            unestimated,
+        };
+        assert_eq!(estimate(&row(390, 3, 0)), "6 h 30 min");
+        assert_eq!(estimate(&row(390, 3, 2)), "6 h 30 min (2 without)");
+        assert_eq!(estimate(&row(0, 3, 3)), "-");
+        assert_eq!(estimate(&row(0, 0, 0)), "-");
+    }
+
+    #[test]
+    fn open_tasks_are_worked_back_from_now() {
+        let day = |day| NaiveDate::from_ymd_opt(2026, 3, day).unwrap();
+        let changes = [
+            change(Action::Created, 2, None),
+            change(Action::Created, 2, None),
+            change(Action::Completed, 3, Some(Action::Created)),
+            // A completed task going doesn't change what is open, an open one does
+            change(Action::Deleted, 4, Some(Action::Completed)),
+            change(Action::Deleted, 4, Some(Action::Created)),
+            change(Action::Reopened, 5, Some(Action::Completed)),
+        ];
+        assert_eq!(open_by_day(3, &changes, day(1), day(5)), [2, 4, 3, 2, 3]);
+        assert_eq!(open_by_day(3, &[], day(1), day(2)), [3, 3]);
+    }
+
+    #[test]
+    fn completion_times_read_at_a_glance() {
+        assert_eq!(format_age(59), "0 min");
+        assert_eq!(format_age(12 * 60 + 30), "12 min");
+        assert_eq!(format_age(3 * 3600 + 5 * 60), "3 h 5 min");
+        assert_eq!(format_age(2 * 86400 + 4 * 3600 + 59 * 60), "2 d 4 h");
+        assert_eq!(format_age(-100), "0 min");
+    }
+
+    #[test]
+    fn this_week_is_compared_with_the_last() {
+        assert_eq!(week_change(5, 2), " (3 more this week)");
+        assert_eq!(week_change(1, 4), " (3 fewer this week)");
+        assert_eq!(week_change(2, 2), "");
+    }
+}
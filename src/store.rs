@@ -0,0 +1,141 @@
+// The task operations the command layer needs, as a trait, so the code that decides what to
+// print can run against `TaskRepository` in the binary and against `memory::MemoryStore` in
+// unit tests, with no database. Only what the views use is here; everything else still takes a
+// `TaskRepository`.
+
+use std::future::Future;
+
+use futures::stream::BoxStream;
+
+use crate::repository::{
+    ChecklistItem, ListCursor, Note, Page, SearchCursor, TaskChanges, TaskFilter, TaskRepository, Updated,
+};
+use crate::Task;
+
+pub trait TaskStore: Sync {
+    // The account the store works as, if any
+    fn user(&self) -> Option<i32>;
+
+    fn add(&self, description: &str) -> impl Future<Output = Result<i32, sqlx::Error>> + Send;
+
+    fn get(&self, id: i32) -> impl Future<Output = Result<Option<Task>, sqlx::Error>> + Send;
+
+    // None if there is no such task
+    fn complete(&self, id: i32) -> impl Future<Output = Result<Option<Updated>, sqlx::Error>> + Send;
+
+    fn delete(&self, id: i32) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    fn update(&self, id: i32, changes: &TaskChanges) -> impl Future<Output = Result<bool, sqlx::Error>> + Send;
+
+    // Every visible task, newest first
+    fn stream_all(&self) -> BoxStream<'_, Result<Task, sqlx::Error>>;
+
+    fn list_page(
+        &self,
+        after: Option<ListCursor>,
+        limit: u32,
+    ) -> impl Future<Output = Result<Page<ListCursor>, sqlx::Error>> + Send;
+
+    fn filter_page(
+        &self,
+        filter: &TaskFilter,
+        after: Option<ListCursor>,
+        limit: u32,
+    ) -> impl Future<Output = Result<Page<ListCursor>, sqlx::Error>> + Send;
+
+    fn search_page(
+        &self,
+        query: &str,
+        after: Option<SearchCursor>,
+        limit: u32,
+    ) -> impl Future<Output = Result<Page<SearchCursor>, sqlx::Error>> + Send;
+
+    fn tags(&self, id: i32) -> impl Future<Output = Result<Vec<String>, sqlx::Error>> + Send;
+
+    fn checklist(&self, id: i32) -> impl Future<Output = Result<Vec<ChecklistItem>, sqlx::Error>> + Send;
+
+    fn notes(&self, id: i32) -> impl Future<Output = Result<Vec<Note>, sqlx::Error>> + Send;
+
+    // (source, external id) pairs
+    fn links(&self, id: i32) -> impl Future<Output = Result<Vec<(String, String)>, sqlx::Error>> + Send;
+
+    fn project_name(&self, project_id: i32) -> impl Future<Output = Result<Option<String>, sqlx::Error>> + Send;
+
+    fn username(&self, user_id: i32) -> impl Future<Output = Result<Option<String>, sqlx::Error>> + Send;
+}
+
+impl TaskStore for TaskRepository {
+    fn user(&self) -> Option<i32> {
+        TaskRepository::user(self)
+    }
+
+    async fn add(&self, description: &str) -> Result<i32, sqlx::Error> {
+        TaskRepository::add(self, description).await
+    }
+
+    async fn get(&self, id: i32) -> Result<Option<Task>, sqlx::Error> {
+        TaskRepository::get(self, id).await
+    }
+
+    async fn complete(&self, id: i32) -> Result<Option<Updated>, sqlx::Error> {
+        TaskRepository::complete(self, id).await
+    }
+
+    async fn delete(&self, id: i32) -> Result<bool, sqlx::Error> {
+        TaskRepository::delete(self, id).await
+    }
+
+    async fn update(&self, id: i32, changes: &TaskChanges) -> Result<bool, sqlx::Error> {
+        TaskRepository::update(self, id, changes).await.map(|updated| updated.is_some())
+    }
+
+    fn stream_all(&self) -> BoxStream<'_, Result<Task, sqlx::Error>> {
+        TaskRepository::stream_all(self)
+    }
+
+    async fn list_page(&self, after: Option<ListCursor>, limit: u32) -> Result<Page<ListCursor>, sqlx::Error> {
+        TaskRepository::list_page(self, after, limit).await
+    }
+
+    async fn filter_page(
+        &self,
+        filter: &TaskFilter,
+        after: Option<ListCursor>,
+        limit: u32,
+    ) -> Result<Page<ListCursor>, sqlx::Error> {
+        TaskRepository::filter_page(self, filter, after, limit).await
+    }
+
+    async fn search_page(
+        &self,
+        query: &str,
+        after: Option<SearchCursor>,
+        limit: u32,
+    ) -> Result<Page<SearchCursor>, sqlx::Error> {
+        TaskRepository::search_page(self, query, after, limit).await
+    }
+
+    async fn tags(&self, id: i32) -> Result<Vec<String>, sqlx::Error> {
+        TaskRepository::tags(self, id).await
+    }
+
+    async fn checklist(&self, id: i32) -> Result<Vec<ChecklistItem>, sqlx::Error> {
+        TaskRepository::checklist(self, id).await
+    }
+
+    async fn notes(&self, id: i32) -> Result<Vec<Note>, sqlx::Error> {
+        TaskRepository::notes(self, id).await
+    }
+
+    async fn links(&self, id: i32) -> Result<Vec<(String, String)>, sqlx::Error> {
+        TaskRepository::links(self, id).await
+    }
+
+    async fn project_name(&self, project_id: i32) -> Result<Option<String>, sqlx::Error> {
+        TaskRepository::project_name(self, project_id).await
+    }
+
+    async fn username(&self, user_id: i32) -> Result<Option<String>, sqlx::Error> {
+        TaskRepository::username(self, user_id).await
+    }
+}
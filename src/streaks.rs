@@ -0,0 +1,74 @@
+// Streaks of days on which at least one task was completed, for `task stats` and the warning
+// in the email digest that one is about to end.
+
+use chrono::{Days, NaiveDate, NaiveDateTime};
+
+use crate::error::Result;
+use crate::repository::TaskRepository;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Streaks {
+    // Days in a row up to today, or up to yesterday while nothing is done today yet
+    pub current: u32,
+    pub best: u32,
+    pub done_today: bool,
+}
+
+impl Streaks {
+    // A streak that ends tonight unless something gets done today
+    pub fn at_risk(&self) -> bool {
+        self.current > 0 && !self.done_today
+    }
+}
+
+// Over every task the user sees that was ever completed
+pub async fn load(repo: &TaskRepository, today: NaiveDate) -> Result<Streaks> {
+    let days = repo.completions_by_day(NaiveDateTime::default(), None).await?;
+    let days: Vec<NaiveDate> = days.into_iter().map(|(day, _)| day).collect();
+    Ok(streaks(&days, today))
+}
+
+// From the days with completions, in order
+pub fn streaks(days: &[NaiveDate], today: NaiveDate) -> Streaks {
+    let mut best = 0;
+    let mut run = 0;
+    let mut last: Option<NaiveDate> = None;
+    for &day in days.iter().filter(|&&day| day <= today) {
+        run = match last {
+            Some(last) if last + Days::new(1) == day => run + 1,
+            _ => 1,
+        };
+        best = best.max(run);
+        last = Some(day);
+    }
+    let done_today = last == Some(today);
+    let alive = done_today || last.is_some_and(|last| last + Days::new(1) == today);
+    Streaks { current: if alive { run } else { 0 }, best, done_today }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 3, day).unwrap()
+    }
+
+    #[test]
+    fn the_current_streak_lasts_until_a_day_is_missed() {
+        let days = [day(1), day(2), day(3), day(4), day(7), day(8)];
+        let today = |today| streaks(&days, day(today));
+        assert_eq!(today(8), Streaks { current: 2, best: 4, done_today: true });
+        // Nothing yet on the 9th, but there's still time
+        assert_eq!(today(9), Streaks { current: 2, best: 4, done_today: false });
+        assert!(today(9).at_risk());
+        assert_eq!(today(10).current, 0);
+        assert!(!today(10).at_risk());
+    }
+
+    #[test]
+    fn days_after_today_and_no_days_at_all_count_for_nothing() {
+        assert_eq!(streaks(&[day(5), day(6)], day(4)), Streaks::default());
+        assert_eq!(streaks(&[], day(4)), Streaks::default());
+    }
+}
@@ -0,0 +1,469 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use quick_xml::Reader;
+use quick_xml::events::Event;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::{Method, StatusCode, Url};
+
+use crate::cli::{CaldavSyncArgs, Prefer};
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::export::ics;
+use crate::ical::{parse_time, priority_from_ical, split_list, unescape_text, unfold};
+use crate::import;
+use crate::priority::Priority;
+use crate::repository::{TaskBundle, TaskRepository};
+use crate::secrets::{self, Service};
+use crate::Task;
+
+use super::{check_status, remote_error, send};
+
+const SERVICE: &str = "CalDAV";
+
+// Summary for to-dos that have none, since tasks need a description
+const UNTITLED: &str = "(no summary)";
+
+const CTAG_REQUEST: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<d:propfind xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/">
+  <d:prop><cs:getctag/></d:prop>
+</d:propfind>"#;
+
+// Entity tags of every VTODO in the collection; events in a shared calendar are left out
+const ETAGS_REQUEST: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+  <d:prop><d:getetag/></d:prop>
+  <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter>
+</c:calendar-query>"#;
+
+// One <response> of a WebDAV multistatus reply
+#[derive(Debug, Default)]
+struct DavResponse {
+    href: String,
+    etag: Option<String>,
+    ctag: Option<String>,
+}
+
+// The VTODO properties we map
+#[derive(Debug)]
+struct Todo {
+    uid: String,
+    summary: String,
+    completed: bool,
+    due_at: Option<NaiveDateTime>,
+    priority: Option<Priority>,
+    categories: Vec<String>,
+}
+
+// A resource as just fetched from the server
+#[derive(Debug)]
+struct Fetched {
+    href: String,
+    etag: Option<String>,
+    todo: Todo,
+}
+
+#[derive(Debug)]
+enum Action {
+    // New on the server
+    Import(Fetched),
+    // Changed on the server
+    Pull { task_id: i32, remote: Fetched },
+    // Changed here. The write only succeeds while the server still has `etag`; with None the
+    // resource must not exist (it was deleted on the server and is created again).
+    Push { task: Task, href: String, uid: String, etag: Option<String> },
+    // New here
+    Create(Task),
+    // Deleted on the server
+    DeleteLocal { task_id: i32, href: String },
+    // Deleted here
+    DeleteRemote { href: String, etag: Option<String> },
+    // Deleted on both sides
+    Forget { href: String },
+}
+
+// Result of a conditional write
+enum Written {
+    Done { etag: Option<String> },
+    // Someone changed the resource since we looked; the next sync sorts it out
+    Conflict,
+}
+
+struct Client {
+    http: reqwest::Client,
+    // Always ends with '/', so resource names can be joined onto it
+    collection: Url,
+    username: String,
+    password: String,
+}
+
+impl Client {
+    fn new(config: &Config) -> Result<Self> {
+        let caldav = &config.caldav;
+        let (Some(url), Some(username)) = (&caldav.url, &caldav.username) else {
+            return Err(TaskError::Config(format!(
+                "CalDAV is not configured. Set `url` (the task list's collection URL) and `username` \
+                 in the [caldav] section of {}.",
+                Config::path().display()
+            )));
+        };
+        let collection = Url::parse(&format!("{}/", url.trim_end_matches('/')))
+            .map_err(|e| TaskError::Config(format!("Invalid CalDAV url '{}': {}", url, e)))?;
+
+        Ok(Client {
+            http: super::http_client(SERVICE)?,
+            collection,
+            username: username.clone(),
+            password: secrets::token(config, Service::Caldav)?,
+        })
+    }
+
+    fn request(&self, method: Method, url: Url) -> reqwest::RequestBuilder {
+        self.http.request(method, url).basic_auth(&self.username, Some(&self.password))
+    }
+
+    fn resource_url(&self, href: &str) -> Result<Url> {
+        self.collection.join(href).map_err(|e| remote_error(SERVICE, format!("invalid href '{}': {}", href, e)))
+    }
+
+    async fn dav(&self, method: &[u8], depth: &str, body: &'static str) -> Result<Vec<DavResponse>> {
+        let method = Method::from_bytes(method).expect("valid WebDAV method");
+        let request = self
+            .request(method, self.collection.clone())
+            .header("Depth", depth)
+            .header(CONTENT_TYPE, "application/xml; charset=utf-8")
+            .body(body);
+        let xml = send(SERVICE, request).await?.text().await.map_err(|e| remote_error(SERVICE, e))?;
+        parse_multistatus(&xml).map_err(|e| remote_error(SERVICE, format!("unreadable WebDAV response: {}", e)))
+    }
+
+    // Changes whenever anything in the collection changes. Not every server has one.
+    async fn ctag(&self) -> Result<Option<String>> {
+        Ok(self.dav(b"PROPFIND", "0", CTAG_REQUEST).await?.into_iter().find_map(|response| response.ctag))
+    }
+
+    async fn etags(&self) -> Result<HashMap<String, Option<String>>> {
+        let responses = self.dav(b"REPORT", "1", ETAGS_REQUEST).await?;
+        Ok(responses.into_iter().map(|response| (response.href, response.etag)).collect())
+    }
+
+    // None if the resource holds no VTODO after all
+    async fn fetch(&self, href: &str) -> Result<Option<Fetched>> {
+        let response = send(SERVICE, self.request(Method::GET, self.resource_url(href)?)).await?;
+        let etag = header_etag(&response);
+        let body = response.text().await.map_err(|e| remote_error(SERVICE, e))?;
+        Ok(parse_todo(&body).map(|todo| Fetched { href: href.to_string(), etag, todo }))
+    }
+
+    async fn put(&self, href: &str, body: String, etag: Option<&str>) -> Result<Written> {
+        let request = self
+            .request(Method::PUT, self.resource_url(href)?)
+            .header(CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .body(body);
+        let request = match etag {
+            Some(etag) => request.header(IF_MATCH, etag),
+            None => request.header(IF_NONE_MATCH, "*"),
+        };
+
+        let response = request.send().await.map_err(|e| remote_error(SERVICE, e))?;
+        if response.status() == StatusCode::PRECONDITION_FAILED {
+            return Ok(Written::Conflict);
+        }
+        let response = check_status(SERVICE, response).await?;
+        // Servers that change the data on the way in send no ETag; the next sync fetches it again
+        Ok(Written::Done { etag: header_etag(&response) })
+    }
+
+    async fn delete(&self, href: &str, etag: Option<&str>) -> Result<Written> {
+        let mut request = self.request(Method::DELETE, self.resource_url(href)?);
+        if let Some(etag) = etag {
+            request = request.header(IF_MATCH, etag);
+        }
+
+        let response = request.send().await.map_err(|e| remote_error(SERVICE, e))?;
+        match response.status() {
+            StatusCode::PRECONDITION_FAILED => Ok(Written::Conflict),
+            StatusCode::NOT_FOUND => Ok(Written::Done { etag: None }),
+            _ => {
+                check_status(SERVICE, response).await?;
+                Ok(Written::Done { etag: None })
+            }
+        }
+    }
+}
+
+// Two-way sync with a CalDAV task list (Nextcloud Tasks, Fastmail, ...). The collection's ctag
+// tells whether anything changed on the server at all; if so, the entity tag of each VTODO
+// tells which ones, and only those are downloaded. Changes here are found through updated_at
+// as with the other syncs. When a task changed on both sides, --prefer decides which version
+// is kept. Tags go to the server as CATEGORIES but are only taken over for new tasks.
+pub async fn run(repo: &TaskRepository, config: &Config, args: CaldavSyncArgs) -> Result<()> {
+    let client = Client::new(config)?;
+    let actions = plan(repo, &client, args.prefer).await?;
+
+    if actions.is_empty() {
+        println!("Everything is in sync with {}.", client.collection);
+        return Ok(());
+    }
+    for action in &actions {
+        println!("{}", describe(action));
+    }
+    if args.options.dry_run {
+        println!("Dry run: {} changes not applied.", actions.len());
+        return Ok(());
+    }
+
+    let host = ics::hostname();
+    let mut conflicts = 0;
+    let total = actions.len();
+    for action in actions {
+        if !apply(repo, &client, &host, action).await? {
+            conflicts += 1;
+        }
+    }
+
+    // Our own writes changed the ctag; remember the new one so they don't count as remote changes
+    let ctag = client.ctag().await?;
+    repo.save_caldav_ctag(client.collection.as_str(), ctag.as_deref()).await?;
+
+    println!("Synced {} changes with {}.", total - conflicts, client.collection);
+    if conflicts > 0 {
+        println!("{} resources changed on the server during the sync; run it again to pick them up.", conflicts);
+    }
+    Ok(())
+}
+
+async fn plan(repo: &TaskRepository, client: &Client, prefer: Prefer) -> Result<Vec<Action>> {
+    let path = client.collection.path();
+    let tracked = repo.caldav_resources(path).await?;
+
+    let ctag = client.ctag().await?;
+    let unchanged = ctag.is_some() && ctag == repo.caldav_ctag(client.collection.as_str()).await?;
+    let mut remote: HashMap<String, Option<String>> = if unchanged {
+        tracked.iter().map(|resource| (resource.href.clone(), resource.etag.clone())).collect()
+    } else {
+        client.etags().await?
+    };
+
+    let mut actions = Vec::new();
+    for resource in tracked {
+        let href = resource.href;
+        let remote_etag = remote.remove(&href);
+        // A resource without an entity tag can't be compared and is always fetched
+        let remote_changed = remote_etag.as_ref().is_some_and(|etag| etag.is_none() || *etag != resource.etag);
+
+        match (resource.task_id, remote_etag) {
+            (None, None) => actions.push(Action::Forget { href }),
+            (None, Some(etag)) => {
+                if remote_changed && prefer == Prefer::Remote {
+                    actions.extend(client.fetch(&href).await?.map(Action::Import));
+                } else {
+                    actions.push(Action::DeleteRemote { href, etag });
+                }
+            }
+            (Some(task_id), None) => match repo.get(task_id).await? {
+                Some(task) if resource.changed && prefer == Prefer::Local => {
+                    actions.push(Action::Push { task, href, uid: resource.uid, etag: None });
+                }
+                _ => actions.push(Action::DeleteLocal { task_id, href }),
+            },
+            (Some(task_id), Some(etag)) => {
+                let push = match (resource.changed, remote_changed) {
+                    (false, false) => continue,
+                    (true, false) => true,
+                    (false, true) => false,
+                    (true, true) => prefer == Prefer::Local,
+                };
+                if !push {
+                    if let Some(remote) = client.fetch(&href).await? {
+                        actions.push(Action::Pull { task_id, remote });
+                    }
+                } else if let Some(task) = repo.get(task_id).await? {
+                    // Overwrites what is on the server now, which after a conflict is newer than our copy
+                    let etag = if remote_changed { etag } else { resource.etag };
+                    actions.push(Action::Push { task, href, uid: resource.uid, etag });
+                }
+            }
+        }
+    }
+
+    let mut new_remote: Vec<String> = remote.into_keys().collect();
+    new_remote.sort();
+    for href in new_remote {
+        actions.extend(client.fetch(&href).await?.map(Action::Import));
+    }
+
+    actions.extend(repo.caldav_unlinked_pending().await?.into_iter().map(Action::Create));
+    Ok(actions)
+}
+
+// Returns false if a write hit a conflict and was skipped
+async fn apply(repo: &TaskRepository, client: &Client, host: &str, action: Action) -> Result<bool> {
+    match action {
+        Action::Import(remote) => {
+            let todo = remote.todo;
+            let mut task = import::new_task(todo.summary.chars().take(import::MAX_TEXT_CHARS).collect());
+            task.completed = todo.completed;
+            task.due_at = todo.due_at;
+            task.priority = todo.priority;
+            let bundle = TaskBundle { tags: todo.categories, ..import::bundle(task) };
+
+            let ids = repo.import(&[bundle]).await?;
+            repo.save_caldav_resource(&remote.href, &todo.uid, remote.etag.as_deref(), ids[0]).await?;
+        }
+        Action::Pull { task_id, remote } => {
+            let todo = remote.todo;
+            let description: String = todo.summary.chars().take(import::MAX_TEXT_CHARS).collect();
+            repo.update_synced_fields(task_id, &description, todo.completed, todo.due_at, todo.priority).await?;
+            repo.save_caldav_resource(&remote.href, &todo.uid, remote.etag.as_deref(), task_id).await?;
+        }
+        Action::Push { task, href, uid, etag } => {
+            let body = calendar(repo, &task, &uid).await?;
+            match client.put(&href, body, etag.as_deref()).await? {
+                Written::Done { etag } => repo.save_caldav_resource(&href, &uid, etag.as_deref(), task.id).await?,
+                Written::Conflict => return Ok(false),
+            }
+        }
+        Action::Create(task) => {
+            let uid = ics::default_uid(task.id, host);
+            let href = format!("{}{}.ics", client.collection.path(), resource_name(&uid));
+            let body = calendar(repo, &task, &uid).await?;
+            match client.put(&href, body, None).await? {
+                Written::Done { etag } => repo.save_caldav_resource(&href, &uid, etag.as_deref(), task.id).await?,
+                Written::Conflict => return Ok(false),
+            }
+        }
+        Action::DeleteLocal { task_id, href } => {
+            repo.delete(task_id).await?;
+            repo.delete_caldav_resource(&href).await?;
+        }
+        Action::DeleteRemote { href, etag } => match client.delete(&href, etag.as_deref()).await? {
+            Written::Done { .. } => repo.delete_caldav_resource(&href).await?,
+            Written::Conflict => return Ok(false),
+        },
+        Action::Forget { href } => repo.delete_caldav_resource(&href).await?,
+    }
+    Ok(true)
+}
+
+fn describe(action: &Action) -> String {
+    match action {
+        Action::Import(remote) => format!("New from server:     '{}'", remote.todo.summary),
+        Action::Pull { task_id, remote } => format!("Update task {}:      '{}' (changed on the server)", task_id, remote.todo.summary),
+        Action::Push { task, etag: None, .. } => format!("Upload again:        '{}' (task {}, deleted on the server)", task.description, task.id),
+        Action::Push { task, .. } => format!("Update on server:    '{}' (task {})", task.description, task.id),
+        Action::Create(task) => format!("Add to server:       '{}' (task {})", task.description, task.id),
+        Action::DeleteLocal { task_id, .. } => format!("Delete task {}:      deleted on the server", task_id),
+        Action::DeleteRemote { href, .. } => format!("Delete on server:    {} (task deleted here)", href),
+        Action::Forget { href } => format!("Forget:              {} (deleted on both sides)", href),
+    }
+}
+
+// The task as a calendar resource with a single VTODO
+async fn calendar(repo: &TaskRepository, task: &Task, uid: &str) -> Result<String> {
+    let tags = repo.tags(task.id).await?;
+    let mut out = Vec::new();
+    ics::write_calendar_header(&mut out)?;
+    ics::write_component(&mut out, task, uid, &tags, &ics::timestamp(), false)?;
+    ics::write_line(&mut out, "END:VCALENDAR")?;
+    // Everything written above came from Strings
+    Ok(String::from_utf8(out).expect("calendar data is UTF-8"))
+}
+
+// File name for a new resource; UIDs may contain characters that don't belong in a URL
+fn resource_name(uid: &str) -> String {
+    uid.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' }).collect()
+}
+
+fn header_etag(response: &reqwest::Response) -> Option<String> {
+    response.headers().get(ETAG).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
+
+// Collects href, getetag and getctag of every <response>, matching elements by local name so
+// any namespace prefix works
+fn parse_multistatus(xml: &str) -> std::result::Result<Vec<DavResponse>, quick_xml::Error> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+
+    let mut responses = Vec::new();
+    let mut current: Option<DavResponse> = None;
+    let mut element: Vec<u8> = Vec::new();
+    loop {
+        match reader.read_event()? {
+            Event::Start(start) => {
+                element = start.local_name().as_ref().to_vec();
+                if element == b"response" {
+                    current = Some(DavResponse::default());
+                }
+            }
+            Event::Text(text) => {
+                if let Some(response) = current.as_mut() {
+                    let text = text.unescape()?.into_owned();
+                    match element.as_slice() {
+                        b"href" => response.href = text,
+                        b"getetag" => response.etag = Some(text),
+                        b"getctag" => response.ctag = Some(text),
+                        _ => {}
+                    }
+                }
+            }
+            Event::End(end) => {
+                if end.local_name().as_ref() == b"response"
+                    && let Some(response) = current.take()
+                {
+                    responses.push(response);
+                }
+                element.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(responses)
+}
+
+// The first VTODO of an iCalendar object, or None if there is none. Properties of nested
+// components (alarms) are ignored.
+fn parse_todo(ics: &str) -> Option<Todo> {
+    let mut todo: Option<Todo> = None;
+    let mut depth = 0;
+
+    for line in unfold(ics) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        let name = name.to_ascii_uppercase();
+
+        match (name.as_str(), todo.as_mut()) {
+            ("BEGIN", None) if value.eq_ignore_ascii_case("VTODO") => {
+                todo = Some(Todo {
+                    uid: String::new(),
+                    summary: UNTITLED.to_string(),
+                    completed: false,
+                    due_at: None,
+                    priority: None,
+                    categories: Vec::new(),
+                });
+            }
+            ("BEGIN", Some(_)) => depth += 1,
+            ("END", Some(_)) if depth > 0 => depth -= 1,
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VTODO") => break,
+            (_, Some(todo)) if depth == 0 => match name.as_str() {
+                "UID" => todo.uid = value.to_string(),
+                "SUMMARY" if !value.trim().is_empty() => todo.summary = unescape_text(value.trim()),
+                "STATUS" => todo.completed = value.eq_ignore_ascii_case("COMPLETED"),
+                "DUE" => todo.due_at = parse_time(value, params),
+                "PRIORITY" => todo.priority = value.trim().parse().ok().and_then(priority_from_ical),
+                "CATEGORIES" => todo.categories.extend(
+                    split_list(value).into_iter().map(|tag| tag.trim().to_string()).filter(|tag| !tag.is_empty()),
+                ),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    // Every VTODO has a UID; one without can't be tracked
+    todo.filter(|todo| !todo.uid.is_empty())
+}
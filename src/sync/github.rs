@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+
+use crate::cli::SyncArgs;
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::repository::{ExternalId, TaskRepository};
+use crate::secrets::{self, Service};
+
+use super::{issue_bundle, send, send_json, RemoteIssue};
+
+const API: &str = "https://api.github.com";
+const SERVICE: &str = "GitHub";
+// `source` of the external ids; the id itself is "owner/name#number"
+const SOURCE: &str = "github";
+// Largest page the API allows
+const PER_PAGE: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct User {
+    login: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    number: u64,
+    title: String,
+    body: Option<String>,
+    #[serde(default)]
+    labels: Vec<IssueLabel>,
+    // Present when the "issue" is a pull request, which the issues API lists too
+    pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueLabel {
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct IssueUpdate {
+    state: &'static str,
+}
+
+#[derive(Debug)]
+enum Action {
+    // Assigned to the user and not linked to a task yet
+    Create(Issue),
+    // The linked task was completed here
+    Close { task_id: i32, number: u64 },
+}
+
+struct Client {
+    http: reqwest::Client,
+    token: String,
+}
+
+impl Client {
+    fn get(&self, url: String) -> reqwest::RequestBuilder {
+        self.authorized(self.http.get(url))
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        request
+            .bearer_auth(&self.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+    }
+
+    async fn login(&self) -> Result<String> {
+        let user: User = send_json(SERVICE, self.get(format!("{}/user", API))).await?;
+        Ok(user.login)
+    }
+
+    // Every open issue in the repository assigned to `login`, pull requests excluded
+    async fn assigned_issues(&self, repository: &str, login: &str) -> Result<Vec<Issue>> {
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let request = self.get(format!("{}/repos/{}/issues", API, repository)).query(&[
+                ("state", "open"),
+                ("assignee", login),
+                ("per_page", &PER_PAGE.to_string()),
+                ("page", &page.to_string()),
+            ]);
+            let batch: Vec<Issue> = send_json(SERVICE, request).await?;
+            let last = batch.len() < PER_PAGE;
+            issues.extend(batch.into_iter().filter(|issue| issue.pull_request.is_none()));
+            if last {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    async fn close(&self, repository: &str, number: u64) -> Result<()> {
+        let request = self.http.patch(format!("{}/repos/{}/issues/{}", API, repository, number));
+        send(SERVICE, self.authorized(request).json(&IssueUpdate { state: "closed" })).await?;
+        Ok(())
+    }
+}
+
+// One-way in each direction: issues assigned to the user become tasks, and completing such a
+// task closes its issue. Links are kept in external_ids with the issue number, so each issue
+// is imported once, and titles edited on either side are left alone.
+pub async fn run(repo: &TaskRepository, config: &Config, args: SyncArgs) -> Result<()> {
+    let repository = repository(config)?;
+    let client = Client { http: super::http_client(SERVICE)?, token: secrets::token(config, Service::Github)? };
+
+    let login = client.login().await?;
+    let issues = client.assigned_issues(repository, &login).await?;
+    let actions = plan(repo, repository, issues).await?;
+
+    if actions.is_empty() {
+        println!("Everything is in sync with {}.", repository);
+        return Ok(());
+    }
+    for action in &actions {
+        match action {
+            Action::Create(issue) => println!("New task for #{}:  '{}'", issue.number, issue.title),
+            Action::Close { task_id, number } => println!("Close #{}:         task {} is completed", number, task_id),
+        }
+    }
+    if args.dry_run {
+        println!("Dry run: {} changes not applied.", actions.len());
+        return Ok(());
+    }
+
+    let total = actions.len();
+    for action in actions {
+        match action {
+            Action::Create(issue) => {
+                let external = external(repository, issue.number);
+                let issue = RemoteIssue {
+                    title: issue.title,
+                    body: issue.body,
+                    labels: issue.labels.into_iter().map(|label| label.name).collect(),
+                };
+                repo.import(&[issue_bundle(issue, external, None)]).await?;
+            }
+            Action::Close { number, .. } => {
+                client.close(repository, number).await?;
+                repo.mark_synced(&external(repository, number)).await?;
+            }
+        }
+    }
+    println!("Synced {} changes with {}.", total, repository);
+    Ok(())
+}
+
+fn repository(config: &Config) -> Result<&str> {
+    let repository = config.github.repository.as_deref().ok_or_else(|| {
+        TaskError::Config(format!(
+            "No GitHub repository configured. Set `repository = \"owner/name\"` in the [github] section of {}.",
+            Config::path().display()
+        ))
+    })?;
+    match repository.split_once('/') {
+        Some((owner, name)) if !owner.is_empty() && !name.is_empty() && !name.contains('/') => Ok(repository),
+        _ => Err(TaskError::Config(format!("GitHub repository '{}' is not of the form owner/name.", repository))),
+    }
+}
+
+async fn plan(repo: &TaskRepository, repository: &str, issues: Vec<Issue>) -> Result<Vec<Action>> {
+    let prefix = format!("{}#", repository);
+    let linked: Vec<_> = repo
+        .linked_tasks(SOURCE)
+        .await?
+        .into_iter()
+        .filter_map(|linked| {
+            let number = linked.external_id.strip_prefix(&prefix)?.parse::<u64>().ok()?;
+            Some((linked, number))
+        })
+        .collect();
+
+    let mut actions: Vec<Action> = issues
+        .into_iter()
+        .filter(|issue| !linked.iter().any(|(_, number)| *number == issue.number))
+        .map(Action::Create)
+        .collect();
+
+    // Only tasks completed since the last sync; closing is not repeated for issues
+    // someone reopened afterwards
+    actions.extend(
+        linked
+            .iter()
+            .filter(|(linked, _)| linked.task.completed && linked.changed)
+            .map(|(linked, number)| Action::Close { task_id: linked.task.id, number: *number }),
+    );
+    Ok(actions)
+}
+
+fn external(repository: &str, number: u64) -> ExternalId {
+    ExternalId { source: SOURCE, id: format!("{}#{}", repository, number) }
+}
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+
+use crate::cli::GitlabSyncArgs;
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::repository::{ExternalId, TaskRepository};
+use crate::secrets::{self, Service};
+
+use super::{issue_bundle, send, send_json, RemoteIssue};
+
+const DEFAULT_URL: &str = "https://gitlab.com";
+const SERVICE: &str = "GitLab";
+// `source` of the external ids; the id itself is "group/project#iid"
+const SOURCE: &str = "gitlab";
+// Largest page the API allows
+const PER_PAGE: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct Issue {
+    // Number within the project, as shown in the web UI
+    iid: u64,
+    title: String,
+    description: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct IssueUpdate {
+    state_event: &'static str,
+}
+
+#[derive(Debug)]
+enum Action {
+    Create { project: String, path: String, issue: Issue },
+    Close { task_id: i32, path: String, iid: u64 },
+}
+
+struct Client {
+    http: reqwest::Client,
+    api: String,
+    token: String,
+}
+
+impl Client {
+    // Projects are addressed by their URL-encoded path
+    fn project_url(&self, path: &str) -> String {
+        format!("{}/projects/{}", self.api, path.replace('/', "%2F"))
+    }
+
+    async fn assigned_issues(&self, path: &str) -> Result<Vec<Issue>> {
+        let mut issues = Vec::new();
+        for page in 1.. {
+            let request = self
+                .http
+                .get(format!("{}/issues", self.project_url(path)))
+                .header("PRIVATE-TOKEN", &self.token)
+                .query(&[
+                    ("state", "opened"),
+                    ("scope", "assigned_to_me"),
+                    ("per_page", &PER_PAGE.to_string()),
+                    ("page", &page.to_string()),
+                ]);
+            let batch: Vec<Issue> = send_json(SERVICE, request).await?;
+            let last = batch.len() < PER_PAGE;
+            issues.extend(batch);
+            if last {
+                break;
+            }
+        }
+        Ok(issues)
+    }
+
+    async fn close(&self, path: &str, iid: u64) -> Result<()> {
+        let request = self
+            .http
+            .put(format!("{}/issues/{}", self.project_url(path), iid))
+            .header("PRIVATE-TOKEN", &self.token)
+            .json(&IssueUpdate { state_event: "close" });
+        send(SERVICE, request).await?;
+        Ok(())
+    }
+}
+
+// Like the GitHub sync, but per project: each local project listed under [gitlab.projects]
+// takes the issues assigned to the user in one GitLab project, and completing such a task
+// closes its issue. --project limits a run to one of them.
+pub async fn run(repo: &TaskRepository, config: &Config, args: GitlabSyncArgs) -> Result<()> {
+    let gitlab = &config.gitlab;
+    if gitlab.projects.is_empty() {
+        return Err(TaskError::Config(format!(
+            "No GitLab projects configured. Map local projects to GitLab projects in the [gitlab.projects] section of {}, \
+             e.g. Work = \"group/project\".",
+            Config::path().display()
+        )));
+    }
+
+    let projects: Vec<(&String, &String)> = match &args.project {
+        Some(name) => {
+            let mapping = gitlab.projects.get_key_value(name).ok_or_else(|| {
+                TaskError::InvalidInput(format!("Project '{}' has no GitLab project in [gitlab.projects].", name))
+            })?;
+            vec![mapping]
+        }
+        None => gitlab.projects.iter().collect(),
+    };
+
+    let url = gitlab.url.as_deref().unwrap_or(DEFAULT_URL).trim_end_matches('/');
+    let client = Client {
+        http: super::http_client(SERVICE)?,
+        api: format!("{}/api/v4", url),
+        token: secrets::token(config, Service::Gitlab)?,
+    };
+
+    let linked = repo.linked_tasks(SOURCE).await?;
+    let mut actions = Vec::new();
+    for (project, path) in projects {
+        let prefix = format!("{}#", path);
+        let numbers: Vec<(i32, bool, u64)> = linked
+            .iter()
+            .filter_map(|linked| {
+                let iid = linked.external_id.strip_prefix(&prefix)?.parse::<u64>().ok()?;
+                Some((linked.task.id, linked.task.completed && linked.changed, iid))
+            })
+            .collect();
+
+        for issue in client.assigned_issues(path).await? {
+            if !numbers.iter().any(|(_, _, iid)| *iid == issue.iid) {
+                actions.push(Action::Create { project: project.clone(), path: path.clone(), issue });
+            }
+        }
+        actions.extend(
+            numbers
+                .into_iter()
+                .filter(|(_, completed, _)| *completed)
+                .map(|(task_id, _, iid)| Action::Close { task_id, path: path.clone(), iid }),
+        );
+    }
+
+    if actions.is_empty() {
+        println!("Everything is in sync with GitLab.");
+        return Ok(());
+    }
+    for action in &actions {
+        match action {
+            Action::Create { project, path, issue } => {
+                println!("New task in '{}' for {}#{}: '{}'", project, path, issue.iid, issue.title)
+            }
+            Action::Close { task_id, path, iid } => println!("Close {}#{}: task {} is completed", path, iid, task_id),
+        }
+    }
+    if args.options.dry_run {
+        println!("Dry run: {} changes not applied.", actions.len());
+        return Ok(());
+    }
+
+    let total = actions.len();
+    for action in actions {
+        match action {
+            Action::Create { project, path, issue } => {
+                let external = external(&path, issue.iid);
+                let issue = RemoteIssue {
+                    title: issue.title,
+                    body: issue.description,
+                    labels: issue.labels,
+                };
+                repo.import(&[issue_bundle(issue, external, Some(project))]).await?;
+            }
+            Action::Close { path, iid, .. } => {
+                client.close(&path, iid).await?;
+                repo.mark_synced(&external(&path, iid)).await?;
+            }
+        }
+    }
+    println!("Synced {} changes with GitLab.", total);
+    Ok(())
+}
+
+fn external(path: &str, iid: u64) -> ExternalId {
+    ExternalId { source: SOURCE, id: format!("{}#{}", path, iid) }
+}
@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use chrono::{Days, Local, NaiveDateTime, TimeDelta};
+use serde::{Deserialize, Serialize};
+
+use crate::cli::SyncArgs;
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::repository::{ExternalId, TaskRepository};
+use crate::secrets::{self, Service};
+use crate::Task;
+
+use super::{is_all_day, remote_error, send_json};
+
+const SERVICE: &str = "Google Calendar";
+// `source` of the external ids; the id itself is the event id
+const SOURCE: &str = "google";
+
+const DEVICE_CODE_URL: &str = "https://oauth2.googleapis.com/device/code";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const API: &str = "https://www.googleapis.com/calendar/v3";
+// Events only, not the rest of the user's calendars
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+const DEVICE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+
+// Length of the event for a due date with a time
+const EVENT_MINUTES: i64 = 30;
+// Private extended properties of the events. MARKER tags every event created by this tool, so
+// events whose task was deleted here can be found and removed; use a calendar of its own per
+// database, or one database's sync removes the other's events.
+const TASK_PROPERTY: &str = "taskId";
+const MARKER: (&str, &str) = ("createdBy", "task");
+
+#[derive(Debug, Deserialize)]
+struct DeviceCode {
+    device_code: String,
+    user_code: String,
+    verification_url: String,
+    expires_in: u64,
+    #[serde(default = "default_interval")]
+    interval: u64,
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Event {
+    summary: String,
+    start: EventTime,
+    end: EventTime,
+    extended_properties: ExtendedProperties,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EventTime {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date_time: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ExtendedProperties {
+    private: HashMap<&'static str, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreatedEvent {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventList {
+    #[serde(default)]
+    items: Vec<CreatedEvent>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug)]
+enum Action {
+    // Pending with a due date and no event yet
+    Create(Task),
+    // Changed since the event was last written
+    Update { task: Task, event_id: String },
+    // Completed, or no longer has a due date
+    Remove { task_id: i32, event_id: String },
+    // Event of a task that was deleted here
+    RemoveOrphan { event_id: String },
+}
+
+struct Client {
+    http: reqwest::Client,
+    access_token: String,
+    calendar: String,
+}
+
+impl Client {
+    fn events_url(&self) -> String {
+        // Calendar ids are email addresses or "primary"; '@' is fine in a path, '#' is not
+        format!("{}/calendars/{}/events", API, self.calendar.replace('#', "%23"))
+    }
+
+    async fn create(&self, event: &Event) -> Result<String> {
+        let request = self.http.post(self.events_url()).bearer_auth(&self.access_token).json(event);
+        let created: CreatedEvent = send_json(SERVICE, request).await?;
+        Ok(created.id)
+    }
+
+    // False if the event no longer exists (deleted in the calendar)
+    async fn update(&self, event_id: &str, event: &Event) -> Result<bool> {
+        let url = format!("{}/{}", self.events_url(), event_id);
+        let response = self
+            .http
+            .put(url)
+            .bearer_auth(&self.access_token)
+            .json(event)
+            .send()
+            .await
+            .map_err(|e| remote_error(SERVICE, e))?;
+        if matches!(response.status().as_u16(), 404 | 410) {
+            return Ok(false);
+        }
+        super::check_status(SERVICE, response).await?;
+        Ok(true)
+    }
+
+    async fn delete(&self, event_id: &str) -> Result<()> {
+        let url = format!("{}/{}", self.events_url(), event_id);
+        let response = self
+            .http
+            .delete(url)
+            .bearer_auth(&self.access_token)
+            .send()
+            .await
+            .map_err(|e| remote_error(SERVICE, e))?;
+        // Already gone is as good as deleted
+        if !matches!(response.status().as_u16(), 404 | 410) {
+            super::check_status(SERVICE, response).await?;
+        }
+        Ok(())
+    }
+
+    // Ids of all events this tool created in the calendar
+    async fn own_events(&self) -> Result<Vec<String>> {
+        let marker = format!("{}={}", MARKER.0, MARKER.1);
+        let mut ids = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .http
+                .get(self.events_url())
+                .bearer_auth(&self.access_token)
+                .query(&[("privateExtendedProperty", marker.as_str()), ("maxResults", "2500")]);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+            let list: EventList = send_json(SERVICE, request).await?;
+            ids.extend(list.items.into_iter().map(|event| event.id));
+            match list.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
+        Ok(ids)
+    }
+}
+
+// Pushes tasks with due dates to a Google Calendar as events. Events are created for pending
+// tasks, rewritten when their task changes, and removed once the task is completed, loses its
+// due date or is deleted. It only goes this way: edits made to the events in the calendar
+// are overwritten by the next change to the task. Sign-in uses the OAuth device flow; the
+// refresh token it yields is kept in the system keyring.
+pub async fn run(repo: &TaskRepository, config: &Config, args: SyncArgs) -> Result<()> {
+    let http = super::http_client(SERVICE)?;
+    let access_token = access_token(&http, config).await?;
+    let calendar = config.google.calendar.clone().unwrap_or_else(|| "primary".to_string());
+    let client = Client { http, access_token, calendar };
+
+    let actions = plan(repo, &client).await?;
+    if actions.is_empty() {
+        println!("Google Calendar is up to date.");
+        return Ok(());
+    }
+    for action in &actions {
+        match action {
+            Action::Create(task) => println!("Add event:     '{}' (task {})", task.description, task.id),
+            Action::Update { task, .. } => println!("Update event:  '{}' (task {})", task.description, task.id),
+            Action::Remove { task_id, .. } => println!("Remove event:  task {} is completed or has no due date", task_id),
+            Action::RemoveOrphan { event_id } => println!("Remove event:  {} (task deleted)", event_id),
+        }
+    }
+    if args.dry_run {
+        println!("Dry run: {} changes not applied.", actions.len());
+        return Ok(());
+    }
+
+    let total = actions.len();
+    for action in actions {
+        match action {
+            Action::Create(task) => {
+                let event_id = client.create(&event(&task)).await?;
+                repo.link_external(task.id, &external(event_id)).await?;
+            }
+            Action::Update { task, event_id } => {
+                if client.update(&event_id, &event(&task)).await? {
+                    repo.mark_synced(&external(event_id)).await?;
+                } else {
+                    // Deleted in the calendar; the task still has a due date, so it gets a new one
+                    repo.unlink_external(task.id, SOURCE, Some(&event_id)).await?;
+                    let event_id = client.create(&event(&task)).await?;
+                    repo.link_external(task.id, &external(event_id)).await?;
+                }
+            }
+            Action::Remove { task_id, event_id } => {
+                client.delete(&event_id).await?;
+                repo.unlink_external(task_id, SOURCE, Some(&event_id)).await?;
+            }
+            Action::RemoveOrphan { event_id } => client.delete(&event_id).await?,
+        }
+    }
+    println!("Applied {} changes to Google Calendar.", total);
+    Ok(())
+}
+
+async fn plan(repo: &TaskRepository, client: &Client) -> Result<Vec<Action>> {
+    let mut actions = Vec::new();
+    let mut linked_events = HashSet::new();
+
+    for linked in repo.linked_tasks(SOURCE).await? {
+        linked_events.insert(linked.external_id.clone());
+        let task = linked.task;
+        if task.completed || task.due_at.is_none() {
+            actions.push(Action::Remove { task_id: task.id, event_id: linked.external_id });
+        } else if linked.changed {
+            actions.push(Action::Update { task, event_id: linked.external_id });
+        }
+    }
+
+    for event_id in client.own_events().await? {
+        if !linked_events.contains(&event_id) {
+            actions.push(Action::RemoveOrphan { event_id });
+        }
+    }
+
+    let unlinked = repo.unlinked_pending(SOURCE).await?;
+    actions.extend(unlinked.into_iter().filter(|task| task.due_at.is_some()).map(Action::Create));
+    Ok(actions)
+}
+
+fn event(task: &Task) -> Event {
+    let due_at = task.due_at.unwrap_or_default();
+    let (start, end) = if is_all_day(&due_at) {
+        // All-day events end (exclusively) on the next day
+        let next_day = due_at.checked_add_days(Days::new(1)).unwrap_or(due_at);
+        (date(&due_at), date(&next_day))
+    } else {
+        (date_time(&due_at), date_time(&(due_at + TimeDelta::minutes(EVENT_MINUTES))))
+    };
+
+    Event {
+        summary: task.description.clone(),
+        start,
+        end,
+        extended_properties: ExtendedProperties { private: [(TASK_PROPERTY, task.id.to_string()), (MARKER.0, MARKER.1.to_string())].into() },
+    }
+}
+
+fn date(value: &NaiveDateTime) -> EventTime {
+    EventTime { date: Some(value.format("%Y-%m-%d").to_string()), date_time: None }
+}
+
+// Due times are local; the API wants an offset
+fn date_time(value: &NaiveDateTime) -> EventTime {
+    let date_time = value.and_local_timezone(Local).earliest().map(|local| local.to_rfc3339());
+    EventTime { date: None, date_time }
+}
+
+fn external(event_id: String) -> ExternalId {
+    ExternalId { source: SOURCE, id: event_id }
+}
+
+fn credentials(config: &Config) -> Result<(&str, &str)> {
+    match (config.google.client_id.as_deref(), config.google.client_secret.as_deref()) {
+        (Some(id), Some(secret)) => Ok((id, secret)),
+        _ => Err(TaskError::Config(format!(
+            "Google Calendar sync needs an OAuth client of type \"TVs and Limited Input devices\". \
+             Set `client_id` and `client_secret` in the [google] section of {}.",
+            Config::path().display()
+        ))),
+    }
+}
+
+// A fresh access token from the stored refresh token, signing in first if there is none
+async fn access_token(http: &reqwest::Client, config: &Config) -> Result<String> {
+    let (client_id, client_secret) = credentials(config)?;
+    let refresh_token = match secrets::lookup(config, Service::Google)? {
+        Some(token) => token,
+        None => sign_in(http, client_id, client_secret).await?,
+    };
+
+    let form = [
+        ("client_id", client_id),
+        ("client_secret", client_secret),
+        ("refresh_token", &refresh_token),
+        ("grant_type", "refresh_token"),
+    ];
+    let response: TokenResponse = send_json(SERVICE, http.post(TOKEN_URL).form(&form)).await.map_err(|e| {
+        TaskError::Config(format!(
+            "{}. If access was revoked, run `task token delete google` and sync again to sign in.",
+            e
+        ))
+    })?;
+    response.access_token.ok_or_else(|| remote_error(SERVICE, "no access token in the response"))
+}
+
+// OAuth device flow: the user approves access on another device while we poll for the result
+async fn sign_in(http: &reqwest::Client, client_id: &str, client_secret: &str) -> Result<String> {
+    let request = http.post(DEVICE_CODE_URL).form(&[("client_id", client_id), ("scope", SCOPE)]);
+    let code: DeviceCode = send_json(SERVICE, request).await?;
+
+    println!("To let this tool add events to your calendar, open {}", code.verification_url);
+    println!("and enter the code {}", code.user_code);
+
+    let mut interval = Duration::from_secs(code.interval);
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(code.expires_in);
+    while tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(interval).await;
+
+        let form = [
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("device_code", code.device_code.as_str()),
+            ("grant_type", DEVICE_GRANT),
+        ];
+        // Pending approval is reported as an error status, so the body is read either way
+        let response = http.post(TOKEN_URL).form(&form).send().await.map_err(|e| remote_error(SERVICE, e))?;
+        let token: TokenResponse = response.json().await.map_err(|e| remote_error(SERVICE, e))?;
+
+        match (token.refresh_token, token.error.as_deref()) {
+            (Some(refresh_token), _) => {
+                secrets::store(Service::Google, &refresh_token)?;
+                println!("Signed in. The refresh token is stored in the system keyring.");
+                return Ok(refresh_token);
+            }
+            (None, Some("authorization_pending")) => {}
+            (None, Some("slow_down")) => interval += Duration::from_secs(5),
+            (None, Some("access_denied")) => {
+                return Err(remote_error(SERVICE, "access was denied"));
+            }
+            (None, error) => {
+                return Err(remote_error(SERVICE, format!("sign-in failed: {}", error.unwrap_or("no refresh token"))));
+            }
+        }
+    }
+    Err(remote_error(SERVICE, "the sign-in code expired before it was entered"))
+}
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::de::DeserializeOwned;
+
+use crate::error::{Result, TaskError};
+use crate::import;
+use crate::repository::{ExternalId, Note, TaskBundle};
+
+// The services of `task sync`; the helpers below are also what the other HTTP clients use
+#[cfg(feature = "integrations")]
+pub mod caldav;
+#[cfg(feature = "integrations")]
+pub mod github;
+#[cfg(feature = "integrations")]
+pub mod gitlab;
+#[cfg(feature = "integrations")]
+pub mod google;
+#[cfg(feature = "integrations")]
+pub mod notion;
+#[cfg(feature = "integrations")]
+pub mod todoist;
+#[cfg(feature = "integrations")]
+pub mod vault;
+
+// Applies to every request so a hanging API can't hang the CLI
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn http_client(service: &'static str) -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .user_agent(concat!("task/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| remote_error(service, e))
+}
+
+pub fn remote_error(service: &'static str, e: impl std::fmt::Display) -> TaskError {
+    TaskError::Remote { service, message: e.to_string() }
+}
+
+// Sends a request and fails on non-2xx responses, including the body the API sent back
+pub async fn send(service: &'static str, request: reqwest::RequestBuilder) -> Result<reqwest::Response> {
+    let response = request.send().await.map_err(|e| remote_error(service, e))?;
+    check_status(service, response).await
+}
+
+pub async fn check_status(service: &'static str, response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    let hint = match status.as_u16() {
+        401 | 403 => " Check the API token.",
+        _ => "",
+    };
+    Err(remote_error(service, format!("{} {}{}", status, body.trim(), hint)))
+}
+
+pub async fn send_json<T: DeserializeOwned>(service: &'static str, request: reqwest::RequestBuilder) -> Result<T> {
+    send(service, request).await?.json().await.map_err(|e| remote_error(service, e))
+}
+
+// Remote timestamps are RFC 3339; ones without an offset are taken as local time already
+pub fn parse_remote_time(value: &str) -> Option<NaiveDateTime> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Local).naive_local());
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok()
+}
+
+pub fn parse_remote_date(value: &str) -> Option<NaiveDateTime> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().map(|date| date.and_time(NaiveTime::MIN))
+}
+
+// Local time (as stored) to UTC in RFC 3339, for APIs that want an absolute time
+pub fn to_utc_rfc3339(value: &NaiveDateTime) -> Option<String> {
+    value
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|local| local.with_timezone(&Utc).format("%Y-%m-%dT%H:%M:%SZ").to_string())
+}
+
+pub fn is_all_day(value: &NaiveDateTime) -> bool {
+    value.time() == NaiveTime::MIN
+}
+
+// An issue from an issue tracker, in the shape both trackers share
+pub struct RemoteIssue {
+    pub title: String,
+    pub body: Option<String>,
+    pub labels: Vec<String>,
+}
+
+// New task for an issue: labels become tags and the issue text a note
+pub fn issue_bundle(issue: RemoteIssue, external: ExternalId, project: Option<String>) -> TaskBundle {
+    // Issue titles may be longer than a description
+    let task = import::new_task(issue.title.chars().take(import::MAX_TEXT_CHARS).collect());
+    let notes = match issue.body.as_deref().map(str::trim) {
+        None | Some("") => Vec::new(),
+        Some(body) => vec![Note { created_at: task.created_at, body: body.to_string(), created_by: None }],
+    };
+    TaskBundle { tags: issue.labels, project, notes, external: Some(external), ..import::bundle(task) }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::fixtures;
+
+    proptest! {
+        // What we send as UTC comes back as the same local time
+        #[test]
+        fn utc_times_read_back(time in fixtures::date_time()) {
+            // Local times that don't exist (a DST gap) are never sent
+            if let Some(utc) = to_utc_rfc3339(&time) {
+                prop_assert_eq!(parse_remote_time(&utc), Some(time));
+            }
+        }
+
+        #[test]
+        fn times_without_offset_are_local(time in fixtures::date_time()) {
+            prop_assert_eq!(parse_remote_time(&time.format("%Y-%m-%dT%H:%M:%S").to_string()), Some(time));
+        }
+
+        #[test]
+        fn dates_read_back(date in fixtures::date()) {
+            let midnight = date.and_time(NaiveTime::MIN);
+            prop_assert_eq!(parse_remote_date(&date.format("%Y-%m-%d").to_string()), Some(midnight));
+        }
+
+        #[test]
+        fn any_text_parses_or_is_rejected(value in "\\PC*") {
+            let _ = parse_remote_time(&value);
+            let _ = parse_remote_date(&value);
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::cli::NotionSyncArgs;
+use crate::config::{Config, NotionConfig};
+use crate::error::{Result, TaskError};
+use crate::import;
+use crate::repository::{ExternalId, TaskBundle, TaskRepository};
+use crate::secrets::{self, Service};
+use crate::Task;
+
+use super::{is_all_day, parse_remote_date, parse_remote_time, send, send_json, to_utc_rfc3339};
+
+const API: &str = "https://api.notion.com/v1";
+const API_VERSION: &str = "2022-06-28";
+const SERVICE: &str = "Notion";
+// `source` of the external ids; the id itself is the page id
+const SOURCE: &str = "notion";
+// The largest page size the API allows
+const PAGE_SIZE: u32 = 100;
+
+// Property names used when [notion] doesn't name them
+const STATUS_PROPERTY: &str = "Status";
+const DUE_PROPERTY: &str = "Due";
+const TAGS_PROPERTY: &str = "Tags";
+const DONE_STATUS: &str = "Done";
+const OPEN_STATUS: &str = "Not started";
+
+#[derive(Debug, Deserialize)]
+struct Database {
+    properties: HashMap<String, PropertySchema>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PropertySchema {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryResults {
+    results: Vec<RemotePage>,
+    has_more: bool,
+    next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RemotePage {
+    id: String,
+    // RFC 3339, to the minute
+    last_edited_time: String,
+    properties: Map<String, Value>,
+}
+
+// How a completed task shows in the database. For status and select properties done is the
+// `done_status` option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StatusKind {
+    Status,
+    Select,
+    Checkbox,
+}
+
+impl StatusKind {
+    // Key of the property's value in pages, the same as its type name
+    fn key(self) -> &'static str {
+        match self {
+            StatusKind::Status => "status",
+            StatusKind::Select => "select",
+            StatusKind::Checkbox => "checkbox",
+        }
+    }
+}
+
+// The database's properties that tasks map to. Status, due and tags are optional; when the
+// database lacks one, that field is left out of the sync.
+#[derive(Debug)]
+struct Properties {
+    title: String,
+    status: Option<(String, StatusKind)>,
+    due: Option<String>,
+    tags: Option<String>,
+    done_status: String,
+    open_status: String,
+}
+
+// A page read into the fields being synced
+#[derive(Debug, Clone)]
+struct RemoteTask {
+    id: String,
+    title: String,
+    completed: bool,
+    due_at: Option<NaiveDateTime>,
+    tags: Vec<String>,
+}
+
+// What one sync run will do, worked out before anything is changed so --dry-run can show it
+#[derive(Debug)]
+enum Action {
+    // New in Notion: create the task here
+    Import(RemoteTask),
+    // Changed in Notion only: take its fields
+    Pull { task: Task, remote: RemoteTask },
+    // Changed here: overwrite the page's properties. Local edits win if both sides changed.
+    Push { task: Task, tags: Vec<String>, page_id: String },
+    // New here: add a page for it
+    Create { task: Task, tags: Vec<String> },
+}
+
+struct Client {
+    http: reqwest::Client,
+    token: String,
+    database_id: String,
+}
+
+impl Client {
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.http
+            .request(method, format!("{}/{}", API, path))
+            .bearer_auth(&self.token)
+            .header("Notion-Version", API_VERSION)
+    }
+
+    async fn database(&self) -> Result<Database> {
+        send_json(SERVICE, self.request(reqwest::Method::GET, &format!("databases/{}", self.database_id))).await
+    }
+
+    // Every page of the database, or only those edited at or after `since`
+    async fn pages(&self, since: Option<&str>) -> Result<Vec<RemotePage>> {
+        let path = format!("databases/{}/query", self.database_id);
+        let mut pages = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let mut body = json!({ "page_size": PAGE_SIZE });
+            if let Some(since) = since {
+                body["filter"] = json!({ "timestamp": "last_edited_time", "last_edited_time": { "on_or_after": since } });
+            }
+            if let Some(cursor) = &cursor {
+                body["start_cursor"] = json!(cursor);
+            }
+            let results: QueryResults = send_json(SERVICE, self.request(reqwest::Method::POST, &path).json(&body)).await?;
+            pages.extend(results.results);
+            match results.next_cursor {
+                Some(next) if results.has_more => cursor = Some(next),
+                _ => return Ok(pages),
+            }
+        }
+    }
+
+    async fn create(&self, properties: Map<String, Value>) -> Result<RemotePage> {
+        let body = json!({ "parent": { "database_id": self.database_id }, "properties": properties });
+        send_json(SERVICE, self.request(reqwest::Method::POST, "pages").json(&body)).await
+    }
+
+    async fn update(&self, page_id: &str, properties: Map<String, Value>) -> Result<()> {
+        let body = json!({ "properties": properties });
+        send(SERVICE, self.request(reqwest::Method::PATCH, &format!("pages/{}", page_id)).json(&body)).await?;
+        Ok(())
+    }
+}
+
+// Two-way sync with a Notion database, one page per task. Pages are linked to tasks through the
+// external_ids table. After the first run only pages edited since the newest edit seen last
+// time are fetched (`--full` fetches all of them again); pages deleted in Notion are therefore
+// not noticed, and their tasks stay as they are. Tags changed here are sent along the next time
+// the task itself changes.
+pub async fn run(repo: &TaskRepository, config: &Config, args: NotionSyncArgs) -> Result<()> {
+    let notion = &config.notion;
+    let database_id = notion.database_id.clone().ok_or_else(|| {
+        TaskError::Config(format!(
+            "No Notion database configured. Set `database_id` in the [notion] section of {}.",
+            Config::path().display()
+        ))
+    })?;
+    let client = Client { http: super::http_client(SERVICE)?, token: secrets::token(config, Service::Notion)?, database_id };
+
+    let properties = properties(notion, client.database().await?)?;
+    let since = if args.full { None } else { repo.notion_last_edited(&client.database_id).await? };
+    let pages = client.pages(since.as_deref()).await?;
+    // Kept as text: comparing Notion's own timestamps needs no parsing
+    let newest = pages.iter().map(|page| page.last_edited_time.clone()).max().or(since);
+
+    let remote = pages.iter().map(|page| read_page(&properties, page)).collect();
+    let actions = plan(repo, remote).await?;
+
+    if actions.is_empty() {
+        println!("Everything is in sync with Notion.");
+    } else {
+        for action in &actions {
+            println!("{}", describe(action));
+        }
+    }
+    if args.options.dry_run {
+        if !actions.is_empty() {
+            println!("Dry run: {} changes not applied.", actions.len());
+        }
+        return Ok(());
+    }
+
+    let total = actions.len();
+    for action in actions {
+        apply(repo, &client, &properties, action).await?;
+    }
+    if let Some(newest) = newest {
+        repo.save_notion_last_edited(&client.database_id, &newest).await?;
+    }
+    if total > 0 {
+        println!("Synced {} changes with Notion.", total);
+    }
+    Ok(())
+}
+
+// Matches the configured property names against the database. Only the title is required.
+fn properties(notion: &NotionConfig, database: Database) -> Result<Properties> {
+    let mut schema = database.properties;
+    let title = schema
+        .iter()
+        .find(|(_, property)| property.kind == "title")
+        .map(|(name, _)| name.clone())
+        .ok_or_else(|| TaskError::Remote { service: SERVICE, message: "the database has no title property".to_string() })?;
+
+    // A property named in the config must exist with a usable type; a default name may be missing
+    let mut lookup = |configured: &Option<String>, default: &str, kinds: &[&str]| -> Result<Option<(String, String)>> {
+        let name = configured.as_deref().unwrap_or(default);
+        match schema.remove(name) {
+            Some(property) if kinds.contains(&property.kind.as_str()) => Ok(Some((name.to_string(), property.kind))),
+            Some(property) => Err(TaskError::Config(format!(
+                "Notion property '{}' is a {} property; expected one of: {}.",
+                name,
+                property.kind,
+                kinds.join(", ")
+            ))),
+            None if configured.is_some() => {
+                Err(TaskError::Config(format!("The Notion database has no property named '{}'.", name)))
+            }
+            None => Ok(None),
+        }
+    };
+
+    let status = lookup(&notion.status_property, STATUS_PROPERTY, &["status", "select", "checkbox"])?;
+    let due = lookup(&notion.due_property, DUE_PROPERTY, &["date"])?;
+    let tags = lookup(&notion.tags_property, TAGS_PROPERTY, &["multi_select"])?;
+    for (field, property) in [("completion", status.is_none()), ("due dates", due.is_none()), ("tags", tags.is_none())] {
+        if property {
+            println!("Warning: the Notion database has no property for {}; they are not synced.", field);
+        }
+    }
+
+    Ok(Properties {
+        title,
+        status: status.map(|(name, kind)| {
+            let kind = match kind.as_str() {
+                "checkbox" => StatusKind::Checkbox,
+                "select" => StatusKind::Select,
+                _ => StatusKind::Status,
+            };
+            (name, kind)
+        }),
+        due: due.map(|(name, _)| name),
+        tags: tags.map(|(name, _)| name),
+        done_status: notion.done_status.clone().unwrap_or_else(|| DONE_STATUS.to_string()),
+        open_status: notion.open_status.clone().unwrap_or_else(|| OPEN_STATUS.to_string()),
+    })
+}
+
+async fn plan(repo: &TaskRepository, remote: Vec<RemoteTask>) -> Result<Vec<Action>> {
+    let mut remote: HashMap<String, RemoteTask> = remote.into_iter().map(|task| (task.id.clone(), task)).collect();
+    let mut actions = Vec::new();
+
+    for linked in repo.linked_tasks(SOURCE).await? {
+        let tags = repo.tags(linked.task.id).await?;
+        // Pages not fetched this time haven't changed in Notion
+        match remote.remove(&linked.external_id) {
+            Some(page) if !differs(&linked.task, &tags, &page) => {}
+            Some(page) if !linked.changed => actions.push(Action::Pull { task: linked.task, remote: page }),
+            None if !linked.changed => {}
+            _ => actions.push(Action::Push { task: linked.task, tags, page_id: linked.external_id }),
+        }
+    }
+
+    // Pages that were already done when they showed up aren't worth importing
+    let mut new_remote: Vec<RemoteTask> = remote.into_values().filter(|page| !page.completed).collect();
+    new_remote.sort_by(|a, b| a.id.cmp(&b.id));
+    actions.extend(new_remote.into_iter().map(Action::Import));
+
+    for task in repo.unlinked_pending(SOURCE).await? {
+        let tags = repo.tags(task.id).await?;
+        actions.push(Action::Create { task, tags });
+    }
+
+    Ok(actions)
+}
+
+async fn apply(repo: &TaskRepository, client: &Client, properties: &Properties, action: Action) -> Result<()> {
+    match action {
+        Action::Import(remote) => {
+            let mut task = import::new_task(remote.title.chars().take(import::MAX_TEXT_CHARS).collect());
+            task.due_at = remote.due_at;
+            let bundle = TaskBundle { tags: remote.tags, external: Some(external(remote.id)), ..import::bundle(task) };
+            repo.import(&[bundle]).await?;
+        }
+        Action::Pull { task, remote } => {
+            // Fields the database doesn't have keep their local value
+            let completed = if properties.status.is_some() { remote.completed } else { task.completed };
+            let due_at = if properties.due.is_some() { remote.due_at } else { task.due_at };
+            let description: String = remote.title.chars().take(import::MAX_TEXT_CHARS).collect();
+            repo.update_synced_fields(task.id, &description, completed, due_at, task.priority).await?;
+            if properties.tags.is_some() {
+                repo.set_tags(task.id, &remote.tags).await?;
+            }
+            repo.mark_synced(&external(remote.id)).await?;
+        }
+        Action::Push { task, tags, page_id } => {
+            client.update(&page_id, page_properties(properties, &task, &tags)).await?;
+            repo.mark_synced(&external(page_id)).await?;
+        }
+        Action::Create { task, tags } => {
+            let created = client.create(page_properties(properties, &task, &tags)).await?;
+            repo.link_external(task.id, &external(created.id)).await?;
+        }
+    }
+    Ok(())
+}
+
+fn describe(action: &Action) -> String {
+    match action {
+        Action::Import(remote) => format!("New from Notion:      '{}'", remote.title),
+        Action::Pull { task, remote } => format!("Update task {}:       '{}' (changed in Notion)", task.id, remote.title),
+        Action::Push { task, .. } => format!("Update in Notion:     '{}' (task {})", task.description, task.id),
+        Action::Create { task, .. } => format!("Add to Notion:        '{}' (task {})", task.description, task.id),
+    }
+}
+
+fn external(id: String) -> ExternalId {
+    ExternalId { source: SOURCE, id }
+}
+
+// Fields the database doesn't have read as empty on both sides, so they never differ
+fn differs(task: &Task, tags: &[String], remote: &RemoteTask) -> bool {
+    // Tag names compare like the column collation does
+    let normalize = |tags: &[String]| {
+        let mut tags: Vec<String> = tags.iter().map(|tag| tag.to_lowercase()).collect();
+        tags.sort();
+        tags
+    };
+    task.description != remote.title
+        || task.completed != remote.completed
+        || task.due_at != remote.due_at
+        || normalize(tags) != normalize(&remote.tags)
+}
+
+fn read_page(properties: &Properties, page: &RemotePage) -> RemoteTask {
+    let property = |name: &str| page.properties.get(name);
+
+    let title = property(&properties.title)
+        .and_then(|value| value["title"].as_array())
+        .map(|parts| parts.iter().filter_map(|part| part["plain_text"].as_str()).collect::<String>())
+        .unwrap_or_default();
+    let completed = match &properties.status {
+        Some((name, StatusKind::Checkbox)) => property(name).and_then(|value| value["checkbox"].as_bool()).unwrap_or(false),
+        Some((name, kind)) => property(name)
+            .and_then(|value| value[kind.key()]["name"].as_str())
+            .is_some_and(|status| status == properties.done_status),
+        None => false,
+    };
+    let due_at = properties
+        .due
+        .as_deref()
+        .and_then(property)
+        .and_then(|value| value["date"]["start"].as_str())
+        .and_then(|start| if start.len() == 10 { parse_remote_date(start) } else { parse_remote_time(start) });
+    let tags = properties
+        .tags
+        .as_deref()
+        .and_then(property)
+        .and_then(|value| value["multi_select"].as_array())
+        .map(|options| options.iter().filter_map(|option| option["name"].as_str().map(String::from)).collect())
+        .unwrap_or_default();
+
+    RemoteTask { id: page.id.clone(), title: title.trim().to_string(), completed, due_at, tags }
+}
+
+fn page_properties(properties: &Properties, task: &Task, tags: &[String]) -> Map<String, Value> {
+    let mut values = Map::new();
+    values.insert(properties.title.clone(), json!({ "title": [{ "text": { "content": task.description } }] }));
+    match &properties.status {
+        Some((name, StatusKind::Checkbox)) => {
+            values.insert(name.clone(), json!({ "checkbox": task.completed }));
+        }
+        Some((name, kind)) => {
+            let status = if task.completed { &properties.done_status } else { &properties.open_status };
+            values.insert(name.clone(), json!({ kind.key(): { "name": status } }));
+        }
+        None => {}
+    }
+    if let Some(name) = &properties.due {
+        let date = match &task.due_at {
+            Some(due_at) if is_all_day(due_at) => json!({ "start": due_at.format("%Y-%m-%d").to_string() }),
+            Some(due_at) => json!({ "start": to_utc_rfc3339(due_at) }),
+            None => Value::Null,
+        };
+        values.insert(name.clone(), json!({ "date": date }));
+    }
+    if let Some(name) = &properties.tags {
+        let options: Vec<Value> = tags.iter().map(|tag| json!({ "name": tag })).collect();
+        values.insert(name.clone(), json!({ "multi_select": options }));
+    }
+    values
+}
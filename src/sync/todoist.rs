@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::cli::SyncArgs;
+use crate::config::Config;
+use crate::error::Result;
+use crate::import;
+use crate::priority::Priority;
+use crate::repository::{ExternalId, TaskBundle, TaskRepository};
+use crate::secrets::{self, Service};
+use crate::Task;
+
+use super::{is_all_day, parse_remote_date, parse_remote_time, send, send_json, to_utc_rfc3339};
+
+const API: &str = "https://api.todoist.com/rest/v2";
+const SERVICE: &str = "Todoist";
+// `source` of the external ids linking local tasks to Todoist task ids
+const SOURCE: &str = "todoist";
+
+#[derive(Debug, Clone, Deserialize)]
+struct RemoteTask {
+    id: String,
+    content: String,
+    // 1 (normal) to 4 (urgent)
+    priority: u8,
+    due: Option<RemoteDue>,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RemoteDue {
+    date: String,
+    datetime: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct TaskPayload<'a> {
+    content: &'a str,
+    priority: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due_datetime: Option<String>,
+    // Clears the due date on update
+    #[serde(skip_serializing_if = "Option::is_none")]
+    due_string: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    labels: Option<&'a [String]>,
+}
+
+// What one sync run will do, worked out before anything is changed so --dry-run can show it
+#[derive(Debug)]
+enum Action {
+    // New in Todoist: create the task here
+    Import(RemoteTask),
+    // Changed in Todoist only: take the remote fields
+    Pull { task_id: i32, remote: RemoteTask },
+    // Gone from Todoist's active tasks (completed or deleted there): complete it here
+    CompleteLocal { task_id: i32, remote_id: String },
+    // Changed here: overwrite the remote fields. Local edits win if both sides changed.
+    Push { task: Task, remote_id: String },
+    // Completed here but still open in Todoist
+    Close { task_id: i32, remote_id: String },
+    // New here: create it in Todoist
+    Create { task: Task, tags: Vec<String> },
+}
+
+struct Client {
+    http: reqwest::Client,
+    token: String,
+}
+
+impl Client {
+    async fn active_tasks(&self) -> Result<Vec<RemoteTask>> {
+        send_json(SERVICE, self.http.get(format!("{}/tasks", API)).bearer_auth(&self.token)).await
+    }
+
+    async fn create(&self, payload: &TaskPayload<'_>) -> Result<RemoteTask> {
+        send_json(SERVICE, self.http.post(format!("{}/tasks", API)).bearer_auth(&self.token).json(payload)).await
+    }
+
+    async fn update(&self, id: &str, payload: &TaskPayload<'_>) -> Result<()> {
+        send(SERVICE, self.http.post(format!("{}/tasks/{}", API, id)).bearer_auth(&self.token).json(payload)).await?;
+        Ok(())
+    }
+
+    async fn close(&self, id: &str) -> Result<()> {
+        send(SERVICE, self.http.post(format!("{}/tasks/{}/close", API, id)).bearer_auth(&self.token)).await?;
+        Ok(())
+    }
+}
+
+// Two-way sync with the user's Todoist account. Linked tasks are matched through the
+// external_ids table; a task counts as changed here when it was updated after its last sync.
+// The REST API only lists open tasks, so a linked task missing from that list has been
+// completed or deleted in Todoist and is completed here.
+pub async fn run(repo: &TaskRepository, config: &Config, args: SyncArgs) -> Result<()> {
+    let client = Client { http: super::http_client(SERVICE)?, token: secrets::token(config, Service::Todoist)? };
+
+    let remote = client.active_tasks().await?;
+    let actions = plan(repo, remote).await?;
+
+    if actions.is_empty() {
+        println!("Everything is in sync with Todoist.");
+        return Ok(());
+    }
+    for action in &actions {
+        println!("{}", describe(action));
+    }
+    if args.dry_run {
+        println!("Dry run: {} changes not applied.", actions.len());
+        return Ok(());
+    }
+
+    let total = actions.len();
+    for action in actions {
+        apply(repo, &client, action).await?;
+    }
+    println!("Synced {} changes with Todoist.", total);
+    Ok(())
+}
+
+async fn plan(repo: &TaskRepository, remote: Vec<RemoteTask>) -> Result<Vec<Action>> {
+    let mut remote: HashMap<String, RemoteTask> = remote.into_iter().map(|task| (task.id.clone(), task)).collect();
+    let mut actions = Vec::new();
+
+    for linked in repo.linked_tasks(SOURCE).await? {
+        let task_id = linked.task.id;
+        match (remote.remove(&linked.external_id), linked.task.completed) {
+            (None, false) => actions.push(Action::CompleteLocal { task_id, remote_id: linked.external_id }),
+            (None, true) => {}
+            (Some(_), true) => actions.push(Action::Close { task_id, remote_id: linked.external_id }),
+            (Some(remote_task), false) => {
+                if !differs(&linked.task, &remote_task) {
+                    continue;
+                }
+                if linked.changed {
+                    actions.push(Action::Push { task: linked.task, remote_id: linked.external_id });
+                } else {
+                    actions.push(Action::Pull { task_id, remote: remote_task });
+                }
+            }
+        }
+    }
+
+    let mut new_remote: Vec<RemoteTask> = remote.into_values().collect();
+    new_remote.sort_by(|a, b| a.id.cmp(&b.id));
+    actions.extend(new_remote.into_iter().map(Action::Import));
+
+    for task in repo.unlinked_pending(SOURCE).await? {
+        let tags = repo.tags(task.id).await?;
+        actions.push(Action::Create { task, tags });
+    }
+
+    Ok(actions)
+}
+
+async fn apply(repo: &TaskRepository, client: &Client, action: Action) -> Result<()> {
+    match action {
+        Action::Import(remote) => {
+            let mut task = import::new_task(remote.content.clone());
+            task.due_at = remote_due(&remote);
+            task.priority = priority_from_remote(remote.priority);
+            let bundle = TaskBundle {
+                tags: remote.labels,
+                external: Some(external(remote.id)),
+                ..import::bundle(task)
+            };
+            repo.import(&[bundle]).await?;
+        }
+        Action::Pull { task_id, remote } => {
+            // Only open tasks are listed, so the remote side is never completed here
+            let (due_at, priority) = (remote_due(&remote), priority_from_remote(remote.priority));
+            repo.update_synced_fields(task_id, &remote.content, false, due_at, priority).await?;
+            repo.mark_synced(&external(remote.id)).await?;
+        }
+        Action::CompleteLocal { task_id, remote_id } => {
+            repo.complete(task_id).await?;
+            repo.mark_synced(&external(remote_id)).await?;
+        }
+        Action::Push { task, remote_id } => {
+            let mut payload = payload(&task, None);
+            if task.due_at.is_none() {
+                payload.due_string = Some("no date");
+            }
+            client.update(&remote_id, &payload).await?;
+            repo.mark_synced(&external(remote_id)).await?;
+        }
+        Action::Close { task_id: _, remote_id } => {
+            client.close(&remote_id).await?;
+            repo.mark_synced(&external(remote_id)).await?;
+        }
+        Action::Create { task, tags } => {
+            let created = client.create(&payload(&task, Some(&tags))).await?;
+            repo.link_external(task.id, &external(created.id)).await?;
+        }
+    }
+    Ok(())
+}
+
+fn describe(action: &Action) -> String {
+    match action {
+        Action::Import(remote) => format!("New from Todoist:     '{}'", remote.content),
+        Action::Pull { task_id, remote } => format!("Update task {}:       '{}' (changed in Todoist)", task_id, remote.content),
+        Action::CompleteLocal { task_id, .. } => format!("Complete task {}:     done or deleted in Todoist", task_id),
+        Action::Push { task, .. } => format!("Update in Todoist:    '{}' (task {})", task.description, task.id),
+        Action::Close { task_id, .. } => format!("Close in Todoist:     task {}", task_id),
+        Action::Create { task, .. } => format!("Add to Todoist:       '{}' (task {})", task.description, task.id),
+    }
+}
+
+fn external(id: String) -> ExternalId {
+    ExternalId { source: SOURCE, id }
+}
+
+fn differs(task: &Task, remote: &RemoteTask) -> bool {
+    task.description != remote.content
+        || task.priority != priority_from_remote(remote.priority)
+        || task.due_at != remote_due(remote)
+}
+
+fn remote_due(remote: &RemoteTask) -> Option<chrono::NaiveDateTime> {
+    let due = remote.due.as_ref()?;
+    match &due.datetime {
+        Some(datetime) => parse_remote_time(datetime),
+        None => parse_remote_date(&due.date),
+    }
+}
+
+fn payload<'a>(task: &'a Task, labels: Option<&'a [String]>) -> TaskPayload<'a> {
+    let (due_date, due_datetime) = match &task.due_at {
+        Some(due_at) if is_all_day(due_at) => (Some(due_at.format("%Y-%m-%d").to_string()), None),
+        Some(due_at) => (None, to_utc_rfc3339(due_at)),
+        None => (None, None),
+    };
+
+    TaskPayload {
+        content: &task.description,
+        priority: priority_to_remote(task.priority),
+        due_date,
+        due_datetime,
+        due_string: None,
+        labels: labels.filter(|labels| !labels.is_empty()),
+    }
+}
+
+// Todoist's 1 is its default "no priority"
+fn priority_from_remote(priority: u8) -> Option<Priority> {
+    match priority {
+        4 => Some(Priority::High),
+        3 => Some(Priority::Medium),
+        2 => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn priority_to_remote(priority: Option<Priority>) -> u8 {
+    match priority {
+        Some(Priority::High) => 4,
+        Some(Priority::Medium) => 3,
+        Some(Priority::Low) => 2,
+        None => 1,
+    }
+}
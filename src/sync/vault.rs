@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+
+use crate::cli::SyncArgs;
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::import;
+use crate::priority::Priority;
+use crate::repository::{ExternalId, TaskBundle, TaskRepository};
+use crate::Task;
+
+// `source` of the external ids marking tasks that are written to the vault; the id is the
+// task's own id
+const SOURCE: &str = "vault";
+// Frontmatter key that marks a file as written by this sync. Other notes are never touched.
+const MARKER: &str = "task-sync";
+// File for tasks without a project
+const INBOX: &str = "Inbox";
+const DUE: &str = "📅";
+
+// Markdown files in a directory, one per project, in the checkbox syntax of Obsidian's Tasks
+// plugin:
+//
+//   ---
+//   task-sync: true
+//   project: Work
+//   ---
+//   - [ ] Draft quarterly report #office ⏫ 📅 2026-10-20 ^task-12
+//   - [x] Renew domain name 📅 2026-10-03 14:00 ^task-7
+//
+// The ^task-<id> block id ties a line to its task. Ticking a box, or editing the text, due
+// date or priority of a line is read back on the next sync; lines added without an id become
+// new tasks in the file's project, with the #tags at their end as tags. If a task also changed
+// here since the last sync, this side wins, like in the other syncs. Tags and projects of
+// existing tasks are only written: moving a line to another file or deleting it doesn't change
+// the task, and the next sync puts it back.
+pub async fn run(repo: &TaskRepository, config: &Config, args: SyncArgs) -> Result<()> {
+    let dir = vault_dir(config)?;
+    let files = read_files(&dir)?;
+
+    let linked: HashMap<i32, (Task, bool)> = repo
+        .linked_tasks(SOURCE)
+        .await?
+        .into_iter()
+        .map(|linked| (linked.task.id, (linked.task, linked.changed)))
+        .collect();
+    let mut actions = Vec::new();
+    for file in &files {
+        for entry in &file.entries {
+            match entry.id {
+                None => actions.push(Action::Import { entry: entry.clone(), project: file.project.clone() }),
+                Some(id) => {
+                    // Unknown ids are tasks deleted here; their lines are dropped when rewriting
+                    let Some((task, false)) = linked.get(&id) else {
+                        continue;
+                    };
+                    let tags = tag_words(&repo.tags(id).await?);
+                    if entry.text != line_text(task, &tags)
+                        || entry.completed != task.completed
+                        || entry.due_at != task.due_at
+                        || entry.priority != task.priority
+                    {
+                        let (description, _) = split_tags(&entry.text, |tag| tags.iter().any(|known| known == tag));
+                        actions.push(Action::Pull { task_id: id, description, entry: entry.clone() });
+                    }
+                }
+            }
+        }
+    }
+
+    for action in &actions {
+        println!("{}", describe(action));
+    }
+    if args.dry_run {
+        println!("Dry run: {} changes from the vault not applied, no files written.", actions.len());
+        return Ok(());
+    }
+    let pulled = actions.len();
+    for action in actions {
+        apply(repo, action).await?;
+    }
+
+    let written = write_files(repo, &dir, &files).await?;
+    println!("Synced {} changes from the vault and wrote {} files to {}.", pulled, written, dir.display());
+    Ok(())
+}
+
+fn vault_dir(config: &Config) -> Result<PathBuf> {
+    let Some(path) = &config.vault.path else {
+        return Err(TaskError::Config(format!(
+            "The vault is not configured. Set `path` in the [vault] section of {}.",
+            Config::path().display()
+        )));
+    };
+    fs::create_dir_all(path)
+        .map_err(|e| TaskError::Config(format!("Could not create the vault directory {}: {}", path.display(), e)))?;
+    Ok(path.clone())
+}
+
+#[derive(Debug)]
+enum Action {
+    // A line without an id
+    Import { entry: Entry, project: Option<String> },
+    // A line edited in the vault; `description` is its text without the task's tags
+    Pull { task_id: i32, description: String, entry: Entry },
+}
+
+fn describe(action: &Action) -> String {
+    match action {
+        Action::Import { entry, project } => match project {
+            Some(project) => format!("New from the vault in {}: {}", project, entry.text),
+            None => format!("New from the vault: {}", entry.text),
+        },
+        Action::Pull { task_id, description, .. } => {
+            format!("Changed in the vault: task {} ({})", task_id, description)
+        }
+    }
+}
+
+async fn apply(repo: &TaskRepository, action: Action) -> Result<()> {
+    match action {
+        Action::Import { entry, project } => {
+            let (description, tags) = split_tags(&entry.text, |_| true);
+            let mut task = import::new_task(description);
+            task.completed = entry.completed;
+            task.due_at = entry.due_at;
+            task.priority = entry.priority;
+            let bundle = TaskBundle { project, tags, ..import::bundle(task) };
+            if let Err(e) = import::validate(&bundle) {
+                println!("Warning: skipped '{}' from the vault: {}", entry.text, e);
+                return Ok(());
+            }
+            // Linked when the files are written
+            repo.import(&[bundle]).await?;
+        }
+        Action::Pull { task_id, description, entry } => {
+            if let Err(e) = import::validate(&import::bundle(import::new_task(description.clone()))) {
+                println!("Warning: kept task {} as it was, the vault's version is invalid: {}", task_id, e);
+                return Ok(());
+            }
+            repo.update_synced_fields(task_id, &description, entry.completed, entry.due_at, entry.priority).await?;
+            repo.mark_synced(&external(task_id)).await?;
+        }
+    }
+    Ok(())
+}
+
+fn external(task_id: i32) -> ExternalId {
+    ExternalId { source: SOURCE, id: task_id.to_string() }
+}
+
+// A file written by this sync, as found in the vault
+struct VaultFile {
+    path: PathBuf,
+    project: Option<String>,
+    entries: Vec<Entry>,
+}
+
+// A checkbox line. `text` is the description followed by any #tags.
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    id: Option<i32>,
+    text: String,
+    completed: bool,
+    due_at: Option<NaiveDateTime>,
+    priority: Option<Priority>,
+}
+
+fn read_files(dir: &Path) -> Result<Vec<VaultFile>> {
+    let mut files = Vec::new();
+    for dir_entry in fs::read_dir(dir)? {
+        let path = dir_entry?.path();
+        if path.extension().is_none_or(|extension| extension != "md") {
+            continue;
+        }
+        let text = fs::read_to_string(&path)?;
+        let Some((frontmatter, body)) = split_frontmatter(&text) else {
+            continue;
+        };
+        let mut synced = false;
+        let mut project = None;
+        for line in frontmatter.lines() {
+            match line.split_once(':').map(|(key, value)| (key.trim(), unquote(value.trim()))) {
+                Some((MARKER, value)) => synced = value == "true",
+                Some(("project", value)) if !value.is_empty() => project = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        if synced {
+            let entries = body.lines().filter_map(parse_line).collect();
+            files.push(VaultFile { path, project, entries });
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn split_frontmatter(text: &str) -> Option<(&str, &str)> {
+    let rest = text.strip_prefix("---\n").or_else(|| text.strip_prefix("---\r\n"))?;
+    let end = rest.find("\n---")?;
+    let body = rest[end + 4..].trim_start_matches(['\r', '\n']);
+    Some((&rest[..end], body))
+}
+
+fn unquote(value: &str) -> &str {
+    value.strip_prefix('"').and_then(|value| value.strip_suffix('"')).unwrap_or(value)
+}
+
+// "- [ ] text ...", also with * or + as the bullet. Metadata is taken from the end of the line
+// in the order it is written: block id, due date, priority.
+fn parse_line(line: &str) -> Option<Entry> {
+    let line = line.trim();
+    let rest = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")).or_else(|| line.strip_prefix("+ "))?;
+    let (completed, rest) = if let Some(rest) = rest.strip_prefix("[ ]") {
+        (false, rest)
+    } else if let Some(rest) = rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]")) {
+        (true, rest)
+    } else {
+        return None;
+    };
+
+    let mut words: Vec<&str> = rest.split_whitespace().collect();
+    let id = match words.last().and_then(|word| word.strip_prefix("^task-")) {
+        Some(id) => {
+            let id = id.parse().ok();
+            words.pop();
+            id
+        }
+        None => None,
+    };
+
+    let mut due_at = None;
+    if let Some(at) = words.iter().rposition(|word| *word == DUE) {
+        let date = words.get(at + 1).and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok());
+        if let Some(date) = date {
+            let time = words.get(at + 2).and_then(|time| NaiveTime::parse_from_str(time, "%H:%M").ok());
+            due_at = Some(date.and_time(time.unwrap_or(NaiveTime::MIN)));
+            words.truncate(at);
+        }
+    }
+
+    let mut priority = None;
+    if let Some(emoji) = words.last().and_then(|word| priority_from_emoji(word)) {
+        priority = Some(emoji);
+        words.pop();
+    }
+
+    let text = words.join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    Some(Entry { id, text, completed, due_at, priority })
+}
+
+// Splits the #tags that `is_tag` accepts off the end of a line's text
+fn split_tags(text: &str, is_tag: impl Fn(&str) -> bool) -> (String, Vec<String>) {
+    let mut words: Vec<&str> = text.split_whitespace().collect();
+    let mut tags = Vec::new();
+    while words.len() > 1
+        && let Some(tag) = words.last().and_then(|word| word.strip_prefix('#'))
+        && !tag.is_empty()
+        && is_tag(tag)
+    {
+        tags.insert(0, tag.to_string());
+        words.pop();
+    }
+    (words.join(" "), tags)
+}
+
+// Tags as they are written after a line's text; they end at the first space
+fn tag_words(tags: &[String]) -> Vec<String> {
+    tags.iter().map(|tag| tag.split_whitespace().collect::<Vec<_>>().join("_")).collect()
+}
+
+fn line_text(task: &Task, tags: &[String]) -> String {
+    let mut text = one_line(&task.description);
+    for tag in tags {
+        text.push_str(&format!(" #{}", tag));
+    }
+    text
+}
+
+fn priority_emoji(priority: Priority) -> &'static str {
+    match priority {
+        Priority::High => "⏫",
+        Priority::Medium => "🔼",
+        Priority::Low => "🔽",
+    }
+}
+
+fn priority_from_emoji(word: &str) -> Option<Priority> {
+    match word {
+        "⏫" | "🔺" => Some(Priority::High),
+        "🔼" => Some(Priority::Medium),
+        "🔽" | "⏬" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn format_line(task: &Task, tags: &[String]) -> String {
+    let mut line = format!("- [{}] {}", if task.completed { "x" } else { " " }, line_text(task, tags));
+    if let Some(priority) = task.priority {
+        line.push_str(&format!(" {}", priority_emoji(priority)));
+    }
+    if let Some(due_at) = task.due_at {
+        if due_at.time() == NaiveTime::MIN {
+            line.push_str(&format!(" {} {}", DUE, due_at.format("%Y-%m-%d")));
+        } else {
+            line.push_str(&format!(" {} {}", DUE, due_at.format("%Y-%m-%d %H:%M")));
+        }
+    }
+    line.push_str(&format!(" ^task-{}", task.id));
+    line
+}
+
+// Rewrites every synced file from the database: the pending tasks, plus the completed ones that
+// were in the vault before. Files that were read but whose project has no tasks any more are
+// emptied rather than deleted, in case they hold links from other notes.
+async fn write_files(repo: &TaskRepository, dir: &Path, read: &[VaultFile]) -> Result<usize> {
+    let mut tasks: Vec<Task> = repo.linked_tasks(SOURCE).await?.into_iter().map(|linked| linked.task).collect();
+    for task in repo.unlinked_pending(SOURCE).await? {
+        repo.link_external(task.id, &external(task.id)).await?;
+        tasks.push(task);
+    }
+    tasks.sort_by_key(|task| (task.completed, task.due_at.is_none(), task.due_at, task.id));
+
+    let mut project_names: HashMap<i32, Option<String>> = HashMap::new();
+    let mut by_project: HashMap<Option<String>, Vec<String>> = HashMap::new();
+    for file in read {
+        by_project.entry(file.project.clone()).or_default();
+    }
+    for task in &tasks {
+        let project = match task.project_id {
+            Some(project_id) => match project_names.get(&project_id) {
+                Some(name) => name.clone(),
+                None => {
+                    let name = repo.project_name(project_id).await?;
+                    project_names.insert(project_id, name.clone());
+                    name
+                }
+            },
+            None => None,
+        };
+        let tags = tag_words(&repo.tags(task.id).await?);
+        by_project.entry(project).or_default().push(format_line(task, &tags));
+    }
+
+    // Files keep their name once written, even if the user renamed them
+    let mut paths: HashMap<Option<String>, PathBuf> =
+        read.iter().map(|file| (file.project.clone(), file.path.clone())).collect();
+    for (project, lines) in &by_project {
+        let path = paths
+            .remove(project)
+            .unwrap_or_else(|| dir.join(format!("{}.md", file_name(project.as_deref().unwrap_or(INBOX)))));
+        let mut text = format!("---\n{}: true\n", MARKER);
+        if let Some(project) = project {
+            text.push_str(&format!("project: \"{}\"\n", project.replace('"', "'")));
+        }
+        text.push_str("---\n");
+        for line in lines {
+            text.push_str(line);
+            text.push('\n');
+        }
+        // Written next to the file and renamed, so the notes app never sees half a file
+        let partial = path.with_extension("md.partial");
+        fs::write(&partial, text)?;
+        fs::rename(&partial, &path)?;
+    }
+
+    for task in &tasks {
+        repo.mark_synced(&external(task.id)).await?;
+    }
+    Ok(by_project.len())
+}
+
+// Characters that aren't allowed in file names on some system
+fn file_name(project: &str) -> String {
+    project.chars().map(|c| if r#"/\:*?"<>|"#.contains(c) { '_' } else { c }).collect::<String>().trim().to_string()
+}
@@ -0,0 +1,246 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use teloxide::prelude::*;
+use teloxide::utils::command::BotCommands;
+
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::import;
+use crate::jira;
+use crate::metrics;
+use crate::notify;
+use crate::repository::{TaskFilter, TaskRepository};
+use crate::secrets::{self, Service};
+use crate::users;
+use crate::webhooks::{WebhookEvent, Webhooks};
+
+// Tasks shown by /list
+const LIST_LIMIT: u32 = 20;
+// How often the server and the bot look for new assignments and mentions to tell users about
+const NOTICE_INTERVAL: Duration = Duration::from_secs(30);
+// notifications_sent entries of those messages
+const NOTICE_CHANNEL: &str = "telegram";
+const ASSIGNMENT_KIND: &str = "assigned";
+const ASSIGNED: &str = "Task {id} was assigned to you: {description}";
+
+/// Commands:
+#[derive(BotCommands, Clone)]
+#[command(rename_rule = "lowercase")]
+enum BotCommand {
+    /// Show this help
+    Help,
+    /// Add a task, in todo.txt style: /add (A) Call the bank +Home @phone due:2026-10-20
+    Add(String),
+    /// Show pending tasks, soonest due first
+    List,
+    /// Complete tasks by id: /done 12 15
+    Done(String),
+}
+
+struct BotState {
+    // Chat id -> the name changes from that chat are recorded under, with its repository
+    chats: HashMap<i64, TaskRepository>,
+    config: Config,
+    webhooks: Webhooks,
+}
+
+// `task telegram`: answers bot commands until Ctrl-C or SIGTERM. Only the chats listed in
+// [telegram.chats] may use the bot; each is mapped to the name recorded as the author of the
+// changes made from it. Once there are accounts, that name must be a username, and the chat
+// works on that user's tasks.
+pub async fn run(repo: &TaskRepository, config: &Config) -> Result<()> {
+    let token = secrets::token(config, Service::Telegram)?;
+    let accounts = repo.user_count().await? > 0;
+    let mut chats = HashMap::new();
+    for (chat, name) in &config.telegram.chats {
+        let chat = parse_chat(chat)?;
+        let chat_repo = if accounts {
+            let user = repo.user_by_name(name).await?.ok_or_else(|| {
+                TaskError::Config(format!("[telegram.chats] maps chat {} to '{}', who has no account.", chat, name))
+            })?;
+            if repo.user_disabled(user.id).await? {
+                println!("Warning: the account of {} is disabled; refusing chat {}.", name, chat);
+                continue;
+            }
+            users::as_user(repo.clone(), &user)
+        } else {
+            repo.clone().with_actor(name.clone())
+        };
+        chats.insert(chat, chat_repo);
+    }
+    if chats.is_empty() {
+        println!("Warning: no chats in [telegram.chats]; the bot will only tell chats their id.");
+    }
+
+    spawn_notices(repo, config)?;
+
+    let state = Arc::new(BotState { chats, config: config.clone(), webhooks: Webhooks::new(config)? });
+    let bot = Bot::new(token);
+    let handler = Update::filter_message().filter_command::<BotCommand>().endpoint(answer);
+    let mut dispatcher = Dispatcher::builder(bot, handler).dependencies(dptree::deps![state]).build();
+
+    println!("Telegram bot running. Press Ctrl-C to stop.");
+    tokio::select! {
+        _ = dispatcher.dispatch() => {},
+        _ = crate::shutdown_signal() => {},
+    }
+    println!("Telegram bot stopped.");
+    Ok(())
+}
+
+// With [telegram] notify_assignments, messages users in their chat of [telegram.chats] once for
+// every task assigned to them, and with notify_mentions for every note that mentions them, in
+// the background until the process ends. `task serve` and `task telegram` both run this; each
+// message is claimed first, so running both sends it once.
+pub fn spawn_notices(repo: &TaskRepository, config: &Config) -> Result<()> {
+    let telegram = &config.telegram;
+    let (assignments, mentions) = (telegram.notify_assignments, telegram.notify_mentions);
+    if !assignments && !mentions {
+        return Ok(());
+    }
+    let bot = Bot::new(secrets::token(config, Service::Telegram)?);
+    let mut chats = HashMap::new();
+    for (chat, name) in &config.telegram.chats {
+        chats.insert(name.clone(), parse_chat(chat)?);
+    }
+    // Those of every user, not just of whoever started the process
+    let repo = repo.clone().with_user(None);
+    tokio::spawn(async move {
+        loop {
+            if assignments && let Err(e) = post_assignments(&repo, &bot, &chats).await {
+                println!("Warning: could not send assignment messages to Telegram: {}", e);
+            }
+            if mentions && let Err(e) = post_mentions(&repo, &bot, &chats).await {
+                println!("Warning: could not send mentions to Telegram: {}", e);
+            }
+            tokio::time::sleep(NOTICE_INTERVAL).await;
+        }
+    });
+    Ok(())
+}
+
+async fn post_assignments(repo: &TaskRepository, bot: &Bot, chats: &HashMap<String, i64>) -> Result<()> {
+    for assignment in repo.assignments().await? {
+        let Some(&chat) = chats.get(&assignment.assignee) else {
+            continue;
+        };
+        let task = &assignment.task;
+        // Assigning the task to someone else brings a new message
+        let subject = format!("{}@{}", task.id, task.assignee_id.unwrap_or_default());
+        if !repo.claim_notification(NOTICE_CHANNEL, ASSIGNMENT_KIND, &subject).await? {
+            continue;
+        }
+        let text = notify::render(ASSIGNED, &notify::task_values(repo, task).await?);
+        let result = bot.send_message(ChatId(chat), text).await;
+        metrics::record_notification(NOTICE_CHANNEL, result.is_ok());
+        if let Err(e) = result {
+            // Given back, so the next round tries again
+            repo.release_notification(NOTICE_CHANNEL, ASSIGNMENT_KIND, &subject).await?;
+            println!("Warning: could not tell {} about task {} on Telegram: {}", assignment.assignee, task.id, e);
+        }
+    }
+    Ok(())
+}
+
+async fn post_mentions(repo: &TaskRepository, bot: &Bot, chats: &HashMap<String, i64>) -> Result<()> {
+    for mention in repo.mentions(notify::MENTION_DAYS).await? {
+        let Some(&chat) = chats.get(&mention.username) else {
+            continue;
+        };
+        let subject = notify::mention_subject(&mention);
+        if !repo.claim_notification(NOTICE_CHANNEL, notify::MENTION_KIND, &subject).await? {
+            continue;
+        }
+        let text = notify::render(notify::MENTIONED, &notify::mention_values(repo, &mention).await?);
+        let result = bot.send_message(ChatId(chat), text).await;
+        metrics::record_notification(NOTICE_CHANNEL, result.is_ok());
+        if let Err(e) = result {
+            repo.release_notification(NOTICE_CHANNEL, notify::MENTION_KIND, &subject).await?;
+            let (username, id) = (&mention.username, mention.task.id);
+            println!("Warning: could not tell {} about a note on task {} on Telegram: {}", username, id, e);
+        }
+    }
+    Ok(())
+}
+
+fn parse_chat(chat: &str) -> Result<i64> {
+    chat.parse().map_err(|_| {
+        TaskError::Config(format!("Invalid chat id '{}' in [telegram.chats]; chat ids are numbers.", chat))
+    })
+}
+
+async fn answer(bot: Bot, message: Message, command: BotCommand, state: Arc<BotState>) -> ResponseResult<()> {
+    let chat = message.chat.id;
+    let reply = match state.chats.get(&chat.0) {
+        None => format!("This chat may not use the bot. To allow it, add {} to [telegram.chats].", chat.0),
+        Some(repo) => match handle(repo, &state, command).await {
+            Ok(reply) => reply,
+            Err(e) => {
+                println!("Warning: Telegram command from chat {} failed: {}", chat.0, e);
+                format!("Error: {}", e)
+            }
+        },
+    };
+    bot.send_message(chat, reply).await?;
+    Ok(())
+}
+
+async fn handle(repo: &TaskRepository, state: &BotState, command: BotCommand) -> Result<String> {
+    match command {
+        BotCommand::Help => Ok(BotCommand::descriptions().to_string()),
+        BotCommand::Add(text) => {
+            if text.trim().is_empty() {
+                return Ok("Usage: /add <description> [+project] [@tag] [due:YYYY-MM-DD]".to_string());
+            }
+            let bundle = import::todotxt::parse_line(&text).map_err(TaskError::InvalidInput)?;
+            import::validate(&bundle).map_err(TaskError::InvalidInput)?;
+            let id = repo.import(std::slice::from_ref(&bundle)).await?[0];
+            state.webhooks.task_event(repo, WebhookEvent::Created, id).await?;
+            Ok(format!("Added task {}: {}", id, bundle.task.description))
+        }
+        BotCommand::List => {
+            let filter = TaskFilter { completed: Some(false), ..TaskFilter::default() };
+            let mut tasks = repo.filter_page(&filter, None, LIST_LIMIT).await?.tasks;
+            if tasks.is_empty() {
+                return Ok("No pending tasks.".to_string());
+            }
+            tasks.sort_by_key(|task| (task.due_at.is_none(), task.due_at, task.id));
+            let lines: Vec<String> = tasks
+                .iter()
+                .map(|task| match task.due_at {
+                    Some(due_at) => format!("{} · {} (due {})", task.id, task.description, due_at.format("%Y-%m-%d %H:%M")),
+                    None => format!("{} · {}", task.id, task.description),
+                })
+                .collect();
+            Ok(lines.join("\n"))
+        }
+        BotCommand::Done(ids) => {
+            let ids: Vec<i32> = match ids.split_whitespace().map(str::parse).collect() {
+                Ok(ids) => ids,
+                Err(_) => return Ok("Usage: /done <id> [<id> ...]".to_string()),
+            };
+            if ids.is_empty() {
+                return Ok("Usage: /done <id> [<id> ...]".to_string());
+            }
+            let mut replies = Vec::new();
+            for id in ids {
+                match repo.get(id).await? {
+                    None => replies.push(format!("No task with id {}.", id)),
+                    // Checked again by `complete`, in case it was completed in between
+                    Some(task) if task.completed || !repo.complete(id).await?.is_some_and(|done| done.completed) => {
+                        replies.push(format!("Task {} was already completed.", id))
+                    }
+                    Some(task) => {
+                        jira::task_completed(repo, &state.config, id).await?;
+                        notify::task_completed(repo, &state.config, id).await?;
+                        state.webhooks.task_event(repo, WebhookEvent::Completed, id).await?;
+                        replies.push(format!("Completed task {}: {}", id, task.description));
+                    }
+                }
+            }
+            Ok(replies.join("\n"))
+        }
+    }
+}
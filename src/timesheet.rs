@@ -0,0 +1,190 @@
+// `task time`: a timer per user to track the time spent on tasks, and reports of the tracked
+// time per task, project, day or week, with every entry as CSV to bill from.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use crate::cli::{TimeCommand, TimeGroup, TimeReportArgs};
+use crate::error::{Result, TaskError};
+use crate::export::{self, report};
+use crate::repository::{TaskRepository, TimeEntry};
+
+pub async fn run(repo: &TaskRepository, command: TimeCommand) -> Result<()> {
+    match command {
+        TimeCommand::Start { id } => {
+            let running = repo.running_timer().await?;
+            if !repo.start_timer(id).await? {
+                return Err(TaskError::InvalidInput(format!("There is no task with ID {}.", id)));
+            }
+            if let Some(running) = running {
+                println!("Stopped the timer on task {} after {}.", running.task_id, format_duration(running.seconds));
+            }
+            println!("Started a timer on task {}.", id);
+        }
+        TimeCommand::Stop => match repo.stop_timer().await? {
+            Some(entry) => println!(
+                "Stopped the timer on task {} ({}) after {}.",
+                entry.task_id,
+                entry.description,
+                format_duration(entry.seconds)
+            ),
+            None => println!("No timer is running."),
+        },
+        TimeCommand::Status => match repo.running_timer().await? {
+            Some(entry) => println!(
+                "The timer on task {} ({}) has been running for {}.",
+                entry.task_id,
+                entry.description,
+                format_duration(entry.seconds)
+            ),
+            None => println!("No timer is running."),
+        },
+        TimeCommand::Report(args) => print_report(repo, args).await?,
+    }
+    Ok(())
+}
+
+async fn print_report(repo: &TaskRepository, args: TimeReportArgs) -> Result<()> {
+    let (from, to) = report::range(args.from.as_deref(), args.to.as_deref())?;
+    let entries = repo.time_entries(from, to).await?;
+    let projects = export::load_project_names(repo.pool()).await?;
+
+    if let Some(path) = &args.csv {
+        let mut out = export::open_output(Some(path))?;
+        write_csv(&entries, &projects, &mut out)?;
+        out.flush()?;
+        println!("Wrote {} time entries to {}", entries.len(), path.display());
+        return Ok(());
+    }
+
+    let by = match args.by {
+        TimeGroup::Task => "Task",
+        TimeGroup::Project => "Project",
+        TimeGroup::Day => "Day",
+        TimeGroup::Week => "Week",
+    };
+    println!("\n--- Time per {}, {} to {} ---", by, from.format("%Y-%m-%d"), to.format("%Y-%m-%d"));
+    if entries.is_empty() {
+        println!("No time tracked.");
+        return Ok(());
+    }
+    for (label, seconds) in totals(&entries, args.by, &projects) {
+        println!("{:>12}  {}", format_duration(seconds), label);
+    }
+    println!("{:>12}  Total", format_duration(entries.iter().map(|entry| entry.seconds).sum()));
+    Ok(())
+}
+
+// Seconds per group; days and weeks in order, tasks and projects with the most time first
+fn totals(entries: &[TimeEntry], by: TimeGroup, projects: &HashMap<i32, String>) -> Vec<(String, i64)> {
+    let mut totals: BTreeMap<String, i64> = BTreeMap::new();
+    for entry in entries {
+        let label = match by {
+            TimeGroup::Task => format!("{}: {}", entry.task_id, entry.description),
+            TimeGroup::Project => project(entry, projects).unwrap_or("(no project)").to_string(),
+            TimeGroup::Day => entry.started_at.format("%Y-%m-%d").to_string(),
+            // ISO weeks, which start on Monday
+            TimeGroup::Week => entry.started_at.format("%G-W%V").to_string(),
+        };
+        *totals.entry(label).or_default() += entry.seconds;
+    }
+    let mut totals: Vec<(String, i64)> = totals.into_iter().collect();
+    if matches!(by, TimeGroup::Task | TimeGroup::Project) {
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    }
+    totals
+}
+
+fn project<'a>(entry: &TimeEntry, projects: &'a HashMap<i32, String>) -> Option<&'a str> {
+    entry.project_id.and_then(|id| projects.get(&id)).map(String::as_str)
+}
+
+// One row per entry, with the hours as a decimal number to multiply by a rate
+fn write_csv(entries: &[TimeEntry], projects: &HashMap<i32, String>, out: impl Write) -> Result<()> {
+    let mut writer = ::csv::Writer::from_writer(out);
+    let invalid = |e: ::csv::Error| TaskError::Io(std::io::Error::other(e));
+    writer.write_record(["date", "started", "task_id", "task", "project", "user", "hours"]).map_err(invalid)?;
+    for entry in entries {
+        writer
+            .write_record([
+                entry.started_at.format("%Y-%m-%d").to_string(),
+                entry.started_at.format("%H:%M").to_string(),
+                entry.task_id.to_string(),
+                entry.description.clone(),
+                project(entry, projects).unwrap_or_default().to_string(),
+                entry.username.clone().unwrap_or_default(),
+                format!("{:.2}", entry.seconds.max(0) as f64 / 3600.0),
+            ])
+            .map_err(invalid)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+// E.g. "3 h 05 min" or "45 min"
+pub fn format_duration(seconds: i64) -> String {
+    let minutes = seconds.max(0) / 60;
+    match minutes / 60 {
+        0 => format!("{} min", minutes),
+        hours => format!("{} h {:02} min", hours, minutes % 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{at, at_time};
+
+    fn entry(task_id: i32, project_id: Option<i32>, started_at: chrono::NaiveDateTime, minutes: i64) -> TimeEntry {
+        TimeEntry {
+            task_id,
+            description: format!("Task {}", task_id),
+            project_id,
+            username: Some("alice".to_string()),
+            started_at,
+            seconds: minutes * 60,
+        }
+    }
+
+    fn week() -> (Vec<TimeEntry>, HashMap<i32, String>) {
+        let entries = vec![
+            entry(1, Some(1), at(6), 30),
+            entry(2, None, at_time(6, 14, 0), 45),
+            entry(1, Some(1), at(9), 90),
+        ];
+        (entries, HashMap::from([(1, "Client, Inc.".to_string())]))
+    }
+
+    #[test]
+    fn time_adds_up_per_group() {
+        let (entries, projects) = week();
+        let totals = |by| totals(&entries, by, &projects);
+        let by_project = [("Client, Inc.".to_string(), 7200), ("(no project)".to_string(), 2700)];
+        assert_eq!(totals(TimeGroup::Project), by_project);
+        assert_eq!(totals(TimeGroup::Task)[0], ("1: Task 1".to_string(), 7200));
+        assert_eq!(totals(TimeGroup::Day), [("2026-03-06".to_string(), 4500), ("2026-03-09".to_string(), 5400)]);
+        // The 6th is a Friday, the 9th the Monday after
+        assert_eq!(totals(TimeGroup::Week), [("2026-W10".to_string(), 4500), ("2026-W11".to_string(), 5400)]);
+    }
+
+    #[test]
+    fn csv_has_a_row_per_entry_in_hours() {
+        let (entries, projects) = week();
+        let mut out = Vec::new();
+        write_csv(&entries, &projects, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "date,started,task_id,task,project,user,hours");
+        assert_eq!(lines[1], "2026-03-06,09:00,1,Task 1,\"Client, Inc.\",alice,0.50");
+        assert_eq!(lines[2], "2026-03-06,14:00,2,Task 2,,alice,0.75");
+        assert_eq!(lines.len(), 4);
+    }
+
+    #[test]
+    fn durations_read_at_a_glance() {
+        assert_eq!(format_duration(59), "0 min");
+        assert_eq!(format_duration(45 * 60), "45 min");
+        assert_eq!(format_duration(3 * 3600 + 5 * 60), "3 h 05 min");
+        assert_eq!(format_duration(-30), "0 min");
+    }
+}
@@ -0,0 +1,265 @@
+use std::io::{self, Write};
+
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use sha2::{Digest, Sha256};
+
+use crate::cli::UserCommand;
+use crate::config::Config;
+use crate::error::{Result, TaskError};
+use crate::ldap::Directory;
+use crate::privacy;
+use crate::repository::{TaskRepository, User};
+use crate::secrets;
+
+// How long a login lasts
+const SESSION_DAYS: i64 = 30;
+// Length of users.username
+const MAX_USERNAME: usize = 64;
+pub const MIN_PASSWORD: usize = 8;
+
+// Accounts decide whose tasks a command works on: once the first one exists, commands on
+// tasks need a login and see only that user's tasks. They are not a barrier against anyone
+// holding the database credentials, who can read every table directly.
+
+// The repository as the logged-in user. Until the first account is created nobody logs in and
+// everything stays visible, as before there were accounts.
+pub async fn authenticate(repo: TaskRepository) -> Result<TaskRepository> {
+    if repo.user_count().await? == 0 {
+        return Ok(repo);
+    }
+    let session = match session_hash()? {
+        Some(hash) => repo.session(&hash).await?,
+        None => None,
+    };
+    let session = session.ok_or_else(|| {
+        TaskError::Config("Not logged in, or the login has expired. Run `task login` first.".to_string())
+    })?;
+    Ok(as_user(repo, &session.user).with_workspace(session.workspace_id.zip(session.role)))
+}
+
+// For changes made on a user's behalf, e.g. from a chat the bot maps to the account. They are
+// made in the user's personal space.
+pub fn as_user(repo: TaskRepository, user: &User) -> TaskRepository {
+    repo.with_actor(user.username.clone()).with_user(Some(user.id))
+}
+
+// `task login`: checks the password and keeps a session token in the keyring. The database
+// only gets a hash of the token, so reading the sessions table doesn't let anyone log in.
+pub async fn login(repo: &TaskRepository, config: &Config, username: Option<String>) -> Result<()> {
+    let username = match username {
+        Some(username) => username,
+        None => prompt("Username: ")?,
+    };
+    let password = rpassword::prompt_password("Password: ")?;
+
+    let directory = Directory::new(config)?;
+    // The same answer for unknown users and wrong passwords
+    let Some(user) = verify(repo, directory.as_ref(), username.trim(), &password).await? else {
+        return Err(TaskError::InvalidInput("Wrong username or password.".to_string()));
+    };
+
+    let token = new_token();
+    repo.create_session(&hash_token(&token), user.id, SESSION_DAYS).await?;
+    secrets::store_session(&token)?;
+    println!("Logged in as {} for {} days.", user.username, SESSION_DAYS);
+    Ok(())
+}
+
+// The user with this name and password. With [ldap] the directory checks the password, and local
+// passwords no longer count.
+pub async fn verify(
+    repo: &TaskRepository,
+    directory: Option<&Directory>,
+    username: &str,
+    password: &str,
+) -> Result<Option<User>> {
+    if let Some(directory) = directory {
+        return directory.login(repo, username, password).await;
+    }
+    let Some(user) = repo.user_by_name(username).await? else {
+        return Ok(None);
+    };
+    let hash = repo.password_hash(user.id).await?;
+    Ok(hash.is_some_and(|hash| verify_password(password, &hash)).then_some(user))
+}
+
+// `task logout`: ends the session in the database too, so a copy of the token stops working
+pub async fn logout(repo: &TaskRepository) -> Result<()> {
+    let Some(token) = secrets::session()? else {
+        println!("Not logged in.");
+        return Ok(());
+    };
+    repo.delete_session(&hash_token(&token)).await?;
+    if !secrets::delete_session()? {
+        // The token came from TASK_SESSION, which we can't unset
+        println!("The session from TASK_SESSION has ended; unset the variable as well.");
+        return Ok(());
+    }
+    println!("Logged out.");
+    Ok(())
+}
+
+pub async fn run(repo: TaskRepository, config: &Config, command: UserCommand) -> Result<()> {
+    let manages_accounts =
+        !matches!(command, UserCommand::List | UserCommand::Export { .. } | UserCommand::Erase { .. });
+    if config.ldap.url.is_some() && manages_accounts {
+        return Err(TaskError::InvalidInput(
+            "With [ldap] set, the directory manages accounts and passwords; users appear when they first log in."
+                .to_string(),
+        ));
+    }
+    match command {
+        UserCommand::Add { username } => add(repo, username.trim()).await,
+        UserCommand::List => list(&authenticate(repo).await?).await,
+        UserCommand::Passwd => passwd(&authenticate(repo).await?).await,
+        UserCommand::Join { code, username } => join(&repo, code.trim(), username).await,
+        UserCommand::Export { output } => privacy::export(&authenticate(repo).await?, output.as_deref()).await,
+        UserCommand::Erase { yes } => privacy::erase(&authenticate(repo).await?, config, yes).await,
+    }
+}
+
+// Anyone may create the first account; further ones need a login
+async fn add(repo: TaskRepository, username: &str) -> Result<()> {
+    let repo = authenticate(repo).await?;
+    check_username(&repo, username).await?;
+
+    let password = new_password()?;
+    let (_, claimed) = repo.create_user(username, &hash_password(&password)?).await?;
+    println!("Created user {}.", username);
+    if claimed > 0 {
+        println!("The {0} existing tasks now belong to {1}; run `task login {1}` to see them.", claimed, username);
+    }
+    Ok(())
+}
+
+// `task user join`: needs no login, only the code. The new account is logged in right away,
+// working in the workspace it joined.
+async fn join(repo: &TaskRepository, code: &str, username: Option<String>) -> Result<()> {
+    let hash = hash_token(code);
+    let Some(invitation) = repo.invitation_by_code(&hash).await? else {
+        return Err(invalid_invitation());
+    };
+    println!("This invitation joins workspace {} as {}.", invitation.workspace, invitation.role);
+    let username = match username {
+        Some(username) => username.trim().to_string(),
+        None => prompt("Username: ")?,
+    };
+    check_username(repo, &username).await?;
+    let password = new_password()?;
+
+    // Checked again here, in case the code was used meanwhile
+    let Some(redeemed) = repo.redeem_invitation(&hash, &username, &hash_password(&password)?).await? else {
+        return Err(invalid_invitation());
+    };
+    let token = new_token();
+    let session = hash_token(&token);
+    repo.create_session(&session, redeemed.user_id, SESSION_DAYS).await?;
+    repo.set_session_workspace(&session, Some(redeemed.workspace_id)).await?;
+    secrets::store_session(&token)?;
+    println!(
+        "Created user {} in workspace {} as {}, and logged in for {} days.",
+        username, redeemed.workspace, redeemed.role, SESSION_DAYS
+    );
+    Ok(())
+}
+
+pub fn invalid_invitation() -> TaskError {
+    TaskError::InvalidInput("The invitation is unknown, used or expired; ask an admin for a new one.".to_string())
+}
+
+// Fails unless the name is free and fits users.username
+pub async fn check_username(repo: &TaskRepository, username: &str) -> Result<()> {
+    if username.is_empty() || username.chars().count() > MAX_USERNAME || username.contains(char::is_whitespace) {
+        return Err(TaskError::InvalidInput(format!(
+            "'{}' can't be a username: use up to {} characters without spaces.",
+            username, MAX_USERNAME
+        )));
+    }
+    if repo.user_by_name(username).await?.is_some() {
+        return Err(TaskError::InvalidInput(format!("There is already a user named {}.", username)));
+    }
+    Ok(())
+}
+
+async fn list(repo: &TaskRepository) -> Result<()> {
+    let users = repo.users().await?;
+    if users.is_empty() {
+        println!("No users yet. Create the first with `task user add <name>`.");
+        return Ok(());
+    }
+    for user in &users {
+        let current = if repo.user() == Some(user.id) { " (you)" } else { "" };
+        println!("{:<24} created {}{}", user.username, user.created_at.format("%Y-%m-%d"), current);
+    }
+    Ok(())
+}
+
+async fn passwd(repo: &TaskRepository) -> Result<()> {
+    let Some(user_id) = repo.user() else {
+        return Err(TaskError::InvalidInput(
+            "There are no users yet; create one with `task user add <name>`.".to_string(),
+        ));
+    };
+    let current = rpassword::prompt_password("Current password: ")?;
+    if !repo.password_hash(user_id).await?.is_some_and(|hash| verify_password(&current, &hash)) {
+        return Err(TaskError::InvalidInput("Wrong password.".to_string()));
+    }
+    let password = new_password()?;
+    repo.set_password_hash(user_id, &hash_password(&password)?).await?;
+    println!("Password changed.");
+    Ok(())
+}
+
+// Asks for a new password twice
+fn new_password() -> Result<String> {
+    let password = rpassword::prompt_password("New password: ")?;
+    if password.chars().count() < MIN_PASSWORD {
+        return Err(TaskError::InvalidInput(format!("Passwords need at least {} characters.", MIN_PASSWORD)));
+    }
+    if rpassword::prompt_password("Repeat the password: ")? != password {
+        return Err(TaskError::InvalidInput("The passwords don't match.".to_string()));
+    }
+    Ok(password)
+}
+
+fn prompt(label: &str) -> Result<String> {
+    print!("{}", label);
+    let _ = io::stdout().flush();
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+// Argon2id with its default parameters, as a PHC string that records them along with the salt
+pub fn hash_password(password: &str) -> Result<String> {
+    Argon2::default()
+        .hash_password(password.as_bytes(), &SaltString::generate(&mut OsRng))
+        .map(|hash| hash.to_string())
+        .map_err(|e| TaskError::Config(format!("Could not hash the password: {}", e)))
+}
+
+fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+}
+
+pub fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex(&bytes)
+}
+
+// Hash of the token of this machine's login, as stored in user_sessions
+pub fn session_hash() -> Result<Option<String>> {
+    Ok(secrets::session()?.map(|token| hash_token(&token)))
+}
+
+// Tokens are random, so a plain hash is enough to store them
+pub fn hash_token(token: &str) -> String {
+    hex(&Sha256::digest(token.as_bytes()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,323 @@
+// The task views of the CLI: the list, search results and the details of one task. They read
+// through a `TaskStore` and write to any `Write`, so main.rs hands them the repository and
+// stdout while the tests below use a `MemoryStore` and a buffer.
+
+use std::io::{self, Write};
+
+use futures::TryStreamExt;
+
+use crate::error::Result;
+use crate::input::Input;
+use crate::repository::{ListCursor, SearchCursor};
+use crate::store::TaskStore;
+use crate::{format_due, format_task, format_timestamp, Task};
+
+// Number of tasks shown per page in the interactive list and search views
+pub const PAGE_SIZE: u32 = 20;
+
+// Prints the list page by page. With an `input`, pauses after each page and lets the user
+// stop; without one every page is printed.
+pub async fn list(store: &impl TaskStore, mut input: Option<&mut Input>, out: &mut impl Write) -> Result<()> {
+    let mut cursor: Option<ListCursor> = None;
+
+    loop {
+        let page = store.list_page(cursor, PAGE_SIZE).await?;
+        if cursor.is_none() {
+            print_heading(out, page.tasks.is_empty(), "Your Tasks")?;
+        }
+        print_tasks(out, &page.tasks)?;
+
+        let Some(next) = page.next else { break };
+        if let Some(input) = input.as_deref_mut()
+            && !show_more(out, input).await?
+        {
+            break;
+        }
+        cursor = Some(next);
+    }
+    Ok(())
+}
+
+// Search results, paged like `list`
+pub async fn search(
+    store: &impl TaskStore,
+    query: &str,
+    mut input: Option<&mut Input>,
+    out: &mut impl Write,
+) -> Result<()> {
+    let mut cursor: Option<SearchCursor> = None;
+
+    loop {
+        let page = store.search_page(query, cursor, PAGE_SIZE).await?;
+        if cursor.is_none() {
+            print_heading(out, page.tasks.is_empty(), "Search Results")?;
+        }
+        print_tasks(out, &page.tasks)?;
+
+        let Some(next) = page.next else { break };
+        if let Some(input) = input.as_deref_mut()
+            && !show_more(out, input).await?
+        {
+            break;
+        }
+        cursor = Some(next);
+    }
+    Ok(())
+}
+
+// Non-interactive list of every task, streamed straight from the store to `out`. Given a
+// blocking buffered writer, when stdout is a slow pipe the next row isn't pulled from the
+// database until the previous one has been written.
+pub async fn all(store: &impl TaskStore, out: &mut impl Write) -> Result<()> {
+    let mut tasks = store.stream_all();
+    let mut count = 0;
+
+    while let Some(task) = tasks.try_next().await? {
+        if let Err(e) = writeln!(out, "{}", format_task(&task)) {
+            // The reader went away (e.g. piped into `head`); stop quietly
+            if e.kind() == io::ErrorKind::BrokenPipe {
+                return Ok(());
+            }
+            return Err(e.into());
+        }
+        count += 1;
+    }
+
+    if count == 0 {
+        writeln!(out, "No tasks found.")?;
+    }
+    match out.flush() {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+pub async fn details(store: &impl TaskStore, task_id: i32, out: &mut impl Write) -> Result<()> {
+    let Some(task) = store.get(task_id).await? else {
+        writeln!(out, "No task found with ID {}.", task_id)?;
+        return Ok(());
+    };
+
+    let status = if task.completed { "COMPLETED" } else { "PENDING" };
+    let by = |user: &Option<String>| user.as_deref().map(|u| format!(" by {}", u)).unwrap_or_default();
+
+    writeln!(out, "\n--- Task {} ---", task.id)?;
+    writeln!(out, "Description: {}", task.description)?;
+    writeln!(out, "Status:      {}", status)?;
+    writeln!(out, "Created:     {}{}", format_timestamp(&task.created_at), by(&task.created_by))?;
+    writeln!(out, "Updated:     {}{}", format_timestamp(&task.updated_at), by(&task.updated_by))?;
+    if let Some(due_at) = &task.due_at {
+        writeln!(out, "Due:         {}", format_due(due_at))?;
+    }
+    if let Some(priority) = task.priority {
+        writeln!(out, "Priority:    {}", priority)?;
+    }
+    if let Some(project_id) = task.project_id
+        && let Some(project) = store.project_name(project_id).await?
+    {
+        writeln!(out, "Project:     {}", project)?;
+    }
+    if let Some(assignee_id) = task.assignee_id
+        && let Some(assignee) = store.username(assignee_id).await?
+    {
+        writeln!(out, "Assigned to: {}", assignee)?;
+    }
+
+    let tags = store.tags(task.id).await?;
+    if !tags.is_empty() {
+        writeln!(out, "Tags:        {}", tags.join(", "))?;
+    }
+
+    let checklist = store.checklist(task.id).await?;
+    if !checklist.is_empty() {
+        writeln!(out, "Checklist:")?;
+        for item in &checklist {
+            writeln!(out, "  [{}] {}", if item.done { "x" } else { " " }, item.text)?;
+        }
+    }
+
+    let notes = store.notes(task.id).await?;
+    if !notes.is_empty() {
+        writeln!(out, "Notes:")?;
+        for note in &notes {
+            let author = note.created_by.as_deref().map(|author| format!("{}: ", author)).unwrap_or_default();
+            writeln!(out, "  {}  {}{}", format_timestamp(&note.created_at), author, note.body)?;
+        }
+    }
+
+    let links = store.links(task.id).await?;
+    if !links.is_empty() {
+        writeln!(out, "Linked to:")?;
+        for (source, external_id) in &links {
+            writeln!(out, "  {} {}", source, external_id)?;
+        }
+    }
+    Ok(())
+}
+
+// Asks whether to fetch the next page; anything but "q" continues
+async fn show_more(out: &mut impl Write, input: &mut Input) -> Result<bool> {
+    // What was printed so far must be out before the prompt
+    out.flush()?;
+    Ok(match input.prompt("-- Press Enter for more, or 'q' to stop: ").await {
+        Some(answer) => !answer.trim().eq_ignore_ascii_case("q"),
+        None => false,
+    })
+}
+
+fn print_heading(out: &mut impl Write, empty: bool, heading: &str) -> io::Result<()> {
+    if empty {
+        writeln!(out, "No tasks found.")
+    } else {
+        writeln!(out, "\n--- {} ---", heading)
+    }
+}
+
+fn print_tasks(out: &mut impl Write, tasks: &[Task]) -> io::Result<()> {
+    tasks.iter().try_for_each(|task| writeln!(out, "{}", format_task(task)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{self, at, bundle};
+    use crate::memory::MemoryStore;
+    use crate::priority::Priority;
+    use crate::repository::{ChecklistItem, ExternalId, Note, TaskBundle, Updated};
+
+    // Runs a view into a buffer and returns what it printed
+    macro_rules! render {
+        ($view:ident($($arg:expr),*)) => {{
+            let mut out = Vec::new();
+            $view($($arg,)* &mut out).await.unwrap();
+            String::from_utf8(out).unwrap()
+        }};
+    }
+
+    #[tokio::test]
+    async fn all_lists_newest_first() {
+        let store = MemoryStore::new("tester");
+        store.import(&[bundle("Older", 1), bundle("Newer", 2)]);
+
+        let printed = render!(all(&store));
+        let lines: Vec<&str> = printed.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("ID: 2, [PENDING] Description: 'Newer'"), "{}", printed);
+        assert!(lines[1].starts_with("ID: 1, [PENDING] Description: 'Older'"), "{}", printed);
+    }
+
+    #[tokio::test]
+    async fn empty_views_say_so() {
+        let store = MemoryStore::new("tester");
+
+        assert_eq!(render!(all(&store)), "No tasks found.\n");
+        assert_eq!(render!(list(&store, None)), "No tasks found.\n");
+        let printed = render!(details(&store, 7));
+        assert_eq!(printed, "No task found with ID 7.\n");
+    }
+
+    #[tokio::test]
+    async fn list_without_input_prints_every_page() {
+        let store = MemoryStore::new("tester");
+        let bundles: Vec<TaskBundle> = (1..=PAGE_SIZE + 5).map(|n| bundle(&format!("Task {}", n), 1)).collect();
+        store.import(&bundles);
+
+        let printed = render!(list(&store, None));
+        assert_eq!(printed.matches("[PENDING]").count(), PAGE_SIZE as usize + 5);
+        assert_eq!(printed.matches("--- Your Tasks ---").count(), 1);
+    }
+
+    #[tokio::test]
+    async fn search_matches_regardless_of_case() {
+        let store = MemoryStore::new("tester");
+        store.import(&[bundle("Buy milk", 1), bundle("Call the bank", 2)]);
+
+        let printed = render!(search(&store, "MILK", None));
+        assert!(printed.contains("--- Search Results ---"), "{}", printed);
+        assert!(printed.contains("Buy milk") && !printed.contains("Call the bank"), "{}", printed);
+
+        let printed = render!(search(&store, "bread", None));
+        assert_eq!(printed, "No tasks found.\n");
+    }
+
+    #[tokio::test]
+    async fn details_show_what_hangs_off_a_task() {
+        let store = MemoryStore::new("tester");
+        let bob = store.add_user("bob");
+        let mut task = bundle("Plan the trip", 1);
+        task.task.priority = Some(Priority::High);
+        task.task.assignee_id = Some(bob);
+        task.tags = vec!["travel".to_string()];
+        task.project = Some("Holidays".to_string());
+        task.checklist = vec![
+            ChecklistItem { text: "Book flights".to_string(), done: true },
+            ChecklistItem { text: "Pack".to_string(), done: false },
+        ];
+        task.notes =
+            vec![Note { created_at: at(2), body: "window seat".to_string(), created_by: Some("bob".to_string()) }];
+        task.external = Some(ExternalId { source: "jira", id: "TRIP-1".to_string() });
+        let id = store.import(&[task])[0];
+
+        let printed = render!(details(&store, id));
+        for expected in [
+            "--- Task 1 ---",
+            "Description: Plan the trip",
+            "Status:      PENDING",
+            "by alice",
+            "Priority:    high",
+            "Project:     Holidays",
+            "Assigned to: bob",
+            "Tags:        travel",
+            "  [x] Book flights",
+            "  [ ] Pack",
+            "bob: window seat",
+            "  jira TRIP-1",
+        ] {
+            assert!(printed.contains(expected), "missing {:?} in:\n{}", expected, printed);
+        }
+        assert!(!printed.contains("Due:"), "{}", printed);
+    }
+
+    #[tokio::test]
+    async fn accounts_see_only_their_tasks() {
+        let store = MemoryStore::new("tester").with_user(Some(1));
+        let mut theirs = bundle("Someone else's", 1);
+        theirs.task.owner_id = Some(2);
+        store.import(&[bundle("Mine", 2), theirs]);
+
+        let printed = render!(all(&store));
+        assert!(printed.contains("Mine") && !printed.contains("Someone else's"), "{}", printed);
+        let printed = render!(details(&store, 2));
+        assert_eq!(printed, "No task found with ID 2.\n");
+    }
+
+    #[tokio::test]
+    async fn completing_shows_in_the_views() {
+        let store = MemoryStore::new("tester");
+        let id = store.add("Water the plants").await.unwrap();
+        assert_eq!(store.complete(id).await.unwrap(), Some(Updated { completed: true }));
+        assert_eq!(store.complete(id).await.unwrap(), Some(Updated { completed: false }));
+        assert!(store.complete(id + 1).await.unwrap().is_none());
+
+        let printed = render!(details(&store, id));
+        assert!(printed.contains("Status:      COMPLETED"), "{}", printed);
+        assert!(printed.contains("by tester"), "{}", printed);
+    }
+
+    // Every kind of task in `fixtures::mixed`, as `task list` prints them
+    #[tokio::test]
+    async fn list_snapshot() {
+        let store = MemoryStore::new("tester");
+        store.import(&fixtures::mixed());
+        insta::assert_snapshot!(render!(all(&store)));
+    }
+
+    #[tokio::test]
+    async fn details_snapshots() {
+        let store = MemoryStore::new("tester");
+        let ids = store.import(&fixtures::mixed());
+        for (name, id) in [("long", ids[1]), ("unicode", ids[2]), ("overdue", ids[4]), ("completed", ids[5])] {
+            insta::assert_snapshot!(format!("details_{}", name), render!(details(&store, id)));
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::cli::WebhookCommand;
+use crate::config::{Config, WebhookConfig};
+use crate::error::Result;
+use crate::hooks::{Hooks, Plugin};
+use crate::mirror::GitMirror;
+use crate::repository::{TaskRepository, WebhookDelivery};
+use crate::sync::http_client;
+use crate::Task;
+
+const SERVICE: &str = "Webhook";
+// Attempts after which a delivery that keeps failing is "failed" rather than "pending". Each
+// attempt after the first is a `task webhook retry` or a run of the daemon's webhook retry job.
+const MAX_ATTEMPTS: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookEvent {
+    Created,
+    Completed,
+    Deleted,
+}
+
+impl WebhookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WebhookEvent::Created => "created",
+            WebhookEvent::Completed => "completed",
+            WebhookEvent::Deleted => "deleted",
+        }
+    }
+}
+
+// Body of every request:
+//
+//   {"event":"completed","task_id":12,"occurred_at":"2026-10-14T09:30:00+02:00","task":{...}}
+//
+// `task` is the task as it is after the event, and null for "deleted".
+#[derive(Debug, Serialize)]
+struct Payload<'a> {
+    event: WebhookEvent,
+    task_id: i32,
+    occurred_at: String,
+    task: Option<&'a Task>,
+}
+
+// Everything that hears about task changes from outside: the configured webhooks, the git
+// mirror, the commands and plugins of hooks.rs and, in builds with the `mqtt` feature, the MQTT
+// broker
+#[derive(Clone)]
+pub struct Webhooks {
+    http: reqwest::Client,
+    hooks: Vec<WebhookConfig>,
+    mirror: Option<GitMirror>,
+    commands: Hooks,
+    #[cfg(feature = "mqtt")]
+    mqtt: Option<crate::mqtt::Mqtt>,
+}
+
+impl Webhooks {
+    pub fn new(config: &Config) -> Result<Self> {
+        #[cfg(not(feature = "mqtt"))]
+        if config.mqtt.host.is_some() {
+            tracing::warn!("[mqtt] is configured, but this build of task has no MQTT support (feature `mqtt`)");
+        }
+        Ok(Webhooks {
+            http: http_client(SERVICE)?,
+            hooks: config.webhooks.clone(),
+            mirror: GitMirror::new(config),
+            commands: Hooks::new(config),
+            #[cfg(feature = "mqtt")]
+            mqtt: crate::mqtt::Mqtt::connect(config)?,
+        })
+    }
+
+    // Also tells `plugin` about every event
+    pub fn with_plugin(mut self, plugin: std::sync::Arc<dyn Plugin>) -> Self {
+        self.commands.register(plugin);
+        self
+    }
+
+    // Sends the event to every webhook that wants it, publishes it to MQTT, commits it to the git
+    // mirror and runs the hooks. A webhook request that fails is only reported and left pending,
+    // since the change itself is done and waiting to try again would hold up whoever made it;
+    // `task webhook retry` and the daemon send it again later.
+    pub async fn task_event(&self, repo: &TaskRepository, event: WebhookEvent, id: i32) -> Result<()> {
+        if let Some(mirror) = &self.mirror
+            && let Err(e) = mirror.update(repo, Some((event, id))).await
+        {
+            tracing::warn!(task_id = id, error = %e, "could not update the git mirror");
+        }
+
+        let hooks: Vec<&WebhookConfig> =
+            self.hooks.iter().filter(|hook| hook.events.is_empty() || hook.events.contains(&event)).collect();
+        if hooks.is_empty() && !self.publishes_mqtt() && !self.commands.wants(event) {
+            return Ok(());
+        }
+
+        let task = match event {
+            WebhookEvent::Deleted => None,
+            _ => repo.get(id).await?,
+        };
+        let payload = Payload {
+            event,
+            task_id: id,
+            occurred_at: chrono::Local::now().to_rfc3339(),
+            task: task.as_ref(),
+        };
+        let body = serde_json::to_string(&payload).map_err(std::io::Error::from)?;
+
+        #[cfg(feature = "mqtt")]
+        if let Some(mqtt) = &self.mqtt {
+            mqtt.publish(event, &body);
+        }
+        if self.commands.wants(event) {
+            self.commands.run(event, id, task.as_ref(), &body).await;
+        }
+        for hook in hooks {
+            let delivery = repo.log_webhook_delivery(&hook.url, event.as_str(), id, &body).await?;
+            if let Err(e) = self.deliver(repo, hook, delivery, 0, event.as_str(), &body).await? {
+                // `task webhook retry` and the daemon send it again
+                tracing::warn!(task_id = id, url = %hook.url, error = %e, "webhook failed");
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "mqtt")]
+    fn publishes_mqtt(&self) -> bool {
+        self.mqtt.is_some()
+    }
+
+    #[cfg(not(feature = "mqtt"))]
+    fn publishes_mqtt(&self) -> bool {
+        false
+    }
+
+    // Makes one attempt at a logged request, after `attempts` earlier ones, and records it. The
+    // outer result is for database errors, the inner one says whether the webhook took it.
+    async fn deliver(
+        &self,
+        repo: &TaskRepository,
+        hook: &WebhookConfig,
+        delivery: u64,
+        attempts: i32,
+        event: &str,
+        body: &str,
+    ) -> Result<std::result::Result<(), String>> {
+        let (code, error) = match self.post(hook, delivery, event, body).await {
+            Ok(code) => {
+                repo.record_webhook_attempt(delivery, "delivered", Some(code), None).await?;
+                return Ok(Ok(()));
+            }
+            Err(failure) => failure,
+        };
+        repo.record_webhook_attempt(delivery, failed_status(attempts + 1), code, Some(&error)).await?;
+        Ok(Err(error))
+    }
+
+    // Returns the response status, or the failure with the status if there was a response
+    async fn post(
+        &self,
+        hook: &WebhookConfig,
+        delivery: u64,
+        event: &str,
+        body: &str,
+    ) -> std::result::Result<u16, (Option<u16>, String)> {
+        let mut request = self
+            .http
+            .post(&hook.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Task-Event", event)
+            .header("X-Task-Delivery", delivery.to_string());
+        if let Some(secret) = &hook.secret {
+            request = request.header("X-Task-Signature", signature(secret, body));
+        }
+
+        let response = request.body(body.to_string()).send().await.map_err(|e| (None, e.to_string()))?;
+        let status = response.status();
+        if status.is_success() {
+            Ok(status.as_u16())
+        } else {
+            Err((Some(status.as_u16()), format!("server answered {}", status)))
+        }
+    }
+}
+
+// "sha256=<hex HMAC-SHA256 of the body>", so receivers can check the request came from us
+fn signature(secret: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    let digest = mac.finalize().into_bytes();
+    let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("sha256={}", hex)
+}
+
+pub async fn run(repo: &TaskRepository, config: &Config, command: WebhookCommand) -> Result<()> {
+    match command {
+        WebhookCommand::Log { limit } => log(repo, limit).await,
+        WebhookCommand::Retry => retry(repo, config).await,
+    }
+}
+
+async fn log(repo: &TaskRepository, limit: u32) -> Result<()> {
+    let deliveries = repo.webhook_deliveries(limit).await?;
+    if deliveries.is_empty() {
+        println!("No webhook deliveries yet.");
+        return Ok(());
+    }
+
+    for delivery in &deliveries {
+        println!("{}", format_delivery(delivery));
+    }
+    Ok(())
+}
+
+fn format_delivery(delivery: &WebhookDelivery) -> String {
+    let task = delivery.task_id.map(|id| format!(" task {}", id)).unwrap_or_default();
+    let code = delivery.response_code.map(|code| format!(" HTTP {}", code)).unwrap_or_default();
+    let attempts = match delivery.last_attempt_at {
+        Some(last) => format!(", {} attempt(s), last at {}", delivery.attempts, last.format("%Y-%m-%d %H:%M:%S")),
+        None => String::new(),
+    };
+    let error = match (&delivery.error, delivery.status.as_str()) {
+        (Some(error), "failed" | "pending") => format!(": {}", error),
+        _ => String::new(),
+    };
+    format!(
+        "#{} {} {}{} -> {}\n    {}{}{}{}",
+        delivery.id,
+        delivery.created_at.format("%Y-%m-%d %H:%M:%S"),
+        delivery.event,
+        task,
+        delivery.url,
+        delivery.status,
+        code,
+        attempts,
+        error
+    )
+}
+
+// Status of a delivery whose `attempts` all failed: "pending" until MAX_ATTEMPTS, when the
+// retries stop
+fn failed_status(attempts: i32) -> &'static str {
+    if attempts >= MAX_ATTEMPTS { "failed" } else { "pending" }
+}
+
+// Sends the pending deliveries that didn't go through again, once each, with the stored payload and the
+// current secret of their URL
+async fn retry(repo: &TaskRepository, config: &Config) -> Result<()> {
+    let failed = repo.undelivered_webhook_deliveries().await?;
+    if failed.is_empty() {
+        println!("No webhook deliveries to retry.");
+        return Ok(());
+    }
+
+    let webhooks = Webhooks::new(config)?;
+    let (mut delivered, mut still_failing) = (0, 0);
+    for delivery in &failed {
+        let Some(hook) = webhooks.hooks.iter().find(|hook| hook.url == delivery.url) else {
+            println!("Skipping #{}: {} is no longer configured.", delivery.id, delivery.url);
+            continue;
+        };
+        let (id, attempts) = (delivery.id, delivery.attempts);
+        match webhooks.deliver(repo, hook, id, attempts, &delivery.event, &delivery.payload).await? {
+            Ok(()) => delivered += 1,
+            Err(e) => {
+                println!("#{} to {} failed again: {}", delivery.id, delivery.url, e);
+                still_failing += 1;
+            }
+        }
+    }
+    println!("Delivered {}, still failing {}.", delivered, still_failing);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_stop_after_the_last_attempt() {
+        assert_eq!(failed_status(1), "pending");
+        assert_eq!(failed_status(MAX_ATTEMPTS - 1), "pending");
+        assert_eq!(failed_status(MAX_ATTEMPTS), "failed");
+    }
+}
@@ -0,0 +1,195 @@
+use crate::cli::WorkspaceCommand;
+use crate::error::{Result, TaskError};
+use crate::repository::{TaskRepository, User};
+use crate::roles::Role;
+use crate::users;
+
+// Length of workspaces.name
+const MAX_NAME: usize = 64;
+
+// Workspaces keep groups that share one database apart: a command sees only the projects and
+// tasks of the workspace its login switched to, or those of the personal space. Each member has
+// a role there: admins may do everything, members everything but deleting tasks and managing
+// projects and members, and viewers only read. The repository enforces the roles; this only
+// keeps a workspace from losing its last admin. Shares and assignments work as usual inside.
+// Admins can also invite people who have no account yet: an invitation is a random code,
+// stored hashed like session tokens, that creates one account in the workspace before it
+// expires.
+pub async fn run(repo: &TaskRepository, command: WorkspaceCommand) -> Result<()> {
+    let Some(user_id) = repo.user() else {
+        return Err(TaskError::InvalidInput(
+            "Workspaces need accounts; create the first with `task user add <name>`.".to_string(),
+        ));
+    };
+    match command {
+        WorkspaceCommand::Create { name } => {
+            let name = name.trim();
+            if name.is_empty() || name.chars().count() > MAX_NAME {
+                return Err(TaskError::InvalidInput(format!("Workspace names have 1 to {} characters.", MAX_NAME)));
+            }
+            if repo.workspace_id(name).await?.is_some() {
+                return Err(TaskError::InvalidInput(format!("There is already a workspace named {}.", name)));
+            }
+            repo.create_workspace(name).await?;
+            println!("Created workspace {0}. Run `task workspace switch {0}` to work in it.", name);
+        }
+        WorkspaceCommand::List => list(repo).await?,
+        WorkspaceCommand::Switch { name } => {
+            let workspace = match name.as_deref().map(str::trim) {
+                Some(name) => Some(member_of(repo, name, user_id).await?),
+                None => None,
+            };
+            let hash = users::session_hash()?.ok_or_else(|| {
+                TaskError::Config("Not logged in. Run `task login` first.".to_string())
+            })?;
+            repo.set_session_workspace(&hash, workspace).await?;
+            match name {
+                Some(name) => println!("Now working in workspace {}.", name.trim()),
+                None => println!("Now working in your personal space."),
+            }
+        }
+        WorkspaceCommand::Members { workspace } => {
+            let workspace_id = member_of(repo, workspace.trim(), user_id).await?;
+            for member in repo.members(workspace_id).await? {
+                let current = if member.user_id == user_id { " (you)" } else { "" };
+                println!("{:<24} {}{}", member.username, member.role, current);
+            }
+        }
+        WorkspaceCommand::Add { workspace, user, role } => {
+            let workspace_id = member_of(repo, workspace.trim(), user_id).await?;
+            let user = user_named(repo, &user).await?;
+            if repo.add_member(workspace_id, user.id, role).await? {
+                println!("Added {} to workspace {} as {}.", user.username, workspace.trim(), role);
+            } else {
+                println!("{} is already in workspace {}.", user.username, workspace.trim());
+            }
+        }
+        WorkspaceCommand::Role { workspace, user, role } => {
+            let workspace_id = member_of(repo, workspace.trim(), user_id).await?;
+            let user = user_named(repo, &user).await?;
+            if !repo.is_member(workspace_id, user.id).await? {
+                let message = format!("{} is not in workspace {}.", user.username, workspace.trim());
+                return Err(TaskError::InvalidInput(message));
+            }
+            if role != Role::Admin {
+                keep_an_admin(repo, workspace_id, user.id).await?;
+            }
+            repo.set_role(workspace_id, user.id, role).await?;
+            println!("{} is now {} in workspace {}.", user.username, article(role), workspace.trim());
+        }
+        WorkspaceCommand::Remove { workspace, user } => {
+            let workspace_id = member_of(repo, workspace.trim(), user_id).await?;
+            let user = user_named(repo, &user).await?;
+            keep_an_admin(repo, workspace_id, user.id).await?;
+            if repo.remove_member(workspace_id, user.id).await? {
+                // Their tasks there stay, but keep them out until they are added again
+                println!("Removed {} from workspace {}.", user.username, workspace.trim());
+            } else {
+                println!("{} is not in workspace {}.", user.username, workspace.trim());
+            }
+        }
+        WorkspaceCommand::Invite { workspace, role, days, url } => {
+            let workspace_id = member_of(repo, workspace.trim(), user_id).await?;
+            if days == 0 {
+                return Err(TaskError::InvalidInput("An invitation has to last at least a day.".to_string()));
+            }
+            let code = users::new_token();
+            let expires_at =
+                repo.create_invitation(workspace_id, &users::hash_token(&code), role, i64::from(days)).await?;
+            println!(
+                "Invitation to join {} as {}, until {}. It works once and won't be shown again:",
+                workspace.trim(),
+                article(role),
+                expires_at.format("%Y-%m-%d %H:%M")
+            );
+            println!("{}", code);
+            println!("Redeem it with `task user join <code>`.");
+            if let Some(url) = url {
+                println!("Or, through `task serve`: {}/invitations/{}", url.trim_end_matches('/'), code);
+            }
+        }
+        WorkspaceCommand::Invitations { workspace } => {
+            let workspace_id = member_of(repo, workspace.trim(), user_id).await?;
+            let invitations = repo.invitations(workspace_id).await?;
+            if invitations.is_empty() {
+                println!("Workspace {} has no open invitations.", workspace.trim());
+            }
+            for invitation in &invitations {
+                println!(
+                    "{:>4}  {:<7} by {:<24} on {}, expires {}",
+                    invitation.id,
+                    invitation.role,
+                    invitation.created_by.as_deref().unwrap_or("(deleted user)"),
+                    invitation.created_at.format("%Y-%m-%d"),
+                    invitation.expires_at.format("%Y-%m-%d %H:%M")
+                );
+            }
+        }
+        WorkspaceCommand::Uninvite { workspace, id } => {
+            let workspace_id = member_of(repo, workspace.trim(), user_id).await?;
+            if !repo.delete_invitation(workspace_id, id).await? {
+                return Err(TaskError::InvalidInput(format!(
+                    "Workspace {} has no open invitation {}.",
+                    workspace.trim(),
+                    id
+                )));
+            }
+            println!("Withdrew invitation {}.", id);
+        }
+    }
+    Ok(())
+}
+
+async fn list(repo: &TaskRepository) -> Result<()> {
+    let workspaces = repo.workspaces().await?;
+    let marker = |current: bool| if current { "*" } else { " " };
+    println!("{} (personal space)", marker(repo.workspace().is_none()));
+    for workspace in &workspaces {
+        println!(
+            "{} {:<24} {:<7} {} member(s), created {}",
+            marker(repo.workspace() == Some(workspace.id)),
+            workspace.name,
+            workspace.role,
+            workspace.members,
+            workspace.created_at.format("%Y-%m-%d")
+        );
+    }
+    if workspaces.is_empty() {
+        println!("You are not in any workspace. Create one with `task workspace create <name>`.");
+    }
+    Ok(())
+}
+
+// Id of the workspace, if the user is in it; others are reported as missing
+async fn member_of(repo: &TaskRepository, name: &str, user_id: i32) -> Result<i32> {
+    match repo.workspace_id(name).await? {
+        Some(id) if repo.is_member(id, user_id).await? => Ok(id),
+        _ => Err(TaskError::InvalidInput(format!("You are not in a workspace named {}.", name))),
+    }
+}
+
+// Fails if the user is the workspace's only admin, who would leave nobody able to manage it
+async fn keep_an_admin(repo: &TaskRepository, workspace_id: i32, user_id: i32) -> Result<()> {
+    let members = repo.members(workspace_id).await?;
+    let admins: Vec<i32> =
+        members.iter().filter(|member| member.role == Role::Admin).map(|member| member.user_id).collect();
+    if admins == [user_id] {
+        return Err(TaskError::InvalidInput(
+            "A workspace needs an admin; make someone else admin first.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn article(role: Role) -> String {
+    match role {
+        Role::Admin => format!("an {}", role),
+        _ => format!("a {}", role),
+    }
+}
+
+async fn user_named(repo: &TaskRepository, username: &str) -> Result<User> {
+    repo.user_by_name(username.trim())
+        .await?
+        .ok_or_else(|| TaskError::InvalidInput(format!("There is no user named {}.", username.trim())))
+}
@@ -0,0 +1,158 @@
+// The `task` binary end to end, run as a user would against a MySQL container. Commands run
+// without a config file, in a directory of their own, so nothing of the machine's leaks in.
+
+mod common;
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+use common::Database;
+
+// A fresh directory for one test's files
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("task-cli-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create scratch directory");
+    dir
+}
+
+fn task(db: &Database, dir: &PathBuf, args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_task"))
+        .args(args)
+        .current_dir(dir)
+        .env("DATABASE_URL", &db.url)
+        .env("TASK_CONFIG", dir.join("task.toml"))
+        .env("TASK_DATA_DIR", dir)
+        .env("TASK_LOG", "off")
+        .env_remove("TASK_PROFILE")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("run task");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    child.wait_with_output().expect("wait for task")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+// Runs a command that should succeed and returns what it printed
+fn ok(db: &Database, dir: &PathBuf, args: &[&str]) -> String {
+    let output = task(db, dir, args, "");
+    assert!(
+        output.status.success(),
+        "task {} failed: {}",
+        args.join(" "),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    stdout(&output)
+}
+
+#[tokio::test]
+async fn interactive_menu_adds_and_lists() {
+    let Some(db) = common::database().await else { return };
+    let dir = scratch("menu");
+
+    let output = task(&db, &dir, &[], "1\nBuy milk\n2\n8\n");
+    assert!(output.status.success());
+    let printed = stdout(&output);
+    assert!(printed.contains("Task 'Buy milk' added successfully!"), "{}", printed);
+    assert!(printed.contains("Description: 'Buy milk'"), "{}", printed);
+
+    assert!(ok(&db, &dir, &["list"]).contains("Buy milk"));
+    assert!(ok(&db, &dir, &["search", "milk"]).contains("Buy milk"));
+    assert!(ok(&db, &dir, &["search", "bread"]).contains("No tasks found."));
+}
+
+#[tokio::test]
+async fn shows_tasks_with_their_notes() {
+    let Some(db) = common::database().await else { return };
+    let dir = scratch("show");
+    let id = db.repo().add("Water the plants").await.unwrap().to_string();
+
+    assert!(ok(&db, &dir, &["note", &id, "the", "ferns", "too"]).contains(&format!("Added a note to task {}.", id)));
+    let shown = ok(&db, &dir, &["show", &id]);
+    assert!(shown.contains("Description: Water the plants"), "{}", shown);
+    assert!(shown.contains("the ferns too"), "{}", shown);
+    assert!(ok(&db, &dir, &["show", "9999"]).contains("No task found with ID 9999."));
+    assert!(ok(&db, &dir, &["activity"]).contains("Water the plants"));
+}
+
+#[tokio::test]
+async fn exports_and_imports_json() {
+    let Some(db) = common::database().await else { return };
+    let dir = scratch("json");
+    let repo = db.repo();
+    let id = repo.add("Renew passport").await.unwrap();
+    repo.set_tags(id, &["admin".to_string()]).await.unwrap();
+
+    ok(&db, &dir, &["export", "json", "--output", "tasks.json"]);
+    assert!(repo.delete(id).await.unwrap());
+    ok(&db, &dir, &["import", "json", "tasks.json", "--yes"]);
+
+    let page = repo.search_page("passport", None, 10).await.unwrap();
+    assert_eq!(page.tasks.len(), 1);
+    assert_eq!(repo.tags(page.tasks[0].id).await.unwrap(), ["admin"]);
+}
+
+#[tokio::test]
+async fn backs_up_and_restores() {
+    let Some(db) = common::database().await else { return };
+    let dir = scratch("backup");
+    let repo = db.repo();
+    let id = repo.add("File taxes").await.unwrap();
+    let done = repo.add("Pay rent").await.unwrap();
+    assert!(repo.complete(done).await.unwrap().is_some());
+    // Changed since it was completed, so updated_at would give the wrong day
+    let completed_at = "2026-01-05 10:00:00";
+    let stamp = sqlx::query("UPDATE tasks SET completed_at = ? WHERE id = ?").bind(completed_at).bind(done);
+    stamp.execute(&db.pool).await.unwrap();
+    repo.set_sms_alert(id, true).await.unwrap();
+    let activity = "SELECT GROUP_CONCAT(id, action, COALESCE(actor, '') ORDER BY id) FROM task_activity";
+    let history: String = sqlx::query_scalar(activity).fetch_one(&db.pool).await.unwrap();
+
+    ok(&db, &dir, &["backup", "tasks.backup"]);
+    assert!(repo.delete(id).await.unwrap());
+    ok(&db, &dir, &["restore", "tasks.backup", "--wipe", "--yes"]);
+    assert_eq!(repo.get(id).await.unwrap().expect("restored").description, "File taxes");
+    let restored: String = sqlx::query_scalar("SELECT CAST(completed_at AS CHAR) FROM tasks WHERE id = ?")
+        .bind(done)
+        .fetch_one(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(restored, completed_at);
+    // The deletion since the backup is gone from the feed with the rest of the database
+    assert_eq!(sqlx::query_scalar::<_, String>(activity).fetch_one(&db.pool).await.unwrap(), history);
+    assert_eq!(repo.sms_alert_tasks().await.unwrap(), [id]);
+}
+
+#[tokio::test]
+async fn exit_codes_tell_failures_apart() {
+    let Some(db) = common::database().await else { return };
+    let dir = scratch("exit");
+
+    // A file that isn't there is bad input; one that isn't JSON can't be parsed
+    assert_eq!(task(&db, &dir, &["import", "json", "missing.json", "--yes"], "").status.code(), Some(64));
+    std::fs::write(dir.join("broken.json"), "not json").unwrap();
+    assert_eq!(task(&db, &dir, &["import", "json", "broken.json", "--yes"], "").status.code(), Some(65));
+
+    sqlx::query("DELETE FROM schema_meta").execute(&db.pool).await.unwrap();
+    let outdated = task(&db, &dir, &["list"], "");
+    assert_eq!(outdated.status.code(), Some(75));
+    assert!(String::from_utf8_lossy(&outdated.stderr).contains("task migrate"));
+    ok(&db, &dir, &["migrate"]);
+    ok(&db, &dir, &["list"]);
+}
+
+#[tokio::test]
+async fn reports_database_statistics() {
+    let Some(db) = common::database().await else { return };
+    let dir = scratch("stats");
+    db.repo().add("Counted").await.unwrap();
+    ok(&db, &dir, &["stats", "db"]);
+    ok(&db, &dir, &["export", "todotxt"]);
+    ok(&db, &dir, &["export", "ics"]);
+}
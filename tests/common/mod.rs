@@ -0,0 +1,61 @@
+// Setup shared by the integration tests: a MySQL server of its own for each test, in a
+// container, migrated to the current schema. Without Docker the tests are skipped with a note;
+// set TASK_TEST_REQUIRE_DOCKER to make that a failure instead, as CI should.
+
+// Each test binary uses only part of this module
+#![allow(dead_code)]
+
+use sqlx::MySqlPool;
+use taskcore::config::ConnectionSettings;
+use taskcore::repository::TaskRepository;
+use taskcore::{db, schema};
+use testcontainers_modules::mysql::Mysql;
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+use testcontainers_modules::testcontainers::ContainerAsync;
+
+// Name the audit columns get from repositories of these tests
+pub const ACTOR: &str = "tester";
+
+pub struct Database {
+    pub url: String,
+    pub pool: MySqlPool,
+    // The container goes away with it
+    _container: ContainerAsync<Mysql>,
+}
+
+impl Database {
+    // Sees the whole database, as before there are accounts
+    pub fn repo(&self) -> TaskRepository {
+        TaskRepository::new(self.pool.clone(), ACTOR.to_string())
+    }
+
+    // Works as the account, like a logged-in CLI
+    pub fn repo_as(&self, user_id: i32, username: &str) -> TaskRepository {
+        TaskRepository::new(self.pool.clone(), username.to_string()).with_user(Some(user_id))
+    }
+
+    // Creates an account; the first one is the instance admin
+    pub async fn user(&self, username: &str) -> i32 {
+        let (id, _) = self.repo().create_user(username, "not-a-real-hash").await.expect("create user");
+        id
+    }
+}
+
+pub async fn database() -> Option<Database> {
+    let container = match Mysql::default().start().await {
+        Ok(container) => container,
+        Err(e) if std::env::var_os("TASK_TEST_REQUIRE_DOCKER").is_none() => {
+            eprintln!("skipping: could not start a MySQL container ({})", e);
+            return None;
+        }
+        Err(e) => panic!("could not start a MySQL container: {}", e),
+    };
+    let host = container.get_host().await.expect("container host");
+    let port = container.get_host_port_ipv4(3306).await.expect("container port");
+    let url = format!("mysql://root@{}:{}/test", host, port);
+
+    let settings = ConnectionSettings { profile: None, database_url: url.clone(), max_connections: 5 };
+    let pool = db::connect(&settings).await.expect("connect to the container");
+    schema::migrate(&pool).await.expect("migrate");
+    Some(Database { url, pool, _container: container })
+}
@@ -0,0 +1,500 @@
+// The repository against a real MySQL server, so changes to its SQL are checked where they
+// run. See common/mod.rs for how the server is started.
+
+mod common;
+
+use chrono::{Duration, Local, NaiveDateTime};
+use futures::TryStreamExt;
+use taskcore::activity::Action;
+use taskcore::api_tokens::Scope;
+use taskcore::error::TaskError;
+use taskcore::priority::Priority;
+use taskcore::repository::{
+    BATCH_SIZE, ChecklistItem, ExternalId, ListCursor, NewTask, Note, SearchCursor, TaskBundle, TaskChanges, TaskFilter,
+    Updated,
+};
+use taskcore::roles::Role;
+
+fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+fn new_task(description: &str) -> NewTask {
+    NewTask {
+        id: None,
+        description: description.to_string(),
+        completed: false,
+        created_at: now(),
+        created_by: None,
+        updated_by: None,
+        updated_at: now(),
+        completed_at: None,
+        due_at: None,
+        priority: None,
+        project_id: None,
+        owner_id: None,
+        assignee_id: None,
+        workspace_id: None,
+    }
+}
+
+fn is_forbidden(e: sqlx::Error) -> bool {
+    matches!(TaskError::from(e), TaskError::Forbidden(_))
+}
+
+#[tokio::test]
+async fn adds_changes_and_deletes_tasks() {
+    let Some(db) = common::database().await else { return };
+    let repo = db.repo();
+
+    let id = repo.add("Buy milk").await.unwrap();
+    let task = repo.get(id).await.unwrap().expect("the new task");
+    assert_eq!(task.description, "Buy milk");
+    assert!(!task.completed);
+    assert_eq!(task.created_by.as_deref(), Some(common::ACTOR));
+
+    let due = now().date().and_hms_opt(9, 0, 0).unwrap() + Duration::days(1);
+    let changes = TaskChanges {
+        description: Some("Buy oat milk".to_string()),
+        due_at: Some(Some(due)),
+        priority: Some(Some(Priority::High)),
+        ..TaskChanges::default()
+    };
+    assert!(repo.update(id, &changes).await.unwrap().is_some());
+    let task = repo.get(id).await.unwrap().unwrap();
+    assert_eq!(task.description, "Buy oat milk");
+    assert_eq!(task.due_at, Some(due));
+    assert_eq!(task.priority, Some(Priority::High));
+
+    let cleared = TaskChanges { due_at: Some(None), priority: Some(None), ..TaskChanges::default() };
+    assert!(repo.update(id, &cleared).await.unwrap().is_some());
+    let task = repo.get(id).await.unwrap().unwrap();
+    assert_eq!((task.due_at, task.priority), (None, None));
+
+    assert_eq!(repo.complete(id).await.unwrap(), Some(Updated { completed: true }));
+    assert!(repo.get(id).await.unwrap().unwrap().completed);
+    // Completing it again, as a retry might, succeeds and isn't recorded twice
+    assert_eq!(repo.complete(id).await.unwrap(), Some(Updated { completed: false }));
+    // Only going from open to done counts as completing it
+    let done = TaskChanges { completed: Some(true), ..TaskChanges::default() };
+    assert_eq!(repo.update(id, &done).await.unwrap().map(|updated| updated.completed), Some(false));
+    let reopen = TaskChanges { completed: Some(false), ..TaskChanges::default() };
+    assert!(repo.update(id, &reopen).await.unwrap().is_some());
+    assert!(!repo.get(id).await.unwrap().unwrap().completed);
+    assert_eq!(repo.update(id, &done).await.unwrap().map(|updated| updated.completed), Some(true));
+    assert!(repo.update(id, &reopen).await.unwrap().is_some());
+
+    assert!(repo.delete(id).await.unwrap());
+    assert!(repo.get(id).await.unwrap().is_none());
+    assert!(!repo.delete(id).await.unwrap());
+    assert!(repo.complete(id).await.unwrap().is_none());
+
+    // Newest first, and kept after the task is gone
+    let actions: Vec<Action> = repo.activity(None, None, 10).await.unwrap().entries.iter().map(|e| e.action).collect();
+    assert_eq!(
+        actions,
+        [
+            Action::Deleted,
+            Action::Reopened,
+            Action::Completed,
+            Action::Reopened,
+            Action::Completed,
+            Action::Updated,
+            Action::Updated,
+            Action::Created
+        ]
+    );
+}
+
+#[tokio::test]
+async fn completes_and_deletes_many() {
+    let Some(db) = common::database().await else { return };
+    let repo = db.repo();
+    let first = repo.add("First").await.unwrap();
+    let second = repo.add("Second").await.unwrap();
+
+    // Ids that don't exist are left out
+    let mut completed = repo.complete_many(&[first, second, 9999]).await.unwrap();
+    completed.sort();
+    assert_eq!(completed, [first, second]);
+    assert!(repo.get(first).await.unwrap().unwrap().completed);
+    // So are those already done, which aren't recorded again
+    let third = repo.add("Third").await.unwrap();
+    assert_eq!(repo.complete_many(&[first, third]).await.unwrap(), [third]);
+    let entries = repo.activity(None, None, 10).await.unwrap().entries;
+    assert_eq!(entries.iter().filter(|entry| entry.action == Action::Completed).count(), 3);
+
+    assert_eq!(repo.delete_many(&[second, 9999]).await.unwrap(), [second]);
+    assert!(repo.get(second).await.unwrap().is_none());
+    assert!(repo.complete_many(&[]).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn imports_in_batches() {
+    let Some(db) = common::database().await else { return };
+    let repo = db.repo();
+    // More than one statement's worth, each task with a tag to attach to its new id
+    let bundles: Vec<TaskBundle> = (0..BATCH_SIZE + 5)
+        .map(|n| TaskBundle {
+            task: new_task(&format!("Task {}", n)),
+            tags: vec![format!("tag {}", n)],
+            project: None,
+            checklist: Vec::new(),
+            notes: Vec::new(),
+            external: None,
+        })
+        .collect();
+    let ids = repo.import(&bundles).await.unwrap();
+    assert_eq!(ids.len(), BATCH_SIZE + 5);
+    for n in [0, 1, BATCH_SIZE - 1, BATCH_SIZE, BATCH_SIZE + 4] {
+        assert_eq!(repo.get(ids[n]).await.unwrap().unwrap().description, format!("Task {}", n));
+        assert_eq!(repo.tags(ids[n]).await.unwrap(), [format!("tag {}", n)]);
+    }
+}
+
+#[tokio::test]
+async fn pages_through_every_task() {
+    let Some(db) = common::database().await else { return };
+    let repo = db.repo();
+    let tasks: Vec<NewTask> = (0..45).map(|n| new_task(&format!("Task {}", n))).collect();
+    assert_eq!(repo.insert_batch(&tasks).await.unwrap(), 45);
+
+    let mut seen = Vec::new();
+    let mut cursor: Option<ListCursor> = None;
+    loop {
+        let page = repo.list_page(cursor, 20).await.unwrap();
+        assert!(page.tasks.len() <= 20);
+        seen.extend(page.tasks.iter().map(|task| task.id));
+        match page.next {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    let mut unique = seen.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!((seen.len(), unique.len()), (45, 45));
+
+    let streamed: Vec<i32> = repo.stream_all().map_ok(|task| task.id).try_collect().await.unwrap();
+    assert_eq!(streamed, seen);
+}
+
+#[tokio::test]
+async fn searches_descriptions() {
+    let Some(db) = common::database().await else { return };
+    let repo = db.repo();
+    let milk = repo.add("Buy oat milk").await.unwrap();
+    repo.add("Walk the dog").await.unwrap();
+
+    let page = repo.search_page("milk", None, 20).await.unwrap();
+    assert_eq!(page.tasks.iter().map(|task| task.id).collect::<Vec<_>>(), [milk]);
+    assert!(page.next.is_none());
+
+    // Later pages continue after the last hit
+    for n in 0..5 {
+        repo.add(&format!("More milk {}", n)).await.unwrap();
+    }
+    let first = repo.search_page("milk", None, 3).await.unwrap();
+    let next: SearchCursor = first.next.expect("a second page");
+    let second = repo.search_page("milk", Some(next), 3).await.unwrap();
+    assert_eq!(first.tasks.len() + second.tasks.len(), 6);
+    assert!(second.tasks.iter().all(|task| first.tasks.iter().all(|seen| seen.id != task.id)));
+}
+
+#[tokio::test]
+async fn imports_bundles_with_everything_attached() {
+    let Some(db) = common::database().await else { return };
+    let repo = db.repo();
+    let external = ExternalId { source: "todoist", id: "42".to_string() };
+    let bundle = TaskBundle {
+        task: new_task("Paint the fence"),
+        tags: vec!["outside".to_string(), "weekend".to_string()],
+        project: Some("Home".to_string()),
+        checklist: vec![
+            ChecklistItem { text: "Buy paint".to_string(), done: true },
+            ChecklistItem { text: "Paint".to_string(), done: false },
+        ],
+        notes: vec![Note { created_at: now(), body: "White, not cream".to_string(), created_by: None }],
+        external: Some(external.clone()),
+    };
+
+    let ids = repo.import(&[bundle]).await.unwrap();
+    assert_eq!(ids.len(), 1);
+    let id = ids[0];
+
+    let project = repo.project_id("Home").await.unwrap().expect("the project was created");
+    assert_eq!(repo.get(id).await.unwrap().unwrap().project_id, Some(project));
+    assert_eq!(repo.project_name(project).await.unwrap().as_deref(), Some("Home"));
+    assert_eq!(repo.tags(id).await.unwrap(), ["outside", "weekend"]);
+    let checklist = repo.checklist(id).await.unwrap();
+    assert_eq!(checklist.iter().map(|item| (item.text.as_str(), item.done)).collect::<Vec<_>>(), [
+        ("Buy paint", true),
+        ("Paint", false)
+    ]);
+    assert_eq!(repo.notes(id).await.unwrap()[0].body, "White, not cream");
+    assert_eq!(repo.links(id).await.unwrap(), [("todoist".to_string(), "42".to_string())]);
+    assert!(repo.external_id_exists(&external).await.unwrap());
+    assert!(repo.description_exists("PAINT THE FENCE").await.unwrap());
+
+    repo.set_tags(id, &["garden".to_string()]).await.unwrap();
+    assert!(repo.tags(id).await.unwrap().contains(&"garden".to_string()));
+
+    let filter = TaskFilter { project: Some("Home".to_string()), ..TaskFilter::default() };
+    assert_eq!(repo.filter_page(&filter, None, 20).await.unwrap().tasks.len(), 1);
+    let filter = TaskFilter { completed: Some(true), ..TaskFilter::default() };
+    assert!(repo.filter_page(&filter, None, 20).await.unwrap().tasks.is_empty());
+
+    assert_eq!(repo.linked_tasks("todoist").await.unwrap().len(), 1);
+    assert_eq!(repo.unlink_external(id, "todoist", None).await.unwrap(), 1);
+    assert!(!repo.external_id_exists(&external).await.unwrap());
+}
+
+#[tokio::test]
+async fn accounts_only_see_their_own_and_shared_tasks() {
+    let Some(db) = common::database().await else { return };
+    let alice_id = db.user("alice").await;
+    let bob_id = db.user("bob").await;
+    let alice = db.repo_as(alice_id, "alice");
+    let bob = db.repo_as(bob_id, "bob");
+
+    let id = alice.add("Alice's task").await.unwrap();
+    assert_eq!(alice.get(id).await.unwrap().unwrap().owner_id, Some(alice_id));
+    assert!(bob.get(id).await.unwrap().is_none());
+    assert!(bob.complete(id).await.unwrap().is_none());
+
+    // Read-only first: bob sees it but can't change it
+    assert!(alice.share_task(id, bob_id, false).await.unwrap());
+    assert!(bob.get(id).await.unwrap().is_some());
+    assert!(bob.complete(id).await.unwrap().is_none());
+    assert!(!bob.share_task(id, alice_id, true).await.unwrap());
+    // Nor what hangs off it
+    assert!(!bob.set_tags(id, &["bob's".to_string()]).await.unwrap());
+    assert!(!bob.set_sms_alert(id, true).await.unwrap());
+    assert!(!bob.link_external(id, &ExternalId { source: "todoist", id: "1".to_string() }).await.unwrap());
+    assert!(alice.tags(id).await.unwrap().is_empty());
+
+    assert!(alice.share_task(id, bob_id, true).await.unwrap());
+    assert!(bob.complete(id).await.unwrap().is_some());
+    assert_eq!(bob.shares().await.unwrap().len(), 1);
+
+    assert_eq!(alice.unshare_task(id, bob_id).await.unwrap(), 1);
+    assert!(bob.get(id).await.unwrap().is_none());
+
+    // Assigning makes it bob's to see and do
+    assert!(alice.assign(id, Some(bob_id)).await.unwrap());
+    assert!(bob.get(id).await.unwrap().is_some());
+    let assignments = db.repo().assignments().await.unwrap();
+    assert!(assignments.is_empty(), "completed tasks aren't pending assignments");
+}
+
+#[tokio::test]
+async fn workspace_roles_limit_changes() {
+    let Some(db) = common::database().await else { return };
+    let alice_id = db.user("alice").await;
+    let bob_id = db.user("bob").await;
+    let alice = db.repo_as(alice_id, "alice");
+
+    let workspace = alice.create_workspace("Engineering").await.unwrap();
+    assert_eq!(alice.workspace_id("Engineering").await.unwrap(), Some(workspace));
+    assert!(alice.add_member(workspace, bob_id, Role::Viewer).await.unwrap());
+    assert!(!alice.add_member(workspace, bob_id, Role::Member).await.unwrap());
+    assert!(alice.is_member(workspace, bob_id).await.unwrap());
+    assert_eq!(alice.workspaces().await.unwrap()[0].members, 2);
+
+    let alice_there = db.repo_as(alice_id, "alice").with_workspace(Some((workspace, Role::Admin)));
+    let id = alice_there.add("Ship it").await.unwrap();
+    assert!(alice.get(id).await.unwrap().is_none(), "workspace tasks stay out of the personal space");
+
+    let bob_there = db.repo_as(bob_id, "bob").with_workspace(Some((workspace, Role::Viewer)));
+    assert!(is_forbidden(bob_there.add("Not allowed").await.unwrap_err()));
+    assert!(is_forbidden(db.repo_as(bob_id, "bob").add_member(workspace, alice_id, Role::Viewer).await.unwrap_err()));
+
+    alice.set_role(workspace, bob_id, Role::Member).await.unwrap();
+    let roles: Vec<Role> = alice.members(workspace).await.unwrap().iter().map(|member| member.role).collect();
+    assert_eq!(roles, [Role::Admin, Role::Member]);
+    let bob_member = db.repo_as(bob_id, "bob").with_workspace(Some((workspace, Role::Member)));
+    bob_member.add("Allowed now").await.unwrap();
+    assert!(is_forbidden(bob_member.delete(id).await.unwrap_err()));
+
+    // Anyone may leave
+    assert!(db.repo_as(bob_id, "bob").remove_member(workspace, bob_id).await.unwrap());
+    assert!(!alice.is_member(workspace, bob_id).await.unwrap());
+
+    let read_only = alice_there.clone().with_read_only(true);
+    assert!(is_forbidden(read_only.complete(id).await.unwrap_err()));
+}
+
+#[tokio::test]
+async fn notes_record_mentions_of_those_who_can_see_the_task() {
+    let Some(db) = common::database().await else { return };
+    let alice_id = db.user("alice").await;
+    let bob_id = db.user("bob").await;
+    db.user("carol").await;
+    let alice = db.repo_as(alice_id, "alice");
+    let bob = db.repo_as(bob_id, "bob");
+
+    let id = alice.add("Plan the offsite").await.unwrap();
+    alice.share_task(id, bob_id, false).await.unwrap();
+    let names = ["bob".to_string(), "carol".to_string(), "nobody".to_string(), "alice".to_string()];
+    let mentioned = alice.add_comment(id, "@bob @carol @nobody @alice thoughts?", &names).await.unwrap().unwrap();
+    assert_eq!(mentioned.notified, ["bob"]);
+    assert_eq!(mentioned.unseen, ["carol"]);
+
+    let notes = bob.notes(id).await.unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].created_by.as_deref(), Some("alice"));
+
+    let mentions = bob.mentions(7).await.unwrap();
+    assert_eq!(mentions.len(), 1);
+    assert_eq!((mentions[0].username.as_str(), mentions[0].task.id), ("bob", id));
+    assert!(alice.mentions(7).await.unwrap().is_empty());
+
+    // Bob may read it but not comment on it
+    assert!(bob.add_comment(id, "fine by me", &[]).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn sessions_tokens_and_settings() {
+    let Some(db) = common::database().await else { return };
+    let admin_id = db.user("admin").await;
+    let bob_id = db.user("bob").await;
+    let admin = db.repo_as(admin_id, "admin");
+    let bob = db.repo_as(bob_id, "bob");
+    assert_eq!(db.repo().user_count().await.unwrap(), 2);
+    assert_eq!(db.repo().user_by_name("bob").await.unwrap().unwrap().id, bob_id);
+    assert_eq!(db.repo().username(bob_id).await.unwrap().as_deref(), Some("bob"));
+
+    db.repo().create_session("old-hash", bob_id, 30).await.unwrap();
+    assert_eq!(db.repo().session("old-hash").await.unwrap().unwrap().user.id, bob_id);
+    assert!(db.repo().rotate_session("old-hash", "new-hash", 30).await.unwrap());
+    assert!(db.repo().session("old-hash").await.unwrap().is_none());
+    assert!(db.repo().session("new-hash").await.unwrap().is_some());
+
+    let token = bob.create_api_token("ci", "token-hash", Scope::Read, Some(30)).await.unwrap();
+    assert_eq!(bob.api_tokens().await.unwrap()[0].id, token);
+    let login = db.repo().api_token_login("token-hash").await.unwrap().expect("a valid token");
+    assert_eq!((login.session.user.id, login.scope), (bob_id, Scope::Read));
+
+    // Only instance admins manage accounts; disabling ends sessions and tokens
+    assert!(is_forbidden(bob.set_disabled(admin_id, true).await.unwrap_err()));
+    assert!(admin.set_disabled(bob_id, true).await.unwrap());
+    assert!(db.repo().user_disabled(bob_id).await.unwrap());
+    assert!(db.repo().session("new-hash").await.unwrap().is_none());
+    assert!(db.repo().api_token_login("token-hash").await.unwrap().is_none());
+    assert!(admin.set_disabled(bob_id, false).await.unwrap());
+    assert!(bob.delete_api_token(token).await.unwrap());
+    assert_eq!(admin.accounts().await.unwrap().len(), 2);
+    assert!(admin.webhook_deliveries(10).await.unwrap().is_empty());
+    assert!(is_forbidden(bob.webhook_deliveries(10).await.unwrap_err()));
+    assert_eq!(admin.instance_stats().await.unwrap().users, 2);
+
+    bob.set_setting("timezone", "Europe/Berlin").await.unwrap();
+    bob.set_setting("timezone", "Europe/Paris").await.unwrap();
+    assert_eq!(bob.settings().await.unwrap(), [("timezone".to_string(), "Europe/Paris".to_string())]);
+    assert!(bob.delete_setting("timezone").await.unwrap());
+    assert!(admin.settings().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn invitations_are_redeemed_once() {
+    let Some(db) = common::database().await else { return };
+    let alice_id = db.user("alice").await;
+    let alice = db.repo_as(alice_id, "alice");
+    let workspace = alice.create_workspace("Design").await.unwrap();
+
+    alice.create_invitation(workspace, "code-hash", Role::Member, 7).await.unwrap();
+    assert_eq!(alice.invitations(workspace).await.unwrap().len(), 1);
+    assert_eq!(db.repo().invitation_by_code("code-hash").await.unwrap().unwrap().workspace, "Design");
+
+    let redeemed = db.repo().redeem_invitation("code-hash", "dave", "hash").await.unwrap().expect("redeemed");
+    assert_eq!((redeemed.workspace_id, redeemed.role), (workspace, Role::Member));
+    assert!(alice.is_member(workspace, redeemed.user_id).await.unwrap());
+    assert!(db.repo().redeem_invitation("code-hash", "eve", "hash").await.unwrap().is_none());
+    assert!(alice.invitations(workspace).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn erasing_an_account_keeps_others_work() {
+    let Some(db) = common::database().await else { return };
+    let alice_id = db.user("alice").await;
+    let bob_id = db.user("bob").await;
+    let alice = db.repo_as(alice_id, "alice");
+    let bob = db.repo_as(bob_id, "bob");
+
+    let shared = alice.add("Alice's task").await.unwrap();
+    alice.share_task(shared, bob_id, true).await.unwrap();
+    bob.complete(shared).await.unwrap();
+    bob.add("Bob's task").await.unwrap();
+
+    let erased = bob.erase_account().await.unwrap();
+    assert_eq!(erased.tasks, 1);
+    assert!(erased.activity >= 1);
+    assert!(db.repo().user_by_name("bob").await.unwrap().is_none());
+
+    let task = alice.get(shared).await.unwrap().unwrap();
+    assert!(task.completed);
+    assert_eq!(task.updated_by, None);
+}
+
+#[tokio::test]
+async fn notification_claims_and_webhook_log() {
+    let Some(db) = common::database().await else { return };
+    let repo = db.repo();
+    let id = repo.add("Pay rent").await.unwrap();
+
+    assert!(repo.claim_notification("email", "due", "1").await.unwrap());
+    assert!(!repo.claim_notification("email", "due", "1").await.unwrap());
+    assert!(repo.notification_sent("email", "due", "1").await.unwrap());
+    // Compared with the server's clock, which needn't be in our timezone
+    let server_now: NaiveDateTime = sqlx::query_scalar("SELECT NOW()").fetch_one(&db.pool).await.unwrap();
+    assert_eq!(repo.notifications_sent_since("email", server_now - Duration::hours(1)).await.unwrap(), 1);
+    repo.release_notification("email", "due", "1").await.unwrap();
+    assert!(!repo.notification_sent("email", "due", "1").await.unwrap());
+
+    repo.set_sms_alert(id, true).await.unwrap();
+    assert_eq!(repo.sms_alert_tasks().await.unwrap(), [id]);
+
+    let delivery = repo.log_webhook_delivery("https://example.com/hook", "created", id, "{}").await.unwrap();
+    // Not tried yet, so not for a retry either
+    assert!(repo.undelivered_webhook_deliveries().await.unwrap().is_empty());
+    repo.record_webhook_attempt(delivery, "pending", Some(500), Some("server error")).await.unwrap();
+    let failed = repo.undelivered_webhook_deliveries().await.unwrap();
+    assert_eq!(failed.len(), 1);
+    assert_eq!((failed[0].attempts, failed[0].response_code), (1, Some(500)));
+    // Once it has failed for good it isn't tried again
+    repo.record_webhook_attempt(delivery, "failed", Some(500), Some("server error")).await.unwrap();
+    assert!(repo.undelivered_webhook_deliveries().await.unwrap().is_empty());
+    assert_eq!(repo.webhook_deliveries(10).await.unwrap().len(), 1);
+
+    let due = now() + Duration::hours(2);
+    repo.update(id, &TaskChanges { due_at: Some(Some(due)), ..TaskChanges::default() }).await.unwrap();
+    assert_eq!(repo.pending_due_before(now() + Duration::hours(3)).await.unwrap().len(), 1);
+    assert!(repo.pending_due_before(now()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn sync_state_is_kept() {
+    let Some(db) = common::database().await else { return };
+    let repo = db.repo();
+    let id = repo.add("Synced").await.unwrap();
+    let external = ExternalId { source: "github", id: "7".to_string() };
+
+    assert_eq!(repo.unlinked_pending("github").await.unwrap().len(), 1);
+    repo.link_external(id, &external).await.unwrap();
+    assert!(repo.unlinked_pending("github").await.unwrap().is_empty());
+    assert!(repo.linked_tasks("github").await.unwrap()[0].changed, "never synced");
+    repo.mark_synced(&external).await.unwrap();
+    repo.update_synced_fields(id, "Synced and renamed", true, None, Some(Priority::Low)).await.unwrap();
+    let task = repo.get(id).await.unwrap().unwrap();
+    assert_eq!((task.description.as_str(), task.completed), ("Synced and renamed", true));
+
+    repo.save_caldav_resource("/tasks/1.ics", "uid-1", Some("\"etag\""), id).await.unwrap();
+    assert_eq!(repo.caldav_resources("/tasks/").await.unwrap().len(), 1);
+    repo.delete_caldav_resource("/tasks/1.ics").await.unwrap();
+    repo.save_caldav_ctag("https://dav.example.com/tasks/", Some("ctag-1")).await.unwrap();
+    assert_eq!(repo.caldav_ctag("https://dav.example.com/tasks/").await.unwrap().as_deref(), Some("ctag-1"));
+
+    repo.save_notion_last_edited("db", "2026-01-01T00:00:00Z").await.unwrap();
+    assert_eq!(repo.notion_last_edited("db").await.unwrap().as_deref(), Some("2026-01-01T00:00:00Z"));
+    repo.ping().await.unwrap();
+}